@@ -24,6 +24,13 @@ pub mod network {
     /// Guest connects to this port to signal it's ready to serve
     /// Port 2696 = "BOXM" on phone keypad
     pub const GUEST_READY_PORT: u32 = 2696;
+
+    /// First vsock port of host Unix sockets forwarded into the container;
+    /// the guest connects to it to reach the host socket, one port each
+    pub const HOST_SOCKET_BASE_PORT: u32 = 2700;
+
+    /// First vsock port of reverse ports, one each like host sockets
+    pub const REVERSE_PORT_BASE_PORT: u32 = 2800;
 }
 
 /// Executor environment variable
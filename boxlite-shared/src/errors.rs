@@ -66,6 +66,18 @@ pub enum BoxliteError {
     /// Invalid argument provided.
     #[error("invalid argument: {0}")]
     InvalidArgument(String),
+
+    /// Operation did not complete before its deadline.
+    #[error("timed out: {0}")]
+    Timeout(String),
+
+    /// A runtime-wide resource limit would be exceeded.
+    #[error("resource exhausted: {0}")]
+    ResourceExhausted(String),
+
+    /// A host port to publish is already bound by another process.
+    #[error("host port {0} is already in use")]
+    PortInUse(u16),
 }
 
 // Implement From for common error types to enable `?` operator
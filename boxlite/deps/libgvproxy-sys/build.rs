@@ -36,6 +36,15 @@ fn build_gvproxy(source_dir: &Path, output_path: &Path) {
         output_path.to_str().expect("Invalid output path"),
         "main.go",
         "stats.go",
+        "egress.go",
+        "guestconn.go",
+        "ipv6.go",
+        "ratelimit.go",
+        "boxnames.go",
+        "tunnel.go",
+        "capture.go",
+        "dnscache.go",
+        "drain.go",
     ]);
 
     let build_status = build_cmd
@@ -101,6 +110,15 @@ fn main() {
     // Rebuild if Go sources change
     println!("cargo:rerun-if-changed=gvproxy-bridge/main.go");
     println!("cargo:rerun-if-changed=gvproxy-bridge/stats.go");
+    println!("cargo:rerun-if-changed=gvproxy-bridge/egress.go");
+    println!("cargo:rerun-if-changed=gvproxy-bridge/guestconn.go");
+    println!("cargo:rerun-if-changed=gvproxy-bridge/ipv6.go");
+    println!("cargo:rerun-if-changed=gvproxy-bridge/ratelimit.go");
+    println!("cargo:rerun-if-changed=gvproxy-bridge/boxnames.go");
+    println!("cargo:rerun-if-changed=gvproxy-bridge/tunnel.go");
+    println!("cargo:rerun-if-changed=gvproxy-bridge/capture.go");
+    println!("cargo:rerun-if-changed=gvproxy-bridge/dnscache.go");
+    println!("cargo:rerun-if-changed=gvproxy-bridge/drain.go");
     println!("cargo:rerun-if-changed=gvproxy-bridge/go.mod");
 
     // Check for stub mode (for CI linting without building)
@@ -0,0 +1,187 @@
+package main
+
+import (
+	"bufio"
+	"encoding/binary"
+	"net"
+	"os"
+	"strings"
+	"sync"
+	"time"
+
+	"github.com/miekg/dns"
+	logrus "github.com/sirupsen/logrus"
+)
+
+// Boxes on a network come and go, so their answers are not cached long
+const boxNameTTL = 5
+
+// boxNames answers the guest's DNS queries for the boxes on its inter-box
+// networks, read from the networks' hosts files (`<ip> <name>` lines) that
+// the runtime rewrites as boxes join.
+//
+// Queries for other names go on to the gateway's DNS server.
+type boxNames struct {
+	files   []string
+	domains []string // Search domains, lowercase without dots
+	gateway net.IP
+
+	mu      sync.Mutex
+	records map[string]net.IP
+	mtimes  map[string]time.Time
+}
+
+// newBoxNames returns nil without hosts files
+func newBoxNames(files []string, gatewayIP string, searchDomains []string) *boxNames {
+	if len(files) == 0 {
+		return nil
+	}
+	b := &boxNames{
+		files:   files,
+		gateway: net.ParseIP(gatewayIP),
+		records: make(map[string]net.IP),
+		mtimes:  make(map[string]time.Time),
+	}
+	for _, domain := range searchDomains {
+		b.domains = append(b.domains, strings.ToLower(strings.Trim(domain, ".")))
+	}
+	return b
+}
+
+// lookup returns the address of a box name, or nil
+func (b *boxNames) lookup(name string) net.IP {
+	b.mu.Lock()
+	defer b.mu.Unlock()
+	b.reloadLocked()
+
+	if ip := b.records[name]; ip != nil {
+		return ip
+	}
+	// The resolver tries the search domains first
+	for _, domain := range b.domains {
+		if box, ok := strings.CutSuffix(name, "."+domain); ok {
+			if ip := b.records[box]; ip != nil {
+				return ip
+			}
+		}
+	}
+	return nil
+}
+
+// reloadLocked reads the hosts files again when one has changed
+func (b *boxNames) reloadLocked() {
+	changed := false
+	for _, file := range b.files {
+		var mtime time.Time
+		if info, err := os.Stat(file); err == nil {
+			mtime = info.ModTime()
+		}
+		if !mtime.Equal(b.mtimes[file]) {
+			b.mtimes[file] = mtime
+			changed = true
+		}
+	}
+	if !changed {
+		return
+	}
+
+	records := make(map[string]net.IP)
+	for _, file := range b.files {
+		f, err := os.Open(file)
+		if err != nil {
+			continue
+		}
+		scanner := bufio.NewScanner(f)
+		for scanner.Scan() {
+			fields := strings.Fields(scanner.Text())
+			if len(fields) < 2 {
+				continue
+			}
+			if ip := net.ParseIP(fields[0]).To4(); ip != nil {
+				for _, name := range fields[1:] {
+					records[strings.ToLower(name)] = ip
+				}
+			}
+		}
+		f.Close()
+	}
+	b.records = records
+	logrus.WithField("names", len(records)).Debug("Loaded box names")
+}
+
+// answer returns the reply frame to a guest query for a box name, or nil
+// when the frame is something else
+func (b *boxNames) answer(frame []byte) []byte {
+	p, ok := parseFrame(frame)
+	if !ok || p.proto != protoUDP || p.dstPort != dnsPort || p.dst.To4() == nil || !p.dst.Equal(b.gateway) {
+		return nil
+	}
+	var query dns.Msg
+	if err := query.Unpack(p.payload); err != nil || len(query.Question) != 1 {
+		return nil
+	}
+	q := query.Question[0]
+	if q.Qclass != dns.ClassINET || (q.Qtype != dns.TypeA && q.Qtype != dns.TypeAAAA) {
+		return nil
+	}
+	ip := b.lookup(strings.ToLower(strings.TrimSuffix(q.Name, ".")))
+	if ip == nil {
+		return nil
+	}
+
+	var reply dns.Msg
+	reply.SetReply(&query)
+	reply.Authoritative = true
+	// Boxes have no IPv6 address on inter-box networks: AAAA gets no records
+	if q.Qtype == dns.TypeA {
+		reply.Answer = append(reply.Answer, &dns.A{
+			Hdr: dns.RR_Header{Name: q.Name, Rrtype: dns.TypeA, Class: dns.ClassINET, Ttl: boxNameTTL},
+			A:   ip,
+		})
+	}
+	payload, err := reply.Pack()
+	if err != nil {
+		return nil
+	}
+	return udpReplyFrame(frame, payload)
+}
+
+// udpReplyFrame builds the frame answering an IPv4 UDP frame with payload
+func udpReplyFrame(frame []byte, payload []byte) []byte {
+	ip := frame[14:]
+	udp := ip[int(ip[0]&0x0f)*4:]
+
+	out := make([]byte, 14+20+8+len(payload))
+	copy(out[0:6], frame[6:12])
+	copy(out[6:12], frame[0:6])
+	binary.BigEndian.PutUint16(out[12:14], etherTypeIPv4)
+
+	h := out[14:34]
+	h[0] = 0x45
+	binary.BigEndian.PutUint16(h[2:4], uint16(20+8+len(payload)))
+	h[8] = 64 // TTL
+	h[9] = protoUDP
+	copy(h[12:16], ip[16:20])
+	copy(h[16:20], ip[12:16])
+	binary.BigEndian.PutUint16(h[10:12], ipv4Checksum(h))
+
+	u := out[34:42]
+	copy(u[0:2], udp[2:4])
+	copy(u[2:4], udp[0:2])
+	binary.BigEndian.PutUint16(u[4:6], uint16(8+len(payload)))
+	// A zero UDP checksum means none, which IPv4 allows
+	copy(out[42:], payload)
+	return out
+}
+
+// ipv4Checksum is the checksum of an IPv4 header with its checksum field zero
+func ipv4Checksum(header []byte) uint16 {
+	var sum uint32
+	for i := 0; i+1 < len(header); i += 2 {
+		sum += uint32(binary.BigEndian.Uint16(header[i : i+2]))
+	}
+	for sum > 0xffff {
+		sum = (sum >> 16) + (sum & 0xffff)
+	}
+	return ^uint16(sum)
+}
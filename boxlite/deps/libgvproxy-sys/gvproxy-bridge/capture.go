@@ -0,0 +1,198 @@
+package main
+
+import (
+	"bufio"
+	"encoding/binary"
+	"errors"
+	"fmt"
+	"net"
+	"os"
+	"sync"
+	"sync/atomic"
+	"time"
+)
+
+// pcap file format, with microsecond timestamps
+const (
+	pcapMagic            = 0xa1b2c3d4
+	pcapSnapLen          = 65535
+	pcapLinkEthernet     = 1
+	pcapHeaderSize       = 24
+	pcapRecordHeaderSize = 16
+)
+
+const (
+	protoICMP   = 1
+	protoICMPv6 = 58
+)
+
+// CaptureConfig asks for a capture of the guest's traffic
+type CaptureConfig struct {
+	Path       string `json:"path"`
+	Host       string `json:"host,omitempty"`     // Only packets from or to this address
+	Port       uint16 `json:"port,omitempty"`     // Only TCP and UDP packets from or to this port
+	Protocol   string `json:"protocol,omitempty"` // Only "tcp", "udp" or "icmp" packets
+	DurationMs uint64 `json:"duration_ms"`
+	MaxBytes   uint64 `json:"max_bytes"`
+}
+
+// CaptureStats reports a finished capture
+type CaptureStats struct {
+	Packets   uint64 `json:"packets"`
+	Bytes     uint64 `json:"bytes"`     // Size of the pcap file
+	Truncated bool   `json:"truncated"` // Stopped at MaxBytes
+}
+
+// captureMatch selects the frames a capture records
+type captureMatch struct {
+	host     net.IP // nil for any
+	port     uint16 // 0 for any
+	protocol string // "" for any
+}
+
+func newCaptureMatch(config CaptureConfig) (captureMatch, error) {
+	m := captureMatch{port: config.Port, protocol: config.Protocol}
+	if config.Host != "" {
+		if m.host = net.ParseIP(config.Host); m.host == nil {
+			return m, fmt.Errorf("invalid capture host %q", config.Host)
+		}
+	}
+	switch m.protocol {
+	case "", "tcp", "udp", "icmp":
+	default:
+		return m, fmt.Errorf("invalid capture protocol %q", config.Protocol)
+	}
+	return m, nil
+}
+
+func (m captureMatch) matches(frame []byte) bool {
+	if m.host == nil && m.port == 0 && m.protocol == "" {
+		return true
+	}
+	// Other frames (e.g. ARP) only match without a filter
+	p, ok := parseFrame(frame)
+	if !ok {
+		return false
+	}
+	if m.host != nil && !m.host.Equal(p.src) && !m.host.Equal(p.dst) {
+		return false
+	}
+	switch m.protocol {
+	case "tcp":
+		if p.proto != protoTCP {
+			return false
+		}
+	case "udp":
+		if p.proto != protoUDP {
+			return false
+		}
+	case "icmp":
+		if p.proto != protoICMP && p.proto != protoICMPv6 {
+			return false
+		}
+	}
+	return m.port == 0 || (p.hasPorts && (p.srcPort == m.port || p.dstPort == m.port))
+}
+
+// trafficCapture writes the frames passing the guest tap to a pcap file
+// while a capture runs; one capture runs at a time
+type trafficCapture struct {
+	on   atomic.Bool // Checked on every frame before taking mu
+	busy atomic.Bool // A capture runs, recording or not
+
+	mu      sync.Mutex
+	out     *bufio.Writer
+	match   captureMatch
+	limit   uint64
+	stats   CaptureStats
+	reached chan struct{} // Closed at the size limit
+}
+
+// run records the matching frames to config.Path until config.DurationMs
+// passes or the file would grow past config.MaxBytes
+func (c *trafficCapture) run(config CaptureConfig) (CaptureStats, error) {
+	match, err := newCaptureMatch(config)
+	if err != nil {
+		return CaptureStats{}, err
+	}
+	if config.MaxBytes < pcapHeaderSize {
+		return CaptureStats{}, fmt.Errorf("capture size limit %d is below the pcap header size", config.MaxBytes)
+	}
+	if !c.busy.CompareAndSwap(false, true) {
+		return CaptureStats{}, errors.New("a capture is already running")
+	}
+	defer c.busy.Store(false)
+
+	file, err := os.Create(config.Path)
+	if err != nil {
+		return CaptureStats{}, err
+	}
+	defer file.Close()
+	out := bufio.NewWriter(file)
+	header := make([]byte, pcapHeaderSize)
+	binary.LittleEndian.PutUint32(header[0:4], pcapMagic)
+	binary.LittleEndian.PutUint16(header[4:6], 2)
+	binary.LittleEndian.PutUint16(header[6:8], 4)
+	binary.LittleEndian.PutUint32(header[16:20], pcapSnapLen)
+	binary.LittleEndian.PutUint32(header[20:24], pcapLinkEthernet)
+	out.Write(header)
+
+	reached := make(chan struct{})
+	c.mu.Lock()
+	c.out, c.match, c.limit, c.reached = out, match, config.MaxBytes, reached
+	c.stats = CaptureStats{Bytes: pcapHeaderSize}
+	c.mu.Unlock()
+	c.on.Store(true)
+
+	timer := time.NewTimer(time.Duration(config.DurationMs) * time.Millisecond)
+	select {
+	case <-timer.C:
+	case <-reached:
+		timer.Stop()
+	}
+
+	c.on.Store(false)
+	c.mu.Lock()
+	c.out = nil
+	stats := c.stats
+	c.mu.Unlock()
+	if err := out.Flush(); err != nil {
+		return stats, err
+	}
+	return stats, file.Close()
+}
+
+// record writes frame if a capture is running and wants it
+func (c *trafficCapture) record(frame []byte) {
+	if !c.on.Load() {
+		return
+	}
+	c.mu.Lock()
+	defer c.mu.Unlock()
+	if c.out == nil || !c.match.matches(frame) {
+		return
+	}
+
+	captured := frame
+	if len(captured) > pcapSnapLen {
+		captured = captured[:pcapSnapLen]
+	}
+	size := uint64(pcapRecordHeaderSize + len(captured))
+	if c.stats.Bytes+size > c.limit {
+		c.stats.Truncated = true
+		c.out = nil
+		close(c.reached)
+		return
+	}
+
+	now := time.Now()
+	var header [pcapRecordHeaderSize]byte
+	binary.LittleEndian.PutUint32(header[0:4], uint32(now.Unix()))
+	binary.LittleEndian.PutUint32(header[4:8], uint32(now.Nanosecond()/1000))
+	binary.LittleEndian.PutUint32(header[8:12], uint32(len(captured)))
+	binary.LittleEndian.PutUint32(header[12:16], uint32(len(frame)))
+	c.out.Write(header[:])
+	c.out.Write(captured)
+	c.stats.Packets++
+	c.stats.Bytes += size
+}
@@ -0,0 +1,288 @@
+package main
+
+import (
+	"container/list"
+	"net"
+	"strings"
+	"sync"
+	"time"
+
+	"github.com/miekg/dns"
+	logrus "github.com/sirupsen/logrus"
+)
+
+const (
+	// Answers are kept no longer, whatever their TTL
+	dnsCacheMaxTTL      = time.Hour
+	// Negative answers without a SOA record to take the TTL from
+	dnsCacheNegativeTTL = 30 * time.Second
+	// How long an upstream server gets to answer
+	dnsUpstreamTimeout  = 5 * time.Second
+	// Largest UDP answer to a query without EDNS
+	dnsMaxUDPSize       = 512
+)
+
+// DNSCacheConfig matches the Rust structure (must stay in sync!)
+type DNSCacheConfig struct {
+	Upstreams  []string `json:"upstreams,omitempty"` // ip:port, the host's resolvers if empty
+	MaxEntries int      `json:"max_entries"`
+}
+
+type dnsCacheKey struct {
+	name   string // Lowercase
+	qtype  uint16
+	qclass uint16
+	dnssec bool
+}
+
+type dnsCacheEntry struct {
+	key     dnsCacheKey
+	answer  *dns.Msg
+	stored  time.Time
+	expires time.Time
+}
+
+// dnsCache answers the guest's DNS queries to the gateway, from the
+// answers to earlier queries while their TTL lasts, and resolves the
+// others through upstream servers. Identical queries in flight share one
+// upstream query.
+type dnsCache struct {
+	gateway    net.IP
+	upstreams  []string
+	maxEntries int
+	udp        *dns.Client
+	tcp        *dns.Client
+
+	mu       sync.Mutex
+	entries  map[dnsCacheKey]*list.Element
+	lru      *list.List // Of *dnsCacheEntry, most recently used first
+	inflight map[dnsCacheKey][]func(*dns.Msg)
+	hits     uint64
+	misses   uint64
+}
+
+// newDNSCache returns nil without config, or when the host has no resolver
+// to use as upstream
+func newDNSCache(config *DNSCacheConfig, gatewayIP string) *dnsCache {
+	if config == nil {
+		return nil
+	}
+	upstreams := config.Upstreams
+	if len(upstreams) == 0 {
+		upstreams = hostResolvers()
+		if len(upstreams) == 0 {
+			logrus.Warn("Host has no DNS resolver, DNS cache disabled")
+			return nil
+		}
+	}
+	logrus.WithFields(logrus.Fields{"upstreams": upstreams, "max_entries": config.MaxEntries}).Info("DNS cache enabled")
+	return &dnsCache{
+		gateway:    net.ParseIP(gatewayIP),
+		upstreams:  upstreams,
+		maxEntries: config.MaxEntries,
+		udp:        &dns.Client{Net: "udp", Timeout: dnsUpstreamTimeout},
+		tcp:        &dns.Client{Net: "tcp", Timeout: dnsUpstreamTimeout},
+		entries:    make(map[dnsCacheKey]*list.Element),
+		lru:        list.New(),
+		inflight:   make(map[dnsCacheKey][]func(*dns.Msg)),
+	}
+}
+
+// hostResolvers returns the nameservers of the host's resolv.conf, as
+// ip:port. Loopback resolvers are fine: the cache runs on the host.
+func hostResolvers() []string {
+	config, err := dns.ClientConfigFromFile("/etc/resolv.conf")
+	if err != nil {
+		logrus.WithError(err).Warn("Failed to read host DNS configuration")
+		return nil
+	}
+	servers := make([]string, 0, len(config.Servers))
+	for _, server := range config.Servers {
+		servers = append(servers, net.JoinHostPort(server, config.Port))
+	}
+	return servers
+}
+
+// intercept reports whether frame is a guest query to the gateway, which
+// is then answered through reply, right away or once resolved
+func (c *dnsCache) intercept(frame []byte, reply func(frame []byte)) bool {
+	p, ok := parseFrame(frame)
+	if !ok || p.proto != protoUDP || p.dstPort != dnsPort || p.dst.To4() == nil || !p.dst.Equal(c.gateway) {
+		return false
+	}
+	query := new(dns.Msg)
+	if err := query.Unpack(p.payload); err != nil || query.Response || query.Opcode != dns.OpcodeQuery || len(query.Question) != 1 {
+		return false
+	}
+
+	// The datagram connection reuses its buffer
+	frame = append([]byte(nil), frame...)
+	respond := func(answer *dns.Msg) {
+		if out := answerFrame(frame, query, answer); out != nil {
+			reply(out)
+		}
+	}
+
+	key := dnsCacheKey{
+		name:   strings.ToLower(query.Question[0].Name),
+		qtype:  query.Question[0].Qtype,
+		qclass: query.Question[0].Qclass,
+	}
+	if opt := query.IsEdns0(); opt != nil {
+		key.dnssec = opt.Do()
+	}
+
+	c.mu.Lock()
+	if answer := c.getLocked(key, time.Now()); answer != nil {
+		c.hits++
+		c.mu.Unlock()
+		respond(answer)
+		return true
+	}
+	c.misses++
+	waiters, resolving := c.inflight[key]
+	c.inflight[key] = append(waiters, respond)
+	c.mu.Unlock()
+
+	if !resolving {
+		go c.resolve(key, query)
+	}
+	return true
+}
+
+// resolve asks the upstream servers and answers the queries waiting for key
+func (c *dnsCache) resolve(key dnsCacheKey, query *dns.Msg) {
+	answer := c.exchange(query)
+	now := time.Now()
+
+	c.mu.Lock()
+	if ttl := cacheTTL(answer); ttl > 0 {
+		c.putLocked(key, answer, now, ttl)
+	}
+	waiters := c.inflight[key]
+	delete(c.inflight, key)
+	hits, misses := c.hits, c.misses
+	c.mu.Unlock()
+
+	logrus.WithFields(logrus.Fields{"name": key.name, "rcode": dns.RcodeToString[answer.Rcode], "hits": hits, "misses": misses}).Trace("Resolved DNS query")
+	for _, respond := range waiters {
+		respond(answer.Copy())
+	}
+}
+
+// exchange sends query to the upstream servers in turn, returning the first
+// answer, or SERVFAIL when none answers
+func (c *dnsCache) exchange(query *dns.Msg) *dns.Msg {
+	upstreamQuery := query.Copy()
+	upstreamQuery.Id = dns.Id()
+	for _, upstream := range c.upstreams {
+		answer, _, err := c.udp.Exchange(upstreamQuery, upstream)
+		if err == nil && answer.Truncated {
+			answer, _, err = c.tcp.Exchange(upstreamQuery, upstream)
+		}
+		if err != nil {
+			logrus.WithFields(logrus.Fields{"upstream": upstream, "error": err}).Debug("DNS upstream failed")
+			continue
+		}
+		if answer.Rcode == dns.RcodeServerFailure || answer.Rcode == dns.RcodeRefused {
+			continue
+		}
+		return answer
+	}
+	failure := new(dns.Msg)
+	failure.SetRcode(query, dns.RcodeServerFailure)
+	return failure
+}
+
+// getLocked returns a copy of the cached answer for key, its TTLs counted
+// down, or nil
+func (c *dnsCache) getLocked(key dnsCacheKey, now time.Time) *dns.Msg {
+	element := c.entries[key]
+	if element == nil {
+		return nil
+	}
+	entry := element.Value.(*dnsCacheEntry)
+	if !now.Before(entry.expires) {
+		c.lru.Remove(element)
+		delete(c.entries, key)
+		return nil
+	}
+	c.lru.MoveToFront(element)
+
+	answer := entry.answer.Copy()
+	elapsed := uint32(now.Sub(entry.stored) / time.Second)
+	for _, section := range [][]dns.RR{answer.Answer, answer.Ns, answer.Extra} {
+		for _, rr := range section {
+			if header := rr.Header(); header.Rrtype != dns.TypeOPT {
+				header.Ttl -= min(header.Ttl, elapsed)
+			}
+		}
+	}
+	return answer
+}
+
+func (c *dnsCache) putLocked(key dnsCacheKey, answer *dns.Msg, now time.Time, ttl time.Duration) {
+	entry := &dnsCacheEntry{key: key, answer: answer.Copy(), stored: now, expires: now.Add(ttl)}
+	if element := c.entries[key]; element != nil {
+		element.Value = entry
+		c.lru.MoveToFront(element)
+		return
+	}
+	c.entries[key] = c.lru.PushFront(entry)
+	for c.lru.Len() > c.maxEntries {
+		oldest := c.lru.Back()
+		c.lru.Remove(oldest)
+		delete(c.entries, oldest.Value.(*dnsCacheEntry).key)
+	}
+}
+
+// cacheTTL is how long answer may be cached, 0 for not at all
+func cacheTTL(answer *dns.Msg) time.Duration {
+	var ttl uint32
+	switch {
+	case answer.Rcode == dns.RcodeSuccess && len(answer.Answer) > 0:
+		ttl = answer.Answer[0].Header().Ttl
+		for _, rr := range answer.Answer[1:] {
+			ttl = min(ttl, rr.Header().Ttl)
+		}
+	case answer.Rcode == dns.RcodeSuccess || answer.Rcode == dns.RcodeNameError:
+		// No such name or record: the SOA says for how long (RFC 2308)
+		for _, rr := range answer.Ns {
+			if soa, ok := rr.(*dns.SOA); ok {
+				return min(time.Duration(min(soa.Hdr.Ttl, soa.Minttl))*time.Second, dnsCacheMaxTTL)
+			}
+		}
+		return dnsCacheNegativeTTL
+	default:
+		return 0
+	}
+	return min(time.Duration(ttl)*time.Second, dnsCacheMaxTTL)
+}
+
+// answerFrame builds the reply frame to the guest's query frame, or nil
+func answerFrame(frame []byte, query *dns.Msg, answer *dns.Msg) []byte {
+	answer.Id = query.Id
+	// The guest may have randomized the case of the name
+	answer.Question = query.Question
+	size := dnsMaxUDPSize
+	if opt := query.IsEdns0(); opt != nil {
+		size = max(int(opt.UDPSize()), dnsMaxUDPSize)
+	} else {
+		// Answered to a query with EDNS
+		extra := answer.Extra[:0]
+		for _, rr := range answer.Extra {
+			if rr.Header().Rrtype != dns.TypeOPT {
+				extra = append(extra, rr)
+			}
+		}
+		answer.Extra = extra
+	}
+	answer.Truncate(size)
+
+	payload, err := answer.Pack()
+	if err != nil {
+		logrus.WithError(err).Debug("Failed to pack DNS answer")
+		return nil
+	}
+	return udpReplyFrame(frame, payload)
+}
@@ -0,0 +1,106 @@
+package main
+
+import (
+	"net"
+	"sync"
+)
+
+// TCP header flags
+const (
+	tcpFIN = 0x01
+	tcpSYN = 0x02
+	tcpRST = 0x04
+	tcpACK = 0x10
+)
+
+// Directions of a forwarded connection that sent a FIN
+const (
+	finToGuest = 1 << iota
+	finFromGuest
+)
+
+// forwardedConn is a connection of a port forward: the forwarder's port on
+// the gateway and the guest's port
+type forwardedConn struct {
+	gatewayPort uint16
+	guestPort   uint16
+}
+
+// forwardedConns counts the TCP connections the port forwards have open to
+// the guest, from the forwarder's SYN until both sides closed or either
+// reset, so that a stopping box can wait for them to finish
+type forwardedConns struct {
+	gateway net.IP
+	guest   net.IP
+	ports   map[uint16]bool // Guest ports of the forwards
+
+	mu    sync.Mutex
+	conns map[forwardedConn]uint8 // Directions that sent a FIN
+}
+
+// newForwardedConns returns nil without port forwards
+func newForwardedConns(gatewayIP, guestIP string, mappings []PortMapping) *forwardedConns {
+	if len(mappings) == 0 {
+		return nil
+	}
+	f := &forwardedConns{
+		gateway: net.ParseIP(gatewayIP),
+		guest:   net.ParseIP(guestIP),
+		ports:   make(map[uint16]bool),
+		conns:   make(map[forwardedConn]uint8),
+	}
+	for _, pm := range mappings {
+		f.ports[pm.GuestPort] = true
+	}
+	return f
+}
+
+// toGuest sees a frame the virtual network sends to the guest
+func (f *forwardedConns) toGuest(frame []byte) {
+	p, ok := parseFrame(frame)
+	if !ok || p.proto != protoTCP || !p.hasPorts || !p.src.Equal(f.gateway) || !p.dst.Equal(f.guest) || !f.ports[p.dstPort] {
+		return
+	}
+	f.update(forwardedConn{gatewayPort: p.srcPort, guestPort: p.dstPort}, p.tcpFlags, finToGuest)
+}
+
+// fromGuest sees a frame from the guest
+func (f *forwardedConns) fromGuest(frame []byte) {
+	p, ok := parseFrame(frame)
+	if !ok || p.proto != protoTCP || !p.hasPorts || !p.src.Equal(f.guest) || !p.dst.Equal(f.gateway) || !f.ports[p.srcPort] {
+		return
+	}
+	f.update(forwardedConn{gatewayPort: p.dstPort, guestPort: p.srcPort}, p.tcpFlags, finFromGuest)
+}
+
+func (f *forwardedConns) update(conn forwardedConn, flags uint8, fin uint8) {
+	f.mu.Lock()
+	defer f.mu.Unlock()
+	switch {
+	case flags&tcpRST != 0:
+		delete(f.conns, conn)
+	case flags&(tcpSYN|tcpACK) == tcpSYN && fin == finToGuest:
+		f.conns[conn] = 0
+	case flags&tcpFIN != 0:
+		closed, ok := f.conns[conn]
+		if !ok {
+			return
+		}
+		closed |= fin
+		if closed == finToGuest|finFromGuest {
+			delete(f.conns, conn)
+		} else {
+			f.conns[conn] = closed
+		}
+	}
+}
+
+// count returns the number of open connections, 0 without port forwards
+func (f *forwardedConns) count() int {
+	if f == nil {
+		return 0
+	}
+	f.mu.Lock()
+	defer f.mu.Unlock()
+	return len(f.conns)
+}
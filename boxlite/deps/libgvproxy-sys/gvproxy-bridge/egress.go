@@ -0,0 +1,300 @@
+package main
+
+import (
+	"encoding/binary"
+	"fmt"
+	"net"
+	"strings"
+	"sync"
+
+	"github.com/miekg/dns"
+	logrus "github.com/sirupsen/logrus"
+)
+
+// EgressRule matches destinations of guest traffic (must match Rust EgressRule)
+type EgressRule struct {
+	Host  string   `json:"host"`  // Domain ("*.example.com"), IP or CIDR
+	Ports []uint16 `json:"ports"` // Empty matches any port
+}
+
+// EgressPolicy restricts where the guest may connect (must match Rust NetworkPolicy)
+type EgressPolicy struct {
+	Allow []EgressRule `json:"allow"`
+	Deny  []EgressRule `json:"deny"`
+}
+
+const (
+	etherTypeIPv4 = 0x0800
+	etherTypeIPv6 = 0x86dd
+	protoTCP      = 6
+	protoUDP      = 17
+	dnsPort       = 53
+
+	// IPv6 extension headers
+	protoHopByHop = 0
+	protoRouting  = 43
+	protoFragment = 44
+	protoAH       = 51
+	protoDestOpts = 60
+)
+
+// egressRule is an EgressRule ready for matching
+type egressRule struct {
+	network *net.IPNet // IP or CIDR rules
+	domain  string     // Domain rules, lowercase without trailing dot
+	suffix  bool       // Domain matches subdomains only ("*.")
+	ports   map[uint16]bool
+
+	// Addresses domain rules were resolved to, guarded by egressFilter.mu
+	resolved map[string]bool
+}
+
+func newEgressRule(rule EgressRule) (*egressRule, error) {
+	r := &egressRule{ports: make(map[uint16]bool)}
+	for _, port := range rule.Ports {
+		r.ports[port] = true
+	}
+
+	host := rule.Host
+	if _, network, err := net.ParseCIDR(host); err == nil {
+		r.network = network
+	} else if ip := net.ParseIP(host); ip != nil {
+		bits := 8 * len(ip.To16())
+		if ip.To4() != nil {
+			ip, bits = ip.To4(), 32
+		}
+		r.network = &net.IPNet{IP: ip, Mask: net.CIDRMask(bits, bits)}
+	} else if host != "" {
+		r.domain = strings.ToLower(strings.TrimSuffix(host, "."))
+		if strings.HasPrefix(r.domain, "*.") {
+			r.domain, r.suffix = r.domain[1:], true
+		}
+		r.resolved = make(map[string]bool)
+	} else {
+		return nil, fmt.Errorf("empty egress rule host")
+	}
+	return r, nil
+}
+
+// matchesName reports whether a DNS name (lowercase, no trailing dot) is
+// covered by a domain rule
+func (r *egressRule) matchesName(name string) bool {
+	if r.suffix {
+		return strings.HasSuffix(name, r.domain)
+	}
+	return r.domain != "" && name == r.domain
+}
+
+// egressFilter drops guest packets to destinations the policy forbids.
+//
+// It sees the Ethernet frames between the VM and the virtual network,
+// and learns the addresses of domain rules from the answers of the
+// gateway's DNS server.
+type egressFilter struct {
+	gateways []net.IP
+	allow    []*egressRule
+	deny     []*egressRule
+	mu       sync.RWMutex
+}
+
+// newEgressFilter returns nil when there is no policy; empty gateway
+// addresses are skipped
+func newEgressFilter(policy *EgressPolicy, gatewayIPs ...string) (*egressFilter, error) {
+	if policy == nil || (len(policy.Allow) == 0 && len(policy.Deny) == 0) {
+		return nil, nil
+	}
+	f := &egressFilter{}
+	for _, ip := range gatewayIPs {
+		if ip != "" {
+			f.gateways = append(f.gateways, net.ParseIP(ip))
+		}
+	}
+	for _, rule := range policy.Allow {
+		r, err := newEgressRule(rule)
+		if err != nil {
+			return nil, err
+		}
+		f.allow = append(f.allow, r)
+	}
+	for _, rule := range policy.Deny {
+		r, err := newEgressRule(rule)
+		if err != nil {
+			return nil, err
+		}
+		f.deny = append(f.deny, r)
+	}
+	return f, nil
+}
+
+// packet is what the filter reads from an IP frame
+type packet struct {
+	src, dst         net.IP
+	proto            uint8
+	srcPort, dstPort uint16
+	hasPorts         bool
+	tcpFlags         uint8
+	payload          []byte // Transport payload (UDP only)
+}
+
+// parseFrame decodes the IP and transport headers of an Ethernet frame;
+// ok is false for frames that are not IP (e.g. ARP)
+func parseFrame(frame []byte) (p packet, ok bool) {
+	if len(frame) < 14 {
+		return p, false
+	}
+	var l4 int
+	ip := frame[14:]
+	switch binary.BigEndian.Uint16(frame[12:14]) {
+	case etherTypeIPv4:
+		if len(ip) < 20 {
+			return p, false
+		}
+		p.src, p.dst, p.proto = net.IP(ip[12:16]), net.IP(ip[16:20]), ip[9]
+		// Later fragments carry no transport header
+		if binary.BigEndian.Uint16(ip[6:8])&0x1fff != 0 {
+			return p, true
+		}
+		l4 = int(ip[0]&0x0f) * 4
+	case etherTypeIPv6:
+		if len(ip) < 40 {
+			return p, false
+		}
+		p.src, p.dst, p.proto = net.IP(ip[8:24]), net.IP(ip[24:40]), ip[6]
+		l4 = 40
+		// The transport header follows the extension headers
+		for {
+			switch p.proto {
+			case protoHopByHop, protoRouting, protoDestOpts:
+				if len(ip) < l4+8 {
+					return p, true
+				}
+				p.proto, l4 = ip[l4], l4+(int(ip[l4+1])+1)*8
+				continue
+			case protoFragment:
+				if len(ip) < l4+8 {
+					return p, true
+				}
+				p.proto = ip[l4]
+				// Later fragments carry no transport header
+				if binary.BigEndian.Uint16(ip[l4+2:l4+4])&0xfff8 != 0 {
+					return p, true
+				}
+				l4 += 8
+				continue
+			case protoAH:
+				if len(ip) < l4+8 {
+					return p, true
+				}
+				p.proto, l4 = ip[l4], l4+(int(ip[l4+1])+2)*4
+				continue
+			}
+			break
+		}
+	default:
+		return p, false
+	}
+
+	if (p.proto == protoTCP || p.proto == protoUDP) && len(ip) >= l4+8 {
+		p.srcPort = binary.BigEndian.Uint16(ip[l4 : l4+2])
+		p.dstPort = binary.BigEndian.Uint16(ip[l4+2 : l4+4])
+		p.hasPorts = true
+		if p.proto == protoTCP && len(ip) >= l4+14 {
+			p.tcpFlags = ip[l4+13]
+		}
+		if udpEnd := l4 + int(binary.BigEndian.Uint16(ip[l4+4:l4+6])); p.proto == protoUDP &&
+			udpEnd >= l4+8 && udpEnd <= len(ip) {
+			p.payload = ip[l4+8 : udpEnd]
+		}
+	}
+	return p, true
+}
+
+func (f *egressFilter) isGateway(ip net.IP) bool {
+	for _, gateway := range f.gateways {
+		if ip.Equal(gateway) {
+			return true
+		}
+	}
+	return false
+}
+
+func (f *egressFilter) matches(r *egressRule, p *packet) bool {
+	if len(r.ports) > 0 && (!p.hasPorts || !r.ports[p.dstPort]) {
+		return false
+	}
+	if r.network != nil {
+		return r.network.Contains(p.dst)
+	}
+	return r.resolved[p.dst.String()]
+}
+
+// allowEgress reports whether a frame from the guest may pass
+func (f *egressFilter) allowEgress(frame []byte) bool {
+	p, ok := parseFrame(frame)
+	if !ok {
+		return true
+	}
+	// DHCP, DNS and neighbor discovery stay on the virtual network
+	if f.isGateway(p.dst) || p.dst.Equal(net.IPv4bcast) || p.dst.IsMulticast() ||
+		p.dst.IsLinkLocalUnicast() {
+		return true
+	}
+
+	f.mu.RLock()
+	defer f.mu.RUnlock()
+	for _, r := range f.deny {
+		if f.matches(r, &p) {
+			logrus.WithFields(logrus.Fields{"dst": p.dst, "port": p.dstPort}).Debug("Egress denied by rule")
+			return false
+		}
+	}
+	if len(f.allow) == 0 {
+		return true
+	}
+	for _, r := range f.allow {
+		if f.matches(r, &p) {
+			return true
+		}
+	}
+	logrus.WithFields(logrus.Fields{"dst": p.dst, "port": p.dstPort}).Debug("Egress not allowed")
+	return false
+}
+
+// inspectIngress learns domain addresses from a frame sent to the guest
+func (f *egressFilter) inspectIngress(frame []byte) {
+	p, ok := parseFrame(frame)
+	if !ok || p.proto != protoUDP || p.srcPort != dnsPort || !f.isGateway(p.src) {
+		return
+	}
+	var msg dns.Msg
+	if err := msg.Unpack(p.payload); err != nil || len(msg.Question) == 0 {
+		return
+	}
+
+	// The answers of a query, CNAMEs included, all belong to its name
+	name := strings.ToLower(strings.TrimSuffix(msg.Question[0].Name, "."))
+	var addrs []string
+	for _, rr := range msg.Answer {
+		switch rr := rr.(type) {
+		case *dns.A:
+			addrs = append(addrs, rr.A.String())
+		case *dns.AAAA:
+			addrs = append(addrs, rr.AAAA.String())
+		}
+	}
+	if len(addrs) == 0 {
+		return
+	}
+
+	f.mu.Lock()
+	defer f.mu.Unlock()
+	for _, r := range append(append([]*egressRule{}, f.allow...), f.deny...) {
+		if !r.matchesName(name) {
+			continue
+		}
+		for _, addr := range addrs {
+			r.resolved[addr] = true
+		}
+		logrus.WithFields(logrus.Fields{"name": name, "addresses": addrs}).Debug("Resolved egress rule")
+	}
+}
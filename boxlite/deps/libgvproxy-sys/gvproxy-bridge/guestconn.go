@@ -0,0 +1,210 @@
+package main
+
+import (
+	"encoding/binary"
+	"io"
+	"net"
+	"sync"
+
+	logrus "github.com/sirupsen/logrus"
+)
+
+// Length prefix of frames on the Qemu stream protocol
+const qemuLengthSize = 4
+
+// guestTap sits between the VM and the virtual network: it filters and
+// paces the guest's frames, answers its queries for box names and from the
+// DNS cache, hands its IPv6 frames to the IPv6 stack, which the virtual
+// network does not route, counts the connections of port forwards and
+// records traffic captures
+type guestTap struct {
+	filter    *egressFilter   // nil without a policy
+	ipv6      *ipv6Stack      // nil without IPv6
+	ingress   *tokenBucket    // nil without an ingress limit
+	egress    *tokenBucket    // nil without an egress limit
+	names     *boxNames       // nil without inter-box networks
+	dns       *dnsCache       // nil without a DNS cache
+	forwarded *forwardedConns // nil without port forwards
+	capture   trafficCapture
+
+	inject func(frame []byte) // Writes a frame to the guest, set when wrapping
+}
+
+// fromGuest reports whether a frame from the guest goes on to the virtual
+// network
+func (t *guestTap) fromGuest(frame []byte) bool {
+	t.capture.record(frame)
+	if t.forwarded != nil {
+		t.forwarded.fromGuest(frame)
+	}
+	if t.filter != nil && !t.filter.allowEgress(frame) {
+		return false
+	}
+	t.egress.wait(len(frame))
+	if t.names != nil {
+		if reply := t.names.answer(frame); reply != nil {
+			t.inject(reply)
+			return false
+		}
+	}
+	if t.dns != nil && t.dns.intercept(frame, t.answerDNS) {
+		return false
+	}
+	if t.ipv6 != nil && len(frame) >= 14 && binary.BigEndian.Uint16(frame[12:14]) == etherTypeIPv6 {
+		t.ipv6.deliver(frame)
+		return false
+	}
+	return true
+}
+
+// toGuest sees a frame the virtual network sends to the guest
+func (t *guestTap) toGuest(frame []byte) {
+	t.capture.record(frame)
+	if t.forwarded != nil {
+		t.forwarded.toGuest(frame)
+	}
+	if t.filter != nil {
+		t.filter.inspectIngress(frame)
+	}
+}
+
+// answerDNS sends an answer of the DNS cache to the guest, showing it to
+// the egress filter like the virtual network's answers
+func (t *guestTap) answerDNS(frame []byte) {
+	if t.filter != nil {
+		t.filter.inspectIngress(frame)
+	}
+	t.inject(frame)
+}
+
+// attach sends injected frames to the guest through inject
+func (t *guestTap) attach(inject func(frame []byte)) {
+	t.inject = func(frame []byte) {
+		t.capture.record(frame)
+		inject(frame)
+	}
+	if t.ipv6 != nil {
+		t.ipv6.attach(t.inject)
+	}
+}
+
+// wrapStream taps a Qemu protocol connection. Connections are always
+// tapped, since a capture may start at any time.
+func (t *guestTap) wrapStream(conn net.Conn) net.Conn {
+	c := &tapStreamConn{Conn: conn, tap: t}
+	t.attach(c.inject)
+	return c
+}
+
+// wrapDatagram taps a VFKit protocol connection
+func (t *guestTap) wrapDatagram(conn net.Conn) net.Conn {
+	c := &tapDatagramConn{Conn: conn, tap: t}
+	t.attach(c.inject)
+	return c
+}
+
+// tapStreamConn carries length-prefixed frames
+type tapStreamConn struct {
+	net.Conn
+	tap     *guestTap
+	pending []byte // Frames for the virtual network not yet returned by Read
+
+	writeMu sync.Mutex
+	written []byte   // Start of a frame written by the virtual network
+	queued  [][]byte // Injected frames waiting for the end of that frame
+}
+
+func (c *tapStreamConn) Read(b []byte) (int, error) {
+	for len(c.pending) == 0 {
+		var size [qemuLengthSize]byte
+		if _, err := io.ReadFull(c.Conn, size[:]); err != nil {
+			return 0, err
+		}
+		frame := make([]byte, qemuLengthSize+int(binary.BigEndian.Uint32(size[:])))
+		copy(frame, size[:])
+		if _, err := io.ReadFull(c.Conn, frame[qemuLengthSize:]); err != nil {
+			return 0, err
+		}
+		if c.tap.fromGuest(frame[qemuLengthSize:]) {
+			c.pending = frame
+		}
+	}
+	n := copy(b, c.pending)
+	c.pending = c.pending[n:]
+	return n, nil
+}
+
+func (c *tapStreamConn) Write(b []byte) (int, error) {
+	c.tap.ingress.wait(len(b))
+	c.writeMu.Lock()
+	defer c.writeMu.Unlock()
+
+	n, err := c.Conn.Write(b)
+	c.written = append(c.written, b[:n]...)
+	for len(c.written) >= qemuLengthSize {
+		end := qemuLengthSize + int(binary.BigEndian.Uint32(c.written))
+		if len(c.written) < end {
+			break
+		}
+		c.tap.toGuest(c.written[qemuLengthSize:end])
+		c.written = c.written[end:]
+	}
+	if len(c.written) == 0 {
+		c.flushLocked()
+	}
+	return n, err
+}
+
+// inject writes a frame to the guest between the virtual network's frames
+func (c *tapStreamConn) inject(frame []byte) {
+	sized := make([]byte, qemuLengthSize+len(frame))
+	binary.BigEndian.PutUint32(sized, uint32(len(frame)))
+	copy(sized[qemuLengthSize:], frame)
+
+	c.tap.ingress.wait(len(sized))
+	c.writeMu.Lock()
+	defer c.writeMu.Unlock()
+	c.queued = append(c.queued, sized)
+	if len(c.written) == 0 {
+		c.flushLocked()
+	}
+}
+
+func (c *tapStreamConn) flushLocked() {
+	for _, frame := range c.queued {
+		if _, err := c.Conn.Write(frame); err != nil {
+			logrus.WithError(err).Trace("Failed to inject frame")
+			break
+		}
+	}
+	c.queued = nil
+}
+
+// tapDatagramConn carries one frame per datagram
+type tapDatagramConn struct {
+	net.Conn
+	tap *guestTap
+}
+
+func (c *tapDatagramConn) Read(b []byte) (int, error) {
+	for {
+		n, err := c.Conn.Read(b)
+		if err != nil || c.tap.fromGuest(b[:n]) {
+			return n, err
+		}
+	}
+}
+
+func (c *tapDatagramConn) Write(b []byte) (int, error) {
+	c.tap.toGuest(b)
+	c.tap.ingress.wait(len(b))
+	return c.Conn.Write(b)
+}
+
+// inject writes a frame to the guest
+func (c *tapDatagramConn) inject(frame []byte) {
+	c.tap.ingress.wait(len(frame))
+	if _, err := c.Conn.Write(frame); err != nil {
+		logrus.WithError(err).Trace("Failed to inject frame")
+	}
+}
@@ -0,0 +1,175 @@
+package main
+
+import (
+	"context"
+	"fmt"
+	"io"
+	"net"
+	"strconv"
+	"sync"
+	"time"
+
+	logrus "github.com/sirupsen/logrus"
+	"gvisor.dev/gvisor/pkg/buffer"
+	"gvisor.dev/gvisor/pkg/tcpip"
+	"gvisor.dev/gvisor/pkg/tcpip/adapters/gonet"
+	"gvisor.dev/gvisor/pkg/tcpip/header"
+	"gvisor.dev/gvisor/pkg/tcpip/link/channel"
+	"gvisor.dev/gvisor/pkg/tcpip/link/ethernet"
+	"gvisor.dev/gvisor/pkg/tcpip/network/ipv6"
+	"gvisor.dev/gvisor/pkg/tcpip/stack"
+	"gvisor.dev/gvisor/pkg/tcpip/transport/icmp"
+	"gvisor.dev/gvisor/pkg/tcpip/transport/tcp"
+	"gvisor.dev/gvisor/pkg/waiter"
+)
+
+const (
+	ipv6NIC tcpip.NICID = 1
+
+	// Prefix of the guest's IPv6 subnet (must match Rust IPV6_PREFIX_LEN)
+	ipv6PrefixLen = 64
+
+	// Frames queued towards the guest
+	ipv6QueueSize = 512
+
+	ipv6DialTimeout = 10 * time.Second
+)
+
+// ipv6Stack routes the guest's IPv6 traffic, which the virtual network of
+// gvisor-tap-vsock does not handle.
+//
+// It is a userspace stack on the gateway's IPv6 address that answers
+// neighbor discovery and pings, and terminates the guest's TCP connections
+// to open them again from the host.
+type ipv6Stack struct {
+	stack *stack.Stack
+	link  *channel.Endpoint
+
+	mu     sync.RWMutex
+	inject func(frame []byte) // Writes a frame to the guest, nil until it connects
+}
+
+func newIPv6Stack(gatewayIP, gatewayMac string, mtu uint16) (*ipv6Stack, error) {
+	ip := net.ParseIP(gatewayIP)
+	if ip == nil || ip.To4() != nil {
+		return nil, fmt.Errorf("invalid gateway IPv6 address %q", gatewayIP)
+	}
+	mac, err := net.ParseMAC(gatewayMac)
+	if err != nil {
+		return nil, fmt.Errorf("invalid gateway MAC %q: %w", gatewayMac, err)
+	}
+
+	s := stack.New(stack.Options{
+		NetworkProtocols:   []stack.NetworkProtocolFactory{ipv6.NewProtocol},
+		TransportProtocols: []stack.TransportProtocolFactory{tcp.NewProtocol, icmp.NewProtocol6},
+	})
+	link := channel.New(ipv6QueueSize, uint32(mtu), tcpip.LinkAddress(mac))
+	if err := s.CreateNIC(ipv6NIC, ethernet.New(link)); err != nil {
+		return nil, fmt.Errorf("failed to create IPv6 NIC: %s", err)
+	}
+	addr := tcpip.ProtocolAddress{
+		Protocol: ipv6.ProtocolNumber,
+		AddressWithPrefix: tcpip.AddressWithPrefix{
+			Address:   tcpip.AddrFromSlice(ip.To16()),
+			PrefixLen: ipv6PrefixLen,
+		},
+	}
+	if err := s.AddProtocolAddress(ipv6NIC, addr, stack.AddressProperties{}); err != nil {
+		return nil, fmt.Errorf("failed to add gateway IPv6 address: %s", err)
+	}
+
+	// Accept packets to any address, and answer from it: the guest
+	// connects to hosts on the internet through the gateway
+	if err := s.SetPromiscuousMode(ipv6NIC, true); err != nil {
+		return nil, fmt.Errorf("failed to enable promiscuous mode: %s", err)
+	}
+	if err := s.SetSpoofing(ipv6NIC, true); err != nil {
+		return nil, fmt.Errorf("failed to enable spoofing: %s", err)
+	}
+	s.SetRouteTable([]tcpip.Route{{Destination: header.IPv6EmptySubnet, NIC: ipv6NIC}})
+
+	v := &ipv6Stack{stack: s, link: link}
+	forwarder := tcp.NewForwarder(s, 0, 1024, v.forwardTCP)
+	s.SetTransportProtocolHandler(tcp.ProtocolNumber, forwarder.HandlePacket)
+	return v, nil
+}
+
+// attach sends the stack's frames to the guest through inject
+func (v *ipv6Stack) attach(inject func(frame []byte)) {
+	v.mu.Lock()
+	v.inject = inject
+	v.mu.Unlock()
+}
+
+// deliver hands an IPv6 Ethernet frame from the guest to the stack
+func (v *ipv6Stack) deliver(frame []byte) {
+	pkt := stack.NewPacketBuffer(stack.PacketBufferOptions{
+		Payload: buffer.MakeWithData(frame),
+	})
+	v.link.InjectInbound(header.IPv6ProtocolNumber, pkt)
+	pkt.DecRef()
+}
+
+// run writes the stack's frames to the guest until ctx is done
+func (v *ipv6Stack) run(ctx context.Context) {
+	defer v.stack.Close()
+	for {
+		pkt := v.link.ReadContext(ctx)
+		if pkt == nil {
+			return
+		}
+		v.mu.RLock()
+		inject := v.inject
+		v.mu.RUnlock()
+		if inject != nil {
+			view := pkt.ToView()
+			inject(view.AsSlice())
+			view.Release()
+		}
+		pkt.DecRef()
+	}
+}
+
+// forwardTCP connects to the destination of a guest connection from the
+// host, resetting the guest's connection if that fails
+func (v *ipv6Stack) forwardTCP(r *tcp.ForwarderRequest) {
+	id := r.ID()
+	remote := net.JoinHostPort(id.LocalAddress.String(), strconv.Itoa(int(id.LocalPort)))
+	outbound, err := net.DialTimeout("tcp6", remote, ipv6DialTimeout)
+	if err != nil {
+		logrus.WithFields(logrus.Fields{"remote": remote, "error": err}).Debug("IPv6 connection failed")
+		r.Complete(true)
+		return
+	}
+
+	var wq waiter.Queue
+	ep, tcpErr := r.CreateEndpoint(&wq)
+	r.Complete(false)
+	if tcpErr != nil {
+		logrus.WithFields(logrus.Fields{"remote": remote, "error": tcpErr}).Debug("Failed to accept IPv6 connection")
+		outbound.Close()
+		return
+	}
+	pipe(gonet.NewTCPConn(&wq, ep), outbound)
+}
+
+// pipe copies between two connections until both directions are closed
+func pipe(a, b net.Conn) {
+	defer a.Close()
+	defer b.Close()
+
+	done := make(chan struct{}, 2)
+	copyHalf := func(dst, src net.Conn) {
+		io.Copy(dst, src)
+		if c, ok := dst.(interface{ CloseWrite() error }); ok {
+			c.CloseWrite()
+		} else {
+			dst.Close()
+		}
+		done <- struct{}{}
+	}
+	go copyHalf(a, b)
+	go copyHalf(b, a)
+	<-done
+	<-done
+}
@@ -23,6 +23,7 @@ import (
 	"path/filepath"
 	"runtime"
 	"runtime/debug"
+	"strconv"
 	"sync"
 	"time"
 	"unsafe"
@@ -161,6 +162,7 @@ func gvproxy_set_log_callback(callback unsafe.Pointer) {
 
 // PortMapping represents a single port forward configuration
 type PortMapping struct {
+	HostIP    string `json:"host_ip,omitempty"` // Empty means all interfaces
 	HostPort  uint16 `json:"host_port"`
 	GuestPort uint16 `json:"guest_port"`
 }
@@ -175,17 +177,26 @@ type DNSZone struct {
 
 // GvproxyConfig matches the Rust structure (must stay in sync!)
 type GvproxyConfig struct {
-	Subnet           string        `json:"subnet"`
-	GatewayIP        string        `json:"gateway_ip"`
-	GatewayMac       string        `json:"gateway_mac"`
-	GuestIP          string        `json:"guest_ip"`
-	GuestMac         string        `json:"guest_mac"`
-	MTU              uint16        `json:"mtu"`
-	PortMappings     []PortMapping `json:"port_mappings"`
-	DNSZones         []DNSZone     `json:"dns_zones"`
-	DNSSearchDomains []string      `json:"dns_search_domains"`
-	Debug            bool          `json:"debug"`
-	CaptureFile      *string       `json:"capture_file,omitempty"`
+	Subnet           string          `json:"subnet"`
+	GatewayIP        string          `json:"gateway_ip"`
+	GatewayMac       string          `json:"gateway_mac"`
+	GuestIP          string          `json:"guest_ip"`
+	GuestMac         string          `json:"guest_mac"`
+	HostIP           string          `json:"host_ip"`
+	MTU              uint16          `json:"mtu"`
+	PortMappings     []PortMapping   `json:"port_mappings"`
+	DNSZones         []DNSZone       `json:"dns_zones"`
+	DNSSearchDomains []string        `json:"dns_search_domains"`
+	Debug            bool            `json:"debug"`
+	CaptureFile      *string         `json:"capture_file,omitempty"`
+	EgressPolicy     *EgressPolicy   `json:"egress_policy,omitempty"`
+	GuestIPv6        string          `json:"guest_ipv6,omitempty"`   // Empty for IPv4 only
+	GatewayIPv6      string          `json:"gateway_ipv6,omitempty"` // Set with GuestIPv6
+	RateLimit        *RateLimit      `json:"rate_limit,omitempty"`
+	HostsFiles       []string        `json:"hosts_files,omitempty"`         // Box names of inter-box networks
+	TunnelSocket     string          `json:"tunnel_socket,omitempty"`       // Connections to guest ports from the host
+	SocketBuffer     int             `json:"socket_buffer_bytes,omitempty"` // Buffers of our end of the VM's socket, system default if 0
+	DNSCache         *DNSCacheConfig `json:"dns_cache,omitempty"`           // Caching resolver on the gateway, none if nil
 }
 
 // GvproxyInstance tracks a running gvisor-tap-vsock instance
@@ -198,6 +209,7 @@ type GvproxyInstance struct {
 	listener   net.Listener                   // For Linux UnixStream (Qemu)
 	vn         *virtualnetwork.VirtualNetwork // Virtual network for stats collection
 	vnMu       sync.RWMutex                   // Protects vn field
+	tap        *guestTap                      // Frames between the VM and the virtual network
 }
 
 var (
@@ -216,6 +228,12 @@ func gvproxy_create(configJSON *C.char) C.longlong {
 		return -1
 	}
 
+	filter, err := newEgressFilter(config.EgressPolicy, config.GatewayIP, config.GatewayIPv6)
+	if err != nil {
+		logrus.WithError(err).Error("Invalid egress policy")
+		return -1
+	}
+
 	instancesMu.Lock()
 	id := nextID
 	nextID++
@@ -267,19 +285,39 @@ func gvproxy_create(configJSON *C.char) C.longlong {
 		DNSSearchDomains:  config.DNSSearchDomains,
 	}
 
+	// Let the guest reach services on the host's loopback interface
+	if config.HostIP != "" {
+		tapConfig.NAT[config.HostIP] = "127.0.0.1"
+		tapConfig.GatewayVirtualIPs = append(tapConfig.GatewayVirtualIPs, config.HostIP)
+	}
+
 	// Set CaptureFile if provided
 	if config.CaptureFile != nil && *config.CaptureFile != "" {
 		tapConfig.CaptureFile = *config.CaptureFile
 		logrus.WithField("capture_file", *config.CaptureFile).Info("Packet capture enabled")
 	}
 
+	if filter != nil {
+		logrus.WithFields(logrus.Fields{"allow": len(filter.allow), "deny": len(filter.deny)}).Info("Egress policy enabled")
+	}
+
+	if config.GuestIPv6 != "" {
+		logrus.WithFields(logrus.Fields{"guest": config.GuestIPv6, "gateway": config.GatewayIPv6}).Info("IPv6 enabled")
+	}
+
 	// Add port forwards from config
-	// Format: "0.0.0.0:PORT" for TCP (default), or "udp:0.0.0.0:PORT" for UDP
+	// Format: "IP:PORT" for TCP (default), or "udp:IP:PORT" for UDP; IP is
+	// the mapping's host_ip, or 0.0.0.0 for all IPv4 interfaces (empty,
+	// all IPv4 and IPv6 interfaces, with IPv6)
 	// Do NOT use "tcp://" prefix - it causes "too many colons in address" error
 	// Forward to guest's DHCP IP, not localhost
 	// Containers bind to 0.0.0.0 inside the guest, accessible via guest IP
 	for _, pm := range config.PortMappings {
-		forwardKey := fmt.Sprintf("0.0.0.0:%d", pm.HostPort)
+		hostIP := pm.HostIP
+		if hostIP == "" && config.GuestIPv6 == "" {
+			hostIP = "0.0.0.0"
+		}
+		forwardKey := net.JoinHostPort(hostIP, strconv.Itoa(int(pm.HostPort)))
 		forwardVal := fmt.Sprintf("%s:%d", config.GuestIP, pm.GuestPort)
 		tapConfig.Forwards[forwardKey] = forwardVal
 		logrus.WithFields(logrus.Fields{"host": forwardKey, "guest": forwardVal}).Info("Added TCP port forward")
@@ -288,7 +326,6 @@ func gvproxy_create(configJSON *C.char) C.longlong {
 	// Platform-specific socket creation
 	var conn net.Conn
 	var listener net.Listener
-	var err error
 
 	if runtime.GOOS == "darwin" {
 		// macOS: Use UnixDgram with VFKit protocol (SOCK_DGRAM)
@@ -299,6 +336,7 @@ func gvproxy_create(configJSON *C.char) C.longlong {
 			return -1
 		}
 		logrus.WithField("path", socketPath).Info("Created UnixDgram socket for VFKit protocol")
+		setSocketBuffer(conn, config.SocketBuffer)
 	} else {
 		// Linux: Use UnixStream with Qemu protocol (SOCK_STREAM)
 		listener, err = net.Listen("unix", socketPath)
@@ -309,9 +347,36 @@ func gvproxy_create(configJSON *C.char) C.longlong {
 		logrus.WithField("path", socketPath).Info("Created UnixStream socket for Qemu protocol")
 	}
 
+	tap := &guestTap{filter: filter}
+	tap.names = newBoxNames(config.HostsFiles, config.GatewayIP, config.DNSSearchDomains)
+	tap.dns = newDNSCache(config.DNSCache, config.GatewayIP)
+	tap.forwarded = newForwardedConns(config.GatewayIP, config.GuestIP, config.PortMappings)
+	if config.RateLimit != nil {
+		tap.ingress = newTokenBucket(config.RateLimit.IngressBps)
+		tap.egress = newTokenBucket(config.RateLimit.EgressBps)
+		logrus.WithFields(logrus.Fields{"ingress_bps": config.RateLimit.IngressBps, "egress_bps": config.RateLimit.EgressBps}).Info("Rate limit enabled")
+	}
+	if config.GuestIPv6 != "" {
+		tap.ipv6, err = newIPv6Stack(config.GatewayIPv6, config.GatewayMac, config.MTU)
+		if err != nil {
+			logrus.WithError(err).Error("Failed to create IPv6 stack")
+			if listener != nil {
+				listener.Close()
+			}
+			if conn != nil {
+				conn.Close()
+			}
+			return -1
+		}
+	}
+
 	// Start gvisor-tap-vsock in background
 	ctx, cancel := context.WithCancel(context.Background())
 
+	if tap.ipv6 != nil {
+		go tap.ipv6.run(ctx)
+	}
+
 	instance := &GvproxyInstance{
 		ID:         id,
 		SocketPath: socketPath,
@@ -319,6 +384,7 @@ func gvproxy_create(configJSON *C.char) C.longlong {
 		Cancel:     cancel,
 		conn:       conn,
 		listener:   listener,
+		tap:        tap,
 	}
 
 	instancesMu.Lock()
@@ -364,6 +430,10 @@ func gvproxy_create(configJSON *C.char) C.longlong {
 		instance.vn = vn
 		instance.vnMu.Unlock()
 
+		if config.TunnelSocket != "" {
+			go serveTunnel(ctx, config.TunnelSocket, vn, config.GuestIP)
+		}
+
 		// Platform-specific packet handling
 		if runtime.GOOS == "darwin" {
 			// macOS: Handle VFKit datagram packets
@@ -384,7 +454,7 @@ func gvproxy_create(configJSON *C.char) C.longlong {
 				logrus.WithFields(logrus.Fields{"id": id, "remote": wrappedConn.RemoteAddr().String()}).Info("VFKit connection accepted")
 
 				// Handle the VFKit protocol with the wrapped connection
-				if err := vn.AcceptVfkit(ctx, wrappedConn); err != nil {
+				if err := vn.AcceptVfkit(ctx, tap.wrapDatagram(wrappedConn)); err != nil {
 					if ctx.Err() == nil {
 						logrus.WithFields(logrus.Fields{"error": err, "id": id}).Error("AcceptVfkit error")
 					}
@@ -405,12 +475,13 @@ func gvproxy_create(configJSON *C.char) C.longlong {
 				}
 
 				logrus.WithFields(logrus.Fields{"id": id, "remote": acceptedConn.RemoteAddr().String()}).Info("Qemu connection accepted")
+				setSocketBuffer(acceptedConn, config.SocketBuffer)
 
 				// Close listener after first connection (one VM per gvproxy instance)
 				listener.Close()
 
 				// Handle the Qemu protocol
-				if err := vn.AcceptQemu(ctx, acceptedConn); err != nil {
+				if err := vn.AcceptQemu(ctx, tap.wrapStream(acceptedConn)); err != nil {
 					if ctx.Err() == nil {
 						logrus.WithFields(logrus.Fields{"error": err, "id": id}).Error("AcceptQemu error")
 					}
@@ -434,6 +505,21 @@ func gvproxy_create(configJSON *C.char) C.longlong {
 	return C.longlong(id)
 }
 
+// setSocketBuffer sizes the send and receive buffers of our end of the VM's
+// socket; size 0 keeps the system default
+func setSocketBuffer(conn net.Conn, size int) {
+	unixConn, ok := conn.(*net.UnixConn)
+	if size == 0 || !ok {
+		return
+	}
+	if err := unixConn.SetReadBuffer(size); err != nil {
+		logrus.WithFields(logrus.Fields{"error": err, "size": size}).Warn("Failed to set socket receive buffer")
+	}
+	if err := unixConn.SetWriteBuffer(size); err != nil {
+		logrus.WithFields(logrus.Fields{"error": err, "size": size}).Warn("Failed to set socket send buffer")
+	}
+}
+
 //export gvproxy_get_socket_path
 func gvproxy_get_socket_path(id C.longlong) *C.char {
 	instancesMu.RLock()
@@ -503,6 +589,52 @@ func gvproxy_get_stats(id C.longlong) *C.char {
 	return C.CString(stats)
 }
 
+//export gvproxy_forwarded_connections
+func gvproxy_forwarded_connections(id C.longlong) C.longlong {
+	instancesMu.RLock()
+	instance, ok := instances[int64(id)]
+	instancesMu.RUnlock()
+
+	if !ok {
+		return -1
+	}
+	return C.longlong(instance.tap.forwarded.count())
+}
+
+// captureResult is a capture's stats, or why it failed
+type captureResult struct {
+	CaptureStats
+	Error string `json:"error,omitempty"`
+}
+
+//export gvproxy_capture
+func gvproxy_capture(id C.longlong, configJSON *C.char) *C.char {
+	instancesMu.RLock()
+	instance, ok := instances[int64(id)]
+	instancesMu.RUnlock()
+
+	var result captureResult
+	var config CaptureConfig
+	if !ok {
+		result.Error = fmt.Sprintf("no gvproxy instance %d", id)
+	} else if err := json.Unmarshal([]byte(C.GoString(configJSON)), &config); err != nil {
+		result.Error = fmt.Sprintf("invalid capture config: %v", err)
+	} else {
+		// Blocks for the length of the capture
+		stats, err := instance.tap.capture.run(config)
+		result.CaptureStats = stats
+		if err != nil {
+			result.Error = err.Error()
+		}
+	}
+
+	out, err := json.Marshal(result)
+	if err != nil {
+		return nil
+	}
+	return C.CString(string(out))
+}
+
 //export gvproxy_get_version
 func gvproxy_get_version() *C.char {
 	// Get gvisor-tap-vsock version from build info
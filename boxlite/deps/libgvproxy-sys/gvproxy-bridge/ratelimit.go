@@ -0,0 +1,63 @@
+package main
+
+import (
+	"sync"
+	"time"
+)
+
+// Smallest burst of a bucket, so that slow limits still pass full frames
+const minBurstBytes = 64 * 1024
+
+// RateLimit caps the guest's bandwidth (must match Rust NetworkRateLimit)
+type RateLimit struct {
+	IngressBps uint64 `json:"ingress_bps,omitempty"` // Bits per second to the guest, 0 for no limit
+	EgressBps  uint64 `json:"egress_bps,omitempty"`  // Bits per second from the guest, 0 for no limit
+}
+
+// tokenBucket paces a stream of frames to a byte rate, allowing bursts of
+// a tenth of a second
+type tokenBucket struct {
+	mu     sync.Mutex
+	rate   float64 // Bytes per second
+	burst  float64
+	tokens float64
+	last   time.Time
+}
+
+// newTokenBucket returns nil for no limit
+func newTokenBucket(bitsPerSecond uint64) *tokenBucket {
+	if bitsPerSecond == 0 {
+		return nil
+	}
+	rate := float64(bitsPerSecond) / 8
+	burst := rate / 10
+	if burst < minBurstBytes {
+		burst = minBurstBytes
+	}
+	return &tokenBucket{rate: rate, burst: burst, tokens: burst, last: time.Now()}
+}
+
+// wait takes n bytes from the bucket, sleeping until the debt it leaves
+// is paid off; a nil bucket never waits
+func (b *tokenBucket) wait(n int) {
+	if b == nil {
+		return
+	}
+	b.mu.Lock()
+	now := time.Now()
+	b.tokens += now.Sub(b.last).Seconds() * b.rate
+	if b.tokens > b.burst {
+		b.tokens = b.burst
+	}
+	b.last = now
+	b.tokens -= float64(n)
+	var delay time.Duration
+	if b.tokens < 0 {
+		delay = time.Duration(-b.tokens / b.rate * float64(time.Second))
+	}
+	b.mu.Unlock()
+
+	if delay > 0 {
+		time.Sleep(delay)
+	}
+}
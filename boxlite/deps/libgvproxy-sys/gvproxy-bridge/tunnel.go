@@ -0,0 +1,97 @@
+package main
+
+import (
+	"context"
+	"encoding/binary"
+	"io"
+	"net"
+	"os"
+	"strconv"
+	"time"
+
+	"github.com/containers/gvisor-tap-vsock/pkg/virtualnetwork"
+	"github.com/sirupsen/logrus"
+)
+
+// Tunnel status byte sent back before relaying
+const (
+	tunnelOK     byte = 0
+	tunnelFailed byte = 1
+)
+
+// How long the guest gets to accept a tunneled connection
+const tunnelDialTimeout = 10 * time.Second
+
+// serveTunnel accepts connections on a Unix socket at path and connects each
+// to a TCP port of the guest over the virtual network, without a port
+// forward on the host. A client sends the guest port (2 bytes, big endian),
+// reads a status byte, and on tunnelOK talks to the guest's server.
+func serveTunnel(ctx context.Context, path string, vn *virtualnetwork.VirtualNetwork, guestIP string) {
+	if err := os.Remove(path); err != nil && !os.IsNotExist(err) {
+		logrus.WithFields(logrus.Fields{"error": err, "path": path}).Warn("Failed to remove existing tunnel socket")
+	}
+	listener, err := net.Listen("unix", path)
+	if err != nil {
+		logrus.WithFields(logrus.Fields{"error": err, "path": path}).Error("Failed to create tunnel socket")
+		return
+	}
+	logrus.WithField("path", path).Info("Serving tunnel to guest ports")
+
+	go func() {
+		<-ctx.Done()
+		listener.Close()
+		os.Remove(path)
+	}()
+
+	for {
+		conn, err := listener.Accept()
+		if err != nil {
+			if ctx.Err() == nil {
+				logrus.WithError(err).Error("Failed to accept tunnel connection")
+			}
+			return
+		}
+		go tunnel(ctx, conn, vn, guestIP)
+	}
+}
+
+func tunnel(ctx context.Context, conn net.Conn, vn *virtualnetwork.VirtualNetwork, guestIP string) {
+	defer conn.Close()
+
+	var header [2]byte
+	if _, err := io.ReadFull(conn, header[:]); err != nil {
+		return
+	}
+	port := binary.BigEndian.Uint16(header[:])
+	addr := net.JoinHostPort(guestIP, strconv.Itoa(int(port)))
+
+	dialCtx, cancel := context.WithTimeout(ctx, tunnelDialTimeout)
+	guest, err := vn.DialContextTCP(dialCtx, addr)
+	cancel()
+	if err != nil {
+		logrus.WithFields(logrus.Fields{"error": err, "guest": addr}).Debug("Failed to tunnel to guest port")
+		conn.Write([]byte{tunnelFailed})
+		return
+	}
+	defer guest.Close()
+	if _, err := conn.Write([]byte{tunnelOK}); err != nil {
+		return
+	}
+
+	done := make(chan struct{}, 2)
+	go relay(guest, conn, done)
+	go relay(conn, guest, done)
+	<-done
+	<-done
+}
+
+// relay copies src to dst, then passes the end of the stream on.
+func relay(dst, src net.Conn, done chan<- struct{}) {
+	io.Copy(dst, src)
+	if cw, ok := dst.(interface{ CloseWrite() error }); ok {
+		cw.CloseWrite()
+	} else {
+		dst.Close()
+	}
+	done <- struct{}{}
+}
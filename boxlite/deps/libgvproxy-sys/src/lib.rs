@@ -73,6 +73,30 @@ extern "C" {
     /// - Do not use pointer after calling gvproxy_free_string
     pub fn gvproxy_get_stats(id: c_longlong) -> *mut c_char;
 
+    /// Capture the guest's traffic to a pcap file
+    ///
+    /// Blocks until the capture's duration passes or its size limit is
+    /// reached.
+    ///
+    /// # Arguments
+    /// * `id` - Instance ID returned from gvproxy_create
+    /// * `configJSON` - JSON string with the file path, filter and limits
+    ///
+    /// # Returns
+    /// Pointer to a JSON string with the capture's stats and an `error`
+    /// field if it failed (must be freed with gvproxy_free_string), or NULL
+    /// if the result could not be serialized
+    pub fn gvproxy_capture(id: c_longlong, configJSON: *const c_char) -> *mut c_char;
+
+    /// Count the TCP connections the port forwards have open to the guest
+    ///
+    /// # Arguments
+    /// * `id` - Instance ID returned from gvproxy_create
+    ///
+    /// # Returns
+    /// Number of connections, or -1 if the instance doesn't exist
+    pub fn gvproxy_forwarded_connections(id: c_longlong) -> c_longlong;
+
     /// Get the libgvproxy version string
     ///
     /// # Returns
@@ -63,6 +63,14 @@ extern "C" {
         filepath: *const c_char,
         listen: bool,
     ) -> i32;
+
+    /// Don't add the vsock device libkrun creates by default, which has
+    /// TSI (Transparent Socket Impersonation) enabled.
+    pub fn krun_disable_implicit_vsock(ctx_id: u32) -> i32;
+
+    /// Add a vsock device with the given `KRUN_TSI_*` features (0 = no TSI).
+    pub fn krun_add_vsock(ctx_id: u32, tsi_features: u32) -> i32;
+
     pub fn krun_add_disk(
         ctx_id: u32,
         block_id: *const c_char,
@@ -92,6 +100,15 @@ extern "C" {
         features: u32,
         flags: u32,
     ) -> i32;
+    /// Add a network interface backed by the host's TAP device `c_tap_name`
+    /// (Linux only).
+    pub fn krun_add_net_tap(
+        ctx_id: u32,
+        c_tap_name: *const c_char,
+        c_mac: *const u8,
+        features: u32,
+        flags: u32,
+    ) -> i32;
     pub fn krun_start_enter(ctx_id: u32) -> i32;
 
     /// Set a file path to redirect the console output to.
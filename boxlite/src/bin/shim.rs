@@ -8,18 +8,21 @@
 //!
 //! ## Network Backend
 //!
-//! The shim creates the network backend (gvproxy) from network_config if present.
-//! This ensures networking survives detach operations - the gvproxy lives in the
-//! shim subprocess, not the main boxlite process.
+//! The shim creates the network backend (gvproxy, or passt when selected) from
+//! network_config if present. This ensures networking survives detach operations -
+//! the backend lives in the shim subprocess, not the main boxlite process.
+//! The host reads the backend's counters and captures the box's traffic
+//! through the shim's control socket.
 
 use std::path::Path;
 use std::thread;
 use std::time::Duration;
 
 use boxlite::{
+    net::{NetworkBackend, NetworkBackendKind, PasstBackend},
     runtime::layout,
     util::{self, is_process_alive},
-    vmm::{self, InstanceSpec, VmmConfig, VmmKind},
+    vmm::{self, InstanceSpec, VmmConfig, VmmKind, controller::control},
 };
 use boxlite_shared::errors::BoxliteResult;
 use clap::Parser;
@@ -27,7 +30,10 @@ use clap::Parser;
 use tracing_subscriber::{EnvFilter, fmt, prelude::*};
 
 #[cfg(feature = "gvproxy-backend")]
-use boxlite::net::{ConnectionType, NetworkBackendEndpoint, gvproxy::GvproxyInstance};
+use boxlite::net::{
+    ConnectionType, NetworkBackendEndpoint,
+    gvproxy::{GvproxyConfig, GvproxyInstance},
+};
 
 /// Universal Box runner binary - subprocess that executes isolated Boxes
 #[derive(Parser, Debug)]
@@ -102,19 +108,41 @@ fn main() -> BoxliteResult<()> {
         "Guest entrypoint configured"
     );
 
+    // Counters and captures of the network backend, for the control socket
+    #[allow(unused_mut)]
+    let mut control_handlers = control::ControlHandlers::default();
+
     // Create network backend (gvproxy) from network_config if present.
     // gvproxy provides virtio-net (eth0) to the guest - required even without port mappings.
     // The gvproxy instance is leaked intentionally - it must live for the entire
     // duration of the VM. When the shim process exits, OS cleans up all resources.
     #[cfg(feature = "gvproxy-backend")]
-    if let Some(ref net_config) = config.network_config {
+    if let Some(ref net_config) = config.network_config
+        && net_config.backend == NetworkBackendKind::Gvproxy
+        && net_config.host.is_none()
+    {
         tracing::info!(
             port_mappings = ?net_config.port_mappings,
+            policy = ?net_config.policy,
+            rate_limit = ?net_config.rate_limit,
+            ipv6 = net_config.ipv6,
+            tuning = ?net_config.tuning,
+            dns_cache = ?net_config.dns_cache,
             "Creating network backend (gvproxy) from config"
         );
 
         // Create gvproxy instance
-        let gvproxy = GvproxyInstance::new(&net_config.port_mappings)?;
+        let gvproxy = GvproxyInstance::with_config(
+            &GvproxyConfig::new(net_config.port_mappings.clone())
+                .with_egress_policy(net_config.policy.clone())
+                .with_rate_limit(net_config.rate_limit)
+                .with_ipv6(net_config.ipv6)
+                .with_hosts_files(&net_config.hosts_files)
+                .with_tunnel_socket(net_config.tunnel_socket.as_deref())
+                .with_mtu(net_config.tuning.mtu)
+                .with_socket_buffer(net_config.tuning.socket_buffer_bytes)
+                .with_dns_cache(net_config.dns_cache.as_ref())?,
+        )?;
         let socket_path = gvproxy.get_socket_path()?;
 
         tracing::info!(
@@ -144,10 +172,52 @@ fn main() -> BoxliteResult<()> {
         // Leak the gvproxy instance to keep it alive for VM lifetime.
         // This is intentional - the VM needs networking for its entire life,
         // and OS cleanup handles resources when process exits.
-        let _gvproxy_leaked = Box::leak(Box::new(gvproxy));
+        let gvproxy: &'static GvproxyInstance = Box::leak(Box::new(gvproxy));
         tracing::debug!("Leaked gvproxy instance for VM lifetime");
+        control_handlers.network_metrics = Box::new(move || Ok(Some(gvproxy.metrics()?)));
+        control_handlers.capture_traffic =
+            Box::new(move |path, filter| gvproxy.capture(path, filter));
+    }
+
+    // passt runs as a child process, which exits when the VM disconnects.
+    // Leaked like gvproxy for the VM lifetime.
+    if let Some(ref net_config) = config.network_config
+        && net_config.backend == NetworkBackendKind::Passt
+        && net_config.host.is_none()
+    {
+        tracing::info!(
+            port_mappings = ?net_config.port_mappings,
+            ipv6 = net_config.ipv6,
+            "Creating network backend (passt) from config"
+        );
+
+        let passt = PasstBackend::new(net_config.clone())?;
+        config.network_backend_endpoint = Some(passt.endpoint()?);
+        let _passt_leaked = Box::leak(Box::new(passt));
+    }
+
+    // Host network mode: the runtime already set up the TAP device
+    if let Some(ref net_config) = config.network_config
+        && let Some(ref host) = net_config.host
+    {
+        tracing::info!(tap = %host.tap, ip = %host.ip, "Using host network");
+        config.network_backend_endpoint = Some(boxlite::net::NetworkBackendEndpoint::Tap {
+            name: host.tap.clone(),
+            mac_address: host.mac_address,
+        });
     }
 
+    // The box still runs without it, only lacking the shim's metrics and
+    // captures
+    if let Some(ref path) = config.control_socket
+        && let Err(e) = control::serve(path, control_handlers)
+    {
+        tracing::warn!(error = %e, "Failed to serve control socket");
+    }
+
+    // Apply host memory policy before the engine allocates guest memory
+    vmm::memory::apply_memory_policy(&config.memory_policy);
+
     // Save detach/parent_pid before config is moved into engine.create()
     let detach = config.detach;
     let parent_pid = config.parent_pid;
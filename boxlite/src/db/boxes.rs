@@ -6,11 +6,13 @@
 //!
 //! Each table has queryable columns for filtering + JSON blob for full struct.
 
-use chrono::Utc;
-use rusqlite::{OptionalExtension, params};
+use chrono::{DateTime, Utc};
+use rusqlite::types::Value;
+use rusqlite::{OptionalExtension, params, params_from_iter};
 
+use crate::litebox::ExecRecord;
 use crate::litebox::config::BoxConfig;
-use crate::runtime::types::{BoxID, BoxState};
+use crate::runtime::types::{BoxID, BoxState, LabelRequirement, ListOptions};
 use boxlite_shared::errors::{BoxliteError, BoxliteResult};
 
 use super::{Database, db_err};
@@ -30,6 +32,11 @@ impl BoxStore {
         Self { db }
     }
 
+    /// Flush pending writes to the database file.
+    pub fn checkpoint(&self) -> BoxliteResult<()> {
+        self.db.checkpoint()
+    }
+
     // ========================================================================
     // BoxConfig operations (immutable after creation)
     // ========================================================================
@@ -50,15 +57,38 @@ impl BoxStore {
 
         match json {
             Some(j) => {
-                let config: BoxConfig = serde_json::from_str(&j).map_err(|e| {
-                    BoxliteError::Database(format!("Failed to deserialize config: {}", e))
-                })?;
+                let config = parse_config(&j)?;
                 Ok(Some(config))
             }
             None => Ok(None),
         }
     }
 
+    /// Update box configuration.
+    ///
+    /// For the few settings that may change after creation (name,
+    /// resources). Returns error if box doesn't exist.
+    pub fn update_config(&self, config: &BoxConfig) -> BoxliteResult<()> {
+        let conn = self.db.conn();
+
+        let json = serde_json::to_string(config)
+            .map_err(|e| BoxliteError::Database(format!("Failed to serialize config: {}", e)))?;
+
+        let rows_affected = db_err!(conn.execute(
+            "UPDATE box_config SET name = ?1, json = ?2 WHERE id = ?3",
+            params![config.name.as_deref(), json, config.id],
+        ))?;
+
+        if rows_affected == 0 {
+            return Err(BoxliteError::NotFound(format!(
+                "Box not found: {}",
+                config.id
+            )));
+        }
+
+        Ok(())
+    }
+
     /// Delete box configuration (and state via CASCADE).
     pub fn delete(&self, box_id: &str) -> BoxliteResult<bool> {
         let conn = self.db.conn();
@@ -197,9 +227,84 @@ impl BoxStore {
         let mut result = Vec::new();
         for row in rows {
             let (config_json, state_json) = db_err!(row)?;
-            let config: BoxConfig = serde_json::from_str(&config_json).map_err(|e| {
-                BoxliteError::Database(format!("Failed to deserialize config: {}", e))
+            let config = parse_config(&config_json)?;
+            let state: BoxState = serde_json::from_str(&state_json).map_err(|e| {
+                BoxliteError::Database(format!("Failed to deserialize state: {}", e))
             })?;
+            result.push((config, state));
+        }
+
+        Ok(result)
+    }
+
+    /// List the boxes matching `options`, newest first.
+    ///
+    /// Filters, `limit` and `offset` all go into the query.
+    pub fn list(&self, options: &ListOptions) -> BoxliteResult<Vec<(BoxConfig, BoxState)>> {
+        let mut conditions = Vec::new();
+        let mut values = Vec::new();
+
+        if let Some(status) = options.status {
+            conditions.push("s.status = ?".to_string());
+            values.push(Value::Text(status.as_str().to_string()));
+        }
+        if let Some(prefix) = &options.name_prefix {
+            conditions.push("substr(c.name, 1, length(?)) = ?".to_string());
+            values.push(Value::Text(prefix.clone()));
+            values.push(Value::Text(prefix.clone()));
+        }
+        if let Some(image) = &options.image {
+            conditions.push("json_extract(c.json, '$.options.rootfs.Image') = ?".to_string());
+            values.push(Value::Text(image.clone()));
+        }
+        for requirement in options.labels.requirements() {
+            let (negated, key, value) = match requirement {
+                LabelRequirement::Equals(key, value) => (false, key, Some(value)),
+                LabelRequirement::NotEquals(key, value) => (true, key, Some(value)),
+                LabelRequirement::Exists(key) => (false, key, None),
+                LabelRequirement::NotExists(key) => (true, key, None),
+            };
+            let mut condition = String::from(
+                "EXISTS (SELECT 1 FROM json_each(c.json, '$.options.labels') WHERE key = ?",
+            );
+            values.push(Value::Text(key.clone()));
+            if let Some(value) = value {
+                condition.push_str(" AND value = ?");
+                values.push(Value::Text(value.clone()));
+            }
+            condition.push(')');
+            if negated {
+                condition.insert_str(0, "NOT ");
+            }
+            conditions.push(condition);
+        }
+
+        let mut sql =
+            String::from("SELECT c.json, s.json FROM box_config c JOIN box_state s ON c.id = s.id");
+        if !conditions.is_empty() {
+            sql.push_str(" WHERE ");
+            sql.push_str(&conditions.join(" AND "));
+        }
+        sql.push_str(" ORDER BY c.created_at DESC, c.id DESC LIMIT ? OFFSET ?");
+        // A negative LIMIT means no limit
+        let limit = options
+            .limit
+            .map_or(-1, |l| i64::try_from(l).unwrap_or(i64::MAX));
+        values.push(Value::Integer(limit));
+        values.push(Value::Integer(
+            i64::try_from(options.offset).unwrap_or(i64::MAX),
+        ));
+
+        let conn = self.db.conn();
+        let mut stmt = db_err!(conn.prepare(&sql))?;
+        let rows = db_err!(stmt.query_map(params_from_iter(values), |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        }))?;
+
+        let mut result = Vec::new();
+        for row in rows {
+            let (config_json, state_json) = db_err!(row)?;
+            let config = parse_config(&config_json)?;
             let state: BoxState = serde_json::from_str(&state_json).map_err(|e| {
                 BoxliteError::Database(format!("Failed to deserialize state: {}", e))
             })?;
@@ -209,7 +314,7 @@ impl BoxStore {
         Ok(result)
     }
 
-    /// List active boxes (Starting, Running, Detached).
+    /// List active boxes (Starting, Running, Paused, Detached).
     pub fn list_active(&self) -> BoxliteResult<Vec<(BoxConfig, BoxState)>> {
         let conn = self.db.conn();
 
@@ -218,7 +323,7 @@ impl BoxStore {
             SELECT c.json as config_json, s.json as state_json
             FROM box_config c
             JOIN box_state s ON c.id = s.id
-            WHERE s.status IN ('starting', 'running', 'detached')
+            WHERE s.status IN ('starting', 'running', 'paused', 'detached')
             ORDER BY c.created_at DESC
             "#
         ))?;
@@ -232,9 +337,7 @@ impl BoxStore {
         let mut result = Vec::new();
         for row in rows {
             let (config_json, state_json) = db_err!(row)?;
-            let config: BoxConfig = serde_json::from_str(&config_json).map_err(|e| {
-                BoxliteError::Database(format!("Failed to deserialize config: {}", e))
-            })?;
+            let config = parse_config(&config_json)?;
             let state: BoxState = serde_json::from_str(&state_json).map_err(|e| {
                 BoxliteError::Database(format!("Failed to deserialize state: {}", e))
             })?;
@@ -244,6 +347,106 @@ impl BoxStore {
         Ok(result)
     }
 
+    // ========================================================================
+    // Exec history operations
+    // ========================================================================
+
+    /// Insert or update an exec history record.
+    ///
+    /// Called when a command starts and again when it finishes.
+    pub fn save_exec(&self, box_id: &str, record: &ExecRecord) -> BoxliteResult<()> {
+        let conn = self.db.conn();
+
+        let args = serde_json::to_string(&record.args)
+            .map_err(|e| BoxliteError::Database(format!("Failed to serialize args: {}", e)))?;
+
+        db_err!(conn.execute(
+            r#"
+            INSERT INTO exec_history (
+                execution_id, box_id, command, args, started_at,
+                finished_at, exit_code, output_bytes, output_preview, output_digest
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+            ON CONFLICT(execution_id) DO UPDATE SET
+                finished_at = ?6, exit_code = ?7, output_bytes = ?8, output_preview = ?9,
+                output_digest = ?10
+            "#,
+            params![
+                record.id,
+                box_id,
+                record.command,
+                args,
+                record.started_at.timestamp_millis(),
+                record.finished_at.map(|t| t.timestamp_millis()),
+                record.exit_code,
+                record.output_bytes as i64,
+                record.output_preview,
+                record.output_digest,
+            ],
+        ))?;
+
+        Ok(())
+    }
+
+    /// List exec history of a box, oldest first.
+    pub fn exec_history(&self, box_id: &str) -> BoxliteResult<Vec<ExecRecord>> {
+        let conn = self.db.conn();
+
+        let mut stmt = db_err!(conn.prepare(
+            r#"
+            SELECT execution_id, command, args, started_at, finished_at,
+                   exit_code, output_bytes, output_preview, output_digest
+            FROM exec_history
+            WHERE box_id = ?1
+            ORDER BY started_at ASC, rowid ASC
+            "#
+        ))?;
+
+        let rows = db_err!(stmt.query_map(params![box_id], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, i64>(3)?,
+                row.get::<_, Option<i64>>(4)?,
+                row.get::<_, Option<i32>>(5)?,
+                row.get::<_, i64>(6)?,
+                row.get::<_, String>(7)?,
+                row.get::<_, Option<String>>(8)?,
+            ))
+        }))?;
+
+        let mut result = Vec::new();
+        for row in rows {
+            let (
+                id,
+                command,
+                args,
+                started_at,
+                finished_at,
+                exit_code,
+                output_bytes,
+                preview,
+                digest,
+            ) = db_err!(row)?;
+            let args: Vec<String> = serde_json::from_str(&args).map_err(|e| {
+                BoxliteError::Database(format!("Failed to deserialize args: {}", e))
+            })?;
+            result.push(ExecRecord {
+                id,
+                command,
+                args,
+                started_at: from_millis(started_at)?,
+                finished_at: finished_at.map(from_millis).transpose()?,
+                exit_code,
+                output_bytes: output_bytes as u64,
+                output_preview: preview,
+                output_digest: digest,
+            });
+        }
+
+        Ok(result)
+    }
+
     // ========================================================================
     // Reboot detection via alive table
     // ========================================================================
@@ -304,10 +507,48 @@ impl BoxStore {
     }
 }
 
+fn from_millis(ms: i64) -> BoxliteResult<DateTime<Utc>> {
+    DateTime::from_timestamp_millis(ms)
+        .ok_or_else(|| BoxliteError::Database(format!("Invalid timestamp: {}", ms)))
+}
+
 /// Get system boot ID (unique per boot).
 ///
 /// On macOS: Uses kern.bootsessionuuid
 /// On Linux: Uses /proc/sys/kernel/random/boot_id
+/// Deserialize a stored config, moving the `tmpfs` mounts of configs from
+/// before [`VolumeSpec::Tmpfs`] into its volumes.
+///
+/// [`VolumeSpec::Tmpfs`]: crate::runtime::options::VolumeSpec::Tmpfs
+fn parse_config(json: &str) -> BoxliteResult<BoxConfig> {
+    let invalid = |e: serde_json::Error| {
+        BoxliteError::Database(format!("Failed to deserialize config: {}", e))
+    };
+    let mut config: serde_json::Value = serde_json::from_str(json).map_err(invalid)?;
+    if let Some(options) = config
+        .get_mut("options")
+        .and_then(serde_json::Value::as_object_mut)
+        && let Some(serde_json::Value::Array(tmpfs)) = options.remove("tmpfs")
+    {
+        let volumes = options
+            .entry("volumes")
+            .or_insert_with(|| serde_json::Value::Array(Vec::new()));
+        if let Some(volumes) = volumes.as_array_mut() {
+            for mount in tmpfs {
+                let size = mount
+                    .get("size_mib")
+                    .and_then(serde_json::Value::as_u64)
+                    .map(|mib| mib << 20);
+                volumes.push(serde_json::json!({
+                    "guest_path": mount.get("guest_path"),
+                    "size": size,
+                }));
+            }
+        }
+    }
+    serde_json::from_value(config).map_err(invalid)
+}
+
 fn get_boot_id() -> String {
     #[cfg(target_os = "macos")]
     {
@@ -392,6 +633,30 @@ mod tests {
         assert_eq!(loaded.unwrap().id, config.id);
     }
 
+    #[test]
+    fn test_parse_config_legacy_tmpfs() {
+        use crate::runtime::options::VolumeSpec;
+
+        let mut json = serde_json::to_value(create_test_config(TEST_ID_1)).unwrap();
+        json["options"]["tmpfs"] = serde_json::json!([
+            {"guest_path": "/scratch", "size_mib": 64},
+            {"guest_path": "/cache", "size_mib": null},
+        ]);
+
+        let config = parse_config(&json.to_string()).unwrap();
+        assert_eq!(
+            config.options.volumes,
+            [
+                VolumeSpec::Tmpfs {
+                    guest_path: "/scratch".to_string(),
+                    size: Some(64 << 20),
+                    mode: None,
+                },
+                VolumeSpec::tmpfs("/cache"),
+            ]
+        );
+    }
+
     #[test]
     fn test_save_and_load_state() {
         let (store, _dir) = create_test_db();
@@ -453,6 +718,73 @@ mod tests {
         assert_eq!(all.len(), 3);
     }
 
+    #[test]
+    fn test_list_with_options() {
+        use crate::runtime::options::RootfsSpec;
+        use crate::runtime::types::LabelSelector;
+
+        let (store, _dir) = create_test_db();
+        let created_at = Utc::now();
+        for (id, name, tenant, status) in [
+            (TEST_ID_1, "web-1", "acme", BoxStatus::Running),
+            (TEST_ID_2, "web-2", "globex", BoxStatus::Stopped),
+            (TEST_ID_3, "db-1", "acme", BoxStatus::Running),
+        ] {
+            let mut config = create_test_config(id);
+            config.name = Some(name.to_string());
+            config.created_at = created_at;
+            config
+                .options
+                .labels
+                .insert("tenant".to_string(), tenant.to_string());
+            if name == "db-1" {
+                config.options.rootfs = RootfsSpec::Image("postgres:16".to_string());
+            }
+            let mut state = BoxState::new();
+            state.set_status(status);
+            store.save(&config, &state).unwrap();
+        }
+        let ids = |options: ListOptions| -> Vec<String> {
+            let boxes = store.list(&options).unwrap();
+            boxes.into_iter().map(|(c, _)| c.id.to_string()).collect()
+        };
+
+        assert_eq!(ids(ListOptions::default()).len(), 3);
+        assert_eq!(
+            ids(ListOptions {
+                status: Some(BoxStatus::Running),
+                name_prefix: Some("web-".to_string()),
+                ..Default::default()
+            }),
+            vec![TEST_ID_1]
+        );
+        assert_eq!(
+            ids(ListOptions {
+                labels: LabelSelector::new().eq("tenant", "acme"),
+                image: Some("postgres:16".to_string()),
+                ..Default::default()
+            }),
+            vec![TEST_ID_3]
+        );
+        assert_eq!(
+            ids(ListOptions {
+                labels: "!tenant".parse().unwrap(),
+                ..Default::default()
+            }),
+            Vec::<String>::new()
+        );
+
+        // Same creation time, so ordered by ID
+        assert_eq!(
+            ids(ListOptions {
+                limit: Some(1),
+                offset: 1,
+                ..Default::default()
+            }),
+            vec![TEST_ID_2]
+        );
+    }
+
     #[test]
     fn test_list_active() {
         let (store, _dir) = create_test_db();
@@ -474,6 +806,46 @@ mod tests {
         assert_eq!(active[0].0.id.as_str(), TEST_ID_1);
     }
 
+    #[test]
+    fn test_exec_history() {
+        let (store, _dir) = create_test_db();
+        let config = create_test_config(TEST_ID_1);
+        store.save(&config, &BoxState::new()).unwrap();
+
+        let started_at = DateTime::from_timestamp_millis(1_700_000_000_000).unwrap();
+        let mut record = ExecRecord {
+            id: "exec-1".to_string(),
+            command: "echo".to_string(),
+            args: vec!["hello".to_string()],
+            started_at,
+            finished_at: None,
+            exit_code: None,
+            output_bytes: 0,
+            output_preview: String::new(),
+            output_digest: None,
+        };
+        store.save_exec(TEST_ID_1, &record).unwrap();
+        assert_eq!(store.exec_history(TEST_ID_1).unwrap(), vec![record.clone()]);
+
+        record.finished_at = Some(started_at + chrono::Duration::milliseconds(1500));
+        record.exit_code = Some(0);
+        record.output_bytes = 6;
+        record.output_preview = "hello\n".to_string();
+        record.output_digest = Some("sha256:abc".to_string());
+        store.save_exec(TEST_ID_1, &record).unwrap();
+
+        let history = store.exec_history(TEST_ID_1).unwrap();
+        assert_eq!(history, vec![record]);
+        assert_eq!(
+            history[0].duration(),
+            Some(std::time::Duration::from_millis(1500))
+        );
+
+        // Removed with the box
+        store.delete(TEST_ID_1).unwrap();
+        assert!(store.exec_history(TEST_ID_1).unwrap().is_empty());
+    }
+
     #[test]
     fn test_reboot_detection() {
         let (store, _dir) = create_test_db();
@@ -75,6 +75,26 @@ impl ImageIndexStore {
         }
     }
 
+    /// Get a complete cached image by its config digest, under any
+    /// reference.
+    pub fn find_by_config(&self, config_digest: &str) -> BoxliteResult<Option<CachedImage>> {
+        let reference: Option<String> = {
+            let conn = self.db.conn();
+            db_err!(
+                conn.query_row(
+                    "SELECT reference FROM image_index WHERE config_digest = ?1 AND complete = 1 LIMIT 1",
+                    params![config_digest],
+                    |row| row.get(0),
+                )
+                .optional()
+            )?
+        };
+        match reference {
+            Some(reference) => self.get(&reference),
+            None => Ok(None),
+        }
+    }
+
     /// Add or update cached image.
     pub fn upsert(&self, reference: &str, image: &CachedImage) -> BoxliteResult<()> {
         let conn = self.db.conn();
@@ -198,6 +218,27 @@ mod tests {
         assert_eq!(store.len().unwrap(), 1);
     }
 
+    #[test]
+    fn test_find_by_config() {
+        let (store, _dir) = create_test_db();
+
+        let mut image = CachedImage {
+            manifest_digest: "sha256:abc123".to_string(),
+            config_digest: "sha256:config123".to_string(),
+            layers: vec!["sha256:layer1".to_string()],
+            cached_at: "2025-10-24T12:00:00Z".to_string(),
+            complete: false,
+        };
+        store.upsert("python:alpine", &image).unwrap();
+        assert!(store.find_by_config("sha256:config123").unwrap().is_none());
+
+        image.complete = true;
+        store.upsert("python:alpine", &image).unwrap();
+        let loaded = store.find_by_config("sha256:config123").unwrap().unwrap();
+        assert_eq!(loaded.manifest_digest, "sha256:abc123");
+        assert!(store.find_by_config("sha256:other").unwrap().is_none());
+    }
+
     #[test]
     fn test_get_nonexistent() {
         let (store, _dir) = create_test_db();
@@ -8,7 +8,9 @@
 
 mod boxes;
 mod images;
+mod networks;
 mod schema;
+mod volumes;
 
 use std::path::Path;
 use std::sync::Arc;
@@ -21,6 +23,8 @@ use boxlite_shared::errors::{BoxliteError, BoxliteResult};
 
 pub use boxes::BoxStore;
 pub use images::{CachedImage, ImageIndexStore};
+pub use networks::NetworkStore;
+pub use volumes::VolumeStore;
 
 /// Helper macro to convert rusqlite errors to BoxliteError.
 macro_rules! db_err {
@@ -75,6 +79,14 @@ impl Database {
         self.conn.lock()
     }
 
+    /// Write the WAL back into the database file and truncate it.
+    pub fn checkpoint(&self) -> BoxliteResult<()> {
+        db_err!(
+            self.conn()
+                .execute_batch("PRAGMA wal_checkpoint(TRUNCATE);")
+        )
+    }
+
     /// Initialize database schema.
     ///
     /// Order of operations:
@@ -175,6 +187,53 @@ impl Database {
             current = 4;
         }
 
+        // Migration 4 -> 5: Add exec_history table
+        if current == 4 {
+            tracing::info!("Running migration 4 -> 5: Adding exec_history table");
+
+            db_err!(conn.execute_batch(schema::EXEC_HISTORY_TABLE))?;
+
+            current = 5;
+        }
+
+        // Migration 5 -> 6: Add network tables
+        if current == 5 {
+            tracing::info!("Running migration 5 -> 6: Adding network tables");
+
+            db_err!(conn.execute_batch(schema::NETWORK_TABLE))?;
+            db_err!(conn.execute_batch(schema::NETWORK_ENDPOINT_TABLE))?;
+
+            current = 6;
+        }
+
+        // Migration 6 -> 7: Add volume table
+        if current == 6 {
+            tracing::info!("Running migration 6 -> 7: Adding volume table");
+
+            db_err!(conn.execute_batch(schema::VOLUME_TABLE))?;
+
+            current = 7;
+        }
+
+        // Migration 7 -> 8: Add exec_history.output_digest, which tables
+        // created by migration 4 -> 5 above already have
+        if current == 7 {
+            tracing::info!("Running migration 7 -> 8: Adding exec_history.output_digest");
+
+            let has_column: i64 = db_err!(conn.query_row(
+                "SELECT COUNT(*) FROM pragma_table_info('exec_history') WHERE name = 'output_digest'",
+                [],
+                |row| row.get(0),
+            ))?;
+            if has_column == 0 {
+                db_err!(
+                    conn.execute_batch("ALTER TABLE exec_history ADD COLUMN output_digest TEXT;")
+                )?;
+            }
+
+            current = 8;
+        }
+
         // Update schema version
         let now = Utc::now().to_rfc3339();
         db_err!(conn.execute(
@@ -0,0 +1,307 @@
+//! Inter-box network storage.
+//!
+//! Networks get a /24 of [`SUBNET_POOL`] each; boxes get an address in it
+//! the first time they start on the network, and keep it until removed.
+
+use std::net::Ipv4Addr;
+
+use chrono::{DateTime, Utc};
+use rusqlite::{OptionalExtension, params};
+
+use crate::runtime::types::{BoxID, NetworkInfo};
+use boxlite_shared::errors::{BoxliteError, BoxliteResult};
+
+use super::{Database, db_err};
+
+/// First two octets of network subnets, `10.89.N.0/24`.
+const SUBNET_POOL: [u8; 2] = [10, 89];
+
+/// Box addresses within a subnet (`.1` is left out as customary for a gateway).
+const HOSTS: std::ops::RangeInclusive<u8> = 2..=254;
+
+/// Network storage wrapping Database.
+#[derive(Clone)]
+pub struct NetworkStore {
+    db: Database,
+}
+
+impl NetworkStore {
+    /// Create a new NetworkStore from a Database.
+    pub fn new(db: Database) -> Self {
+        Self { db }
+    }
+
+    /// Create a network on the first free subnet.
+    ///
+    /// Returns `AlreadyExists` if the name is taken, and `ResourceExhausted`
+    /// when all subnets are in use.
+    pub fn create(&self, name: &str) -> BoxliteResult<NetworkInfo> {
+        let conn = self.db.conn();
+
+        let exists: Option<String> = db_err!(
+            conn.query_row(
+                "SELECT name FROM network WHERE name = ?1",
+                params![name],
+                |row| row.get(0),
+            )
+            .optional()
+        )?;
+        if exists.is_some() {
+            return Err(BoxliteError::AlreadyExists(format!(
+                "network '{}' already exists",
+                name
+            )));
+        }
+
+        let mut stmt = db_err!(conn.prepare("SELECT subnet FROM network"))?;
+        let used: Vec<String> =
+            db_err!(db_err!(stmt.query_map([], |row| row.get(0)))?.collect::<Result<Vec<_>, _>>())?;
+        let subnet = (0..=u8::MAX)
+            .map(subnet_of)
+            .find(|subnet| !used.contains(subnet))
+            .ok_or_else(|| {
+                BoxliteError::ResourceExhausted("no free network subnet left".to_string())
+            })?;
+
+        let created_at = Utc::now();
+        db_err!(conn.execute(
+            "INSERT INTO network (name, subnet, created_at) VALUES (?1, ?2, ?3)",
+            params![name, subnet, created_at.timestamp()],
+        ))?;
+
+        Ok(NetworkInfo {
+            name: name.to_string(),
+            subnet,
+            // Round to the stored precision
+            created_at: from_timestamp(created_at.timestamp())?,
+        })
+    }
+
+    /// Get a network by name.
+    pub fn get(&self, name: &str) -> BoxliteResult<Option<NetworkInfo>> {
+        let conn = self.db.conn();
+        let row: Option<(String, String, i64)> = db_err!(
+            conn.query_row(
+                "SELECT name, subnet, created_at FROM network WHERE name = ?1",
+                params![name],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .optional()
+        )?;
+        row.map(|(name, subnet, created_at)| {
+            Ok(NetworkInfo {
+                name,
+                subnet,
+                created_at: from_timestamp(created_at)?,
+            })
+        })
+        .transpose()
+    }
+
+    /// List all networks, oldest first.
+    pub fn list(&self) -> BoxliteResult<Vec<NetworkInfo>> {
+        let conn = self.db.conn();
+        let mut stmt = db_err!(
+            conn.prepare("SELECT name, subnet, created_at FROM network ORDER BY created_at, name")
+        )?;
+        let rows: Vec<(String, String, i64)> = db_err!(
+            db_err!(stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?))))?
+                .collect::<Result<Vec<_>, _>>()
+        )?;
+        rows.into_iter()
+            .map(|(name, subnet, created_at)| {
+                Ok(NetworkInfo {
+                    name,
+                    subnet,
+                    created_at: from_timestamp(created_at)?,
+                })
+            })
+            .collect()
+    }
+
+    /// Delete a network and the addresses of its boxes.
+    pub fn delete(&self, name: &str) -> BoxliteResult<bool> {
+        let conn = self.db.conn();
+        let rows_affected =
+            db_err!(conn.execute("DELETE FROM network WHERE name = ?1", params![name]))?;
+        Ok(rows_affected > 0)
+    }
+
+    /// Address of a box on a network, assigning the first free one if the
+    /// box has none yet.
+    pub fn attach(&self, network: &str, box_id: &BoxID) -> BoxliteResult<Ipv4Addr> {
+        let mut conn = self.db.conn();
+        let tx = db_err!(conn.transaction())?;
+
+        let existing: Option<String> = db_err!(
+            tx.query_row(
+                "SELECT ip FROM network_endpoint WHERE network = ?1 AND box_id = ?2",
+                params![network, box_id.as_str()],
+                |row| row.get(0),
+            )
+            .optional()
+        )?;
+        if let Some(ip) = existing {
+            return parse_ip(&ip);
+        }
+
+        let subnet: String = db_err!(
+            tx.query_row(
+                "SELECT subnet FROM network WHERE name = ?1",
+                params![network],
+                |row| row.get(0),
+            )
+            .optional()
+        )?
+        .ok_or_else(|| BoxliteError::NotFound(format!("network '{}' not found", network)))?;
+        let base = parse_ip(subnet.split('/').next().unwrap_or_default())?.octets();
+
+        let used: Vec<String> = {
+            let mut stmt =
+                db_err!(tx.prepare("SELECT ip FROM network_endpoint WHERE network = ?1"))?;
+            db_err!(
+                db_err!(stmt.query_map(params![network], |row| row.get(0)))?
+                    .collect::<Result<Vec<_>, _>>()
+            )?
+        };
+        let ip = HOSTS
+            .map(|host| Ipv4Addr::new(base[0], base[1], base[2], host))
+            .find(|ip| !used.contains(&ip.to_string()))
+            .ok_or_else(|| {
+                BoxliteError::ResourceExhausted(format!("network '{}' is full", network))
+            })?;
+
+        db_err!(tx.execute(
+            "INSERT INTO network_endpoint (network, box_id, ip) VALUES (?1, ?2, ?3)",
+            params![network, box_id.as_str(), ip.to_string()],
+        ))?;
+        db_err!(tx.commit())?;
+        Ok(ip)
+    }
+
+    /// Boxes with an address on a network: (ID, name, address).
+    pub fn endpoints(
+        &self,
+        network: &str,
+    ) -> BoxliteResult<Vec<(BoxID, Option<String>, Ipv4Addr)>> {
+        let conn = self.db.conn();
+        let mut stmt = db_err!(conn.prepare(
+            r#"
+            SELECT e.box_id, c.name, e.ip
+            FROM network_endpoint e
+            JOIN box_config c ON c.id = e.box_id
+            WHERE e.network = ?1
+            ORDER BY e.rowid
+            "#
+        ))?;
+        let rows: Vec<(String, Option<String>, String)> = db_err!(
+            db_err!(stmt.query_map(params![network], |row| Ok((
+                row.get(0)?,
+                row.get(1)?,
+                row.get(2)?
+            ))))?
+            .collect::<Result<Vec<_>, _>>()
+        )?;
+        rows.into_iter()
+            .map(|(id, name, ip)| {
+                let id = BoxID::parse(&id)
+                    .ok_or_else(|| BoxliteError::Database(format!("Invalid box ID: {}", id)))?;
+                Ok((id, name, parse_ip(&ip)?))
+            })
+            .collect()
+    }
+}
+
+fn subnet_of(index: u8) -> String {
+    format!("{}.{}.{}.0/24", SUBNET_POOL[0], SUBNET_POOL[1], index)
+}
+
+fn parse_ip(ip: &str) -> BoxliteResult<Ipv4Addr> {
+    ip.parse()
+        .map_err(|e| BoxliteError::Database(format!("Invalid network address {}: {}", ip, e)))
+}
+
+fn from_timestamp(secs: i64) -> BoxliteResult<DateTime<Utc>> {
+    DateTime::from_timestamp(secs, 0)
+        .ok_or_else(|| BoxliteError::Database(format!("Invalid timestamp: {}", secs)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::BoxStore;
+    use crate::litebox::config::{BoxConfig, ContainerRuntimeConfig};
+    use crate::runtime::types::{BoxState, ContainerID};
+    use crate::vmm::VmmKind;
+    use boxlite_shared::Transport;
+    use std::path::PathBuf;
+    use tempfile::tempdir;
+
+    fn save_box(db: &Database, name: &str) -> BoxID {
+        let config = BoxConfig {
+            id: BoxID::new(),
+            name: Some(name.to_string()),
+            created_at: Utc::now(),
+            container: ContainerRuntimeConfig {
+                id: ContainerID::new(),
+            },
+            options: Default::default(),
+            engine_kind: VmmKind::Libkrun,
+            transport: Transport::unix(PathBuf::from("/tmp/test.sock")),
+            box_home: PathBuf::from("/tmp/boxes/test"),
+            ready_socket_path: PathBuf::from("/tmp/ready.sock"),
+        };
+        BoxStore::new(db.clone())
+            .save(&config, &BoxState::new())
+            .unwrap();
+        config.id
+    }
+
+    #[test]
+    fn test_create_allocates_subnets() {
+        let dir = tempdir().unwrap();
+        let store = NetworkStore::new(Database::open(&dir.path().join("test.db")).unwrap());
+
+        assert_eq!(store.create("backend").unwrap().subnet, "10.89.0.0/24");
+        assert_eq!(store.create("frontend").unwrap().subnet, "10.89.1.0/24");
+        assert!(matches!(
+            store.create("backend"),
+            Err(BoxliteError::AlreadyExists(_))
+        ));
+
+        assert!(store.delete("backend").unwrap());
+        assert_eq!(store.create("cache").unwrap().subnet, "10.89.0.0/24");
+        assert_eq!(store.list().unwrap().len(), 2);
+        assert!(store.get("backend").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_attach_is_stable() {
+        let dir = tempdir().unwrap();
+        let db = Database::open(&dir.path().join("test.db")).unwrap();
+        let store = NetworkStore::new(db.clone());
+        store.create("backend").unwrap();
+        let app = save_box(&db, "app");
+        let redis = save_box(&db, "redis");
+
+        let app_ip = store.attach("backend", &app).unwrap();
+        assert_eq!(app_ip, Ipv4Addr::new(10, 89, 0, 2));
+        assert_eq!(
+            store.attach("backend", &redis).unwrap(),
+            Ipv4Addr::new(10, 89, 0, 3)
+        );
+        assert_eq!(store.attach("backend", &app).unwrap(), app_ip);
+        assert!(matches!(
+            store.attach("missing", &app),
+            Err(BoxliteError::NotFound(_))
+        ));
+
+        let endpoints = store.endpoints("backend").unwrap();
+        assert_eq!(endpoints.len(), 2);
+        assert_eq!(endpoints[1].1.as_deref(), Some("redis"));
+
+        // Removing the box frees its address
+        BoxStore::new(db).delete(app.as_str()).unwrap();
+        assert_eq!(store.endpoints("backend").unwrap().len(), 1);
+    }
+}
@@ -7,7 +7,7 @@
 //! Each table has queryable columns for efficient filtering + JSON blob for full data.
 
 /// Current schema version.
-pub const SCHEMA_VERSION: i32 = 4;
+pub const SCHEMA_VERSION: i32 = 8;
 
 /// Schema version tracking table.
 pub const SCHEMA_VERSION_TABLE: &str = r#"
@@ -80,6 +80,72 @@ CREATE TABLE IF NOT EXISTS image_index (
 CREATE INDEX IF NOT EXISTS idx_image_index_manifest_digest ON image_index(manifest_digest);
 "#;
 
+/// Exec history table schema.
+///
+/// One row per command executed in a box, for auditing. Inserted when the
+/// command starts; `finished_at`, `exit_code` and the output columns are
+/// filled in once it exits. Times are Unix milliseconds.
+pub const EXEC_HISTORY_TABLE: &str = r#"
+CREATE TABLE IF NOT EXISTS exec_history (
+    execution_id TEXT PRIMARY KEY NOT NULL,
+    box_id TEXT NOT NULL,
+    command TEXT NOT NULL,
+    args TEXT NOT NULL,
+    started_at INTEGER NOT NULL,
+    finished_at INTEGER,
+    exit_code INTEGER,
+    output_bytes INTEGER NOT NULL DEFAULT 0,
+    output_preview TEXT NOT NULL DEFAULT '',
+    output_digest TEXT,
+    FOREIGN KEY (box_id) REFERENCES box_config(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_exec_history_box_id ON exec_history(box_id, started_at);
+"#;
+
+/// Network table schema.
+///
+/// One row per inter-box network. Queryable columns: name, subnet (unique,
+/// each network has its own).
+pub const NETWORK_TABLE: &str = r#"
+CREATE TABLE IF NOT EXISTS network (
+    name TEXT PRIMARY KEY NOT NULL,
+    subnet TEXT UNIQUE NOT NULL,
+    created_at INTEGER NOT NULL
+);
+"#;
+
+/// Network endpoint table schema.
+///
+/// The address of a box on a network, kept while the box exists so that
+/// it does not change across restarts.
+pub const NETWORK_ENDPOINT_TABLE: &str = r#"
+CREATE TABLE IF NOT EXISTS network_endpoint (
+    network TEXT NOT NULL,
+    box_id TEXT NOT NULL,
+    ip TEXT NOT NULL,
+    PRIMARY KEY (network, box_id),
+    UNIQUE (network, ip),
+    FOREIGN KEY (network) REFERENCES network(name) ON DELETE CASCADE,
+    FOREIGN KEY (box_id) REFERENCES box_config(id) ON DELETE CASCADE
+);
+"#;
+
+/// Volume table schema.
+///
+/// One row per named volume. `size_bytes` is set for volumes backed by a
+/// disk image; `path` is the volume's directory or disk image.
+pub const VOLUME_TABLE: &str = r#"
+CREATE TABLE IF NOT EXISTS volume (
+    name TEXT PRIMARY KEY NOT NULL,
+    kind TEXT NOT NULL,
+    size_bytes INTEGER,
+    path TEXT NOT NULL,
+    created_at INTEGER NOT NULL,
+    lock_id INTEGER
+);
+"#;
+
 /// Get all schema creation statements.
 pub fn all_schemas() -> Vec<&'static str> {
     vec![
@@ -88,5 +154,9 @@ pub fn all_schemas() -> Vec<&'static str> {
         BOX_STATE_TABLE,
         ALIVE_TABLE,
         IMAGE_INDEX_TABLE,
+        EXEC_HISTORY_TABLE,
+        NETWORK_TABLE,
+        NETWORK_ENDPOINT_TABLE,
+        VOLUME_TABLE,
     ]
 }
@@ -0,0 +1,194 @@
+//! Named volume storage.
+//!
+//! The rows only record volumes; their data lives in the volume's directory
+//! or disk image under the home directory, created and removed by the
+//! runtime.
+
+use chrono::DateTime;
+use rusqlite::{OptionalExtension, params};
+
+use crate::lock::LockId;
+use crate::runtime::types::{VolumeInfo, VolumeKind};
+use boxlite_shared::errors::{BoxliteError, BoxliteResult};
+
+use super::{Database, db_err};
+
+/// Volume storage wrapping Database.
+#[derive(Clone)]
+pub struct VolumeStore {
+    db: Database,
+}
+
+/// Row of the volume table: name, kind, disk size, path, creation time.
+type VolumeRow = (String, String, Option<i64>, String, i64);
+
+impl VolumeStore {
+    /// Create a new VolumeStore from a Database.
+    pub fn new(db: Database) -> Self {
+        Self { db }
+    }
+
+    /// Record a volume, with the lock a disk volume is held by while mounted.
+    ///
+    /// Returns `AlreadyExists` if the name is taken.
+    pub fn create(&self, volume: &VolumeInfo, lock_id: Option<LockId>) -> BoxliteResult<()> {
+        let conn = self.db.conn();
+        let (kind, size_bytes) = match volume.kind {
+            VolumeKind::Directory => ("directory", None),
+            VolumeKind::Disk { size_bytes } => ("disk", Some(size_bytes as i64)),
+        };
+        let inserted = db_err!(conn.execute(
+            "INSERT OR IGNORE INTO volume (name, kind, size_bytes, path, created_at, lock_id) \
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                volume.name,
+                kind,
+                size_bytes,
+                volume.path.to_string_lossy(),
+                volume.created_at.timestamp(),
+                lock_id.map(|id| id.0)
+            ],
+        ))?;
+        if inserted == 0 {
+            return Err(BoxliteError::AlreadyExists(format!(
+                "volume '{}' already exists",
+                volume.name
+            )));
+        }
+        Ok(())
+    }
+
+    /// Get a volume by name.
+    pub fn get(&self, name: &str) -> BoxliteResult<Option<VolumeInfo>> {
+        let conn = self.db.conn();
+        let row: Option<VolumeRow> = db_err!(
+            conn.query_row(
+                "SELECT name, kind, size_bytes, path, created_at FROM volume WHERE name = ?1",
+                params![name],
+                read_row,
+            )
+            .optional()
+        )?;
+        row.map(volume_from_row).transpose()
+    }
+
+    /// List all volumes, oldest first.
+    pub fn list(&self) -> BoxliteResult<Vec<VolumeInfo>> {
+        let conn = self.db.conn();
+        let mut stmt = db_err!(conn.prepare(
+            "SELECT name, kind, size_bytes, path, created_at FROM volume \
+             ORDER BY created_at, name"
+        ))?;
+        let rows: Vec<VolumeRow> =
+            db_err!(db_err!(stmt.query_map([], read_row))?.collect::<Result<Vec<_>, _>>())?;
+        rows.into_iter().map(volume_from_row).collect()
+    }
+
+    /// Lock of a volume, if it has one.
+    pub fn lock_id(&self, name: &str) -> BoxliteResult<Option<LockId>> {
+        let conn = self.db.conn();
+        let lock_id: Option<Option<u32>> = db_err!(
+            conn.query_row(
+                "SELECT lock_id FROM volume WHERE name = ?1",
+                params![name],
+                |row| row.get(0),
+            )
+            .optional()
+        )?;
+        Ok(lock_id.flatten().map(LockId))
+    }
+
+    /// Locks of all volumes, to reclaim on startup.
+    pub fn lock_ids(&self) -> BoxliteResult<Vec<LockId>> {
+        let conn = self.db.conn();
+        let mut stmt =
+            db_err!(conn.prepare("SELECT lock_id FROM volume WHERE lock_id IS NOT NULL"))?;
+        let ids: Vec<u32> =
+            db_err!(db_err!(stmt.query_map([], |row| row.get(0)))?.collect::<Result<Vec<_>, _>>())?;
+        Ok(ids.into_iter().map(LockId).collect())
+    }
+
+    /// Delete a volume's row.
+    pub fn delete(&self, name: &str) -> BoxliteResult<bool> {
+        let conn = self.db.conn();
+        let rows_affected =
+            db_err!(conn.execute("DELETE FROM volume WHERE name = ?1", params![name]))?;
+        Ok(rows_affected > 0)
+    }
+}
+
+fn read_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<VolumeRow> {
+    Ok((
+        row.get(0)?,
+        row.get(1)?,
+        row.get(2)?,
+        row.get(3)?,
+        row.get(4)?,
+    ))
+}
+
+fn volume_from_row(
+    (name, kind, size_bytes, path, created_at): VolumeRow,
+) -> BoxliteResult<VolumeInfo> {
+    let kind = match (kind.as_str(), size_bytes) {
+        ("directory", _) => VolumeKind::Directory,
+        ("disk", Some(size_bytes)) => VolumeKind::Disk {
+            size_bytes: size_bytes as u64,
+        },
+        _ => {
+            return Err(BoxliteError::Database(format!(
+                "Invalid kind of volume '{}': {}",
+                name, kind
+            )));
+        }
+    };
+    Ok(VolumeInfo {
+        name,
+        kind,
+        path: path.into(),
+        created_at: DateTime::from_timestamp(created_at, 0)
+            .ok_or_else(|| BoxliteError::Database(format!("Invalid timestamp: {}", created_at)))?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_create_and_list() {
+        let dir = tempdir().unwrap();
+        let store = VolumeStore::new(Database::open(&dir.path().join("test.db")).unwrap());
+        let volume = |name: &str, kind| VolumeInfo {
+            name: name.to_string(),
+            kind,
+            path: dir.path().join(name),
+            created_at: DateTime::from_timestamp(Utc::now().timestamp(), 0).unwrap(),
+        };
+
+        let cache = volume("cache", VolumeKind::Directory);
+        let db = volume(
+            "db",
+            VolumeKind::Disk {
+                size_bytes: 1 << 30,
+            },
+        );
+        store.create(&cache, None).unwrap();
+        store.create(&db, Some(LockId(3))).unwrap();
+        assert!(matches!(
+            store.create(&cache, None),
+            Err(BoxliteError::AlreadyExists(_))
+        ));
+
+        assert_eq!(store.get("db").unwrap(), Some(db));
+        assert_eq!(store.lock_id("db").unwrap(), Some(LockId(3)));
+        assert_eq!(store.lock_id("cache").unwrap(), None);
+        assert_eq!(store.lock_ids().unwrap(), [LockId(3)]);
+        assert_eq!(store.list().unwrap().len(), 2);
+        assert!(store.delete("cache").unwrap());
+        assert!(!store.delete("cache").unwrap());
+        assert!(store.get("cache").unwrap().is_none());
+    }
+}
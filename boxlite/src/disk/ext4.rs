@@ -160,6 +160,54 @@ pub fn create_ext4_from_dir(source: &Path, output_path: &Path) -> BoxliteResult<
     ))
 }
 
+/// Create an empty ext4 disk image of `size_bytes` using mke2fs.
+///
+/// The root directory is owned by 0:0. Returns a persistent Disk, which
+/// outlives the handle.
+pub fn create_blank_ext4(output_path: &Path, size_bytes: u64) -> BoxliteResult<Disk> {
+    let size_blocks = size_bytes.max(MIN_DISK_SIZE_BYTES) / BLOCK_SIZE;
+
+    let output_str = output_path.to_str().ok_or_else(|| {
+        BoxliteError::Storage(format!("Invalid output path: {}", output_path.display()))
+    })?;
+
+    let mke2fs = get_mke2fs_path();
+    let output = Command::new(&mke2fs)
+        .args([
+            "-t",
+            "ext4",
+            "-b",
+            "4096",
+            "-m",
+            "0",
+            "-E",
+            "root_owner=0:0",
+            "-F",
+            "-q",
+            output_str,
+            &size_blocks.to_string(),
+        ])
+        .output()
+        .map_err(|e| {
+            BoxliteError::Storage(format!(
+                "Failed to run mke2fs ({}): {}",
+                mke2fs.display(),
+                e
+            ))
+        })?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(BoxliteError::Storage(format!(
+            "mke2fs failed with exit code {:?}: {}",
+            output.status.code(),
+            stderr
+        )));
+    }
+
+    Ok(Disk::new(output_path.to_path_buf(), DiskFormat::Ext4, true))
+}
+
 /// Fix ownership of all files in ext4 image to 0:0 using debugfs.
 ///
 /// mke2fs -E root_owner=0:0 only sets the root inode.
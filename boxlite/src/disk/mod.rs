@@ -4,6 +4,7 @@
 //! - `Disk` - RAII wrapper for disk image files
 //! - `DiskFormat` - Disk format types (Ext4, Qcow2)
 //! - `create_ext4_from_dir` - Create ext4 filesystem from directory
+//! - `create_blank_ext4` - Create an empty ext4 filesystem
 //! - `Qcow2Helper` - QCOW2 copy-on-write disk creation
 
 pub mod constants;
@@ -11,6 +12,6 @@ pub(crate) mod ext4;
 mod image;
 mod qcow2;
 
-pub use ext4::create_ext4_from_dir;
+pub use ext4::{create_blank_ext4, create_ext4_from_dir};
 pub use image::{Disk, DiskFormat};
 pub use qcow2::{BackingFormat, Qcow2Helper};
@@ -13,15 +13,30 @@ use qcow2_rs::meta::Qcow2Header;
 use super::constants::qcow2::{BLOCK_SIZE, CLUSTER_BITS, DEFAULT_DISK_SIZE_GB, REFCOUNT_ORDER};
 use super::{Disk, DiskFormat};
 
+/// qcow2 magic bytes ("QFI\xfb").
+const QCOW2_MAGIC: u32 = 0x514649fb;
+
+/// Incompatible feature bit: image refcounts may be stale (unclean shutdown).
+const INCOMPAT_DIRTY: u64 = 1 << 0;
+
+/// Incompatible feature bit: image was marked corrupt by a previous writer.
+const INCOMPAT_CORRUPT: u64 = 1 << 1;
+
+/// Incompatible feature bits we know how to interpret (dirty, corrupt,
+/// external data file, compression type, extended L2).
+const INCOMPAT_KNOWN: u64 = 0x1f;
+
 /// Parsed qcow2 header information.
-#[allow(dead_code)]
 #[derive(Debug)]
 struct Qcow2HeaderInfo {
-    #[allow(dead_code)]
     version: u32,
-    size: u64,
-    #[allow(dead_code)]
+    backing_file_offset: u64,
+    backing_file_size: u32,
     cluster_bits: u32,
+    size: u64,
+    l1_table_offset: u64,
+    refcount_table_offset: u64,
+    incompatible_features: u64,
 }
 
 /// Helper for qcow2 disk operations.
@@ -253,8 +268,124 @@ impl Qcow2Helper {
         Ok(header.size)
     }
 
+    /// Quick structural consistency check of a qcow2 image.
+    ///
+    /// This is not a full `qemu-img check`: it only validates what can be
+    /// verified cheaply from the header, which is enough to catch truncated
+    /// files, images flagged corrupt by a previous writer, and COW overlays
+    /// whose backing file has disappeared.
+    ///
+    /// Checks performed:
+    /// - Magic and version (2 or 3)
+    /// - No unknown incompatible features, and the corrupt bit is not set
+    /// - L1 and refcount tables are cluster-aligned and inside the file
+    /// - Backing file (if any) still exists
+    pub fn check_consistency(path: &Path) -> BoxliteResult<()> {
+        let header = Self::read_qcow2_header(path)?;
+
+        if header.version != 2 && header.version != 3 {
+            return Err(BoxliteError::Storage(format!(
+                "Unsupported qcow2 version {} in {}",
+                header.version,
+                path.display()
+            )));
+        }
+
+        if header.incompatible_features & INCOMPAT_CORRUPT != 0 {
+            return Err(BoxliteError::Storage(format!(
+                "qcow2 image {} is marked corrupt",
+                path.display()
+            )));
+        }
+
+        let unknown = header.incompatible_features & !INCOMPAT_KNOWN;
+        if unknown != 0 {
+            return Err(BoxliteError::Storage(format!(
+                "qcow2 image {} has unknown incompatible features 0x{:x}",
+                path.display(),
+                unknown
+            )));
+        }
+
+        if header.incompatible_features & INCOMPAT_DIRTY != 0 {
+            tracing::warn!(
+                "qcow2 image {} was not closed cleanly (dirty bit set)",
+                path.display()
+            );
+        }
+
+        if !(9..=21).contains(&header.cluster_bits) {
+            return Err(BoxliteError::Storage(format!(
+                "Invalid qcow2 cluster_bits {} in {}",
+                header.cluster_bits,
+                path.display()
+            )));
+        }
+
+        let file_len = std::fs::metadata(path)
+            .map_err(|e| {
+                BoxliteError::Storage(format!("Failed to stat {}: {}", path.display(), e))
+            })?
+            .len();
+        let cluster_mask = (1u64 << header.cluster_bits) - 1;
+
+        for (name, offset) in [
+            ("L1 table", header.l1_table_offset),
+            ("refcount table", header.refcount_table_offset),
+        ] {
+            if offset & cluster_mask != 0 || offset >= file_len {
+                return Err(BoxliteError::Storage(format!(
+                    "qcow2 {} offset 0x{:x} is invalid for {} ({} bytes)",
+                    name,
+                    offset,
+                    path.display(),
+                    file_len
+                )));
+            }
+        }
+
+        if header.backing_file_offset != 0 {
+            let backing = Self::read_backing_file(path, &header)?;
+            if !Path::new(&backing).exists() {
+                return Err(BoxliteError::Storage(format!(
+                    "Backing file {} of {} not found",
+                    backing,
+                    path.display()
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Read the backing file path stored in a qcow2 header.
+    fn read_backing_file(path: &Path, header: &Qcow2HeaderInfo) -> BoxliteResult<String> {
+        use std::io::{Read, Seek, SeekFrom};
+
+        let mut file = std::fs::File::open(path).map_err(|e| {
+            BoxliteError::Storage(format!("Failed to open {}: {}", path.display(), e))
+        })?;
+
+        let mut buf = vec![0u8; header.backing_file_size as usize];
+        file.seek(SeekFrom::Start(header.backing_file_offset))
+            .and_then(|_| file.read_exact(&mut buf))
+            .map_err(|e| {
+                BoxliteError::Storage(format!(
+                    "Failed to read backing file name from {}: {}",
+                    path.display(),
+                    e
+                ))
+            })?;
+
+        String::from_utf8(buf).map_err(|_| {
+            BoxliteError::Storage(format!(
+                "Backing file name in {} is not valid UTF-8",
+                path.display()
+            ))
+        })
+    }
+
     /// Read qcow2 header from disk file.
-    #[allow(dead_code)]
     fn read_qcow2_header(path: &Path) -> BoxliteResult<Qcow2HeaderInfo> {
         use std::io::Read;
 
@@ -272,9 +403,11 @@ impl Qcow2Helper {
         })?;
 
         // Parse qcow2 header (big-endian)
-        let magic = u32::from_be_bytes([header[0], header[1], header[2], header[3]]);
-        if magic != 0x514649fb {
-            // "QFI\xfb"
+        let be_u32 = |off: usize| u32::from_be_bytes(header[off..off + 4].try_into().unwrap());
+        let be_u64 = |off: usize| u64::from_be_bytes(header[off..off + 8].try_into().unwrap());
+
+        let magic = be_u32(0);
+        if magic != QCOW2_MAGIC {
             return Err(BoxliteError::Storage(format!(
                 "Invalid qcow2 magic in {}: 0x{:08x}",
                 path.display(),
@@ -282,17 +415,18 @@ impl Qcow2Helper {
             )));
         }
 
-        let version = u32::from_be_bytes([header[4], header[5], header[6], header[7]]);
-        let size = u64::from_be_bytes([
-            header[24], header[25], header[26], header[27], header[28], header[29], header[30],
-            header[31],
-        ]);
-        let cluster_bits = u32::from_be_bytes([header[20], header[21], header[22], header[23]]);
+        let version = be_u32(4);
 
         Ok(Qcow2HeaderInfo {
             version,
-            size,
-            cluster_bits,
+            backing_file_offset: be_u64(8),
+            backing_file_size: be_u32(16),
+            cluster_bits: be_u32(20),
+            size: be_u64(24),
+            l1_table_offset: be_u64(40),
+            refcount_table_offset: be_u64(48),
+            // Feature bitmaps only exist in v3 headers
+            incompatible_features: if version >= 3 { be_u64(72) } else { 0 },
         })
     }
 
@@ -372,7 +506,7 @@ impl Qcow2Helper {
 
         // Write qcow2 v3 header
         // Magic (QFI\xfb)
-        header[0..4].copy_from_slice(&0x514649fbu32.to_be_bytes());
+        header[0..4].copy_from_slice(&QCOW2_MAGIC.to_be_bytes());
         // Version 3
         header[4..8].copy_from_slice(&3u32.to_be_bytes());
         // Backing file offset
@@ -471,6 +605,31 @@ impl Qcow2Helper {
         Ok(())
     }
 
+    /// Copy a disk, and everything it reads from its backing chain, into a
+    /// standalone qcow2 image at `target`.
+    ///
+    /// Uses the external qemu-img binary.
+    pub fn flatten(source: &Path, target: &Path) -> BoxliteResult<()> {
+        // Equivalent to: qemu-img convert -O qcow2 child.qcow2 flat.qcow2
+        let output = Command::new("qemu-img")
+            .args(["convert", "-O", "qcow2"])
+            .arg(source)
+            .arg(target)
+            .output()
+            .map_err(|e| {
+                BoxliteError::Storage(format!("Failed to run qemu-img (is it installed?): {}", e))
+            })?;
+
+        if !output.status.success() {
+            return Err(BoxliteError::Storage(format!(
+                "Failed to flatten disk {}: {}",
+                source.display(),
+                String::from_utf8_lossy(&output.stderr)
+            )));
+        }
+        Ok(())
+    }
+
     /// Create COW child disk using external qemu-img binary.
     #[allow(dead_code)]
     fn create_cow_child_disk_external(
@@ -555,3 +714,64 @@ impl BackingFormat {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Seek, SeekFrom};
+
+    /// Create a raw base image and a COW child on top of it.
+    fn create_child(dir: &Path) -> std::path::PathBuf {
+        let base = dir.join("base.ext4");
+        std::fs::write(&base, vec![0u8; 1024 * 1024]).unwrap();
+
+        let child = dir.join("root.qcow2");
+        Qcow2Helper::new()
+            .create_cow_child_disk(&base, BackingFormat::Raw, &child, 64 * 1024 * 1024)
+            .unwrap()
+            .leak();
+        child
+    }
+
+    #[test]
+    fn test_check_consistency_valid_child() {
+        let dir = tempfile::tempdir().unwrap();
+        let child = create_child(dir.path());
+
+        assert!(Qcow2Helper::check_consistency(&child).is_ok());
+    }
+
+    #[test]
+    fn test_check_consistency_corrupt_bit() {
+        let dir = tempfile::tempdir().unwrap();
+        let child = create_child(dir.path());
+
+        // incompatible_features lives at offset 72 in v3 headers
+        let mut file = OpenOptions::new().write(true).open(&child).unwrap();
+        file.seek(SeekFrom::Start(72)).unwrap();
+        file.write_all(&INCOMPAT_CORRUPT.to_be_bytes()).unwrap();
+        drop(file);
+
+        let err = Qcow2Helper::check_consistency(&child).unwrap_err();
+        assert!(err.to_string().contains("marked corrupt"));
+    }
+
+    #[test]
+    fn test_check_consistency_missing_backing_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let child = create_child(dir.path());
+        std::fs::remove_file(dir.path().join("base.ext4")).unwrap();
+
+        let err = Qcow2Helper::check_consistency(&child).unwrap_err();
+        assert!(err.to_string().contains("not found"));
+    }
+
+    #[test]
+    fn test_check_consistency_bad_magic() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("garbage.qcow2");
+        std::fs::write(&path, vec![0u8; 4096]).unwrap();
+
+        assert!(Qcow2Helper::check_consistency(&path).is_err());
+    }
+}
@@ -9,6 +9,14 @@ pub struct BindMountConfig<'a> {
     pub source: &'a Path,
     pub target: &'a Path,
     pub read_only: bool,
+    /// FUSE only: trade speed for full filesystem semantics.
+    ///
+    /// The default FUSE mount skips open/opendir round trips and has no
+    /// xattr support, which breaks tools that rely on open file handles,
+    /// xattrs or locks (git, sqlite). Full fidelity enables real open and
+    /// opendir, xattrs, and kernel-side POSIX/flock locking. Native bind
+    /// mounts always have full fidelity.
+    pub full_fidelity: bool,
 }
 
 #[cfg_attr(not(target_os = "linux"), allow(dead_code))]
@@ -18,6 +26,7 @@ impl<'a> BindMountConfig<'a> {
             source,
             target,
             read_only: false,
+            full_fidelity: false,
         }
     }
 
@@ -25,4 +34,11 @@ impl<'a> BindMountConfig<'a> {
         self.read_only = true;
         self
     }
+
+    // No FUSE mount needs it yet: volumes are shared with virtiofs directly
+    #[allow(dead_code)]
+    pub fn full_fidelity(mut self) -> Self {
+        self.full_fidelity = true;
+        self
+    }
 }
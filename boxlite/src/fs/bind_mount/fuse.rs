@@ -1,6 +1,8 @@
 //! FUSE-based bind mount for rootless operation.
 //!
-//! Uses fuse-backend-rs passthrough filesystem with fusermount3.
+//! Uses fuse-backend-rs passthrough filesystem with fusermount3. The
+//! default mode skips open/opendir and xattrs for speed;
+//! [`BindMountConfig::full_fidelity`] turns them back on.
 
 use boxlite_shared::errors::{BoxliteError, BoxliteResult};
 use fuse_backend_rs::api::server::Server;
@@ -27,7 +29,7 @@ impl FuseBindMount {
 
         ensure_target_dir_exists(&target)?;
 
-        let fs = create_passthrough_fs(&source, config.read_only)?;
+        let fs = create_passthrough_fs(&source, config.read_only, config.full_fidelity)?;
         let mut session = create_fuse_session(&target)?;
         mount_session(&mut session, &target)?;
 
@@ -38,6 +40,7 @@ impl FuseBindMount {
             source = %source.display(),
             target = %target.display(),
             read_only = config.read_only,
+            full_fidelity = config.full_fidelity,
             "FUSE bind mount created"
         );
 
@@ -87,16 +90,13 @@ impl BindMountImpl for FuseBindMount {
 // Helper functions
 // ============================================================================
 
-fn create_passthrough_fs(source: &Path, read_only: bool) -> BoxliteResult<Arc<PassthroughFs>> {
-    let config = Config {
-        root_dir: source.to_string_lossy().to_string(),
-        do_import: false,
-        writeback: !read_only,
-        no_open: true,
-        no_opendir: true,
-        killpriv_v2: false,
-        ..Default::default()
-    };
+fn create_passthrough_fs(
+    source: &Path,
+    read_only: bool,
+    full_fidelity: bool,
+) -> BoxliteResult<Arc<PassthroughFs>> {
+    let config = passthrough_config(source, read_only, full_fidelity);
+    let import_on_init = config.do_import;
 
     let fs = PassthroughFs::new(config).map_err(|e| {
         BoxliteError::Storage(format!(
@@ -106,17 +106,53 @@ fn create_passthrough_fs(source: &Path, read_only: bool) -> BoxliteResult<Arc<Pa
         ))
     })?;
 
-    fs.import().map_err(|e| {
-        BoxliteError::Storage(format!(
-            "Failed to initialize passthrough filesystem for {}: {}",
-            source.display(),
-            e
-        ))
-    })?;
+    if !import_on_init {
+        fs.import().map_err(|e| {
+            BoxliteError::Storage(format!(
+                "Failed to initialize passthrough filesystem for {}: {}",
+                source.display(),
+                e
+            ))
+        })?;
+    }
 
     Ok(Arc::new(fs))
 }
 
+fn passthrough_config(source: &Path, read_only: bool, full_fidelity: bool) -> Config {
+    let root_dir = source.to_string_lossy().to_string();
+
+    if !full_fidelity {
+        return Config {
+            root_dir,
+            do_import: false,
+            writeback: !read_only,
+            no_open: true,
+            no_opendir: true,
+            killpriv_v2: false,
+            ..Default::default()
+        };
+    }
+
+    // With do_import=false the passthrough fs negotiates every fast-path
+    // option the kernel offers and ignores no_open/no_opendir, so import
+    // on FUSE init to have them honored.
+    //
+    // Locks: PassthroughFs doesn't negotiate POSIX_LOCKS/FLOCK_LOCKS, so the
+    // kernel handles fcntl and flock locks on the mount itself. That needs
+    // real open handles, hence no_open=false.
+    Config {
+        root_dir,
+        do_import: true,
+        writeback: !read_only,
+        xattr: true,
+        no_open: false,
+        no_opendir: false,
+        killpriv_v2: !read_only,
+        ..Default::default()
+    }
+}
+
 fn create_fuse_session(target: &Path) -> BoxliteResult<FuseSession> {
     FuseSession::new(target, "boxlite-bindfs", "", true).map_err(|e| {
         BoxliteError::Storage(format!(
@@ -188,3 +224,19 @@ fn is_transport_channel_closed(e: &fuse_backend_rs::transport::Error) -> bool {
         _ => false,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_passthrough_config_fidelity() {
+        let fast = passthrough_config(Path::new("/src"), true, false);
+        assert!(fast.no_open && fast.no_opendir && !fast.xattr);
+        assert!(!fast.do_import);
+
+        let full = passthrough_config(Path::new("/src"), false, true);
+        assert!(!full.no_open && !full.no_opendir && full.xattr);
+        assert!(full.do_import && full.writeback);
+    }
+}
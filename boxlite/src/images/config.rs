@@ -25,6 +25,11 @@ pub struct ContainerImageConfig {
 
     /// Working directory (e.g., "/app", "/workspace")
     pub working_dir: String,
+
+    /// User execs run as (e.g., "app", "1000:1000"), from the images's
+    /// USER directive. Empty means root.
+    #[serde(default)]
+    pub user: String,
 }
 
 impl ContainerImageConfig {
@@ -150,11 +155,15 @@ impl ContainerImageConfig {
         // Extract exposed ports
         let exposed_ports = config.exposed_ports().clone().unwrap_or_default();
 
+        // Extract user
+        let user = config.user().clone().unwrap_or_default();
+
         Ok(ContainerImageConfig {
             cmd: entrypoint,
             env,
             working_dir: workdir,
             exposed_ports,
+            user,
         })
     }
 }
@@ -168,6 +177,7 @@ impl Default for ContainerImageConfig {
             ],
             working_dir: "/".to_string(),
             exposed_ports: Vec::new(),
+            user: String::new(),
         }
     }
 }
@@ -199,6 +209,7 @@ mod tests {
             cmd: vec![],
             env: vec![],
             working_dir: "/".to_string(),
+            user: String::new(),
             exposed_ports: vec![
                 "8080/tcp".to_string(),
                 "443/tcp".to_string(),
@@ -215,6 +226,7 @@ mod tests {
             cmd: vec![],
             env: vec![],
             working_dir: "/".to_string(),
+            user: String::new(),
             exposed_ports: vec![
                 "8080/tcp".to_string(),
                 "53/udp".to_string(),
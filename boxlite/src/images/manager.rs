@@ -101,4 +101,20 @@ impl ImageManager {
             Arc::clone(&self.store),
         ))
     }
+
+    /// Look an image up in the local store only, never pulling it.
+    ///
+    /// With `config_digest`, finds the image with that ID whatever its
+    /// reference points to now; otherwise the image `image_ref` was last
+    /// pulled as. Returns None if it is not stored locally.
+    pub async fn cached(
+        &self,
+        image_ref: &str,
+        config_digest: Option<&str>,
+    ) -> BoxliteResult<Option<ImageObject>> {
+        let manifest = self.store.cached(image_ref, config_digest).await?;
+        Ok(manifest.map(|manifest| {
+            ImageObject::new(image_ref.to_string(), manifest, Arc::clone(&self.store))
+        }))
+    }
 }
@@ -8,6 +8,7 @@ use std::path::PathBuf;
 
 use super::manager::ImageManifest;
 use crate::images::store::SharedImageStore;
+use crate::litebox::HealthCheck;
 use boxlite_shared::errors::{BoxliteError, BoxliteResult};
 
 // ============================================================================
@@ -58,7 +59,6 @@ impl ImageObject {
     }
 
     /// Get list of layer digests
-    #[allow(dead_code)]
     pub fn layer_digests(&self) -> Vec<&str> {
         self.manifest
             .layers
@@ -67,8 +67,12 @@ impl ImageObject {
             .collect()
     }
 
+    /// Get manifest digest
+    pub fn manifest_digest(&self) -> &str {
+        &self.manifest.manifest_digest
+    }
+
     /// Get config digest
-    #[allow(dead_code)]
     pub fn config_digest(&self) -> &str {
         &self.manifest.config_digest
     }
@@ -97,6 +101,42 @@ impl ImageObject {
             .map_err(|e| BoxliteError::Storage(format!("Failed to parse image config: {}", e)))
     }
 
+    /// Load the image's default shell (Docker `SHELL` instruction).
+    ///
+    /// The shell is a Docker extension of the image config, so it is not part
+    /// of [`load_config`](Self::load_config). Returns `None` if unset.
+    pub async fn load_shell(&self) -> BoxliteResult<Option<Vec<String>>> {
+        let config_json = self.store.config(&self.manifest.config_digest).await?;
+
+        let config: serde_json::Value = serde_json::from_str(&config_json)
+            .map_err(|e| BoxliteError::Storage(format!("Failed to parse image config: {}", e)))?;
+        Ok(config
+            .pointer("/config/Shell")
+            .and_then(|shell| serde_json::from_value::<Vec<String>>(shell.clone()).ok())
+            .filter(|shell| !shell.is_empty()))
+    }
+
+    /// Load the image's STOPSIGNAL (e.g. `SIGINT`), if set.
+    pub async fn load_stop_signal(&self) -> BoxliteResult<Option<String>> {
+        let config = self.load_config().await?;
+        Ok(config
+            .config()
+            .as_ref()
+            .and_then(|c| c.stop_signal().clone())
+            .filter(|signal| !signal.is_empty()))
+    }
+
+    /// Load the image's HEALTHCHECK, if it has one that is not disabled.
+    pub(crate) async fn load_healthcheck(&self) -> BoxliteResult<Option<HealthCheck>> {
+        let config_json = self.store.config(&self.manifest.config_digest).await?;
+
+        let config: serde_json::Value = serde_json::from_str(&config_json)
+            .map_err(|e| BoxliteError::Storage(format!("Failed to parse image config: {}", e)))?;
+        Ok(config
+            .pointer("/config/Healthcheck")
+            .and_then(HealthCheck::from_docker))
+    }
+
     // ========================================================================
     // LAYER OPERATIONS
     // ========================================================================
@@ -123,6 +123,26 @@ impl ImageStore {
         self.pull_from_registry(image_ref).await
     }
 
+    /// Local copy of an image, without going to the registry: by its config
+    /// digest if given, otherwise by reference.
+    pub async fn cached(
+        &self,
+        image_ref: &str,
+        config_digest: Option<&str>,
+    ) -> BoxliteResult<Option<ImageManifest>> {
+        let inner = self.inner.read().await;
+        let Some(config_digest) = config_digest else {
+            return self.try_load_cached(&inner, image_ref);
+        };
+        let Some(cached) = inner.index.find_by_config(config_digest)? else {
+            return Ok(None);
+        };
+        if !self.verify_cached_image(&inner, &cached)? {
+            return Ok(None);
+        }
+        self.load_manifest_from_disk(&inner, &cached).map(Some)
+    }
+
     /// Load config JSON for an image.
     ///
     /// Returns the raw JSON string. Use `serde_json::from_str()` to parse.
@@ -14,6 +14,7 @@ pub mod metrics;
 pub mod net;
 pub mod pipeline;
 pub mod runtime;
+pub mod telemetry;
 pub mod util;
 pub mod vmm;
 
@@ -26,17 +27,33 @@ mod rootfs;
 mod volumes;
 
 pub use litebox::LiteBox;
-pub use runtime::BoxliteRuntime;
+pub use runtime::{BoxliteRuntime, Volumes};
 
 use boxlite_shared::errors::{BoxliteError, BoxliteResult};
 pub use litebox::{
-    BoxCommand, ExecResult, ExecStderr, ExecStdin, ExecStdout, Execution, ExecutionId,
+    BoxCommand, BoxHttpClient, BoxSpec, ExecEnvSnapshot, ExecInfo, ExecOutput, ExecRecord,
+    ExecResult, ExecState, ExecStderr, ExecStdin, ExecStdout, Execution, ExecutionId, ExitStatus,
+    FileEvent, FileEventKind, FileInfo, FileSource, FileType, HealthStatus, LogRotation,
+    OutputChunk, OutputLimitPolicy, Signal, SshAccess, SyncOptions, SyncStats, TarStream,
+    TimestampedChunk, TimestampedOutput, TransferOptions, TransferProgress, WatchStream,
 };
 pub use metrics::{BoxMetrics, RuntimeMetrics};
+pub use net::{CaptureFilter, CaptureProtocol, CaptureStats, NetworkBackendKind};
 use runtime::layout::FilesystemLayout;
-pub use runtime::options::{BoxOptions, BoxliteOptions, RootfsSpec};
+pub use runtime::options::{
+    BoxDefaults, BoxOptions, BoxliteOptions, CloneOptions, CpuFeatureMask, DiskQuota, DnsCache,
+    DropBehavior, EgressRule, ExecBufferOptions, ExecBufferPolicy, ExecLimitPolicy, IdleAction,
+    MemoryPolicy, NetworkMode, NetworkPolicy, NetworkRateLimit, NetworkTuning, PackageRegistry,
+    PruneFilter, QuotaTarget, RegistryCacheOptions, RestartMode, RestartPolicy, ReversePortSpec,
+    RootfsSpec, SocketSpec, ThpPolicy, VolumeOptions, X86Level,
+};
 pub use runtime::types::ContainerID;
-pub use runtime::types::{BoxID, BoxInfo, BoxState, BoxStatus};
+pub use runtime::types::{
+    BatchReport, BoxID, BoxInfo, BoxInspect, BoxPaths, BoxState, BoxStatus, DrainReport,
+    ImageInspect, LabelSelector, ListOptions, MountInspect, MountKind, NetworkInfo, PortMapping,
+    PruneReport, VolumeInfo, VolumeInspect, VolumeKind,
+};
+pub use telemetry::TelemetrySink;
 
 /// Initialize tracing for Boxlite using the provided filesystem layout.
 ///
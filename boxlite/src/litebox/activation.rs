@@ -0,0 +1,167 @@
+//! Lazy start of boxes, see [`BoxOptions::lazy_start`](crate::BoxOptions::lazy_start).
+//!
+//! The runtime listens on the box's host ports itself. Each connection
+//! starts the box if needed, waits for the guest port to accept, and is
+//! relayed to it through the network backend's tunnel.
+
+use std::net::{IpAddr, Ipv4Addr};
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+
+use boxlite_shared::errors::{BoxliteError, BoxliteResult};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinHandle;
+
+use super::box_impl::SharedBoxImpl;
+use super::config::BoxConfig;
+use super::tunnel;
+use crate::runtime::rt_impl::RuntimeImpl;
+use crate::runtime::types::{BoxID, PortMapping};
+
+/// How long a connection waits for the box to boot and its port to accept.
+const START_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// How often an open connection counts as activity for `idle_timeout`.
+const ACTIVITY_INTERVAL: Duration = Duration::from_secs(5);
+
+/// The box the listeners start, once known.
+///
+/// A box created in this runtime is not persisted until it boots, and only
+/// found by its handles, so it is passed in. Kept while listening, so the
+/// box's idle watcher outlives each connection.
+type Pending = Arc<parking_lot::Mutex<Option<SharedBoxImpl>>>;
+
+/// Listeners on the host ports of a lazily started box, closed when
+/// dropped.
+pub(crate) struct Activator {
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl Activator {
+    /// Listen on the host ports of `config`'s `ports`, returning them with
+    /// host port 0 and taken ports replaced by the assigned port. `pending` is the handle
+    /// of a box created but not yet booted.
+    pub(crate) fn listen(
+        runtime: &Arc<RuntimeImpl>,
+        config: &BoxConfig,
+        pending: Option<SharedBoxImpl>,
+    ) -> BoxliteResult<(Self, Vec<PortMapping>)> {
+        let handle = tokio::runtime::Handle::try_current().map_err(|_| {
+            BoxliteError::InvalidState("lazy_start needs an async runtime".to_string())
+        })?;
+        let pending: Pending = Arc::new(parking_lot::Mutex::new(pending));
+
+        let mut activator = Self { tasks: Vec::new() };
+        let mut mappings = config.options.port_mappings(&[]);
+        for mapping in &mut mappings {
+            let ip = mapping.host_ip.unwrap_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED));
+            let listener = crate::net::bind_host_port(
+                ip,
+                mapping.host_port,
+                config.options.auto_assign_on_conflict,
+            )
+            .map_err(|e| match e.kind() {
+                std::io::ErrorKind::AddrInUse => BoxliteError::PortInUse(mapping.host_port),
+                _ => BoxliteError::Network(format!(
+                    "Failed to listen on {}:{} for guest port {}: {}",
+                    ip, mapping.host_port, mapping.guest_port, e
+                )),
+            })?;
+            listener.set_nonblocking(true)?;
+            mapping.host_port = listener.local_addr()?.port();
+            let listener = TcpListener::from_std(listener)?;
+            activator.tasks.push(handle.spawn(accept_loop(
+                Arc::downgrade(runtime),
+                config.id.clone(),
+                listener,
+                mapping.guest_port,
+                Arc::clone(&pending),
+            )));
+        }
+        mappings.sort_unstable_by_key(|m| (m.host_port, m.host_ip));
+        tracing::info!(box_id = %config.id, ports = ?mappings, "Waiting for connections to start box");
+        Ok((activator, mappings))
+    }
+}
+
+impl Drop for Activator {
+    fn drop(&mut self) {
+        for task in &self.tasks {
+            task.abort();
+        }
+    }
+}
+
+async fn accept_loop(
+    runtime: Weak<RuntimeImpl>,
+    box_id: BoxID,
+    listener: TcpListener,
+    guest_port: u16,
+    pending: Pending,
+) {
+    loop {
+        let client = match listener.accept().await {
+            Ok((client, _)) => client,
+            Err(e) => {
+                tracing::debug!(box_id = %box_id, error = %e, "Failed to accept connection");
+                continue;
+            }
+        };
+        let runtime = runtime.clone();
+        let box_id = box_id.clone();
+        let pending = Arc::clone(&pending);
+        tokio::spawn(async move {
+            if let Err(e) = relay(&runtime, &box_id, guest_port, client, &pending).await {
+                tracing::warn!(
+                    box_id = %box_id,
+                    guest_port,
+                    error = %e,
+                    "Failed to relay connection to box"
+                );
+            }
+        });
+    }
+}
+
+/// Start the box if needed and relay `client` to `guest_port`.
+async fn relay(
+    runtime: &Weak<RuntimeImpl>,
+    box_id: &BoxID,
+    guest_port: u16,
+    mut client: TcpStream,
+    pending: &Pending,
+) -> BoxliteResult<()> {
+    let pending_box = pending.lock().clone();
+    let box_impl = match pending_box.filter(|box_impl| !box_impl.is_shutdown()) {
+        Some(box_impl) => box_impl,
+        // Created by an earlier runtime, or stopped through another handle
+        None => {
+            let Some(runtime) = runtime.upgrade() else {
+                return Ok(());
+            };
+            match runtime.box_impl(box_id.as_str())? {
+                Some(box_impl) => {
+                    *pending.lock() = Some(Arc::clone(&box_impl));
+                    box_impl
+                }
+                // Removed
+                None => return Ok(()),
+            }
+        }
+    };
+    box_impl.mark_active();
+    box_impl.wait_for_port(guest_port, START_TIMEOUT).await?;
+
+    let layout = box_impl.runtime.layout.box_layout(box_id.as_str(), false)?;
+    let mut guest = tunnel::connect(&layout.tunnel_socket_path(), box_id, guest_port).await?;
+    let copy = tokio::io::copy_bidirectional(&mut client, &mut guest);
+    tokio::pin!(copy);
+    let mut activity = tokio::time::interval(ACTIVITY_INTERVAL);
+    loop {
+        tokio::select! {
+            _ = &mut copy => break,
+            _ = activity.tick() => box_impl.mark_active(),
+        }
+    }
+    Ok(())
+}
@@ -4,27 +4,52 @@
 // IMPORTS
 // ============================================================================
 
+use std::path::Path;
 use std::sync::Arc;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
 
+use bytes::Bytes;
+use futures::StreamExt;
 use parking_lot::RwLock;
-use tokio::sync::OnceCell;
+use tokio::io::{AsyncRead, AsyncSeekExt, AsyncWriteExt};
+use tokio::sync::{OnceCell, OwnedSemaphorePermit, Semaphore, mpsc};
 
 use boxlite_shared::errors::{BoxliteError, BoxliteResult};
 
 use super::config::BoxConfig;
-use super::exec::{BoxCommand, ExecStderr, ExecStdin, ExecStdout, Execution};
-use super::state::BoxState;
+use super::exec::{
+    BoxCommand, ExecEnvSnapshot, ExecInfo, ExecState, Execution, ExecutionId, ExitStatus,
+};
+use super::files::{FileInfo, FileSource, TarStream};
+use super::health::{self, HealthCheck, HealthStatus};
+use super::history;
+use super::http::BoxHttpClient;
+use super::idle;
+use super::redirect::OutputRedirect;
+use super::spec::BoxSpec;
+use super::ssh::{self, SshAccess, SshForward};
+use super::state::{BoxState, ExitReason};
+use super::sync::{self, SyncOptions, SyncStats};
+use super::transfer::{ProgressReader, TransferOptions};
+use super::watch::{FileEvent, WatchStream};
 use crate::disk::Disk;
 #[cfg(target_os = "linux")]
 use crate::fs::BindMountHandle;
-use crate::lock::LockGuard;
+use crate::lock::{LockGuard, LockId, LockManager};
 use crate::metrics::{BoxMetrics, BoxMetricsStorage};
+use crate::net::{CaptureFilter, CaptureStats, NetworkMetrics};
 use crate::portal::GuestSession;
+use crate::portal::interfaces::ExecComponents;
+use crate::runtime::options::{
+    DropBehavior, ExecBufferOptions, ExecBufferPolicy, ExecLimitPolicy, VolumeSpec,
+};
 use crate::runtime::rt_impl::SharedRuntimeImpl;
-use crate::runtime::types::BoxStatus;
+use crate::runtime::types::{BoxInspect, BoxStatus, PortMapping};
+use crate::runtime::volumes::VolumeLock;
 use crate::vmm::controller::VmmHandler;
-use crate::{BoxID, BoxInfo};
+use crate::vmm::controller::control;
+use crate::{BoxID, BoxInfo, LiteBox};
 
 // ============================================================================
 // TYPE ALIASES
@@ -54,6 +79,12 @@ pub(crate) struct LiveState {
     #[allow(dead_code)]
     guest_rootfs_disk: Option<Disk>,
 
+    // Host ports forwarded to the VM when it booted (empty on reattach)
+    ports: Vec<PortMapping>,
+
+    // Disk volumes mounted by the VM, released when it stops
+    _volume_locks: Vec<VolumeLock>,
+
     // Platform-specific
     #[cfg(target_os = "linux")]
     #[allow(dead_code)]
@@ -68,6 +99,8 @@ impl LiveState {
         metrics: BoxMetricsStorage,
         container_rootfs_disk: Disk,
         guest_rootfs_disk: Option<Disk>,
+        ports: Vec<PortMapping>,
+        volume_locks: Vec<VolumeLock>,
         #[cfg(target_os = "linux")] bind_mount: Option<BindMountHandle>,
     ) -> Self {
         Self {
@@ -76,12 +109,42 @@ impl LiveState {
             metrics,
             _container_rootfs_disk: container_rootfs_disk,
             guest_rootfs_disk,
+            ports,
+            _volume_locks: volume_locks,
             #[cfg(target_os = "linux")]
             bind_mount,
         }
     }
 }
 
+/// Keep an exec slot taken until the command has exited.
+fn hold_until_exit(permit: OwnedSemaphorePermit, components: ExecComponents) -> ExecComponents {
+    let ExecComponents {
+        execution_id,
+        stdin_tx,
+        stdout_rx,
+        stderr_rx,
+        mut result_rx,
+    } = components;
+
+    let (result_tx, held_result_rx) = mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        let result = result_rx.recv().await;
+        drop(permit);
+        if let Some(result) = result {
+            let _ = result_tx.send(result);
+        }
+    });
+
+    ExecComponents {
+        execution_id,
+        stdin_tx,
+        stdout_rx,
+        stderr_rx,
+        result_rx: held_result_rx,
+    }
+}
+
 // ============================================================================
 // BOX IMPL
 // ============================================================================
@@ -95,9 +158,26 @@ pub(crate) struct BoxImpl {
     pub(crate) state: RwLock<BoxState>,
     pub(crate) runtime: SharedRuntimeImpl,
     is_shutdown: AtomicBool,
+    /// Slots for running commands (`max_concurrent_execs`).
+    exec_slots: Option<Arc<Semaphore>>,
+
+    /// When the last exec was started or a `lazy_start` connection was
+    /// open, for `idle_timeout`.
+    last_active: parking_lot::Mutex<Instant>,
+    /// Set while the VM is paused for `idle_timeout`.
+    idle_paused: AtomicBool,
+    /// User handles open on this box, for `on_drop`.
+    user_handles: AtomicUsize,
 
     // --- Lazily initialized ---
-    live: OnceCell<LiveState>,
+    /// Dropped again when the box is suspended for `idle_timeout`.
+    live: RwLock<Option<Arc<LiveState>>>,
+    /// Held while `live` is being initialized or suspended.
+    live_init: tokio::sync::Mutex<()>,
+    /// Shell argv for `shell()`, resolved from the image on first use.
+    shell: OnceCell<Vec<String>>,
+    /// Host port relayed to the box's SSH server, once `enable_ssh()` ran.
+    ssh_forward: tokio::sync::Mutex<Option<SshForward>>,
 }
 
 impl BoxImpl {
@@ -109,12 +189,23 @@ impl BoxImpl {
     ///
     /// LiveState will be lazily initialized when operations requiring it are called.
     pub(crate) fn new(config: BoxConfig, state: BoxState, runtime: SharedRuntimeImpl) -> Self {
+        let exec_slots = config
+            .options
+            .max_concurrent_execs
+            .map(|max| Arc::new(Semaphore::new(max as usize)));
         Self {
+            exec_slots,
             config,
             state: RwLock::new(state),
             runtime,
             is_shutdown: AtomicBool::new(false),
-            live: OnceCell::new(),
+            last_active: parking_lot::Mutex::new(Instant::now()),
+            idle_paused: AtomicBool::new(false),
+            user_handles: AtomicUsize::new(0),
+            live: RwLock::new(None),
+            live_init: tokio::sync::Mutex::new(()),
+            shell: OnceCell::new(),
+            ssh_forward: tokio::sync::Mutex::new(None),
         }
     }
 
@@ -130,6 +221,16 @@ impl BoxImpl {
         self.config.container.id.as_str()
     }
 
+    /// Check if `stop()` was called on this box.
+    pub(crate) fn is_shutdown(&self) -> bool {
+        self.is_shutdown.load(Ordering::SeqCst)
+    }
+
+    /// Whether the box has been saved to the database.
+    pub(crate) fn is_persisted(&self) -> bool {
+        self.state.read().lock_id.is_some()
+    }
+
     pub(crate) fn info(&self) -> BoxInfo {
         let state = self.state.read();
         BoxInfo::new(&self.config, &state)
@@ -140,14 +241,78 @@ impl BoxImpl {
     // ========================================================================
 
     pub(crate) async fn exec(&self, command: BoxCommand) -> BoxliteResult<Execution> {
-        use boxlite_shared::constants::executor as executor_const;
-
         // Check if box is stopped before proceeding
         if self.is_shutdown.load(Ordering::SeqCst) {
             return Err(BoxliteError::InvalidState("Box is stopped".into()));
         }
+        self.runtime.check_not_draining()?;
+        // A box paused for being idle is resumed below
+        if self.state.read().status.is_paused() && !self.idle_paused.load(Ordering::SeqCst) {
+            return Err(BoxliteError::InvalidState("Box is paused".into()));
+        }
+        if let Some(limits) = &command.limits {
+            limits.validate()?;
+        }
+        let command = command.resolve_env_files()?;
+        let redirect = OutputRedirect::open(&self.runtime.layout.logs_dir(), &command)?;
+        let exec_slot = self.acquire_exec_slot().await?;
 
+        self.mark_active();
         let live = self.live_state().await?;
+        let command = self.container_command(command);
+
+        let program = command.command.clone();
+        let args = command.args.clone();
+        let mut exec_interface = live.guest_session.execution().await?;
+        let result = exec_interface
+            .exec(command, self.config.options.exec_buffer)
+            .await;
+        self.runtime.telemetry.exec(
+            self.id(),
+            &program,
+            &args,
+            result.as_ref().map(|c| &c.execution_id),
+        );
+
+        // Instrument metrics
+        live.metrics.increment_commands_executed();
+        self.runtime
+            .runtime_metrics
+            .total_commands
+            .fetch_add(1, Ordering::Relaxed);
+
+        if result.is_err() {
+            live.metrics.increment_exec_errors();
+            self.runtime
+                .runtime_metrics
+                .total_exec_errors
+                .fetch_add(1, Ordering::Relaxed);
+        }
+
+        let components = history::record_exec(
+            self.runtime.box_manager.clone(),
+            self.id().clone(),
+            program,
+            args,
+            self.config.options.exec_buffer.policy,
+            result?,
+        )
+        .await;
+        let components = match redirect {
+            Some(redirect) => redirect.attach(components),
+            None => components,
+        };
+        let components = match exec_slot {
+            Some(permit) => hold_until_exit(permit, components),
+            None => components,
+        };
+        Ok(Execution::from_components(components, exec_interface))
+    }
+
+    /// Point a command at the container, in the box's working directory
+    /// unless it sets its own.
+    fn container_command(&self, command: BoxCommand) -> BoxCommand {
+        use boxlite_shared::constants::executor as executor_const;
 
         // Inject container ID into environment if not already set
         let command = if command
@@ -165,221 +330,1768 @@ impl BoxImpl {
         };
 
         // Set working directory from BoxOptions if not set in command
-        let command = if command.working_dir.is_none() && self.config.options.working_dir.is_some()
-        {
+        if command.working_dir.is_none() && self.config.options.working_dir.is_some() {
             command.working_dir(self.config.options.working_dir.as_ref().unwrap())
         } else {
             command
-        };
+        }
+    }
 
-        let mut exec_interface = live.guest_session.execution().await?;
-        let result = exec_interface.exec(command).await;
+    /// Run a healthcheck command. Unlike `exec()`, it takes no exec slot and
+    /// is not recorded in the exec history; its output is discarded.
+    pub(crate) async fn probe(&self, check: &HealthCheck) -> bool {
+        let Some(live) = self.attached() else {
+            return false;
+        };
+        let (program, args) = check.command.split_first().expect("healthcheck command");
+        let command = self.container_command(
+            BoxCommand::new(program)
+                .args(args.iter().cloned())
+                .timeout(check.timeout),
+        );
+        let buffer = ExecBufferOptions {
+            capacity: Some(1),
+            policy: ExecBufferPolicy::Drop,
+        };
 
-        // Instrument metrics
-        live.metrics.increment_commands_executed();
-        self.runtime
-            .runtime_metrics
-            .total_commands
-            .fetch_add(1, Ordering::Relaxed);
+        let run = async {
+            let mut exec_interface = live.guest_session.execution().await?;
+            let components = exec_interface.exec(command, buffer).await?;
+            Execution::from_components(components, exec_interface)
+                .wait()
+                .await
+        };
+        // The guest enforces the timeout; this covers an unresponsive guest
+        match tokio::time::timeout(check.timeout + Duration::from_secs(5), run).await {
+            Ok(Ok(result)) => result.success(),
+            Ok(Err(e)) => {
+                tracing::debug!(box_id = %self.id(), error = %e, "Healthcheck failed to run");
+                false
+            }
+            Err(_) => false,
+        }
+    }
 
-        if result.is_err() {
-            live.metrics.increment_exec_errors();
-            self.runtime
-                .runtime_metrics
-                .total_exec_errors
-                .fetch_add(1, Ordering::Relaxed);
+    /// Record the health of the box.
+    pub(crate) fn set_health(&self, health: Option<HealthStatus>) {
+        let mut state = self.state.write();
+        if state.health == health {
+            return;
+        }
+        state.health = health;
+        if let Some(health) = health {
+            tracing::info!(box_id = %self.id(), %health, "Box health changed");
         }
+        if state.lock_id.is_some()
+            && let Err(e) = self.runtime.box_manager.save_box(self.id(), &state)
+        {
+            tracing::warn!(box_id = %self.id(), error = %e, "Failed to save box health");
+        }
+    }
 
-        let components = result?;
-        Ok(Execution::new(
-            components.execution_id,
-            exec_interface,
-            components.result_rx,
-            Some(ExecStdin::new(components.stdin_tx)),
-            Some(ExecStdout::new(components.stdout_rx)),
-            Some(ExecStderr::new(components.stderr_rx)),
-        ))
+    /// Wait until the box's HEALTHCHECK passes, starting the box if needed.
+    pub(crate) async fn wait_healthy(&self, timeout: Duration) -> BoxliteResult<()> {
+        const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+        let until = tokio::time::Instant::now() + timeout;
+        tokio::time::timeout(timeout, self.start())
+            .await
+            .map_err(|_| BoxliteError::Timeout(format!("box {} did not start", self.id())))??;
+        loop {
+            let health = self.state.read().health;
+            match health {
+                Some(HealthStatus::Healthy) => return Ok(()),
+                Some(HealthStatus::Unhealthy) => {
+                    return Err(BoxliteError::InvalidState(format!(
+                        "box {} is unhealthy",
+                        self.id()
+                    )));
+                }
+                Some(HealthStatus::Starting) => {}
+                None => {
+                    return Err(BoxliteError::InvalidState(format!(
+                        "box {} has no healthcheck",
+                        self.id()
+                    )));
+                }
+            }
+            let now = tokio::time::Instant::now();
+            if now >= until {
+                return Err(BoxliteError::Timeout(format!(
+                    "box {} was not healthy within {:?}",
+                    self.id(),
+                    timeout
+                )));
+            }
+            tokio::time::sleep(POLL_INTERVAL.min(until - now)).await;
+        }
     }
 
-    pub(crate) async fn metrics(&self) -> BoxliteResult<BoxMetrics> {
-        // Check if box is stopped before proceeding
-        if self.is_shutdown.load(Ordering::SeqCst) {
-            return Err(BoxliteError::InvalidState("Box is stopped".into()));
+    /// Wait until `guest_port` accepts connections in the box, starting the
+    /// box if needed.
+    pub(crate) async fn wait_for_port(
+        &self,
+        guest_port: u16,
+        timeout: Duration,
+    ) -> BoxliteResult<()> {
+        const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+        let until = tokio::time::Instant::now() + timeout;
+        tokio::time::timeout(timeout, self.start())
+            .await
+            .map_err(|_| BoxliteError::Timeout(format!("box {} did not start", self.id())))??;
+        loop {
+            if self.is_shutdown() {
+                return Err(BoxliteError::InvalidState("Box is stopped".into()));
+            }
+            let live = self.live_state().await?;
+            let mut guest = live.guest_session.guest().await?;
+            if guest.check_port(guest_port).await? {
+                return Ok(());
+            }
+            let now = tokio::time::Instant::now();
+            if now >= until {
+                return Err(BoxliteError::Timeout(format!(
+                    "port {} of box {} did not accept connections within {:?}",
+                    guest_port,
+                    self.id(),
+                    timeout
+                )));
+            }
+            tokio::time::sleep(POLL_INTERVAL.min(until - now)).await;
         }
+    }
 
-        let live = self.live_state().await?;
-        let handler = live
-            .handler
-            .lock()
-            .map_err(|e| BoxliteError::Internal(format!("handler lock poisoned: {}", e)))?;
-        let raw = handler.metrics()?;
+    /// HTTP client for the box's guest ports, starting the box if needed.
+    pub(crate) async fn http(&self) -> BoxliteResult<BoxHttpClient> {
+        self.require_gvproxy("tunnel to guest ports")?;
 
-        Ok(BoxMetrics::from_storage(
-            &live.metrics,
-            raw.cpu_percent,
-            raw.memory_bytes,
-            None,
-            None,
-            None,
-            None,
+        self.live_state().await?;
+        let layout = self.runtime.layout.box_layout(self.id().as_str(), false)?;
+        Ok(BoxHttpClient::new(
+            self.id().clone(),
+            layout.tunnel_socket_path(),
         ))
     }
 
-    pub(crate) async fn stop(&self) -> BoxliteResult<()> {
-        self.is_shutdown.store(true, Ordering::SeqCst);
+    /// Record the box's traffic to a pcap file at `path`, starting the box
+    /// if needed.
+    pub(crate) async fn capture_traffic(
+        &self,
+        path: &Path,
+        filter: CaptureFilter,
+    ) -> BoxliteResult<CaptureStats> {
+        self.require_gvproxy("capture traffic")?;
+        filter.validate()?;
+        // The shim runs in another working directory
+        let path = std::path::absolute(path)?;
 
-        // Only try to stop VM if LiveState exists
-        if let Some(live) = self.live.get() {
-            // Gracefully shut down guest
-            if let Ok(mut guest) = live.guest_session.guest().await {
-                let _ = guest.shutdown().await;
-            }
+        self.live_state().await?;
+        let layout = self.runtime.layout.box_layout(self.id().as_str(), false)?;
+        tracing::info!(box_id = %self.id(), path = %path.display(), ?filter, "Capturing traffic");
+        let stats = control::capture_traffic(&layout.control_socket_path(), &path, &filter).await?;
+        tracing::info!(
+            box_id = %self.id(),
+            packets = stats.packets,
+            bytes = stats.bytes,
+            truncated = stats.truncated,
+            "Traffic capture finished"
+        );
+        Ok(stats)
+    }
 
-            // Stop handler
-            if let Ok(mut handler) = live.handler.lock() {
-                handler.stop()?;
-            }
-        }
+    /// Authorize `public_key` in the box, start the image's SSH server if
+    /// it is not running yet, and forward a host port to it.
+    pub(crate) async fn enable_ssh(&self, public_key: &str) -> BoxliteResult<SshAccess> {
+        /// Bound on authorizing the key and generating a host key.
+        const SETUP_TIMEOUT: Duration = Duration::from_secs(30);
+        const SERVER_START_TIMEOUT: Duration = Duration::from_secs(10);
 
-        // Check if box was persisted
-        let was_persisted = self.state.read().lock_id.is_some();
+        self.require_gvproxy("forward SSH")?;
+        let public_key = public_key.trim();
+        ssh::validate_key(public_key)?;
 
-        // Update state
-        {
-            let mut state = self.state.write();
-            state.set_status(BoxStatus::Stopped);
-            state.set_pid(None);
+        let live = self.live_state().await?;
+        let running = live
+            .guest_session
+            .guest()
+            .await?
+            .check_port(ssh::GUEST_PORT)
+            .await?;
+        let shell = self.shell.get_or_try_init(|| self.resolve_shell()).await?;
+        let command = BoxCommand::new(&shell[0])
+            .args(shell[1..].iter().cloned())
+            .arg(ssh::SETUP_SCRIPT)
+            .arg("boxlite-ssh")
+            .arg(public_key)
+            .arg(ssh::GUEST_PORT.to_string())
+            .arg(if running { "keep" } else { "start" })
+            .timeout(SETUP_TIMEOUT);
 
-            if was_persisted {
-                // Box was persisted - sync to DB
-                self.runtime.box_manager.save_box(&self.config.id, &state)?;
-            } else {
-                // Box was never started - persist now so it survives restarts
-                self.runtime.box_manager.add_box(&self.config, &state)?;
+        let mut execution = self.exec(command).await?;
+        let (Some(mut stdout), Some(mut stderr)) = (execution.stdout(), execution.stderr()) else {
+            return Err(BoxliteError::Internal("SSH setup output was taken".into()));
+        };
+        let read_stdout = async {
+            let mut out = Vec::new();
+            while let Some(chunk) = stdout.next_chunk().await {
+                out.extend_from_slice(&chunk);
+            }
+            out
+        };
+        let read_stderr = async {
+            let mut out = Vec::new();
+            while let Some(chunk) = stderr.next_chunk().await {
+                out.extend_from_slice(&chunk);
+            }
+            out
+        };
+        let (out, err) = tokio::join!(read_stdout, read_stderr);
+        let result = execution.wait().await?;
+        let err = String::from_utf8_lossy(&err);
+        match result.exit_code {
+            0 => {}
+            ssh::NO_SERVER => {
+                return Err(BoxliteError::Unsupported(format!(
+                    "image of box {} has no SSH server (sshd or dropbear)",
+                    self.id()
+                )));
+            }
+            code => {
+                return Err(BoxliteError::Execution(format!(
+                    "SSH setup exited with code {}: {}",
+                    code,
+                    err.trim()
+                )));
             }
         }
+        let user = String::from_utf8_lossy(&out).trim().to_string();
 
-        // Invalidate cache so new handles get fresh BoxImpl
-        self.runtime
-            .invalidate_box_impl(self.id(), self.config.name.as_deref());
+        self.wait_for_port(ssh::GUEST_PORT, SERVER_START_TIMEOUT)
+            .await?;
+        let mut forward = self.ssh_forward.lock().await;
+        let port = match forward.as_ref() {
+            Some(forward) => forward.port(),
+            None => {
+                let layout = self.runtime.layout.box_layout(self.id().as_str(), false)?;
+                let started =
+                    SshForward::start(self.id().clone(), layout.tunnel_socket_path()).await?;
+                let port = started.port();
+                *forward = Some(started);
+                port
+            }
+        };
+        tracing::info!(box_id = %self.id(), port, user = %user, "SSH enabled");
+        Ok(SshAccess {
+            host: std::net::Ipv4Addr::LOCALHOST.into(),
+            port,
+            user,
+        })
+    }
 
-        tracing::info!("Stopped box {}", self.id());
+    /// Fail unless the box's network is served by gvproxy, which alone
+    /// can `what`.
+    fn require_gvproxy(&self, what: &str) -> BoxliteResult<()> {
+        use crate::net::NetworkBackendKind;
+        use crate::runtime::options::{NetworkMode, NetworkSpec};
 
-        if self.config.options.auto_remove {
-            self.runtime.remove_box(self.id(), false)?;
+        let options = &self.config.options;
+        if options.network == NetworkSpec::None {
+            return Err(BoxliteError::InvalidState(format!(
+                "box {} has no network",
+                self.id()
+            )));
+        }
+        if options.network_mode == NetworkMode::Host {
+            return Err(BoxliteError::Unsupported(format!(
+                "the host network mode does not {}",
+                what
+            )));
+        }
+        if options.network_backend == NetworkBackendKind::Passt {
+            return Err(BoxliteError::Unsupported(format!(
+                "the passt backend does not {}",
+                what
+            )));
         }
-
         Ok(())
     }
 
-    // ========================================================================
-    // LIVE STATE INITIALIZATION (internal)
-    // ========================================================================
+    /// The image's STOPSIGNAL, or SIGTERM.
+    async fn stop_signal(&self) -> String {
+        use crate::runtime::options::RootfsSpec;
 
-    /// Get LiveState, lazily initializing it if needed.
-    async fn live_state(&self) -> BoxliteResult<&LiveState> {
-        self.live.get_or_try_init(|| self.init_live_state()).await
+        const DEFAULT: &str = "SIGTERM";
+        let RootfsSpec::Image(image_ref) = &self.config.options.rootfs else {
+            return DEFAULT.to_string();
+        };
+        let loaded = match self.runtime.image_manager.pull(image_ref).await {
+            Ok(image) => image.load_stop_signal().await,
+            Err(e) => Err(e),
+        };
+        match loaded {
+            Ok(signal) => signal.unwrap_or_else(|| DEFAULT.to_string()),
+            Err(e) => {
+                tracing::warn!(box_id = %self.id(), error = %e, "Failed to load image stop signal");
+                DEFAULT.to_string()
+            }
+        }
     }
 
-    /// Initialize LiveState via BoxBuilder.
-    ///
-    /// BoxBuilder handles all status types with different execution plans:
-    /// - Starting: full pipeline (filesystem, rootfs, spawn, connect, init)
-    /// - Stopped: restart pipeline (reuse rootfs, spawn, connect, init)
-    /// - Running: attach pipeline (attach, connect)
-    ///
-    /// For Starting (new boxes), this also allocates a lock and persists to database
-    /// after successful build.
-    async fn init_live_state(&self) -> BoxliteResult<LiveState> {
-        use super::BoxBuilder;
-        use std::sync::Arc;
+    /// Digest and HEALTHCHECK of the box's image, if it boots from one that
+    /// loads.
+    async fn load_boot_image(&self) -> Option<BootImage> {
+        use crate::runtime::options::RootfsSpec;
 
-        let state = self.state.read().clone();
-        let is_new_box = state.status == BoxStatus::Starting;
+        let RootfsSpec::Image(image_ref) = &self.config.options.rootfs else {
+            return None;
+        };
+        let loaded = async {
+            let image = self.runtime.image_manager.pull(image_ref).await?;
+            BoxliteResult::Ok(BootImage {
+                digest: image.config_digest().to_string(),
+                manifest_digest: image.manifest_digest().to_string(),
+                healthcheck: image.load_healthcheck().await?,
+            })
+        };
+        loaded
+            .await
+            .inspect_err(|e| {
+                tracing::warn!(box_id = %self.id(), error = %e, "Failed to load image for boot");
+            })
+            .ok()
+    }
 
-        // Acquire lock before build
-        // - New boxes: allocate new lock
-        // - Existing boxes: retrieve existing lock
-        let locker = if is_new_box {
-            let lock_id = self.runtime.lock_manager.allocate()?;
-            let locker = self.runtime.lock_manager.retrieve(lock_id)?;
-            tracing::debug!(
-                box_id = %self.config.id,
-                lock_id = %lock_id,
-                "Allocated and acquired lock for new box"
-            );
-            locker
-        } else if let Some(lock_id) = state.lock_id {
-            let locker = self.runtime.lock_manager.retrieve(lock_id)?;
-            tracing::debug!(
-                box_id = %self.config.id,
-                lock_id = %lock_id,
-                "Acquired lock for existing box"
-            );
-            locker
-        } else {
-            // Existing box without lock_id - should never happen in normal operation
-            return Err(BoxliteError::Internal(format!(
-                "box {} is missing lock_id (status: {:?})",
-                self.config.id, state.status
-            )));
+    /// Take a slot for a new command if `max_concurrent_execs` is set.
+    async fn acquire_exec_slot(&self) -> BoxliteResult<Option<OwnedSemaphorePermit>> {
+        let Some(slots) = &self.exec_slots else {
+            return Ok(None);
         };
 
-        // Hold the lock for the duration of build and persist operations.
-        // LockGuard acquires lock on creation and releases on drop.
-        let _guard = LockGuard::new(&*locker);
+        let permit = match self.config.options.exec_limit_policy {
+            ExecLimitPolicy::Queue => slots.clone().acquire_owned().await.ok(),
+            ExecLimitPolicy::Reject => slots.clone().try_acquire_owned().ok(),
+        };
+        permit.map(Some).ok_or_else(|| {
+            BoxliteError::InvalidState(format!(
+                "Box is already running {} commands (max_concurrent_execs)",
+                self.config.options.max_concurrent_execs.unwrap_or_default()
+            ))
+        })
+    }
 
-        // Build the box (lock is held)
-        let builder = BoxBuilder::new(Arc::clone(&self.runtime), self.config.clone(), state)?;
-        let live_state = match builder.build().await {
-            Ok(live_state) => live_state,
-            Err(e) => {
-                // Build failed - free the lock only if newly allocated
-                // (unlock happens automatically when _guard drops)
-                if is_new_box {
-                    let lock_id = locker.id();
-                    if let Err(free_err) = self.runtime.lock_manager.free(lock_id) {
-                        tracing::error!(
-                            lock_id = %lock_id,
-                            error = %free_err,
-                            "Failed to free lock after build error"
-                        );
-                    }
-                }
-                return Err(e);
-            }
+    pub(crate) async fn shell(&self, script: String) -> BoxliteResult<Execution> {
+        let shell = self.shell.get_or_try_init(|| self.resolve_shell()).await?;
+        let command = BoxCommand::new(&shell[0])
+            .args(shell[1..].iter().cloned())
+            .arg(script);
+        self.exec(command).await
+    }
+
+    /// `/bin/sh -c`, or the image's SHELL if the image has no `/bin/sh`.
+    async fn resolve_shell(&self) -> BoxliteResult<Vec<String>> {
+        use crate::rootfs::diff::ImageManifest;
+        use crate::runtime::options::RootfsSpec;
+
+        let default = vec!["/bin/sh".to_string(), "-c".to_string()];
+        let image_ref = match &self.config.options.rootfs {
+            RootfsSpec::Image(r) => r,
+            RootfsSpec::RootfsPath(_) => return Ok(default),
         };
 
-        // Build succeeded - persist to DB for new boxes (lock still held)
-        if is_new_box {
-            let lock_id = locker.id();
+        let image = self.runtime.image_manager.pull(image_ref).await?;
+        let layers = image.layer_extracted().await?;
+        let has_sh = tokio::task::spawn_blocking(move || {
+            ImageManifest::from_layers(&layers).map(|manifest| manifest.contains("/bin/sh"))
+        })
+        .await
+        .map_err(|e| BoxliteError::Internal(format!("Manifest task failed: {}", e)))??;
+        if has_sh {
+            return Ok(default);
+        }
 
-            // Hold the state lock while updating lock_id and persisting to DB
-            let mut state = self.state.write();
-            state.set_lock_id(lock_id);
+        let shell = image.load_shell().await?;
+        tracing::debug!(box_id = %self.id(), ?shell, "Image has no /bin/sh");
+        Ok(shell.unwrap_or(default))
+    }
 
-            if let Err(e) = self.runtime.box_manager.add_box(&self.config, &state) {
-                // Failed to persist - free the lock
-                // (unlock happens automatically when _guard drops)
-                drop(state);
-                if let Err(free_err) = self.runtime.lock_manager.free(lock_id) {
-                    tracing::error!(
-                        lock_id = %lock_id,
-                        error = %free_err,
-                        "Failed to free lock after DB persist error"
-                    );
-                }
-                return Err(e);
+    pub(crate) async fn attach_exec(&self, execution_id: &ExecutionId) -> BoxliteResult<Execution> {
+        if self.is_shutdown.load(Ordering::SeqCst) {
+            return Err(BoxliteError::InvalidState("Box is stopped".into()));
+        }
+
+        let live = self.live_state().await?;
+        let mut exec_interface = live.guest_session.execution().await?;
+        let components = exec_interface
+            .attach(execution_id, self.config.options.exec_buffer)
+            .await?;
+        Ok(Execution::from_components(components, exec_interface))
+    }
+
+    pub(crate) async fn attach(&self) -> BoxliteResult<Execution> {
+        if self.is_shutdown.load(Ordering::SeqCst) {
+            return Err(BoxliteError::InvalidState("Box is stopped".into()));
+        }
+
+        let live = self.live_state().await?;
+        let mut exec_interface = live.guest_session.execution().await?;
+        let components = exec_interface
+            .attach_init(self.container_id(), self.config.options.exec_buffer)
+            .await?;
+        Ok(Execution::from_components(components, exec_interface))
+    }
+
+    pub(crate) async fn wait(&self) -> BoxliteResult<ExitStatus> {
+        if self.is_shutdown.load(Ordering::SeqCst) {
+            return Err(BoxliteError::InvalidState("Box is stopped".into()));
+        }
+
+        let live = self.live_state().await?;
+        let mut exec_interface = live.guest_session.execution().await?;
+        let status = exec_interface.wait_init(self.container_id()).await?;
+        tracing::info!(
+            box_id = %self.id(),
+            exit_code = status.exit_code,
+            oom_killed = status.oom_killed,
+            "Container init process exited"
+        );
+        Ok(status)
+    }
+
+    pub(crate) async fn list_execs(&self) -> BoxliteResult<Vec<ExecInfo>> {
+        if self.is_shutdown.load(Ordering::SeqCst) {
+            return Err(BoxliteError::InvalidState("Box is stopped".into()));
+        }
+
+        let live = self.live_state().await?;
+        let mut exec_interface = live.guest_session.execution().await?;
+        exec_interface.list().await
+    }
+
+    pub(crate) async fn env_snapshot(&self) -> BoxliteResult<ExecEnvSnapshot> {
+        use crate::images::ContainerImageConfig;
+        use crate::runtime::options::RootfsSpec;
+        use boxlite_shared::constants::executor as executor_const;
+
+        let options = &self.config.options;
+        let image_ref = match &options.rootfs {
+            RootfsSpec::Image(r) => r,
+            RootfsSpec::RootfsPath(_) => {
+                return Err(BoxliteError::Unsupported(
+                    "Direct rootfs paths not yet supported".into(),
+                ));
             }
+        };
 
-            tracing::debug!(
-                box_id = %self.config.id,
-                lock_id = %lock_id,
-                "Persisted new box to database"
-            );
+        // Same merge the container is started with (image env, then box env),
+        // from the local store: the image the box booted from, or before its
+        // first boot the one its reference was pulled as
+        let image_digest = self.state.read().image_digest.clone();
+        let image = self
+            .runtime
+            .image_manager
+            .cached(image_ref, image_digest.as_deref())
+            .await?
+            .ok_or_else(|| {
+                BoxliteError::NotFound(format!("image {} is not in the local store", image_ref))
+            })?;
+        let image_config = image.load_config().await?;
+        let mut container_config = ContainerImageConfig::from_oci_config(&image_config)?;
+        let box_env = options.container_env();
+        if !box_env.is_empty() {
+            container_config.merge_env(box_env);
         }
 
-        // Lock is automatically released when _guard drops
-        Ok(live_state)
+        let mut env: Vec<(String, String)> = container_config
+            .env
+            .iter()
+            .filter_map(|entry| entry.split_once('='))
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .filter(|(k, _)| k != executor_const::ENV_VAR)
+            .collect();
+        env.push((
+            executor_const::ENV_VAR.to_string(),
+            format!("{}={}", executor_const::CONTAINER_KEY, self.container_id()),
+        ));
+        env.sort();
+
+        Ok(ExecEnvSnapshot {
+            env,
+            // Guest falls back to the container root when no workdir is sent
+            working_dir: options.working_dir.clone().unwrap_or_else(|| "/".into()),
+            // The guest runs execs as the image USER, root if it has none
+            user: if container_config.user.is_empty() {
+                "root".into()
+            } else {
+                container_config.user.clone()
+            },
+            mounts: options.volumes.clone(),
+        })
+    }
+
+    pub(crate) async fn put_file(
+        &self,
+        source: FileSource,
+        guest_path: &str,
+        mode: u32,
+    ) -> BoxliteResult<u64> {
+        if self.is_shutdown.load(Ordering::SeqCst) {
+            return Err(BoxliteError::InvalidState("Box is stopped".into()));
+        }
+
+        let (reader, size) = source.open().await?;
+        let live = self.live_state().await?;
+        let mut files = live.guest_session.files().await?;
+        files
+            .put_file(self.container_id(), guest_path, mode, size, 0, reader)
+            .await
+    }
+
+    pub(crate) async fn upload(
+        &self,
+        host_path: &Path,
+        guest_path: &str,
+        mode: u32,
+        options: TransferOptions,
+    ) -> BoxliteResult<u64> {
+        if self.is_shutdown.load(Ordering::SeqCst) {
+            return Err(BoxliteError::InvalidState("Box is stopped".into()));
+        }
+        let open = || async {
+            tokio::fs::File::open(host_path).await.map_err(|e| {
+                BoxliteError::Storage(format!("Failed to open {}: {}", host_path.display(), e))
+            })
+        };
+        let metadata = open().await?.metadata().await?;
+        if !metadata.is_file() {
+            return Err(BoxliteError::InvalidArgument(format!(
+                "Not a regular file: {}",
+                host_path.display()
+            )));
+        }
+        let total = metadata.len();
+
+        let mut offset = 0;
+        let mut attempt = 0;
+        loop {
+            let mut file = open().await?;
+            file.seek(std::io::SeekFrom::Start(offset)).await?;
+            let reader = ProgressReader::new(file, offset, total, options.clone());
+
+            let live = self.live_state().await?;
+            let mut files = live.guest_session.files().await?;
+            let result = files
+                .put_file(self.container_id(), guest_path, mode, total, offset, reader)
+                .await;
+            match result {
+                Err(BoxliteError::RpcTransport(e)) if attempt < options.max_retries => {
+                    attempt += 1;
+                    tracing::warn!(
+                        path = guest_path,
+                        offset,
+                        attempt,
+                        error = %e,
+                        "Upload interrupted, resuming"
+                    );
+                    tokio::time::sleep(TransferOptions::backoff(attempt)).await;
+                    // Continue after what reached the guest
+                    offset = match live
+                        .guest_session
+                        .files()
+                        .await?
+                        .stat(self.container_id(), guest_path)
+                        .await
+                    {
+                        Ok(stat) if stat.size <= total => stat.size,
+                        Ok(_) | Err(BoxliteError::NotFound(_)) => 0,
+                        // Still unreachable; the guest has at least as much
+                        Err(_) => offset,
+                    };
+                }
+                result => return result,
+            }
+        }
+    }
+
+    pub(crate) async fn download(
+        &self,
+        guest_path: &str,
+        host_path: &Path,
+        options: TransferOptions,
+    ) -> BoxliteResult<u64> {
+        if self.is_shutdown.load(Ordering::SeqCst) {
+            return Err(BoxliteError::InvalidState("Box is stopped".into()));
+        }
+
+        let live = self.live_state().await?;
+        let total = live
+            .guest_session
+            .files()
+            .await?
+            .stat(self.container_id(), guest_path)
+            .await?
+            .size;
+        let mut file = tokio::fs::File::create(host_path).await.map_err(|e| {
+            BoxliteError::Storage(format!("Failed to create {}: {}", host_path.display(), e))
+        })?;
+
+        let mut written = 0;
+        let mut attempt = 0;
+        loop {
+            let result = async {
+                let mut files = live.guest_session.files().await?;
+                let mut stream = files
+                    .read_file(self.container_id(), guest_path, written, 0)
+                    .await?;
+                while let Some(chunk) = stream.next().await {
+                    let chunk = chunk?;
+                    file.write_all(&chunk).await?;
+                    written += chunk.len() as u64;
+                    options.report(written, total.max(written));
+                }
+                file.flush().await?;
+                Ok(written)
+            }
+            .await;
+            match result {
+                Err(BoxliteError::RpcTransport(e)) if attempt < options.max_retries => {
+                    attempt += 1;
+                    tracing::warn!(
+                        path = guest_path,
+                        offset = written,
+                        attempt,
+                        error = %e,
+                        "Download interrupted, resuming"
+                    );
+                    tokio::time::sleep(TransferOptions::backoff(attempt)).await;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    pub(crate) async fn get_file(&self, guest_path: &str, max_size: u64) -> BoxliteResult<Bytes> {
+        if self.is_shutdown.load(Ordering::SeqCst) {
+            return Err(BoxliteError::InvalidState("Box is stopped".into()));
+        }
+
+        let live = self.live_state().await?;
+        let mut files = live.guest_session.files().await?;
+        files
+            .get_file(self.container_id(), guest_path, max_size)
+            .await
+    }
+
+    pub(crate) async fn read_to_string(&self, guest_path: &str) -> BoxliteResult<String> {
+        let limit = self.config.options.max_text_file_size;
+        let data = self.get_file(guest_path, limit).await?;
+        String::from_utf8(data.into()).map_err(|_| {
+            BoxliteError::InvalidArgument(format!("{} is not valid UTF-8", guest_path))
+        })
+    }
+
+    pub(crate) async fn write_string(
+        &self,
+        guest_path: &str,
+        content: String,
+    ) -> BoxliteResult<()> {
+        let limit = self.config.options.max_text_file_size;
+        if content.len() as u64 > limit {
+            return Err(BoxliteError::InvalidArgument(format!(
+                "{} bytes for {} is over the limit of {}",
+                content.len(),
+                guest_path,
+                limit
+            )));
+        }
+        self.put_file(FileSource::Bytes(content.into()), guest_path, 0o644)
+            .await?;
+        Ok(())
+    }
+
+    pub(crate) async fn copy_in<R>(&self, tar: R, guest_dir: &str) -> BoxliteResult<u64>
+    where
+        R: AsyncRead + Unpin + Send + 'static,
+    {
+        if self.is_shutdown.load(Ordering::SeqCst) {
+            return Err(BoxliteError::InvalidState("Box is stopped".into()));
+        }
+
+        let live = self.live_state().await?;
+        let mut files = live.guest_session.files().await?;
+        files
+            .copy_in(self.container_id(), guest_dir, false, tar)
+            .await
+    }
+
+    pub(crate) async fn sync_dir(
+        &self,
+        host_dir: &Path,
+        guest_dir: &str,
+        options: SyncOptions,
+    ) -> BoxliteResult<SyncStats> {
+        if self.is_shutdown.load(Ordering::SeqCst) {
+            return Err(BoxliteError::InvalidState("Box is stopped".into()));
+        }
+        if !host_dir.is_dir() {
+            return Err(BoxliteError::InvalidArgument(format!(
+                "Not a directory: {}",
+                host_dir.display()
+            )));
+        }
+
+        let live = self.live_state().await?;
+        let mut files = live.guest_session.files().await?;
+        let container_id = self.container_id();
+        let (guest, guest_exists) = match files
+            .list_tree(container_id, guest_dir, options.checksum)
+            .await
+        {
+            Ok(entries) => (entries, true),
+            Err(BoxliteError::NotFound(_)) => (Vec::new(), false),
+            Err(e) => return Err(e),
+        };
+
+        let host_dir = host_dir.to_path_buf();
+        let (host_dir, plan) = tokio::task::spawn_blocking(move || {
+            let host = sync::host_manifest(&host_dir, &options)?;
+            let plan = sync::plan(&host_dir, &host, guest, &options)?;
+            Ok::<_, BoxliteError>((host_dir, plan))
+        })
+        .await
+        .map_err(|e| BoxliteError::Internal(format!("Sync task failed: {}", e)))??;
+
+        let mut stats = SyncStats {
+            files: plan.files,
+            bytes: plan.bytes,
+            deleted: 0,
+        };
+        if !plan.remove.is_empty() {
+            stats.deleted = files
+                .remove_paths(container_id, guest_dir, plan.remove)
+                .await?;
+        }
+        if !plan.copy.is_empty() || !guest_exists {
+            let (reader, writer) = sync::pipe();
+            let paths = plan.copy;
+            let archiver =
+                tokio::task::spawn_blocking(move || sync::write_archive(&host_dir, &paths, writer));
+            let uploaded = files.copy_in(container_id, guest_dir, true, reader).await;
+            let archived = archiver
+                .await
+                .map_err(|e| BoxliteError::Internal(format!("Archive task failed: {}", e)))?;
+            // A failed upload closes the pipe; report the upload error then
+            uploaded?;
+            archived.map_err(|e| {
+                BoxliteError::Storage(format!("Failed to archive {}: {}", guest_dir, e))
+            })?;
+        }
+
+        tracing::debug!(guest_dir, ?stats, "Synced directory");
+        Ok(stats)
+    }
+
+    pub(crate) async fn copy_out(&self, guest_path: &str) -> BoxliteResult<TarStream> {
+        if self.is_shutdown.load(Ordering::SeqCst) {
+            return Err(BoxliteError::InvalidState("Box is stopped".into()));
+        }
+
+        let live = self.live_state().await?;
+        let mut files = live.guest_session.files().await?;
+        let stream = files.copy_out(self.container_id(), guest_path).await?;
+        Ok(TarStream::new(stream))
+    }
+
+    pub(crate) async fn stat(&self, guest_path: &str) -> BoxliteResult<FileInfo> {
+        if self.is_shutdown.load(Ordering::SeqCst) {
+            return Err(BoxliteError::InvalidState("Box is stopped".into()));
+        }
+
+        let live = self.live_state().await?;
+        let mut files = live.guest_session.files().await?;
+        let stat = files.stat(self.container_id(), guest_path).await?;
+        Ok(stat.into())
+    }
+
+    pub(crate) async fn list_dir(&self, guest_path: &str) -> BoxliteResult<Vec<FileInfo>> {
+        if self.is_shutdown.load(Ordering::SeqCst) {
+            return Err(BoxliteError::InvalidState("Box is stopped".into()));
+        }
+
+        let live = self.live_state().await?;
+        let mut files = live.guest_session.files().await?;
+        let entries = files.list_dir(self.container_id(), guest_path).await?;
+        Ok(entries.into_iter().map(FileInfo::from).collect())
+    }
+
+    pub(crate) async fn chmod(&self, guest_path: &str, mode: u32) -> BoxliteResult<()> {
+        if self.is_shutdown.load(Ordering::SeqCst) {
+            return Err(BoxliteError::InvalidState("Box is stopped".into()));
+        }
+
+        let live = self.live_state().await?;
+        let mut files = live.guest_session.files().await?;
+        files.chmod(self.container_id(), guest_path, mode).await
+    }
+
+    pub(crate) async fn chown(&self, guest_path: &str, uid: u32, gid: u32) -> BoxliteResult<()> {
+        if self.is_shutdown.load(Ordering::SeqCst) {
+            return Err(BoxliteError::InvalidState("Box is stopped".into()));
+        }
+
+        let live = self.live_state().await?;
+        let mut files = live.guest_session.files().await?;
+        files.chown(self.container_id(), guest_path, uid, gid).await
+    }
+
+    pub(crate) async fn watch(&self, guest_path: &str) -> BoxliteResult<WatchStream> {
+        if self.is_shutdown.load(Ordering::SeqCst) {
+            return Err(BoxliteError::InvalidState("Box is stopped".into()));
+        }
+
+        let live = self.live_state().await?;
+        let mut files = live.guest_session.files().await?;
+        let stream = files.watch(self.container_id(), guest_path, true).await?;
+        Ok(WatchStream::new(
+            stream.map(|event| event.map(FileEvent::from)),
+        ))
+    }
+
+    pub(crate) async fn export_changes(&self, path: &Path) -> BoxliteResult<u64> {
+        use crate::rootfs::diff::ImageManifest;
+        use crate::runtime::options::RootfsSpec;
+
+        if self.is_shutdown.load(Ordering::SeqCst) {
+            return Err(BoxliteError::InvalidState("Box is stopped".into()));
+        }
+
+        let image_ref = match &self.config.options.rootfs {
+            RootfsSpec::Image(r) => r,
+            RootfsSpec::RootfsPath(_) => {
+                return Err(BoxliteError::Unsupported(
+                    "Exporting changes requires an image rootfs".into(),
+                ));
+            }
+        };
+
+        let image = self.runtime.image_manager.pull(image_ref).await?;
+        let layers = image.layer_extracted().await?;
+        let manifest = tokio::task::spawn_blocking(move || ImageManifest::from_layers(&layers))
+            .await
+            .map_err(|e| BoxliteError::Internal(format!("Manifest task failed: {}", e)))??;
+
+        let live = self.live_state().await?;
+        let mut container = live.guest_session.container().await?;
+        let files = container.list_files(self.container_id()).await?;
+        let changes = manifest.diff(files);
+        tracing::info!(
+            box_id = %self.id(),
+            changed = changes.paths.len(),
+            deleted = changes.deleted.len(),
+            "Exporting rootfs changes to {}",
+            path.display()
+        );
+
+        container
+            .export_layer(self.container_id(), changes, path)
+            .await
+    }
+
+    pub(crate) async fn start(&self) -> BoxliteResult<()> {
+        if self.is_shutdown.load(Ordering::SeqCst) {
+            return Err(BoxliteError::InvalidState("Box is stopped".into()));
+        }
+        // Attaching to a running VM is allowed while draining; booting is not
+        let status = self.state.read().status;
+        if self.attached().is_none() && !(status.is_running() || status.is_paused()) {
+            self.runtime.check_not_draining()?;
+        }
+
+        self.live_state().await.map(|_| ())
+    }
+
+    pub(crate) async fn metrics(&self) -> BoxliteResult<BoxMetrics> {
+        // Check if box is stopped before proceeding
+        if self.is_shutdown.load(Ordering::SeqCst) {
+            return Err(BoxliteError::InvalidState("Box is stopped".into()));
+        }
+
+        let live = self.live_state().await?;
+        let raw = live
+            .handler
+            .lock()
+            .map_err(|e| BoxliteError::Internal(format!("handler lock poisoned: {}", e)))?
+            .metrics()?;
+        let network = self.network_metrics().await;
+
+        Ok(BoxMetrics::from_storage(
+            &live.metrics,
+            raw.cpu_percent,
+            raw.memory_bytes,
+            network.as_ref().map(|n| n.bytes_sent),
+            network.as_ref().map(|n| n.bytes_received),
+            network.as_ref().and_then(|n| n.tcp_connections),
+            network.as_ref().and_then(|n| n.tcp_connection_errors),
+        ))
+    }
+
+    /// Counters of the box's network backend, from its shim; None if the
+    /// backend keeps none or the shim does not answer.
+    async fn network_metrics(&self) -> Option<NetworkMetrics> {
+        if self.state.read().status == BoxStatus::Paused {
+            return None;
+        }
+        let layout = self
+            .runtime
+            .layout
+            .box_layout(self.id().as_str(), false)
+            .ok()?;
+        match control::network_metrics(&layout.control_socket_path()).await {
+            Ok(metrics) => metrics,
+            Err(e) => {
+                tracing::debug!(box_id = %self.id(), error = %e, "Failed to get network metrics");
+                None
+            }
+        }
+    }
+
+    pub(crate) async fn spec(&self) -> BoxliteResult<BoxSpec> {
+        use crate::runtime::options::RootfsSpec;
+
+        let options = self.config.options.clone();
+        let image_digest = match &options.rootfs {
+            // What the box booted from, not what the tag points to now
+            RootfsSpec::Image(_) => {
+                Some(self.state.read().image_manifest.clone().ok_or_else(|| {
+                    BoxliteError::InvalidState(format!(
+                        "box {} has not booted from its image yet; start it first",
+                        self.id()
+                    ))
+                })?)
+            }
+            RootfsSpec::RootfsPath(_) => None,
+        };
+        BoxSpec::new(options, image_digest)
+    }
+
+    pub(crate) async fn inspect(&self) -> BoxliteResult<BoxInspect> {
+        use crate::images::ContainerImageConfig;
+        use crate::runtime::options::RootfsSpec;
+        use crate::runtime::types::{BoxPaths, ImageInspect, MountInspect, MountKind};
+
+        let options = &self.config.options;
+        let state = self.state.read().clone();
+
+        // Best effort, from the local store: the image the box booted from,
+        // or before its first boot the one its reference was pulled as
+        let mut image = None;
+        let mut exposed = Vec::new();
+        if let RootfsSpec::Image(image_ref) = &options.rootfs {
+            let loaded = async {
+                let Some(object) = self
+                    .runtime
+                    .image_manager
+                    .cached(image_ref, state.image_digest.as_deref())
+                    .await?
+                else {
+                    return Ok(None);
+                };
+                let config = ContainerImageConfig::from_oci_config(&object.load_config().await?)?;
+                BoxliteResult::Ok(Some((object, config)))
+            };
+            match loaded.await {
+                Ok(None) => {
+                    tracing::debug!(box_id = %self.id(), "Image not in the local store for inspect");
+                }
+                Ok(Some((object, config))) => {
+                    exposed = config.tcp_ports();
+                    image = Some(ImageInspect {
+                        reference: image_ref.clone(),
+                        id: object.config_digest().to_string(),
+                        layers: object
+                            .layer_digests()
+                            .into_iter()
+                            .map(String::from)
+                            .collect(),
+                    });
+                }
+                Err(e) => {
+                    tracing::warn!(box_id = %self.id(), error = %e, "Failed to load image for inspect");
+                }
+            }
+        }
+
+        let volumes = options.volumes.iter().map(|volume| match volume {
+            VolumeSpec::Bind {
+                host_path,
+                guest_path,
+                read_only,
+            } => MountInspect {
+                kind: MountKind::Volume,
+                source: Some(host_path.clone()),
+                destination: guest_path.clone(),
+                read_only: *read_only,
+            },
+            VolumeSpec::Tmpfs { guest_path, .. } => MountInspect {
+                kind: MountKind::Tmpfs,
+                source: None,
+                destination: guest_path.clone(),
+                read_only: false,
+            },
+        });
+        let ports = options.port_mappings(&exposed);
+
+        let metrics = if state.status.is_running() && !self.is_shutdown() {
+            self.metrics().await.ok()
+        } else {
+            None
+        };
+
+        Ok(BoxInspect {
+            id: self.config.id.clone(),
+            name: self.config.name.clone(),
+            created_at: self.config.created_at,
+            container_id: self.config.container.id.clone(),
+            state,
+            options: options.clone(),
+            engine: self.config.engine_kind,
+            image,
+            mounts: volumes.collect(),
+            ports,
+            paths: BoxPaths {
+                home: self.config.box_home.clone(),
+                rootfs_disk: self.config.box_home.join("root.qcow2"),
+                transport: self.config.transport.clone(),
+                ready_socket: self.config.ready_socket_path.clone(),
+            },
+            metrics,
+        })
+    }
+
+    pub(crate) async fn stop(&self) -> BoxliteResult<()> {
+        self.stop_within(self.config.options.stop_timeout).await
+    }
+
+    /// Like [`stop`](Self::stop), giving the guest `grace` to shut down.
+    pub(crate) async fn stop_within(&self, grace: Duration) -> BoxliteResult<()> {
+        self.stop_vm(grace).await?;
+
+        if self.config.options.auto_remove {
+            self.runtime.remove_box(self.id(), false)?;
+        }
+
+        Ok(())
+    }
+
+    /// Kill the VM without asking the guest to shut down.
+    pub(crate) async fn kill(&self) -> BoxliteResult<()> {
+        self.is_shutdown.store(true, Ordering::SeqCst);
+
+        // Never attach to the VM here: a hung guest would block that too
+        if self.attached().is_none() {
+            let pid = self.state.read().pid;
+            if let Some(pid) = pid
+                && crate::util::is_same_process(pid, self.id().as_str())
+                && !crate::util::kill_process(pid)
+            {
+                return Err(BoxliteError::Engine(format!(
+                    "Failed to kill VM process {}",
+                    pid
+                )));
+            }
+        }
+        self.finish_stop(ExitReason::Killed)?;
+
+        if self.config.options.auto_remove {
+            self.runtime.remove_box(self.id(), false)?;
+        }
+
+        Ok(())
+    }
+
+    /// Freeze the VM. Its processes keep their memory but get no CPU time
+    /// until [`resume`](Self::resume).
+    pub(crate) async fn pause(&self) -> BoxliteResult<()> {
+        self.set_paused(true).await
+    }
+
+    /// Thaw a paused VM.
+    pub(crate) async fn resume(&self) -> BoxliteResult<()> {
+        self.set_paused(false).await
+    }
+
+    async fn set_paused(&self, paused: bool) -> BoxliteResult<()> {
+        if self.is_shutdown.load(Ordering::SeqCst) {
+            return Err(BoxliteError::InvalidState("Box is stopped".into()));
+        }
+        let from = if paused {
+            BoxStatus::Running
+        } else {
+            BoxStatus::Paused
+        };
+        let status = self.state.read().status;
+        if status != from {
+            return Err(BoxliteError::InvalidState(format!(
+                "Cannot {} box in {} state",
+                if paused { "pause" } else { "resume" },
+                status
+            )));
+        }
+        if !paused {
+            // Resumed by the user rather than by the next operation
+            self.idle_paused.store(false, Ordering::SeqCst);
+        }
+
+        // Attaches to the VM if this handle has not used it yet
+        let live = self.live_state().await?;
+        self.apply_paused(&live, paused)
+    }
+
+    /// Freeze or thaw the attached VM and persist the new status.
+    fn apply_paused(&self, live: &LiveState, paused: bool) -> BoxliteResult<()> {
+        let (from, to) = if paused {
+            (BoxStatus::Running, BoxStatus::Paused)
+        } else {
+            (BoxStatus::Paused, BoxStatus::Running)
+        };
+        let mut handler = live
+            .handler
+            .lock()
+            .map_err(|e| BoxliteError::Internal(format!("handler lock poisoned: {}", e)))?;
+
+        let mut state = self.state.write();
+        state.transition_to(to)?;
+        let frozen = if paused {
+            handler.pause()
+        } else {
+            handler.resume()
+        };
+        if let Err(e) = frozen {
+            state.force_status(from);
+            return Err(e);
+        }
+        self.runtime.box_manager.save_box(&self.config.id, &state)?;
+
+        tracing::info!(box_id = %self.id(), status = %to, "Changed box pause state");
+        Ok(())
+    }
+
+    /// Stop the box and start it again from its stopped state.
+    ///
+    /// The guest gets `timeout` to shut down before the VM is killed. This
+    /// handle is stopped for good; the returned one is the running box.
+    pub(crate) async fn restart(&self, timeout: Duration) -> BoxliteResult<LiteBox> {
+        self.runtime.check_not_draining()?;
+        self.stop_vm(timeout).await?;
+
+        let litebox = self
+            .runtime
+            .get(self.id().as_str())?
+            .ok_or_else(|| BoxliteError::NotFound(self.id().to_string()))?;
+        litebox.start().await?;
+        Ok(litebox)
+    }
+
+    /// Change the box's vCPUs and memory (`None` keeps the current value).
+    ///
+    /// The new values are checked first. A stopped box keeps them for its
+    /// next start; a running one is restarted with them, and its old values
+    /// are restored if that fails.
+    pub(crate) async fn update_resources(
+        &self,
+        cpus: Option<u8>,
+        memory_mib: Option<u32>,
+    ) -> BoxliteResult<LiteBox> {
+        use crate::runtime::admission;
+
+        if cpus == Some(0) || memory_mib == Some(0) {
+            return Err(BoxliteError::InvalidArgument(
+                "cpus and memory_mib must be greater than 0".into(),
+            ));
+        }
+        let Some((mut config, _)) = self.runtime.box_manager.box_by_id(self.id())? else {
+            return Err(BoxliteError::InvalidState(format!(
+                "box {} has not been started yet; create it with the new resources instead",
+                self.id()
+            )));
+        };
+        let previous = config.clone();
+        config.options.cpus = cpus.or(config.options.cpus);
+        config.options.memory_mib = memory_mib.or(config.options.memory_mib);
+        config.options.sanitize()?;
+        admission::check_host(admission::Usage::of(&config.options))?;
+
+        let status = self.state.read().status;
+        if status.is_stopped() {
+            self.runtime.box_manager.save_config(&config)?;
+            // The next handle boots with the new config
+            self.is_shutdown.store(true, Ordering::SeqCst);
+            self.runtime
+                .invalidate_box_impl(self.id(), self.config.name.as_deref());
+        } else {
+            // The box's own current usage makes room for the new one
+            self.runtime
+                .check_admission(Some(self.id()), &config.options)?;
+            self.runtime.check_not_draining()?;
+
+            // The restarted box boots from the saved config
+            self.runtime.box_manager.save_config(&config)?;
+            if let Err(e) = self.restart_with_config().await {
+                tracing::warn!(
+                    box_id = %self.id(),
+                    error = %e,
+                    "Failed to restart box with new resources, restoring the old ones"
+                );
+                self.runtime.box_manager.save_config(&previous)?;
+                // The cached handle holds the new config; boot from the DB
+                self.runtime
+                    .invalidate_box_impl(self.id(), self.config.name.as_deref());
+                if let Some(litebox) = self.runtime.get_internal(self.id().as_str())? {
+                    if let Err(e) = litebox.start().await {
+                        tracing::warn!(box_id = %self.id(), error = %e, "Failed to start box with its old resources");
+                    }
+                }
+                return Err(e);
+            }
+        }
+
+        tracing::info!(
+            box_id = %self.id(),
+            cpus = ?config.options.cpus,
+            memory_mib = ?config.options.memory_mib,
+            "Updated box resources"
+        );
+        self.runtime
+            .get(self.id().as_str())?
+            .ok_or_else(|| BoxliteError::NotFound(self.id().to_string()))
+    }
+
+    /// Stop the VM and boot the box again from its saved config.
+    async fn restart_with_config(&self) -> BoxliteResult<()> {
+        self.stop_vm(self.config.options.stop_timeout).await?;
+        let litebox = self
+            .runtime
+            .get_internal(self.id().as_str())?
+            .ok_or_else(|| BoxliteError::NotFound(self.id().to_string()))?;
+        litebox.start().await
+    }
+
+    /// Wait for the connections of published ports to close, send the
+    /// container its stop signal, give it `grace` to exit, then kill the VM
+    /// and persist the box as stopped.
+    async fn stop_vm(&self, grace: Duration) -> BoxliteResult<()> {
+        self.is_shutdown.store(true, Ordering::SeqCst);
+
+        // Only try to stop VM if LiveState exists
+        if let Some(live) = self.attached() {
+            if let Some(timeout) = self.config.options.drain_timeout {
+                self.drain_connections(timeout).await;
+            }
+            self.shutdown_guest(&live, grace).await;
+        }
+
+        self.finish_stop(ExitReason::Stopped)
+    }
+
+    /// Wait up to `timeout` for the connections of published ports to
+    /// close. A shim that does not count them has none to wait for.
+    async fn drain_connections(&self, timeout: Duration) {
+        const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+        let until = tokio::time::Instant::now() + timeout;
+        loop {
+            let open = self
+                .network_metrics()
+                .await
+                .and_then(|metrics| metrics.forwarded_connections)
+                .unwrap_or(0);
+            if open == 0 {
+                return;
+            }
+            let now = tokio::time::Instant::now();
+            if now >= until {
+                tracing::warn!(
+                    box_id = %self.id(),
+                    open,
+                    ?timeout,
+                    "Connections of published ports still open, stopping anyway"
+                );
+                return;
+            }
+            tokio::time::sleep(POLL_INTERVAL.min(until - now)).await;
+        }
+    }
+
+    /// Send the container its stop signal and wait up to `grace` for the
+    /// guest to shut down.
+    async fn shutdown_guest(&self, live: &LiveState, grace: Duration) {
+        // Time for the guest to answer on top of `grace`
+        const SHUTDOWN_MARGIN: Duration = Duration::from_secs(2);
+
+        // A frozen guest cannot shut down
+        if self.state.read().status.is_paused()
+            && let Ok(mut handler) = live.handler.lock()
+        {
+            let _ = handler.resume();
+        }
+
+        // Gracefully shut down guest
+        if let Ok(mut guest) = live.guest_session.guest().await {
+            let signal = self.stop_signal().await;
+            let shutdown = guest.shutdown(&signal, grace);
+            if tokio::time::timeout(grace + SHUTDOWN_MARGIN, shutdown)
+                .await
+                .is_err()
+            {
+                tracing::warn!(
+                    box_id = %self.id(),
+                    ?grace,
+                    "Guest did not shut down in time, killing the VM"
+                );
+            }
+        }
+    }
+
+    /// Kill the VM if attached and persist the box as stopped for `reason`.
+    fn finish_stop(&self, reason: ExitReason) -> BoxliteResult<()> {
+        if let Some(live) = self.attached() {
+            self.release_vm(&live)?;
+        }
+
+        // Check if box was persisted
+        let was_persisted = self.state.read().lock_id.is_some();
+
+        // Update state
+        {
+            let mut state = self.state.write();
+            state.set_status(BoxStatus::Stopped);
+            state.set_pid(None);
+            state.health = None;
+            state.record_exit(reason);
+            self.idle_paused.store(false, Ordering::SeqCst);
+
+            if was_persisted {
+                // Box was persisted - sync to DB
+                self.runtime.box_manager.save_box(&self.config.id, &state)?;
+            } else {
+                // Box was never started - persist now so it survives restarts
+                self.runtime.box_manager.add_box(&self.config, &state)?;
+            }
+        }
+
+        // Invalidate cache so new handles get fresh BoxImpl
+        self.runtime
+            .invalidate_box_impl(self.id(), self.config.name.as_deref());
+
+        tracing::info!("Stopped box {}", self.id());
+        Ok(())
+    }
+
+    /// Kill the attached VM.
+    fn release_vm(&self, live: &LiveState) -> BoxliteResult<()> {
+        use crate::runtime::options::NetworkMode;
+
+        live.handler
+            .lock()
+            .map_err(|e| BoxliteError::Internal(format!("handler lock poisoned: {}", e)))?
+            .stop()?;
+        if self.config.options.network_mode == NetworkMode::Host {
+            crate::net::host::detach(self.id());
+        }
+        Ok(())
+    }
+
+    // ========================================================================
+    // USER HANDLES (internal)
+    // ========================================================================
+
+    /// Count a new user handle to this box.
+    pub(crate) fn acquire_user_handle(&self) {
+        self.user_handles.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// Release a user handle, applying
+    /// [`BoxOptions::on_drop`](crate::BoxOptions::on_drop) if it was the
+    /// last one to a running box.
+    pub(crate) fn release_user_handle(self: &Arc<Self>) {
+        if self.user_handles.fetch_sub(1, Ordering::SeqCst) != 1 {
+            return;
+        }
+        let status = self.state.read().status;
+        if self.is_shutdown() || !(status.is_running() || status.is_paused()) {
+            return;
+        }
+
+        match self.config.options.on_drop {
+            DropBehavior::Detach => {}
+            DropBehavior::Stop => {
+                let Ok(handle) = tokio::runtime::Handle::try_current() else {
+                    tracing::warn!(box_id = %self.id(), "No async runtime, dropped box left running");
+                    return;
+                };
+                let box_impl = Arc::clone(self);
+                handle.spawn(async move {
+                    // Attach first, so stop() shuts down the VM of a recovered box
+                    let stopped = match box_impl.start().await {
+                        Ok(()) => box_impl.stop().await,
+                        Err(e) => Err(e),
+                    };
+                    match stopped {
+                        Ok(()) => tracing::info!(box_id = %box_impl.id(), "Stopped dropped box"),
+                        Err(e) => {
+                            tracing::warn!(box_id = %box_impl.id(), error = %e, "Failed to stop dropped box")
+                        }
+                    }
+                });
+            }
+            DropBehavior::Panic => {
+                if !std::thread::panicking() {
+                    panic!(
+                        "box {} dropped while {}; stop it first (BoxOptions::on_drop is Panic)",
+                        self.id(),
+                        status
+                    );
+                }
+            }
+        }
+    }
+
+    // ========================================================================
+    // IDLE SUSPEND (internal)
+    // ========================================================================
+
+    /// Count the box as active for `idle_timeout` as of now.
+    pub(crate) fn mark_active(&self) {
+        *self.last_active.lock() = Instant::now();
+    }
+
+    /// When the box was last active.
+    pub(crate) fn last_active(&self) -> Instant {
+        *self.last_active.lock()
+    }
+
+    /// Suspend the box if it has been idle for `timeout`, as set by
+    /// `idle_action`.
+    ///
+    /// The box stays usable: the next operation restarts or resumes it.
+    pub(crate) async fn suspend_if_idle(&self, timeout: Duration) -> BoxliteResult<()> {
+        use crate::runtime::options::IdleAction;
+
+        // Keeps new operations from attaching while the VM goes away
+        let _init = self.live_init.lock().await;
+        let Some(live) = self.attached() else {
+            return Ok(());
+        };
+        if self.is_shutdown() || !self.state.read().status.is_running() {
+            return Ok(());
+        }
+        if self.last_active().elapsed() < timeout {
+            return Ok(());
+        }
+        // Commands left running keep the box busy; the container's own
+        // process is registered under its ID and does not count
+        let mut exec_interface = live.guest_session.execution().await?;
+        let busy =
+            exec_interface.list().await?.iter().any(|info| {
+                matches!(info.state, ExecState::Running) && info.id != self.container_id()
+            });
+        if busy {
+            *self.last_active.lock() = Instant::now();
+            return Ok(());
+        }
+
+        match self.config.options.idle_action {
+            IdleAction::Pause => {
+                self.idle_paused.store(true, Ordering::SeqCst);
+                if let Err(e) = self.apply_paused(&live, true) {
+                    self.idle_paused.store(false, Ordering::SeqCst);
+                    return Err(e);
+                }
+            }
+            IdleAction::Stop => {
+                // Operations that already hold the live state fail from
+                // here on; later ones re-run the Stopped plan
+                *self.live.write() = None;
+                self.shutdown_guest(&live, self.config.options.stop_timeout)
+                    .await;
+                self.release_vm(&live)?;
+
+                let mut state = self.state.write();
+                state.set_status(BoxStatus::Stopped);
+                state.set_pid(None);
+                state.health = None;
+                state.record_exit(ExitReason::Idle);
+                self.runtime.box_manager.save_box(&self.config.id, &state)?;
+            }
+        }
+        tracing::info!(
+            box_id = %self.id(),
+            action = ?self.config.options.idle_action,
+            ?timeout,
+            "Suspended idle box"
+        );
+        Ok(())
+    }
+
+    // ========================================================================
+    // LIVE STATE INITIALIZATION (internal)
+    // ========================================================================
+
+    /// The LiveState, if this handle is attached to the VM.
+    fn attached(&self) -> Option<Arc<LiveState>> {
+        self.live.read().clone()
+    }
+
+    /// Get LiveState, lazily initializing it if needed.
+    ///
+    /// A box suspended for `idle_timeout` is resumed, or restarted with the
+    /// Stopped plan, first.
+    async fn live_state(&self) -> BoxliteResult<Arc<LiveState>> {
+        if let Some(live) = self.attached() {
+            if self.idle_paused.load(Ordering::SeqCst) {
+                self.wake(&live).await?;
+            }
+            return Ok(live);
+        }
+
+        let _init = self.live_init.lock().await;
+        if let Some(live) = self.attached() {
+            return Ok(live);
+        }
+        if self.is_shutdown() {
+            return Err(BoxliteError::InvalidState("Box is stopped".into()));
+        }
+        let live = Arc::new(self.init_live_state().await?);
+        *self.live.write() = Some(Arc::clone(&live));
+
+        if let Some(timeout) = self.config.options.idle_timeout {
+            idle::spawn_watcher(
+                &self.runtime,
+                self.id().clone(),
+                Arc::downgrade(&live),
+                timeout,
+            );
+        }
+        Ok(live)
+    }
+
+    /// Resume a VM paused for `idle_timeout`.
+    async fn wake(&self, live: &LiveState) -> BoxliteResult<()> {
+        // Serializes with suspend_if_idle()
+        let _init = self.live_init.lock().await;
+        if self.idle_paused.swap(false, Ordering::SeqCst) {
+            tracing::debug!(box_id = %self.id(), "Resuming idle box");
+            self.apply_paused(live, false)?;
+        }
+        Ok(())
+    }
+
+    /// Initialize LiveState via BoxBuilder.
+    ///
+    /// BoxBuilder handles all status types with different execution plans:
+    /// - Starting: full pipeline (filesystem, rootfs, spawn, connect, init)
+    /// - Stopped: restart pipeline (reuse rootfs, spawn, connect, init)
+    /// - Running: attach pipeline (attach, connect)
+    ///
+    /// For Starting (new boxes), this also allocates a lock and persists to database
+    /// after successful build.
+    async fn init_live_state(&self) -> BoxliteResult<LiveState> {
+        use super::BoxBuilder;
+        use std::sync::Arc;
+
+        let state = self.state.read().clone();
+        let is_new_box = state.status == BoxStatus::Starting;
+
+        // Acquire lock before build
+        // - New boxes: allocate new lock
+        // - Existing boxes: retrieve existing lock
+        let mut new_lock = NewLockGuard::new(&*self.runtime.lock_manager);
+        let locker = if is_new_box {
+            let lock_id = self.runtime.lock_manager.allocate()?;
+            new_lock.arm(lock_id);
+            let locker = self.runtime.lock_manager.retrieve(lock_id)?;
+            tracing::debug!(
+                box_id = %self.config.id,
+                lock_id = %lock_id,
+                "Allocated and acquired lock for new box"
+            );
+            locker
+        } else if let Some(lock_id) = state.lock_id {
+            let locker = self.runtime.lock_manager.retrieve(lock_id)?;
+            tracing::debug!(
+                box_id = %self.config.id,
+                lock_id = %lock_id,
+                "Acquired lock for existing box"
+            );
+            locker
+        } else {
+            // Existing box without lock_id - should never happen in normal operation
+            return Err(BoxliteError::Internal(format!(
+                "box {} is missing lock_id (status: {:?})",
+                self.config.id, state.status
+            )));
+        };
+
+        // Hold the lock for the duration of build and persist operations.
+        // LockGuard acquires lock on creation and releases on drop.
+        let _guard = LockGuard::new(&*locker);
+
+        // Booting counts against the runtime's resource limits; attaching
+        // to a running VM does not
+        let boots = !(state.status.is_running() || state.status.is_paused());
+        let _admission = if boots {
+            Some(self.runtime.admit(&self.config)?)
+        } else {
+            None
+        };
+        let _boot_slot = if boots {
+            self.runtime.boot_slot().await
+        } else {
+            None
+        };
+
+        // Build the box (lock is held)
+        let builder = BoxBuilder::new(Arc::clone(&self.runtime), self.config.clone(), state)?;
+        // If the build fails (or this future is dropped mid-build), a newly
+        // allocated lock is freed when new_lock drops.
+        let live_state = builder.build().await?;
+
+        // The pipeline only persisted the booted VM; this handle may restart
+        // it in place after an idle stop, so keep its own state current too
+        if let Ok(handler) = live_state.handler.lock() {
+            let mut state = self.state.write();
+            if !state.status.is_paused() {
+                state.set_status(BoxStatus::Running);
+            }
+            state.set_pid(Some(handler.pid()));
+        }
+
+        // Known before this returns, so wait_healthy() sees it
+        let mut image = self.load_boot_image().await;
+        let healthcheck = image.as_mut().and_then(|image| image.healthcheck.take());
+        {
+            let mut state = self.state.write();
+            state.health = healthcheck.as_ref().map(|_| HealthStatus::Starting);
+            // Those of a lazily started box are the runtime's listeners
+            if boots && !self.config.options.lazy_start {
+                state.ports = live_state.ports.clone();
+                self.runtime
+                    .telemetry
+                    .ports_published(&self.config.id, &state.ports);
+            }
+            if boots {
+                (state.image_digest, state.image_manifest) = match image {
+                    Some(image) => (Some(image.digest), Some(image.manifest_digest)),
+                    None => (None, None),
+                };
+                if !is_new_box {
+                    self.runtime.box_manager.save_box(&self.config.id, &state)?;
+                }
+            }
+        }
+
+        // Build succeeded - persist to DB for new boxes (lock still held)
+        if is_new_box {
+            let lock_id = locker.id();
+
+            // Hold the state lock while updating lock_id and persisting to DB
+            let mut state = self.state.write();
+            state.set_lock_id(lock_id);
+
+            // Failed to persist - new_lock frees the lock on return
+            self.runtime.box_manager.add_box(&self.config, &state)?;
+            new_lock.disarm();
+
+            tracing::debug!(
+                box_id = %self.config.id,
+                lock_id = %lock_id,
+                "Persisted new box to database"
+            );
+        }
+
+        self.runtime.schedule_expiry(&self.config);
+        crate::runtime::supervisor::supervise(&self.runtime, &self.config, false);
+        if let Some(check) = healthcheck {
+            health::spawn_checker(&self.runtime, self.id().clone(), check);
+        }
+
+        // Lock is automatically released when _guard drops
+        Ok(live_state)
+    }
+}
+
+/// What booting a box needs from its image.
+struct BootImage {
+    digest: String,
+    manifest_digest: String,
+    healthcheck: Option<HealthCheck>,
+}
+
+/// Frees a newly allocated box lock unless disarmed.
+///
+/// Covers both error returns and cancellation: if the init future is dropped
+/// mid-build, the lock is released instead of leaking.
+struct NewLockGuard<'a> {
+    lock_manager: &'a dyn LockManager,
+    lock_id: Option<LockId>,
+}
+
+impl<'a> NewLockGuard<'a> {
+    fn new(lock_manager: &'a dyn LockManager) -> Self {
+        Self {
+            lock_manager,
+            lock_id: None,
+        }
+    }
+
+    fn arm(&mut self, lock_id: LockId) {
+        self.lock_id = Some(lock_id);
+    }
+
+    fn disarm(&mut self) {
+        self.lock_id = None;
+    }
+}
+
+impl Drop for NewLockGuard<'_> {
+    fn drop(&mut self) {
+        if let Some(lock_id) = self.lock_id.take()
+            && let Err(e) = self.lock_manager.free(lock_id)
+        {
+            tracing::error!(
+                lock_id = %lock_id,
+                error = %e,
+                "Failed to free lock for box that did not finish initializing"
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::litebox::ExecResult;
+
+    #[tokio::test]
+    async fn test_exec_slot_released_on_exit() {
+        let slots = Arc::new(Semaphore::new(1));
+        let permit = slots.clone().try_acquire_owned().unwrap();
+
+        let (result_tx, result_rx) = mpsc::unbounded_channel();
+        let mut components = hold_until_exit(
+            permit,
+            ExecComponents {
+                execution_id: "exec".to_string(),
+                stdin_tx: None,
+                stdout_rx: mpsc::channel(1).1,
+                stderr_rx: mpsc::channel(1).1,
+                result_rx,
+            },
+        );
+        assert_eq!(slots.available_permits(), 0);
+
+        result_tx.send(ExecResult { exit_code: 0 }).unwrap();
+        assert_eq!(components.result_rx.recv().await.unwrap().exit_code, 0);
+        assert_eq!(slots.available_permits(), 1);
     }
 }
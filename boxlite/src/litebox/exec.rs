@@ -3,9 +3,12 @@
 //! Type definitions for executing commands in a box.
 //! The actual execution logic is in BoxImpl::exec().
 
-use crate::portal::interfaces::ExecutionInterface;
-use boxlite_shared::errors::BoxliteResult;
+use crate::portal::interfaces::{ExecComponents, ExecutionInterface};
+use crate::runtime::options::VolumeSpec;
+use boxlite_shared::errors::{BoxliteError, BoxliteResult};
+use bytes::Bytes;
 use futures::Stream;
+use std::path::PathBuf;
 use std::pin::Pin;
 use std::task::{Context, Poll};
 use std::time::Duration;
@@ -31,9 +34,19 @@ pub struct BoxCommand {
     pub(crate) command: String,
     pub(crate) args: Vec<String>,
     pub(crate) env: Option<Vec<(String, String)>>,
+    pub(crate) env_files: Vec<PathBuf>,
     pub(crate) timeout: Option<Duration>,
     pub(crate) working_dir: Option<String>,
+    pub(crate) user: Option<String>,
+    pub(crate) privileged: bool,
     pub(crate) tty: bool,
+    pub(crate) max_output_bytes: Option<u64>,
+    pub(crate) output_limit_policy: OutputLimitPolicy,
+    pub(crate) detach: bool,
+    pub(crate) limits: Option<ExecLimits>,
+    pub(crate) stdout_to: Option<PathBuf>,
+    pub(crate) stderr_to: Option<PathBuf>,
+    pub(crate) log_rotation: LogRotation,
 }
 
 impl BoxCommand {
@@ -43,9 +56,19 @@ impl BoxCommand {
             command: command.into(),
             args: vec![],
             env: None,
+            env_files: Vec::new(),
             timeout: None,
             working_dir: None,
+            user: None,
+            privileged: false,
             tty: false,
+            max_output_bytes: None,
+            output_limit_policy: OutputLimitPolicy::default(),
+            detach: false,
+            limits: None,
+            stdout_to: None,
+            stderr_to: None,
+            log_rotation: LogRotation::default(),
         }
     }
 
@@ -73,6 +96,24 @@ impl BoxCommand {
         self
     }
 
+    /// Add variables from a host file with `KEY=value` lines, like docker's
+    /// `--env-file`. The file is read when the command runs; later files
+    /// win, and variables set with [`env`](Self::env) win over all.
+    pub fn env_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.env_files.push(path.into());
+        self
+    }
+
+    /// Read `env_files` into `env`.
+    pub(crate) fn resolve_env_files(mut self) -> BoxliteResult<Self> {
+        if !self.env_files.is_empty() {
+            let files = std::mem::take(&mut self.env_files);
+            let env = self.env.take().unwrap_or_default();
+            self.env = Some(crate::util::env_file::merge(&files, env)?);
+        }
+        Ok(self)
+    }
+
     /// Set execution timeout.
     pub fn timeout(mut self, timeout: Duration) -> Self {
         self.timeout = Some(timeout);
@@ -85,6 +126,21 @@ impl BoxCommand {
         self
     }
 
+    /// Run as `user` instead of the image `USER`: a name, uid, `name:group`
+    /// or `uid:gid`, resolved against the box's `/etc/passwd` and
+    /// `/etc/group`.
+    pub fn user(mut self, user: impl Into<String>) -> Self {
+        self.user = Some(user.into());
+        self
+    }
+
+    /// Run as root regardless of the image `USER` and [`user`](Self::user),
+    /// e.g. to install packages in an image that runs as a regular user.
+    pub fn privileged(mut self, enable: bool) -> Self {
+        self.privileged = enable;
+        self
+    }
+
     /// Enable TTY (pseudo-terminal) for interactive sessions.
     ///
     /// Terminal size is auto-detected from the current terminal.
@@ -92,6 +148,175 @@ impl BoxCommand {
         self.tty = enable;
         self
     }
+
+    /// Limit how many bytes of stdout and of stderr are forwarded (each).
+    ///
+    /// Enforced in the guest, so excess output never reaches the host.
+    /// What happens at the limit is controlled by
+    /// [`output_limit_policy`](Self::output_limit_policy) (default: keep the head).
+    pub fn max_output_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_output_bytes = Some(max_bytes);
+        self
+    }
+
+    /// Set what happens when output exceeds [`max_output_bytes`](Self::max_output_bytes).
+    pub fn output_limit_policy(mut self, policy: OutputLimitPolicy) -> Self {
+        self.output_limit_policy = policy;
+        self
+    }
+
+    /// Keep the process running after the [`Execution`] handle is dropped.
+    ///
+    /// The guest keeps draining its output and buffers it (up to 1 MiB) while
+    /// no client is attached. Reconnect with
+    /// [`LiteBox::attach_exec`](crate::LiteBox::attach_exec) using the
+    /// execution ID, even from another process.
+    pub fn detach(mut self, detach: bool) -> Self {
+        self.detach = detach;
+        self
+    }
+
+    /// Limit the resources of this command (and everything it spawns).
+    ///
+    /// The guest runs the command in its own cgroup, so one heavy command
+    /// can't starve the rest of the box. `None` leaves a resource unlimited.
+    ///
+    /// - `cpu_quota`: CPUs worth of time, e.g. `0.5` for half a CPU
+    /// - `memory_max`: bytes; the command is OOM-killed above it
+    /// - `pids_max`: processes and threads
+    pub fn limits(
+        mut self,
+        cpu_quota: Option<f64>,
+        memory_max: Option<u64>,
+        pids_max: Option<u64>,
+    ) -> Self {
+        self.limits = Some(ExecLimits {
+            cpu_quota,
+            memory_max,
+            pids_max,
+        });
+        self
+    }
+
+    /// Write stdout to a file instead of the [`Execution`]'s stdout stream.
+    ///
+    /// `path` is relative to the runtime's logs directory
+    /// (`<home_dir>/logs`); parent directories are created and the file is
+    /// appended to. The execution's stdout stream ends right away, and
+    /// [`Execution::wait`] returns once all output is written.
+    pub fn stdout_to(mut self, path: impl Into<PathBuf>) -> Self {
+        self.stdout_to = Some(path.into());
+        self
+    }
+
+    /// Write stderr to a file, like [`stdout_to`](Self::stdout_to).
+    ///
+    /// Both streams may go to the same file.
+    pub fn stderr_to(mut self, path: impl Into<PathBuf>) -> Self {
+        self.stderr_to = Some(path.into());
+        self
+    }
+
+    /// Set how files from [`stdout_to`](Self::stdout_to) and
+    /// [`stderr_to`](Self::stderr_to) are rotated.
+    pub fn log_rotation(mut self, rotation: LogRotation) -> Self {
+        self.log_rotation = rotation;
+        self
+    }
+}
+
+/// Size-based rotation of exec output files.
+///
+/// When a write would grow the file past `max_bytes`, `out.log` is renamed
+/// to `out.log.1` (shifting older files up to `out.log.<max_files>`, the
+/// oldest is deleted) and a fresh `out.log` is started.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LogRotation {
+    /// Rotate at this size. `None` never rotates.
+    pub max_bytes: Option<u64>,
+    /// Rotated files to keep. `0` truncates instead of keeping any.
+    pub max_files: u32,
+}
+
+impl Default for LogRotation {
+    fn default() -> Self {
+        Self {
+            max_bytes: None,
+            max_files: 5,
+        }
+    }
+}
+
+/// Resource limits set with [`BoxCommand::limits`].
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) struct ExecLimits {
+    pub cpu_quota: Option<f64>,
+    pub memory_max: Option<u64>,
+    pub pids_max: Option<u64>,
+}
+
+impl ExecLimits {
+    /// CFS period the CPU quota is expressed in.
+    pub const CPU_PERIOD_US: u64 = 100_000;
+
+    /// CPU time allowed per [`CPU_PERIOD_US`](Self::CPU_PERIOD_US).
+    pub fn cpu_quota_us(&self) -> Option<u64> {
+        self.cpu_quota
+            .map(|cpus| (cpus * Self::CPU_PERIOD_US as f64).round() as u64)
+    }
+
+    pub fn validate(&self) -> BoxliteResult<()> {
+        if let Some(cpus) = self.cpu_quota
+            && !(cpus.is_finite() && cpus > 0.0 && self.cpu_quota_us() >= Some(1000))
+        {
+            return Err(BoxliteError::InvalidArgument(format!(
+                "cpu_quota must be at least 0.01 CPUs, got {}",
+                cpus
+            )));
+        }
+        if self.memory_max == Some(0) {
+            return Err(BoxliteError::InvalidArgument(
+                "memory_max must be greater than 0".to_string(),
+            ));
+        }
+        if self.pids_max == Some(0) {
+            return Err(BoxliteError::InvalidArgument(
+                "pids_max must be greater than 0".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// What to do when an execution exceeds its output limit.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OutputLimitPolicy {
+    /// Keep the first bytes and discard the rest.
+    #[default]
+    Head,
+    /// Keep the last bytes. Output is delivered when the stream ends.
+    Tail,
+    /// Kill the process and end the output stream with an error.
+    Error,
+}
+
+/// Effective execution context of a box.
+///
+/// Describes what the next [`BoxCommand`] would see inside the container
+/// before any per-command overrides are applied. Obtained from
+/// [`LiteBox::env_snapshot`](crate::LiteBox::env_snapshot).
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct ExecEnvSnapshot {
+    /// Environment variables (image env merged with box env), sorted by key.
+    pub env: Vec<(String, String)>,
+    /// Working directory commands start in.
+    pub working_dir: String,
+    /// User commands run as: the image `USER` as written (a name, uid,
+    /// `name:group` or `uid:gid`), or `root`. [`BoxCommand::user`] and
+    /// [`BoxCommand::privileged`] override it per command.
+    pub user: String,
+    /// Volumes mounted into the container.
+    pub mounts: Vec<VolumeSpec>,
 }
 
 /// Handle to a running command execution.
@@ -109,7 +334,7 @@ impl BoxCommand {
 /// let mut execution = litebox.exec(BoxCommand::new("ls").arg("-la")).await?;
 ///
 /// // Read stdout
-/// let mut stdout = execution.stdout.take().unwrap();
+/// let mut stdout = execution.stdout().unwrap();
 /// while let Some(line) = stdout.next().await {
 ///     println!("{}", line);
 /// }
@@ -122,11 +347,12 @@ impl BoxCommand {
 /// ```
 pub struct Execution {
     id: ExecutionId,
+    /// Kept outside `inner` so signals can be sent while `wait()` holds the lock.
+    interface: ExecutionInterface,
     inner: std::sync::Arc<tokio::sync::Mutex<ExecutionInner>>,
 }
 
 pub(crate) struct ExecutionInner {
-    interface: ExecutionInterface,
     result_rx: mpsc::UnboundedReceiver<ExecResult>,
     cached_result: Option<ExecResult>,
 
@@ -154,7 +380,6 @@ impl Execution {
         stderr: Option<ExecStderr>,
     ) -> Self {
         let inner = ExecutionInner {
-            interface,
             result_rx,
             cached_result: None,
             stdin,
@@ -164,10 +389,26 @@ impl Execution {
 
         Self {
             id: execution_id,
+            interface,
             inner: std::sync::Arc::new(tokio::sync::Mutex::new(inner)),
         }
     }
 
+    /// Build an Execution from portal components.
+    pub(crate) fn from_components(
+        components: ExecComponents,
+        interface: ExecutionInterface,
+    ) -> Self {
+        Self::new(
+            components.execution_id,
+            interface,
+            components.result_rx,
+            components.stdin_tx.map(ExecStdin::new),
+            Some(ExecStdout::new(components.stdout_rx)),
+            Some(ExecStderr::new(components.stderr_rx)),
+        )
+    }
+
     /// Get the execution ID.
     pub fn id(&self) -> &ExecutionId {
         &self.id
@@ -197,6 +438,33 @@ impl Execution {
         })
     }
 
+    /// Take stdout and stderr as a single stream in arrival order.
+    ///
+    /// Each item is tagged with the stream it came from, preserving the
+    /// interleaving the process produced. Consumes both the stdout and stderr
+    /// streams, so returns `None` if either was already taken.
+    pub fn output(&mut self) -> Option<ExecOutput> {
+        futures::executor::block_on(async {
+            let mut inner = self.inner.lock().await;
+            if inner.stdout.is_none() || inner.stderr.is_none() {
+                return None;
+            }
+            let stdout = inner.stdout.take()?;
+            let stderr = inner.stderr.take()?;
+            Some(ExecOutput::new(stdout, stderr))
+        })
+    }
+
+    /// Take stdout and stderr as a single stream with guest timestamps.
+    ///
+    /// Like [`output`](Self::output), but each chunk also carries the
+    /// sequence number and monotonic timestamp the guest assigned when it
+    /// read the chunk, so logs can be reconstructed faithfully. Returns
+    /// `None` if stdout or stderr was already taken.
+    pub fn timestamped_output(&mut self) -> Option<TimestampedOutput> {
+        self.output().map(|inner| TimestampedOutput { inner })
+    }
+
     /// Wait for the execution to complete.
     ///
     /// Returns the exit status once the execution finishes. If the result is
@@ -225,24 +493,155 @@ impl Execution {
 
     /// Kill the process (sends SIGKILL).
     pub async fn kill(&mut self) -> BoxliteResult<()> {
-        self.signal(9).await // SIGKILL
+        self.signal(Signal::Kill).await
     }
 
     /// Send a signal to the execution.
-    pub async fn signal(&self, signal: i32) -> BoxliteResult<()> {
-        let mut inner = self.inner.lock().await;
-        inner.interface.kill(&self.id, signal).await
+    ///
+    /// The signal is delivered by the guest executor, so it can be used for
+    /// graceful interruption (e.g. [`Signal::Int`] or [`Signal::Term`]) while
+    /// another task is blocked in [`wait`](Self::wait).
+    pub async fn signal(&self, signal: Signal) -> BoxliteResult<()> {
+        self.interface.clone().kill(&self.id, signal.number()).await
     }
 
     /// Resize PTY terminal window.
     ///
     /// Only works for executions started with TTY enabled.
     pub async fn resize_tty(&self, rows: u32, cols: u32) -> BoxliteResult<()> {
-        let mut inner = self.inner.lock().await;
-        inner.interface.resize_tty(&self.id, rows, cols, 0, 0).await
+        self.interface
+            .clone()
+            .resize_tty(&self.id, rows, cols, 0, 0)
+            .await
     }
 }
 
+/// Signal that can be sent to an execution.
+///
+/// Numbers follow Linux conventions since they are delivered inside the
+/// guest, regardless of the host platform.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Signal {
+    /// SIGHUP (1)
+    Hup,
+    /// SIGINT (2) - interrupt, as with Ctrl-C.
+    Int,
+    /// SIGQUIT (3)
+    Quit,
+    /// SIGKILL (9) - cannot be caught or ignored.
+    Kill,
+    /// SIGUSR1 (10)
+    Usr1,
+    /// SIGUSR2 (12)
+    Usr2,
+    /// SIGTERM (15) - request graceful termination.
+    Term,
+    /// SIGCONT (18)
+    Cont,
+    /// SIGSTOP (19) - cannot be caught or ignored.
+    Stop,
+    /// Any other signal, by Linux signal number.
+    Other(i32),
+}
+
+impl Signal {
+    /// Linux signal number.
+    pub fn number(&self) -> i32 {
+        match self {
+            Signal::Hup => 1,
+            Signal::Int => 2,
+            Signal::Quit => 3,
+            Signal::Kill => 9,
+            Signal::Usr1 => 10,
+            Signal::Usr2 => 12,
+            Signal::Term => 15,
+            Signal::Cont => 18,
+            Signal::Stop => 19,
+            Signal::Other(n) => *n,
+        }
+    }
+}
+
+impl From<i32> for Signal {
+    fn from(number: i32) -> Self {
+        match number {
+            1 => Signal::Hup,
+            2 => Signal::Int,
+            3 => Signal::Quit,
+            9 => Signal::Kill,
+            10 => Signal::Usr1,
+            12 => Signal::Usr2,
+            15 => Signal::Term,
+            18 => Signal::Cont,
+            19 => Signal::Stop,
+            n => Signal::Other(n),
+        }
+    }
+}
+
+/// Summary of an execution known to a box's guest.
+///
+/// Returned by [`LiteBox::list_execs`](crate::LiteBox::list_execs).
+#[derive(Clone, Debug)]
+pub struct ExecInfo {
+    pub id: ExecutionId,
+    /// Program that was started.
+    pub command: String,
+    pub args: Vec<String>,
+    /// Process ID inside the guest.
+    pub pid: u32,
+    pub started_at: chrono::DateTime<chrono::Utc>,
+    /// Started with [`BoxCommand::detach`].
+    pub detached: bool,
+    pub state: ExecState,
+}
+
+/// Persisted record of a command executed in a box.
+///
+/// Returned by [`BoxliteRuntime::exec_history`](crate::BoxliteRuntime::exec_history).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExecRecord {
+    pub id: ExecutionId,
+    /// Program that was started.
+    pub command: String,
+    pub args: Vec<String>,
+    pub started_at: chrono::DateTime<chrono::Utc>,
+    /// `None` while the command is running (or if the runtime went away
+    /// before it exited).
+    pub finished_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// Same convention as [`ExecResult::exit_code`].
+    pub exit_code: Option<i32>,
+    /// Total bytes of stdout and stderr the host received.
+    pub output_bytes: u64,
+    /// Start of the combined stdout/stderr output, truncated to
+    /// [`ExecRecord::PREVIEW_BYTES`] and decoded as UTF-8 (lossy).
+    pub output_preview: String,
+    /// `sha256:` digest of all of that output, in the order it arrived;
+    /// `None` until the command's output has ended.
+    pub output_digest: Option<String>,
+}
+
+impl ExecRecord {
+    /// Maximum output kept in [`output_preview`](Self::output_preview).
+    pub const PREVIEW_BYTES: usize = 4096;
+
+    /// Wall-clock run time, once finished.
+    pub fn duration(&self) -> Option<Duration> {
+        let finished_at = self.finished_at?;
+        (finished_at - self.started_at).to_std().ok()
+    }
+}
+
+/// Lifecycle state of an execution.
+#[derive(Clone, Debug)]
+pub enum ExecState {
+    Running,
+    Exited(ExecResult),
+    /// The process is gone but its exit status could not be collected;
+    /// holds why.
+    Unknown(String),
+}
+
 /// Exit status of a process.
 #[derive(Clone, Debug)]
 pub struct ExecResult {
@@ -261,63 +660,498 @@ impl ExecResult {
     }
 }
 
+/// How a box's container init process exited, from
+/// [`LiteBox::wait`](crate::LiteBox::wait).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ExitStatus {
+    /// Exit code (0 = success). If terminated by signal, code is negative signal number.
+    pub exit_code: i32,
+    /// Killed by the out-of-memory killer of the guest.
+    pub oom_killed: bool,
+}
+
+impl ExitStatus {
+    /// Returns true if the exit code was 0.
+    pub fn success(&self) -> bool {
+        self.exit_code == 0
+    }
+
+    pub fn code(&self) -> i32 {
+        self.exit_code
+    }
+}
+
 /// Standard input stream (write-only).
+///
+/// Dropping it closes stdin, like [`close`](Self::close).
 pub struct ExecStdin {
-    sender: mpsc::UnboundedSender<Vec<u8>>,
+    sender: Option<mpsc::UnboundedSender<Vec<u8>>>,
 }
 
 impl ExecStdin {
     pub(crate) fn new(sender: mpsc::UnboundedSender<Vec<u8>>) -> Self {
-        Self { sender }
+        Self {
+            sender: Some(sender),
+        }
     }
 
     /// Write data to stdin.
     pub async fn write(&mut self, data: &[u8]) -> BoxliteResult<()> {
-        self.sender
+        let sender = self.sender.as_ref().ok_or_else(|| {
+            boxlite_shared::BoxliteError::InvalidState("stdin is closed".to_string())
+        })?;
+        sender
             .send(data.to_vec())
             .map_err(|_| boxlite_shared::BoxliteError::Internal("stdin channel closed".to_string()))
     }
 
+    /// Send EOF to the process, after any data already written.
+    ///
+    /// The execution keeps running and its output can still be read, so
+    /// commands that read stdin until EOF (e.g. `wc -l`, `python -`) can be
+    /// driven to completion. With a TTY, EOF is delivered as Ctrl-D.
+    /// Further writes fail. Calling it again is a no-op.
+    pub fn close(&mut self) {
+        // The stdin pump sends the close message once the channel ends
+        self.sender = None;
+    }
+
+    /// Whether [`close`](Self::close) was called.
+    pub fn is_closed(&self) -> bool {
+        self.sender.is_none()
+    }
+
     /// Write all data to stdin.
     pub async fn write_all(&mut self, data: &[u8]) -> BoxliteResult<()> {
         self.write(data).await
     }
 }
 
+/// Output chunk with its ordering metadata.
+#[derive(Clone, Debug)]
+pub(crate) struct OutputFrame {
+    /// Arrival sequence number, assigned by the single task that
+    /// demultiplexes the guest's output, so it orders stdout against stderr.
+    pub seq: u64,
+    pub data: Bytes,
+    /// Sequence number assigned by the guest when it read the chunk
+    /// (0 for frames synthesized on the host).
+    pub guest_seq: u64,
+    /// Time since the process started, measured by the guest.
+    pub timestamp: Duration,
+}
+
+impl OutputFrame {
+    /// Frame without guest metadata.
+    pub(crate) fn new(seq: u64, data: Bytes) -> Self {
+        Self {
+            seq,
+            data,
+            guest_seq: 0,
+            timestamp: Duration::ZERO,
+        }
+    }
+}
+
 /// Standard output stream (read-only).
+///
+/// As a [`Stream`], yields output chunks decoded as UTF-8 (lossy). Use
+/// [`next_chunk`](Self::next_chunk) to read the raw bytes instead, e.g. for
+/// binary output such as tar archives or images.
 pub struct ExecStdout {
-    receiver: mpsc::UnboundedReceiver<String>,
+    receiver: mpsc::Receiver<OutputFrame>,
 }
 
 impl ExecStdout {
-    pub(crate) fn new(receiver: mpsc::UnboundedReceiver<String>) -> Self {
+    pub(crate) fn new(receiver: mpsc::Receiver<OutputFrame>) -> Self {
         Self { receiver }
     }
+
+    /// Receive the next raw output chunk, or `None` once the stream ends.
+    ///
+    /// Chunks are passed through exactly as produced by the guest, with no
+    /// line splitting or UTF-8 decoding.
+    pub async fn next_chunk(&mut self) -> Option<Bytes> {
+        self.receiver.recv().await.map(|frame| frame.data)
+    }
 }
 
 impl Stream for ExecStdout {
     type Item = String;
 
     fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        self.receiver.poll_recv(cx)
+        self.receiver
+            .poll_recv(cx)
+            .map(|frame| frame.map(|frame| String::from_utf8_lossy(&frame.data).into_owned()))
     }
 }
 
 /// Standard error stream (read-only).
+///
+/// As a [`Stream`], yields output chunks decoded as UTF-8 (lossy). Use
+/// [`next_chunk`](Self::next_chunk) to read the raw bytes instead.
 pub struct ExecStderr {
-    receiver: mpsc::UnboundedReceiver<String>,
+    receiver: mpsc::Receiver<OutputFrame>,
 }
 
 impl ExecStderr {
-    pub(crate) fn new(receiver: mpsc::UnboundedReceiver<String>) -> Self {
+    pub(crate) fn new(receiver: mpsc::Receiver<OutputFrame>) -> Self {
         Self { receiver }
     }
+
+    /// Receive the next raw output chunk, or `None` once the stream ends.
+    ///
+    /// Chunks are passed through exactly as produced by the guest, with no
+    /// line splitting or UTF-8 decoding.
+    pub async fn next_chunk(&mut self) -> Option<Bytes> {
+        self.receiver.recv().await.map(|frame| frame.data)
+    }
 }
 
 impl Stream for ExecStderr {
     type Item = String;
 
     fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        self.receiver.poll_recv(cx)
+        self.receiver
+            .poll_recv(cx)
+            .map(|frame| frame.map(|frame| String::from_utf8_lossy(&frame.data).into_owned()))
+    }
+}
+
+/// A chunk of output from the combined [`ExecOutput`] stream.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum OutputChunk {
+    /// Data written to standard output.
+    Stdout(Bytes),
+    /// Data written to standard error.
+    Stderr(Bytes),
+}
+
+impl OutputChunk {
+    /// Raw bytes of this chunk.
+    pub fn data(&self) -> &Bytes {
+        match self {
+            OutputChunk::Stdout(data) | OutputChunk::Stderr(data) => data,
+        }
+    }
+
+    /// Returns true if this chunk came from standard error.
+    pub fn is_stderr(&self) -> bool {
+        matches!(self, OutputChunk::Stderr(_))
+    }
+
+    fn new(data: Bytes, is_stderr: bool) -> Self {
+        if is_stderr {
+            OutputChunk::Stderr(data)
+        } else {
+            OutputChunk::Stdout(data)
+        }
+    }
+}
+
+/// Combined stdout/stderr stream in arrival order.
+///
+/// Obtained from [`Execution::output`].
+pub struct ExecOutput {
+    stdout: mpsc::Receiver<OutputFrame>,
+    stderr: mpsc::Receiver<OutputFrame>,
+    pending_stdout: Option<OutputFrame>,
+    pending_stderr: Option<OutputFrame>,
+    stdout_done: bool,
+    stderr_done: bool,
+}
+
+impl ExecOutput {
+    pub(crate) fn new(stdout: ExecStdout, stderr: ExecStderr) -> Self {
+        Self {
+            stdout: stdout.receiver,
+            stderr: stderr.receiver,
+            pending_stdout: None,
+            pending_stderr: None,
+            stdout_done: false,
+            stderr_done: false,
+        }
+    }
+
+    /// Fill an empty pending slot from its channel.
+    fn fill(
+        receiver: &mut mpsc::Receiver<OutputFrame>,
+        pending: &mut Option<OutputFrame>,
+        done: &mut bool,
+        cx: &mut Context<'_>,
+    ) {
+        if pending.is_none() && !*done {
+            match receiver.poll_recv(cx) {
+                Poll::Ready(Some(frame)) => *pending = Some(frame),
+                Poll::Ready(None) => *done = true,
+                Poll::Pending => {}
+            }
+        }
+    }
+}
+
+impl ExecOutput {
+    /// Next frame in arrival order, and whether it came from stderr.
+    fn poll_frame(&mut self, cx: &mut Context<'_>) -> Poll<Option<(OutputFrame, bool)>> {
+        Self::fill(
+            &mut self.stdout,
+            &mut self.pending_stdout,
+            &mut self.stdout_done,
+            cx,
+        );
+        Self::fill(
+            &mut self.stderr,
+            &mut self.pending_stderr,
+            &mut self.stderr_done,
+            cx,
+        );
+
+        // Both channels are fed in order by one producer, so a frame that is
+        // visible on one side can never be preceded by a frame still in
+        // flight on the other side.
+        let take_stdout = match (&self.pending_stdout, &self.pending_stderr) {
+            (Some(out), Some(err)) => out.seq < err.seq,
+            (Some(_), None) => true,
+            (None, Some(_)) => false,
+            (None, None) if self.stdout_done && self.stderr_done => return Poll::Ready(None),
+            (None, None) => return Poll::Pending,
+        };
+
+        let frame = if take_stdout {
+            self.pending_stdout.take().map(|frame| (frame, false))
+        } else {
+            self.pending_stderr.take().map(|frame| (frame, true))
+        };
+        Poll::Ready(frame)
+    }
+}
+
+impl Stream for ExecOutput {
+    type Item = OutputChunk;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.poll_frame(cx)
+            .map(|frame| frame.map(|(frame, is_stderr)| OutputChunk::new(frame.data, is_stderr)))
+    }
+}
+
+/// Output chunk with ordering metadata assigned in the guest.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TimestampedChunk {
+    /// Order in which the guest read this chunk, shared by stdout and
+    /// stderr and starting at 1. Sorting by it reconstructs the order the
+    /// process wrote in, even where arrival order differs slightly.
+    pub seq: u64,
+    /// Monotonic time since the process started.
+    pub timestamp: Duration,
+    pub chunk: OutputChunk,
+}
+
+/// Combined stdout/stderr stream with guest timestamps.
+///
+/// Obtained from [`Execution::timestamped_output`]. Yields chunks in
+/// arrival order, like [`ExecOutput`].
+pub struct TimestampedOutput {
+    inner: ExecOutput,
+}
+
+impl Stream for TimestampedOutput {
+    type Item = TimestampedChunk;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.inner.poll_frame(cx).map(|frame| {
+            frame.map(|(frame, is_stderr)| TimestampedChunk {
+                seq: frame.guest_seq,
+                timestamp: frame.timestamp,
+                chunk: OutputChunk::new(frame.data, is_stderr),
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::StreamExt;
+
+    #[test]
+    fn test_signal_numbers_roundtrip() {
+        for signal in [
+            Signal::Hup,
+            Signal::Int,
+            Signal::Quit,
+            Signal::Kill,
+            Signal::Usr1,
+            Signal::Usr2,
+            Signal::Term,
+            Signal::Cont,
+            Signal::Stop,
+        ] {
+            assert_eq!(Signal::from(signal.number()), signal);
+        }
+        assert_eq!(Signal::from(28), Signal::Other(28));
+        assert_eq!(Signal::Other(28).number(), 28);
+    }
+
+    #[tokio::test]
+    async fn test_stdin_close_ends_input() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut stdin = ExecStdin::new(tx);
+
+        stdin.write(b"a\n").await.unwrap();
+        stdin.close();
+        stdin.close();
+
+        assert!(stdin.is_closed());
+        assert!(stdin.write(b"b\n").await.is_err());
+        assert_eq!(rx.recv().await, Some(b"a\n".to_vec()));
+        assert_eq!(rx.recv().await, None);
+    }
+
+    #[tokio::test]
+    async fn test_stdout_next_chunk_preserves_binary() {
+        let (tx, rx) = mpsc::channel(8);
+        let mut stdout = ExecStdout::new(rx);
+
+        let data = Bytes::from_static(&[0x1f, 0x8b, 0xff, 0x00, b'\n', 0xc3]);
+        tx.try_send(OutputFrame::new(1, data.clone())).unwrap();
+        drop(tx);
+
+        assert_eq!(stdout.next_chunk().await, Some(data));
+        assert_eq!(stdout.next_chunk().await, None);
+    }
+
+    #[tokio::test]
+    async fn test_stderr_stream_decodes_utf8() {
+        let (tx, rx) = mpsc::channel(8);
+        let mut stderr = ExecStderr::new(rx);
+
+        tx.try_send(OutputFrame::new(1, Bytes::from("line one\nline two\n")))
+            .unwrap();
+        drop(tx);
+
+        assert_eq!(stderr.next().await.as_deref(), Some("line one\nline two\n"));
+        assert_eq!(stderr.next().await, None);
+    }
+
+    #[tokio::test]
+    async fn test_output_preserves_arrival_order() {
+        let (out_tx, out_rx) = mpsc::channel(8);
+        let (err_tx, err_rx) = mpsc::channel(8);
+        let output = ExecOutput::new(ExecStdout::new(out_rx), ExecStderr::new(err_rx));
+
+        out_tx
+            .try_send(OutputFrame::new(1, Bytes::from("a")))
+            .unwrap();
+        err_tx
+            .try_send(OutputFrame::new(2, Bytes::from("b")))
+            .unwrap();
+        err_tx
+            .try_send(OutputFrame::new(3, Bytes::from("c")))
+            .unwrap();
+        out_tx
+            .try_send(OutputFrame::new(4, Bytes::from("d")))
+            .unwrap();
+        drop(out_tx);
+        drop(err_tx);
+
+        let chunks: Vec<OutputChunk> = output.collect().await;
+        assert_eq!(
+            chunks,
+            vec![
+                OutputChunk::Stdout(Bytes::from("a")),
+                OutputChunk::Stderr(Bytes::from("b")),
+                OutputChunk::Stderr(Bytes::from("c")),
+                OutputChunk::Stdout(Bytes::from("d")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_exec_limits_validate() {
+        let limits = |cpu, mem, pids| ExecLimits {
+            cpu_quota: cpu,
+            memory_max: mem,
+            pids_max: pids,
+        };
+
+        assert!(limits(None, None, None).validate().is_ok());
+        assert!(
+            limits(Some(0.5), Some(64 << 20), Some(32))
+                .validate()
+                .is_ok()
+        );
+        assert_eq!(limits(Some(1.5), None, None).cpu_quota_us(), Some(150_000));
+
+        assert!(limits(Some(0.0), None, None).validate().is_err());
+        assert!(limits(Some(0.001), None, None).validate().is_err());
+        assert!(limits(Some(f64::NAN), None, None).validate().is_err());
+        assert!(limits(None, Some(0), None).validate().is_err());
+        assert!(limits(None, None, Some(0)).validate().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_timestamped_output_carries_guest_metadata() {
+        let (out_tx, out_rx) = mpsc::channel(8);
+        let (err_tx, err_rx) = mpsc::channel(8);
+        let output = TimestampedOutput {
+            inner: ExecOutput::new(ExecStdout::new(out_rx), ExecStderr::new(err_rx)),
+        };
+
+        // Guest read stderr first, but stdout arrived first
+        out_tx
+            .try_send(OutputFrame {
+                guest_seq: 2,
+                timestamp: Duration::from_millis(5),
+                ..OutputFrame::new(1, Bytes::from("out"))
+            })
+            .unwrap();
+        err_tx
+            .try_send(OutputFrame {
+                guest_seq: 1,
+                timestamp: Duration::from_millis(3),
+                ..OutputFrame::new(2, Bytes::from("err"))
+            })
+            .unwrap();
+        drop(out_tx);
+        drop(err_tx);
+
+        let mut chunks: Vec<TimestampedChunk> = output.collect().await;
+        assert_eq!(chunks[0].chunk, OutputChunk::Stdout(Bytes::from("out")));
+
+        chunks.sort_by_key(|c| c.seq);
+        assert_eq!(
+            chunks,
+            vec![
+                TimestampedChunk {
+                    seq: 1,
+                    timestamp: Duration::from_millis(3),
+                    chunk: OutputChunk::Stderr(Bytes::from("err")),
+                },
+                TimestampedChunk {
+                    seq: 2,
+                    timestamp: Duration::from_millis(5),
+                    chunk: OutputChunk::Stdout(Bytes::from("out")),
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_output_drains_after_one_side_closes() {
+        let (out_tx, out_rx) = mpsc::channel(8);
+        let (err_tx, err_rx) = mpsc::channel(8);
+        let mut output = ExecOutput::new(ExecStdout::new(out_rx), ExecStderr::new(err_rx));
+
+        drop(out_tx);
+        err_tx
+            .try_send(OutputFrame::new(1, Bytes::from("only stderr")))
+            .unwrap();
+        drop(err_tx);
+
+        let chunk = output.next().await.unwrap();
+        assert!(chunk.is_stderr());
+        assert_eq!(chunk.data(), &Bytes::from("only stderr"));
+        assert!(output.next().await.is_none());
     }
 }
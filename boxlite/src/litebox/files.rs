@@ -0,0 +1,176 @@
+//! Sources for [`LiteBox::put_file`](crate::LiteBox::put_file), the
+//! archive stream returned by [`LiteBox::copy_out`](crate::LiteBox::copy_out)
+//! and file metadata returned by [`LiteBox::stat`](crate::LiteBox::stat).
+
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use boxlite_shared::errors::{BoxliteError, BoxliteResult};
+use boxlite_shared::{FileKind, FileStat};
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use futures::Stream;
+use tokio::io::AsyncRead;
+
+/// Content to copy into a box: a host file or bytes in memory.
+#[derive(Debug, Clone)]
+pub enum FileSource {
+    /// Read from a file on the host.
+    Path(PathBuf),
+    /// Use these bytes.
+    Bytes(Bytes),
+}
+
+impl FileSource {
+    /// Open the content for reading, with its size.
+    pub(crate) async fn open(
+        self,
+    ) -> BoxliteResult<(Box<dyn AsyncRead + Unpin + Send + 'static>, u64)> {
+        match self {
+            FileSource::Path(path) => {
+                let file = tokio::fs::File::open(&path).await.map_err(|e| {
+                    BoxliteError::Storage(format!("Failed to open {}: {}", path.display(), e))
+                })?;
+                let metadata = file.metadata().await?;
+                if !metadata.is_file() {
+                    return Err(BoxliteError::InvalidArgument(format!(
+                        "Not a regular file: {}",
+                        path.display()
+                    )));
+                }
+                Ok((Box::new(file), metadata.len()))
+            }
+            FileSource::Bytes(bytes) => {
+                let size = bytes.len() as u64;
+                Ok((Box::new(std::io::Cursor::new(bytes)), size))
+            }
+        }
+    }
+}
+
+impl From<PathBuf> for FileSource {
+    fn from(path: PathBuf) -> Self {
+        FileSource::Path(path)
+    }
+}
+
+impl From<&Path> for FileSource {
+    fn from(path: &Path) -> Self {
+        FileSource::Path(path.to_path_buf())
+    }
+}
+
+impl From<Bytes> for FileSource {
+    fn from(bytes: Bytes) -> Self {
+        FileSource::Bytes(bytes)
+    }
+}
+
+impl From<Vec<u8>> for FileSource {
+    fn from(bytes: Vec<u8>) -> Self {
+        FileSource::Bytes(bytes.into())
+    }
+}
+
+impl From<&'static [u8]> for FileSource {
+    fn from(bytes: &'static [u8]) -> Self {
+        FileSource::Bytes(Bytes::from_static(bytes))
+    }
+}
+
+/// Tar archive streamed out of a box, in chunks.
+///
+/// The archive is produced by the guest as it is read; an error ends the
+/// stream.
+pub struct TarStream {
+    inner: Pin<Box<dyn Stream<Item = BoxliteResult<Bytes>> + Send>>,
+}
+
+impl TarStream {
+    pub(crate) fn new(inner: impl Stream<Item = BoxliteResult<Bytes>> + Send + 'static) -> Self {
+        Self {
+            inner: Box::pin(inner),
+        }
+    }
+}
+
+impl Stream for TarStream {
+    type Item = BoxliteResult<Bytes>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.inner.as_mut().poll_next(cx)
+    }
+}
+
+/// Type of a file in a box.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FileType {
+    File,
+    Dir,
+    Symlink,
+    /// Device node, FIFO or socket.
+    Other,
+}
+
+/// Metadata of a file in a box, as returned by
+/// [`LiteBox::stat`](crate::LiteBox::stat) and
+/// [`LiteBox::list_dir`](crate::LiteBox::list_dir).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FileInfo {
+    /// Last path component (`/` for the root).
+    pub name: String,
+    pub file_type: FileType,
+    pub size: u64,
+    /// Permission bits, e.g. `0o644`.
+    pub mode: u32,
+    /// Owner as seen in the box.
+    pub uid: u32,
+    pub gid: u32,
+    pub modified: DateTime<Utc>,
+    /// Target of a symlink.
+    pub symlink_target: Option<String>,
+}
+
+impl From<FileStat> for FileInfo {
+    fn from(stat: FileStat) -> Self {
+        let file_type = match stat.kind() {
+            FileKind::Regular => FileType::File,
+            FileKind::Directory => FileType::Dir,
+            FileKind::Symlink => FileType::Symlink,
+            FileKind::Other => FileType::Other,
+        };
+        Self {
+            name: stat.name,
+            file_type,
+            size: stat.size,
+            mode: stat.mode,
+            uid: stat.uid,
+            gid: stat.gid,
+            modified: DateTime::from_timestamp(stat.mtime, 0).unwrap_or_default(),
+            symlink_target: (file_type == FileType::Symlink).then_some(stat.link_target),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::AsyncReadExt;
+
+    #[tokio::test]
+    async fn test_file_source_open() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("run.sh");
+        std::fs::write(&path, "#!/bin/sh\n").unwrap();
+
+        for source in [FileSource::from(path.as_path()), b"#!/bin/sh\n"[..].into()] {
+            let (mut reader, size) = source.open().await.unwrap();
+            let mut content = String::new();
+            reader.read_to_string(&mut content).await.unwrap();
+            assert_eq!((content.as_str(), size), ("#!/bin/sh\n", 10));
+        }
+
+        assert!(FileSource::from(dir.path()).open().await.is_err());
+    }
+}
@@ -0,0 +1,176 @@
+//! Image HEALTHCHECK support.
+//!
+//! The check from the image config is run in the container at its interval
+//! for as long as the box is used by this runtime, and the result is kept in
+//! the box state as [`HealthStatus`].
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tokio::time::Instant;
+
+use crate::runtime::rt_impl::SharedRuntimeImpl;
+use crate::runtime::types::BoxID;
+
+const DEFAULT_INTERVAL: Duration = Duration::from_secs(30);
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+const DEFAULT_RETRIES: u32 = 3;
+
+/// Health of a box whose image has a HEALTHCHECK.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthStatus {
+    /// No check has passed yet.
+    Starting,
+    /// The last check passed.
+    Healthy,
+    /// The check failed `retries` times in a row.
+    Unhealthy,
+}
+
+impl std::fmt::Display for HealthStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            HealthStatus::Starting => "starting",
+            HealthStatus::Healthy => "healthy",
+            HealthStatus::Unhealthy => "unhealthy",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// HEALTHCHECK of an image, as in the `Healthcheck` of its Docker config.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct HealthCheck {
+    /// Command and arguments, run like an exec.
+    pub command: Vec<String>,
+    pub interval: Duration,
+    pub timeout: Duration,
+    /// Failures during this time after start don't count.
+    pub start_period: Duration,
+    pub retries: u32,
+}
+
+impl HealthCheck {
+    /// Parse the `Healthcheck` object of an image config.
+    ///
+    /// Returns `None` when the check is disabled (`NONE`) or empty.
+    pub fn from_docker(value: &serde_json::Value) -> Option<Self> {
+        let test: Vec<String> = serde_json::from_value(value.get("Test")?.clone()).ok()?;
+        let command = match test.split_first() {
+            Some((kind, args)) if kind == "CMD" && !args.is_empty() => args.to_vec(),
+            Some((kind, [script])) if kind == "CMD-SHELL" => {
+                vec!["/bin/sh".to_string(), "-c".to_string(), script.clone()]
+            }
+            _ => return None,
+        };
+
+        // Durations are in nanoseconds; 0 or missing means the default
+        let duration = |key: &str, default: Duration| {
+            value
+                .get(key)
+                .and_then(|v| v.as_u64())
+                .filter(|&ns| ns > 0)
+                .map_or(default, Duration::from_nanos)
+        };
+        let retries = value
+            .get("Retries")
+            .and_then(|v| v.as_u64())
+            .filter(|&n| n > 0)
+            .map_or(DEFAULT_RETRIES, |n| n.min(u32::MAX as u64) as u32);
+
+        Some(Self {
+            command,
+            interval: duration("Interval", DEFAULT_INTERVAL),
+            timeout: duration("Timeout", DEFAULT_TIMEOUT),
+            start_period: duration("StartPeriod", Duration::ZERO),
+            retries,
+        })
+    }
+}
+
+/// Run `check` on the box at its interval, updating the box's health.
+///
+/// Stops once no handle to the box is left in this runtime, or the box is
+/// stopped (including for `idle_timeout`).
+pub(crate) fn spawn_checker(runtime: &SharedRuntimeImpl, box_id: BoxID, check: HealthCheck) {
+    let runtime = Arc::downgrade(runtime);
+    tokio::spawn(async move {
+        let started = Instant::now();
+        let mut failures = 0;
+        loop {
+            tokio::time::sleep(check.interval).await;
+            let Some(box_impl) = runtime.upgrade().and_then(|rt| rt.cached_box(&box_id)) else {
+                return;
+            };
+            if box_impl.is_shutdown() {
+                return;
+            }
+            let status = box_impl.state.read().status;
+            // Stopped for being idle; bringing it back starts a new checker
+            if status.is_stopped() {
+                return;
+            }
+            if status.is_paused() {
+                continue;
+            }
+
+            let health = if box_impl.probe(&check).await {
+                failures = 0;
+                HealthStatus::Healthy
+            } else if started.elapsed() < check.start_period {
+                continue;
+            } else {
+                failures += 1;
+                if failures < check.retries {
+                    continue;
+                }
+                HealthStatus::Unhealthy
+            };
+            box_impl.set_health(Some(health));
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_from_docker_cmd_shell() {
+        let check = HealthCheck::from_docker(&json!({
+            "Test": ["CMD-SHELL", "curl -f http://localhost/ || exit 1"],
+            "Interval": 5_000_000_000u64,
+            "Retries": 2
+        }))
+        .unwrap();
+        assert_eq!(
+            check.command,
+            ["/bin/sh", "-c", "curl -f http://localhost/ || exit 1"]
+        );
+        assert_eq!(check.interval, Duration::from_secs(5));
+        assert_eq!(check.timeout, DEFAULT_TIMEOUT);
+        assert_eq!(check.start_period, Duration::ZERO);
+        assert_eq!(check.retries, 2);
+    }
+
+    #[test]
+    fn test_from_docker_cmd() {
+        let check = HealthCheck::from_docker(&json!({
+            "Test": ["CMD", "pg_isready", "-U", "postgres"]
+        }))
+        .unwrap();
+        assert_eq!(check.command, ["pg_isready", "-U", "postgres"]);
+        assert_eq!(check.retries, DEFAULT_RETRIES);
+    }
+
+    #[test]
+    fn test_from_docker_disabled() {
+        assert!(HealthCheck::from_docker(&json!({"Test": ["NONE"]})).is_none());
+        assert!(HealthCheck::from_docker(&json!({"Test": []})).is_none());
+        assert!(HealthCheck::from_docker(&json!({"Test": ["CMD"]})).is_none());
+        assert!(HealthCheck::from_docker(&json!({})).is_none());
+    }
+}
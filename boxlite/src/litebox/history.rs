@@ -0,0 +1,273 @@
+//! Exec history recording.
+//!
+//! Sits between the portal's execution channels and the [`Execution`]
+//! handle: output and the exit status are passed through, under the box's
+//! exec buffer policy, while a summary is written to the box's exec history.
+//! The exit status never waits on unread output. Recording continues after
+//! the handle is dropped, so detached commands are recorded too. The SQLite
+//! writes run on the blocking pool, off the async workers.
+//!
+//! [`Execution`]: crate::Execution
+
+use boxlite_shared::errors::BoxliteError;
+use chrono::Utc;
+use sha2::{Digest, Sha256};
+use tokio::sync::mpsc;
+
+use crate::litebox::{BoxManager, ExecRecord};
+use crate::portal::interfaces::ExecComponents;
+use crate::runtime::options::ExecBufferPolicy;
+use crate::runtime::types::BoxID;
+
+/// Record an execution that just started and tap its output and result.
+///
+/// The tapped output follows `policy`, like the portal's own buffers.
+///
+/// Returns components to build the [`Execution`](crate::Execution) from.
+pub(crate) async fn record_exec(
+    manager: BoxManager,
+    box_id: BoxID,
+    command: String,
+    args: Vec<String>,
+    policy: ExecBufferPolicy,
+    components: ExecComponents,
+) -> ExecComponents {
+    let ExecComponents {
+        execution_id,
+        stdin_tx,
+        mut stdout_rx,
+        mut stderr_rx,
+        mut result_rx,
+    } = components;
+
+    let mut record = ExecRecord {
+        id: execution_id.clone(),
+        command,
+        args,
+        started_at: Utc::now(),
+        finished_at: None,
+        exit_code: None,
+        output_bytes: 0,
+        output_preview: String::new(),
+        output_digest: None,
+    };
+    save(&manager, &box_id, &record).await;
+
+    let (stdout_tx, tapped_stdout_rx) = mpsc::channel(stdout_rx.max_capacity());
+    let (stderr_tx, tapped_stderr_rx) = mpsc::channel(stderr_rx.max_capacity());
+    let (result_tx, tapped_result_rx) = mpsc::unbounded_channel();
+
+    // The result is forwarded on its own, so a caller that never reads the
+    // output still gets it from wait()
+    let exit = {
+        let (manager, box_id, mut record) = (manager.clone(), box_id.clone(), record.clone());
+        tokio::spawn(async move {
+            let result = result_rx.recv().await?;
+            // Persist before the caller's wait() returns
+            record.finished_at = Some(Utc::now());
+            record.exit_code = Some(result.exit_code);
+            save(&manager, &box_id, &record).await;
+            let _ = result_tx.send(result);
+            Some(record)
+        })
+    };
+
+    tokio::spawn(async move {
+        let mut preview = Vec::new();
+        let mut digest = Sha256::new();
+        let mut dropped = 0u64;
+        let (mut stdout_open, mut stderr_open) = (true, true);
+
+        while stdout_open || stderr_open {
+            let (frame, tx) = tokio::select! {
+                frame = stdout_rx.recv(), if stdout_open => match frame {
+                    Some(frame) => (frame, &stdout_tx),
+                    None => {
+                        stdout_open = false;
+                        continue;
+                    }
+                },
+                frame = stderr_rx.recv(), if stderr_open => match frame {
+                    Some(frame) => (frame, &stderr_tx),
+                    None => {
+                        stderr_open = false;
+                        continue;
+                    }
+                },
+            };
+            record.output_bytes += frame.data.len() as u64;
+            append_preview(&mut preview, &frame.data);
+            digest.update(&frame.data);
+            match policy {
+                ExecBufferPolicy::Block => {
+                    let _ = tx.send(frame).await;
+                }
+                ExecBufferPolicy::Drop => {
+                    if let Err(mpsc::error::TrySendError::Full(_)) = tx.try_send(frame) {
+                        dropped += 1;
+                    }
+                }
+            }
+        }
+        if dropped > 0 {
+            tracing::warn!(
+                box_id = %box_id,
+                execution_id = %record.id,
+                dropped,
+                "Dropped exec output chunks, the buffer was full"
+            );
+        }
+
+        if let Ok(Some(finished)) = exit.await {
+            record.finished_at = finished.finished_at;
+            record.exit_code = finished.exit_code;
+        }
+        record.output_preview = String::from_utf8_lossy(&preview).into_owned();
+        record.output_digest = Some(format!("sha256:{}", hex::encode(digest.finalize())));
+        save(&manager, &box_id, &record).await;
+    });
+
+    ExecComponents {
+        execution_id,
+        stdin_tx,
+        stdout_rx: tapped_stdout_rx,
+        stderr_rx: tapped_stderr_rx,
+        result_rx: tapped_result_rx,
+    }
+}
+
+fn append_preview(preview: &mut Vec<u8>, data: &[u8]) {
+    let room = ExecRecord::PREVIEW_BYTES.saturating_sub(preview.len());
+    preview.extend_from_slice(&data[..data.len().min(room)]);
+}
+
+async fn save(manager: &BoxManager, box_id: &BoxID, record: &ExecRecord) {
+    let (task_manager, task_box_id, task_record) =
+        (manager.clone(), box_id.clone(), record.clone());
+    let saved =
+        tokio::task::spawn_blocking(move || task_manager.save_exec(&task_box_id, &task_record))
+            .await
+            .unwrap_or_else(|e| {
+                Err(BoxliteError::Internal(format!(
+                    "History task failed: {}",
+                    e
+                )))
+            });
+    // History is best-effort: never fail the command because of it
+    if let Err(e) = saved {
+        tracing::warn!(
+            box_id = %box_id,
+            execution_id = %record.id,
+            error = %e,
+            "Failed to save exec history"
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{BoxStore, Database};
+    use crate::litebox::{ExecResult, OutputFrame};
+    use bytes::Bytes;
+    use std::time::Duration;
+
+    fn components(
+        capacity: usize,
+    ) -> (
+        mpsc::Sender<OutputFrame>,
+        mpsc::UnboundedSender<ExecResult>,
+        ExecComponents,
+    ) {
+        let (stdout_tx, stdout_rx) = mpsc::channel(capacity);
+        let (_, stderr_rx) = mpsc::channel(capacity);
+        let (result_tx, result_rx) = mpsc::unbounded_channel();
+        let components = ExecComponents {
+            execution_id: "exec-1".to_string(),
+            stdin_tx: None,
+            stdout_rx,
+            stderr_rx,
+            result_rx,
+        };
+        (stdout_tx, result_tx, components)
+    }
+
+    async fn tap(
+        dir: &tempfile::TempDir,
+        policy: ExecBufferPolicy,
+        components: ExecComponents,
+    ) -> ExecComponents {
+        let db = Database::open(&dir.path().join("test.db")).unwrap();
+        let manager = BoxManager::new(BoxStore::new(db));
+        record_exec(
+            manager,
+            BoxID::new(),
+            "echo".to_string(),
+            vec![],
+            policy,
+            components,
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_wait_result_forwarded_while_output_unread() {
+        let dir = tempfile::tempdir().unwrap();
+        let (stdout_tx, result_tx, components) = components(1);
+        let mut tapped = tap(&dir, ExecBufferPolicy::Block, components).await;
+
+        // More output than the tapped channel holds, never read
+        for seq in 0..3 {
+            stdout_tx
+                .send(OutputFrame::new(seq, Bytes::from_static(b"x")))
+                .await
+                .unwrap();
+        }
+        result_tx.send(ExecResult { exit_code: 3 }).unwrap();
+
+        let result = tokio::time::timeout(Duration::from_secs(5), tapped.result_rx.recv())
+            .await
+            .expect("result stuck behind unread output")
+            .unwrap();
+        assert_eq!(result.exit_code, 3);
+    }
+
+    #[tokio::test]
+    async fn test_drop_policy_discards_unread_output() {
+        let dir = tempfile::tempdir().unwrap();
+        let (stdout_tx, result_tx, components) = components(1);
+        let mut tapped = tap(&dir, ExecBufferPolicy::Drop, components).await;
+
+        for seq in 0..4 {
+            stdout_tx
+                .send(OutputFrame::new(seq, Bytes::from_static(b"x")))
+                .await
+                .unwrap();
+        }
+        drop(stdout_tx);
+        result_tx.send(ExecResult { exit_code: 0 }).unwrap();
+        tapped.result_rx.recv().await.unwrap();
+
+        // Only what fit was kept, and the stream still ends
+        let mut received = 0;
+        while tokio::time::timeout(Duration::from_secs(5), tapped.stdout_rx.recv())
+            .await
+            .unwrap()
+            .is_some()
+        {
+            received += 1;
+        }
+        assert!(received < 4);
+    }
+
+    #[test]
+    fn test_append_preview_truncates() {
+        let mut preview = Vec::new();
+        append_preview(&mut preview, &[b'a'; ExecRecord::PREVIEW_BYTES - 1]);
+        append_preview(&mut preview, b"bc");
+        append_preview(&mut preview, b"d");
+
+        assert_eq!(preview.len(), ExecRecord::PREVIEW_BYTES);
+        assert_eq!(preview.last(), Some(&b'b'));
+    }
+}
@@ -0,0 +1,151 @@
+//! HTTP client returned by [`LiteBox::http`](crate::LiteBox::http).
+//!
+//! Each request opens a connection through the box's network backend,
+//! straight to the guest port of its URI, so servers in the box are
+//! reachable without publishing their ports.
+
+use std::path::PathBuf;
+
+use boxlite_shared::errors::{BoxliteError, BoxliteResult};
+use bytes::Bytes;
+use http_body_util::{BodyExt, Full};
+use hyper::header::{self, HeaderValue};
+use hyper::{Request, Response, Uri};
+use hyper_util::rt::TokioIo;
+
+use super::tunnel;
+use crate::runtime::types::BoxID;
+
+/// HTTP/1.1 client for servers in a box.
+///
+/// Request URIs name the guest port, e.g. `http://localhost:8080/health`;
+/// their host is only sent as the `Host` header. TLS is not supported.
+#[derive(Clone, Debug)]
+pub struct BoxHttpClient {
+    box_id: BoxID,
+    tunnel_socket: PathBuf,
+}
+
+impl BoxHttpClient {
+    pub(crate) fn new(box_id: BoxID, tunnel_socket: PathBuf) -> Self {
+        Self {
+            box_id,
+            tunnel_socket,
+        }
+    }
+
+    /// GET `uri`, reading the whole response body.
+    pub async fn get(&self, uri: &str) -> BoxliteResult<Response<Bytes>> {
+        let request = Request::get(uri)
+            .body(Bytes::new())
+            .map_err(|e| BoxliteError::Config(format!("Invalid request to {}: {}", uri, e)))?;
+        self.send(request).await
+    }
+
+    /// Send `request`, reading the whole response body.
+    pub async fn send(&self, request: Request<Bytes>) -> BoxliteResult<Response<Bytes>> {
+        let (mut parts, body) = request.into_parts();
+        if parts
+            .uri
+            .scheme_str()
+            .is_some_and(|scheme| scheme != "http")
+        {
+            return Err(BoxliteError::Config(format!(
+                "Unsupported scheme in {}, only http is supported",
+                parts.uri
+            )));
+        }
+        let port = parts.uri.port_u16().unwrap_or(80);
+        if let Some(authority) = parts.uri.authority()
+            && !parts.headers.contains_key(header::HOST)
+        {
+            let host = HeaderValue::from_str(authority.as_str())
+                .map_err(|e| BoxliteError::Config(format!("Invalid host {}: {}", authority, e)))?;
+            parts.headers.insert(header::HOST, host);
+        }
+        // Servers expect the path only
+        parts.uri = parts
+            .uri
+            .path_and_query()
+            .map(|path| Uri::from(path.clone()))
+            .unwrap_or_else(|| Uri::from_static("/"));
+
+        let failed = |e: hyper::Error| {
+            BoxliteError::Network(format!("HTTP request to box {} failed: {}", self.box_id, e))
+        };
+        let stream = tunnel::connect(&self.tunnel_socket, &self.box_id, port).await?;
+        let (mut sender, conn) = hyper::client::conn::http1::handshake(TokioIo::new(stream))
+            .await
+            .map_err(failed)?;
+        tokio::spawn(async move {
+            if let Err(e) = conn.await {
+                tracing::debug!(error = %e, "HTTP connection to box ended");
+            }
+        });
+
+        let response = sender
+            .send_request(Request::from_parts(parts, Full::new(body)))
+            .await
+            .map_err(failed)?;
+        let (parts, body) = response.into_parts();
+        let body = body.collect().await.map_err(failed)?.to_bytes();
+        Ok(Response::from_parts(parts, body))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::litebox::tunnel::TUNNEL_OK;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::UnixListener;
+
+    #[tokio::test]
+    async fn test_send_through_tunnel() {
+        let dir = tempfile::tempdir().unwrap();
+        let socket = dir.path().join("tunnel.sock");
+        let listener = UnixListener::bind(&socket).unwrap();
+        tokio::spawn(async move {
+            loop {
+                let (mut conn, _) = listener.accept().await.unwrap();
+                let mut port = [0u8; 2];
+                conn.read_exact(&mut port).await.unwrap();
+                if u16::from_be_bytes(port) != 8080 {
+                    conn.write_all(&[1]).await.unwrap();
+                    continue;
+                }
+                conn.write_all(&[TUNNEL_OK]).await.unwrap();
+
+                let mut request = Vec::new();
+                let mut buf = [0u8; 1024];
+                while !request.ends_with(b"\r\n\r\n") {
+                    let n = conn.read(&mut buf).await.unwrap();
+                    request.extend_from_slice(&buf[..n]);
+                }
+                let request = String::from_utf8(request).unwrap();
+                assert!(request.starts_with("GET /health?full=1 HTTP/1.1\r\n"));
+                assert!(request.contains("host: localhost:8080\r\n"));
+                conn.write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 2\r\n\r\nok")
+                    .await
+                    .unwrap();
+            }
+        });
+
+        let client = BoxHttpClient::new(BoxID::new(), socket);
+        let response = client
+            .get("http://localhost:8080/health?full=1")
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 200);
+        assert_eq!(response.body().as_ref(), b"ok");
+
+        assert!(matches!(
+            client.get("http://localhost:9090/").await,
+            Err(BoxliteError::Network(_))
+        ));
+        assert!(matches!(
+            client.get("https://localhost/").await,
+            Err(BoxliteError::Config(_))
+        ));
+    }
+}
@@ -0,0 +1,49 @@
+//! Suspending idle boxes, see [`BoxOptions::idle_timeout`](crate::BoxOptions::idle_timeout).
+
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+
+use super::box_impl::LiveState;
+use crate::runtime::rt_impl::SharedRuntimeImpl;
+use crate::runtime::types::BoxID;
+
+/// Longest time between two looks at the box.
+const MAX_POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Suspend the box once it has been idle for `timeout`.
+///
+/// Watches one boot of the VM: stops when the box is stopped (by the user
+/// or for being idle), or no handle to it is left in this runtime.
+/// Restarting the box starts a new watcher.
+pub(crate) fn spawn_watcher(
+    runtime: &SharedRuntimeImpl,
+    box_id: BoxID,
+    live: Weak<LiveState>,
+    timeout: Duration,
+) {
+    let Ok(handle) = tokio::runtime::Handle::try_current() else {
+        return;
+    };
+    let runtime = Arc::downgrade(runtime);
+    let interval = timeout.min(MAX_POLL_INTERVAL);
+    handle.spawn(async move {
+        loop {
+            tokio::time::sleep(interval).await;
+            let Some(box_impl) = runtime.upgrade().and_then(|rt| rt.cached_box(&box_id)) else {
+                return;
+            };
+            if box_impl.is_shutdown() || live.strong_count() == 0 {
+                return;
+            }
+            if box_impl.last_active().elapsed() < timeout {
+                continue;
+            }
+
+            // A paused box is still watched, once resumed, through the
+            // same live state; a stopped one drops it
+            if let Err(e) = box_impl.suspend_if_idle(timeout).await {
+                tracing::warn!(box_id = %box_id, error = %e, "Failed to suspend idle box");
+            }
+        }
+    });
+}
@@ -21,7 +21,7 @@
 //!   4. GuestConnect         (wait for guest ready)
 //!   5. GuestInit            (re-initialize container in new VM)
 //!
-//! Running (reattach):
+//! Running / Paused (reattach):
 //!   1. VmmAttach            (attach to running VM)
 //!   2. GuestConnect         (reconnect to guest)
 //! ```
@@ -36,11 +36,14 @@ pub(crate) use crate::litebox::box_impl::LiveState;
 use crate::litebox::BoxStatus;
 use crate::litebox::config::BoxConfig;
 use crate::metrics::BoxMetricsStorage;
+use crate::pipeline::PipelineTask;
 use crate::pipeline::{
     BoxedTask, ExecutionPlan, PipelineBuilder, PipelineExecutor, PipelineMetrics, Stage,
 };
 use crate::runtime::rt_impl::SharedRuntimeImpl;
-use crate::runtime::types::BoxState;
+use crate::runtime::types::{BoxID, BoxState};
+use crate::telemetry::Telemetry;
+use async_trait::async_trait;
 use boxlite_shared::errors::{BoxliteError, BoxliteResult};
 use std::sync::Arc;
 use tokio::sync::Mutex;
@@ -85,7 +88,7 @@ fn get_execution_plan(status: BoxStatus) -> ExecutionPlan<InitCtx> {
             // GuestInit must run - new VM process has fresh guest daemon
             Stage::sequential(vec![Box::new(GuestInitTask)]),
         ],
-        BoxStatus::Running => vec![
+        BoxStatus::Running | BoxStatus::Paused => vec![
             // Reattach: Attach to existing VM process and connect to guest
             Stage::sequential(vec![Box::new(VmmAttachTask)]),
             Stage::sequential(vec![Box::new(GuestConnectTask)]),
@@ -96,6 +99,62 @@ fn get_execution_plan(status: BoxStatus) -> ExecutionPlan<InitCtx> {
     ExecutionPlan::new(stages)
 }
 
+/// Wrap every task of a plan so it reports start/end to telemetry sinks.
+fn observe_plan(
+    plan: ExecutionPlan<InitCtx>,
+    telemetry: &Telemetry,
+    box_id: &BoxID,
+) -> ExecutionPlan<InitCtx> {
+    let stages = plan
+        .stages()
+        .into_iter()
+        .map(|stage| Stage {
+            tasks: stage
+                .tasks
+                .into_iter()
+                .map(|task| {
+                    Box::new(ObservedTask {
+                        name: task.name().to_string(),
+                        inner: task,
+                        telemetry: telemetry.clone(),
+                        box_id: box_id.clone(),
+                    }) as BoxedTask<InitCtx>
+                })
+                .collect(),
+            execution: stage.execution,
+        })
+        .collect();
+    ExecutionPlan::new(stages)
+}
+
+/// Task decorator that reports to telemetry sinks.
+struct ObservedTask {
+    name: String,
+    inner: BoxedTask<InitCtx>,
+    telemetry: Telemetry,
+    box_id: BoxID,
+}
+
+#[async_trait]
+impl PipelineTask<InitCtx> for ObservedTask {
+    async fn run(self: Box<Self>, ctx: InitCtx) -> BoxliteResult<()> {
+        let start = std::time::Instant::now();
+        self.telemetry.task_start(&self.box_id, &self.name);
+        let result = self.inner.run(ctx).await;
+        self.telemetry.task_end(
+            &self.box_id,
+            &self.name,
+            start.elapsed(),
+            result.as_ref().copied(),
+        );
+        result
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
 fn box_metrics_from_pipeline(pipeline_metrics: &PipelineMetrics) -> BoxMetricsStorage {
     let mut metrics = BoxMetricsStorage::new();
 
@@ -185,9 +244,10 @@ impl BoxBuilder {
 
         let status = state.status;
         let reuse_rootfs = status == BoxStatus::Stopped;
-        let skip_guest_wait = status == BoxStatus::Running;
+        let reattach = matches!(status, BoxStatus::Running | BoxStatus::Paused);
 
-        let ctx = InitPipelineContext::new(config, runtime.clone(), reuse_rootfs, skip_guest_wait);
+        let box_id = config.id.clone();
+        let ctx = InitPipelineContext::new(config, runtime.clone(), reuse_rootfs, reattach);
         let ctx = Arc::new(Mutex::new(ctx));
 
         if status != BoxStatus::Starting {
@@ -195,7 +255,7 @@ impl BoxBuilder {
             ctx_guard.guard.disarm();
         }
 
-        let plan = get_execution_plan(status);
+        let plan = observe_plan(get_execution_plan(status), &runtime.telemetry, &box_id);
         let pipeline = PipelineBuilder::from_plan(plan);
         let pipeline_metrics = PipelineExecutor::execute(pipeline, Arc::clone(&ctx)).await?;
 
@@ -219,8 +279,8 @@ impl BoxBuilder {
             .take()
             .ok_or_else(|| BoxliteError::Internal("guest_connect task must run first".into()))?;
 
-        // Get disks from context (for Running, create disk reference directly)
-        let (container_disk, guest_disk) = if status == BoxStatus::Running {
+        // Get disks from context (for reattach, create disk reference directly)
+        let (container_disk, guest_disk) = if reattach {
             // Reattach: create disk reference to existing qcow2
             use crate::disk::DiskFormat;
             let disk = crate::disk::Disk::new(
@@ -248,6 +308,8 @@ impl BoxBuilder {
             metrics,
             container_disk,
             guest_disk,
+            std::mem::take(&mut ctx.port_mappings),
+            std::mem::take(&mut ctx.volume_locks),
             #[cfg(target_os = "linux")]
             bind_mount,
         ))
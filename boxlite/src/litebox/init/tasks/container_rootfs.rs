@@ -5,6 +5,19 @@
 //! - Overlayfs: Extracts layers for guest-side overlayfs (flexible)
 //!
 //! For restart (reuse_rootfs=true), opens existing COW disk instead of creating new.
+//!
+//! ## Restart integrity check
+//!
+//! Before reusing the COW disk, its qcow2 structure is verified with
+//! [`Qcow2Helper::check_consistency`]. If the check fails, the box is not
+//! booted into filesystem errors. Instead:
+//! 1. The damaged disk is renamed to `root.qcow2.corrupt-<unix-seconds>` in the
+//!    box directory (kept for manual inspection/recovery, never deleted by us).
+//! 2. A fresh COW disk is created from the cached base image, exactly as for a
+//!    new box. Writes made since the box was created are only in the preserved copy.
+//!
+//! When `BoxOptions::fsck_on_restart` is set, the guest additionally runs
+//! `e2fsck -p` on the container rootfs before mounting it.
 
 use super::{InitCtx, log_task_error, task_start};
 use crate::disk::{BackingFormat, Disk, DiskFormat, Qcow2Helper, create_ext4_from_dir};
@@ -16,6 +29,7 @@ use crate::runtime::options::RootfsSpec;
 use crate::runtime::rt_impl::SharedRuntimeImpl;
 use async_trait::async_trait;
 use boxlite_shared::errors::{BoxliteError, BoxliteResult};
+use std::path::{Path, PathBuf};
 
 pub struct ContainerRootfsTask;
 
@@ -33,7 +47,7 @@ impl PipelineTask<InitCtx> for ContainerRootfsTask {
                 .ok_or_else(|| BoxliteError::Internal("filesystem task must run first".into()))?;
             (
                 ctx.config.options.rootfs.clone(),
-                ctx.config.options.env.clone(),
+                ctx.config.options.container_env(),
                 ctx.runtime.clone(),
                 layout,
                 ctx.reuse_rootfs,
@@ -75,20 +89,13 @@ async fn run_container_rootfs(
 ) -> BoxliteResult<(ContainerImageConfig, Disk)> {
     let disk_path = layout.disk_path();
 
-    // For restart, reuse existing COW disk
-    if reuse_rootfs {
+    // For restart, reuse existing COW disk (unless it fails the integrity check)
+    if reuse_rootfs && verify_reusable_disk(&disk_path)? {
         tracing::info!(
             disk_path = %disk_path.display(),
             "Restart mode: reusing existing container rootfs disk"
         );
 
-        if !disk_path.exists() {
-            return Err(BoxliteError::Storage(format!(
-                "Cannot restart: container rootfs disk not found at {}",
-                disk_path.display()
-            )));
-        }
-
         let disk = Disk::new(disk_path.clone(), DiskFormat::Qcow2, true);
 
         let image_ref = match rootfs_spec {
@@ -143,6 +150,59 @@ async fn run_container_rootfs(
     Ok((container_image_config, disk))
 }
 
+/// Check whether an existing COW disk can be reused for restart.
+///
+/// Returns `Ok(false)` if the disk failed the integrity check and was moved
+/// aside, in which case the caller rebuilds it from the base image.
+fn verify_reusable_disk(disk_path: &Path) -> BoxliteResult<bool> {
+    if !disk_path.exists() {
+        return Err(BoxliteError::Storage(format!(
+            "Cannot restart: container rootfs disk not found at {}",
+            disk_path.display()
+        )));
+    }
+
+    match Qcow2Helper::check_consistency(disk_path) {
+        Ok(()) => Ok(true),
+        Err(e) => {
+            let preserved = quarantine_corrupted_disk(disk_path)?;
+            tracing::warn!(
+                disk_path = %disk_path.display(),
+                preserved = %preserved.display(),
+                error = %e,
+                "Container rootfs disk failed integrity check, rebuilding from base image"
+            );
+            Ok(false)
+        }
+    }
+}
+
+/// Move a disk that failed the integrity check out of the way.
+///
+/// The disk is renamed (not deleted) so its contents can still be recovered.
+/// Returns the path of the preserved copy.
+fn quarantine_corrupted_disk(disk_path: &Path) -> BoxliteResult<PathBuf> {
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+
+    let mut preserved = disk_path.as_os_str().to_owned();
+    preserved.push(format!(".corrupt-{}", timestamp));
+    let preserved = PathBuf::from(preserved);
+
+    std::fs::rename(disk_path, &preserved).map_err(|e| {
+        BoxliteError::Storage(format!(
+            "Failed to preserve corrupted disk {} as {}: {}",
+            disk_path.display(),
+            preserved.display(),
+            e
+        ))
+    })?;
+
+    Ok(preserved)
+}
+
 /// Create COW disk from base rootfs.
 ///
 /// # Arguments
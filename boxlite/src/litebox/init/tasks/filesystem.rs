@@ -1,11 +1,12 @@
 //! Task: Filesystem setup.
 //!
-//! Creates box directory structure and optionally sets up the mounts/ → shared/ binding.
+//! Creates box directory structure, clears sockets left behind by a crashed
+//! run and optionally sets up the mounts/ → shared/ binding.
 
 use super::{InitCtx, log_task_error, task_start};
 use crate::pipeline::PipelineTask;
 use async_trait::async_trait;
-use boxlite_shared::errors::BoxliteResult;
+use boxlite_shared::errors::{BoxliteError, BoxliteResult};
 
 pub struct FilesystemTask;
 
@@ -29,6 +30,23 @@ impl PipelineTask<InitCtx> for FilesystemTask {
             .prepare()
             .inspect_err(|e| log_task_error(&box_id, task_name, e))?;
 
+        // The previous shim must really be gone before its sockets are removed
+        let owner_pid = runtime
+            .box_manager
+            .box_by_id(&box_id)?
+            .and_then(|(_, state)| state.pid);
+        if let Some(pid) = owner_pid
+            && crate::util::is_same_process(pid, box_id.as_str())
+        {
+            let err =
+                BoxliteError::InvalidState(format!("Box is still running in process {}", pid));
+            log_task_error(&box_id, task_name, &err);
+            return Err(err);
+        }
+        layout
+            .remove_stale_sockets()
+            .inspect_err(|e| log_task_error(&box_id, task_name, e))?;
+
         #[cfg(target_os = "linux")]
         let bind_mount = if isolate_mounts {
             use crate::fs::{BindMountConfig, create_bind_mount};
@@ -5,13 +5,23 @@
 
 use super::{InitCtx, log_task_error, task_start};
 use crate::images::ContainerImageConfig;
+use crate::litebox::config::BoxConfig;
+use crate::net::constants::{GATEWAY_IPV6, GUEST_IPV6, IPV6_PREFIX_LEN};
+use crate::net::host::{self, HostAttachment};
 use crate::pipeline::PipelineTask;
 use crate::portal::GuestSession;
-use crate::portal::interfaces::{ContainerRootfsInitConfig, GuestInitConfig, NetworkInitConfig};
-use crate::runtime::types::ContainerID;
+use crate::portal::interfaces::{
+    ContainerNetworkConfig, ContainerRootfsInitConfig, GuestInitConfig, NetworkInitConfig,
+};
+use crate::runtime::options::{
+    BoxOptions, NetworkSpec, ReversePortSpec, SocketSpec, VolumeSpec, is_valid_hostname,
+};
+use crate::runtime::rt_impl::SharedRuntimeImpl;
+use crate::runtime::types::{BoxID, ContainerID};
 use crate::volumes::{ContainerMount, GuestVolumeManager};
 use async_trait::async_trait;
 use boxlite_shared::errors::{BoxliteError, BoxliteResult};
+use std::net::IpAddr;
 
 pub struct GuestInitTask;
 
@@ -28,6 +38,13 @@ impl PipelineTask<InitCtx> for GuestInitTask {
             volume_mgr,
             rootfs_init,
             container_mounts,
+            tmpfs,
+            sockets,
+            reverse_ports,
+            tty,
+            network,
+            guest_network,
+            networks,
         ) =
             {
                 let mut ctx = ctx.lock().await;
@@ -48,6 +65,10 @@ impl PipelineTask<InitCtx> for GuestInitTask {
                 let container_mounts = ctx.container_mounts.take().ok_or_else(|| {
                     BoxliteError::Internal("vmm_spawn task must run first".into())
                 })?;
+                let (networks, peers) =
+                    inter_box_networks(&ctx.config.id, &ctx.config.options, &ctx.runtime)?;
+                let mut extra_hosts = ctx.config.options.host_entries()?;
+                extra_hosts.extend(peers);
                 (
                     guest_session,
                     container_image_config,
@@ -55,6 +76,28 @@ impl PipelineTask<InitCtx> for GuestInitTask {
                     volume_mgr,
                     rootfs_init,
                     container_mounts,
+                    ctx.config
+                        .options
+                        .volumes
+                        .iter()
+                        .filter(|v| matches!(v, VolumeSpec::Tmpfs { .. }))
+                        .cloned()
+                        .collect(),
+                    ctx.config.options.sockets.clone(),
+                    ctx.config.options.reverse_ports.clone(),
+                    ctx.config.options.tty,
+                    ContainerNetworkConfig {
+                        hostname: hostname(&ctx.config),
+                        dns_servers: dns_servers(&ctx.config.options, ctx.host_network.is_some()),
+                        dns_search: ctx.config.options.dns_search.clone(),
+                        extra_hosts,
+                    },
+                    guest_network(
+                        &ctx.config.options,
+                        ctx.network_mtu,
+                        ctx.host_network.as_ref(),
+                    ),
+                    networks,
                 )
             };
 
@@ -65,6 +108,13 @@ impl PipelineTask<InitCtx> for GuestInitTask {
             &volume_mgr,
             &rootfs_init,
             &container_mounts,
+            &tmpfs,
+            &sockets,
+            &reverse_ports,
+            tty,
+            &network,
+            guest_network,
+            networks,
         )
         .await
         .inspect_err(|e| log_task_error(&box_id, task_name, e))?;
@@ -83,7 +133,98 @@ impl PipelineTask<InitCtx> for GuestInitTask {
     }
 }
 
+/// Hostname of the box: `options.hostname`, else its name if that is a
+/// valid hostname, else its short ID.
+fn hostname(config: &BoxConfig) -> String {
+    if let Some(hostname) = &config.options.hostname {
+        return hostname.clone();
+    }
+    match &config.name {
+        Some(name) if is_valid_hostname(name) => name.clone(),
+        _ => config.id.short().to_ascii_lowercase(),
+    }
+}
+
+/// Guest interface setup, with the MTU of the network backend; None
+/// (loopback only) for boxes without a network.
+fn guest_network(
+    options: &BoxOptions,
+    mtu: Option<u16>,
+    host_network: Option<&HostAttachment>,
+) -> Option<NetworkInitConfig> {
+    if options.network == NetworkSpec::None {
+        return None;
+    }
+    let (ip, gateway) = match host_network {
+        Some(attachment) => (
+            format!("{}/{}", attachment.ip, host::PREFIX_LEN),
+            host::GATEWAY.to_string(),
+        ),
+        None => ("192.168.127.2/24".to_string(), "192.168.127.1".to_string()),
+    };
+    Some(NetworkInitConfig {
+        interface: "eth0".to_string(),
+        ip: Some(ip),
+        gateway: Some(gateway),
+        ipv6: options
+            .ipv6
+            .then(|| format!("{}/{}", GUEST_IPV6, IPV6_PREFIX_LEN)),
+        gateway_ipv6: options.ipv6.then(|| GATEWAY_IPV6.to_string()),
+        mtu: mtu.map(u32::from),
+    })
+}
+
+/// Nameservers of the container: `options.dns`, else the network backend
+/// on the gateway, which the host network mode has not, so the host's.
+fn dns_servers(options: &BoxOptions, host_network: bool) -> Vec<String> {
+    if options.dns.is_empty() && host_network {
+        return host::nameservers();
+    }
+    options.dns.clone()
+}
+
+/// Interfaces on the box's inter-box networks, and the names of the other
+/// boxes there for /etc/hosts.
+fn inter_box_networks(
+    box_id: &BoxID,
+    options: &BoxOptions,
+    runtime: &SharedRuntimeImpl,
+) -> BoxliteResult<(Vec<NetworkInitConfig>, Vec<(String, IpAddr)>)> {
+    // Numbered after the main interface, in the order of the engine's NICs
+    let first = if options.network == NetworkSpec::None {
+        0
+    } else {
+        1
+    };
+
+    let mut interfaces = Vec::new();
+    let mut peers = Vec::new();
+    for (i, network) in options.networks.iter().enumerate() {
+        let mut own_ip = None;
+        for (id, name, ip) in runtime.network_store.endpoints(network)? {
+            if id == *box_id {
+                own_ip = Some(ip);
+            } else if let Some(name) = name {
+                peers.push((name, IpAddr::V4(ip)));
+            }
+        }
+        let ip = own_ip.ok_or_else(|| {
+            BoxliteError::Internal(format!("box has no address on network '{}'", network))
+        })?;
+        interfaces.push(NetworkInitConfig {
+            interface: format!("eth{}", first + i),
+            ip: Some(format!("{}/24", ip)),
+            gateway: None,
+            ipv6: None,
+            gateway_ipv6: None,
+            mtu: None,
+        });
+    }
+    Ok((interfaces, peers))
+}
+
 /// Initialize guest and start container.
+#[allow(clippy::too_many_arguments)]
 async fn run_guest_init(
     guest_session: GuestSession,
     container_image_config: &ContainerImageConfig,
@@ -91,6 +232,13 @@ async fn run_guest_init(
     volume_mgr: &GuestVolumeManager,
     rootfs_init: &ContainerRootfsInitConfig,
     container_mounts: &[ContainerMount],
+    tmpfs: &[VolumeSpec],
+    sockets: &[SocketSpec],
+    reverse_ports: &[ReversePortSpec],
+    tty: bool,
+    network: &ContainerNetworkConfig,
+    guest_network: Option<NetworkInitConfig>,
+    networks: Vec<NetworkInitConfig>,
 ) -> BoxliteResult<()> {
     let container_id_str = container_id.as_str();
 
@@ -99,11 +247,8 @@ async fn run_guest_init(
 
     let guest_init_config = GuestInitConfig {
         volumes: guest_volumes,
-        network: Some(NetworkInitConfig {
-            interface: "eth0".to_string(),
-            ip: Some("192.168.127.2/24".to_string()),
-            gateway: Some("192.168.127.1".to_string()),
-        }),
+        network: guest_network,
+        networks,
     };
 
     // Step 1: Guest Init (volumes + network)
@@ -112,7 +257,8 @@ async fn run_guest_init(
     guest_interface.init(guest_init_config).await?;
     tracing::info!("Guest initialized successfully");
 
-    // Step 2: Container Init (rootfs + container image config + user volume mounts)
+    // Step 2: Container Init (rootfs + container image config + user volume and tmpfs mounts,
+    // host sockets, reverse ports)
     tracing::info!("Sending container configuration to guest");
     let mut container_interface = guest_session.container().await?;
     let returned_id = container_interface
@@ -121,6 +267,11 @@ async fn run_guest_init(
             container_image_config.clone(),
             rootfs_init.clone(),
             container_mounts.to_vec(),
+            tmpfs.to_vec(),
+            sockets.to_vec(),
+            reverse_ports.to_vec(),
+            tty,
+            network.clone(),
         )
         .await?;
     tracing::info!(container_id = %returned_id, "Container initialized");
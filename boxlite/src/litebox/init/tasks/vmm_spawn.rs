@@ -8,13 +8,17 @@ use crate::disk::DiskFormat;
 use crate::images::ContainerImageConfig;
 use crate::litebox::init::types::resolve_user_volumes;
 use crate::net::NetworkBackendConfig;
+use crate::net::host;
+use crate::net::reverse::{self, ReversePort};
+use crate::net::switch::{self, NetworkAttachment};
 use crate::pipeline::PipelineTask;
 use crate::runtime::constants::{guest_paths, mount_tags};
 use crate::runtime::guest_rootfs::{GuestRootfs, Strategy};
 use crate::runtime::layout::BoxFilesystemLayout;
-use crate::runtime::options::BoxOptions;
+use crate::runtime::options::{BoxOptions, NetworkMode, NetworkSpec};
 use crate::runtime::rt_impl::SharedRuntimeImpl;
 use crate::runtime::types::{BoxID, BoxStatus, ContainerID};
+use crate::runtime::volumes::VolumeLock;
 use crate::util::find_binary;
 use crate::vmm::controller::{ShimController, VmmController, VmmHandler};
 use crate::vmm::{Entrypoint, InstanceSpec, VmmKind};
@@ -22,7 +26,6 @@ use crate::volumes::{ContainerMount, ContainerVolumeManager, GuestVolumeManager}
 use async_trait::async_trait;
 use boxlite_shared::Transport;
 use boxlite_shared::errors::{BoxliteError, BoxliteResult};
-use std::collections::{HashMap, HashSet};
 use std::path::Path;
 
 pub struct VmmSpawnTask;
@@ -43,6 +46,7 @@ impl PipelineTask<InitCtx> for VmmSpawnTask {
             home_dir,
             container_id,
             runtime,
+            reuse_rootfs,
         ) = {
             let ctx = ctx.lock().await;
             let layout = ctx
@@ -69,27 +73,47 @@ impl PipelineTask<InitCtx> for VmmSpawnTask {
                 ctx.config.box_home.clone(),
                 ctx.config.container.id.clone(),
                 ctx.runtime.clone(),
+                ctx.reuse_rootfs,
             )
         };
 
         // Build config and get outputs
-        let (instance_spec, volume_mgr, rootfs_init, container_mounts) = build_config(
-            &options,
-            &layout,
-            &container_image_config,
-            &container_disk_path,
-            guest_disk_path.as_deref(),
-            &home_dir,
-            &container_id,
-            &runtime,
-        )
-        .await
-        .inspect_err(|e| log_task_error(&box_id, task_name, e))?;
-
-        // Spawn VM
-        let handler = spawn_vm(&box_id, &instance_spec)
+        let (instance_spec, volume_mgr, rootfs_init, container_mounts, volume_locks) =
+            build_config(
+                &box_id,
+                &options,
+                &layout,
+                &container_image_config,
+                &container_disk_path,
+                guest_disk_path.as_deref(),
+                &home_dir,
+                &container_id,
+                &runtime,
+                reuse_rootfs,
+            )
             .await
             .inspect_err(|e| log_task_error(&box_id, task_name, e))?;
+        let port_mappings = instance_spec
+            .network_config
+            .as_ref()
+            .map(|config| config.port_mappings.clone())
+            .unwrap_or_default();
+        let network_mtu = instance_spec
+            .network_config
+            .as_ref()
+            .map(|config| config.tuning.mtu);
+        let host_network = instance_spec
+            .network_config
+            .as_ref()
+            .and_then(|config| config.host.clone());
+
+        // Spawn VM
+        let handler = spawn_vm(&box_id, &instance_spec).await.inspect_err(|e| {
+            if host_network.is_some() {
+                host::detach(&box_id);
+            }
+            log_task_error(&box_id, task_name, e)
+        })?;
 
         // Update PID and status in database
         let pid = handler.pid();
@@ -107,6 +131,13 @@ impl PipelineTask<InitCtx> for VmmSpawnTask {
         ctx.volume_mgr = Some(volume_mgr);
         ctx.rootfs_init = Some(rootfs_init);
         ctx.container_mounts = Some(container_mounts);
+        ctx.volume_locks = volume_locks;
+        ctx.port_mappings = port_mappings;
+        ctx.network_mtu = network_mtu;
+        if host_network.is_some() {
+            ctx.guard.set_host_network();
+        }
+        ctx.host_network = host_network;
         Ok(())
     }
 
@@ -118,6 +149,7 @@ impl PipelineTask<InitCtx> for VmmSpawnTask {
 /// Build VMM config from prepared rootfs outputs.
 #[allow(clippy::too_many_arguments)]
 async fn build_config(
+    box_id: &BoxID,
     options: &BoxOptions,
     layout: &BoxFilesystemLayout,
     container_image_config: &ContainerImageConfig,
@@ -126,17 +158,19 @@ async fn build_config(
     home_dir: &Path,
     container_id: &ContainerID,
     runtime: &SharedRuntimeImpl,
+    reuse_rootfs: bool,
 ) -> BoxliteResult<(
     InstanceSpec,
     GuestVolumeManager,
     crate::portal::interfaces::ContainerRootfsInitConfig,
     Vec<ContainerMount>,
+    Vec<VolumeLock>,
 )> {
     // Transport setup
     let transport = Transport::unix(layout.socket_path());
     let ready_transport = Transport::unix(layout.ready_socket_path());
 
-    let user_volumes = resolve_user_volumes(&options.volumes)?;
+    let (user_volumes, volume_locks) = resolve_user_volumes(&options.volumes, runtime, box_id)?;
 
     // Prepare container directories (image/, rw/, rootfs/)
     let container_layout = layout.shared_layout().container(container_id.as_str());
@@ -169,11 +203,34 @@ async fn build_config(
         device: rootfs_device,
         need_format: false, // COW child uses pre-formatted base
         need_resize,        // Expand ext4 if disk_size_gb was specified
+        need_fsck: reuse_rootfs && options.fsck_on_restart, // Repair before reuse
+        quotas: options.disk_quotas.clone(),
     };
 
     // Add user volumes via ContainerVolumeManager
     let mut container_mgr = ContainerVolumeManager::new(&mut volume_mgr);
     for vol in &user_volumes {
+        if vol.disk {
+            container_mgr.add_disk_volume(
+                container_id.as_str(),
+                &vol.tag,
+                &vol.host_path,
+                &vol.guest_path,
+                vol.read_only,
+            );
+            continue;
+        }
+        if let Some(file) = &vol.file {
+            container_mgr.add_file_volume(
+                container_id.as_str(),
+                &vol.tag,
+                vol.host_path.clone(),
+                file,
+                &vol.guest_path,
+                vol.read_only,
+            );
+            continue;
+        }
         container_mgr.add_volume(
             container_id.as_str(),
             &vol.tag,
@@ -202,7 +259,38 @@ async fn build_config(
         build_guest_entrypoint(&transport, &ready_transport, &guest_rootfs, options)?;
 
     // Network configuration
-    let network_config = build_network_config(container_image_config, options);
+    let network_attachments = build_network_attachments(box_id, options, runtime)?;
+    let network_config = build_network_config(container_image_config, options)?.map(|config| {
+        config
+            .with_hosts_files(
+                network_attachments
+                    .iter()
+                    .map(|attachment| switch::hosts_path(&attachment.socket_dir))
+                    .collect(),
+            )
+            .with_tunnel_socket(layout.tunnel_socket_path())
+            .with_tuning(runtime.options.read().unwrap().network_tuning)
+    });
+    let reverse_ports = options
+        .reverse_ports
+        .iter()
+        .map(|spec| {
+            Ok(ReversePort {
+                target: spec.target()?,
+                socket_path: reverse::socket_path(&layout.sockets_dir(), spec.guest_port),
+            })
+        })
+        .collect::<BoxliteResult<Vec<_>>>()?;
+
+    // Host network mode: the box is put on the bridge here, the shim only
+    // opens its TAP device
+    let network_config = match network_config {
+        Some(config) if options.network_mode == NetworkMode::Host => {
+            let attachment = host::attach(box_id, &config.port_mappings, config.tuning.mtu)?;
+            Some(config.with_host(attachment))
+        }
+        config => config,
+    };
 
     // Assemble VMM instance spec
     let instance_spec = InstanceSpec {
@@ -216,13 +304,29 @@ async fn build_config(
         guest_rootfs,
         network_config,
         network_backend_endpoint: None,
+        network_disabled: options.network == NetworkSpec::None,
+        network_attachments,
+        host_sockets: options
+            .sockets
+            .iter()
+            .map(|socket| socket.host_path.clone().into())
+            .collect(),
+        reverse_ports,
+        control_socket: Some(layout.control_socket_path()),
         home_dir: home_dir.to_path_buf(),
         console_output: None,
         detach: options.detach,
         parent_pid: std::process::id(),
+        memory_policy: runtime.options.read().unwrap().memory_policy.clone(),
     };
 
-    Ok((instance_spec, volume_mgr, rootfs_init, container_mounts))
+    Ok((
+        instance_spec,
+        volume_mgr,
+        rootfs_init,
+        container_mounts,
+        volume_locks,
+    ))
 }
 
 /// Configure guest rootfs with device path from volume manager.
@@ -293,42 +397,79 @@ fn build_guest_entrypoint(
 }
 
 /// Build network configuration from container image config and options.
+///
+/// None for boxes without a network.
 fn build_network_config(
     container_image_config: &crate::images::ContainerImageConfig,
     options: &crate::runtime::options::BoxOptions,
-) -> Option<NetworkBackendConfig> {
-    let mut port_map: HashMap<u16, u16> = HashMap::new();
-
-    // Step 1: Collect guest ports that user wants to customize
-    let user_guest_ports: HashSet<u16> = options.ports.iter().map(|p| p.guest_port).collect();
-
-    // Step 2: Image exposed ports (only add default 1:1 mapping if user didn't override)
-    for port in container_image_config.tcp_ports() {
-        if !user_guest_ports.contains(&port) {
-            port_map.insert(port, port);
-        }
-    }
-
-    // Step 3: User-provided mappings (always applied)
-    for port in &options.ports {
-        let host_port = port.host_port.unwrap_or(port.guest_port);
-        port_map.insert(host_port, port.guest_port);
+) -> BoxliteResult<Option<NetworkBackendConfig>> {
+    if options.network == NetworkSpec::None {
+        tracing::info!("Network disabled, not creating a network backend");
+        return Ok(None);
     }
 
-    let final_mappings: Vec<(u16, u16)> = port_map.into_iter().collect();
+    // The runtime listens on the ports of a lazily started box itself
+    let final_mappings = if options.lazy_start {
+        Vec::new()
+    } else {
+        crate::net::assign_host_ports(
+            options.port_mappings(&container_image_config.tcp_ports()),
+            options.auto_assign_on_conflict,
+        )?
+    };
 
     tracing::info!(
-        "Port mappings: {} (image: {}, user: {}, overridden: {})",
+        "Port mappings: {} (image: {}, user: {})",
         final_mappings.len(),
         container_image_config.exposed_ports.len(),
-        options.ports.len(),
-        user_guest_ports
-            .intersection(&container_image_config.tcp_ports().into_iter().collect())
-            .count()
+        options.ports.len()
     );
 
-    // Always return Some - gvproxy provides virtio-net (eth0) even without port mappings
-    Some(NetworkBackendConfig::new(final_mappings))
+    // gvproxy provides virtio-net (eth0) even without port mappings
+    Ok(Some(
+        NetworkBackendConfig::new(final_mappings)
+            .with_policy(options.network_policy.clone())
+            .with_rate_limit(options.network_rate_limit)
+            .with_ipv6(options.ipv6)
+            .with_backend(options.network_backend)
+            .with_dns_cache(options.dns_cache.clone()),
+    ))
+}
+
+/// Interfaces on the box's inter-box networks, assigning its addresses on
+/// first start.
+///
+/// The networks' hosts files are rewritten along the way, so that the other
+/// boxes resolve this one by name.
+fn build_network_attachments(
+    box_id: &BoxID,
+    options: &BoxOptions,
+    runtime: &SharedRuntimeImpl,
+) -> BoxliteResult<Vec<NetworkAttachment>> {
+    options
+        .networks
+        .iter()
+        .map(|network| {
+            let ip = runtime.network_store.attach(network, box_id)?;
+            tracing::info!(network = %network, ip = %ip, "Attaching to network");
+            let socket_dir = runtime.layout.network_dir(network);
+
+            // Unnamed boxes go by their ID
+            let hosts: Vec<(String, std::net::Ipv4Addr)> = runtime
+                .network_store
+                .endpoints(network)?
+                .into_iter()
+                .map(|(id, name, ip)| (name.unwrap_or_else(|| id.to_string()), ip))
+                .collect();
+            switch::write_hosts(&socket_dir, &hosts)?;
+
+            Ok(NetworkAttachment {
+                network: network.clone(),
+                socket_dir,
+                mac_address: NetworkAttachment::mac_for(ip),
+            })
+        })
+        .collect()
 }
 
 /// Spawn VM subprocess and return handler.
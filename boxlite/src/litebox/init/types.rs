@@ -6,15 +6,18 @@ use crate::disk::Disk;
 use crate::fs::BindMountHandle;
 use crate::images::ContainerImageConfig;
 use crate::litebox::config::BoxConfig;
+use crate::net::host::HostAttachment;
 use crate::portal::GuestSession;
 use crate::portal::interfaces::ContainerRootfsInitConfig;
 use crate::runtime::layout::BoxFilesystemLayout;
 use crate::runtime::options::VolumeSpec;
-use crate::runtime::rt_impl::SharedRuntimeImpl;
+use crate::runtime::rt_impl::{RuntimeImpl, SharedRuntimeImpl};
+use crate::runtime::types::{PortMapping, VolumeKind};
+use crate::runtime::volumes::VolumeLock;
 use crate::vmm::controller::VmmHandler;
 use crate::volumes::{ContainerMount, GuestVolumeManager};
 use boxlite_shared::errors::{BoxliteError, BoxliteResult};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::atomic::Ordering;
 
 /// Switch between merged and overlayfs rootfs strategies.
@@ -37,54 +40,155 @@ pub struct ResolvedVolume {
     pub host_path: PathBuf,
     pub guest_path: String,
     pub read_only: bool,
+    /// `host_path` is the ext4 disk image of a named volume, attached as a
+    /// block device instead of shared.
+    pub disk: bool,
+    /// Name of the host file to mount, in `host_path` (a directory holding
+    /// only that file).
+    pub file: Option<String>,
 }
 
-pub fn resolve_user_volumes(volumes: &[VolumeSpec]) -> BoxliteResult<Vec<ResolvedVolume>> {
+/// Resolve the bind volumes of a box, with the locks of its disk volumes
+/// to hold until the VM stops.
+pub fn resolve_user_volumes(
+    volumes: &[VolumeSpec],
+    runtime: &RuntimeImpl,
+    box_id: &BoxID,
+) -> BoxliteResult<(Vec<ResolvedVolume>, Vec<VolumeLock>)> {
     let mut resolved = Vec::with_capacity(volumes.len());
+    let mut locks = Vec::new();
 
     for (i, vol) in volumes.iter().enumerate() {
-        let host_path = PathBuf::from(&vol.host_path);
-
-        if !host_path.exists() {
-            return Err(BoxliteError::Config(format!(
-                "Volume host path does not exist: {}",
-                vol.host_path
-            )));
-        }
-
-        let resolved_path = host_path.canonicalize().map_err(|e| {
-            BoxliteError::Config(format!(
-                "Failed to resolve volume path '{}': {}",
-                vol.host_path, e
-            ))
-        })?;
+        // tmpfs volumes are mounted by the guest, with nothing to share
+        let VolumeSpec::Bind {
+            host_path,
+            guest_path,
+            read_only,
+        } = vol
+        else {
+            continue;
+        };
+        let tag = format!("uservol{}", i);
 
-        if !resolved_path.is_dir() {
-            return Err(BoxliteError::Config(format!(
-                "Volume host path is not a directory: {}",
-                vol.host_path
-            )));
+        if let Some(name) = vol.volume_name() {
+            let (volume, lock) = runtime.resolve_volume(name, box_id)?;
+            locks.extend(lock);
+            tracing::debug!(
+                tag = %tag,
+                volume = %name,
+                guest_path = %guest_path,
+                read_only = *read_only,
+                "Resolved named volume"
+            );
+            resolved.push(ResolvedVolume {
+                tag,
+                disk: matches!(volume.kind, VolumeKind::Disk { .. }),
+                host_path: volume.path,
+                guest_path: guest_path.clone(),
+                read_only: *read_only,
+                file: None,
+            });
+            continue;
         }
 
-        let tag = format!("uservol{}", i);
+        let staging = runtime
+            .layout
+            .box_layout(box_id.as_str(), false)?
+            .volume_files_dir()
+            .join(&tag);
+        let (share_path, file) = share_host_path(host_path, &staging, *read_only)?;
 
         tracing::debug!(
             tag = %tag,
-            host_path = %resolved_path.display(),
-            guest_path = %vol.guest_path,
-            read_only = vol.read_only,
+            host_path = %share_path.display(),
+            file = ?file,
+            guest_path = %guest_path,
+            read_only = *read_only,
             "Resolved user volume"
         );
 
         resolved.push(ResolvedVolume {
             tag,
-            host_path: resolved_path,
-            guest_path: vol.guest_path.clone(),
-            read_only: vol.read_only,
+            host_path: share_path,
+            guest_path: guest_path.clone(),
+            read_only: *read_only,
+            disk: false,
+            file,
         });
     }
 
-    Ok(resolved)
+    Ok((resolved, locks))
+}
+
+/// The directory to share for a bind volume's `host_path`, and the file to
+/// mount from it if the path is a file.
+///
+/// A file is shared from `staging`, a directory holding only that file, so
+/// the guest sees nothing else of its host directory. It is hard-linked, so
+/// writes reach the host file; a read-only file on another filesystem is
+/// copied instead.
+fn share_host_path(
+    host_path: &str,
+    staging: &Path,
+    read_only: bool,
+) -> BoxliteResult<(PathBuf, Option<String>)> {
+    let path = PathBuf::from(host_path);
+
+    if !path.exists() {
+        return Err(BoxliteError::Config(format!(
+            "Volume host path does not exist: {}",
+            host_path
+        )));
+    }
+
+    let path = path.canonicalize().map_err(|e| {
+        BoxliteError::Config(format!(
+            "Failed to resolve volume path '{}': {}",
+            host_path, e
+        ))
+    })?;
+
+    if path.is_dir() {
+        return Ok((path, None));
+    }
+    if !path.is_file() {
+        return Err(BoxliteError::Config(format!(
+            "Volume host path is not a directory or regular file: {}",
+            host_path
+        )));
+    }
+
+    let Some(name) = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+    else {
+        return Err(BoxliteError::Config(format!(
+            "Volume host path has no file name: {}",
+            host_path
+        )));
+    };
+    // Left from an earlier boot, possibly of another file
+    if staging.exists() {
+        std::fs::remove_dir_all(staging)?;
+    }
+    std::fs::create_dir_all(staging)?;
+    let target = staging.join(&name);
+    match std::fs::hard_link(&path, &target) {
+        Ok(()) => {}
+        Err(_) if read_only => {
+            std::fs::copy(&path, &target).map_err(|e| {
+                BoxliteError::Storage(format!("Failed to copy volume file '{}': {}", host_path, e))
+            })?;
+        }
+        Err(e) => {
+            return Err(BoxliteError::Config(format!(
+                "Failed to link volume file '{}' into the box ({}); a writable file must be on the \
+                 same filesystem as the boxlite home, or mount its directory instead",
+                host_path, e
+            )));
+        }
+    }
+    Ok((staging.to_path_buf(), Some(name)))
 }
 
 /// Result of rootfs preparation - either merged, separate layers, or disk image.
@@ -120,6 +224,7 @@ pub struct CleanupGuard {
     box_id: BoxID,
     layout: Option<BoxFilesystemLayout>,
     handler: Option<Box<dyn VmmHandler>>,
+    host_network: bool,
     armed: bool,
 }
 
@@ -130,6 +235,7 @@ impl CleanupGuard {
             box_id,
             layout: None,
             handler: None,
+            host_network: false,
             armed: true,
         }
     }
@@ -144,6 +250,11 @@ impl CleanupGuard {
         self.handler = Some(handler);
     }
 
+    /// Register the box's host network TAP device for removal on failure.
+    pub fn set_host_network(&mut self) {
+        self.host_network = true;
+    }
+
     /// Take ownership of handler (for success path).
     pub fn take_handler(&mut self) -> Option<Box<dyn VmmHandler>> {
         self.handler.take()
@@ -172,6 +283,10 @@ impl Drop for CleanupGuard {
             tracing::warn!("Failed to stop handler during cleanup: {}", e);
         }
 
+        if self.host_network {
+            crate::net::host::detach(&self.box_id);
+        }
+
         // Cleanup filesystem
         if let Some(ref layout) = self.layout
             && let Err(e) = layout.cleanup()
@@ -218,6 +333,14 @@ pub struct InitPipelineContext {
     pub rootfs_init: Option<ContainerRootfsInitConfig>,
     pub container_mounts: Option<Vec<ContainerMount>>,
     pub guest_session: Option<GuestSession>,
+    /// Host ports forwarded to the booted VM, free ports assigned.
+    pub port_mappings: Vec<PortMapping>,
+    /// MTU the network backend was started with, None without a network.
+    pub network_mtu: Option<u16>,
+    /// TAP device of the box in host network mode.
+    pub host_network: Option<HostAttachment>,
+    /// Locks of the disk volumes the VM mounts.
+    pub volume_locks: Vec<VolumeLock>,
 
     #[cfg(target_os = "linux")]
     pub bind_mount: Option<BindMountHandle>,
@@ -245,8 +368,56 @@ impl InitPipelineContext {
             rootfs_init: None,
             container_mounts: None,
             guest_session: None,
+            port_mappings: Vec::new(),
+            network_mtu: None,
+            host_network: None,
+            volume_locks: Vec::new(),
             #[cfg(target_os = "linux")]
             bind_mount: None,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_share_host_path_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let host_dir = dir.path().join("host");
+        std::fs::create_dir(&host_dir).unwrap();
+        let file = host_dir.join("app.conf");
+        std::fs::write(&file, "a").unwrap();
+        std::fs::write(host_dir.join("secret"), "s").unwrap();
+        let staging = dir.path().join("box/volume-files/uservol0");
+
+        // Only the file is shared, linked to the host's
+        let (share, name) = share_host_path(file.to_str().unwrap(), &staging, false).unwrap();
+        assert_eq!(share, staging);
+        assert_eq!(name.as_deref(), Some("app.conf"));
+        let entries: Vec<_> = std::fs::read_dir(&staging)
+            .unwrap()
+            .map(|e| e.unwrap().file_name())
+            .collect();
+        assert_eq!(entries, vec!["app.conf"]);
+        std::fs::write(staging.join("app.conf"), "b").unwrap();
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "b");
+
+        // A later boot starts from a clean staging dir
+        std::fs::write(staging.join("stale"), "x").unwrap();
+        share_host_path(file.to_str().unwrap(), &staging, true).unwrap();
+        assert!(!staging.join("stale").exists());
+
+        // Directories are shared as they are
+        let (share, name) = share_host_path(host_dir.to_str().unwrap(), &staging, false).unwrap();
+        assert_eq!(share, host_dir.canonicalize().unwrap());
+        assert!(name.is_none());
+
+        let missing = host_dir.join("missing");
+        assert!(matches!(
+            share_host_path(missing.to_str().unwrap(), &staging, false),
+            Err(BoxliteError::Config(_))
+        ));
+    }
+}
@@ -8,8 +8,10 @@ use std::sync::Arc;
 use boxlite_shared::errors::{BoxliteError, BoxliteResult};
 
 use crate::db::BoxStore;
+use crate::litebox::ExecRecord;
 use crate::litebox::config::BoxConfig;
-use crate::runtime::types::{BoxID, BoxState};
+use crate::runtime::types::{BoxID, BoxState, ListOptions};
+use crate::telemetry::Telemetry;
 
 /// State backend for box persistence.
 ///
@@ -18,6 +20,7 @@ use crate::runtime::types::{BoxID, BoxState};
 #[derive(Clone)]
 pub struct BoxManager {
     store: Arc<BoxStore>,
+    telemetry: Telemetry,
 }
 
 impl std::fmt::Debug for BoxManager {
@@ -31,9 +34,16 @@ impl BoxManager {
     pub fn new(store: BoxStore) -> Self {
         Self {
             store: Arc::new(store),
+            telemetry: Telemetry::default(),
         }
     }
 
+    /// Report persisted status changes to the given telemetry sinks.
+    pub(crate) fn with_telemetry(mut self, telemetry: Telemetry) -> Self {
+        self.telemetry = telemetry;
+        self
+    }
+
     // ========================================================================
     // State Interface
     // ========================================================================
@@ -59,6 +69,7 @@ impl BoxManager {
         }
 
         self.store.save(config, state)?;
+        self.telemetry.state_change(&config.id, None, state.status);
 
         tracing::debug!(
             box_id = %config.id,
@@ -84,6 +95,50 @@ impl BoxManager {
         Ok(())
     }
 
+    /// Rename a box.
+    ///
+    /// Fails if another box already has `new_name`.
+    pub fn rename_box(&self, id: &BoxID, new_name: &str) -> BoxliteResult<()> {
+        if new_name.is_empty() {
+            return Err(BoxliteError::InvalidArgument(
+                "box name cannot be empty".into(),
+            ));
+        }
+
+        let (mut config, _) = self
+            .box_by_id(id)?
+            .ok_or_else(|| BoxliteError::NotFound(format!("box {}", id)))?;
+        if config.name.as_deref() == Some(new_name) {
+            return Ok(());
+        }
+        if let Some(other) = self.lookup_box_id(new_name)?
+            && other != *id
+        {
+            return Err(BoxliteError::InvalidState(format!(
+                "box with name '{}' already exists",
+                new_name
+            )));
+        }
+
+        let old_name = config.name.replace(new_name.to_string());
+        self.store.update_config(&config)?;
+
+        tracing::debug!(box_id = %id, from = ?old_name, to = %new_name, "Renamed box");
+
+        Ok(())
+    }
+
+    /// Save a changed box configuration.
+    ///
+    /// The name must be changed with [`rename_box`](Self::rename_box).
+    pub fn save_config(&self, config: &BoxConfig) -> BoxliteResult<()> {
+        self.store.update_config(config)?;
+
+        tracing::trace!(box_id = %config.id, "Saved box config to database");
+
+        Ok(())
+    }
+
     /// Get a box by exact ID.
     pub fn box_by_id(&self, id: &BoxID) -> BoxliteResult<Option<(BoxConfig, BoxState)>> {
         self.store.load(id.as_str())
@@ -139,6 +194,16 @@ impl BoxManager {
         self.store.load(id.as_str()).map(|opt| opt.is_some())
     }
 
+    /// Flush pending writes to the database file.
+    pub fn checkpoint(&self) -> BoxliteResult<()> {
+        self.store.checkpoint()
+    }
+
+    /// Get the boxes matching `options`, newest first.
+    pub fn list_boxes(&self, options: &ListOptions) -> BoxliteResult<Vec<(BoxConfig, BoxState)>> {
+        self.store.list(options)
+    }
+
     /// Get all boxes.
     pub fn all_boxes(&self, _load_state: bool) -> BoxliteResult<Vec<(BoxConfig, BoxState)>> {
         self.store.list_all()
@@ -148,7 +213,9 @@ impl BoxManager {
     ///
     /// Reads state from the provided BoxState and persists to DB.
     pub fn save_box(&self, id: &BoxID, state: &BoxState) -> BoxliteResult<()> {
+        let previous = self.store.load_state(id.as_str())?.map(|s| s.status);
         self.store.update_state(id.as_str(), state)?;
+        self.telemetry.state_change(id, previous, state.status);
 
         tracing::trace!(
             box_id = %id,
@@ -168,25 +235,65 @@ impl BoxManager {
             .ok_or_else(|| BoxliteError::NotFound(format!("box {} state not found", id)))
     }
 
+    /// Persist an exec history record (insert or update).
+    pub fn save_exec(&self, id: &BoxID, record: &ExecRecord) -> BoxliteResult<()> {
+        self.store.save_exec(id.as_str(), record)
+    }
+
+    /// Get the exec history of a box, oldest first.
+    pub fn exec_history(&self, id: &BoxID) -> BoxliteResult<Vec<ExecRecord>> {
+        self.store.exec_history(id.as_str())
+    }
+
     // ========================================================================
     // Recovery helpers
     // ========================================================================
 
     /// Check and handle system reboot.
     ///
-    /// Returns true if a reboot was detected.
-    pub fn check_and_handle_reboot(&self) -> BoxliteResult<bool> {
-        let is_reboot = self.store.check_and_update_boot()?;
-
-        if is_reboot {
-            tracing::info!("Detected system reboot, resetting active boxes to stopped");
-            let reset_ids = self.store.reset_active_boxes_after_reboot()?;
-            for id in &reset_ids {
-                tracing::info!(box_id = %id, "Reset box to stopped after reboot");
-            }
+    /// Returns the boxes that were reset to stopped, if a reboot was detected.
+    pub fn check_and_handle_reboot(&self) -> BoxliteResult<Vec<BoxID>> {
+        if !self.store.check_and_update_boot()? {
+            return Ok(Vec::new());
         }
 
-        Ok(is_reboot)
+        tracing::info!("Detected system reboot, resetting active boxes to stopped");
+        let reset_ids = self.store.reset_active_boxes_after_reboot()?;
+        for id in &reset_ids {
+            tracing::info!(box_id = %id, "Reset box to stopped after reboot");
+        }
+        Ok(reset_ids)
+    }
+
+    /// Mark running boxes whose VM process is gone as stopped.
+    ///
+    /// A box counts as crashed when its recorded PID exited or now belongs
+    /// to another process. Boxes for which `skip` returns true are left
+    /// alone. Returns the boxes that were marked, with the PID they had.
+    pub fn refresh_states(
+        &self,
+        skip: impl Fn(&BoxID) -> bool,
+    ) -> BoxliteResult<Vec<(BoxConfig, u32)>> {
+        let mut crashed = Vec::new();
+        for (config, mut state) in self.all_boxes(true)? {
+            if !(state.status.is_running() || state.status.is_paused()) || skip(&config.id) {
+                continue;
+            }
+            let Some(pid) = state.pid else {
+                continue;
+            };
+            if !crate::util::has_exited(pid)
+                && crate::util::is_same_process(pid, config.id.as_str())
+            {
+                continue;
+            }
+
+            tracing::warn!(box_id = %config.id, pid, "Box VM exited unexpectedly");
+            state.mark_crashed();
+            self.save_box(&config.id, &state)?;
+            crashed.push((config, pid));
+        }
+        Ok(crashed)
     }
 }
 
@@ -290,6 +397,32 @@ mod tests {
         assert!(result.unwrap_err().to_string().contains("already exists"));
     }
 
+    #[test]
+    fn test_rename_box() {
+        let store = create_test_store();
+        let manager = BoxManager::new(store);
+
+        let mut config1 = create_test_config(TEST_ID_1);
+        config1.name = Some("old".to_string());
+        manager.add_box(&config1, &BoxState::new()).unwrap();
+        let mut config2 = create_test_config(TEST_ID_2);
+        config2.name = Some("taken".to_string());
+        manager.add_box(&config2, &BoxState::new()).unwrap();
+
+        manager.rename_box(&config1.id, "new").unwrap();
+        assert!(manager.lookup_box("old").unwrap().is_none());
+        let (config, _) = manager.lookup_box("new").unwrap().unwrap();
+        assert_eq!(config.id, config1.id);
+        assert_eq!(config.name.as_deref(), Some("new"));
+
+        // Same name is a no-op
+        manager.rename_box(&config1.id, "new").unwrap();
+
+        let result = manager.rename_box(&config1.id, "taken");
+        assert!(result.unwrap_err().to_string().contains("already exists"));
+        assert!(manager.rename_box(&config1.id, "").is_err());
+    }
+
     #[test]
     fn test_has_box() {
         let store = create_test_store();
@@ -418,4 +551,68 @@ mod tests {
         assert_eq!(loaded_state.status, BoxStatus::Running);
         assert_eq!(loaded_state.pid, Some(12345));
     }
+
+    #[test]
+    fn test_refresh_states() {
+        let manager = BoxManager::new(create_test_store());
+
+        // Alive, but not the box's shim
+        let mut state = create_test_state(BoxStatus::Running);
+        state.set_pid(Some(std::process::id()));
+        let crashed = create_test_config(TEST_ID_1);
+        manager.add_box(&crashed, &state).unwrap();
+        let skipped = create_test_config(TEST_ID_2);
+        manager.add_box(&skipped, &state).unwrap();
+        let stopped = create_test_config(TEST_ID_3);
+        manager
+            .add_box(&stopped, &create_test_state(BoxStatus::Stopped))
+            .unwrap();
+
+        let marked = manager.refresh_states(|id| *id == skipped.id).unwrap();
+        assert_eq!(marked.len(), 1);
+        assert_eq!(marked[0].0.id, crashed.id);
+        assert_eq!(marked[0].1, std::process::id());
+
+        let state = manager.update_box(&crashed.id).unwrap();
+        assert_eq!(state.status, BoxStatus::Stopped);
+        assert_eq!(state.pid, None);
+        let state = manager.update_box(&skipped.id).unwrap();
+        assert_eq!(state.status, BoxStatus::Running);
+        assert_eq!(manager.refresh_states(|_| false).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_save_box_reports_state_change() {
+        use crate::telemetry::TelemetrySink;
+        use std::sync::Mutex;
+
+        #[derive(Default)]
+        struct Transitions(Mutex<Vec<(Option<BoxStatus>, BoxStatus)>>);
+
+        impl TelemetrySink for Transitions {
+            fn on_state_change(&self, _box_id: &BoxID, from: Option<BoxStatus>, to: BoxStatus) {
+                self.0.lock().unwrap().push((from, to));
+            }
+        }
+
+        let sink = Arc::new(Transitions::default());
+        let telemetry = Telemetry::default();
+        telemetry.add_sink(sink.clone());
+
+        let manager = BoxManager::new(create_test_store()).with_telemetry(telemetry);
+        let config = create_test_config(TEST_ID_1);
+        manager.add_box(&config, &BoxState::new()).unwrap();
+
+        let state = create_test_state(BoxStatus::Running);
+        manager.save_box(&config.id, &state).unwrap();
+        manager.save_box(&config.id, &state).unwrap();
+
+        assert_eq!(
+            *sink.0.lock().unwrap(),
+            vec![
+                (None, BoxStatus::Starting),
+                (Some(BoxStatus::Starting), BoxStatus::Running),
+            ]
+        );
+    }
 }
@@ -2,24 +2,56 @@
 //!
 //! Provides lazy initialization and execution capabilities for isolated boxes.
 
+pub(crate) mod activation;
 pub(crate) mod box_impl;
 pub(crate) mod config;
 mod exec;
+mod files;
+mod health;
+mod history;
+mod http;
+mod idle;
 mod init;
 mod manager;
+mod redirect;
+mod spec;
+mod ssh;
 mod state;
+mod sync;
+mod transfer;
+mod tunnel;
+mod watch;
 
-pub use exec::{BoxCommand, ExecResult, ExecStderr, ExecStdin, ExecStdout, Execution, ExecutionId};
+pub use exec::{
+    BoxCommand, ExecEnvSnapshot, ExecInfo, ExecOutput, ExecRecord, ExecResult, ExecState,
+    ExecStderr, ExecStdin, ExecStdout, Execution, ExecutionId, ExitStatus, LogRotation,
+    OutputChunk, OutputLimitPolicy, Signal, TimestampedChunk, TimestampedOutput,
+};
+pub(crate) use exec::{ExecLimits, OutputFrame};
+pub use files::{FileInfo, FileSource, FileType, TarStream};
+pub(crate) use health::HealthCheck;
+pub use health::HealthStatus;
+pub use http::BoxHttpClient;
 pub(crate) use manager::BoxManager;
-pub use state::{BoxState, BoxStatus};
+pub use spec::{BoxSpec, SPEC_VERSION};
+pub use ssh::SshAccess;
+pub use state::{BoxState, BoxStatus, ExitReason, LastExit};
+pub use sync::{SyncOptions, SyncStats};
+pub use transfer::{ProgressCallback, TransferOptions, TransferProgress};
+pub use watch::{FileEvent, FileEventKind, WatchStream};
 
 pub(crate) use box_impl::SharedBoxImpl;
 pub(crate) use init::BoxBuilder;
 
 use crate::metrics::BoxMetrics;
-use crate::{BoxID, BoxInfo};
+use crate::net::{CaptureFilter, CaptureStats};
+use crate::{BoxID, BoxInfo, BoxInspect};
 use boxlite_shared::errors::BoxliteResult;
+use bytes::Bytes;
 pub use config::BoxConfig;
+use std::path::Path;
+use std::time::Duration;
+use tokio::io::AsyncRead;
 
 /// LiteBox - Handle to a box.
 ///
@@ -34,17 +66,40 @@ pub struct LiteBox {
     name: Option<String>,
     /// Box implementation (created immediately, LiveState is lazy).
     inner: SharedBoxImpl,
+    /// Whether this handle counts toward `on_drop`; the runtime's own
+    /// handles do not.
+    user: bool,
 }
 
 impl LiteBox {
     /// Create a LiteBox from a shared BoxImpl.
     ///
     /// Used by RuntimeImpl to create handles that share the same BoxImpl.
-    /// Multiple handles to the same box share the same LiveState.
+    /// Multiple handles to the same box share the same LiveState. The
+    /// box's `on_drop` applies once the last of these handles is dropped.
     pub(crate) fn new(inner: SharedBoxImpl) -> Self {
+        inner.acquire_user_handle();
         let id = inner.id().clone();
         let name = inner.config.name.clone();
-        Self { id, name, inner }
+        Self {
+            id,
+            name,
+            inner,
+            user: true,
+        }
+    }
+
+    /// Create a handle for the runtime's own use, which leaves the box
+    /// running when dropped.
+    pub(crate) fn internal(inner: SharedBoxImpl) -> Self {
+        let id = inner.id().clone();
+        let name = inner.config.name.clone();
+        Self {
+            id,
+            name,
+            inner,
+            user: false,
+        }
     }
 
     pub fn id(&self) -> &BoxID {
@@ -60,17 +115,391 @@ impl LiteBox {
         self.inner.info()
     }
 
+    /// Start the box now instead of on first use.
+    ///
+    /// Runs the full initialization pipeline and returns once the guest is
+    /// ready. No-op if the box is already running.
+    pub async fn start(&self) -> BoxliteResult<()> {
+        self.inner.start().await
+    }
+
     pub async fn exec(&self, command: BoxCommand) -> BoxliteResult<Execution> {
         self.inner.exec(command).await
     }
 
+    /// Run a shell script, e.g. `"cd /app && make 2>&1 | tee build.log"`.
+    ///
+    /// Runs `/bin/sh -c <script>`, or the image's `SHELL` (e.g.
+    /// `["/busybox/sh", "-c"]`) if the image has no `/bin/sh`.
+    pub async fn shell(&self, script: impl Into<String>) -> BoxliteResult<Execution> {
+        self.inner.shell(script.into()).await
+    }
+
+    /// Reattach to an execution, e.g. one started with
+    /// [`BoxCommand::detach`] or by an earlier process that attached to the
+    /// same running box.
+    ///
+    /// Returns a new handle with the execution's output (including output
+    /// buffered while no handle was attached) and exit status. Stdin is not
+    /// available. Fails if another handle is currently attached.
+    pub async fn attach_exec(&self, execution_id: &ExecutionId) -> BoxliteResult<Execution> {
+        self.inner.attach_exec(execution_id).await
+    }
+
+    /// Attach to the container's main process (the image entrypoint).
+    ///
+    /// Returns a handle with the process's stdin, its output (including
+    /// output written before attaching) and its exit status. Closing stdin
+    /// or dropping the handle detaches without ending the process. Create
+    /// the box with [`BoxOptions::tty`](crate::BoxOptions::tty) for
+    /// interactive images. Fails if another handle is currently attached.
+    pub async fn attach(&self) -> BoxliteResult<Execution> {
+        self.inner.attach().await
+    }
+
+    /// Wait for the container's main process (the image entrypoint) to exit.
+    ///
+    /// Starts the box if needed. Resolves with the exit code and whether the
+    /// process was OOM-killed, so an image can be run to completion like
+    /// `docker run`. The box keeps running afterwards; stop it when done.
+    pub async fn wait(&self) -> BoxliteResult<ExitStatus> {
+        self.inner.wait().await
+    }
+
+    /// List executions in this box (running and finished), oldest first.
+    ///
+    /// Useful after reattaching to a box to find executions to
+    /// [`attach_exec`](Self::attach_exec) to.
+    pub async fn list_execs(&self) -> BoxliteResult<Vec<ExecInfo>> {
+        self.inner.list_execs().await
+    }
+
+    /// Get the environment, working directory, user and mounts that the next
+    /// exec would run with, without triggering VM initialization.
+    ///
+    /// Reads the image config from the local image store, never the
+    /// registry; fails with `NotFound` if the image is not stored.
+    pub async fn env_snapshot(&self) -> BoxliteResult<ExecEnvSnapshot> {
+        self.inner.env_snapshot().await
+    }
+
+    /// Copy a host file or bytes to `guest_path` in the box, with
+    /// permission bits `mode` (e.g. `0o755` for a script).
+    ///
+    /// The parent directory must exist; an existing file is replaced.
+    /// Paths resolve as in the container, so volumes and tmpfs mounts are
+    /// reachable. Returns the number of bytes written.
+    pub async fn put_file(
+        &self,
+        source: impl Into<FileSource>,
+        guest_path: impl AsRef<str>,
+        mode: u32,
+    ) -> BoxliteResult<u64> {
+        self.inner
+            .put_file(source.into(), guest_path.as_ref(), mode)
+            .await
+    }
+
+    /// Copy a large host file to `guest_path` in the box, with permission
+    /// bits `mode`.
+    ///
+    /// Like [`put_file`](Self::put_file), but reports progress and resumes
+    /// where it stopped if the connection to the guest breaks, up to
+    /// `options.max_retries` times. Returns the file size.
+    pub async fn upload(
+        &self,
+        host_path: impl AsRef<Path>,
+        guest_path: impl AsRef<str>,
+        mode: u32,
+        options: TransferOptions,
+    ) -> BoxliteResult<u64> {
+        self.inner
+            .upload(host_path.as_ref(), guest_path.as_ref(), mode, options)
+            .await
+    }
+
+    /// Copy the file at `guest_path` in the box to `host_path`.
+    ///
+    /// Streams to disk instead of memory, reports progress and resumes
+    /// where it stopped if the connection to the guest breaks, up to
+    /// `options.max_retries` times. Returns the bytes written.
+    pub async fn download(
+        &self,
+        guest_path: impl AsRef<str>,
+        host_path: impl AsRef<Path>,
+        options: TransferOptions,
+    ) -> BoxliteResult<u64> {
+        self.inner
+            .download(guest_path.as_ref(), host_path.as_ref(), options)
+            .await
+    }
+
+    /// Read the file at `guest_path` in the box.
+    pub async fn get_file(&self, guest_path: impl AsRef<str>) -> BoxliteResult<Bytes> {
+        self.inner.get_file(guest_path.as_ref(), 0).await
+    }
+
+    /// Read a small text file, e.g. a command's `result.json`.
+    ///
+    /// Fails with `InvalidArgument` if the file is larger than the box's
+    /// [`max_text_file_size`](crate::BoxOptions::max_text_file_size) or is
+    /// not UTF-8.
+    pub async fn read_to_string(&self, guest_path: impl AsRef<str>) -> BoxliteResult<String> {
+        self.inner.read_to_string(guest_path.as_ref()).await
+    }
+
+    /// Write `content` to `guest_path` in the box with mode `0o644`.
+    ///
+    /// Replaces an existing file; the parent directory must exist. Fails
+    /// with `InvalidArgument` if `content` is larger than the box's
+    /// [`max_text_file_size`](crate::BoxOptions::max_text_file_size).
+    pub async fn write_string(
+        &self,
+        guest_path: impl AsRef<str>,
+        content: impl Into<String>,
+    ) -> BoxliteResult<()> {
+        self.inner
+            .write_string(guest_path.as_ref(), content.into())
+            .await
+    }
+
+    /// Extract a tar archive into the directory `guest_dir` in the box.
+    ///
+    /// The archive is streamed to the guest as it is read. Permissions,
+    /// symlinks and hard links are preserved; ownership is not, and device
+    /// nodes and fifos are skipped. `guest_dir` must exist, and entries may
+    /// not point outside it. Returns the number of entries extracted.
+    pub async fn copy_in(
+        &self,
+        tar: impl AsyncRead + Unpin + Send + 'static,
+        guest_dir: impl AsRef<str>,
+    ) -> BoxliteResult<u64> {
+        self.inner.copy_in(tar, guest_dir.as_ref()).await
+    }
+
+    /// Make `guest_dir` in the box match `host_dir`, copying only what
+    /// changed since the last sync.
+    ///
+    /// Entries are compared by type, permissions, size and modification time
+    /// (or content, with [`SyncOptions::checksum`]); changed ones are sent as
+    /// one tar stream. Guest entries missing on the host are kept unless
+    /// [`SyncOptions::delete`] is set. `guest_dir` is created if missing.
+    pub async fn sync_dir(
+        &self,
+        host_dir: impl AsRef<Path>,
+        guest_dir: impl AsRef<str>,
+        options: SyncOptions,
+    ) -> BoxliteResult<SyncStats> {
+        self.inner
+            .sync_dir(host_dir.as_ref(), guest_dir.as_ref(), options)
+            .await
+    }
+
+    /// Stream `guest_path` in the box as a tar archive.
+    ///
+    /// A directory is archived recursively under its own name; symlinks are
+    /// stored as links, not followed.
+    pub async fn copy_out(&self, guest_path: impl AsRef<str>) -> BoxliteResult<TarStream> {
+        self.inner.copy_out(guest_path.as_ref()).await
+    }
+
+    /// Metadata of `guest_path` in the box.
+    ///
+    /// A symlink is described itself, not its target. Fails with
+    /// `NotFound` if the path does not exist.
+    pub async fn stat(&self, guest_path: impl AsRef<str>) -> BoxliteResult<FileInfo> {
+        self.inner.stat(guest_path.as_ref()).await
+    }
+
+    /// Metadata of the entries of the directory `guest_path` in the box,
+    /// sorted by name.
+    pub async fn list_dir(&self, guest_path: impl AsRef<str>) -> BoxliteResult<Vec<FileInfo>> {
+        self.inner.list_dir(guest_path.as_ref()).await
+    }
+
+    /// Set the permission bits of `guest_path` in the box (e.g. `0o755`).
+    ///
+    /// Symlinks are followed, within the container.
+    pub async fn chmod(&self, guest_path: impl AsRef<str>, mode: u32) -> BoxliteResult<()> {
+        self.inner.chmod(guest_path.as_ref(), mode).await
+    }
+
+    /// Set the owner of `guest_path` in the box, e.g. to the container user
+    /// after [`put_file`](Self::put_file). IDs are as seen in the box.
+    ///
+    /// Symlinks are followed, within the container.
+    pub async fn chown(
+        &self,
+        guest_path: impl AsRef<str>,
+        uid: u32,
+        gid: u32,
+    ) -> BoxliteResult<()> {
+        self.inner.chown(guest_path.as_ref(), uid, gid).await
+    }
+
+    /// Watch `guest_path` in the box for changes.
+    ///
+    /// A directory is watched recursively, including directories created
+    /// later; their existing entries are reported as created. Files count
+    /// as modified when closed after writing, so a finished output file
+    /// produces one event rather than one per write.
+    pub async fn watch(&self, guest_path: impl AsRef<str>) -> BoxliteResult<WatchStream> {
+        self.inner.watch(guest_path.as_ref()).await
+    }
+
+    /// Write the changes made to the box's rootfs since it was created from
+    /// its image to `path`, as an uncompressed OCI layer tar.
+    ///
+    /// Added and modified files are included as-is and deleted files as
+    /// `.wh.` whiteouts. Files are compared with the image by type,
+    /// permissions, size and modification time. Volume and tmpfs contents
+    /// are not part of the rootfs and are not exported.
+    ///
+    /// Returns the size of the tar in bytes.
+    pub async fn export_changes(&self, path: impl AsRef<Path>) -> BoxliteResult<u64> {
+        self.inner.export_changes(path.as_ref()).await
+    }
+
     pub async fn metrics(&self) -> BoxliteResult<BoxMetrics> {
         self.inner.metrics().await
     }
 
+    /// The box's configuration with its image pinned by digest, to
+    /// recreate it later with
+    /// [`BoxliteRuntime::create_from_spec`](crate::BoxliteRuntime::create_from_spec).
+    ///
+    /// Pins the image the box last booted from, whatever its tag points to
+    /// now. Fails if the box has never booted.
+    pub async fn spec(&self) -> BoxliteResult<BoxSpec> {
+        self.inner.spec().await
+    }
+
+    /// Everything known about the box, for debugging and tooling.
+    ///
+    /// Does not start the box; metrics are included only if it runs.
+    pub async fn inspect(&self) -> BoxliteResult<BoxInspect> {
+        self.inner.inspect().await
+    }
+
     pub async fn stop(&self) -> BoxliteResult<()> {
         self.inner.stop().await
     }
+
+    /// Kill the box's VM right away.
+    ///
+    /// Unlike [`stop`](Self::stop), the guest is not asked to shut down,
+    /// so this works when the guest no longer answers. Processes in the box
+    /// get no chance to clean up.
+    pub async fn kill(&self) -> BoxliteResult<()> {
+        self.inner.kill().await
+    }
+
+    /// Stop the box, giving the guest `grace` instead of its stop timeout.
+    pub(crate) async fn stop_within(&self, grace: Duration) -> BoxliteResult<()> {
+        self.inner.stop_within(grace).await
+    }
+
+    /// Freeze the box's VM.
+    ///
+    /// Processes in the box keep their state but stop running, and the box
+    /// is reported as [`BoxStatus::Paused`](crate::BoxStatus::Paused) until
+    /// [`resume`](Self::resume). Commands cannot be started while paused.
+    pub async fn pause(&self) -> BoxliteResult<()> {
+        self.inner.pause().await
+    }
+
+    /// Thaw a box frozen by [`pause`](Self::pause).
+    pub async fn resume(&self) -> BoxliteResult<()> {
+        self.inner.resume().await
+    }
+
+    /// Wait until the image's HEALTHCHECK passes.
+    ///
+    /// Starts the box if needed. Fails if the image has no healthcheck, the
+    /// box becomes [`Unhealthy`](HealthStatus::Unhealthy), or `timeout`
+    /// passes first.
+    pub async fn wait_healthy(&self, timeout: Duration) -> BoxliteResult<()> {
+        self.inner.wait_healthy(timeout).await
+    }
+
+    /// Wait until a server in the box accepts TCP connections on
+    /// `guest_port`.
+    ///
+    /// Starts the box if needed. The guest agent connects to the port on
+    /// the box's loopback, so this works whether or not the port is
+    /// forwarded to the host. Fails if the box stops or `timeout` passes
+    /// first.
+    pub async fn wait_for_port(&self, guest_port: u16, timeout: Duration) -> BoxliteResult<()> {
+        self.inner.wait_for_port(guest_port, timeout).await
+    }
+
+    /// HTTP client for servers in the box, reached through the network
+    /// backend without publishing their ports.
+    ///
+    /// Starts the box if needed. Not available with the passt backend.
+    pub async fn http(&self) -> BoxliteResult<BoxHttpClient> {
+        self.inner.http().await
+    }
+
+    /// Record the box's network traffic matching `filter` to a pcap file at
+    /// `path`, for debugging.
+    ///
+    /// Starts the box if needed, and returns once the filter's duration
+    /// passes or the file reaches its size limit. Frames are recorded where
+    /// the network backend sees them, so traffic the egress policy blocks
+    /// shows up too. One capture runs per box at a time. Not available with
+    /// the passt backend.
+    pub async fn capture_traffic(
+        &self,
+        path: impl AsRef<Path>,
+        filter: CaptureFilter,
+    ) -> BoxliteResult<CaptureStats> {
+        self.inner.capture_traffic(path.as_ref(), filter).await
+    }
+
+    /// Give SSH access to the box with `public_key`, e.g. for VS Code
+    /// Remote or other remote tooling.
+    ///
+    /// Starts the box if needed. The key is added to the `authorized_keys`
+    /// of the box's user, and the image's OpenSSH or Dropbear server is
+    /// started in the box; images without one are not supported. The
+    /// server is reached through a port on the host's loopback, which stays
+    /// the same for the life of this box. Not available with the passt
+    /// backend.
+    pub async fn enable_ssh(&self, public_key: &str) -> BoxliteResult<SshAccess> {
+        self.inner.enable_ssh(public_key).await
+    }
+
+    /// Change the box's vCPU count and memory; `None` keeps a value as is.
+    ///
+    /// The values are checked against the box's options and the host
+    /// before anything changes. A stopped box keeps them for its next
+    /// start; a running box is restarted with them, and gets its old values
+    /// back if that restart fails. Use the returned handle afterwards; this
+    /// one may no longer be usable.
+    pub async fn update_resources(
+        &self,
+        cpus: Option<u8>,
+        memory_mib: Option<u32>,
+    ) -> BoxliteResult<LiteBox> {
+        self.inner.update_resources(cpus, memory_mib).await
+    }
+
+    /// Stop the box and start it again, keeping its rootfs and config.
+    ///
+    /// The guest gets `timeout` to shut down before the VM is killed.
+    /// Returns a handle to the restarted box; this one is no longer usable.
+    pub async fn restart(&self, timeout: Duration) -> BoxliteResult<LiteBox> {
+        self.inner.restart(timeout).await
+    }
+}
+
+impl Drop for LiteBox {
+    fn drop(&mut self) {
+        if self.user {
+            self.inner.release_user_handle();
+        }
+    }
 }
 
 // ============================================================================
@@ -0,0 +1,286 @@
+//! Exec output redirection to host files.
+//!
+//! Streams selected with [`BoxCommand::stdout_to`] / [`BoxCommand::stderr_to`]
+//! are drained into files under the runtime's logs directory instead of
+//! being handed to the caller. The exit status is held back until the files
+//! are fully written.
+
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use boxlite_shared::errors::{BoxliteError, BoxliteResult};
+use tokio::sync::mpsc;
+
+use crate::litebox::{BoxCommand, LogRotation, OutputFrame};
+use crate::portal::interfaces::ExecComponents;
+
+type SharedFile = Arc<Mutex<RotatingFile>>;
+
+/// Output files of one execution, opened before the command starts.
+pub(crate) struct OutputRedirect {
+    stdout: Option<SharedFile>,
+    stderr: Option<SharedFile>,
+}
+
+impl OutputRedirect {
+    /// Open the files requested by `command`, or `None` if nothing is redirected.
+    pub fn open(logs_dir: &Path, command: &BoxCommand) -> BoxliteResult<Option<Self>> {
+        let open = |path: &Path| -> BoxliteResult<SharedFile> {
+            let path = resolve(logs_dir, path)?;
+            Ok(Arc::new(Mutex::new(RotatingFile::open(
+                path,
+                command.log_rotation,
+            )?)))
+        };
+
+        let stdout = command.stdout_to.as_deref().map(open).transpose()?;
+        let stderr = match (&command.stderr_to, &stdout) {
+            // Share the handle so both streams rotate together
+            (Some(path), Some(file)) if command.stdout_to.as_ref() == Some(path) => {
+                Some(file.clone())
+            }
+            (Some(path), _) => Some(open(path)?),
+            (None, _) => None,
+        };
+
+        Ok((stdout.is_some() || stderr.is_some()).then_some(Self { stdout, stderr }))
+    }
+
+    /// Take over the redirected streams of a started execution.
+    ///
+    /// Returns components whose redirected streams are already closed.
+    pub fn attach(self, components: ExecComponents) -> ExecComponents {
+        let ExecComponents {
+            execution_id,
+            stdin_tx,
+            mut stdout_rx,
+            mut stderr_rx,
+            mut result_rx,
+        } = components;
+
+        let mut writers = Vec::new();
+        if let Some(file) = self.stdout {
+            writers.push(spawn_writer(
+                std::mem::replace(&mut stdout_rx, closed()),
+                file,
+            ));
+        }
+        if let Some(file) = self.stderr {
+            writers.push(spawn_writer(
+                std::mem::replace(&mut stderr_rx, closed()),
+                file,
+            ));
+        }
+
+        let (result_tx, held_result_rx) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            let result = result_rx.recv().await;
+            for writer in writers {
+                let _ = writer.await;
+            }
+            if let Some(result) = result {
+                let _ = result_tx.send(result);
+            }
+        });
+
+        ExecComponents {
+            execution_id,
+            stdin_tx,
+            stdout_rx,
+            stderr_rx,
+            result_rx: held_result_rx,
+        }
+    }
+}
+
+fn closed() -> mpsc::Receiver<OutputFrame> {
+    mpsc::channel(1).1
+}
+
+fn spawn_writer(
+    mut rx: mpsc::Receiver<OutputFrame>,
+    file: SharedFile,
+) -> tokio::task::JoinHandle<()> {
+    tokio::task::spawn_blocking(move || {
+        let mut failed = false;
+        // Keep draining after a write error so the execution isn't held up
+        while let Some(frame) = rx.blocking_recv() {
+            if failed {
+                continue;
+            }
+            let mut file = file.lock().unwrap();
+            if let Err(e) = file.write(&frame.data) {
+                tracing::warn!(
+                    path = %file.path.display(),
+                    error = %e,
+                    "Failed to write exec output, discarding the rest"
+                );
+                failed = true;
+            }
+        }
+    })
+}
+
+/// Resolve a path relative to the logs directory, refusing to leave it.
+fn resolve(logs_dir: &Path, path: &Path) -> BoxliteResult<PathBuf> {
+    if path.as_os_str().is_empty()
+        || path
+            .components()
+            .any(|c| !matches!(c, Component::Normal(_)))
+    {
+        return Err(BoxliteError::InvalidArgument(format!(
+            "Output path must be relative to the logs directory: {}",
+            path.display()
+        )));
+    }
+    Ok(logs_dir.join(path))
+}
+
+/// Append-only file with size-based rotation.
+struct RotatingFile {
+    path: PathBuf,
+    file: File,
+    written: u64,
+    rotation: LogRotation,
+}
+
+impl RotatingFile {
+    fn open(path: PathBuf, rotation: LogRotation) -> BoxliteResult<Self> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let file = Self::open_append(&path)?;
+        let written = file.metadata()?.len();
+        Ok(Self {
+            path,
+            file,
+            written,
+            rotation,
+        })
+    }
+
+    fn open_append(path: &Path) -> std::io::Result<File> {
+        OpenOptions::new().create(true).append(true).open(path)
+    }
+
+    fn write(&mut self, data: &[u8]) -> std::io::Result<()> {
+        if let Some(max_bytes) = self.rotation.max_bytes
+            && self.written > 0
+            && self.written + data.len() as u64 > max_bytes
+        {
+            self.rotate()?;
+        }
+        self.file.write_all(data)?;
+        self.written += data.len() as u64;
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> std::io::Result<()> {
+        let rotated = |n: u32| {
+            let mut name = self.path.clone().into_os_string();
+            name.push(format!(".{}", n));
+            PathBuf::from(name)
+        };
+
+        if self.rotation.max_files == 0 {
+            self.file.set_len(0)?;
+        } else {
+            for n in (1..self.rotation.max_files).rev() {
+                let from = rotated(n);
+                if from.exists() {
+                    std::fs::rename(&from, rotated(n + 1))?;
+                }
+            }
+            std::fs::rename(&self.path, rotated(1))?;
+            self.file = Self::open_append(&self.path)?;
+        }
+        self.written = 0;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_stays_in_logs_dir() {
+        let logs = Path::new("/home/boxlite/logs");
+        assert_eq!(
+            resolve(logs, Path::new("build/out.log")).unwrap(),
+            logs.join("build/out.log")
+        );
+        assert!(resolve(logs, Path::new("/tmp/out.log")).is_err());
+        assert!(resolve(logs, Path::new("../out.log")).is_err());
+        assert!(resolve(logs, Path::new("")).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_redirect_writes_before_result() {
+        use crate::litebox::ExecResult;
+        use bytes::Bytes;
+
+        let logs = tempfile::tempdir().unwrap();
+        let command = BoxCommand::new("make")
+            .stdout_to("build.log")
+            .stderr_to("build.log");
+        let redirect = OutputRedirect::open(logs.path(), &command)
+            .unwrap()
+            .unwrap();
+
+        let (stdout_tx, stdout_rx) = mpsc::channel(8);
+        let (stderr_tx, stderr_rx) = mpsc::channel(8);
+        let (result_tx, result_rx) = mpsc::unbounded_channel();
+        let mut components = redirect.attach(ExecComponents {
+            execution_id: "exec".to_string(),
+            stdin_tx: None,
+            stdout_rx,
+            stderr_rx,
+            result_rx,
+        });
+
+        stdout_tx
+            .try_send(OutputFrame::new(1, Bytes::from("out\n")))
+            .unwrap();
+        stderr_tx
+            .try_send(OutputFrame::new(2, Bytes::from("err\n")))
+            .unwrap();
+        result_tx.send(ExecResult { exit_code: 0 }).unwrap();
+
+        // Streams are redirected, so the caller sees them closed
+        assert!(components.stdout_rx.recv().await.is_none());
+        assert!(components.stderr_rx.recv().await.is_none());
+
+        // The result waits for the files to be complete
+        drop((stdout_tx, stderr_tx));
+        assert_eq!(components.result_rx.recv().await.unwrap().exit_code, 0);
+        let log = std::fs::read_to_string(logs.path().join("build.log")).unwrap();
+        assert_eq!(log.len(), 8);
+        assert!(log.contains("out\n") && log.contains("err\n"));
+    }
+
+    #[test]
+    fn test_rotating_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("out.log");
+        let rotation = LogRotation {
+            max_bytes: Some(4),
+            max_files: 2,
+        };
+        let mut file = RotatingFile::open(path.clone(), rotation).unwrap();
+
+        for chunk in ["aaa", "bbb", "ccc", "dd", "ee"] {
+            file.write(chunk.as_bytes()).unwrap();
+        }
+
+        let read = |suffix: &str| {
+            std::fs::read_to_string(format!("{}{}", path.display(), suffix)).unwrap()
+        };
+        assert_eq!(read(""), "ddee");
+        assert_eq!(read(".1"), "ccc");
+        assert_eq!(read(".2"), "bbb");
+        assert!(!dir.path().join("out.log.3").exists());
+    }
+}
@@ -0,0 +1,165 @@
+//! Reproducible box specs, see [`LiteBox::spec`](crate::LiteBox::spec).
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use boxlite_shared::errors::{BoxliteError, BoxliteResult};
+
+use crate::runtime::options::{BoxOptions, RootfsSpec};
+
+/// Current [`BoxSpec::version`].
+pub const SPEC_VERSION: u32 = 1;
+
+/// Everything needed to create the same box again, e.g. in a later CI run.
+///
+/// The image is pinned to the manifest digest the box booted from, so a
+/// moved tag does not change the box. Serialize it (e.g. to JSON) and
+/// commit it next to the code it runs.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BoxSpec {
+    /// Format of the spec; newer formats are rejected.
+    pub version: u32,
+    /// Image reference the box was created with (None for a rootfs path).
+    pub image: Option<String>,
+    /// Manifest digest `image` resolved to when the box last booted.
+    pub image_digest: Option<String>,
+    /// Options to create the box with. `rootfs` is pinned to
+    /// `image_digest`; volumes, env and everything else are as given.
+    pub options: BoxOptions,
+    /// `sha256:` digest of `options`, to detect edits.
+    pub options_hash: String,
+}
+
+impl BoxSpec {
+    /// Spec of a box with `options`, pinning its image to `image_digest`.
+    pub(crate) fn new(
+        mut options: BoxOptions,
+        image_digest: Option<String>,
+    ) -> BoxliteResult<Self> {
+        let image = match &options.rootfs {
+            RootfsSpec::Image(reference) => Some(reference.clone()),
+            RootfsSpec::RootfsPath(_) => None,
+        };
+        if let (Some(reference), Some(digest)) = (&image, &image_digest) {
+            options.rootfs = RootfsSpec::Image(pin(reference, digest));
+        }
+        let options_hash = hash_options(&options)?;
+        Ok(Self {
+            version: SPEC_VERSION,
+            image,
+            image_digest,
+            options,
+            options_hash,
+        })
+    }
+
+    /// Check that the spec can be used as is.
+    pub(crate) fn verify(&self) -> BoxliteResult<()> {
+        if self.version > SPEC_VERSION {
+            return Err(BoxliteError::InvalidArgument(format!(
+                "Box spec version {} is newer than the supported {}",
+                self.version, SPEC_VERSION
+            )));
+        }
+        if hash_options(&self.options)? != self.options_hash {
+            return Err(BoxliteError::InvalidArgument(
+                "Box spec options do not match its options_hash".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// `reference` resolved to exactly the manifest `digest`.
+fn pin(reference: &str, digest: &str) -> String {
+    let name = reference
+        .split_once('@')
+        .map_or(reference, |(name, _)| name);
+    format!("{}@{}", name, digest)
+}
+
+/// Digest of the options' JSON, with object keys sorted so that map
+/// fields such as labels hash the same in any order.
+fn hash_options(options: &BoxOptions) -> BoxliteResult<String> {
+    let value = serde_json::to_value(options)
+        .map_err(|e| BoxliteError::Internal(format!("Failed to serialize box options: {}", e)))?;
+    let canonical = canonicalize(value).to_string();
+    Ok(format!("sha256:{}", hex::encode(Sha256::digest(canonical))))
+}
+
+fn canonicalize(value: Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let mut entries: Vec<_> = map.into_iter().collect();
+            entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+            Value::Object(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| (key, canonicalize(value)))
+                    .collect(),
+            )
+        }
+        Value::Array(items) => Value::Array(items.into_iter().map(canonicalize).collect()),
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIGEST: &str = "sha256:4bcff63911fcb4448bd4fdacec207030997caf25e9bea4045fa6c8c44de311d1";
+
+    fn options() -> BoxOptions {
+        BoxOptions {
+            rootfs: RootfsSpec::Image("alpine:3.19".to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_pins_image() {
+        let spec = BoxSpec::new(options(), Some(DIGEST.to_string())).unwrap();
+        assert_eq!(spec.image.as_deref(), Some("alpine:3.19"));
+        assert!(
+            matches!(&spec.options.rootfs, RootfsSpec::Image(r) if *r == format!("alpine:3.19@{}", DIGEST))
+        );
+        assert_eq!(
+            pin(&format!("alpine@{}", DIGEST), DIGEST),
+            format!("alpine@{}", DIGEST)
+        );
+    }
+
+    #[test]
+    fn test_hash_ignores_map_order() {
+        let mut a = options();
+        let mut b = options();
+        for (key, value) in [("team", "infra"), ("job", "ci"), ("tier", "1")] {
+            a.labels.insert(key.to_string(), value.to_string());
+        }
+        for (key, value) in [("tier", "1"), ("job", "ci"), ("team", "infra")] {
+            b.labels.insert(key.to_string(), value.to_string());
+        }
+        assert_eq!(hash_options(&a).unwrap(), hash_options(&b).unwrap());
+
+        b.labels.insert("job".to_string(), "nightly".to_string());
+        assert_ne!(hash_options(&a).unwrap(), hash_options(&b).unwrap());
+    }
+
+    #[test]
+    fn test_verify() {
+        let spec = BoxSpec::new(options(), Some(DIGEST.to_string())).unwrap();
+        let json = serde_json::to_string(&spec).unwrap();
+        let loaded: BoxSpec = serde_json::from_str(&json).unwrap();
+        assert!(loaded.verify().is_ok());
+
+        let mut edited = loaded.clone();
+        edited.options.cpus = Some(8);
+        assert!(edited.verify().is_err());
+
+        let mut newer = loaded;
+        newer.version = SPEC_VERSION + 1;
+        assert!(newer.verify().is_err());
+    }
+}
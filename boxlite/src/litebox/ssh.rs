@@ -0,0 +1,185 @@
+//! SSH access to a box, see [`LiteBox::enable_ssh`](crate::LiteBox::enable_ssh).
+//!
+//! The image's OpenSSH or Dropbear server runs in the container on
+//! [`GUEST_PORT`], and a port on the host's loopback is relayed to it through
+//! the network backend's tunnel, so the box needs no published port.
+
+use std::net::{IpAddr, Ipv4Addr};
+use std::path::PathBuf;
+
+use boxlite_shared::errors::{BoxliteError, BoxliteResult};
+use tokio::net::TcpListener;
+use tokio::task::JoinHandle;
+
+use super::tunnel;
+use crate::runtime::types::BoxID;
+
+/// Port of the SSH server in the box; unprivileged so that it also starts
+/// for non-root users, and clear of a server the image may run on 22.
+pub(crate) const GUEST_PORT: u16 = 2222;
+
+/// Exit code of [`SETUP_SCRIPT`] when the image has no SSH server.
+pub(crate) const NO_SERVER: i32 = 127;
+
+/// Authorizes key `$1` for the command's user, prints the user, and with
+/// `$3 = start` starts the image's SSH server on port `$2` in the
+/// background. The host key is kept in the user's `~/.ssh`, which works
+/// without root too.
+pub(crate) const SETUP_SCRIPT: &str = r#"set -e
+key=$1
+port=$2
+home=$(sed -n "s/^[^:]*:[^:]*:$(id -u):[^:]*:[^:]*:\([^:]*\):.*/\1/p" /etc/passwd | head -n 1)
+[ -n "$home" ] || home=${HOME:-/root}
+mkdir -p "$home/.ssh"
+chmod 700 "$home/.ssh"
+touch "$home/.ssh/authorized_keys"
+chmod 600 "$home/.ssh/authorized_keys"
+grep -qxF "$key" "$home/.ssh/authorized_keys" || printf '%s\n' "$key" >> "$home/.ssh/authorized_keys"
+id -un
+[ "$3" = start ] || exit 0
+
+PATH=$PATH:/usr/local/sbin:/usr/sbin:/sbin
+if command -v sshd >/dev/null 2>&1; then
+    host_key=$home/.ssh/boxlite_host_ed25519_key
+    [ -f "$host_key" ] || ssh-keygen -q -t ed25519 -N '' -f "$host_key"
+    mkdir -p /run/sshd 2>/dev/null || true
+    exec "$(command -v sshd)" -p "$port" -h "$host_key" \
+        -o PasswordAuthentication=no -o KbdInteractiveAuthentication=no \
+        -o PermitRootLogin=prohibit-password
+fi
+if command -v dropbear >/dev/null 2>&1; then
+    exec dropbear -R -s -p "$port"
+fi
+echo "no SSH server (sshd or dropbear) found" >&2
+exit 127
+"#;
+
+/// Where to connect for SSH access to a box.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SshAccess {
+    /// Host address of the forwarded port (loopback)
+    pub host: IpAddr,
+    /// Host port forwarded to the box's SSH server
+    pub port: u16,
+    /// User whose `authorized_keys` holds the key
+    pub user: String,
+}
+
+impl SshAccess {
+    /// `ssh` command line connecting to the box.
+    pub fn command(&self) -> String {
+        format!("ssh -p {} {}@{}", self.port, self.user, self.host)
+    }
+}
+
+/// Check that `key` is one line of an `authorized_keys` file: a key type
+/// and base64 key, optionally followed by a comment.
+pub(crate) fn validate_key(key: &str) -> BoxliteResult<()> {
+    let mut fields = key.split_whitespace();
+    let valid = !key.contains(['\n', '\r'])
+        && fields.next().is_some_and(|kind| {
+            kind.starts_with("ssh-") || kind.starts_with("ecdsa-") || kind.starts_with("sk-")
+        })
+        && fields.next().is_some();
+    if !valid {
+        return Err(BoxliteError::InvalidArgument(
+            "SSH key must be a single public key line, e.g. \"ssh-ed25519 AAAA... user@host\""
+                .to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Host loopback port relayed to the SSH server of a box, closed when
+/// dropped.
+pub(crate) struct SshForward {
+    port: u16,
+    task: JoinHandle<()>,
+}
+
+impl SshForward {
+    /// Listen on a free loopback port, relaying each connection to
+    /// [`GUEST_PORT`] through `tunnel_socket`.
+    pub(crate) async fn start(box_id: BoxID, tunnel_socket: PathBuf) -> BoxliteResult<Self> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+            .await
+            .map_err(|e| BoxliteError::Network(format!("Failed to listen for SSH: {}", e)))?;
+        let port = listener.local_addr()?.port();
+
+        let task = tokio::spawn(async move {
+            loop {
+                let mut client = match listener.accept().await {
+                    Ok((client, _)) => client,
+                    Err(e) => {
+                        tracing::debug!(error = %e, "Failed to accept SSH connection");
+                        continue;
+                    }
+                };
+                let box_id = box_id.clone();
+                let tunnel_socket = tunnel_socket.clone();
+                tokio::spawn(async move {
+                    match tunnel::connect(&tunnel_socket, &box_id, GUEST_PORT).await {
+                        Ok(mut guest) => {
+                            let _ = tokio::io::copy_bidirectional(&mut client, &mut guest).await;
+                        }
+                        Err(e) => {
+                            tracing::debug!(box_id = %box_id, error = %e, "Failed to relay SSH connection");
+                        }
+                    }
+                });
+            }
+        });
+        Ok(Self { port, task })
+    }
+
+    /// Host port of the forward.
+    pub(crate) fn port(&self) -> u16 {
+        self.port
+    }
+}
+
+impl Drop for SshForward {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpStream, UnixListener};
+
+    #[test]
+    fn test_validate_key() {
+        assert!(validate_key("ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAI user@host").is_ok());
+        assert!(validate_key("ecdsa-sha2-nistp256 AAAAE2VjZHNh").is_ok());
+        assert!(validate_key("").is_err());
+        assert!(validate_key("ssh-ed25519").is_err());
+        assert!(validate_key("AAAAC3NzaC1lZDI1NTE5AAAAI").is_err());
+        assert!(validate_key("ssh-ed25519 AAAA\nssh-rsa BBBB").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_forward_relays_to_guest_port() {
+        let dir = tempfile::tempdir().unwrap();
+        let socket = dir.path().join("tunnel.sock");
+        let listener = UnixListener::bind(&socket).unwrap();
+        tokio::spawn(async move {
+            let (mut conn, _) = listener.accept().await.unwrap();
+            let mut port = [0u8; 2];
+            conn.read_exact(&mut port).await.unwrap();
+            assert_eq!(u16::from_be_bytes(port), GUEST_PORT);
+            conn.write_all(&[tunnel::TUNNEL_OK]).await.unwrap();
+            conn.write_all(b"SSH-2.0-OpenSSH_9.6\r\n").await.unwrap();
+        });
+
+        let forward = SshForward::start(BoxID::new(), socket).await.unwrap();
+        let mut client = TcpStream::connect((Ipv4Addr::LOCALHOST, forward.port()))
+            .await
+            .unwrap();
+        let mut banner = vec![0u8; 21];
+        client.read_exact(&mut banner).await.unwrap();
+        assert_eq!(banner, b"SSH-2.0-OpenSSH_9.6\r\n");
+    }
+}
@@ -3,7 +3,9 @@
 //! Defines the possible states of a box and valid transitions between them.
 
 use crate::ContainerID;
+use crate::litebox::HealthStatus;
 use crate::lock::LockId;
+use crate::runtime::types::PortMapping;
 use boxlite_shared::errors::{BoxliteError, BoxliteResult};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
@@ -25,6 +27,9 @@ pub enum BoxStatus {
     /// Box is running and guest server is accepting commands.
     Running,
 
+    /// VM is frozen; the process is alive but the guest does not run.
+    Paused,
+
     /// Box is shutting down gracefully.
     Stopping,
 
@@ -36,13 +41,20 @@ pub enum BoxStatus {
 impl BoxStatus {
     /// Check if this status represents an active VM (process may be running).
     pub fn is_active(&self) -> bool {
-        matches!(self, BoxStatus::Starting | BoxStatus::Running)
+        matches!(
+            self,
+            BoxStatus::Starting | BoxStatus::Running | BoxStatus::Paused
+        )
     }
 
     pub fn is_running(&self) -> bool {
         matches!(self, BoxStatus::Running)
     }
 
+    pub fn is_paused(&self) -> bool {
+        matches!(self, BoxStatus::Paused)
+    }
+
     pub fn is_starting(&self) -> bool {
         matches!(self, BoxStatus::Starting)
     }
@@ -65,7 +77,10 @@ impl BoxStatus {
     ///
     /// Starting boxes can be stopped because the VM was never fully spawned.
     pub fn can_stop(&self) -> bool {
-        matches!(self, BoxStatus::Running | BoxStatus::Starting)
+        matches!(
+            self,
+            BoxStatus::Running | BoxStatus::Starting | BoxStatus::Paused
+        )
     }
 
     /// Check if remove() can be called from this state.
@@ -101,6 +116,12 @@ impl BoxStatus {
             (Running, Stopping) |
             (Running, Stopped) |
             (Running, Unknown) |
+            // Running ⇄ Paused (freeze/thaw)
+            (Running, Paused) |
+            (Paused, Running) |
+            // Paused → Stopped (stop or crash)
+            (Paused, Stopped) |
+            (Paused, Unknown) |
             // Stopping → Stopped (complete) or Unknown (error)
             (Stopping, Stopped) |
             (Stopping, Unknown) |
@@ -116,6 +137,7 @@ impl BoxStatus {
             BoxStatus::Unknown => "unknown",
             BoxStatus::Starting => "starting",
             BoxStatus::Running => "running",
+            BoxStatus::Paused => "paused",
             BoxStatus::Stopping => "stopping",
             BoxStatus::Stopped => "stopped",
         }
@@ -130,6 +152,7 @@ impl std::str::FromStr for BoxStatus {
             "unknown" => Ok(BoxStatus::Unknown),
             "starting" => Ok(BoxStatus::Starting),
             "running" => Ok(BoxStatus::Running),
+            "paused" => Ok(BoxStatus::Paused),
             "stopping" => Ok(BoxStatus::Stopping),
             "stopped" => Ok(BoxStatus::Stopped),
             _ => Err(()),
@@ -160,6 +183,42 @@ pub struct BoxState {
     /// Allocated when the box is first initialized (not at creation time).
     /// Used to retrieve the lock across process restarts.
     pub lock_id: Option<LockId>,
+    /// Result of the image HEALTHCHECK (None if the image has none).
+    #[serde(default)]
+    pub health: Option<HealthStatus>,
+    /// Config digest of the image the box last booted from.
+    #[serde(default)]
+    pub image_digest: Option<String>,
+    /// Manifest digest of the same image, which box specs pin.
+    #[serde(default)]
+    pub image_manifest: Option<String>,
+    /// Ports forwarded into the box when it last booted.
+    #[serde(default)]
+    pub ports: Vec<PortMapping>,
+    /// How the box last went from running to stopped.
+    #[serde(default)]
+    pub last_exit: Option<LastExit>,
+}
+
+/// How a box last stopped, in [`BoxState::last_exit`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LastExit {
+    pub reason: ExitReason,
+    pub at: DateTime<Utc>,
+}
+
+/// Why a box stopped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExitReason {
+    /// Stopped through `stop()`, including a TTL or drain.
+    Stopped,
+    /// Killed through `kill()`.
+    Killed,
+    /// Suspended after `idle_timeout`.
+    Idle,
+    /// The VM went away without being stopped.
+    Crashed,
 }
 
 impl BoxState {
@@ -171,9 +230,22 @@ impl BoxState {
             container_id: None,
             last_updated: Utc::now(),
             lock_id: None,
+            health: None,
+            image_digest: None,
+            image_manifest: None,
+            ports: Vec::new(),
+            last_exit: None,
         }
     }
 
+    /// Record that the box stopped now, for `reason`.
+    pub fn record_exit(&mut self, reason: ExitReason) {
+        self.last_exit = Some(LastExit {
+            reason,
+            at: Utc::now(),
+        });
+    }
+
     /// Set lock ID and update timestamp.
     pub fn set_lock_id(&mut self, lock_id: LockId) {
         self.lock_id = Some(lock_id);
@@ -222,6 +294,7 @@ impl BoxState {
         self.status = BoxStatus::Stopped;
         self.pid = None;
         self.last_updated = Utc::now();
+        self.record_exit(ExitReason::Crashed);
     }
 
     /// Reset state after system reboot.
@@ -251,6 +324,7 @@ mod tests {
     fn test_status_is_active() {
         assert!(BoxStatus::Starting.is_active());
         assert!(BoxStatus::Running.is_active());
+        assert!(BoxStatus::Paused.is_active());
         assert!(!BoxStatus::Stopping.is_active());
         assert!(!BoxStatus::Stopped.is_active());
         assert!(!BoxStatus::Unknown.is_active());
@@ -269,6 +343,7 @@ mod tests {
     fn test_status_can_stop() {
         assert!(BoxStatus::Starting.can_stop()); // Starting boxes can be stopped
         assert!(BoxStatus::Running.can_stop());
+        assert!(BoxStatus::Paused.can_stop());
         assert!(!BoxStatus::Stopping.can_stop());
         assert!(!BoxStatus::Stopped.can_stop());
         assert!(!BoxStatus::Unknown.can_stop());
@@ -278,6 +353,7 @@ mod tests {
     fn test_status_can_exec() {
         assert!(BoxStatus::Starting.can_exec());
         assert!(BoxStatus::Running.can_exec());
+        assert!(!BoxStatus::Paused.can_exec());
         assert!(!BoxStatus::Stopping.can_exec());
         assert!(BoxStatus::Stopped.can_exec()); // Triggers restart
         assert!(!BoxStatus::Unknown.can_exec());
@@ -295,6 +371,13 @@ mod tests {
         assert!(BoxStatus::Running.can_transition_to(BoxStatus::Stopped));
         assert!(!BoxStatus::Running.can_transition_to(BoxStatus::Starting));
 
+        // Paused transitions
+        assert!(BoxStatus::Running.can_transition_to(BoxStatus::Paused));
+        assert!(BoxStatus::Paused.can_transition_to(BoxStatus::Running));
+        assert!(BoxStatus::Paused.can_transition_to(BoxStatus::Stopped));
+        assert!(!BoxStatus::Paused.can_transition_to(BoxStatus::Starting));
+        assert!(!BoxStatus::Stopped.can_transition_to(BoxStatus::Paused));
+
         // Stopping transitions
         assert!(BoxStatus::Stopping.can_transition_to(BoxStatus::Stopped));
         assert!(!BoxStatus::Stopping.can_transition_to(BoxStatus::Running));
@@ -368,6 +451,31 @@ mod tests {
         assert_eq!(state.status, BoxStatus::Stopped);
     }
 
+    #[test]
+    fn test_mark_crashed_records_exit() {
+        let mut state = BoxState::new();
+        state.status = BoxStatus::Running;
+        state.pid = Some(1234);
+
+        state.mark_crashed();
+
+        assert_eq!(state.status, BoxStatus::Stopped);
+        assert_eq!(
+            state.last_exit.map(|exit| exit.reason),
+            Some(ExitReason::Crashed)
+        );
+    }
+
+    #[test]
+    fn test_state_without_new_fields_loads() {
+        let json = r#"{"status":"running","pid":42,"container_id":null,
+            "last_updated":"2024-01-01T00:00:00Z","lock_id":null}"#;
+        let state: BoxState = serde_json::from_str(json).unwrap();
+        assert_eq!(state.status, BoxStatus::Running);
+        assert!(state.ports.is_empty());
+        assert!(state.last_exit.is_none());
+    }
+
     #[test]
     fn test_status_as_str() {
         assert_eq!(BoxStatus::Unknown.as_str(), "unknown");
@@ -0,0 +1,337 @@
+//! Incremental directory sync for [`LiteBox::sync_dir`](crate::LiteBox::sync_dir).
+//!
+//! Both trees are listed and compared entry by entry: by type and
+//! permissions, then by size and modification time, or by SHA-256 digest
+//! in checksum mode. Only changed entries are sent, as a tar stream.
+//! Extraction keeps modification times, so a file that was synced once
+//! compares equal on the next run.
+
+use std::collections::{BTreeMap, HashMap};
+use std::io::{self, Read, Write};
+use std::os::unix::fs::{MetadataExt, PermissionsExt};
+use std::path::{Component, Path};
+
+use boxlite_shared::errors::{BoxliteError, BoxliteResult};
+use boxlite_shared::{FileEntry, FileKind};
+use sha2::{Digest, Sha256};
+use tokio::io::{AsyncWriteExt, DuplexStream};
+use walkdir::WalkDir;
+
+/// Buffer size of the pipe between the tar builder and the upload.
+const PIPE_SIZE: usize = 64 * 1024;
+
+/// Options for [`LiteBox::sync_dir`](crate::LiteBox::sync_dir).
+#[derive(Clone, Debug, Default)]
+pub struct SyncOptions {
+    /// Remove entries from the guest directory that are not on the host.
+    pub delete: bool,
+    /// Compare regular files by content digest instead of modification
+    /// time. Slower, but catches changes that keep size and mtime.
+    pub checksum: bool,
+    /// File or directory names skipped on both sides, e.g. `.git` or
+    /// `node_modules`. Matched against every path component.
+    pub exclude: Vec<String>,
+}
+
+/// What a [`LiteBox::sync_dir`](crate::LiteBox::sync_dir) call changed.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SyncStats {
+    /// Files and symlinks copied into the box.
+    pub files: u64,
+    /// Bytes of file content copied.
+    pub bytes: u64,
+    /// Entries removed from the box (directories count once).
+    pub deleted: u64,
+}
+
+/// Entries to change in the guest directory.
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct SyncPlan {
+    /// Paths to remove first, without paths below an already removed one.
+    pub remove: Vec<String>,
+    /// Paths to copy, parents before children.
+    pub copy: Vec<String>,
+    /// Files and symlinks in `copy`.
+    pub files: u64,
+    /// Bytes of file content in `copy`.
+    pub bytes: u64,
+}
+
+impl SyncOptions {
+    fn excluded(&self, path: &str) -> bool {
+        !self.exclude.is_empty()
+            && path
+                .split('/')
+                .any(|name| self.exclude.iter().any(|e| e == name))
+    }
+}
+
+/// List the host directory, keyed by path relative to `dir`.
+pub(crate) fn host_manifest(
+    dir: &Path,
+    options: &SyncOptions,
+) -> BoxliteResult<BTreeMap<String, FileEntry>> {
+    let mut entries = BTreeMap::new();
+    let walker = WalkDir::new(dir)
+        .min_depth(1)
+        .follow_links(false)
+        .into_iter()
+        .filter_entry(|e| {
+            !options
+                .exclude
+                .iter()
+                .any(|name| e.file_name() == name.as_str())
+        });
+
+    for entry in walker {
+        let entry = entry.map_err(|e| {
+            BoxliteError::Storage(format!("Failed to walk {}: {}", dir.display(), e))
+        })?;
+        let metadata = entry.path().symlink_metadata()?;
+        let file_type = metadata.file_type();
+        let (kind, link_target) = if file_type.is_symlink() {
+            let target = std::fs::read_link(entry.path())?;
+            (FileKind::Symlink, target.to_string_lossy().into_owned())
+        } else if file_type.is_dir() {
+            (FileKind::Directory, String::new())
+        } else if file_type.is_file() {
+            (FileKind::Regular, String::new())
+        } else {
+            continue;
+        };
+
+        let relative = entry
+            .path()
+            .strip_prefix(dir)
+            .unwrap_or(entry.path())
+            .to_string_lossy()
+            .into_owned();
+        entries.insert(
+            relative.clone(),
+            FileEntry {
+                path: relative,
+                kind: kind as i32,
+                mode: metadata.permissions().mode() & 0o7777,
+                size: if file_type.is_file() {
+                    metadata.len()
+                } else {
+                    0
+                },
+                mtime: metadata.mtime(),
+                link_target,
+                sha256: String::new(),
+            },
+        );
+    }
+    Ok(entries)
+}
+
+/// Compare the host manifest with the guest listing.
+///
+/// In checksum mode, host files are hashed only when the guest has a file
+/// of the same size.
+pub(crate) fn plan(
+    host_dir: &Path,
+    host: &BTreeMap<String, FileEntry>,
+    guest: Vec<FileEntry>,
+    options: &SyncOptions,
+) -> BoxliteResult<SyncPlan> {
+    let guest: HashMap<String, FileEntry> = guest
+        .into_iter()
+        .filter(|e| !options.excluded(&e.path))
+        .map(|e| (e.path.clone(), e))
+        .collect();
+
+    let mut plan = SyncPlan::default();
+
+    // Guest entries that are gone on the host or changed type
+    let mut stale: Vec<&String> = guest
+        .iter()
+        .filter(|(path, entry)| match host.get(*path) {
+            Some(local) => local.kind != entry.kind,
+            None => options.delete,
+        })
+        .map(|(path, _)| path)
+        .collect();
+    stale.sort();
+    for path in stale {
+        let under_removed = plan.remove.last().is_some_and(|dir| {
+            path.starts_with(dir.as_str()) && path[dir.len()..].starts_with('/')
+        });
+        if !under_removed {
+            plan.remove.push(path.clone());
+        }
+    }
+
+    for (path, local) in host {
+        let changed = match guest.get(path) {
+            None => true,
+            Some(remote) if remote.kind != local.kind || remote.mode != local.mode => true,
+            Some(remote) => match local.kind() {
+                FileKind::Directory | FileKind::Other => false,
+                FileKind::Symlink => remote.link_target != local.link_target,
+                FileKind::Regular if remote.size != local.size => true,
+                FileKind::Regular if options.checksum => {
+                    file_digest(&host_dir.join(path))? != remote.sha256
+                }
+                FileKind::Regular => remote.mtime != local.mtime,
+            },
+        };
+        if !changed {
+            continue;
+        }
+        if local.kind() != FileKind::Directory {
+            plan.files += 1;
+            plan.bytes += local.size;
+        }
+        plan.copy.push(path.clone());
+    }
+    Ok(plan)
+}
+
+/// Write a tar of `paths` (relative to `dir`) into `pipe`.
+///
+/// Runs on a blocking thread; the other end of the pipe is uploaded
+/// while the archive is built.
+pub(crate) fn write_archive(dir: &Path, paths: &[String], pipe: DuplexStream) -> io::Result<()> {
+    let mut builder = tar::Builder::new(PipeWriter {
+        handle: tokio::runtime::Handle::current(),
+        pipe,
+    });
+    builder.follow_symlinks(false);
+    for path in paths {
+        let relative = Path::new(path);
+        if relative
+            .components()
+            .any(|c| !matches!(c, Component::Normal(_)))
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Invalid sync path: {}", path),
+            ));
+        }
+        builder.append_path_with_name(dir.join(relative), relative)?;
+    }
+    builder.into_inner()?.shutdown()
+}
+
+/// Blocking writer over the async pipe.
+struct PipeWriter {
+    handle: tokio::runtime::Handle,
+    pipe: DuplexStream,
+}
+
+impl PipeWriter {
+    fn shutdown(mut self) -> io::Result<()> {
+        self.handle.block_on(self.pipe.shutdown())
+    }
+}
+
+impl Write for PipeWriter {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        self.handle.block_on(self.pipe.write(data))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.handle.block_on(self.pipe.flush())
+    }
+}
+
+/// Create the pipe for [`write_archive`].
+pub(crate) fn pipe() -> (DuplexStream, DuplexStream) {
+    tokio::io::duplex(PIPE_SIZE)
+}
+
+fn file_digest(path: &Path) -> BoxliteResult<String> {
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0; PIPE_SIZE];
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_plan_copies_changes_only() {
+        let host = tempfile::tempdir().unwrap();
+        fs::create_dir_all(host.path().join("src")).unwrap();
+        fs::write(host.path().join("src/main.rs"), "fn main() {}").unwrap();
+        fs::write(host.path().join("README"), "same").unwrap();
+        fs::create_dir_all(host.path().join(".git/objects")).unwrap();
+        fs::write(host.path().join("build"), "now a file").unwrap();
+
+        let options = SyncOptions {
+            delete: true,
+            exclude: vec![".git".into()],
+            ..Default::default()
+        };
+        let manifest = host_manifest(host.path(), &options).unwrap();
+        assert!(!manifest.keys().any(|p| p.starts_with(".git")));
+
+        // The guest has an old main.rs, the same README, a stale file, a
+        // directory where the host has a file and an excluded directory
+        let mut guest = vec![
+            manifest["src"].clone(),
+            manifest["README"].clone(),
+            FileEntry {
+                size: 3,
+                ..manifest["src/main.rs"].clone()
+            },
+            FileEntry {
+                path: "old.txt".into(),
+                ..manifest["README"].clone()
+            },
+            FileEntry {
+                path: "build".into(),
+                kind: FileKind::Directory as i32,
+                ..manifest["src"].clone()
+            },
+            FileEntry {
+                path: "build/out".into(),
+                ..manifest["README"].clone()
+            },
+            FileEntry {
+                path: ".git".into(),
+                ..manifest["src"].clone()
+            },
+        ];
+        guest.reverse();
+
+        let plan = plan(host.path(), &manifest, guest, &options).unwrap();
+        assert_eq!(plan.remove, ["build", "old.txt"]);
+        assert_eq!(plan.copy, ["build", "src/main.rs"]);
+        assert_eq!((plan.files, plan.bytes), (2, 22));
+    }
+
+    #[test]
+    fn test_plan_checksum_mode() {
+        let host = tempfile::tempdir().unwrap();
+        fs::write(host.path().join("a"), "abc").unwrap();
+        let options = SyncOptions {
+            checksum: true,
+            ..Default::default()
+        };
+        let manifest = host_manifest(host.path(), &options).unwrap();
+
+        let remote = |sha256: &str| FileEntry {
+            mtime: 0,
+            sha256: sha256.into(),
+            ..manifest["a"].clone()
+        };
+        let same = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+        let unchanged = plan(host.path(), &manifest, vec![remote(same)], &options).unwrap();
+        assert!(unchanged.copy.is_empty());
+        let changed = plan(host.path(), &manifest, vec![remote("00")], &options).unwrap();
+        assert_eq!(changed.copy, ["a"]);
+    }
+}
@@ -0,0 +1,141 @@
+//! Options and progress reporting for
+//! [`LiteBox::upload`](crate::LiteBox::upload) and
+//! [`LiteBox::download`](crate::LiteBox::download).
+//!
+//! Transfers stream in chunks, so neither side holds the whole file. An
+//! interrupted transfer is resumed where it stopped once the connection to
+//! the guest is back, instead of starting over.
+
+use std::fmt;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use tokio::io::{AsyncRead, ReadBuf};
+
+/// Called as a transfer makes progress.
+pub type ProgressCallback = Arc<dyn Fn(TransferProgress) + Send + Sync>;
+
+/// How far a transfer is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TransferProgress {
+    /// Bytes transferred so far, including those of earlier attempts.
+    pub transferred: u64,
+    /// File size.
+    pub total: u64,
+}
+
+/// Options for [`LiteBox::upload`](crate::LiteBox::upload) and
+/// [`LiteBox::download`](crate::LiteBox::download).
+#[derive(Clone)]
+pub struct TransferOptions {
+    /// Called after every chunk.
+    pub progress: Option<ProgressCallback>,
+    /// How often to resume after the connection to the guest broke.
+    pub max_retries: u32,
+}
+
+impl TransferOptions {
+    /// Report progress to `callback`.
+    pub fn on_progress(
+        mut self,
+        callback: impl Fn(TransferProgress) + Send + Sync + 'static,
+    ) -> Self {
+        self.progress = Some(Arc::new(callback));
+        self
+    }
+
+    pub(crate) fn report(&self, transferred: u64, total: u64) {
+        if let Some(progress) = &self.progress {
+            progress(TransferProgress { transferred, total });
+        }
+    }
+
+    /// Wait before retry `attempt` (starting at 1).
+    pub(crate) fn backoff(attempt: u32) -> Duration {
+        Duration::from_millis(500 * u64::from(attempt.min(10)))
+    }
+}
+
+impl Default for TransferOptions {
+    fn default() -> Self {
+        Self {
+            progress: None,
+            max_retries: 3,
+        }
+    }
+}
+
+impl fmt::Debug for TransferOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TransferOptions")
+            .field("progress", &self.progress.is_some())
+            .field("max_retries", &self.max_retries)
+            .finish()
+    }
+}
+
+/// Reports upload progress as the content is read.
+pub(crate) struct ProgressReader<R> {
+    inner: R,
+    transferred: u64,
+    total: u64,
+    options: TransferOptions,
+}
+
+impl<R> ProgressReader<R> {
+    /// Wrap `inner`, which starts `offset` bytes into the file.
+    pub fn new(inner: R, offset: u64, total: u64, options: TransferOptions) -> Self {
+        Self {
+            inner,
+            transferred: offset,
+            total,
+            options,
+        }
+    }
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for ProgressReader<R> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        let before = buf.filled().len();
+        let result = Pin::new(&mut self.inner).poll_read(cx, buf);
+        let read = (buf.filled().len() - before) as u64;
+        if read > 0 {
+            self.transferred += read;
+            self.options.report(self.transferred, self.total);
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use tokio::io::AsyncReadExt;
+
+    #[tokio::test]
+    async fn test_progress_reader_counts_from_offset() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let sink = seen.clone();
+        let options = TransferOptions::default().on_progress(move |p| sink.lock().unwrap().push(p));
+
+        let mut reader = ProgressReader::new(&b"world"[..], 6, 11, options);
+        let mut rest = String::new();
+        reader.read_to_string(&mut rest).await.unwrap();
+
+        assert_eq!(rest, "world");
+        assert_eq!(
+            seen.lock().unwrap().last(),
+            Some(&TransferProgress {
+                transferred: 11,
+                total: 11
+            })
+        );
+    }
+}
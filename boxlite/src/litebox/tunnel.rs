@@ -0,0 +1,40 @@
+//! Connections from the host to guest ports through the network backend's
+//! tunnel socket, without port forwards.
+//!
+//! A client sends the guest port (2 bytes, big endian) and reads a status
+//! byte; on [`TUNNEL_OK`] the stream carries the connection to the guest.
+
+use std::path::Path;
+
+use boxlite_shared::errors::{BoxliteError, BoxliteResult};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::UnixStream;
+
+use crate::runtime::types::BoxID;
+
+/// Status byte sent by the backend once connected to the guest port.
+pub(crate) const TUNNEL_OK: u8 = 0;
+
+/// Open a connection to `port` of the guest of `box_id`.
+pub(crate) async fn connect(
+    tunnel_socket: &Path,
+    box_id: &BoxID,
+    port: u16,
+) -> BoxliteResult<UnixStream> {
+    let mut stream = UnixStream::connect(tunnel_socket).await.map_err(|e| {
+        BoxliteError::Network(format!(
+            "Failed to reach the network of box {}: {}",
+            box_id, e
+        ))
+    })?;
+    stream.write_all(&port.to_be_bytes()).await?;
+    let mut status = [0u8; 1];
+    stream.read_exact(&mut status).await?;
+    if status[0] != TUNNEL_OK {
+        return Err(BoxliteError::Network(format!(
+            "port {} of box {} refused the connection",
+            port, box_id
+        )));
+    }
+    Ok(stream)
+}
@@ -0,0 +1,73 @@
+//! Change events returned by [`LiteBox::watch`](crate::LiteBox::watch).
+
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use boxlite_shared::errors::BoxliteResult;
+use boxlite_shared::{WatchEvent, WatchEventKind};
+use futures::Stream;
+
+/// What happened to a watched path.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FileEventKind {
+    /// Created, or moved into the watched tree.
+    Created,
+    /// Closed after being opened for writing.
+    Modified,
+    /// Removed, or moved out of the watched tree.
+    Deleted,
+    /// The guest dropped events because they were not read fast enough.
+    /// Rescan the tree if you need an exact picture.
+    Overflow,
+}
+
+/// A change in a watched file or directory.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FileEvent {
+    pub kind: FileEventKind,
+    /// Absolute path in the box.
+    pub path: String,
+    pub is_dir: bool,
+}
+
+impl From<WatchEvent> for FileEvent {
+    fn from(event: WatchEvent) -> Self {
+        let kind = match event.kind() {
+            WatchEventKind::Created => FileEventKind::Created,
+            WatchEventKind::Modified => FileEventKind::Modified,
+            WatchEventKind::Deleted => FileEventKind::Deleted,
+            WatchEventKind::Overflow => FileEventKind::Overflow,
+        };
+        Self {
+            kind,
+            path: event.path,
+            is_dir: event.is_dir,
+        }
+    }
+}
+
+/// Change events for a path in a box.
+///
+/// Ends after the watched path itself is deleted, or when the box stops.
+/// Dropping the stream removes the watch.
+pub struct WatchStream {
+    inner: Pin<Box<dyn Stream<Item = BoxliteResult<FileEvent>> + Send>>,
+}
+
+impl WatchStream {
+    pub(crate) fn new(
+        inner: impl Stream<Item = BoxliteResult<FileEvent>> + Send + 'static,
+    ) -> Self {
+        Self {
+            inner: Box::pin(inner),
+        }
+    }
+}
+
+impl Stream for WatchStream {
+    type Item = BoxliteResult<FileEvent>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.inner.as_mut().poll_next(cx)
+    }
+}
@@ -146,7 +146,7 @@ impl BoxMetricsStorage {
 ///
 /// Snapshot of metrics at query time.
 /// All counters are monotonic and never reset.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, serde::Serialize)]
 pub struct BoxMetrics {
     /// Commands executed on this box
     pub commands_executed_total: u64,
@@ -6,7 +6,7 @@ use std::sync::atomic::{AtomicU64, Ordering};
 /// Storage for runtime-wide metrics.
 ///
 /// Stored in `RuntimeState`, shared across all operations.
-/// All counters are monotonic (never decrease).
+/// All counters are monotonic (never decrease), except `boots_queued`.
 #[derive(Clone, Default)]
 pub struct RuntimeMetricsStorage {
     /// Total boxes created since runtime startup
@@ -17,6 +17,8 @@ pub struct RuntimeMetricsStorage {
     pub(crate) total_commands: Arc<AtomicU64>,
     /// Total command execution errors across all boxes
     pub(crate) total_exec_errors: Arc<AtomicU64>,
+    /// Boxes waiting for a boot slot (`max_concurrent_boots`) right now
+    pub(crate) boots_queued: Arc<AtomicU64>,
 }
 
 impl RuntimeMetricsStorage {
@@ -29,7 +31,7 @@ impl RuntimeMetricsStorage {
 /// Handle for querying runtime-wide metrics.
 ///
 /// Cloneable, lightweight handle (only Arc pointers).
-/// All counters are monotonic and never reset.
+/// All counters are monotonic and never reset; gauges are current values.
 #[derive(Clone)]
 pub struct RuntimeMetrics {
     storage: RuntimeMetricsStorage,
@@ -84,4 +86,12 @@ impl RuntimeMetrics {
     pub fn total_exec_errors(&self) -> u64 {
         self.storage.total_exec_errors.load(Ordering::Relaxed)
     }
+
+    /// Number of boxes waiting to boot because `max_concurrent_boots`
+    /// boxes are already booting.
+    ///
+    /// Gauge: goes down as boxes get a boot slot.
+    pub fn boot_queue_depth(&self) -> u64 {
+        self.storage.boots_queued.load(Ordering::Relaxed)
+    }
 }
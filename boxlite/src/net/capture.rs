@@ -0,0 +1,110 @@
+//! Traffic captures, see [`LiteBox::capture_traffic`](crate::LiteBox::capture_traffic).
+//!
+//! The network backend records the Ethernet frames between the guest and the
+//! virtual network to a pcap file, readable with Wireshark or tcpdump.
+
+use std::net::IpAddr;
+use std::time::Duration;
+
+use boxlite_shared::errors::{BoxliteError, BoxliteResult};
+use serde::{Deserialize, Serialize};
+
+/// Longest capture, so that a forgotten one does not fill the disk.
+const MAX_DURATION: Duration = Duration::from_secs(3600);
+
+/// Size of the pcap file header, the smallest capture.
+const PCAP_HEADER_SIZE: u64 = 24;
+
+/// Transport protocol of captured packets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CaptureProtocol {
+    Tcp,
+    Udp,
+    /// ICMP and ICMPv6
+    Icmp,
+}
+
+/// Which of the box's traffic a capture records, and for how long.
+///
+/// Without a host, port or protocol every frame is recorded, ARP included.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CaptureFilter {
+    /// Only packets from or to this address
+    pub host: Option<IpAddr>,
+    /// Only TCP and UDP packets from or to this port
+    pub port: Option<u16>,
+    /// Only packets of this protocol
+    pub protocol: Option<CaptureProtocol>,
+    /// Stop after this long (at most an hour)
+    pub duration: Duration,
+    /// Stop before the file grows past this many bytes
+    pub max_bytes: u64,
+}
+
+impl Default for CaptureFilter {
+    fn default() -> Self {
+        Self {
+            host: None,
+            port: None,
+            protocol: None,
+            duration: Duration::from_secs(30),
+            max_bytes: 64 * 1024 * 1024,
+        }
+    }
+}
+
+impl CaptureFilter {
+    /// Check the capture's bounds.
+    pub(crate) fn validate(&self) -> BoxliteResult<()> {
+        if self.duration.is_zero() || self.duration > MAX_DURATION {
+            return Err(BoxliteError::Config(format!(
+                "capture duration must be between 1ms and {}s, got {:?}",
+                MAX_DURATION.as_secs(),
+                self.duration
+            )));
+        }
+        if self.max_bytes < PCAP_HEADER_SIZE {
+            return Err(BoxliteError::Config(format!(
+                "capture size limit must be at least {} bytes, got {}",
+                PCAP_HEADER_SIZE, self.max_bytes
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Outcome of a finished capture.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CaptureStats {
+    /// Packets recorded
+    pub packets: u64,
+    /// Size of the pcap file
+    pub bytes: u64,
+    /// Whether the capture stopped at `max_bytes` rather than at the end of
+    /// its duration
+    pub truncated: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate() {
+        assert!(CaptureFilter::default().validate().is_ok());
+        let filter = |duration, max_bytes| CaptureFilter {
+            duration,
+            max_bytes,
+            ..Default::default()
+        };
+        assert!(filter(Duration::ZERO, 1024).validate().is_err());
+        assert!(filter(Duration::from_secs(7200), 1024).validate().is_err());
+        assert!(filter(Duration::from_secs(1), 10).validate().is_err());
+        assert!(
+            filter(Duration::from_millis(1), PCAP_HEADER_SIZE)
+                .validate()
+                .is_ok()
+        );
+    }
+}
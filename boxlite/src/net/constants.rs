@@ -13,6 +13,27 @@ pub const GATEWAY_IP: &str = "192.168.127.1";
 /// Guest IP address (assigned via DHCP static lease)
 pub const GUEST_IP: &str = "192.168.127.2";
 
+/// Address under which guests reach the host's loopback interface
+///
+/// The network backend translates connections to this IP into connections
+/// to 127.0.0.1 on the host, e.g. for the registry cache.
+pub const HOST_IP: &str = "192.168.127.254";
+
+/// IPv6 subnet of boxes with [`BoxOptions::ipv6`](crate::BoxOptions::ipv6)
+///
+/// A unique local prefix, translated to the host's IPv6 addresses by the
+/// network backend.
+pub const SUBNET_IPV6: &str = "fd42:b0c5:127::/64";
+
+/// Gateway IPv6 address
+pub const GATEWAY_IPV6: &str = "fd42:b0c5:127::1";
+
+/// Guest IPv6 address
+pub const GUEST_IPV6: &str = "fd42:b0c5:127::2";
+
+/// Prefix length of [`SUBNET_IPV6`]
+pub const IPV6_PREFIX_LEN: u8 = 64;
+
 /// Gateway MAC address
 ///
 /// This MAC is used by gvproxy's virtual network interface.
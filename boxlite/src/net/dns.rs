@@ -0,0 +1,253 @@
+//! Host resolver for box names, see [`BoxliteOptions::dns_listen`](crate::BoxliteOptions::dns_listen).
+//!
+//! A minimal DNS server answering `<name>.boxlite` with the host address
+//! the running box's published ports listen on. The host's resolver is
+//! pointed at it for the `boxlite` domain, e.g. with `/etc/resolver/boxlite`
+//! on macOS or a DNS route of systemd-resolved.
+
+use std::net::{Ipv4Addr, SocketAddr, UdpSocket};
+
+use boxlite_shared::errors::{BoxliteError, BoxliteResult};
+use tokio::sync::oneshot;
+
+use crate::db::BoxStore;
+
+/// Domain of box names.
+const DOMAIN: &str = "boxlite";
+
+/// Boxes come and go, so their answers are not cached long.
+const TTL_SECS: u32 = 5;
+
+const TYPE_A: u16 = 1;
+const TYPE_ANY: u16 = 255;
+const CLASS_IN: u16 = 1;
+
+const RCODE_NOERROR: u16 = 0;
+const RCODE_NXDOMAIN: u16 = 3;
+const RCODE_NOTIMP: u16 = 4;
+const RCODE_REFUSED: u16 = 5;
+
+/// Running box name resolver, stopped when dropped.
+pub(crate) struct BoxDns {
+    _shutdown: oneshot::Sender<()>,
+}
+
+impl BoxDns {
+    /// Start serving on `listen` (UDP).
+    ///
+    /// The server runs on its own thread, like the registry cache.
+    pub fn start(listen: SocketAddr, boxes: BoxStore) -> BoxliteResult<Self> {
+        let socket = UdpSocket::bind(listen).map_err(|e| {
+            BoxliteError::Network(format!("Failed to listen for DNS on {}: {}", listen, e))
+        })?;
+        socket.set_nonblocking(true)?;
+
+        let (shutdown_tx, shutdown_rx) = oneshot::channel();
+        std::thread::Builder::new()
+            .name("box-dns".into())
+            .spawn(move || {
+                let runtime = match tokio::runtime::Builder::new_current_thread()
+                    .enable_all()
+                    .build()
+                {
+                    Ok(runtime) => runtime,
+                    Err(e) => {
+                        tracing::error!(error = %e, "Failed to start box DNS");
+                        return;
+                    }
+                };
+                runtime.block_on(serve(socket, boxes, shutdown_rx));
+            })?;
+
+        tracing::info!(%listen, "Started box DNS");
+        Ok(Self {
+            _shutdown: shutdown_tx,
+        })
+    }
+}
+
+async fn serve(socket: UdpSocket, boxes: BoxStore, mut shutdown: oneshot::Receiver<()>) {
+    let socket = match tokio::net::UdpSocket::from_std(socket) {
+        Ok(socket) => socket,
+        Err(e) => {
+            tracing::error!(error = %e, "Failed to start box DNS");
+            return;
+        }
+    };
+
+    let mut buf = [0u8; 512];
+    loop {
+        let (n, peer) = tokio::select! {
+            _ = &mut shutdown => break,
+            received = socket.recv_from(&mut buf) => match received {
+                Ok(received) => received,
+                Err(e) => {
+                    tracing::debug!(error = %e, "Box DNS failed to receive a query");
+                    continue;
+                }
+            },
+        };
+        if let Some(reply) = respond(&buf[..n], |name| lookup(&boxes, name)) {
+            let _ = socket.send_to(&reply, peer).await;
+        }
+    }
+}
+
+/// Address of the running box named `name`: the host address of its first
+/// IPv4 port, loopback when its ports listen on all addresses.
+fn lookup(boxes: &BoxStore, name: &str) -> Option<Ipv4Addr> {
+    let active = match boxes.list_active() {
+        Ok(active) => active,
+        Err(e) => {
+            tracing::warn!(error = %e, "Box DNS failed to list boxes");
+            return None;
+        }
+    };
+    let (config, _) = active.into_iter().find(|(config, _)| {
+        config
+            .name
+            .as_deref()
+            .is_some_and(|n| n.eq_ignore_ascii_case(name))
+    })?;
+
+    let host_ip = config
+        .options
+        .ports
+        .iter()
+        .find_map(|port| match port.host_ip.as_deref() {
+            None => Some(Ipv4Addr::UNSPECIFIED),
+            Some(ip) => ip.parse::<Ipv4Addr>().ok(),
+        })
+        .unwrap_or(Ipv4Addr::UNSPECIFIED);
+    if host_ip.is_unspecified() {
+        Some(Ipv4Addr::LOCALHOST)
+    } else {
+        Some(host_ip)
+    }
+}
+
+/// Reply to a DNS query, resolving box names with `lookup`; None for
+/// messages that are not queries.
+fn respond(query: &[u8], lookup: impl Fn(&str) -> Option<Ipv4Addr>) -> Option<Vec<u8>> {
+    if query.len() < 12 {
+        return None;
+    }
+    let flags = u16::from_be_bytes([query[2], query[3]]);
+    let qdcount = u16::from_be_bytes([query[4], query[5]]);
+    // Responses are not ours to answer
+    if flags & 0x8000 != 0 {
+        return None;
+    }
+
+    let reply = |rcode: u16, question: &[u8], answer: Option<Ipv4Addr>| {
+        let mut out = Vec::with_capacity(12 + question.len() + 16);
+        out.extend_from_slice(&query[0..2]);
+        // QR, authoritative, recursion desired as asked
+        let flags = 0x8000 | 0x0400 | (flags & 0x7900) | rcode;
+        out.extend_from_slice(&flags.to_be_bytes());
+        out.extend_from_slice(&u16::from(!question.is_empty()).to_be_bytes());
+        out.extend_from_slice(&u16::from(answer.is_some()).to_be_bytes());
+        out.extend_from_slice(&[0, 0, 0, 0]);
+        out.extend_from_slice(question);
+        if let Some(ip) = answer {
+            // Name: pointer to the question's
+            out.extend_from_slice(&[0xc0, 0x0c]);
+            out.extend_from_slice(&TYPE_A.to_be_bytes());
+            out.extend_from_slice(&CLASS_IN.to_be_bytes());
+            out.extend_from_slice(&TTL_SECS.to_be_bytes());
+            out.extend_from_slice(&4u16.to_be_bytes());
+            out.extend_from_slice(&ip.octets());
+        }
+        out
+    };
+
+    // Standard queries with one question only
+    if (flags >> 11) & 0xf != 0 || qdcount != 1 {
+        return Some(reply(RCODE_NOTIMP, &[], None));
+    }
+    let (name, end) = parse_name(query, 12)?;
+    let question = query.get(12..end + 4)?;
+    let qtype = u16::from_be_bytes([query[end], query[end + 1]]);
+    let qclass = u16::from_be_bytes([query[end + 2], query[end + 3]]);
+
+    let Some(box_name) = name
+        .to_ascii_lowercase()
+        .strip_suffix(&format!(".{}", DOMAIN))
+        .map(str::to_string)
+    else {
+        return Some(reply(RCODE_REFUSED, question, None));
+    };
+    match lookup(&box_name) {
+        None => Some(reply(RCODE_NXDOMAIN, question, None)),
+        Some(ip) if qclass == CLASS_IN && (qtype == TYPE_A || qtype == TYPE_ANY) => {
+            Some(reply(RCODE_NOERROR, question, Some(ip)))
+        }
+        // The name exists, without records of this type
+        Some(_) => Some(reply(RCODE_NOERROR, question, None)),
+    }
+}
+
+/// Read the uncompressed name at `offset`, returning it without the
+/// trailing dot and the offset past it.
+fn parse_name(msg: &[u8], mut offset: usize) -> Option<(String, usize)> {
+    let mut labels = Vec::new();
+    loop {
+        let len = *msg.get(offset)? as usize;
+        offset += 1;
+        if len == 0 {
+            break;
+        }
+        // Compression pointers and extended labels have no place in queries
+        if len & 0xc0 != 0 {
+            return None;
+        }
+        labels.push(std::str::from_utf8(msg.get(offset..offset + len)?).ok()?);
+        offset += len;
+    }
+    Some((labels.join("."), offset))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn query(name: &str, qtype: u16) -> Vec<u8> {
+        let mut msg = vec![0x12, 0x34, 0x01, 0x00, 0, 1, 0, 0, 0, 0, 0, 0];
+        for label in name.split('.') {
+            msg.push(label.len() as u8);
+            msg.extend_from_slice(label.as_bytes());
+        }
+        msg.push(0);
+        msg.extend_from_slice(&qtype.to_be_bytes());
+        msg.extend_from_slice(&CLASS_IN.to_be_bytes());
+        msg
+    }
+
+    fn lookup(name: &str) -> Option<Ipv4Addr> {
+        (name == "worker-1").then_some(Ipv4Addr::LOCALHOST)
+    }
+
+    #[test]
+    fn test_respond() {
+        let q = query("Worker-1.boxlite", TYPE_A);
+        let reply = respond(&q, lookup).unwrap();
+        assert_eq!(&reply[0..2], &[0x12, 0x34]);
+        // Response, authoritative, recursion desired, no error
+        assert_eq!(u16::from_be_bytes([reply[2], reply[3]]), 0x8500);
+        assert_eq!(&reply[6..8], &[0, 1]);
+        assert_eq!(&reply[12..q.len()], &q[12..]);
+        assert_eq!(&reply[reply.len() - 4..], &[127, 0, 0, 1]);
+
+        // Known name, no AAAA record
+        let reply = respond(&query("worker-1.boxlite", 28), lookup).unwrap();
+        assert_eq!(reply[3] & 0xf, RCODE_NOERROR as u8);
+        assert_eq!(&reply[6..8], &[0, 0]);
+
+        let reply = respond(&query("worker-2.boxlite", TYPE_A), lookup).unwrap();
+        assert_eq!(reply[3] & 0xf, RCODE_NXDOMAIN as u8);
+        let reply = respond(&query("example.com", TYPE_A), lookup).unwrap();
+        assert_eq!(reply[3] & 0xf, RCODE_REFUSED as u8);
+
+        assert!(respond(&q[..5], lookup).is_none());
+    }
+}
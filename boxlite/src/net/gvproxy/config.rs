@@ -1,7 +1,12 @@
 //! Gvproxy configuration structures
 
+use std::path::{Path, PathBuf};
+
+use boxlite_shared::errors::BoxliteResult;
 use serde::{Deserialize, Serialize};
 
+use crate::runtime::options::{DnsCache, NetworkPolicy, NetworkRateLimit};
+
 /// Local DNS zone configuration
 ///
 /// Defines local DNS records served by the gateway's embedded DNS server.
@@ -14,9 +19,22 @@ pub struct DnsZone {
     pub default_ip: String,
 }
 
+/// Caching resolver on the gateway
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DnsCacheConfig {
+    /// Upstream servers as `ip:port` (the host's resolvers if empty)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub upstreams: Vec<String>,
+    /// Most answers kept
+    pub max_entries: u32,
+}
+
 /// Port mapping configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PortMapping {
+    /// Host address to bind (all interfaces if None)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub host_ip: Option<String>,
     /// Host port to bind
     pub host_port: u16,
     /// Guest port to forward to
@@ -44,10 +62,13 @@ pub struct GvproxyConfig {
     /// Guest MAC address
     pub guest_mac: String,
 
+    /// Virtual IP translated to the host's 127.0.0.1
+    pub host_ip: String,
+
     /// MTU for the virtual network
     pub mtu: u16,
 
-    /// Port mappings: (host_port, guest_port)
+    /// Ports forwarded from the host into the guest
     pub port_mappings: Vec<PortMapping>,
 
     /// Local DNS zones for the gateway's embedded DNS server
@@ -64,6 +85,41 @@ pub struct GvproxyConfig {
     /// Set via config or BOXLITE_NET_CAPTURE_FILE environment variable
     #[serde(skip_serializing_if = "Option::is_none")]
     pub capture_file: Option<String>,
+
+    /// Filter of the guest's outgoing traffic (all allowed if None)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub egress_policy: Option<NetworkPolicy>,
+
+    /// Bandwidth cap of the guest's traffic (unlimited if None)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rate_limit: Option<NetworkRateLimit>,
+
+    /// Guest IPv6 address (IPv4 only if None)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub guest_ipv6: Option<String>,
+
+    /// Gateway IPv6 address, set with `guest_ipv6`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gateway_ipv6: Option<String>,
+
+    /// Hosts files of the guest's inter-box networks, whose box names the
+    /// gateway's DNS answers
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub hosts_files: Vec<String>,
+
+    /// Unix socket on which the host connects to guest ports, without port
+    /// forwards
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tunnel_socket: Option<String>,
+
+    /// Send and receive buffer size of gvproxy's end of the VM's socket
+    /// (system default if None)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub socket_buffer_bytes: Option<u32>,
+
+    /// Caching resolver answering the guest's DNS queries (none if None)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dns_cache: Option<DnsCacheConfig>,
 }
 
 impl Default for GvproxyConfig {
@@ -76,12 +132,21 @@ impl Default for GvproxyConfig {
             gateway_mac: GATEWAY_MAC_STRING.to_string(),
             guest_ip: GUEST_IP.to_string(),
             guest_mac: GUEST_MAC_STRING.to_string(),
+            host_ip: HOST_IP.to_string(),
             mtu: DEFAULT_MTU,
             port_mappings: Vec::new(),
             dns_zones: Vec::new(),
             dns_search_domains: DNS_SEARCH_DOMAINS.iter().map(|s| s.to_string()).collect(),
             debug: false,
             capture_file: None,
+            egress_policy: None,
+            rate_limit: None,
+            guest_ipv6: None,
+            gateway_ipv6: None,
+            hosts_files: Vec::new(),
+            tunnel_socket: None,
+            socket_buffer_bytes: None,
+            dns_cache: None,
         }
     }
 }
@@ -93,22 +158,23 @@ impl GvproxyConfig {
     ///
     /// # Arguments
     ///
-    /// * `port_mappings` - List of (host_port, guest_port) tuples
+    /// * `port_mappings` - Ports to forward from the host into the guest
     ///
     /// # Example
     ///
     /// ```no_run
     /// use boxlite::net::gvproxy::GvproxyConfig;
     ///
-    /// let config = GvproxyConfig::new(vec![(8080, 80), (8443, 443)]);
+    /// let config = GvproxyConfig::new(vec![(8080, 80).into(), (8443, 443).into()]);
     /// ```
-    pub fn new(port_mappings: Vec<(u16, u16)>) -> Self {
+    pub fn new(port_mappings: Vec<crate::runtime::types::PortMapping>) -> Self {
         let mut config = Self {
             port_mappings: port_mappings
                 .into_iter()
-                .map(|(host_port, guest_port)| PortMapping {
-                    host_port,
-                    guest_port,
+                .map(|mapping| PortMapping {
+                    host_ip: mapping.host_ip.map(|ip| ip.to_string()),
+                    host_port: mapping.host_port,
+                    guest_port: mapping.guest_port,
                 })
                 .collect(),
             ..Default::default()
@@ -152,6 +218,65 @@ impl GvproxyConfig {
         self
     }
 
+    /// Filter the guest's outgoing traffic with `policy`
+    pub fn with_egress_policy(mut self, policy: NetworkPolicy) -> Self {
+        self.egress_policy = (!policy.is_open()).then_some(policy);
+        self
+    }
+
+    /// Cap the guest's bandwidth at `rate_limit`
+    pub fn with_rate_limit(mut self, rate_limit: NetworkRateLimit) -> Self {
+        self.rate_limit = (!rate_limit.is_unlimited()).then_some(rate_limit);
+        self
+    }
+
+    /// Give the guest an IPv6 address and route its IPv6 traffic
+    pub fn with_ipv6(mut self, ipv6: bool) -> Self {
+        use crate::net::constants::{GATEWAY_IPV6, GUEST_IPV6};
+
+        self.guest_ipv6 = ipv6.then(|| GUEST_IPV6.to_string());
+        self.gateway_ipv6 = ipv6.then(|| GATEWAY_IPV6.to_string());
+        self
+    }
+
+    /// Resolve the box names in `hosts_files`
+    pub fn with_hosts_files(mut self, hosts_files: &[PathBuf]) -> Self {
+        self.hosts_files = hosts_files
+            .iter()
+            .map(|path| path.to_string_lossy().into_owned())
+            .collect();
+        self
+    }
+
+    /// Serve connections to guest ports on `tunnel_socket`
+    pub fn with_tunnel_socket(mut self, tunnel_socket: Option<&Path>) -> Self {
+        self.tunnel_socket = tunnel_socket.map(|path| path.to_string_lossy().into_owned());
+        self
+    }
+
+    /// Size the buffers of gvproxy's end of the VM's socket
+    pub fn with_socket_buffer(mut self, socket_buffer_bytes: Option<u32>) -> Self {
+        self.socket_buffer_bytes = socket_buffer_bytes;
+        self
+    }
+
+    /// Cache the guest's DNS answers, resolving through the upstreams of
+    /// `dns_cache`
+    pub fn with_dns_cache(mut self, dns_cache: Option<&DnsCache>) -> BoxliteResult<Self> {
+        self.dns_cache = match dns_cache {
+            Some(cache) => Some(DnsCacheConfig {
+                upstreams: cache
+                    .upstream_addrs()?
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect(),
+                max_entries: cache.max_entries,
+            }),
+            None => None,
+        };
+        Ok(self)
+    }
+
     /// Enable packet capture to pcap file
     ///
     /// Records all network traffic to a file that can be analyzed with Wireshark.
@@ -162,7 +287,7 @@ impl GvproxyConfig {
     /// ```no_run
     /// use boxlite::net::gvproxy::GvproxyConfig;
     ///
-    /// let config = GvproxyConfig::new(vec![(8080, 80)])
+    /// let config = GvproxyConfig::new(vec![(8080, 80).into()])
     ///     .with_capture_file("/tmp/network.pcap".to_string());
     /// ```
     pub fn with_capture_file(mut self, capture_file: String) -> Self {
@@ -188,15 +313,110 @@ mod tests {
 
     #[test]
     fn test_new_with_port_mappings() {
-        let config = GvproxyConfig::new(vec![(8080, 80), (8443, 443)]);
+        let config = GvproxyConfig::new(vec![(8080, 80).into(), (8443, 443).into()]);
         assert_eq!(config.port_mappings.len(), 2);
         assert_eq!(config.port_mappings[0].host_port, 8080);
         assert_eq!(config.port_mappings[0].guest_port, 80);
+        assert_eq!(config.port_mappings[0].host_ip, None);
+    }
+
+    #[test]
+    fn test_port_mapping_host_ip() {
+        let mapping = crate::runtime::types::PortMapping {
+            host_ip: Some("127.0.0.1".parse().unwrap()),
+            host_port: 8080,
+            guest_port: 80,
+        };
+        let config = GvproxyConfig::new(vec![mapping]);
+        let json = serde_json::to_value(&config).unwrap();
+        assert_eq!(json["port_mappings"][0]["host_ip"], "127.0.0.1");
+
+        let json = serde_json::to_value(GvproxyConfig::new(vec![(8080, 80).into()])).unwrap();
+        assert!(json["port_mappings"][0].get("host_ip").is_none());
+    }
+
+    #[test]
+    fn test_dns_cache_serialization() {
+        let json = serde_json::to_value(GvproxyConfig::default()).unwrap();
+        assert!(json.get("dns_cache").is_none());
+
+        let cache = DnsCache {
+            upstreams: vec!["1.1.1.1".to_string(), "[fd00::53]:5353".to_string()],
+            max_entries: 100,
+        };
+        let config = GvproxyConfig::default()
+            .with_dns_cache(Some(&cache))
+            .unwrap();
+        let json = serde_json::to_value(&config).unwrap();
+        assert_eq!(
+            json["dns_cache"]["upstreams"],
+            serde_json::json!(["1.1.1.1:53", "[fd00::53]:5353"])
+        );
+        assert_eq!(json["dns_cache"]["max_entries"], 100);
+
+        let host = GvproxyConfig::default()
+            .with_dns_cache(Some(&DnsCache::default()))
+            .unwrap();
+        let json = serde_json::to_value(&host).unwrap();
+        assert!(json["dns_cache"].get("upstreams").is_none());
+    }
+
+    #[test]
+    fn test_egress_policy_serialization() {
+        use crate::runtime::options::EgressRule;
+
+        let config = GvproxyConfig::new(vec![]).with_egress_policy(NetworkPolicy::default());
+        assert!(config.egress_policy.is_none());
+        assert!(
+            !serde_json::to_string(&config)
+                .unwrap()
+                .contains("egress_policy")
+        );
+
+        let policy = NetworkPolicy::allow_only([EgressRule::host("pypi.org").with_ports([443])]);
+        let json = serde_json::to_value(config.with_egress_policy(policy)).unwrap();
+        assert_eq!(json["egress_policy"]["allow"][0]["host"], "pypi.org");
+        assert_eq!(json["egress_policy"]["allow"][0]["ports"][0], 443);
+        assert!(json["egress_policy"]["deny"].as_array().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_rate_limit_serialization() {
+        let config = GvproxyConfig::new(vec![]).with_rate_limit(NetworkRateLimit::default());
+        assert!(config.rate_limit.is_none());
+
+        let json = serde_json::to_value(config.with_rate_limit(NetworkRateLimit {
+            ingress_bps: None,
+            egress_bps: Some(8_000_000),
+        }))
+        .unwrap();
+        assert_eq!(json["rate_limit"]["egress_bps"], 8_000_000);
+        assert!(json["rate_limit"].get("ingress_bps").is_none());
+    }
+
+    #[test]
+    fn test_ipv6_serialization() {
+        let json = serde_json::to_value(GvproxyConfig::new(vec![])).unwrap();
+        assert!(json.get("guest_ipv6").is_none());
+
+        let json = serde_json::to_value(GvproxyConfig::new(vec![]).with_ipv6(true)).unwrap();
+        assert_eq!(json["guest_ipv6"], "fd42:b0c5:127::2");
+        assert_eq!(json["gateway_ipv6"], "fd42:b0c5:127::1");
+    }
+
+    #[test]
+    fn test_socket_buffer_serialization() {
+        let json = serde_json::to_value(GvproxyConfig::new(vec![])).unwrap();
+        assert!(json.get("socket_buffer_bytes").is_none());
+
+        let config = GvproxyConfig::new(vec![]).with_socket_buffer(Some(4 << 20));
+        let json = serde_json::to_value(config).unwrap();
+        assert_eq!(json["socket_buffer_bytes"], 4 << 20);
     }
 
     #[test]
     fn test_builder_pattern() {
-        let config = GvproxyConfig::new(vec![(8080, 80)])
+        let config = GvproxyConfig::new(vec![(8080, 80).into()])
             .with_debug(true)
             .with_mtu(9000);
 
@@ -206,7 +426,7 @@ mod tests {
 
     #[test]
     fn test_serialization() {
-        let config = GvproxyConfig::new(vec![(8080, 80)]);
+        let config = GvproxyConfig::new(vec![(8080, 80).into()]);
         let json = serde_json::to_string(&config).unwrap();
         let deserialized: GvproxyConfig = serde_json::from_str(&json).unwrap();
         assert_eq!(config.subnet, deserialized.subnet);
@@ -215,8 +435,8 @@ mod tests {
 
     #[test]
     fn test_capture_file_builder() {
-        let config =
-            GvproxyConfig::new(vec![(8080, 80)]).with_capture_file("/tmp/test.pcap".to_string());
+        let config = GvproxyConfig::new(vec![(8080, 80).into()])
+            .with_capture_file("/tmp/test.pcap".to_string());
 
         assert_eq!(config.capture_file, Some("/tmp/test.pcap".to_string()));
     }
@@ -224,7 +444,7 @@ mod tests {
     #[test]
     fn test_capture_file_serialization() {
         // Without capture file - should not include field in JSON
-        let config = GvproxyConfig::new(vec![(8080, 80)]);
+        let config = GvproxyConfig::new(vec![(8080, 80).into()]);
         let json = serde_json::to_string(&config).unwrap();
         assert!(!json.contains("capture_file"));
 
@@ -10,8 +10,8 @@ use boxlite_shared::errors::{BoxliteError, BoxliteResult};
 
 use super::config::GvproxyConfig;
 use libgvproxy_sys::{
-    gvproxy_create, gvproxy_destroy, gvproxy_free_string, gvproxy_get_socket_path,
-    gvproxy_get_stats, gvproxy_get_version,
+    gvproxy_capture, gvproxy_create, gvproxy_destroy, gvproxy_forwarded_connections,
+    gvproxy_free_string, gvproxy_get_socket_path, gvproxy_get_stats, gvproxy_get_version,
 };
 
 /// Create a new gvproxy instance with full configuration
@@ -143,6 +143,57 @@ pub fn get_stats_json(id: i64) -> BoxliteResult<String> {
     Ok(json_str)
 }
 
+/// Count the TCP connections the port forwards of a gvproxy instance have
+/// open to the guest
+///
+/// # Arguments
+/// * `id` - Instance ID returned from `create_instance`
+///
+/// # Returns
+/// Number of connections, or error if the instance doesn't exist
+pub fn forwarded_connections(id: i64) -> BoxliteResult<u64> {
+    let count = unsafe { gvproxy_forwarded_connections(id) };
+
+    u64::try_from(count).map_err(|_| {
+        BoxliteError::Network(format!(
+            "gvproxy_forwarded_connections failed for instance {} (not found)",
+            id
+        ))
+    })
+}
+
+/// Capture the traffic of a gvproxy instance, blocking until it ends
+///
+/// # Arguments
+/// * `id` - Instance ID returned from `create_instance`
+/// * `config_json` - JSON string with the file path, filter and limits
+///
+/// # Returns
+/// JSON string with the capture's stats, and its error if it failed
+pub fn capture_json(id: i64, config_json: &str) -> BoxliteResult<String> {
+    let c_json = CString::new(config_json)
+        .map_err(|e| BoxliteError::Network(format!("Invalid JSON string: {}", e)))?;
+
+    let c_str = unsafe { gvproxy_capture(id, c_json.as_ptr()) };
+
+    if c_str.is_null() {
+        return Err(BoxliteError::Network(format!(
+            "gvproxy_capture failed for instance {}",
+            id
+        )));
+    }
+
+    let json_str = unsafe { CStr::from_ptr(c_str) }
+        .to_str()
+        .map_err(|e| BoxliteError::Network(format!("Invalid UTF-8 in capture JSON: {}", e)))?
+        .to_string();
+
+    // Free the string returned by CGO
+    unsafe { gvproxy_free_string(c_str) };
+
+    Ok(json_str)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -158,7 +209,7 @@ mod tests {
     #[test]
     #[ignore] // Requires libgvproxy.dylib to be available
     fn test_ffi_create_destroy() {
-        let config = GvproxyConfig::new(vec![(8080, 80), (8443, 443)]);
+        let config = GvproxyConfig::new(vec![(8080, 80).into(), (8443, 443).into()]);
         let id = create_instance(&config).unwrap();
 
         // Get socket path
@@ -3,14 +3,24 @@
 //! This module provides a safe, RAII-style wrapper around gvproxy instances.
 //! Instances are automatically cleaned up when dropped.
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Weak;
 
 use boxlite_shared::errors::{BoxliteError, BoxliteResult};
+use serde::Deserialize;
 
 use super::ffi;
 use super::logging;
 use super::stats::NetworkStats;
+use crate::net::{CaptureFilter, CaptureStats, NetworkMetrics};
+
+/// Result of a capture from the CGO layer
+#[derive(Deserialize)]
+struct CaptureResult {
+    #[serde(flatten)]
+    stats: CaptureStats,
+    error: Option<String>,
+}
 
 /// Safe wrapper for gvproxy library with automatic resource management
 ///
@@ -42,7 +52,7 @@ use super::stats::NetworkStats;
 /// use boxlite::net::gvproxy::GvproxyInstance;
 ///
 /// // Create instance with port forwards
-/// let instance = GvproxyInstance::new(&[(8080, 80), (8443, 443)])?;
+/// let instance = GvproxyInstance::new(&[(8080, 80).into(), (8443, 443).into()])?;
 ///
 /// // Get socket path for connecting
 /// let socket_path = instance.get_socket_path()?;
@@ -62,7 +72,7 @@ impl GvproxyInstance {
     ///
     /// # Arguments
     ///
-    /// * `port_mappings` - List of (host_port, guest_port) tuples for port forwarding
+    /// * `port_mappings` - Ports to forward from the host into the guest
     ///
     /// # Returns
     ///
@@ -74,19 +84,22 @@ impl GvproxyInstance {
     /// use boxlite::net::gvproxy::GvproxyInstance;
     ///
     /// // Forward host port 8080 to guest port 80, and 8443 to 443
-    /// let instance = GvproxyInstance::new(&[(8080, 80), (8443, 443)])?;
+    /// let instance = GvproxyInstance::new(&[(8080, 80).into(), (8443, 443).into()])?;
     /// # Ok::<(), boxlite_shared::errors::BoxliteError>(())
     /// ```
-    pub fn new(port_mappings: &[(u16, u16)]) -> BoxliteResult<Self> {
+    pub fn new(port_mappings: &[crate::runtime::types::PortMapping]) -> BoxliteResult<Self> {
+        // Create config with defaults + port mappings
+        Self::with_config(&super::config::GvproxyConfig::new(port_mappings.to_vec()))
+    }
+
+    /// Create a new gvproxy instance from a full configuration
+    pub fn with_config(config: &super::config::GvproxyConfig) -> BoxliteResult<Self> {
         // Initialize logging callback (one-time setup)
         // This ensures all gvproxy logs are routed to Rust's tracing system
         logging::init_logging();
 
-        // Create config with defaults + port mappings
-        let config = super::config::GvproxyConfig::new(port_mappings.to_vec());
-
         // Create instance via FFI with full config
-        let id = ffi::create_instance(&config)?;
+        let id = ffi::create_instance(config)?;
 
         tracing::info!(id, "Created GvproxyInstance");
 
@@ -106,7 +119,7 @@ impl GvproxyInstance {
     /// ```no_run
     /// use boxlite::net::gvproxy::GvproxyInstance;
     ///
-    /// let instance = GvproxyInstance::new(&[(8080, 80)])?;
+    /// let instance = GvproxyInstance::new(&[(8080, 80).into()])?;
     /// let socket_path = instance.get_socket_path()?;
     /// println!("Connect to: {:?}", socket_path);
     /// # Ok::<(), boxlite_shared::errors::BoxliteError>(())
@@ -132,7 +145,7 @@ impl GvproxyInstance {
     /// ```no_run
     /// use boxlite::net::gvproxy::GvproxyInstance;
     ///
-    /// let instance = GvproxyInstance::new(&[(8080, 80)])?;
+    /// let instance = GvproxyInstance::new(&[(8080, 80).into()])?;
     /// let stats = instance.get_stats()?;
     ///
     /// // Check for packet drops due to maxInFlight limit
@@ -162,6 +175,60 @@ impl GvproxyInstance {
         })
     }
 
+    /// Get the counters of [`get_stats`](Self::get_stats) and the count of
+    /// [`forwarded_connections`](Self::forwarded_connections) as backend
+    /// metrics
+    pub fn metrics(&self) -> BoxliteResult<NetworkMetrics> {
+        let mut metrics = NetworkMetrics::from(self.get_stats()?);
+        metrics.forwarded_connections = Some(self.forwarded_connections()?);
+        Ok(metrics)
+    }
+
+    /// Count the TCP connections the port forwards have open to the guest
+    ///
+    /// A connection counts from the forwarder's SYN until both sides sent a
+    /// FIN or either reset it.
+    pub fn forwarded_connections(&self) -> BoxliteResult<u64> {
+        ffi::forwarded_connections(self.id)
+    }
+
+    /// Capture the guest's traffic to a pcap file at `path`
+    ///
+    /// Blocks until the filter's duration passes or the file reaches its
+    /// size limit. Only one capture runs at a time.
+    ///
+    /// # Returns
+    ///
+    /// The capture's stats or an error if:
+    /// - Instance not found (already destroyed)
+    /// - The file cannot be written
+    /// - Another capture is running
+    pub fn capture(&self, path: &Path, filter: &CaptureFilter) -> BoxliteResult<CaptureStats> {
+        let config = serde_json::json!({
+            "path": path,
+            "host": filter.host.map(|host| host.to_string()),
+            "port": filter.port,
+            "protocol": filter.protocol,
+            "duration_ms": filter.duration.as_millis() as u64,
+            "max_bytes": filter.max_bytes,
+        });
+        let json_str = ffi::capture_json(self.id, &config.to_string())?;
+
+        let result: CaptureResult = serde_json::from_str(&json_str).map_err(|e| {
+            BoxliteError::Network(format!(
+                "Failed to parse capture JSON from gvproxy: {} (JSON: {})",
+                e, json_str
+            ))
+        })?;
+        match result.error {
+            Some(e) => Err(BoxliteError::Network(format!(
+                "Traffic capture failed: {}",
+                e
+            ))),
+            None => Ok(result.stats),
+        }
+    }
+
     /// Get the gvproxy version string
     ///
     /// Returns the version of the gvproxy-bridge library.
@@ -289,7 +356,7 @@ mod tests {
     #[test]
     #[ignore] // Requires libgvproxy.dylib to be available
     fn test_gvproxy_create_destroy() {
-        let port_mappings = vec![(8080, 80), (8443, 443)];
+        let port_mappings = vec![(8080, 80).into(), (8443, 443).into()];
         let instance = GvproxyInstance::new(&port_mappings).unwrap();
 
         // Get socket path
@@ -302,8 +369,8 @@ mod tests {
     #[test]
     #[ignore] // Requires libgvproxy.dylib to be available
     fn test_multiple_instances() {
-        let instance1 = GvproxyInstance::new(&[(8080, 80)]).unwrap();
-        let instance2 = GvproxyInstance::new(&[(9090, 90)]).unwrap();
+        let instance1 = GvproxyInstance::new(&[(8080, 80).into()]).unwrap();
+        let instance2 = GvproxyInstance::new(&[(9090, 90).into()]).unwrap();
 
         assert_ne!(instance1.id(), instance2.id());
 
@@ -56,9 +56,7 @@
 //! ```no_run
 //! use boxlite::net::{NetworkBackendConfig, GvisorTapBackend, NetworkBackend};
 //!
-//! let config = NetworkBackendConfig {
-//!     port_mappings: vec![(8080, 80), (8443, 443)],
-//! };
+//! let config = NetworkBackendConfig::new(vec![(8080, 80).into(), (8443, 443).into()]);
 //!
 //! // Create backend - logs from gvproxy will appear in tracing
 //! let backend = GvisorTapBackend::new(config)?;
@@ -127,9 +125,7 @@ impl GvisorTapBackend {
     /// ```no_run
     /// use boxlite::net::{NetworkBackendConfig, GvisorTapBackend};
     ///
-    /// let config = NetworkBackendConfig {
-    ///     port_mappings: vec![(8080, 80), (8443, 443)],
-    /// };
+    /// let config = NetworkBackendConfig::new(vec![(8080, 80).into(), (8443, 443).into()]);
     ///
     /// let backend = GvisorTapBackend::new(config)?;
     /// # Ok::<(), boxlite_shared::errors::BoxliteError>(())
@@ -140,8 +136,18 @@ impl GvisorTapBackend {
             config.port_mappings
         );
 
-        // Create gvproxy instance with port mappings
-        let instance = Arc::new(GvproxyInstance::new(&config.port_mappings)?);
+        // Create gvproxy instance with port mappings, egress policy, rate limit, IPv6,
+        // box names, the tunnel to guest ports, tuning and the DNS cache
+        let gvproxy_config = GvproxyConfig::new(config.port_mappings)
+            .with_egress_policy(config.policy)
+            .with_rate_limit(config.rate_limit)
+            .with_ipv6(config.ipv6)
+            .with_hosts_files(&config.hosts_files)
+            .with_tunnel_socket(config.tunnel_socket.as_deref())
+            .with_mtu(config.tuning.mtu)
+            .with_socket_buffer(config.tuning.socket_buffer_bytes)
+            .with_dns_cache(config.dns_cache.as_ref())?;
+        let instance = Arc::new(GvproxyInstance::with_config(&gvproxy_config)?);
 
         // Start background stats logging thread
         instance::start_stats_logging(Arc::downgrade(&instance));
@@ -175,9 +181,7 @@ impl GvisorTapBackend {
     /// ```no_run
     /// use boxlite::net::{NetworkBackendConfig, GvisorTapBackend};
     ///
-    /// let config = NetworkBackendConfig {
-    ///     port_mappings: vec![(8080, 80)],
-    /// };
+    /// let config = NetworkBackendConfig::new(vec![(8080, 80).into()]);
     /// let backend = GvisorTapBackend::new(config)?;
     ///
     /// // Get stats
@@ -217,13 +221,19 @@ impl NetworkBackend for GvisorTapBackend {
     }
 
     fn metrics(&self) -> BoxliteResult<Option<super::NetworkMetrics>> {
-        let stats = self.get_stats()?;
-        Ok(Some(super::NetworkMetrics {
+        Ok(Some(self.instance.metrics()?))
+    }
+}
+
+impl From<NetworkStats> for super::NetworkMetrics {
+    fn from(stats: NetworkStats) -> Self {
+        Self {
             bytes_sent: stats.bytes_sent,
             bytes_received: stats.bytes_received,
             tcp_connections: Some(stats.tcp.current_established),
             tcp_connection_errors: Some(stats.tcp.failed_connection_attempts),
-        }))
+            forwarded_connections: None,
+        }
     }
 }
 
@@ -0,0 +1,506 @@
+//! Host network mode, see [`NetworkMode::Host`](crate::NetworkMode::Host).
+//!
+//! Each box gets a TAP device on the `boxlite0` bridge, and the address of
+//! the bridge's subnet that goes with the device's number: `blt<N>` serves
+//! the `N + 2`th address. Creating the device either succeeds or finds the
+//! number taken, so the kernel is the only registry of addresses. The
+//! device's alias is its box ID, to find it again when the box stops.
+//!
+//! The host routes and masquerades the boxes' traffic, turning on
+//! `ip_forward` for all of the host, and the box's ports are published by
+//! DNAT rules tagged with its ID. The boxes reach whatever the host routes
+//! to, private networks included. They are kept from each other, with
+//! isolated bridge ports and no forwarding from the bridge back to it, and
+//! from the host, whose INPUT chain drops new connections from the bridge.
+//! The bridge and its own rules are removed with the last box's device, and
+//! `ip_forward` is set back to the value the bridge's alias recorded when
+//! it was turned on. All of it is set up with the `ip` and `iptables`
+//! tools, which need `CAP_NET_ADMIN`.
+
+use std::net::{IpAddr, Ipv4Addr};
+use std::path::Path;
+use std::process::Command;
+
+use boxlite_shared::errors::{BoxliteError, BoxliteResult};
+
+use super::switch::NetworkAttachment;
+use crate::runtime::types::{BoxID, PortMapping};
+
+/// Bridge of the boxes in host network mode.
+const BRIDGE: &str = "boxlite0";
+
+/// Subnet of the bridge, out of the way of inter-box networks (`10.89/16`).
+const SUBNET: Ipv4Addr = Ipv4Addr::new(10, 87, 0, 0);
+pub(crate) const PREFIX_LEN: u8 = 16;
+
+/// Address of the host on the bridge, the boxes' gateway.
+pub(crate) const GATEWAY: Ipv4Addr = Ipv4Addr::new(10, 87, 0, 1);
+
+const TAP_PREFIX: &str = "blt";
+
+/// Boxes on the bridge: its addresses but the network, gateway and
+/// broadcast ones.
+const MAX_BOXES: u32 = (1 << (32 - PREFIX_LEN)) - 3;
+
+const SYS_CLASS_NET: &str = "/sys/class/net";
+
+const IP_FORWARD: &str = "/proc/sys/net/ipv4/ip_forward";
+
+/// Alias of the bridge, followed by the `ip_forward` value it replaced.
+const IP_FORWARD_ALIAS: &str = "boxlite:ip_forward=";
+
+/// Tag of the bridge's own rules, next to the boxes' `boxlite:<id>` ones.
+const BRIDGE_TAG: &str = "boxlite:bridge";
+
+/// Chains holding the bridge's rules, as `(table, chain)`.
+const BRIDGE_CHAINS: [(&str, &str); 3] = [
+    ("nat", "POSTROUTING"),
+    ("filter", "FORWARD"),
+    ("filter", "INPUT"),
+];
+
+/// Chains holding the boxes' port rules.
+const PORT_CHAINS: [(&str, &str); 2] = [("nat", "PREROUTING"), ("nat", "OUTPUT")];
+
+/// A box's TAP device in host network mode.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct HostAttachment {
+    /// TAP device name
+    pub tap: String,
+    /// Address of the box on the bridge
+    pub ip: Ipv4Addr,
+    /// MAC address of the box's interface
+    pub mac_address: [u8; 6],
+}
+
+/// Put the box on the bridge and publish `ports` to it.
+///
+/// Anything left over from an earlier boot of the box is removed first.
+pub(crate) fn attach(
+    box_id: &BoxID,
+    ports: &[PortMapping],
+    mtu: u16,
+) -> BoxliteResult<HostAttachment> {
+    remove_box(box_id);
+
+    // The device keeps the bridge from being removed from here on
+    let (index, tap) = create_tap()?;
+    let ip = guest_ip(index);
+    let attached = (|| {
+        ensure_bridge()?;
+        run(
+            "ip",
+            &["link", "set", "dev", &tap, "alias", box_id.as_str()],
+        )?;
+        run(
+            "ip",
+            &[
+                "link",
+                "set",
+                "dev",
+                &tap,
+                "mtu",
+                &mtu.to_string(),
+                "master",
+                BRIDGE,
+                "up",
+            ],
+        )?;
+        // No traffic to the other boxes' ports
+        run(
+            "ip",
+            &[
+                "link",
+                "set",
+                "dev",
+                &tap,
+                "type",
+                "bridge_slave",
+                "isolated",
+                "on",
+            ],
+        )?;
+        for mapping in ports {
+            publish(box_id, ip, mapping)?;
+        }
+        Ok(())
+    })();
+    if let Err(e) = attached {
+        let _ = run("ip", &["link", "del", "dev", &tap]);
+        detach(box_id);
+        return Err(e);
+    }
+
+    tracing::info!(box_id = %box_id, tap = %tap, ip = %ip, "Attached box to host network");
+    Ok(HostAttachment {
+        tap,
+        ip,
+        mac_address: NetworkAttachment::mac_for(ip),
+    })
+}
+
+/// Remove the box's TAP device and port rules, if any, and the bridge if
+/// no box is left on it.
+pub(crate) fn detach(box_id: &BoxID) {
+    remove_box(box_id);
+    remove_unused_bridge();
+}
+
+fn remove_box(box_id: &BoxID) {
+    if let Ok(entries) = std::fs::read_dir(SYS_CLASS_NET) {
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let alias = std::fs::read_to_string(entry.path().join("ifalias")).unwrap_or_default();
+            if name.starts_with(TAP_PREFIX)
+                && alias.trim() == box_id.as_str()
+                && let Err(e) = run("ip", &["link", "del", "dev", &name])
+            {
+                tracing::warn!(box_id = %box_id, tap = %name, error = %e, "Failed to remove TAP device");
+            }
+        }
+    }
+    remove_rules(&PORT_CHAINS, &rule_tag(box_id));
+}
+
+/// Remove the bridge and its rules once no TAP device is left. A box
+/// another runtime attaches meanwhile fails to attach.
+fn remove_unused_bridge() {
+    if !Path::new(SYS_CLASS_NET).join(BRIDGE).exists() {
+        return;
+    }
+    let in_use = std::fs::read_dir(SYS_CLASS_NET).is_ok_and(|entries| {
+        entries
+            .flatten()
+            .any(|entry| entry.file_name().to_string_lossy().starts_with(TAP_PREFIX))
+    });
+    if in_use {
+        return;
+    }
+
+    remove_rules(&BRIDGE_CHAINS, BRIDGE_TAG);
+    let alias = std::fs::read_to_string(Path::new(SYS_CLASS_NET).join(BRIDGE).join("ifalias"))
+        .unwrap_or_default();
+    if let Some(previous) = alias.trim().strip_prefix(IP_FORWARD_ALIAS)
+        && let Err(e) = std::fs::write(IP_FORWARD, previous)
+    {
+        tracing::warn!(error = %e, "Failed to restore ip_forward");
+    }
+    match run("ip", &["link", "del", "dev", BRIDGE]) {
+        Ok(_) => tracing::info!("Removed host network bridge"),
+        Err(e) => tracing::warn!(error = %e, "Failed to remove host network bridge"),
+    }
+}
+
+/// Delete the rules of `chains` tagged with `tag`.
+fn remove_rules(chains: &[(&str, &str)], tag: &str) {
+    for &(table, chain) in chains {
+        let Ok(rules) = run("iptables", &["-t", table, "-S", chain]) else {
+            continue;
+        };
+        for rule in rules.lines().filter(|rule| rule.contains(tag)) {
+            // `-A CHAIN ...` as listed, deleted with `-D CHAIN ...`
+            let mut args: Vec<&str> = rule
+                .split_whitespace()
+                .map(|arg| arg.trim_matches('"'))
+                .collect();
+            if args.first() != Some(&"-A") {
+                continue;
+            }
+            args[0] = "-D";
+            let mut delete = vec!["-t", table];
+            delete.extend(args);
+            if let Err(e) = run("iptables", &delete) {
+                tracing::warn!(tag = %tag, error = %e, "Failed to remove iptables rule");
+            }
+        }
+    }
+}
+
+/// Nameservers of the host for the box, which has no DNS on its gateway.
+///
+/// Loopback resolvers (such as systemd-resolved's stub) are not reachable
+/// from the box, so their upstream servers are used instead.
+pub(crate) fn nameservers() -> Vec<String> {
+    for path in ["/etc/resolv.conf", "/run/systemd/resolve/resolv.conf"] {
+        let servers = parse_nameservers(&std::fs::read_to_string(path).unwrap_or_default());
+        if !servers.is_empty() {
+            return servers;
+        }
+    }
+    tracing::warn!("Host has no nameserver a box in host network mode can reach");
+    Vec::new()
+}
+
+fn parse_nameservers(resolv_conf: &str) -> Vec<String> {
+    resolv_conf
+        .lines()
+        .filter_map(|line| line.strip_prefix("nameserver"))
+        .filter_map(|server| server.trim().parse::<IpAddr>().ok())
+        .filter(|ip| !ip.is_loopback())
+        .map(|ip| ip.to_string())
+        .collect()
+}
+
+fn guest_ip(index: u32) -> Ipv4Addr {
+    Ipv4Addr::from(u32::from(SUBNET) + index + 2)
+}
+
+fn rule_tag(box_id: &BoxID) -> String {
+    format!("boxlite:{}", box_id)
+}
+
+/// Create the bridge and its routing if missing; another runtime may be
+/// doing the same.
+fn ensure_bridge() -> BoxliteResult<()> {
+    if !Path::new(SYS_CLASS_NET).join(BRIDGE).exists() {
+        let _ = run("ip", &["link", "add", "name", BRIDGE, "type", "bridge"]);
+        let _ = run(
+            "ip",
+            &[
+                "addr",
+                "add",
+                &format!("{}/{}", GATEWAY, PREFIX_LEN),
+                "dev",
+                BRIDGE,
+            ],
+        );
+    }
+    run("ip", &["link", "set", "dev", BRIDGE, "up"])?;
+
+    // Host-wide: the old value is kept on the bridge, to be restored with it
+    let forwarding = std::fs::read_to_string(IP_FORWARD)
+        .map_err(|e| BoxliteError::Network(format!("Failed to read {}: {}", IP_FORWARD, e)))?;
+    if forwarding.trim() != "1" {
+        run(
+            "ip",
+            &[
+                "link",
+                "set",
+                "dev",
+                BRIDGE,
+                "alias",
+                &format!("{}{}", IP_FORWARD_ALIAS, forwarding.trim()),
+            ],
+        )?;
+    }
+
+    // Published ports are also reached from the host's loopback
+    for (path, value) in [
+        (IP_FORWARD.to_string(), "1"),
+        (
+            format!("/proc/sys/net/ipv4/conf/{}/route_localnet", BRIDGE),
+            "1",
+        ),
+    ] {
+        std::fs::write(&path, value)
+            .map_err(|e| BoxliteError::Network(format!("Failed to write {}: {}", path, e)))?;
+    }
+
+    // Inserted first to last at the top of their chains, to come before
+    // rules such as docker's
+    for (table, chain, rule) in bridge_rules().iter().rev() {
+        ensure_rule(table, chain, rule)?;
+    }
+    Ok(())
+}
+
+/// The bridge's rules as `(table, chain, rule)`, in the order they apply.
+fn bridge_rules() -> Vec<(&'static str, &'static str, Vec<String>)> {
+    let subnet = format!("{}/{}", SUBNET, PREFIX_LEN);
+    let rules: [(&str, &str, &[&str]); 7] = [
+        (
+            "nat",
+            "POSTROUTING",
+            &["-s", subnet.as_str(), "!", "-o", BRIDGE, "-j", "MASQUERADE"],
+        ),
+        (
+            "nat",
+            "POSTROUTING",
+            &["-s", "127.0.0.0/8", "-o", BRIDGE, "-j", "MASQUERADE"],
+        ),
+        // Boxes reaching each other through the host
+        (
+            "filter",
+            "FORWARD",
+            &["-i", BRIDGE, "-o", BRIDGE, "-j", "DROP"],
+        ),
+        // Hosts running docker drop forwarded traffic by default. The boxes
+        // reach anything the host routes to; only published ports and
+        // replies are let in to them.
+        ("filter", "FORWARD", &["-i", BRIDGE, "-j", "ACCEPT"]),
+        (
+            "filter",
+            "FORWARD",
+            &[
+                "-o",
+                BRIDGE,
+                "-m",
+                "conntrack",
+                "--ctstate",
+                "RELATED,ESTABLISHED,DNAT",
+                "-j",
+                "ACCEPT",
+            ],
+        ),
+        // Boxes reaching the host's own services, on the gateway or any
+        // other of its addresses
+        (
+            "filter",
+            "INPUT",
+            &[
+                "-i",
+                BRIDGE,
+                "-m",
+                "conntrack",
+                "--ctstate",
+                "RELATED,ESTABLISHED",
+                "-j",
+                "ACCEPT",
+            ],
+        ),
+        ("filter", "INPUT", &["-i", BRIDGE, "-j", "DROP"]),
+    ];
+    rules
+        .into_iter()
+        .map(|(table, chain, rule)| {
+            let mut tagged: Vec<String> = ["-m", "comment", "--comment", BRIDGE_TAG]
+                .into_iter()
+                .map(String::from)
+                .collect();
+            tagged.extend(rule.iter().map(|arg| arg.to_string()));
+            (table, chain, tagged)
+        })
+        .collect()
+}
+
+/// Insert `rule` at the top of `chain` unless it is there.
+fn ensure_rule(table: &str, chain: &str, rule: &[String]) -> BoxliteResult<()> {
+    let mut check = vec!["-t", table, "-C", chain];
+    check.extend(rule.iter().map(String::as_str));
+    if run("iptables", &check).is_err() {
+        let mut insert = vec!["-t", table, "-I", chain, "1"];
+        insert.extend(rule.iter().map(String::as_str));
+        run("iptables", &insert)?;
+    }
+    Ok(())
+}
+
+/// Create the first free TAP device, returning its number and name.
+fn create_tap() -> BoxliteResult<(u32, String)> {
+    // The shim opens it as the same user
+    let user = unsafe { libc::getuid() }.to_string();
+    for index in 0..MAX_BOXES {
+        let name = format!("{}{}", TAP_PREFIX, index);
+        if Path::new(SYS_CLASS_NET).join(&name).exists() {
+            continue;
+        }
+        // Fails if another box took the number since
+        let created = run(
+            "ip",
+            &[
+                "tuntap", "add", "dev", &name, "mode", "tap", "vnet_hdr", "user", &user,
+            ],
+        );
+        if created.is_ok() {
+            return Ok((index, name));
+        }
+    }
+    Err(BoxliteError::ResourceExhausted(
+        "no free address left on the host network bridge".to_string(),
+    ))
+}
+
+/// DNAT `mapping` to the box at `ip`, for connections from elsewhere and
+/// from the host itself.
+fn publish(box_id: &BoxID, ip: Ipv4Addr, mapping: &PortMapping) -> BoxliteResult<()> {
+    let host_port = mapping.host_port.to_string();
+    let destination = format!("{}:{}", ip, mapping.guest_port);
+    let host_ip = mapping.host_ip.map(|ip| ip.to_string());
+    let tag = rule_tag(box_id);
+
+    let mut rule = vec!["-p", "tcp", "--dport", &host_port];
+    match &host_ip {
+        Some(host_ip) => rule.extend(["-d", host_ip]),
+        None => rule.extend(["-m", "addrtype", "--dst-type", "LOCAL"]),
+    }
+    rule.extend([
+        "-m",
+        "comment",
+        "--comment",
+        &tag,
+        "-j",
+        "DNAT",
+        "--to-destination",
+        &destination,
+    ]);
+    for chain in ["PREROUTING", "OUTPUT"] {
+        let mut args = vec!["-t", "nat", "-A", chain];
+        args.extend_from_slice(&rule);
+        run("iptables", &args)?;
+    }
+    Ok(())
+}
+
+/// Run `tool`, returning its output.
+fn run(tool: &str, args: &[&str]) -> BoxliteResult<String> {
+    let output = Command::new(tool)
+        .args(args)
+        .output()
+        .map_err(|e| BoxliteError::Network(format!("Failed to run {}: {}", tool, e)))?;
+    if !output.status.success() {
+        return Err(BoxliteError::Network(format!(
+            "{} {} failed: {}",
+            tool,
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_addresses() {
+        assert_eq!(guest_ip(0), Ipv4Addr::new(10, 87, 0, 2));
+        assert_eq!(guest_ip(300), Ipv4Addr::new(10, 87, 1, 46));
+        assert_eq!(guest_ip(MAX_BOXES - 1), Ipv4Addr::new(10, 87, 255, 254));
+
+        let resolv_conf = "# generated\nnameserver 127.0.0.53\nnameserver 1.1.1.1\n\
+                           nameserver 2606:4700::1111\nsearch lan\n";
+        assert_eq!(
+            parse_nameservers(resolv_conf),
+            vec!["1.1.1.1".to_string(), "2606:4700::1111".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_bridge_rules() {
+        let rules = bridge_rules();
+        assert!(
+            rules
+                .iter()
+                .all(|(_, _, rule)| rule.contains(&BRIDGE_TAG.to_string()))
+        );
+        assert!(
+            rules
+                .iter()
+                .all(|(table, chain, _)| BRIDGE_CHAINS.contains(&(*table, *chain)))
+        );
+
+        // Traffic between boxes is dropped before the bridge's traffic is
+        // let through, new connections to the host after replies are let in
+        let position = |chain: &str, target: &str| {
+            rules
+                .iter()
+                .filter(|(_, c, _)| *c == chain)
+                .position(|(_, _, rule)| rule.last().map(String::as_str) == Some(target))
+                .unwrap()
+        };
+        assert_eq!(position("FORWARD", "DROP"), 0);
+        assert_eq!(position("INPUT", "ACCEPT"), 0);
+        assert_eq!(position("INPUT", "DROP"), 1);
+    }
+}
@@ -11,6 +11,7 @@
 //! - Requires libslirp-helper binary in PATH
 
 use super::{NetworkBackend, NetworkBackendConfig, NetworkBackendEndpoint};
+use crate::runtime::types::PortMapping;
 use boxlite_shared::errors::{BoxliteError, BoxliteResult};
 use std::os::unix::io::{AsRawFd, RawFd};
 use std::os::unix::net::UnixStream;
@@ -21,9 +22,9 @@ use std::process::{Child, Command};
 /// This backend spawns a libslirp-helper process and communicates via Unix sockets.
 #[derive(Debug)]
 pub struct LibslirpBackend {
-    /// Port mappings: (host_port, guest_port)
+    /// Ports forwarded from the host into the guest
     #[allow(dead_code)]
-    port_mappings: Vec<(u16, u16)>,
+    port_mappings: Vec<PortMapping>,
 
     /// The socket file descriptor for communication with libslirp
     #[allow(dead_code)]
@@ -45,10 +46,28 @@ impl LibslirpBackend {
     /// # Errors
     ///
     /// Returns error if:
+    /// - `config.policy` filters traffic, `config.rate_limit` caps it or
+    ///   `config.ipv6` is set, which this backend does not do
     /// - Socket pair creation fails
     /// - libslirp-helper binary not found in PATH
     /// - Helper process fails to start
     pub fn new(config: NetworkBackendConfig) -> BoxliteResult<Self> {
+        if !config.policy.is_open() {
+            return Err(BoxliteError::Unsupported(
+                "network_policy is not supported by the libslirp backend".to_string(),
+            ));
+        }
+        if !config.rate_limit.is_unlimited() {
+            return Err(BoxliteError::Unsupported(
+                "network_rate_limit is not supported by the libslirp backend".to_string(),
+            ));
+        }
+        if config.ipv6 {
+            return Err(BoxliteError::Unsupported(
+                "ipv6 is not supported by the libslirp backend".to_string(),
+            ));
+        }
+
         tracing::info!(
             port_count = config.port_mappings.len(),
             "Initializing libslirp backend"
@@ -75,13 +94,21 @@ impl LibslirpBackend {
         helper_args.push(format!("--fd={}", guest_fd));
 
         // Add port forwarding configuration
-        for (host_port, guest_port) in &config.port_mappings {
-            let forward_spec = format!("tcp:127.0.0.1:{}::{}:tcp", host_port, guest_port);
+        // Forwards without a host address stay on loopback here
+        for mapping in &config.port_mappings {
+            let host_ip = mapping
+                .host_ip
+                .map_or_else(|| "127.0.0.1".to_string(), |ip| ip.to_string());
+            let forward_spec = format!(
+                "tcp:{}:{}::{}:tcp",
+                host_ip, mapping.host_port, mapping.guest_port
+            );
             helper_args.push(format!("--forward={}", forward_spec));
 
             tracing::info!(
-                host_port = host_port,
-                guest_port = guest_port,
+                host_ip = %host_ip,
+                host_port = mapping.host_port,
+                guest_port = mapping.guest_port,
                 "Configuring libslirp port forwarding"
             );
         }
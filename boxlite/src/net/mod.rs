@@ -7,10 +7,21 @@
 //! When no backend is configured (None), the engine uses its default net
 //! implementation.
 
-use boxlite_shared::errors::BoxliteResult;
+use crate::runtime::options::{DnsCache, NetworkPolicy, NetworkRateLimit, NetworkTuning};
+use crate::runtime::types::PortMapping;
+use boxlite_shared::errors::{BoxliteError, BoxliteResult};
+use std::io::ErrorKind;
+use std::net::{IpAddr, Ipv4Addr, TcpListener};
 use std::path::PathBuf;
 
+mod capture;
 pub mod constants;
+pub(crate) mod dns;
+pub(crate) mod host;
+mod passt;
+pub(crate) mod registry_cache;
+pub(crate) mod reverse;
+pub(crate) mod switch;
 
 #[cfg(feature = "libslirp-backend")]
 mod libslirp;
@@ -24,6 +35,21 @@ pub use libslirp::LibslirpBackend;
 #[cfg(feature = "gvproxy-backend")]
 pub use gvproxy::GvisorTapBackend;
 
+pub use capture::{CaptureFilter, CaptureProtocol, CaptureStats};
+pub use passt::PasstBackend;
+
+/// Which user-mode network stack serves the box's network.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum NetworkBackendKind {
+    /// gvisor-tap-vsock, built in (libslirp in builds with only the
+    /// `libslirp-backend` feature).
+    #[default]
+    Gvproxy,
+    /// The external `passt` binary (Linux only), without `network_policy`
+    /// or `network_rate_limit`.
+    Passt,
+}
+
 /// How the Box connects to the network backend.
 ///
 /// This represents the connection information that needs to be passed to the engine.
@@ -40,6 +66,13 @@ pub enum NetworkBackendEndpoint {
         /// This must match the DHCP static lease configured in the network backend
         mac_address: [u8; 6],
     },
+    /// Host TAP device to attach to, in the host network mode.
+    /// Used by: host network mode (Linux)
+    Tap {
+        name: String,
+        /// MAC address for the guest network interface
+        mac_address: [u8; 6],
+    },
 }
 
 /// Configuration for network backend initialization.
@@ -48,20 +81,114 @@ pub enum NetworkBackendEndpoint {
 /// to know which backend will be used.
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct NetworkBackendConfig {
-    /// Port mappings: (host_port, guest_port)
-    pub port_mappings: Vec<(u16, u16)>,
+    /// Ports forwarded from the host into the guest
+    pub port_mappings: Vec<PortMapping>,
+    /// Egress filter of the guest's traffic
+    #[serde(default)]
+    pub policy: NetworkPolicy,
+    /// Bandwidth cap of the guest's traffic
+    #[serde(default)]
+    pub rate_limit: NetworkRateLimit,
+    /// Give the guest an IPv6 address and route its IPv6 traffic
+    #[serde(default)]
+    pub ipv6: bool,
+    /// Backend to create
+    #[serde(default)]
+    pub backend: NetworkBackendKind,
+    /// Hosts files of the guest's inter-box networks, for backends that
+    /// resolve box names (gvproxy)
+    #[serde(default)]
+    pub hosts_files: Vec<PathBuf>,
+    /// Socket on which backends that support it (gvproxy) let the host
+    /// connect to guest ports, see [`crate::LiteBox::http`]
+    #[serde(default)]
+    pub tunnel_socket: Option<PathBuf>,
+    /// MTU and socket buffers of the backend
+    #[serde(default)]
+    pub tuning: NetworkTuning,
+    /// TAP device of the host network mode, which replaces the backend
+    #[serde(default)]
+    pub host: Option<host::HostAttachment>,
+    /// Caching resolver of backends that support it (gvproxy)
+    #[serde(default)]
+    pub dns_cache: Option<DnsCache>,
 }
 
 impl NetworkBackendConfig {
-    pub fn new(port_mappings: Vec<(u16, u16)>) -> Self {
-        Self { port_mappings }
+    pub fn new(port_mappings: Vec<PortMapping>) -> Self {
+        Self {
+            port_mappings,
+            policy: NetworkPolicy::default(),
+            rate_limit: NetworkRateLimit::default(),
+            ipv6: false,
+            backend: NetworkBackendKind::default(),
+            hosts_files: Vec::new(),
+            tunnel_socket: None,
+            tuning: NetworkTuning::default(),
+            host: None,
+            dns_cache: None,
+        }
+    }
+
+    /// Run the backend with `tuning`.
+    pub fn with_tuning(mut self, tuning: NetworkTuning) -> Self {
+        self.tuning = tuning;
+        self
+    }
+
+    /// Filter the guest's traffic with `policy`.
+    pub fn with_policy(mut self, policy: NetworkPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Cap the guest's bandwidth at `rate_limit`.
+    pub fn with_rate_limit(mut self, rate_limit: NetworkRateLimit) -> Self {
+        self.rate_limit = rate_limit;
+        self
+    }
+
+    /// Route the guest's IPv6 traffic.
+    pub fn with_ipv6(mut self, ipv6: bool) -> Self {
+        self.ipv6 = ipv6;
+        self
+    }
+
+    /// Serve the network with `backend`.
+    pub fn with_backend(mut self, backend: NetworkBackendKind) -> Self {
+        self.backend = backend;
+        self
+    }
+
+    /// Resolve the box names in `hosts_files`.
+    pub fn with_hosts_files(mut self, hosts_files: Vec<PathBuf>) -> Self {
+        self.hosts_files = hosts_files;
+        self
+    }
+
+    /// Let the host connect to guest ports through `tunnel_socket`.
+    pub fn with_tunnel_socket(mut self, tunnel_socket: PathBuf) -> Self {
+        self.tunnel_socket = Some(tunnel_socket);
+        self
+    }
+
+    /// Cache the answers of the gateway's DNS with `dns_cache`.
+    pub fn with_dns_cache(mut self, dns_cache: Option<DnsCache>) -> Self {
+        self.dns_cache = dns_cache;
+        self
+    }
+
+    /// Attach the guest to the host's TAP device instead of a backend.
+    pub fn with_host(mut self, host: host::HostAttachment) -> Self {
+        self.host = Some(host);
+        self
     }
 }
 
 /// Network metrics from a network backend.
 ///
 /// Contains bandwidth counters and connection statistics.
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
 pub struct NetworkMetrics {
     /// Total bytes sent from host to guest
     pub bytes_sent: u64,
@@ -71,6 +198,8 @@ pub struct NetworkMetrics {
     pub tcp_connections: Option<u64>,
     /// Total failed connection attempts
     pub tcp_connection_errors: Option<u64>,
+    /// Open TCP connections of published ports into the guest
+    pub forwarded_connections: Option<u64>,
 }
 
 /// Network backend trait that all net implementations must implement.
@@ -118,14 +247,22 @@ impl NetworkBackendFactory {
     /// Create an appropriate network backend based on configuration.
     ///
     /// Backend selection (in priority order):
-    /// 1. gvisor-tap-vsock (when gvproxy-backend feature is enabled)
-    /// 2. libslirp (when libslirp-backend feature is enabled)
-    /// 3. None (no backend features enabled)
+    /// 1. passt (when `config.backend` asks for it)
+    /// 2. gvisor-tap-vsock (when gvproxy-backend feature is enabled)
+    /// 3. libslirp (when libslirp-backend feature is enabled)
+    /// 4. None (no backend features enabled)
     ///
-    /// Returns None when no backend features are enabled, which means the
+    /// Returns None when no backend is available, which means the
     /// engine will use its default net implementation.
     pub fn create(config: NetworkBackendConfig) -> BoxliteResult<Option<Box<dyn NetworkBackend>>> {
-        // Priority 1: gvisor-tap-vsock
+        // Explicitly selected, available in every build
+        if config.backend == NetworkBackendKind::Passt {
+            tracing::info!("Using passt backend");
+            let backend = PasstBackend::new(config)?;
+            return Ok(Some(Box::new(backend)));
+        }
+
+        // Priority 2: gvisor-tap-vsock
         #[cfg(feature = "gvproxy-backend")]
         {
             tracing::info!("Using gvisor-tap-vsock backend");
@@ -133,7 +270,7 @@ impl NetworkBackendFactory {
             Ok(Some(Box::new(backend)))
         }
 
-        // Priority 2: libslirp
+        // Priority 3: libslirp
         #[cfg(all(feature = "libslirp-backend", not(feature = "gvproxy-backend")))]
         {
             tracing::info!("Using libslirp backend");
@@ -150,3 +287,166 @@ impl NetworkBackendFactory {
         }
     }
 }
+
+/// Replace host port 0 in `mappings` with free ports of their host
+/// addresses, and check that the other host ports are free.
+///
+/// A host port mapped twice on the same address fails with
+/// [`BoxliteError::Config`]. A taken port fails with
+/// [`BoxliteError::PortInUse`], or with
+/// `reassign_taken` moves to the next free port above it. The ports are
+/// found by binding them, and released right before the network backend
+/// binds them again; the kernel does not hand them out in between unless
+/// its ephemeral range runs out.
+pub(crate) fn assign_host_ports(
+    mut mappings: Vec<PortMapping>,
+    reassign_taken: bool,
+) -> BoxliteResult<Vec<PortMapping>> {
+    // The unspecified address overlaps every other one
+    let overlaps = |a: Option<IpAddr>, b: Option<IpAddr>| match (a, b) {
+        (Some(a), Some(b)) => a == b || a.is_unspecified() || b.is_unspecified(),
+        _ => true,
+    };
+    for (i, mapping) in mappings.iter().enumerate() {
+        let duplicate = mapping.host_port != 0
+            && mappings[i + 1..].iter().any(|other| {
+                other.host_port == mapping.host_port && overlaps(other.host_ip, mapping.host_ip)
+            });
+        if duplicate {
+            return Err(BoxliteError::Config(format!(
+                "host port {} is mapped more than once",
+                mapping.host_port
+            )));
+        }
+    }
+
+    // Held until all are assigned, so no port is picked twice; requested
+    // ports first, so that none is picked for port 0
+    let mut reserved = Vec::new();
+    mappings.sort_by_key(|m| m.host_port == 0);
+    for mapping in &mut mappings {
+        let ip = mapping.host_ip.unwrap_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED));
+        let listener = match bind_host_port(ip, mapping.host_port, reassign_taken) {
+            Ok(listener) => listener,
+            Err(e) if e.kind() == ErrorKind::AddrInUse => {
+                return Err(BoxliteError::PortInUse(mapping.host_port));
+            }
+            // E.g. privileged ports, left to the backend as before
+            Err(e) if mapping.host_port != 0 => {
+                tracing::debug!(host_port = mapping.host_port, error = %e, "Could not check host port");
+                continue;
+            }
+            Err(e) => {
+                return Err(BoxliteError::Network(format!(
+                    "Failed to find a free port on {} for guest port {}: {}",
+                    ip, mapping.guest_port, e
+                )));
+            }
+        };
+        let host_port = listener.local_addr()?.port();
+        if host_port != mapping.host_port {
+            tracing::info!(
+                requested = mapping.host_port,
+                host_port,
+                guest_port = mapping.guest_port,
+                "Assigned host port"
+            );
+            mapping.host_port = host_port;
+        }
+        reserved.push(listener);
+    }
+    mappings.sort_unstable_by_key(|m| (m.host_port, m.host_ip));
+    Ok(mappings)
+}
+
+/// Bind `port` of `ip`, or with `reassign_taken` the next free port above
+/// it when taken.
+pub(crate) fn bind_host_port(
+    ip: IpAddr,
+    port: u16,
+    reassign_taken: bool,
+) -> std::io::Result<TcpListener> {
+    let mut next = port;
+    loop {
+        match TcpListener::bind((ip, next)) {
+            Err(e) if e.kind() == ErrorKind::AddrInUse && reassign_taken && next != 0 => {
+                next = next.checked_add(1).ok_or(e)?;
+            }
+            result => return result,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_assign_host_ports() {
+        let mappings = assign_host_ports(
+            vec![
+                (0, 80).into(),
+                (8080, 8080).into(),
+                PortMapping {
+                    host_ip: Some("127.0.0.1".parse().unwrap()),
+                    host_port: 0,
+                    guest_port: 443,
+                },
+            ],
+            false,
+        )
+        .unwrap();
+
+        assert_eq!(mappings.len(), 3);
+        assert!(mappings.iter().all(|m| m.host_port != 0));
+        assert_ne!(mappings[0].host_port, mappings[1].host_port);
+        assert!(mappings.iter().any(|m| m.host_port == 8080));
+    }
+
+    #[test]
+    fn test_assign_host_ports_duplicate() {
+        let port = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+
+        let result = assign_host_ports(vec![(port, 80).into(), (port, 443).into()], false);
+        assert!(
+            matches!(&result, Err(BoxliteError::Config(msg)) if msg.contains(&port.to_string()))
+        );
+
+        // Different addresses may share a port
+        let on = |ip: Ipv4Addr, guest_port| PortMapping {
+            host_ip: Some(ip.into()),
+            host_port: port,
+            guest_port,
+        };
+        let distinct = vec![
+            on(Ipv4Addr::LOCALHOST, 80),
+            on(Ipv4Addr::new(127, 0, 0, 2), 443),
+        ];
+        assert!(!matches!(
+            assign_host_ports(distinct, false),
+            Err(BoxliteError::Config(_))
+        ));
+    }
+
+    #[test]
+    fn test_assign_host_ports_taken() {
+        let taken = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let port = taken.local_addr().unwrap().port();
+        let mapping = PortMapping {
+            host_ip: Some(Ipv4Addr::LOCALHOST.into()),
+            host_port: port,
+            guest_port: 80,
+        };
+
+        assert!(matches!(
+            assign_host_ports(vec![mapping], false),
+            Err(BoxliteError::PortInUse(p)) if p == port
+        ));
+        let mappings = assign_host_ports(vec![mapping], true).unwrap();
+        assert!(mappings[0].host_port > port);
+    }
+}
@@ -0,0 +1,173 @@
+//! passt network backend.
+//!
+//! [passt](https://passt.top) is a user-mode network stack that translates
+//! the guest's Ethernet frames to the host's sockets, without a NAT table
+//! of its own. It serves one VM over a Unix socket speaking the Qemu stream
+//! protocol.
+//!
+//! Key characteristics:
+//! - External `passt` binary from PATH (Linux only)
+//! - The guest keeps the addresses of the default backend (`192.168.127.0/24`)
+//! - No egress filtering, rate limiting or metrics
+
+use super::constants::{
+    DEFAULT_MTU, GATEWAY_IP, GATEWAY_IPV6, GATEWAY_MAC_STRING, GUEST_IP, GUEST_IPV6, GUEST_MAC,
+    HOST_IP,
+};
+use super::{ConnectionType, NetworkBackend, NetworkBackendConfig, NetworkBackendEndpoint};
+use boxlite_shared::errors::{BoxliteError, BoxliteResult};
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant};
+
+/// How long passt gets to create its socket.
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// passt backend implementation.
+///
+/// The passt process serves a single connection and exits when the VM
+/// disconnects; it is also killed when the backend is dropped.
+#[derive(Debug)]
+pub struct PasstBackend {
+    socket_path: PathBuf,
+    process: Option<Child>,
+}
+
+impl PasstBackend {
+    /// Start passt for a VM.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if:
+    /// - the host is not Linux, or `config.policy` filters traffic or
+    ///   `config.rate_limit` caps it, which this backend does not do
+    /// - the passt binary is not found in PATH, or exits before serving
+    pub fn new(config: NetworkBackendConfig) -> BoxliteResult<Self> {
+        if !cfg!(target_os = "linux") {
+            return Err(BoxliteError::Unsupported(
+                "the passt backend is only available on Linux".to_string(),
+            ));
+        }
+        if !config.policy.is_open() {
+            return Err(BoxliteError::Unsupported(
+                "network_policy is not supported by the passt backend".to_string(),
+            ));
+        }
+        if !config.rate_limit.is_unlimited() {
+            return Err(BoxliteError::Unsupported(
+                "network_rate_limit is not supported by the passt backend".to_string(),
+            ));
+        }
+
+        let socket_path = std::env::temp_dir().join(format!("passt-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&socket_path);
+
+        let mut args: Vec<String> = vec![
+            "--foreground".into(),
+            "--quiet".into(),
+            // Exit when the VM disconnects
+            "--one-off".into(),
+            "--socket".into(),
+            socket_path.to_string_lossy().into_owned(),
+            "--address".into(),
+            GUEST_IP.into(),
+            "--netmask".into(),
+            "24".into(),
+            "--gateway".into(),
+            GATEWAY_IP.into(),
+            "--mac-addr".into(),
+            GATEWAY_MAC_STRING.into(),
+            // The guest resolves through the gateway, like with gvproxy
+            "--dns-forward".into(),
+            GATEWAY_IP.into(),
+            "--map-host-loopback".into(),
+            HOST_IP.into(),
+        ];
+        if config.ipv6 {
+            args.extend([
+                "--address".into(),
+                GUEST_IPV6.into(),
+                "--gateway".into(),
+                GATEWAY_IPV6.into(),
+            ]);
+        } else {
+            args.push("--ipv4-only".into());
+        }
+        if config.tuning.mtu != DEFAULT_MTU {
+            args.extend(["--mtu".into(), config.tuning.mtu.to_string()]);
+        }
+        // Forwards without a host address listen on all interfaces
+        for mapping in &config.port_mappings {
+            let spec = match mapping.host_ip {
+                Some(ip) => format!("{}/{}:{}", ip, mapping.host_port, mapping.guest_port),
+                None => format!("{}:{}", mapping.host_port, mapping.guest_port),
+            };
+            args.extend(["--tcp-ports".into(), spec]);
+        }
+
+        tracing::debug!(args = ?args, "Spawning passt");
+        let mut process = Command::new("passt")
+            .args(&args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .spawn()
+            .map_err(|e| {
+                BoxliteError::Network(format!(
+                    "Failed to spawn passt (is it installed and in PATH?): {}",
+                    e
+                ))
+            })?;
+
+        let deadline = Instant::now() + STARTUP_TIMEOUT;
+        while !socket_path.exists() {
+            let exited = process
+                .try_wait()
+                .map_err(|e| BoxliteError::Network(format!("Failed to check passt: {}", e)))?;
+            if let Some(status) = exited {
+                return Err(BoxliteError::Network(format!(
+                    "passt exited during startup: {}",
+                    status
+                )));
+            }
+            if Instant::now() >= deadline {
+                let _ = process.kill();
+                let _ = process.wait();
+                return Err(BoxliteError::Network(
+                    "passt did not create its socket in time".to_string(),
+                ));
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+
+        tracing::info!(pid = process.id(), socket_path = ?socket_path, "passt started");
+        Ok(Self {
+            socket_path,
+            process: Some(process),
+        })
+    }
+}
+
+impl NetworkBackend for PasstBackend {
+    fn endpoint(&self) -> BoxliteResult<NetworkBackendEndpoint> {
+        Ok(NetworkBackendEndpoint::UnixSocket {
+            path: self.socket_path.clone(),
+            connection_type: ConnectionType::UnixStream,
+            mac_address: GUEST_MAC,
+        })
+    }
+
+    fn name(&self) -> &'static str {
+        "passt"
+    }
+}
+
+impl Drop for PasstBackend {
+    fn drop(&mut self) {
+        if let Some(mut process) = self.process.take() {
+            tracing::debug!(pid = process.id(), "Terminating passt");
+            let _ = process.kill();
+            let _ = process.wait();
+        }
+        let _ = std::fs::remove_file(&self.socket_path);
+    }
+}
@@ -0,0 +1,565 @@
+//! Caching mirror for package registries.
+//!
+//! A small HTTP server on the host's loopback interface that boxes reach
+//! through [`HOST_IP`]. It mirrors crates.io (sparse index), the PyPI
+//! simple index and the npm registry:
+//!
+//! - Artifacts (`.crate` files, wheels and sdists, npm tarballs) never
+//!   change once published, so they are downloaded once and served from
+//!   disk afterwards.
+//! - Index documents are fetched from the registry on every request. The
+//!   last good copy is served when the registry is unreachable.
+//!
+//! Download URLs inside index documents are rewritten to point back at the
+//! mirror. Entries are stored under the SHA-256 of their upstream URL, so
+//! paths never have to be trusted as file names.
+
+use std::convert::Infallible;
+use std::io;
+use std::net::{Ipv4Addr, TcpListener};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use boxlite_shared::errors::{BoxliteError, BoxliteResult};
+use bytes::Bytes;
+use http_body_util::{BodyExt, Full, StreamBody, combinators::BoxBody};
+use hyper::body::{Frame, Incoming};
+use hyper::service::service_fn;
+use hyper::{Method, Request, Response, StatusCode, header};
+use hyper_util::rt::TokioIo;
+use sha2::{Digest, Sha256};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::sync::oneshot;
+
+use crate::net::constants::HOST_IP;
+use crate::runtime::options::{BoxOptions, PackageRegistry, RegistryCacheOptions};
+
+type Body = BoxBody<Bytes, io::Error>;
+
+const CRATES_INDEX: &str = "https://index.crates.io/";
+const CRATES_DOWNLOAD: &str = "https://static.crates.io/crates/";
+const PYPI_INDEX: &str = "https://pypi.org/simple/";
+const PYPI_FILES: &str = "https://files.pythonhosted.org/";
+const NPM_REGISTRY: &str = "https://registry.npmjs.org/";
+
+/// Bytes per chunk when serving cached files.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Running registry cache, stopped when dropped.
+pub(crate) struct RegistryCache {
+    port: u16,
+    registries: Vec<PackageRegistry>,
+    _shutdown: oneshot::Sender<()>,
+}
+
+impl RegistryCache {
+    /// Start serving on an ephemeral loopback port.
+    ///
+    /// The server runs on its own thread, so this works with or without a
+    /// surrounding tokio runtime.
+    pub fn start(options: &RegistryCacheOptions, default_dir: PathBuf) -> BoxliteResult<Self> {
+        let dir = options.dir.clone().unwrap_or(default_dir);
+        let store = Store::open(dir)?;
+        let client = reqwest::Client::builder()
+            .connect_timeout(Duration::from_secs(10))
+            .user_agent(concat!("boxlite/", env!("CARGO_PKG_VERSION")))
+            .build()
+            .map_err(|e| BoxliteError::Network(format!("Failed to create HTTP client: {}", e)))?;
+
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))?;
+        listener.set_nonblocking(true)?;
+        let port = listener.local_addr()?.port();
+
+        let state = Arc::new(State {
+            registries: options.registries.clone(),
+            store,
+            client,
+        });
+        let (shutdown_tx, shutdown_rx) = oneshot::channel();
+        std::thread::Builder::new()
+            .name("registry-cache".into())
+            .spawn(move || {
+                let runtime = match tokio::runtime::Builder::new_current_thread()
+                    .enable_all()
+                    .build()
+                {
+                    Ok(runtime) => runtime,
+                    Err(e) => {
+                        tracing::error!(error = %e, "Failed to start registry cache");
+                        return;
+                    }
+                };
+                runtime.block_on(serve(listener, state, shutdown_rx));
+            })?;
+
+        tracing::info!(port, registries = ?options.registries, "Started registry cache");
+        Ok(Self {
+            port,
+            registries: options.registries.clone(),
+            _shutdown: shutdown_tx,
+        })
+    }
+
+    /// Point the package managers in `options` at the cache.
+    ///
+    /// Variables the box already sets are left alone.
+    pub fn apply(&self, mut options: BoxOptions) -> BoxOptions {
+        for (key, value) in guest_env(&self.registries, &format!("{}:{}", HOST_IP, self.port)) {
+            if !options.env.iter().any(|(k, _)| *k == key) {
+                options.env.push((key, value));
+            }
+        }
+        options
+    }
+}
+
+/// Environment variables routing package managers to the mirror at `host`.
+fn guest_env(registries: &[PackageRegistry], host: &str) -> Vec<(String, String)> {
+    let base = format!("http://{}", host);
+    let mut env = Vec::new();
+    let mut set = |key: &str, value: String| env.push((key.to_string(), value));
+
+    for registry in registries {
+        match registry {
+            PackageRegistry::CratesIo => {
+                set("CARGO_REGISTRIES_CRATES_IO_PROTOCOL", "sparse".into());
+                set("CARGO_SOURCE_CRATES_IO_REPLACE_WITH", "boxlite".into());
+                set(
+                    "CARGO_SOURCE_BOXLITE_REGISTRY",
+                    format!("sparse+{}/crates/", base),
+                );
+            }
+            PackageRegistry::PyPi => {
+                set("PIP_INDEX_URL", format!("{}/pypi/simple/", base));
+                set("PIP_TRUSTED_HOST", HOST_IP.into());
+                set("UV_DEFAULT_INDEX", format!("{}/pypi/simple/", base));
+                set("UV_INSECURE_HOST", HOST_IP.into());
+            }
+            PackageRegistry::Npm => {
+                set("NPM_CONFIG_REGISTRY", format!("{}/npm/", base));
+            }
+        }
+    }
+    env
+}
+
+struct State {
+    registries: Vec<PackageRegistry>,
+    store: Store,
+    client: reqwest::Client,
+}
+
+/// What to do for a request path.
+#[derive(Debug, PartialEq)]
+enum Route {
+    /// Serve a generated document.
+    Static {
+        body: String,
+        content_type: &'static str,
+    },
+    /// Immutable download, cached forever.
+    Artifact { upstream: String },
+    /// Index document, refreshed on every request.
+    Index {
+        upstream: String,
+        content_type: &'static str,
+        /// Registry download URL prefix to replace with the mirror's.
+        rewrite: Option<(&'static str, String)>,
+    },
+}
+
+/// Map a request path to a route; `base` is the mirror's URL as seen by
+/// the client.
+fn route(registries: &[PackageRegistry], path: &str, base: &str) -> Option<Route> {
+    let enabled = |registry| registries.contains(&registry);
+
+    if let Some(rest) = path.strip_prefix("/crates/")
+        && enabled(PackageRegistry::CratesIo)
+    {
+        if rest == "config.json" {
+            // Downloads come back through the mirror; the API stays upstream
+            let body = serde_json::json!({
+                "dl": format!("{}/crates/dl/{{crate}}/{{version}}", base),
+                "api": "https://crates.io",
+            });
+            return Some(Route::Static {
+                body: body.to_string(),
+                content_type: "application/json",
+            });
+        }
+        if let Some(download) = rest.strip_prefix("dl/") {
+            let (name, version) = download.split_once('/')?;
+            return Some(Route::Artifact {
+                upstream: format!("{}{}/{}-{}.crate", CRATES_DOWNLOAD, name, name, version),
+            });
+        }
+        return Some(Route::Index {
+            upstream: format!("{}{}", CRATES_INDEX, rest),
+            content_type: "text/plain",
+            rewrite: None,
+        });
+    }
+
+    if let Some(rest) = path.strip_prefix("/pypi/")
+        && enabled(PackageRegistry::PyPi)
+    {
+        if let Some(file) = rest.strip_prefix("files/") {
+            return Some(Route::Artifact {
+                upstream: format!("{}{}", PYPI_FILES, file),
+            });
+        }
+        let project = rest.strip_prefix("simple/")?;
+        return Some(Route::Index {
+            upstream: format!("{}{}", PYPI_INDEX, project),
+            content_type: "text/html",
+            rewrite: Some((PYPI_FILES, format!("{}/pypi/files/", base))),
+        });
+    }
+
+    if let Some(rest) = path.strip_prefix("/npm/")
+        && enabled(PackageRegistry::Npm)
+    {
+        let upstream = format!("{}{}", NPM_REGISTRY, rest);
+        if rest.contains("/-/") {
+            return Some(Route::Artifact { upstream });
+        }
+        return Some(Route::Index {
+            upstream,
+            content_type: "application/json",
+            rewrite: Some((NPM_REGISTRY, format!("{}/npm/", base))),
+        });
+    }
+
+    None
+}
+
+async fn serve(listener: TcpListener, state: Arc<State>, mut shutdown: oneshot::Receiver<()>) {
+    let listener = match tokio::net::TcpListener::from_std(listener) {
+        Ok(listener) => listener,
+        Err(e) => {
+            tracing::error!(error = %e, "Failed to start registry cache");
+            return;
+        }
+    };
+
+    loop {
+        let stream = tokio::select! {
+            _ = &mut shutdown => break,
+            accepted = listener.accept() => match accepted {
+                Ok((stream, _)) => stream,
+                Err(e) => {
+                    tracing::warn!(error = %e, "Registry cache failed to accept a connection");
+                    continue;
+                }
+            },
+        };
+
+        let state = state.clone();
+        tokio::spawn(async move {
+            let service = service_fn(move |request| handle(state.clone(), request));
+            if let Err(e) = hyper::server::conn::http1::Builder::new()
+                .serve_connection(TokioIo::new(stream), service)
+                .await
+            {
+                tracing::debug!(error = %e, "Registry cache connection ended");
+            }
+        });
+    }
+    tracing::debug!("Stopped registry cache");
+}
+
+async fn handle(
+    state: Arc<State>,
+    request: Request<Incoming>,
+) -> Result<Response<Body>, Infallible> {
+    if request.method() != Method::GET && request.method() != Method::HEAD {
+        return Ok(status(StatusCode::METHOD_NOT_ALLOWED));
+    }
+
+    let path = request.uri().path();
+    let base = match request
+        .headers()
+        .get(header::HOST)
+        .and_then(|h| h.to_str().ok())
+    {
+        Some(host) => format!("http://{}", host),
+        None => return Ok(status(StatusCode::BAD_REQUEST)),
+    };
+    let Some(route) = route(&state.registries, path, &base) else {
+        return Ok(status(StatusCode::NOT_FOUND));
+    };
+
+    let response = match route {
+        Route::Static { body, content_type } => Ok(full(body.into(), content_type)),
+        Route::Artifact { upstream } => state.artifact(&upstream).await,
+        Route::Index {
+            upstream,
+            content_type,
+            rewrite,
+        } => state.index(&upstream, content_type, rewrite).await,
+    };
+
+    Ok(response.unwrap_or_else(|e| {
+        tracing::warn!(path, error = %e, "Registry cache request failed");
+        status(StatusCode::BAD_GATEWAY)
+    }))
+}
+
+impl State {
+    async fn artifact(&self, upstream: &str) -> io::Result<Response<Body>> {
+        let path = self.store.path("artifacts", upstream);
+        if let Ok(file) = tokio::fs::File::open(&path).await {
+            tracing::trace!(upstream, "Registry cache hit");
+            return Ok(file_response(file));
+        }
+
+        let mut response = self
+            .client
+            .get(upstream)
+            .send()
+            .await
+            .map_err(io::Error::other)?;
+        if !response.status().is_success() {
+            return Ok(status(upstream_status(response.status())));
+        }
+
+        let (temp_path, mut temp) = self.store.temp_file().await?;
+        let download = async {
+            while let Some(chunk) = response.chunk().await.map_err(io::Error::other)? {
+                temp.write_all(&chunk).await?;
+            }
+            temp.flush().await
+        };
+        if let Err(e) = download.await {
+            let _ = tokio::fs::remove_file(&temp_path).await;
+            return Err(e);
+        }
+        self.store.commit(&temp_path, &path).await?;
+        tracing::debug!(upstream, "Cached registry artifact");
+
+        Ok(file_response(tokio::fs::File::open(&path).await?))
+    }
+
+    async fn index(
+        &self,
+        upstream: &str,
+        content_type: &'static str,
+        rewrite: Option<(&'static str, String)>,
+    ) -> io::Result<Response<Body>> {
+        let path = self.store.path("index", upstream);
+        let body = match self.fetch(upstream).await {
+            Ok(Ok(body)) => {
+                let (temp_path, mut temp) = self.store.temp_file().await?;
+                temp.write_all(&body).await?;
+                self.store.commit(&temp_path, &path).await?;
+                body
+            }
+            Ok(Err(code)) => return Ok(status(upstream_status(code))),
+            Err(e) => {
+                // Offline: fall back to the last good copy
+                let cached = tokio::fs::read(&path).await.map_err(|_| e)?;
+                tracing::debug!(upstream, "Registry unreachable, serving cached index");
+                Bytes::from(cached)
+            }
+        };
+
+        let body = match rewrite {
+            Some((from, to)) => String::from_utf8_lossy(&body).replace(from, &to).into(),
+            None => body,
+        };
+        Ok(full(body, content_type))
+    }
+
+    /// Fetch a document; `Ok(Err(_))` is a registry error response.
+    async fn fetch(&self, url: &str) -> io::Result<Result<Bytes, reqwest::StatusCode>> {
+        let response = self
+            .client
+            .get(url)
+            .send()
+            .await
+            .map_err(io::Error::other)?;
+        if !response.status().is_success() {
+            return Ok(Err(response.status()));
+        }
+        Ok(Ok(response.bytes().await.map_err(io::Error::other)?))
+    }
+}
+
+/// On-disk cache entries.
+struct Store {
+    dir: PathBuf,
+}
+
+impl Store {
+    fn open(dir: PathBuf) -> BoxliteResult<Self> {
+        let temp = dir.join("tmp");
+        // Leftovers of interrupted downloads
+        let _ = std::fs::remove_dir_all(&temp);
+        std::fs::create_dir_all(&temp).map_err(|e| {
+            BoxliteError::Storage(format!(
+                "Failed to create registry cache at {}: {}",
+                dir.display(),
+                e
+            ))
+        })?;
+        Ok(Self { dir })
+    }
+
+    /// Location of the entry for `url` in `kind`.
+    fn path(&self, kind: &str, url: &str) -> PathBuf {
+        let digest = hex::encode(Sha256::digest(url.as_bytes()));
+        self.dir.join(kind).join(&digest[..2]).join(&digest[2..])
+    }
+
+    async fn temp_file(&self) -> io::Result<(PathBuf, tokio::fs::File)> {
+        let path = self.dir.join("tmp").join(uuid::Uuid::new_v4().to_string());
+        let file = tokio::fs::File::create(&path).await?;
+        Ok((path, file))
+    }
+
+    /// Move a finished temp file into place.
+    async fn commit(&self, temp: &Path, path: &Path) -> io::Result<()> {
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        tokio::fs::rename(temp, path).await
+    }
+}
+
+fn upstream_status(code: reqwest::StatusCode) -> StatusCode {
+    StatusCode::from_u16(code.as_u16()).unwrap_or(StatusCode::BAD_GATEWAY)
+}
+
+fn status(code: StatusCode) -> Response<Body> {
+    let mut response = Response::new(Full::new(Bytes::new()).map_err(|e| match e {}).boxed());
+    *response.status_mut() = code;
+    response
+}
+
+fn full(body: Bytes, content_type: &'static str) -> Response<Body> {
+    let mut response = Response::new(Full::new(body).map_err(|e| match e {}).boxed());
+    response.headers_mut().insert(
+        header::CONTENT_TYPE,
+        header::HeaderValue::from_static(content_type),
+    );
+    response
+}
+
+fn file_response(mut file: tokio::fs::File) -> Response<Body> {
+    let chunks = async_stream::stream! {
+        loop {
+            let mut data = vec![0; CHUNK_SIZE];
+            match file.read(&mut data).await {
+                Ok(0) => break,
+                Ok(n) => {
+                    data.truncate(n);
+                    yield Ok(Frame::data(Bytes::from(data)));
+                }
+                Err(e) => {
+                    yield Err(e);
+                    break;
+                }
+            }
+        }
+    };
+    let mut response = Response::new(BodyExt::boxed(StreamBody::new(chunks)));
+    response.headers_mut().insert(
+        header::CONTENT_TYPE,
+        header::HeaderValue::from_static("application/octet-stream"),
+    );
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL: &[PackageRegistry] = &[
+        PackageRegistry::CratesIo,
+        PackageRegistry::PyPi,
+        PackageRegistry::Npm,
+    ];
+
+    #[test]
+    fn test_route_upstreams() {
+        let base = "http://192.168.127.254:4000";
+        let upstream = |path| match route(ALL, path, base) {
+            Some(Route::Artifact { upstream }) => format!("artifact {}", upstream),
+            Some(Route::Index { upstream, .. }) => format!("index {}", upstream),
+            other => format!("{:?}", other),
+        };
+
+        assert_eq!(
+            upstream("/crates/se/rd/serde"),
+            "index https://index.crates.io/se/rd/serde"
+        );
+        assert_eq!(
+            upstream("/crates/dl/serde/1.0.0"),
+            "artifact https://static.crates.io/crates/serde/serde-1.0.0.crate"
+        );
+        assert_eq!(
+            upstream("/pypi/simple/requests/"),
+            "index https://pypi.org/simple/requests/"
+        );
+        assert_eq!(
+            upstream("/pypi/files/packages/ab/cd/requests-2.0.whl"),
+            "artifact https://files.pythonhosted.org/packages/ab/cd/requests-2.0.whl"
+        );
+        assert_eq!(
+            upstream("/npm/@types%2fnode"),
+            "index https://registry.npmjs.org/@types%2fnode"
+        );
+        assert_eq!(
+            upstream("/npm/lodash/-/lodash-4.17.21.tgz"),
+            "artifact https://registry.npmjs.org/lodash/-/lodash-4.17.21.tgz"
+        );
+        assert_eq!(upstream("/maven/junit"), "None");
+
+        // Only enabled registries are served
+        assert_eq!(
+            route(&[PackageRegistry::Npm], "/pypi/simple/x/", base),
+            None
+        );
+    }
+
+    #[test]
+    fn test_crates_config_points_downloads_at_mirror() {
+        let Some(Route::Static { body, .. }) = route(ALL, "/crates/config.json", "http://mirror:1")
+        else {
+            panic!("config.json is generated");
+        };
+        let config: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(config["dl"], "http://mirror:1/crates/dl/{crate}/{version}");
+    }
+
+    #[test]
+    fn test_apply_keeps_box_env() {
+        let env = guest_env(&[PackageRegistry::PyPi], "192.168.127.254:4000");
+        assert!(env.contains(&(
+            "PIP_INDEX_URL".into(),
+            "http://192.168.127.254:4000/pypi/simple/".into()
+        )));
+        assert!(!env.iter().any(|(k, _)| k.starts_with("CARGO_")));
+
+        let (shutdown, _) = oneshot::channel();
+        let cache = RegistryCache {
+            port: 4000,
+            registries: vec![PackageRegistry::PyPi],
+            _shutdown: shutdown,
+        };
+        let options = cache.apply(BoxOptions {
+            env: vec![("PIP_INDEX_URL".into(), "https://internal/simple".into())],
+            ..Default::default()
+        });
+        let index: Vec<_> = options
+            .env
+            .iter()
+            .filter(|(k, _)| k == "PIP_INDEX_URL")
+            .collect();
+        assert_eq!(
+            index,
+            [&("PIP_INDEX_URL".into(), "https://internal/simple".into())]
+        );
+    }
+}
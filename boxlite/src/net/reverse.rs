@@ -0,0 +1,131 @@
+//! Reverse port forwarding, see [`BoxOptions::reverse_ports`](crate::BoxOptions::reverse_ports).
+//!
+//! The guest listens on the box's loopback and relays each connection to a
+//! vsock port, which the VMM bridges to a host Unix socket. Targets that are
+//! TCP addresses get a Unix socket in the box's sockets directory, relayed
+//! to the address by the shim.
+
+use std::io::{Read, Write};
+use std::net::{Shutdown, TcpStream};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+
+use boxlite_shared::errors::{BoxliteError, BoxliteResult};
+
+/// Host end of a reverse port.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum ReverseTarget {
+    /// A Unix socket, bridged to by the VMM directly
+    Unix(PathBuf),
+    /// A TCP address (`host:port`)
+    Tcp(String),
+}
+
+/// A reverse port as passed to the shim.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ReversePort {
+    pub target: ReverseTarget,
+    /// Socket relayed to a TCP target
+    pub socket_path: PathBuf,
+}
+
+impl ReversePort {
+    /// Unix socket the VMM bridges the guest's connections to, starting the
+    /// relay of a TCP target for the life of the process.
+    pub fn host_socket(&self) -> BoxliteResult<PathBuf> {
+        let address = match &self.target {
+            ReverseTarget::Unix(path) => return Ok(path.clone()),
+            ReverseTarget::Tcp(address) => address.clone(),
+        };
+
+        let failed = |e: std::io::Error| {
+            BoxliteError::Network(format!(
+                "Failed to relay reverse port to {}: {}",
+                address, e
+            ))
+        };
+        // Left behind by an earlier run of the box
+        let _ = std::fs::remove_file(&self.socket_path);
+        let listener = UnixListener::bind(&self.socket_path).map_err(failed)?;
+        let thread_address = address.clone();
+        std::thread::Builder::new()
+            .name(format!("reverse-{}", address))
+            .spawn(move || relay(listener, thread_address))
+            .map_err(failed)?;
+        tracing::debug!(address, socket_path = ?self.socket_path, "Relaying reverse port");
+        Ok(self.socket_path.clone())
+    }
+}
+
+/// Socket of the reverse port with box port `guest_port` in `sockets_dir`.
+pub fn socket_path(sockets_dir: &Path, guest_port: u16) -> PathBuf {
+    sockets_dir.join(format!("reverse-{}.sock", guest_port))
+}
+
+/// Connect each accepted connection to `address`.
+fn relay(listener: UnixListener, address: String) {
+    for conn in listener.incoming() {
+        let guest = match conn {
+            Ok(conn) => conn,
+            Err(e) => {
+                tracing::debug!(address, error = %e, "Failed to accept reverse connection");
+                continue;
+            }
+        };
+        let address = address.clone();
+        std::thread::spawn(move || match TcpStream::connect(&address) {
+            Ok(host) => pipe(guest, host),
+            Err(e) => tracing::debug!(address, error = %e, "Failed to reach reverse port target"),
+        });
+    }
+}
+
+/// Copy between the guest and host connections until both are done.
+fn pipe(guest: UnixStream, host: TcpStream) {
+    let (Ok(guest_in), Ok(host_in)) = (guest.try_clone(), host.try_clone()) else {
+        return;
+    };
+    let upstream = std::thread::spawn(move || {
+        copy(guest_in, &host_in);
+        let _ = host_in.shutdown(Shutdown::Write);
+    });
+    copy(&host, &guest);
+    let _ = guest.shutdown(Shutdown::Write);
+    let _ = upstream.join();
+}
+
+fn copy(mut from: impl Read, mut to: impl Write) {
+    let _ = std::io::copy(&mut from, &mut to);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+
+    #[test]
+    fn test_tcp_target_relay() {
+        let dir = tempfile::tempdir().unwrap();
+        let server = TcpListener::bind("127.0.0.1:0").unwrap();
+        let reverse = ReversePort {
+            target: ReverseTarget::Tcp(server.local_addr().unwrap().to_string()),
+            socket_path: socket_path(dir.path(), 8080),
+        };
+
+        let path = reverse.host_socket().unwrap();
+        let mut guest = UnixStream::connect(path).unwrap();
+        guest.write_all(b"ping").unwrap();
+        guest.shutdown(Shutdown::Write).unwrap();
+
+        let (mut host, _) = server.accept().unwrap();
+        let mut received = String::new();
+        host.read_to_string(&mut received).unwrap();
+        assert_eq!(received, "ping");
+        host.write_all(b"pong").unwrap();
+        drop(host);
+
+        let mut reply = String::new();
+        guest.read_to_string(&mut reply).unwrap();
+        assert_eq!(reply, "pong");
+    }
+}
@@ -0,0 +1,224 @@
+//! Inter-box networks, see [`BoxOptions::networks`](crate::BoxOptions::networks).
+//!
+//! There is no switch process: each box binds a datagram socket named after
+//! its MAC address in the network's directory, and sends its frames straight
+//! to the socket of the destination MAC, or to every socket for broadcast and
+//! multicast frames. Boxes that are not running have no live socket, so
+//! frames to them are dropped like on a real network.
+//!
+//! The directory also holds the network's `hosts` file, from which the
+//! network backend answers DNS queries for box names.
+
+use std::io::Write;
+use std::net::Ipv4Addr;
+use std::os::fd::{IntoRawFd, RawFd};
+use std::os::unix::net::UnixDatagram;
+use std::path::{Path, PathBuf};
+
+use boxlite_shared::errors::{BoxliteError, BoxliteResult};
+
+/// Largest frame relayed; interfaces on these networks have no offloads.
+const MAX_FRAME_SIZE: usize = 65536;
+
+/// A box's interface on an inter-box network.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct NetworkAttachment {
+    /// Network name
+    pub network: String,
+    /// Directory with the sockets of the network's boxes
+    pub socket_dir: PathBuf,
+    /// MAC address of the box's interface
+    pub mac_address: [u8; 6],
+}
+
+impl NetworkAttachment {
+    /// MAC address of the box with `ip` on a network; unique since
+    /// networks do not share subnets.
+    pub fn mac_for(ip: Ipv4Addr) -> [u8; 6] {
+        let [a, b, c, d] = ip.octets();
+        [0x02, 0xb1, a, b, c, d]
+    }
+}
+
+/// Plug the box into its network.
+///
+/// Returns the VM's end of a datagram socket pair carrying the interface's
+/// frames, which two threads relay to and from the other boxes for the life
+/// of the process.
+pub fn connect(attachment: &NetworkAttachment) -> BoxliteResult<RawFd> {
+    let network_err = |what: &str, e: std::io::Error| {
+        BoxliteError::Network(format!(
+            "Failed to {} for network '{}': {}",
+            what, attachment.network, e
+        ))
+    };
+
+    std::fs::create_dir_all(&attachment.socket_dir)
+        .map_err(|e| network_err("create socket directory", e))?;
+    let own_path = socket_path(&attachment.socket_dir, &attachment.mac_address);
+    // Left behind by an earlier run of the box
+    let _ = std::fs::remove_file(&own_path);
+    let port = UnixDatagram::bind(&own_path).map_err(|e| network_err("bind socket", e))?;
+    let (vm, host) = UnixDatagram::pair().map_err(|e| network_err("create socket pair", e))?;
+
+    let port_in = port
+        .try_clone()
+        .map_err(|e| network_err("clone socket", e))?;
+    let host_in = host
+        .try_clone()
+        .map_err(|e| network_err("clone socket", e))?;
+    let dir = attachment.socket_dir.clone();
+    std::thread::Builder::new()
+        .name(format!("net-{}-out", attachment.network))
+        .spawn(move || relay_out(host, port, dir, own_path))
+        .map_err(|e| network_err("spawn relay thread", e))?;
+    std::thread::Builder::new()
+        .name(format!("net-{}-in", attachment.network))
+        .spawn(move || relay_in(port_in, host_in))
+        .map_err(|e| network_err("spawn relay thread", e))?;
+
+    tracing::info!(
+        network = %attachment.network,
+        mac_address = ?attachment.mac_address,
+        "Connected to inter-box network"
+    );
+    Ok(vm.into_raw_fd())
+}
+
+/// Hosts file of the network with socket directory `dir`.
+pub fn hosts_path(dir: &Path) -> PathBuf {
+    dir.join("hosts")
+}
+
+/// Replace the network's hosts file with `entries` (name, address).
+///
+/// Written to a temporary file first, so that readers never see a partial
+/// file.
+pub fn write_hosts(dir: &Path, entries: &[(String, Ipv4Addr)]) -> BoxliteResult<()> {
+    let failed = |e: std::io::Error| {
+        BoxliteError::Network(format!(
+            "Failed to write hosts file in {}: {}",
+            dir.display(),
+            e
+        ))
+    };
+    std::fs::create_dir_all(dir).map_err(failed)?;
+    let contents: String = entries
+        .iter()
+        .map(|(name, ip)| format!("{} {}\n", ip, name))
+        .collect();
+    let mut tmp = tempfile::NamedTempFile::new_in(dir).map_err(failed)?;
+    tmp.write_all(contents.as_bytes()).map_err(failed)?;
+    tmp.persist(hosts_path(dir)).map_err(|e| failed(e.error))?;
+    Ok(())
+}
+
+fn socket_path(dir: &Path, mac_address: &[u8; 6]) -> PathBuf {
+    dir.join(format!("{}.sock", hex::encode(mac_address)))
+}
+
+/// Send the VM's frames to the other boxes.
+fn relay_out(vm: UnixDatagram, port: UnixDatagram, dir: PathBuf, own_path: PathBuf) {
+    let mut buf = vec![0u8; MAX_FRAME_SIZE];
+    loop {
+        let n = match vm.recv(&mut buf) {
+            Ok(n) => n,
+            Err(e) => {
+                tracing::debug!(error = %e, "Inter-box network relay stopped");
+                return;
+            }
+        };
+        if n < 14 {
+            continue;
+        }
+        let frame = &buf[..n];
+        let dst: [u8; 6] = frame[..6].try_into().unwrap();
+
+        // Unicast: only the destination, if it is up
+        if dst[0] & 1 == 0 {
+            let _ = port.send_to(frame, socket_path(&dir, &dst));
+            continue;
+        }
+
+        // Broadcast and multicast (e.g. ARP): every other box
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path != own_path && path.extension().is_some_and(|ext| ext == "sock") {
+                let _ = port.send_to(frame, &path);
+            }
+        }
+    }
+}
+
+/// Hand the other boxes' frames to the VM.
+fn relay_in(port: UnixDatagram, vm: UnixDatagram) {
+    let mut buf = vec![0u8; MAX_FRAME_SIZE];
+    loop {
+        let n = match port.recv(&mut buf) {
+            Ok(n) => n,
+            Err(e) => {
+                tracing::debug!(error = %e, "Inter-box network relay stopped");
+                return;
+            }
+        };
+        if vm.send(&buf[..n]).is_err() {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::fd::FromRawFd;
+
+    #[test]
+    fn test_frames_reach_peer() {
+        let dir = tempfile::tempdir().unwrap();
+        let attach = |ip: Ipv4Addr| NetworkAttachment {
+            network: "test".to_string(),
+            socket_dir: dir.path().to_path_buf(),
+            mac_address: NetworkAttachment::mac_for(ip),
+        };
+        let a = attach(Ipv4Addr::new(10, 89, 0, 2));
+        let b = attach(Ipv4Addr::new(10, 89, 0, 3));
+        let vm_a = unsafe { UnixDatagram::from_raw_fd(connect(&a).unwrap()) };
+        let vm_b = unsafe { UnixDatagram::from_raw_fd(connect(&b).unwrap()) };
+        vm_b.set_read_timeout(Some(std::time::Duration::from_secs(5)))
+            .unwrap();
+
+        let mut frame = [0u8; 60];
+        frame[..6].copy_from_slice(&b.mac_address);
+        frame[6..12].copy_from_slice(&a.mac_address);
+        vm_a.send(&frame).unwrap();
+
+        let mut buf = [0u8; 128];
+        let n = vm_b.recv(&mut buf).unwrap();
+        assert_eq!(&buf[..n], &frame[..]);
+
+        // Broadcast
+        frame[..6].copy_from_slice(&[0xff; 6]);
+        vm_a.send(&frame).unwrap();
+        let n = vm_b.recv(&mut buf).unwrap();
+        assert_eq!(&buf[..6], &[0xff; 6]);
+        assert_eq!(n, frame.len());
+    }
+
+    #[test]
+    fn test_write_hosts() {
+        let dir = tempfile::tempdir().unwrap();
+        let entries = vec![
+            ("app".to_string(), Ipv4Addr::new(10, 89, 0, 2)),
+            ("redis".to_string(), Ipv4Addr::new(10, 89, 0, 3)),
+        ];
+        write_hosts(dir.path(), &entries).unwrap();
+        write_hosts(dir.path(), &entries[1..]).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(hosts_path(dir.path())).unwrap(),
+            "10.89.0.3 redis\n"
+        );
+    }
+}
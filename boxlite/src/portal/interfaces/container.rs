@@ -1,14 +1,54 @@
 //! Container service interface.
 
+use std::net::IpAddr;
+use std::path::Path;
+
 use boxlite_shared::{
     BindMount, BoxliteError, BoxliteResult, ContainerClient,
-    ContainerConfig as ProtoContainerConfig, ContainerInitRequest, DiskRootfs, MergedRootfs,
-    OverlayRootfs, RootfsInit, container_init_response,
+    ContainerConfig as ProtoContainerConfig, ContainerInitRequest, ContainerNetwork, DiskRootfs,
+    ExportLayerRequest, FileEntry, HostEntry, ListFilesRequest, MergedRootfs, OverlayRootfs,
+    PortForward, RootfsInit, SocketForward, TmpfsMount, container_init_response,
 };
+use tokio::io::AsyncWriteExt;
 use tonic::transport::Channel;
 
+use crate::rootfs::diff::RootfsChanges;
+use crate::runtime::constants::network::{HOST_SOCKET_BASE_PORT, REVERSE_PORT_BASE_PORT};
+use crate::runtime::options::{DiskQuota, QuotaTarget, ReversePortSpec, SocketSpec, VolumeSpec};
 use crate::volumes::ContainerMount;
 
+/// Hostname and name resolution of the container, set by the guest in its
+/// UTS namespace, `/etc/hostname`, `/etc/resolv.conf` and `/etc/hosts`.
+#[derive(Debug, Clone, Default)]
+pub struct ContainerNetworkConfig {
+    /// Hostname; empty uses `boxlite`.
+    pub hostname: String,
+    /// Nameservers; empty uses the network gateway.
+    pub dns_servers: Vec<String>,
+    /// Search domains; empty uses `localdomain`.
+    pub dns_search: Vec<String>,
+    /// Extra (host, IP) entries of `/etc/hosts`.
+    pub extra_hosts: Vec<(String, IpAddr)>,
+}
+
+impl ContainerNetworkConfig {
+    fn into_proto(self) -> ContainerNetwork {
+        ContainerNetwork {
+            dns_servers: self.dns_servers,
+            dns_search: self.dns_search,
+            extra_hosts: self
+                .extra_hosts
+                .into_iter()
+                .map(|(hostname, ip)| HostEntry {
+                    hostname,
+                    ip: ip.to_string(),
+                })
+                .collect(),
+            hostname: self.hostname,
+        }
+    }
+}
+
 /// Container rootfs initialization strategy.
 /// Guest constructs paths from container_id using its own layout knowledge.
 #[derive(Debug, Clone)]
@@ -32,6 +72,10 @@ pub enum ContainerRootfsInitConfig {
         need_format: bool,
         /// Whether to resize filesystem after mounting to fill disk
         need_resize: bool,
+        /// Whether to check and repair the filesystem before mounting
+        need_fsck: bool,
+        /// Write quotas to enforce on the mounted filesystem
+        quotas: Vec<DiskQuota>,
     },
 }
 
@@ -58,17 +102,33 @@ impl ContainerRootfsInitConfig {
                 device,
                 need_format,
                 need_resize,
+                need_fsck,
+                quotas,
             } => RootfsInit {
                 strategy: Some(boxlite_shared::rootfs_init::Strategy::Disk(DiskRootfs {
                     device,
                     need_format,
                     need_resize,
+                    need_fsck,
+                    quotas: quotas.into_iter().map(quota_to_proto).collect(),
                 })),
             },
         }
     }
 }
 
+fn quota_to_proto(quota: DiskQuota) -> boxlite_shared::DiskQuota {
+    use boxlite_shared::disk_quota::Target;
+
+    boxlite_shared::DiskQuota {
+        target: Some(match quota.target {
+            QuotaTarget::Path(path) => Target::Path(path),
+            QuotaTarget::User(uid) => Target::Uid(uid),
+        }),
+        limit_bytes: quota.limit_bytes,
+    }
+}
+
 /// Container service interface.
 pub struct ContainerInterface {
     client: ContainerClient<Channel>,
@@ -89,20 +149,32 @@ impl ContainerInterface {
     /// * `image_config` - Image-derived container config (entrypoint, env, workdir)
     /// * `rootfs` - Rootfs initialization strategy
     /// * `mounts` - Bind mounts from guest VM paths into container
+    /// * `tmpfs` - tmpfs volumes in the container (other volumes are skipped)
+    /// * `sockets` - Host Unix sockets exposed in the container
+    /// * `reverse_ports` - Host services reachable on the guest's loopback
+    /// * `tty` - Run the init process on a terminal
+    /// * `network` - Name resolution of the container
     ///
     /// # Returns
     /// Container ID on success
+    #[allow(clippy::too_many_arguments)]
     pub async fn init(
         &mut self,
         container_id: &str,
         image_config: crate::images::ContainerImageConfig,
         rootfs: ContainerRootfsInitConfig,
         mounts: Vec<ContainerMount>,
+        tmpfs: Vec<VolumeSpec>,
+        sockets: Vec<SocketSpec>,
+        reverse_ports: Vec<ReversePortSpec>,
+        tty: bool,
+        network: ContainerNetworkConfig,
     ) -> BoxliteResult<String> {
         let proto_config = ProtoContainerConfig {
             entrypoint: image_config.cmd.clone(),
             env: image_config.env.clone(),
             workdir: image_config.working_dir.clone(),
+            user: image_config.user.clone(),
         };
 
         // Convert ContainerMount to proto BindMount
@@ -113,6 +185,39 @@ impl ContainerInterface {
                 volume_name: m.volume_name,
                 destination: m.destination,
                 read_only: m.read_only,
+                subpath: m.subpath.unwrap_or_default(),
+            })
+            .collect();
+
+        let proto_tmpfs: Vec<TmpfsMount> = tmpfs
+            .into_iter()
+            .filter_map(|t| match t {
+                VolumeSpec::Tmpfs {
+                    guest_path,
+                    size,
+                    mode,
+                } => Some(TmpfsMount {
+                    destination: guest_path,
+                    size_bytes: size.unwrap_or(0),
+                    mode: mode.unwrap_or(0),
+                }),
+                VolumeSpec::Bind { .. } => None,
+            })
+            .collect();
+
+        // Ports match the engine's bridges to the host sockets
+        let proto_sockets: Vec<SocketForward> = (HOST_SOCKET_BASE_PORT..)
+            .zip(sockets)
+            .map(|(port, s)| SocketForward {
+                destination: s.guest_path,
+                port,
+            })
+            .collect();
+        let proto_reverse_ports: Vec<PortForward> = (REVERSE_PORT_BASE_PORT..)
+            .zip(reverse_ports)
+            .map(|(port, r)| PortForward {
+                guest_port: r.guest_port.into(),
+                port,
             })
             .collect();
 
@@ -121,6 +226,7 @@ impl ContainerInterface {
             container_id = %container_id,
             entrypoint = ?image_config.cmd,
             workdir = %image_config.working_dir,
+            user = %image_config.user,
             env_count = image_config.env.len(),
             rootfs = ?rootfs,
             mounts_count = proto_mounts.len(),
@@ -132,6 +238,11 @@ impl ContainerInterface {
             container_config: Some(proto_config),
             rootfs: Some(rootfs.into_proto()),
             mounts: proto_mounts,
+            tmpfs: proto_tmpfs,
+            sockets: proto_sockets,
+            reverse_ports: proto_reverse_ports,
+            tty,
+            network: Some(network.into_proto()),
         };
 
         let response = self.client.init(request).await?.into_inner();
@@ -153,4 +264,56 @@ impl ContainerInterface {
             )),
         }
     }
+
+    /// List the files of the container rootfs.
+    pub async fn list_files(&mut self, container_id: &str) -> BoxliteResult<Vec<FileEntry>> {
+        let request = ListFilesRequest {
+            container_id: container_id.to_string(),
+        };
+        let mut stream = self.client.list_files(request).await?.into_inner();
+
+        let mut files = Vec::new();
+        while let Some(batch) = stream.message().await? {
+            files.extend(batch.entries);
+        }
+        Ok(files)
+    }
+
+    /// Write a layer tar of the given rootfs changes to `dest`.
+    ///
+    /// # Returns
+    /// Size of the tar in bytes
+    pub async fn export_layer(
+        &mut self,
+        container_id: &str,
+        changes: RootfsChanges,
+        dest: &Path,
+    ) -> BoxliteResult<u64> {
+        let request = ExportLayerRequest {
+            container_id: container_id.to_string(),
+            paths: changes.paths,
+            deleted: changes.deleted,
+        };
+        let mut stream = self.client.export_layer(request).await?.into_inner();
+
+        let mut file = tokio::fs::File::create(dest).await.map_err(|e| {
+            BoxliteError::Storage(format!("Failed to create {}: {}", dest.display(), e))
+        })?;
+        let result: BoxliteResult<u64> = async {
+            let mut written = 0;
+            while let Some(chunk) = stream.message().await? {
+                file.write_all(&chunk.data).await?;
+                written += chunk.data.len() as u64;
+            }
+            file.flush().await?;
+            Ok(written)
+        }
+        .await;
+
+        // Don't leave a truncated tar behind
+        if result.is_err() {
+            let _ = tokio::fs::remove_file(dest).await;
+        }
+        result
+    }
 }
@@ -3,13 +3,19 @@
 //! High-level API for execution operations (unary Exec + output-only Attach +
 //! blocking Wait).
 
-use crate::litebox::{BoxCommand, ExecResult};
+use crate::litebox::{
+    BoxCommand, ExecInfo, ExecResult, ExecState, ExitStatus, OutputFrame, OutputLimitPolicy,
+};
+use crate::runtime::options::{ExecBufferOptions, ExecBufferPolicy};
 use boxlite_shared::{
     AttachRequest, BoxliteError, BoxliteResult, ExecOutput, ExecRequest, ExecStdin,
-    ExecutionClient, KillRequest, WaitRequest, WaitResponse, exec_output,
+    ExecutionClient, ExecutionInfo, KillRequest, ListExecutionsRequest, WaitRequest, WaitResponse,
+    exec_output,
 };
+use bytes::Bytes;
 use tokio::sync::mpsc;
 use tokio_stream::wrappers::ReceiverStream;
+use tonic::Streaming;
 use tonic::transport::Channel;
 
 /// Execution service interface.
@@ -21,9 +27,10 @@ pub struct ExecutionInterface {
 /// Components for building an Execution.
 pub struct ExecComponents {
     pub execution_id: String,
-    pub stdin_tx: mpsc::UnboundedSender<Vec<u8>>,
-    pub stdout_rx: mpsc::UnboundedReceiver<String>,
-    pub stderr_rx: mpsc::UnboundedReceiver<String>,
+    /// Not available when reattaching to an existing execution.
+    pub stdin_tx: Option<mpsc::UnboundedSender<Vec<u8>>>,
+    pub stdout_rx: mpsc::Receiver<OutputFrame>,
+    pub stderr_rx: mpsc::Receiver<OutputFrame>,
     pub result_rx: mpsc::UnboundedReceiver<ExecResult>,
 }
 
@@ -36,15 +43,19 @@ impl ExecutionInterface {
     }
 
     /// Execute a command and return execution components.
-    pub async fn exec(&mut self, command: BoxCommand) -> BoxliteResult<ExecComponents> {
+    pub async fn exec(
+        &mut self,
+        command: BoxCommand,
+        buffer: ExecBufferOptions,
+    ) -> BoxliteResult<ExecComponents> {
         // Create channels
         let (stdin_tx, stdin_rx) = mpsc::unbounded_channel::<Vec<u8>>();
-        let (stdout_tx, stdout_rx) = mpsc::unbounded_channel::<String>();
-        let (stderr_tx, stderr_rx) = mpsc::unbounded_channel::<String>();
+        let (stdout_tx, stdout_rx) = output_channel(buffer);
+        let (stderr_tx, stderr_rx) = output_channel(buffer);
         let (result_tx, result_rx) = mpsc::unbounded_channel();
 
         // Build request
-        let request = ExecProtocol::build_exec_request(&command);
+        let request = ExecProtocol::build_exec_request(&command, buffer);
 
         tracing::debug!(?command, "Starting execution");
 
@@ -66,8 +77,7 @@ impl ExecutionInterface {
         ExecProtocol::spawn_attach(
             self.client.clone(),
             execution_id.clone(),
-            stdout_tx,
-            stderr_tx,
+            OutputSink::new(stdout_tx, stderr_tx, buffer.policy),
         );
 
         // Spawn wait task for terminal status
@@ -75,13 +85,109 @@ impl ExecutionInterface {
 
         Ok(ExecComponents {
             execution_id,
-            stdin_tx,
+            stdin_tx: Some(stdin_tx),
+            stdout_rx,
+            stderr_rx,
+            result_rx,
+        })
+    }
+
+    /// Reattach to the output and exit status of an existing execution.
+    ///
+    /// Only one client can be attached at a time. Stdin is not reattached.
+    pub async fn attach(
+        &mut self,
+        execution_id: &str,
+        buffer: ExecBufferOptions,
+    ) -> BoxliteResult<ExecComponents> {
+        let (stdout_tx, stdout_rx) = output_channel(buffer);
+        let (stderr_tx, stderr_rx) = output_channel(buffer);
+        let (result_tx, result_rx) = mpsc::unbounded_channel();
+
+        let request = AttachRequest {
+            execution_id: execution_id.to_string(),
+        };
+        let stream = self
+            .client
+            .attach(request)
+            .await
+            .map_err(|e| match e.code() {
+                tonic::Code::NotFound => {
+                    BoxliteError::NotFound(format!("execution {}", execution_id))
+                }
+                tonic::Code::AlreadyExists => BoxliteError::InvalidState(format!(
+                    "execution {} is already attached",
+                    execution_id
+                )),
+                _ => e.into(),
+            })?
+            .into_inner();
+
+        tracing::debug!(execution_id = %execution_id, "Reattached to execution");
+
+        let exec_id = execution_id.to_string();
+        let mut client = self.client.clone();
+        let mut sink = OutputSink::new(stdout_tx, stderr_tx, buffer.policy);
+        tokio::spawn(async move {
+            ExecProtocol::pump_output(&mut client, &exec_id, stream, &mut sink).await;
+        });
+        ExecProtocol::spawn_wait(self.client.clone(), execution_id.to_string(), result_tx);
+
+        Ok(ExecComponents {
+            execution_id: execution_id.to_string(),
+            stdin_tx: None,
             stdout_rx,
             stderr_rx,
             result_rx,
         })
     }
 
+    /// Attach to a container's init process, including its stdin.
+    ///
+    /// The guest registers init as a detached execution under the
+    /// container ID. Its stdin stays open when this handle goes away.
+    pub async fn attach_init(
+        &mut self,
+        container_id: &str,
+        buffer: ExecBufferOptions,
+    ) -> BoxliteResult<ExecComponents> {
+        let mut components = self.attach(container_id, buffer).await?;
+
+        let (stdin_tx, stdin_rx) = mpsc::unbounded_channel::<Vec<u8>>();
+        ExecProtocol::spawn_stdin(self.client.clone(), container_id.to_string(), stdin_rx);
+        components.stdin_tx = Some(stdin_tx);
+        Ok(components)
+    }
+
+    /// Wait for a container's init process to exit.
+    pub async fn wait_init(&mut self, container_id: &str) -> BoxliteResult<ExitStatus> {
+        let request = WaitRequest {
+            execution_id: container_id.to_string(),
+        };
+
+        let response = self.client.wait(request).await?.into_inner();
+        let oom_killed = response.oom_killed;
+        Ok(ExitStatus {
+            exit_code: ExecProtocol::map_wait_response(response).exit_code,
+            oom_killed,
+        })
+    }
+
+    /// List executions known to the guest, oldest first.
+    pub async fn list(&mut self) -> BoxliteResult<Vec<ExecInfo>> {
+        let response = self
+            .client
+            .list_executions(ListExecutionsRequest {})
+            .await?
+            .into_inner();
+
+        Ok(response
+            .executions
+            .into_iter()
+            .map(ExecProtocol::map_execution_info)
+            .collect())
+    }
+
     /// Wait for execution to complete.
     #[allow(dead_code)] // API method for future use
     pub async fn wait(&mut self, execution_id: &str) -> BoxliteResult<ExecResult> {
@@ -144,6 +250,97 @@ impl ExecutionInterface {
     }
 }
 
+/// Times an attach stream or a wait is resumed after the connection to the
+/// guest broke.
+const RECONNECT_ATTEMPTS: u32 = 10;
+
+/// Whether `status` means the connection to the guest broke rather than
+/// the call failing.
+///
+/// `Unknown` is only a disconnect when it wraps an I/O error from the
+/// transport; the guest itself also reports failures as `Unknown`.
+fn is_disconnect(status: &tonic::Status) -> bool {
+    match status.code() {
+        tonic::Code::Unavailable => true,
+        tonic::Code::Unknown => {
+            let mut source = std::error::Error::source(status);
+            while let Some(err) = source {
+                if err.is::<std::io::Error>() {
+                    return true;
+                }
+                source = err.source();
+            }
+            false
+        }
+        _ => false,
+    }
+}
+
+/// Wait before reconnect `attempt` (starting at 1).
+fn reconnect_delay(attempt: u32) -> std::time::Duration {
+    std::time::Duration::from_millis(200 * u64::from(attempt))
+}
+
+/// Output channel holding up to the configured number of chunks.
+fn output_channel(
+    buffer: ExecBufferOptions,
+) -> (mpsc::Sender<OutputFrame>, mpsc::Receiver<OutputFrame>) {
+    // Blocks are allocated as the channel fills, so a huge bound costs
+    // nothing up front
+    mpsc::channel(
+        buffer
+            .capacity
+            .unwrap_or(tokio::sync::Semaphore::MAX_PERMITS)
+            .min(tokio::sync::Semaphore::MAX_PERMITS),
+    )
+}
+
+/// Where attach stream output goes.
+struct OutputSink {
+    stdout_tx: mpsc::Sender<OutputFrame>,
+    stderr_tx: mpsc::Sender<OutputFrame>,
+    policy: ExecBufferPolicy,
+    dropped: u64,
+}
+
+impl OutputSink {
+    fn new(
+        stdout_tx: mpsc::Sender<OutputFrame>,
+        stderr_tx: mpsc::Sender<OutputFrame>,
+        policy: ExecBufferPolicy,
+    ) -> Self {
+        Self {
+            stdout_tx,
+            stderr_tx,
+            policy,
+            dropped: 0,
+        }
+    }
+
+    async fn send(&mut self, frame: OutputFrame, is_stderr: bool) {
+        let tx = if is_stderr {
+            &self.stderr_tx
+        } else {
+            &self.stdout_tx
+        };
+        match self.policy {
+            ExecBufferPolicy::Block => {
+                let _ = tx.send(frame).await;
+            }
+            ExecBufferPolicy::Drop => {
+                if let Err(mpsc::error::TrySendError::Full(_)) = tx.try_send(frame) {
+                    self.dropped += 1;
+                }
+            }
+        }
+    }
+
+    /// Report a stream error on stderr, even if it has to wait for room.
+    async fn send_error(&self, frame: OutputFrame) {
+        let _ = self.stderr_tx.send(frame).await;
+    }
+}
+
 // ============================================================================
 // Helper: Protocol wiring
 // ============================================================================
@@ -151,8 +348,8 @@ impl ExecutionInterface {
 struct ExecProtocol;
 
 impl ExecProtocol {
-    fn build_exec_request(command: &BoxCommand) -> ExecRequest {
-        use boxlite_shared::TtyConfig;
+    fn build_exec_request(command: &BoxCommand, buffer: ExecBufferOptions) -> ExecRequest {
+        use boxlite_shared::{ExecLimits, OutputBufferPolicy, TtyConfig};
 
         ExecRequest {
             execution_id: None,
@@ -177,6 +374,56 @@ impl ExecProtocol {
             } else {
                 None
             },
+            max_output_bytes: command.max_output_bytes.unwrap_or(0),
+            output_limit_policy: match command.output_limit_policy {
+                OutputLimitPolicy::Head => boxlite_shared::OutputLimitPolicy::Head,
+                OutputLimitPolicy::Tail => boxlite_shared::OutputLimitPolicy::Tail,
+                OutputLimitPolicy::Error => boxlite_shared::OutputLimitPolicy::Error,
+            } as i32,
+            detach: command.detach,
+            limits: command.limits.map(|limits| ExecLimits {
+                cpu_quota_us: limits.cpu_quota_us().unwrap_or(0),
+                cpu_period_us: crate::litebox::ExecLimits::CPU_PERIOD_US,
+                memory_max: limits.memory_max.unwrap_or(0),
+                pids_max: limits.pids_max.unwrap_or(0),
+            }),
+            user: command.user.clone(),
+            privileged: command.privileged,
+            // The guest holds the same number of chunks, or its own default
+            output_buffer: buffer
+                .capacity
+                .map_or(0, |capacity| capacity.min(u32::MAX as usize) as u32),
+            output_buffer_policy: match buffer.policy {
+                ExecBufferPolicy::Block => OutputBufferPolicy::Block,
+                ExecBufferPolicy::Drop => OutputBufferPolicy::Drop,
+            } as i32,
+        }
+    }
+
+    fn map_execution_info(info: ExecutionInfo) -> ExecInfo {
+        let state = if !info.error.is_empty() {
+            ExecState::Unknown(info.error)
+        } else if info.running {
+            ExecState::Running
+        } else if info.signal != 0 {
+            ExecState::Exited(ExecResult {
+                exit_code: -info.signal,
+            })
+        } else {
+            ExecState::Exited(ExecResult {
+                exit_code: info.exit_code,
+            })
+        };
+
+        ExecInfo {
+            id: info.execution_id,
+            command: info.program,
+            args: info.args,
+            pid: info.pid,
+            started_at: chrono::DateTime::from_timestamp_millis(info.started_at_ms as i64)
+                .unwrap_or_default(),
+            detached: info.detached,
+            state,
         }
     }
 
@@ -192,8 +439,7 @@ impl ExecProtocol {
     fn spawn_attach(
         mut client: ExecutionClient<Channel>,
         execution_id: String,
-        stdout_tx: mpsc::UnboundedSender<String>,
-        stderr_tx: mpsc::UnboundedSender<String>,
+        mut sink: OutputSink,
     ) {
         tokio::spawn(async move {
             let request = AttachRequest {
@@ -203,55 +449,137 @@ impl ExecProtocol {
             match client.attach(request).await {
                 Ok(response) => {
                     tracing::debug!(execution_id = %execution_id, "Attach stream connected");
-                    let mut stream = response.into_inner();
-                    let mut message_count = 0u64;
-                    while let Some(output) = stream.message().await.transpose() {
-                        match output {
-                            Ok(output) => {
-                                message_count += 1;
-                                Self::route_output(output, &stdout_tx, &stderr_tx);
-                            }
-                            Err(e) => {
-                                tracing::debug!(
-                                    execution_id = %execution_id,
-                                    error = %e,
-                                    message_count,
-                                    "Attach stream error, breaking"
-                                );
-                                let _ = stderr_tx.send(format!("Attach stream error: {}", e));
-                                break;
-                            }
+                    Self::pump_output(&mut client, &execution_id, response.into_inner(), &mut sink)
+                        .await;
+                }
+                Err(e) => {
+                    tracing::debug!(execution_id = %execution_id, error = %e, "Attach failed");
+                    sink.send_error(OutputFrame::new(
+                        0,
+                        Bytes::from(format!("Attach failed: {}", e)),
+                    ))
+                    .await;
+                }
+            }
+        });
+    }
+
+    /// Route an attach stream into the stdout/stderr channels until it ends.
+    ///
+    /// If the connection to the guest breaks, the stream is attached again:
+    /// the guest keeps the output produced in the meantime.
+    async fn pump_output(
+        client: &mut ExecutionClient<Channel>,
+        execution_id: &str,
+        mut stream: Streaming<ExecOutput>,
+        sink: &mut OutputSink,
+    ) {
+        // Doubles as the sequence number that orders stdout
+        // against stderr for the combined output stream.
+        let mut message_count = 0u64;
+        while let Some(output) = stream.message().await.transpose() {
+            match output {
+                Ok(output) => {
+                    message_count += 1;
+                    Self::route_output(message_count, output, sink).await;
+                }
+                Err(e) if is_disconnect(&e) => {
+                    tracing::debug!(
+                        execution_id = %execution_id,
+                        error = %e,
+                        message_count,
+                        "Attach stream broke, reattaching"
+                    );
+                    match Self::reattach(client, execution_id).await {
+                        Ok(resumed) => stream = resumed,
+                        Err(e) => {
+                            let message = format!("Attach stream error: {}", e);
+                            sink.send_error(OutputFrame::new(
+                                message_count + 1,
+                                Bytes::from(message),
+                            ))
+                            .await;
+                            break;
                         }
                     }
+                }
+                Err(e) => {
                     tracing::debug!(
                         execution_id = %execution_id,
+                        error = %e,
                         message_count,
-                        "Attach stream ended normally"
+                        "Attach stream error, breaking"
                     );
+                    let message = if e.code() == tonic::Code::ResourceExhausted {
+                        // Guest killed the process (OutputLimitPolicy::Error)
+                        format!("\n[boxlite] {}\n", e.message())
+                    } else {
+                        format!("Attach stream error: {}", e)
+                    };
+                    sink.send_error(OutputFrame::new(message_count + 1, Bytes::from(message)))
+                        .await;
+                    break;
                 }
-                Err(e) => {
-                    tracing::debug!(execution_id = %execution_id, error = %e, "Attach failed");
-                    let _ = stderr_tx.send(format!("Attach failed: {}", e));
+            }
+        }
+        if sink.dropped > 0 {
+            tracing::warn!(
+                execution_id = %execution_id,
+                dropped = sink.dropped,
+                "Dropped exec output chunks, the buffer was full"
+            );
+        }
+        tracing::debug!(
+            execution_id = %execution_id,
+            message_count,
+            "Attach stream ended normally"
+        );
+    }
+
+    /// Attach again after the connection broke, waiting for it to come
+    /// back and for the guest to notice the old stream is gone.
+    async fn reattach(
+        client: &mut ExecutionClient<Channel>,
+        execution_id: &str,
+    ) -> Result<Streaming<ExecOutput>, tonic::Status> {
+        let mut attempt = 1;
+        loop {
+            tokio::time::sleep(reconnect_delay(attempt)).await;
+            let request = AttachRequest {
+                execution_id: execution_id.to_string(),
+            };
+            match client.attach(request).await {
+                Ok(response) => return Ok(response.into_inner()),
+                Err(e)
+                    if attempt < RECONNECT_ATTEMPTS
+                        && (is_disconnect(&e) || e.code() == tonic::Code::AlreadyExists) =>
+                {
+                    attempt += 1;
                 }
+                Err(e) => return Err(e),
             }
-        });
+        }
     }
 
-    fn route_output(
-        output: ExecOutput,
-        stdout_tx: &mpsc::UnboundedSender<String>,
-        stderr_tx: &mpsc::UnboundedSender<String>,
-    ) {
+    async fn route_output(seq: u64, output: ExecOutput, sink: &mut OutputSink) {
+        let frame = |data: Vec<u8>, guest_seq, timestamp_ns| OutputFrame {
+            seq,
+            // Forward raw bytes; decoding is left to the consumer
+            data: Bytes::from(data),
+            guest_seq,
+            timestamp: std::time::Duration::from_nanos(timestamp_ns),
+        };
+
         match output.event {
             Some(exec_output::Event::Stdout(chunk)) => {
-                let stdout_data = String::from_utf8_lossy(&chunk.data).to_string();
-                tracing::trace!(?stdout_data, "Received exec stdout");
-                let _ = stdout_tx.send(stdout_data);
+                tracing::trace!(len = chunk.data.len(), "Received exec stdout");
+                sink.send(frame(chunk.data, chunk.seq, chunk.timestamp_ns), false)
+                    .await;
             }
             Some(exec_output::Event::Stderr(chunk)) => {
-                let stderr_data = String::from_utf8_lossy(&chunk.data).to_string();
-                tracing::trace!(?stderr_data, "Received exec stderr");
-                let _ = stderr_tx.send(stderr_data);
+                tracing::trace!(len = chunk.data.len(), "Received exec stderr");
+                sink.send(frame(chunk.data, chunk.seq, chunk.timestamp_ns), true)
+                    .await;
             }
             None => {}
         }
@@ -263,23 +591,40 @@ impl ExecProtocol {
         result_tx: mpsc::UnboundedSender<ExecResult>,
     ) {
         tokio::spawn(async move {
-            let request = WaitRequest {
-                execution_id: execution_id.clone(),
-            };
-
-            match client.wait(request).await {
-                Ok(resp) => {
-                    let mapped = Self::map_wait_response(resp.into_inner());
-                    let _ = result_tx.send(mapped);
-                }
-                Err(e) => {
-                    tracing::error!(
-                        execution_id = %execution_id,
-                        error = %e,
-                        "Wait failed"
-                    );
-                    let _ = result_tx.send(ExecResult { exit_code: -1 });
+            let mut attempt = 0;
+            loop {
+                let request = WaitRequest {
+                    execution_id: execution_id.clone(),
+                };
+
+                match client.wait(request).await {
+                    Ok(resp) => {
+                        let mapped = Self::map_wait_response(resp.into_inner());
+                        let _ = result_tx.send(mapped);
+                    }
+                    // The guest keeps the exit status, ask again once the
+                    // connection is back
+                    Err(e) if is_disconnect(&e) && attempt < RECONNECT_ATTEMPTS => {
+                        attempt += 1;
+                        tracing::debug!(
+                            execution_id = %execution_id,
+                            error = %e,
+                            attempt,
+                            "Wait interrupted, retrying"
+                        );
+                        tokio::time::sleep(reconnect_delay(attempt)).await;
+                        continue;
+                    }
+                    Err(e) => {
+                        tracing::error!(
+                            execution_id = %execution_id,
+                            error = %e,
+                            "Wait failed"
+                        );
+                        let _ = result_tx.send(ExecResult { exit_code: -1 });
+                    }
                 }
+                break;
             }
         });
     }
@@ -327,3 +672,22 @@ impl ExecProtocol {
         });
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_is_disconnect() {
+        assert!(is_disconnect(&tonic::Status::unavailable("gone")));
+        assert!(!is_disconnect(&tonic::Status::unknown("guest failed")));
+        assert!(!is_disconnect(&tonic::Status::internal("bad")));
+
+        let mut status = tonic::Status::unknown("stream broke");
+        status.set_source(Arc::new(std::io::Error::from(
+            std::io::ErrorKind::BrokenPipe,
+        )));
+        assert!(is_disconnect(&status));
+    }
+}
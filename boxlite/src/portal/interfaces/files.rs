@@ -0,0 +1,380 @@
+//! Files service interface.
+//!
+//! Copies single files and tar archives into and out of the container, and
+//! lists and removes directory trees for syncs and watches paths for
+//! changes.
+
+use std::sync::{Arc, Mutex};
+
+use boxlite_shared::{
+    BoxliteError, BoxliteResult, ChmodRequest, ChownRequest, CopyInChunk, CopyInHeader,
+    CopyOutRequest, FileEntry, FileStat, FilesClient, GetFileRequest, ListDirRequest,
+    ListTreeRequest, PutFileChunk, PutFileHeader, RemovePathsRequest, StatRequest, WatchEvent,
+    WatchRequest,
+};
+use bytes::{Bytes, BytesMut};
+use futures::{Stream, StreamExt};
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tonic::transport::Channel;
+
+/// Bytes per `PutFile` and `CopyIn` chunk.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Files service interface.
+pub struct FilesInterface {
+    client: FilesClient<Channel>,
+}
+
+impl FilesInterface {
+    /// Create from a channel.
+    pub fn new(channel: Channel) -> Self {
+        Self {
+            client: FilesClient::new(channel),
+        }
+    }
+
+    /// Write a file of `size` bytes to `path` in the container.
+    ///
+    /// `reader` yields the content after the first `offset` bytes; a
+    /// non-zero `offset` resumes an interrupted upload, keeping that much
+    /// of the existing file.
+    ///
+    /// # Returns
+    /// File size
+    pub async fn put_file<R>(
+        &mut self,
+        container_id: &str,
+        path: &str,
+        mode: u32,
+        size: u64,
+        offset: u64,
+        mut reader: R,
+    ) -> BoxliteResult<u64>
+    where
+        R: AsyncRead + Unpin + Send + 'static,
+    {
+        let header = PutFileHeader {
+            container_id: container_id.to_string(),
+            path: path.to_string(),
+            mode,
+            size,
+            offset,
+        };
+
+        // The guest notices the short upload; the slot says why it was short
+        let read_error = Arc::new(Mutex::new(None));
+        let read_error_slot = read_error.clone();
+        let chunks = async_stream::stream! {
+            yield PutFileChunk { header: Some(header), data: Vec::new() };
+            loop {
+                let mut data = vec![0; CHUNK_SIZE];
+                match reader.read(&mut data).await {
+                    Ok(0) => break,
+                    Ok(n) => {
+                        data.truncate(n);
+                        yield PutFileChunk { header: None, data };
+                    }
+                    Err(e) => {
+                        *read_error_slot.lock().unwrap() = Some(e);
+                        break;
+                    }
+                }
+            }
+        };
+
+        let result = self.client.put_file(chunks).await;
+        if let Some(e) = read_error.lock().unwrap().take() {
+            return Err(BoxliteError::Storage(format!(
+                "Failed to read upload for {}: {}",
+                path, e
+            )));
+        }
+        Ok(result.map_err(|e| file_error(path, e))?.into_inner().size)
+    }
+
+    /// Read the file at `path` in the container, refusing files over
+    /// `max_size` bytes (0 = no limit).
+    pub async fn get_file(
+        &mut self,
+        container_id: &str,
+        path: &str,
+        max_size: u64,
+    ) -> BoxliteResult<Bytes> {
+        let mut stream = self.read_file(container_id, path, 0, max_size).await?;
+
+        let mut data = BytesMut::new();
+        while let Some(chunk) = stream.next().await {
+            data.extend_from_slice(&chunk?);
+            // The file may grow while it is read
+            if max_size > 0 && data.len() as u64 > max_size {
+                return Err(BoxliteError::InvalidArgument(format!(
+                    "{} is over the limit of {} bytes",
+                    path, max_size
+                )));
+            }
+        }
+        Ok(data.freeze())
+    }
+
+    /// Stream the file at `path` in the container in chunks, starting at
+    /// `offset`, refusing files over `max_size` bytes (0 = no limit).
+    pub async fn read_file(
+        &mut self,
+        container_id: &str,
+        path: &str,
+        offset: u64,
+        max_size: u64,
+    ) -> BoxliteResult<impl Stream<Item = BoxliteResult<Bytes>> + Send + 'static> {
+        let request = GetFileRequest {
+            container_id: container_id.to_string(),
+            path: path.to_string(),
+            max_size,
+            offset,
+        };
+        let stream = self
+            .client
+            .get_file(request)
+            .await
+            .map_err(|e| file_error(path, e))?
+            .into_inner();
+
+        let path = path.to_string();
+        Ok(stream.map(move |chunk| {
+            chunk
+                .map(|chunk| Bytes::from(chunk.data))
+                .map_err(|e| file_error(&path, e))
+        }))
+    }
+
+    /// Extract the tar archive read from `reader` into `dir` in the container.
+    ///
+    /// `dir` must exist unless `create_dir` is set.
+    ///
+    /// # Returns
+    /// Number of archive entries extracted
+    pub async fn copy_in<R>(
+        &mut self,
+        container_id: &str,
+        dir: &str,
+        create_dir: bool,
+        mut reader: R,
+    ) -> BoxliteResult<u64>
+    where
+        R: AsyncRead + Unpin + Send + 'static,
+    {
+        let header = CopyInHeader {
+            container_id: container_id.to_string(),
+            dir: dir.to_string(),
+            create_dir,
+        };
+
+        // A truncated archive fails extraction; the slot says why it was cut
+        let read_error = Arc::new(Mutex::new(None));
+        let read_error_slot = read_error.clone();
+        let chunks = async_stream::stream! {
+            yield CopyInChunk { header: Some(header), data: Vec::new() };
+            loop {
+                let mut data = vec![0; CHUNK_SIZE];
+                match reader.read(&mut data).await {
+                    Ok(0) => break,
+                    Ok(n) => {
+                        data.truncate(n);
+                        yield CopyInChunk { header: None, data };
+                    }
+                    Err(e) => {
+                        *read_error_slot.lock().unwrap() = Some(e);
+                        break;
+                    }
+                }
+            }
+        };
+
+        let result = self.client.copy_in(chunks).await;
+        if let Some(e) = read_error.lock().unwrap().take() {
+            return Err(BoxliteError::Storage(format!(
+                "Failed to read archive for {}: {}",
+                dir, e
+            )));
+        }
+        Ok(result.map_err(|e| file_error(dir, e))?.into_inner().entries)
+    }
+
+    /// Stream `path` in the container as a tar archive.
+    pub async fn copy_out(
+        &mut self,
+        container_id: &str,
+        path: &str,
+    ) -> BoxliteResult<impl Stream<Item = BoxliteResult<Bytes>> + Send + 'static> {
+        let request = CopyOutRequest {
+            container_id: container_id.to_string(),
+            path: path.to_string(),
+        };
+        let stream = self
+            .client
+            .copy_out(request)
+            .await
+            .map_err(|e| file_error(path, e))?
+            .into_inner();
+
+        let path = path.to_string();
+        Ok(stream.map(move |chunk| {
+            chunk
+                .map(|chunk| Bytes::from(chunk.data))
+                .map_err(|e| file_error(&path, e))
+        }))
+    }
+
+    /// List the tree below `dir` in the container (paths relative to `dir`).
+    pub async fn list_tree(
+        &mut self,
+        container_id: &str,
+        dir: &str,
+        checksums: bool,
+    ) -> BoxliteResult<Vec<FileEntry>> {
+        let request = ListTreeRequest {
+            container_id: container_id.to_string(),
+            dir: dir.to_string(),
+            checksums,
+        };
+        let mut stream = self
+            .client
+            .list_tree(request)
+            .await
+            .map_err(|e| file_error(dir, e))?
+            .into_inner();
+
+        let mut entries = Vec::new();
+        while let Some(batch) = stream.message().await.map_err(|e| file_error(dir, e))? {
+            entries.extend(batch.entries);
+        }
+        Ok(entries)
+    }
+
+    /// Remove `paths` (relative to `dir`) in the container, recursively.
+    ///
+    /// # Returns
+    /// Number of paths that existed
+    pub async fn remove_paths(
+        &mut self,
+        container_id: &str,
+        dir: &str,
+        paths: Vec<String>,
+    ) -> BoxliteResult<u64> {
+        let request = RemovePathsRequest {
+            container_id: container_id.to_string(),
+            dir: dir.to_string(),
+            paths,
+        };
+        let response = self
+            .client
+            .remove_paths(request)
+            .await
+            .map_err(|e| file_error(dir, e))?;
+        Ok(response.into_inner().removed)
+    }
+
+    /// Metadata of `path` in the container, without following a final
+    /// symlink.
+    pub async fn stat(&mut self, container_id: &str, path: &str) -> BoxliteResult<FileStat> {
+        let request = StatRequest {
+            container_id: container_id.to_string(),
+            path: path.to_string(),
+        };
+        let response = self
+            .client
+            .stat(request)
+            .await
+            .map_err(|e| file_error(path, e))?;
+        Ok(response.into_inner())
+    }
+
+    /// Metadata of the entries of the directory `path` in the container.
+    pub async fn list_dir(
+        &mut self,
+        container_id: &str,
+        path: &str,
+    ) -> BoxliteResult<Vec<FileStat>> {
+        let request = ListDirRequest {
+            container_id: container_id.to_string(),
+            path: path.to_string(),
+        };
+        let response = self
+            .client
+            .list_dir(request)
+            .await
+            .map_err(|e| file_error(path, e))?;
+        Ok(response.into_inner().entries)
+    }
+
+    /// Set the permission bits of `path` in the container.
+    pub async fn chmod(&mut self, container_id: &str, path: &str, mode: u32) -> BoxliteResult<()> {
+        let request = ChmodRequest {
+            container_id: container_id.to_string(),
+            path: path.to_string(),
+            mode,
+        };
+        self.client
+            .chmod(request)
+            .await
+            .map_err(|e| file_error(path, e))?;
+        Ok(())
+    }
+
+    /// Set the owner of `path` in the container.
+    pub async fn chown(
+        &mut self,
+        container_id: &str,
+        path: &str,
+        uid: u32,
+        gid: u32,
+    ) -> BoxliteResult<()> {
+        let request = ChownRequest {
+            container_id: container_id.to_string(),
+            path: path.to_string(),
+            uid,
+            gid,
+        };
+        self.client
+            .chown(request)
+            .await
+            .map_err(|e| file_error(path, e))?;
+        Ok(())
+    }
+
+    /// Stream changes to `path` in the container.
+    pub async fn watch(
+        &mut self,
+        container_id: &str,
+        path: &str,
+        recursive: bool,
+    ) -> BoxliteResult<impl Stream<Item = BoxliteResult<WatchEvent>> + Send + 'static> {
+        let request = WatchRequest {
+            container_id: container_id.to_string(),
+            path: path.to_string(),
+            recursive,
+        };
+        let stream = self
+            .client
+            .watch(request)
+            .await
+            .map_err(|e| file_error(path, e))?
+            .into_inner();
+
+        let path = path.to_string();
+        Ok(stream.map(move |event| event.map_err(|e| file_error(&path, e))))
+    }
+}
+
+fn file_error(path: &str, status: tonic::Status) -> BoxliteError {
+    match status.code() {
+        tonic::Code::NotFound => BoxliteError::NotFound(format!("file {}", path)),
+        tonic::Code::InvalidArgument | tonic::Code::OutOfRange => {
+            BoxliteError::InvalidArgument(status.message().to_string())
+        }
+        // The connection to the guest broke; the request may be retried
+        tonic::Code::Unavailable | tonic::Code::Unknown => {
+            BoxliteError::RpcTransport(status.message().to_string())
+        }
+        _ => status.into(),
+    }
+}
@@ -1,9 +1,11 @@
 //! Guest service interface.
 
 use boxlite_shared::{
-    BlockDeviceSource, BoxliteError, BoxliteResult, Filesystem, GuestClient, GuestInitRequest,
-    NetworkInit, PingRequest, ShutdownRequest, VirtiofsSource, Volume, guest_init_response,
+    BlockDeviceSource, BoxliteError, BoxliteResult, CheckPortRequest, Filesystem, GuestClient,
+    GuestInitRequest, NetworkInit, PingRequest, ShutdownRequest, VirtiofsSource, Volume,
+    guest_init_response,
 };
+use std::time::Duration;
 use tonic::transport::Channel;
 
 /// Guest service interface.
@@ -28,16 +30,18 @@ impl GuestInterface {
         tracing::trace!(
             volumes = config.volumes.len(),
             network = ?config.network,
+            networks = ?config.networks,
             "Guest init configuration"
         );
 
         let request = GuestInitRequest {
             volumes: config.volumes.into_iter().map(|v| v.into_proto()).collect(),
-            network: config.network.map(|n| NetworkInit {
-                interface: n.interface,
-                ip: n.ip,
-                gateway: n.gateway,
-            }),
+            network: config.network.map(|n| n.into_proto()),
+            networks: config
+                .networks
+                .into_iter()
+                .map(|n| n.into_proto())
+                .collect(),
         };
 
         let response = self.client.init(request).await?.into_inner();
@@ -67,9 +71,23 @@ impl GuestInterface {
         Ok(())
     }
 
-    /// Shutdown the guest agent.
-    pub async fn shutdown(&mut self) -> BoxliteResult<()> {
-        let _response = self.client.shutdown(ShutdownRequest {}).await?;
+    /// Whether TCP `port` accepts connections on the guest's loopback.
+    pub async fn check_port(&mut self, port: u16) -> BoxliteResult<bool> {
+        let request = CheckPortRequest {
+            port: u32::from(port),
+        };
+        let response = self.client.check_port(request).await?.into_inner();
+        Ok(response.open)
+    }
+
+    /// Stop the containers with `signal`, waiting up to `timeout` for them
+    /// to exit, and sync the guest's filesystems.
+    pub async fn shutdown(&mut self, signal: &str, timeout: Duration) -> BoxliteResult<()> {
+        let request = ShutdownRequest {
+            signal: signal.to_string(),
+            timeout_ms: timeout.as_millis() as u64,
+        };
+        let _response = self.client.shutdown(request).await?;
         Ok(())
     }
 }
@@ -81,6 +99,8 @@ pub struct GuestInitConfig {
     pub volumes: Vec<VolumeConfig>,
     /// Network configuration (optional)
     pub network: Option<NetworkInitConfig>,
+    /// Interfaces on inter-box networks
+    pub networks: Vec<NetworkInitConfig>,
 }
 
 /// Volume configuration.
@@ -190,4 +210,23 @@ pub struct NetworkInitConfig {
     pub ip: Option<String>,
     /// Gateway address (e.g., "192.168.127.1")
     pub gateway: Option<String>,
+    /// IPv6 address with prefix (e.g., "fd42:b0c5:127::2/64")
+    pub ipv6: Option<String>,
+    /// IPv6 gateway address (e.g., "fd42:b0c5:127::1")
+    pub gateway_ipv6: Option<String>,
+    /// Interface MTU (kernel default if None)
+    pub mtu: Option<u32>,
+}
+
+impl NetworkInitConfig {
+    fn into_proto(self) -> NetworkInit {
+        NetworkInit {
+            interface: self.interface,
+            ip: self.ip,
+            gateway: self.gateway,
+            ipv6: self.ipv6,
+            gateway_ipv6: self.gateway_ipv6,
+            mtu: self.mtu,
+        }
+    }
 }
@@ -4,8 +4,10 @@
 
 pub mod container;
 pub mod exec;
+pub mod files;
 pub mod guest;
 
-pub use container::{ContainerInterface, ContainerRootfsInitConfig};
-pub use exec::ExecutionInterface;
+pub use container::{ContainerInterface, ContainerNetworkConfig, ContainerRootfsInitConfig};
+pub use exec::{ExecComponents, ExecutionInterface};
+pub use files::FilesInterface;
 pub use guest::{GuestInitConfig, GuestInterface, NetworkInitConfig, VolumeConfig};
@@ -3,7 +3,9 @@
 //! Thin facade over service interfaces.
 
 use crate::portal::connection::Connection;
-use crate::portal::interfaces::{ContainerInterface, ExecutionInterface, GuestInterface};
+use crate::portal::interfaces::{
+    ContainerInterface, ExecutionInterface, FilesInterface, GuestInterface,
+};
 use boxlite_shared::{BoxliteResult, Transport};
 
 /// High-level guest session.
@@ -34,6 +36,12 @@ impl GuestSession {
         Ok(ContainerInterface::new(channel))
     }
 
+    /// Get files interface.
+    pub async fn files(&self) -> BoxliteResult<FilesInterface> {
+        let channel = self.connection.channel().await?;
+        Ok(FilesInterface::new(channel))
+    }
+
     /// Get guest interface.
     pub async fn guest(&self) -> BoxliteResult<GuestInterface> {
         let channel = self.connection.channel().await?;
@@ -0,0 +1,312 @@
+//! Rootfs change detection.
+//!
+//! Rebuilds the file list of an image's merged rootfs from its extracted
+//! layers (whiteouts applied the same way the rootfs builder does) and
+//! compares it with a listing of a running container's rootfs.
+//!
+//! Entries are compared by type, permissions, symlink target and, for
+//! regular files, size and mtime. Directory timestamps are ignored: they
+//! change whenever an entry is added or removed below them.
+
+use std::collections::{BTreeMap, HashSet};
+use std::os::unix::fs::{MetadataExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+
+use boxlite_shared::errors::{BoxliteError, BoxliteResult};
+use boxlite_shared::{FileEntry, FileKind};
+use walkdir::WalkDir;
+
+const WHITEOUT_PREFIX: &str = ".wh.";
+const OPAQUE_WHITEOUT: &str = ".wh..wh..opq";
+
+/// Symlinks followed by [`ImageManifest::contains`] before giving up.
+const MAX_SYMLINKS: usize = 40;
+
+/// Files of an image's merged rootfs, keyed by relative path.
+#[derive(Debug, Default)]
+pub(crate) struct ImageManifest {
+    entries: BTreeMap<String, FileEntry>,
+}
+
+/// Paths that differ between an image and a container rootfs.
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct RootfsChanges {
+    /// Added or changed paths, parents before children.
+    pub paths: Vec<String>,
+    /// Deleted paths, without paths below an already deleted directory.
+    pub deleted: Vec<String>,
+}
+
+impl ImageManifest {
+    /// Build the manifest from extracted layer directories, bottom to top.
+    pub fn from_layers(layers: &[PathBuf]) -> BoxliteResult<Self> {
+        let mut manifest = Self::default();
+        for layer in layers {
+            manifest.apply_layer(layer)?;
+        }
+        Ok(manifest)
+    }
+
+    fn apply_layer(&mut self, layer: &Path) -> BoxliteResult<()> {
+        let mut added = Vec::new();
+
+        for entry in WalkDir::new(layer).min_depth(1).follow_links(false) {
+            let entry = entry.map_err(|e| {
+                BoxliteError::Storage(format!("Failed to walk layer {}: {}", layer.display(), e))
+            })?;
+            let relative = relative_key(entry.path().strip_prefix(layer).unwrap_or(entry.path()));
+            let name = entry.file_name().to_string_lossy();
+
+            // Whiteouts hide entries of lower layers only
+            if name == OPAQUE_WHITEOUT {
+                let dir = parent_key(&relative);
+                self.remove_below(&dir);
+            } else if let Some(target) = name.strip_prefix(WHITEOUT_PREFIX) {
+                let dir = parent_key(&relative);
+                let target = join_key(&dir, target);
+                self.remove_below(&target);
+                self.entries.remove(&target);
+            } else if let Some(file) = file_entry(entry.path(), relative)? {
+                added.push(file);
+            }
+        }
+
+        for file in added {
+            self.entries.insert(file.path.clone(), file);
+        }
+        Ok(())
+    }
+
+    /// Remove everything below `dir` (but not `dir` itself).
+    fn remove_below(&mut self, dir: &str) {
+        let prefix = if dir.is_empty() {
+            String::new()
+        } else {
+            format!("{}/", dir)
+        };
+        let below: Vec<String> = self
+            .entries
+            .range(prefix.clone()..)
+            .map(|(path, _)| path)
+            .take_while(|path| path.starts_with(&prefix))
+            .cloned()
+            .collect();
+        for path in below {
+            self.entries.remove(&path);
+        }
+    }
+
+    /// Check whether an absolute path exists in the image, following
+    /// symlinks (e.g. `/bin` -> `usr/bin` on merged-usr distributions).
+    pub fn contains(&self, path: &str) -> bool {
+        let mut pending: Vec<String> = path.split('/').rev().map(String::from).collect();
+        let mut current = String::new();
+        let mut symlinks = 0;
+
+        while let Some(name) = pending.pop() {
+            match name.as_str() {
+                "" | "." => continue,
+                ".." => {
+                    current = parent_key(&current);
+                    continue;
+                }
+                _ => {}
+            }
+
+            let next = join_key(&current, &name);
+            let Some(entry) = self.entries.get(&next) else {
+                return false;
+            };
+            if entry.kind() != FileKind::Symlink {
+                current = next;
+                continue;
+            }
+
+            symlinks += 1;
+            if symlinks > MAX_SYMLINKS {
+                return false;
+            }
+            if entry.link_target.starts_with('/') {
+                current.clear();
+            }
+            pending.extend(entry.link_target.split('/').rev().map(String::from));
+        }
+        true
+    }
+
+    /// Compare with a listing of the container rootfs.
+    pub fn diff(&self, mut current: Vec<FileEntry>) -> RootfsChanges {
+        current.sort_by(|a, b| a.path.cmp(&b.path));
+
+        let present: HashSet<&str> = current.iter().map(|e| e.path.as_str()).collect();
+        let mut deleted: Vec<String> = Vec::new();
+        for path in self.entries.keys() {
+            if present.contains(path.as_str()) {
+                continue;
+            }
+            // Sorted order puts a directory before its children
+            let under_deleted = deleted
+                .last()
+                .is_some_and(|dir| path.starts_with(dir) && path[dir.len()..].starts_with('/'));
+            if !under_deleted {
+                deleted.push(path.clone());
+            }
+        }
+
+        let paths = current
+            .iter()
+            .filter(|entry| {
+                self.entries
+                    .get(&entry.path)
+                    .is_none_or(|base| !unchanged(base, entry))
+            })
+            .map(|entry| entry.path.clone())
+            .collect();
+
+        RootfsChanges { paths, deleted }
+    }
+}
+
+fn unchanged(base: &FileEntry, current: &FileEntry) -> bool {
+    if base.kind != current.kind || base.mode != current.mode {
+        return false;
+    }
+    match base.kind() {
+        FileKind::Regular => base.size == current.size && base.mtime == current.mtime,
+        FileKind::Symlink => base.link_target == current.link_target,
+        FileKind::Directory | FileKind::Other => true,
+    }
+}
+
+/// Describe a layer file, or `None` for special files.
+fn file_entry(path: &Path, relative: String) -> BoxliteResult<Option<FileEntry>> {
+    let metadata = std::fs::symlink_metadata(path)?;
+    let file_type = metadata.file_type();
+
+    let (kind, link_target) = if file_type.is_symlink() {
+        let target = std::fs::read_link(path)?;
+        (FileKind::Symlink, target.to_string_lossy().into_owned())
+    } else if file_type.is_dir() {
+        (FileKind::Directory, String::new())
+    } else if file_type.is_file() {
+        (FileKind::Regular, String::new())
+    } else {
+        return Ok(None);
+    };
+
+    Ok(Some(FileEntry {
+        path: relative,
+        kind: kind as i32,
+        mode: metadata.permissions().mode() & 0o7777,
+        size: if file_type.is_file() {
+            metadata.len()
+        } else {
+            0
+        },
+        mtime: metadata.mtime(),
+        link_target,
+        sha256: String::new(),
+    }))
+}
+
+fn relative_key(path: &Path) -> String {
+    path.to_string_lossy().into_owned()
+}
+
+fn parent_key(key: &str) -> String {
+    key.rsplit_once('/')
+        .map_or(String::new(), |(parent, _)| parent.to_string())
+}
+
+fn join_key(dir: &str, name: &str) -> String {
+    if dir.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}/{}", dir, name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn listing(root: &Path) -> Vec<FileEntry> {
+        WalkDir::new(root)
+            .min_depth(1)
+            .into_iter()
+            .filter_map(|e| {
+                let e = e.unwrap();
+                let relative = relative_key(e.path().strip_prefix(root).unwrap());
+                file_entry(e.path(), relative).unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_manifest_applies_whiteouts() {
+        let lower = tempfile::tempdir().unwrap();
+        fs::create_dir_all(lower.path().join("etc")).unwrap();
+        fs::write(lower.path().join("etc/motd"), "hi").unwrap();
+        fs::create_dir_all(lower.path().join("opt/tool")).unwrap();
+        fs::write(lower.path().join("opt/tool/bin"), "x").unwrap();
+        fs::create_dir_all(lower.path().join("var/cache")).unwrap();
+        fs::write(lower.path().join("var/cache/a"), "a").unwrap();
+
+        let upper = tempfile::tempdir().unwrap();
+        fs::create_dir_all(upper.path().join("opt")).unwrap();
+        fs::write(upper.path().join("opt/.wh.tool"), "").unwrap();
+        fs::create_dir_all(upper.path().join("var/cache")).unwrap();
+        fs::write(upper.path().join("var/cache/.wh..wh..opq"), "").unwrap();
+        fs::write(upper.path().join("var/cache/b"), "b").unwrap();
+
+        let manifest =
+            ImageManifest::from_layers(&[lower.path().into(), upper.path().into()]).unwrap();
+        let paths: Vec<&str> = manifest.entries.keys().map(String::as_str).collect();
+        assert_eq!(
+            paths,
+            ["etc", "etc/motd", "opt", "var", "var/cache", "var/cache/b"]
+        );
+    }
+
+    #[test]
+    fn test_diff_finds_changes() {
+        let image = tempfile::tempdir().unwrap();
+        fs::create_dir_all(image.path().join("etc")).unwrap();
+        fs::write(image.path().join("etc/motd"), "hi").unwrap();
+        fs::write(image.path().join("etc/hosts"), "localhost").unwrap();
+        fs::create_dir_all(image.path().join("usr/share/doc")).unwrap();
+        fs::write(image.path().join("usr/share/doc/README"), "docs").unwrap();
+        let manifest = ImageManifest::from_layers(&[image.path().into()]).unwrap();
+
+        // The container's rootfs: one file changed, one tree deleted, one added
+        fs::write(image.path().join("etc/motd"), "hello").unwrap();
+        fs::remove_dir_all(image.path().join("usr/share")).unwrap();
+        fs::create_dir_all(image.path().join("app")).unwrap();
+        fs::write(image.path().join("app/main.py"), "print()").unwrap();
+
+        assert_eq!(
+            manifest.diff(listing(image.path())),
+            RootfsChanges {
+                paths: vec!["app".into(), "app/main.py".into(), "etc/motd".into()],
+                deleted: vec!["usr/share".into()],
+            }
+        );
+    }
+
+    #[test]
+    fn test_manifest_contains_follows_symlinks() {
+        let layer = tempfile::tempdir().unwrap();
+        fs::create_dir_all(layer.path().join("usr/bin")).unwrap();
+        fs::write(layer.path().join("usr/bin/dash"), "").unwrap();
+        std::os::unix::fs::symlink("dash", layer.path().join("usr/bin/sh")).unwrap();
+        std::os::unix::fs::symlink("usr/bin", layer.path().join("bin")).unwrap();
+        std::os::unix::fs::symlink("/loop", layer.path().join("loop")).unwrap();
+        let manifest = ImageManifest::from_layers(&[layer.path().into()]).unwrap();
+
+        assert!(manifest.contains("/bin/sh"));
+        assert!(manifest.contains("/usr/bin/../bin/dash"));
+        assert!(!manifest.contains("/bin/bash"));
+        assert!(!manifest.contains("/loop/sh"));
+    }
+}
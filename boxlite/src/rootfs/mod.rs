@@ -4,6 +4,7 @@
 
 mod builder;
 mod copy_mount;
+pub(crate) mod diff;
 mod dns;
 pub(crate) mod operations;
 
@@ -0,0 +1,198 @@
+//! Runtime-wide caps on running boxes, see
+//! [`BoxliteOptions::max_boxes`](crate::BoxliteOptions::max_boxes).
+//!
+//! A box is admitted before its VM boots. Boxes that are running, paused or
+//! booting count against the caps; stopped ones do not.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use boxlite_shared::errors::{BoxliteError, BoxliteResult};
+
+use crate::runtime::constants::vm_defaults::{DEFAULT_CPUS, DEFAULT_MEMORY_MIB};
+use crate::runtime::options::{BoxOptions, BoxliteOptions};
+use crate::runtime::types::BoxID;
+
+/// Resources used by a number of boxes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct Usage {
+    pub boxes: usize,
+    pub cpus: u32,
+    pub memory_mib: u64,
+}
+
+impl Usage {
+    /// What one box with these options uses.
+    pub fn of(options: &BoxOptions) -> Self {
+        Self {
+            boxes: 1,
+            cpus: options.cpus.unwrap_or(DEFAULT_CPUS) as u32,
+            memory_mib: options.memory_mib.unwrap_or(DEFAULT_MEMORY_MIB) as u64,
+        }
+    }
+
+    pub fn add(&mut self, other: Usage) {
+        self.boxes += other.boxes;
+        self.cpus += other.cpus;
+        self.memory_mib += other.memory_mib;
+    }
+}
+
+/// Check that a box using `request` fits next to `usage`.
+pub(crate) fn check(options: &BoxliteOptions, usage: Usage, request: Usage) -> BoxliteResult<()> {
+    let exceeded = |what: &str, used: u64, requested: u64, max: u64| {
+        BoxliteError::ResourceExhausted(format!(
+            "{} limit reached: {} in use, {} requested, max {}",
+            what, used, requested, max
+        ))
+    };
+    if let Some(max) = options.max_boxes
+        && usage.boxes + request.boxes > max
+    {
+        return Err(exceeded(
+            "box",
+            usage.boxes as u64,
+            request.boxes as u64,
+            max as u64,
+        ));
+    }
+    if let Some(max) = options.max_total_cpus
+        && usage.cpus + request.cpus > max
+    {
+        return Err(exceeded(
+            "CPU",
+            usage.cpus as u64,
+            request.cpus as u64,
+            max as u64,
+        ));
+    }
+    if let Some(max) = options.max_total_memory_mib
+        && usage.memory_mib + request.memory_mib > max
+    {
+        return Err(exceeded(
+            "memory (MiB)",
+            usage.memory_mib,
+            request.memory_mib,
+            max,
+        ));
+    }
+    Ok(())
+}
+
+/// Check that one box using `request` fits on this host at all, given its
+/// CPUs and memory.
+pub(crate) fn check_host(request: Usage) -> BoxliteResult<()> {
+    let host_cpus = std::thread::available_parallelism().map_or(u32::MAX, |n| n.get() as u32);
+    let mut sys = sysinfo::System::new();
+    sys.refresh_memory();
+    fits_host(request, host_cpus, sys.total_memory() / (1024 * 1024))
+}
+
+/// `host_memory_mib` of 0 is unknown.
+fn fits_host(request: Usage, host_cpus: u32, host_memory_mib: u64) -> BoxliteResult<()> {
+    if request.cpus > host_cpus {
+        return Err(BoxliteError::InvalidArgument(format!(
+            "{} vCPUs requested, the host has {} CPUs",
+            request.cpus, host_cpus
+        )));
+    }
+    if host_memory_mib > 0 && request.memory_mib > host_memory_mib {
+        return Err(BoxliteError::InvalidArgument(format!(
+            "{} MiB of memory requested, the host has {} MiB",
+            request.memory_mib, host_memory_mib
+        )));
+    }
+    Ok(())
+}
+
+/// Boxes admitted and still booting, which the database does not show as
+/// running yet.
+pub(crate) type Booting = Arc<Mutex<HashMap<BoxID, Usage>>>;
+
+/// Keeps a booting box counted until it is dropped.
+pub(crate) struct Admission {
+    booting: Booting,
+    box_id: BoxID,
+}
+
+impl Admission {
+    pub fn new(booting: &Booting, box_id: BoxID, usage: Usage) -> Self {
+        booting.lock().unwrap().insert(box_id.clone(), usage);
+        Self {
+            booting: Arc::clone(booting),
+            box_id,
+        }
+    }
+}
+
+impl Drop for Admission {
+    fn drop(&mut self) {
+        self.booting.lock().unwrap().remove(&self.box_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check() {
+        let options = BoxliteOptions {
+            max_boxes: Some(2),
+            max_total_cpus: Some(4),
+            max_total_memory_mib: Some(4096),
+            ..Default::default()
+        };
+        let request = Usage {
+            boxes: 1,
+            cpus: 2,
+            memory_mib: 2048,
+        };
+
+        assert!(check(&options, Usage::default(), request).is_ok());
+        assert!(check(&options, request, request).is_ok());
+
+        let mut full = request;
+        full.add(request);
+        assert!(matches!(
+            check(&options, full, request),
+            Err(BoxliteError::ResourceExhausted(_))
+        ));
+
+        let one_cpu_left = Usage {
+            boxes: 0,
+            cpus: 3,
+            memory_mib: 0,
+        };
+        assert!(check(&options, one_cpu_left, request).is_err());
+
+        assert!(check(&BoxliteOptions::default(), full, request).is_ok());
+    }
+
+    #[test]
+    fn test_fits_host() {
+        let request = Usage {
+            boxes: 1,
+            cpus: 4,
+            memory_mib: 8192,
+        };
+        assert!(fits_host(request, 4, 8192).is_ok());
+        assert!(matches!(
+            fits_host(request, 2, 8192),
+            Err(BoxliteError::InvalidArgument(_))
+        ));
+        assert!(fits_host(request, 8, 4096).is_err());
+        // Unknown host memory
+        assert!(fits_host(request, 8, 0).is_ok());
+    }
+
+    #[test]
+    fn test_admission_released_on_drop() {
+        let booting = Booting::default();
+        let box_id = BoxID::new();
+        let admission = Admission::new(&booting, box_id.clone(), Usage::default());
+        assert!(booting.lock().unwrap().contains_key(&box_id));
+        drop(admission);
+        assert!(booting.lock().unwrap().is_empty());
+    }
+}
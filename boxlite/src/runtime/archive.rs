@@ -0,0 +1,205 @@
+//! Box archives for moving boxes between hosts, see
+//! [`BoxliteRuntime::export_box`](crate::BoxliteRuntime::export_box).
+//!
+//! An archive is a tar file holding `box.json` (the box's config and state)
+//! followed by its disks, flattened so they do not depend on the images
+//! cached on the exporting host.
+
+use std::fs::File;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use boxlite_shared::errors::{BoxliteError, BoxliteResult};
+
+use crate::litebox::config::BoxConfig;
+use crate::runtime::types::BoxState;
+
+/// Current [`Manifest::version`].
+const ARCHIVE_VERSION: u32 = 1;
+
+const MANIFEST_FILE: &str = "box.json";
+/// Archive name of the container rootfs disk.
+pub(crate) const ROOTFS_DISK: &str = "disk.qcow2";
+/// Archive name of the guest rootfs disk.
+pub(crate) const GUEST_DISK: &str = "guest-rootfs.qcow2";
+
+/// What an archive records about the box.
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct Manifest {
+    pub version: u32,
+    pub config: BoxConfig,
+    pub state: BoxState,
+}
+
+impl Manifest {
+    pub fn new(config: BoxConfig, state: BoxState) -> Self {
+        Self {
+            version: ARCHIVE_VERSION,
+            config,
+            state,
+        }
+    }
+}
+
+/// Write an archive with `manifest` and `disks` (archive name, file).
+///
+/// Removes the partly written archive on failure.
+pub(crate) fn write(
+    path: &Path,
+    manifest: &Manifest,
+    disks: &[(&str, &Path)],
+) -> BoxliteResult<()> {
+    let written = write_tar(path, manifest, disks);
+    if written.is_err() {
+        let _ = std::fs::remove_file(path);
+    }
+    written
+}
+
+fn write_tar(path: &Path, manifest: &Manifest, disks: &[(&str, &Path)]) -> BoxliteResult<()> {
+    let storage_err = |e: std::io::Error| {
+        BoxliteError::Storage(format!(
+            "Failed to write box archive {}: {}",
+            path.display(),
+            e
+        ))
+    };
+    let json = serde_json::to_vec_pretty(manifest)
+        .map_err(|e| BoxliteError::Internal(format!("Failed to serialize box: {}", e)))?;
+
+    let mut builder = tar::Builder::new(File::create(path).map_err(storage_err)?);
+    let mut header = tar::Header::new_gnu();
+    header.set_size(json.len() as u64);
+    header.set_mode(0o644);
+    header.set_cksum();
+    builder
+        .append_data(&mut header, MANIFEST_FILE, json.as_slice())
+        .map_err(storage_err)?;
+    for (name, disk) in disks {
+        builder
+            .append_path_with_name(disk, name)
+            .map_err(storage_err)?;
+    }
+    builder
+        .into_inner()
+        .map_err(storage_err)?
+        .sync_all()
+        .map_err(storage_err)
+}
+
+/// Read an archive, unpacking its disks into `dir` under their archive names.
+pub(crate) fn read(path: &Path, dir: &Path) -> BoxliteResult<Manifest> {
+    let invalid = |msg: String| {
+        BoxliteError::InvalidArgument(format!("Invalid box archive {}: {}", path.display(), msg))
+    };
+    let storage_err = |e: std::io::Error| {
+        BoxliteError::Storage(format!(
+            "Failed to read box archive {}: {}",
+            path.display(),
+            e
+        ))
+    };
+
+    let mut archive = tar::Archive::new(File::open(path).map_err(storage_err)?);
+    let mut manifest = None;
+    for entry in archive.entries().map_err(storage_err)? {
+        let mut entry = entry.map_err(storage_err)?;
+        let name = entry
+            .path()
+            .map_err(storage_err)?
+            .to_string_lossy()
+            .into_owned();
+        match name.as_str() {
+            MANIFEST_FILE => {
+                let parsed: Manifest = serde_json::from_reader(&mut entry)
+                    .map_err(|e| invalid(format!("bad {}: {}", MANIFEST_FILE, e)))?;
+                if parsed.version > ARCHIVE_VERSION {
+                    return Err(invalid(format!(
+                        "version {} is newer than the supported {}",
+                        parsed.version, ARCHIVE_VERSION
+                    )));
+                }
+                manifest = Some(parsed);
+            }
+            ROOTFS_DISK | GUEST_DISK => {
+                entry.unpack(dir.join(&name)).map_err(storage_err)?;
+            }
+            _ => return Err(invalid(format!("unexpected entry {:?}", name))),
+        }
+    }
+    manifest.ok_or_else(|| invalid(format!("missing {}", MANIFEST_FILE)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::litebox::config::ContainerRuntimeConfig;
+    use crate::runtime::options::BoxOptions;
+    use crate::runtime::types::{BoxID, ContainerID};
+    use crate::vmm::VmmKind;
+    use boxlite_shared::Transport;
+    use std::path::PathBuf;
+
+    fn config() -> BoxConfig {
+        BoxConfig {
+            id: BoxID::new(),
+            name: Some("worker".to_string()),
+            created_at: chrono::Utc::now(),
+            container: ContainerRuntimeConfig {
+                id: ContainerID::new(),
+            },
+            options: BoxOptions::default(),
+            engine_kind: VmmKind::Libkrun,
+            transport: Transport::unix(PathBuf::from("/tmp/test.sock")),
+            box_home: PathBuf::from("/tmp/boxes/test"),
+            ready_socket_path: PathBuf::from("/tmp/ready.sock"),
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let disk = dir.path().join("source.qcow2");
+        std::fs::write(&disk, b"disk contents").unwrap();
+        let archive = dir.path().join("box.tar");
+        let config = config();
+
+        write(
+            &archive,
+            &Manifest::new(config.clone(), BoxState::new()),
+            &[(ROOTFS_DISK, &disk)],
+        )
+        .unwrap();
+
+        let out = dir.path().join("out");
+        std::fs::create_dir(&out).unwrap();
+        let manifest = read(&archive, &out).unwrap();
+        assert_eq!(manifest.config.id, config.id);
+        assert_eq!(manifest.config.name, config.name);
+        assert_eq!(
+            std::fs::read(out.join(ROOTFS_DISK)).unwrap(),
+            b"disk contents"
+        );
+        assert!(!out.join(GUEST_DISK).exists());
+    }
+
+    #[test]
+    fn test_rejects_unknown_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = dir.path().join("box.tar");
+        let mut builder = tar::Builder::new(File::create(&archive).unwrap());
+        let mut header = tar::Header::new_gnu();
+        header.set_size(1);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, "notes.txt", &b"x"[..])
+            .unwrap();
+        builder.finish().unwrap();
+
+        assert!(matches!(
+            read(&archive, dir.path()),
+            Err(BoxliteError::InvalidArgument(_))
+        ));
+    }
+}
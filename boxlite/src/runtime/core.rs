@@ -1,12 +1,19 @@
 //! High-level sandbox runtime structures.
 
-use std::sync::OnceLock;
+use std::path::Path;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
 
-use crate::litebox::LiteBox;
+use crate::litebox::{BoxSpec, ExecRecord, LiteBox};
 use crate::metrics::RuntimeMetrics;
-use crate::runtime::options::{BoxOptions, BoxliteOptions};
+use crate::runtime::options::{BoxOptions, BoxliteOptions, CloneOptions, PruneFilter};
 use crate::runtime::rt_impl::{RuntimeImpl, SharedRuntimeImpl};
-use crate::runtime::types::BoxInfo;
+use crate::runtime::types::{
+    BatchReport, BoxInfo, BoxInspect, DrainReport, LabelSelector, ListOptions, NetworkInfo,
+    PruneReport,
+};
+use crate::runtime::volumes::Volumes;
+use crate::telemetry::TelemetrySink;
 use boxlite_shared::errors::{BoxliteError, BoxliteResult};
 // ============================================================================
 // GLOBAL DEFAULT RUNTIME
@@ -172,6 +179,69 @@ impl BoxliteRuntime {
         self.rt_impl.create(options, name)
     }
 
+    /// Create a box from a spec taken with [`LiteBox::spec`].
+    ///
+    /// The box gets the spec's options and the exact image it pinned.
+    /// Fails with `InvalidArgument` if the spec was edited or is from a
+    /// newer version.
+    pub fn create_from_spec(&self, spec: &BoxSpec, name: Option<String>) -> BoxliteResult<LiteBox> {
+        spec.verify()?;
+        self.rt_impl.create(spec.options.clone(), name)
+    }
+
+    /// Create a box and start it under a single timeout.
+    ///
+    /// Covers registration, the init pipeline and guest readiness. If the
+    /// timeout expires, startup fails, or the returned future is dropped, the
+    /// box is fully removed: no DB row, lock, or box directory is left behind.
+    pub async fn create_and_start(
+        &self,
+        options: BoxOptions,
+        name: Option<String>,
+        timeout: Duration,
+    ) -> BoxliteResult<LiteBox> {
+        self.rt_impl.create_and_start(options, name, timeout).await
+    }
+
+    /// Create a new box from the current state of a stopped box.
+    ///
+    /// The new box starts from a copy of the source's rootfs changes, on
+    /// top of the same base image, and has the source's options. Many boxes
+    /// can branch off one prepared box this way. The clone is stopped;
+    /// [`LiteBox::start`] or its first command boots it.
+    pub fn clone_box(&self, id_or_name: &str, options: CloneOptions) -> BoxliteResult<LiteBox> {
+        self.rt_impl.clone_box(id_or_name, options)
+    }
+
+    /// Write a stopped box to an archive at `path`, to move it to another
+    /// host with [`import_box`](Self::import_box).
+    ///
+    /// The archive holds the box's config, state and disks. The disks are
+    /// flattened onto their base images, so the archive does not need the
+    /// images to be cached on the other host. Requires `qemu-img`.
+    pub fn export_box(&self, id_or_name: &str, path: impl AsRef<Path>) -> BoxliteResult<()> {
+        self.rt_impl.export_box(id_or_name, path.as_ref())
+    }
+
+    /// Create a stopped box from an archive written by
+    /// [`export_box`](Self::export_box).
+    ///
+    /// The box gets a new ID and keeps its name, options and disks. Fails if
+    /// a box with that name already exists.
+    pub fn import_box(&self, path: impl AsRef<Path>) -> BoxliteResult<LiteBox> {
+        self.rt_impl.import_box(path.as_ref())
+    }
+
+    /// Get the box named `name`, creating it with `options` if there is none.
+    ///
+    /// Unlike [`get`](Self::get) followed by [`create`](Self::create), this
+    /// is atomic: concurrent callers with the same name all get the same
+    /// box. `options` are ignored for an existing box. The returned flag is
+    /// true if the box was created by this call.
+    pub fn get_or_create(&self, name: &str, options: BoxOptions) -> BoxliteResult<(LiteBox, bool)> {
+        self.rt_impl.get_or_create(name, options)
+    }
+
     /// Get a handle to an existing box by ID or name.
     ///
     /// The `id_or_name` parameter can be either:
@@ -186,21 +256,179 @@ impl BoxliteRuntime {
         self.rt_impl.get_info(id_or_name)
     }
 
+    /// Everything known about a box by ID or name, like `docker inspect`.
+    ///
+    /// See [`LiteBox::inspect`].
+    pub async fn inspect(&self, id_or_name: &str) -> BoxliteResult<Option<BoxInspect>> {
+        match self.rt_impl.get_internal(id_or_name)? {
+            Some(litebox) => litebox.inspect().await.map(Some),
+            None => Ok(None),
+        }
+    }
+
     /// List all boxes, sorted by creation time (newest first).
     pub fn list_info(&self) -> BoxliteResult<Vec<BoxInfo>> {
         self.rt_impl.list_info()
     }
 
+    /// List the boxes whose labels match `selector`, newest first.
+    ///
+    /// Labels are set with [`BoxOptions::labels`](crate::BoxOptions::labels),
+    /// e.g. to tag boxes by tenant or job.
+    pub fn list_filtered(&self, selector: &LabelSelector) -> BoxliteResult<Vec<BoxInfo>> {
+        self.rt_impl.list_filtered(selector)
+    }
+
+    /// List the boxes matching `options`, newest first.
+    ///
+    /// Filters and paging run in the database, so a page does not load
+    /// every box of a large runtime.
+    pub fn list(&self, options: &ListOptions) -> BoxliteResult<Vec<BoxInfo>> {
+        self.rt_impl.list(options)
+    }
+
     /// Check if a box with the given ID or name exists.
     pub fn exists(&self, id_or_name: &str) -> BoxliteResult<bool> {
         self.rt_impl.exists(id_or_name)
     }
 
+    /// Get the commands executed in a box by ID or name, oldest first.
+    ///
+    /// Records are kept until the box is removed. Commands still running
+    /// have no `finished_at`/`exit_code` yet.
+    pub fn exec_history(&self, id_or_name: &str) -> BoxliteResult<Vec<ExecRecord>> {
+        self.rt_impl.exec_history(id_or_name)
+    }
+
     /// Get runtime-wide metrics.
     pub fn metrics(&self) -> RuntimeMetrics {
         self.rt_impl.metrics()
     }
 
+    /// Reload runtime configuration without restarting.
+    ///
+    /// `memory_policy` applies to boxes started from now on (running boxes
+    /// keep their settings), `defaults` to boxes created from now on;
+    /// `log_level` applies immediately. Fails without applying anything if
+    /// the new options are invalid, or change an option read only at
+    /// startup: `home_dir`, `registry_cache`, `reap_interval`,
+    /// `max_concurrent_boots`, `dns_listen` or `network_tuning`.
+    pub fn reload_config(&self, options: BoxliteOptions) -> BoxliteResult<()> {
+        self.rt_impl.reload_config(options)
+    }
+
+    /// Reload runtime configuration on every SIGHUP, as daemons do.
+    ///
+    /// `load` is called for the new options, e.g. to read a config file,
+    /// which are then applied as with [`reload_config`](Self::reload_config).
+    /// A failed reload is logged and keeps the current options. SIGHUP no
+    /// longer terminates the process afterwards. Needs an async runtime.
+    pub fn reload_on_sighup<F>(&self, load: F) -> BoxliteResult<()>
+    where
+        F: Fn() -> BoxliteResult<BoxliteOptions> + Send + 'static,
+    {
+        self.rt_impl.reload_on_sighup(load)
+    }
+
+    /// Register a telemetry sink.
+    ///
+    /// The sink receives init pipeline task spans, exec events and box state
+    /// changes from all boxes of this runtime, from now on.
+    pub fn add_telemetry_sink(&self, sink: Arc<dyn TelemetrySink>) {
+        self.rt_impl.telemetry.add_sink(sink);
+    }
+
+    /// Stop all running boxes before host maintenance (reboot, upgrade).
+    ///
+    /// New boxes, box starts and commands are refused from now on. Running
+    /// commands get until `deadline` to finish (detached commands are not
+    /// waited for); then every running or paused box is stopped, killing
+    /// whatever is still running. Boxes with `auto_remove` are removed as
+    /// usual.
+    ///
+    /// The report lists boxes that had commands killed or failed to stop.
+    /// The runtime keeps refusing work; drop it and create a new one after
+    /// maintenance.
+    pub async fn drain(&self, deadline: Duration) -> BoxliteResult<DrainReport> {
+        self.rt_impl.drain(deadline).await
+    }
+
+    /// Stop a box by ID or name and start it again.
+    ///
+    /// See [`LiteBox::restart`]; returns a handle to the restarted box.
+    pub async fn restart(&self, id_or_name: &str, timeout: Duration) -> BoxliteResult<LiteBox> {
+        self.rt_impl.restart(id_or_name, timeout).await
+    }
+
+    /// Give a box a new name.
+    ///
+    /// The name must not be used by another box, and the box must have been
+    /// started once. While a handle to the box is open, handles report the
+    /// old name until the box is stopped.
+    pub fn rename(&self, id_or_name: &str, new_name: &str) -> BoxliteResult<()> {
+        self.rt_impl.rename(id_or_name, new_name)
+    }
+
+    /// Create a network that boxes join through [`BoxOptions::networks`].
+    ///
+    /// Boxes on the same network reach each other directly on its subnet,
+    /// without going through the host's ports. Names are up to 32 letters,
+    /// digits, `_`, `-` or `.`.
+    pub fn create_network(&self, name: &str) -> BoxliteResult<NetworkInfo> {
+        self.rt_impl.create_network(name)
+    }
+
+    /// Remove a network. Fails while a box is configured to join it.
+    pub fn remove_network(&self, name: &str) -> BoxliteResult<()> {
+        self.rt_impl.remove_network(name)
+    }
+
+    /// List the networks boxes can join.
+    pub fn list_networks(&self) -> BoxliteResult<Vec<NetworkInfo>> {
+        self.rt_impl.list_networks()
+    }
+
+    /// Named volumes, which boxes mount by name and which outlive them.
+    pub fn volumes(&self) -> Volumes {
+        Volumes::new(self.rt_impl.clone())
+    }
+
+    /// Stop every running box, up to a few at a time.
+    ///
+    /// Each box gets `timeout` to shut down before its VM is killed. Errors
+    /// are collected per box instead of ending the operation, which suits
+    /// test teardown and host shutdown hooks.
+    pub async fn stop_all(&self, timeout: Duration) -> BoxliteResult<BatchReport> {
+        self.rt_impl.stop_all(timeout).await
+    }
+
+    /// Shut the runtime down so the application can exit cleanly.
+    ///
+    /// New boxes, box starts and commands are refused from now on. Every
+    /// running or paused box gets `timeout` to stop, the database is flushed
+    /// and the lock on the home directory is released, so another runtime
+    /// can open it even before this one is dropped.
+    pub async fn shutdown(&self, timeout: Duration) -> BoxliteResult<BatchReport> {
+        self.rt_impl.shutdown(timeout).await
+    }
+
+    /// Remove every box, up to a few at a time.
+    ///
+    /// Active boxes are skipped (and reported as failed) unless `force` is
+    /// set, in which case their VM is killed first.
+    pub async fn remove_all(&self, force: bool) -> BoxliteResult<BatchReport> {
+        self.rt_impl.remove_all(force).await
+    }
+
+    /// Remove all stopped boxes that match `filter`, like
+    /// `docker container prune`.
+    ///
+    /// Running boxes are never removed. Boxes that fail to be removed are
+    /// listed in the report rather than stopping the prune.
+    pub async fn prune(&self, filter: PruneFilter) -> BoxliteResult<PruneReport> {
+        self.rt_impl.prune(&filter)
+    }
+
     /// Remove a box completely by ID or name.
     pub async fn remove(&self, id_or_name: &str, force: bool) -> BoxliteResult<()> {
         self.rt_impl.remove(id_or_name, force)
@@ -41,6 +41,15 @@ pub mod dirs {
 
     /// Subdirectory for per-entity locks
     pub const LOCKS_DIR: &str = "locks";
+
+    /// Subdirectory for caches shared by all boxes
+    pub const CACHE_DIR: &str = "cache";
+
+    /// Subdirectory for inter-box network sockets
+    pub const NETWORKS_DIR: &str = "networks";
+
+    /// Subdirectory for named volumes
+    pub const VOLUMES_DIR: &str = "volumes";
 }
 
 /// Configuration for filesystem layout behavior.
@@ -131,6 +140,24 @@ impl FilesystemLayout {
         self.home_dir.join(dirs::LOCKS_DIR)
     }
 
+    /// Sockets of an inter-box network's boxes: ~/.boxlite/networks/{name}
+    pub fn network_dir(&self, name: &str) -> PathBuf {
+        self.home_dir.join(dirs::NETWORKS_DIR).join(name)
+    }
+
+    /// Data of a named volume: ~/.boxlite/volumes/{name}
+    ///
+    /// Holds `data/` for a volume backed by a directory, or `disk.ext4` for
+    /// one backed by a disk image.
+    pub fn volume_dir(&self, name: &str) -> PathBuf {
+        self.home_dir.join(dirs::VOLUMES_DIR).join(name)
+    }
+
+    /// Package registry cache: ~/.boxlite/cache/registries
+    pub fn registry_cache_dir(&self) -> PathBuf {
+        self.home_dir.join(dirs::CACHE_DIR).join("registries")
+    }
+
     /// Temporary directory for transient files: ~/.boxlite/tmp
     /// Used for disk image creation and other operations that need
     /// temp files on the same filesystem as the final destination.
@@ -274,6 +301,18 @@ impl BoxFilesystemLayout {
         self.sockets_dir().join("ready.sock")
     }
 
+    /// Shim control socket: ~/.boxlite/boxes/{box_id}/sockets/shim.sock
+    pub fn control_socket_path(&self) -> PathBuf {
+        self.sockets_dir().join("shim.sock")
+    }
+
+    /// Tunnel to guest ports: ~/.boxlite/boxes/{box_id}/sockets/tunnel.sock
+    ///
+    /// Served by the network backend, see [`crate::LiteBox::http`].
+    pub fn tunnel_socket_path(&self) -> PathBuf {
+        self.sockets_dir().join("tunnel.sock")
+    }
+
     // ========================================================================
     // MOUNTS AND SHARED
     // ========================================================================
@@ -325,6 +364,11 @@ impl BoxFilesystemLayout {
         self.box_dir.join("disk.qcow2")
     }
 
+    /// Guest rootfs overlay path: ~/.boxlite/boxes/{box_id}/guest-rootfs.qcow2
+    pub fn guest_disk_path(&self) -> PathBuf {
+        self.box_dir.join("guest-rootfs.qcow2")
+    }
+
     /// Console output path: ~/.boxlite/boxes/{box_id}/console.log
     ///
     /// Captures kernel and init output for debugging.
@@ -332,6 +376,13 @@ impl BoxFilesystemLayout {
         self.box_dir.join("console.log")
     }
 
+    /// Single-file volumes: ~/.boxlite/boxes/{box_id}/volume-files
+    ///
+    /// Each file volume is shared from a subdirectory holding only its file.
+    pub fn volume_files_dir(&self) -> PathBuf {
+        self.box_dir.join("volume-files")
+    }
+
     // ========================================================================
     // PREPARATION AND CLEANUP
     // ========================================================================
@@ -361,6 +412,53 @@ impl BoxFilesystemLayout {
         Ok(())
     }
 
+    /// Remove sockets left behind by a crashed run.
+    ///
+    /// A socket is stale when nothing accepts connections on it anymore.
+    /// Sockets that are still being listened on are kept and reported as an
+    /// error, since a second VM for this box can't bind them.
+    pub fn remove_stale_sockets(&self) -> BoxliteResult<()> {
+        let entries = match std::fs::read_dir(self.sockets_dir()) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => {
+                return Err(BoxliteError::Storage(format!(
+                    "failed to read sockets dir: {e}"
+                )));
+            }
+        };
+
+        for entry in entries {
+            let path = entry?.path();
+            if !is_socket(&path) {
+                continue;
+            }
+
+            match std::os::unix::net::UnixStream::connect(&path) {
+                Ok(_) => {
+                    return Err(BoxliteError::InvalidState(format!(
+                        "socket {} is still in use by another process",
+                        path.display()
+                    )));
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::ConnectionRefused => {
+                    tracing::info!(socket = %path.display(), "Removing stale socket");
+                    std::fs::remove_file(&path).map_err(|e| {
+                        BoxliteError::Storage(format!(
+                            "failed to remove stale socket {}: {e}",
+                            path.display()
+                        ))
+                    })?;
+                }
+                // Gone already, or not ours to judge (e.g. permissions)
+                Err(e) => {
+                    tracing::debug!(socket = %path.display(), error = %e, "Skipping socket");
+                }
+            }
+        }
+        Ok(())
+    }
+
     /// Cleanup the box directory.
     pub fn cleanup(&self) -> BoxliteResult<()> {
         if self.box_dir.exists() {
@@ -371,6 +469,13 @@ impl BoxFilesystemLayout {
     }
 }
 
+fn is_socket(path: &Path) -> bool {
+    use std::os::unix::fs::FileTypeExt;
+    std::fs::symlink_metadata(path)
+        .map(|m| m.file_type().is_socket())
+        .unwrap_or(false)
+}
+
 // ============================================================================
 // IMAGE FILESYSTEM LAYOUT (images directory)
 // ============================================================================
@@ -443,3 +548,34 @@ impl ImageFilesystemLayout {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::net::UnixListener;
+
+    #[test]
+    fn test_remove_stale_sockets() {
+        let dir = tempfile::tempdir().unwrap();
+        let layout = BoxFilesystemLayout::new(
+            dir.path().join("box"),
+            FsLayoutConfig::without_bind_mount(),
+            false,
+        );
+        layout.prepare().unwrap();
+
+        // Listener dropped: the socket file stays but refuses connections
+        drop(UnixListener::bind(layout.ready_socket_path()).unwrap());
+        let other = layout.sockets_dir().join("notes.txt");
+        std::fs::write(&other, "keep").unwrap();
+
+        layout.remove_stale_sockets().unwrap();
+        assert!(!layout.ready_socket_path().exists());
+        assert!(other.exists());
+
+        // Live listener is left alone
+        let _listener = UnixListener::bind(layout.socket_path()).unwrap();
+        assert!(layout.remove_stale_sockets().is_err());
+        assert!(layout.socket_path().exists());
+    }
+}
@@ -1,12 +1,18 @@
+pub(crate) mod admission;
+mod archive;
 pub mod constants;
 pub(crate) mod guest_rootfs;
 pub mod layout;
 pub(crate) mod lock;
 pub mod options;
+pub mod systemd;
 pub mod types;
 
 mod core;
 pub(crate) mod rt_impl;
+pub(crate) mod supervisor;
+pub(crate) mod volumes;
 
 pub use core::BoxliteRuntime;
 pub(crate) use rt_impl::SharedRuntimeImpl;
+pub use volumes::Volumes;
@@ -1,203 +1,2705 @@
 //! Configuration for Boxlite.
 
+use crate::net::NetworkBackendKind;
 use crate::runtime::constants::envs as const_envs;
 use crate::runtime::layout::dirs as const_dirs;
-use boxlite_shared::errors::BoxliteResult;
+use crate::runtime::types::{LabelSelector, PortMapping};
+use boxlite_shared::errors::{BoxliteError, BoxliteResult};
 use dirs::home_dir;
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
 use std::path::PathBuf;
+use std::time::Duration;
 /// Configuration options for BoxliteRuntime.
 ///
 /// Users can create it with defaults and modify fields as needed.
 #[derive(Clone, Debug)]
 pub struct BoxliteOptions {
     pub home_dir: PathBuf,
+    /// Host memory policy applied to every box's VMM process.
+    pub memory_policy: MemoryPolicy,
+    /// Log filter for the runtime log file, in `RUST_LOG` syntax
+    /// (e.g. `"debug"` or `"boxlite=trace,info"`). Overrides `RUST_LOG`.
+    pub log_level: Option<String>,
+    /// Settings merged into every box created by this runtime.
+    pub defaults: BoxDefaults,
+    /// Host-side cache for package registry downloads, shared by all boxes.
+    ///
+    /// Read once when the runtime starts. `None` (default) disables it.
+    pub registry_cache: Option<RegistryCacheOptions>,
+    /// How often the runtime looks for boxes whose VM died, marks them
+    /// stopped and applies their restart policy.
+    ///
+    /// Boxes with a restart policy are also watched more closely while a
+    /// handle is open. Read once when the runtime starts. `None` disables
+    /// it; defaults to 10 seconds.
+    pub reap_interval: Option<Duration>,
+    /// Maximum number of boxes running (or paused) at once.
+    ///
+    /// Starting a box beyond this, or any of the limits below, fails with
+    /// `ResourceExhausted`; so does creating one that could not start now.
+    /// `None` (default) means no limit.
+    pub max_boxes: Option<usize>,
+    /// Maximum memory, in MiB, of all running boxes together.
+    pub max_total_memory_mib: Option<u64>,
+    /// Maximum vCPUs of all running boxes together.
+    pub max_total_cpus: Option<u32>,
+    /// Maximum number of boxes booting at once.
+    ///
+    /// Booting (rootfs setup and VM start) is disk and CPU heavy; further
+    /// boxes wait their turn in arrival order. Attaching to a running box
+    /// does not count. Read once when the runtime starts. `None` (default)
+    /// means no limit.
+    pub max_concurrent_boots: Option<usize>,
+    /// UDP address of a DNS server resolving `<name>.boxlite` to the host
+    /// address where the running box's published ports listen, for the
+    /// host's resolver to use for the `boxlite` domain (e.g.
+    /// `127.0.0.1:5353`).
+    ///
+    /// Boxes on a common network resolve each other by name regardless.
+    /// Read once when the runtime starts. `None` (default) disables it.
+    pub dns_listen: Option<SocketAddr>,
+    /// Network backend parameters, for throughput on large transfers.
+    ///
+    /// Read once when the runtime starts.
+    pub network_tuning: NetworkTuning,
+}
+
+impl Default for BoxliteOptions {
+    fn default() -> Self {
+        let home_dir = std::env::var(const_envs::BOXLITE_HOME)
+            .map(PathBuf::from)
+            .unwrap_or_else(|_| {
+                let mut path = home_dir().unwrap_or_else(|| PathBuf::from("."));
+                path.push(const_dirs::BOXLITE_DIR);
+                path
+            });
+
+        Self {
+            home_dir,
+            memory_policy: MemoryPolicy::default(),
+            log_level: None,
+            defaults: BoxDefaults::default(),
+            registry_cache: None,
+            reap_interval: Some(DEFAULT_REAP_INTERVAL),
+            max_boxes: None,
+            max_total_memory_mib: None,
+            max_total_cpus: None,
+            max_concurrent_boots: None,
+            dns_listen: None,
+            network_tuning: NetworkTuning::default(),
+        }
+    }
+}
+
+/// `extra_hosts` IP standing for the host, as in docker.
+const HOST_GATEWAY: &str = "host-gateway";
+
+const DEFAULT_REAP_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Runtime-wide box settings, e.g. an org-wide CA bundle or proxy.
+///
+/// Merged into each box's [`BoxOptions`] when the box is created; the box's
+/// own settings win on conflict (same env key, label key or guest path).
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+pub struct BoxDefaults {
+    #[serde(default)]
+    pub env: Vec<(String, String)>,
+    #[serde(default)]
+    pub volumes: Vec<VolumeSpec>,
+    #[serde(default)]
+    pub labels: HashMap<String, String>,
+}
+
+impl BoxDefaults {
+    /// Merge the defaults into `options`.
+    pub fn apply(&self, mut options: BoxOptions) -> BoxOptions {
+        let mut env: Vec<_> = self
+            .env
+            .iter()
+            .filter(|(key, _)| !options.env.iter().any(|(k, _)| k == key))
+            .cloned()
+            .collect();
+        env.append(&mut options.env);
+        options.env = env;
+
+        // A guest path can only be mounted once
+        let mut volumes: Vec<_> = self
+            .volumes
+            .iter()
+            .filter(|v| {
+                !options
+                    .volumes
+                    .iter()
+                    .any(|own| own.guest_path() == v.guest_path())
+            })
+            .cloned()
+            .collect();
+        volumes.append(&mut options.volumes);
+        options.volumes = volumes;
+
+        for (key, value) in &self.labels {
+            options
+                .labels
+                .entry(key.clone())
+                .or_insert_with(|| value.clone());
+        }
+        options
+    }
+}
+
+/// Caching mirror for package registries, served to boxes by the host.
+///
+/// Boxes are pointed at the mirror through package manager environment
+/// variables (`CARGO_*`, `PIP_INDEX_URL`, `UV_DEFAULT_INDEX`,
+/// `NPM_CONFIG_REGISTRY`), unless they set those themselves. Downloaded
+/// packages are kept forever; indexes are refreshed on every request and
+/// served from the cache when the registry is unreachable, so installs of
+/// cached packages work offline. Requires the gvproxy network backend.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct RegistryCacheOptions {
+    /// Registries routed through the cache.
+    #[serde(default = "default_registries")]
+    pub registries: Vec<PackageRegistry>,
+    /// Cache directory. Defaults to `<home_dir>/cache/registries`.
+    #[serde(default)]
+    pub dir: Option<PathBuf>,
+}
+
+impl Default for RegistryCacheOptions {
+    fn default() -> Self {
+        Self {
+            registries: default_registries(),
+            dir: None,
+        }
+    }
+}
+
+fn default_registries() -> Vec<PackageRegistry> {
+    vec![
+        PackageRegistry::CratesIo,
+        PackageRegistry::PyPi,
+        PackageRegistry::Npm,
+    ]
+}
+
+/// Public package registry that can be cached.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub enum PackageRegistry {
+    /// crates.io, for cargo.
+    CratesIo,
+    /// pypi.org, for pip and uv.
+    PyPi,
+    /// registry.npmjs.org, for npm.
+    Npm,
+}
+
+/// Host memory policy for VMM processes.
+///
+/// Trades memory density against latency. The defaults leave the host's
+/// system-wide settings untouched. Only supported on Linux.
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct MemoryPolicy {
+    /// Let KSM (kernel same-page merging) deduplicate guest memory.
+    ///
+    /// Improves density when many boxes run the same image, at the cost of
+    /// CPU time for page scanning. Requires Linux 6.4+ and KSM to be running
+    /// (`/sys/kernel/mm/ksm/run`).
+    #[serde(default)]
+    pub ksm: bool,
+
+    /// Transparent hugepage policy for guest memory.
+    #[serde(default)]
+    pub thp: ThpPolicy,
+}
+
+/// Transparent hugepage policy for VMM memory allocations.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum ThpPolicy {
+    /// Follow the system-wide setting (`/sys/kernel/mm/transparent_hugepage/enabled`).
+    #[default]
+    System,
+    /// Allow hugepages for all guest memory (lower latency, higher footprint).
+    ///
+    /// Only takes full effect when the system-wide mode is also `always`.
+    Always,
+    /// Only use hugepages for regions that explicitly request them.
+    ///
+    /// Requires Linux 6.18+ when the system-wide mode is `always`.
+    Madvise,
+    /// Never use hugepages (best density, fewer latency spikes from compaction).
+    Never,
+}
+
+impl MemoryPolicy {
+    /// Validate the policy for the current platform.
+    pub fn sanitize(&self) -> BoxliteResult<()> {
+        #[cfg(not(target_os = "linux"))]
+        if *self != MemoryPolicy::default() {
+            return Err(boxlite_shared::errors::BoxliteError::Unsupported(
+                "memory_policy (KSM/THP) is only supported on Linux".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Options used when constructing a box.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct BoxOptions {
+    pub cpus: Option<u8>,
+    pub memory_mib: Option<u32>,
+    /// Disk size in GB for the container rootfs (sparse, grows as needed).
+    ///
+    /// The actual disk will be at least as large as the base image.
+    /// If set, the COW overlay will have this virtual size, allowing
+    /// the container to write more data than the base image size.
+    pub disk_size_gb: Option<u64>,
+    pub working_dir: Option<String>,
+    pub env: Vec<(String, String)>,
+    /// Host files with `KEY=value` lines, read like docker's `--env-file`
+    /// when the box is created. Later files win; `env` wins over all.
+    #[serde(default)]
+    pub env_files: Vec<PathBuf>,
+    pub rootfs: RootfsSpec,
+    pub volumes: Vec<VolumeSpec>,
+    /// Host Unix sockets exposed in the container, e.g. the docker socket.
+    #[serde(default)]
+    pub sockets: Vec<SocketSpec>,
+    /// Host services reachable from the box on its loopback, e.g. a
+    /// database on the host's `localhost:5432`. Needs no network.
+    #[serde(default)]
+    pub reverse_ports: Vec<ReversePortSpec>,
+    pub network: NetworkSpec,
+    pub ports: Vec<PortSpec>,
+    /// Publish a `ports` host port that is already taken on the next free
+    /// port above it, instead of failing with
+    /// [`PortInUse`](boxlite_shared::errors::BoxliteError::PortInUse). The
+    /// ports used are reported in `BoxInfo::ports`.
+    #[serde(default)]
+    pub auto_assign_on_conflict: bool,
+    /// Hostname of the container, in its UTS namespace and `/etc/hostname`.
+    ///
+    /// None (default) uses the box name if it is a valid hostname, and the
+    /// short box ID otherwise.
+    #[serde(default)]
+    pub hostname: Option<String>,
+    /// DNS servers written to the container's `/etc/resolv.conf`.
+    ///
+    /// Empty (default) resolves through the network gateway.
+    #[serde(default)]
+    pub dns: Vec<String>,
+    /// DNS search domains for the container. Empty means `localdomain`.
+    #[serde(default)]
+    pub dns_search: Vec<String>,
+    /// Cache the answers of the gateway's DNS, for workloads that resolve
+    /// the same hosts over and over (e.g. package installs). None (default)
+    /// forwards each query to the host's resolvers.
+    #[serde(default)]
+    pub dns_cache: Option<DnsCache>,
+    /// Extra `/etc/hosts` entries of the container, as `host:ip` like
+    /// docker's `--add-host`. The IP `host-gateway` is the address under
+    /// which the box reaches the host, e.g. `host.internal:host-gateway`.
+    #[serde(default)]
+    pub extra_hosts: Vec<String>,
+    /// Where the box may connect to, enforced by the network backend.
+    ///
+    /// Domain rules match the addresses the box looked up through the
+    /// gateway's DNS, so they need the default `dns`. The gateway itself
+    /// (DNS) is always reachable; the host (`host-gateway`) is matched like
+    /// any other address. Denied packets are dropped.
+    #[serde(default)]
+    pub network_policy: NetworkPolicy,
+    /// Bandwidth cap of the box, enforced by the network backend so one box
+    /// cannot saturate the host's uplink. Unlimited by default.
+    #[serde(default)]
+    pub network_rate_limit: NetworkRateLimit,
+    /// Give the box an IPv6 address next to its IPv4 one.
+    ///
+    /// The network backend translates the box's outgoing IPv6 TCP
+    /// connections to the host's IPv6 connectivity, and published ports
+    /// also listen on the host's IPv6 addresses. Defaults to false.
+    #[serde(default)]
+    pub ipv6: bool,
+    /// User-mode network stack serving the box's network. Defaults to
+    /// gvproxy; passt needs the `passt` binary in PATH.
+    #[serde(default)]
+    pub network_backend: NetworkBackendKind,
+    /// How the box's network reaches the host's, see [`NetworkMode`].
+    #[serde(default)]
+    pub network_mode: NetworkMode,
+    /// Networks, made with
+    /// [`BoxliteRuntime::create_network`](crate::BoxliteRuntime::create_network),
+    /// the box joins next to its own network.
+    ///
+    /// The box gets an interface and a fixed address on each; boxes on the
+    /// same network reach each other directly, and by name for those that
+    /// started on it before this box.
+    #[serde(default)]
+    pub networks: Vec<String>,
+    /// Proxy for HTTP requests, set as `http_proxy` and `HTTP_PROXY` in the
+    /// container, e.g. `http://proxy.corp:3128`.
+    #[serde(default)]
+    pub http_proxy: Option<String>,
+    /// Proxy for HTTPS requests, set as `https_proxy` and `HTTPS_PROXY`.
+    #[serde(default)]
+    pub https_proxy: Option<String>,
+    /// Hosts reached without proxy, set as `no_proxy` and `NO_PROXY`,
+    /// e.g. `localhost,.corp`.
+    #[serde(default)]
+    pub no_proxy: Option<String>,
+    /// Take the proxy settings not given above from the host's environment
+    /// when the box starts. Defaults to false.
+    ///
+    /// Proxies on the host's loopback are not reachable under that address
+    /// from the box; use the `host-gateway` address instead.
+    #[serde(default)]
+    pub inherit_proxy: bool,
+    /// Enable bind mount isolation for the shared mounts directory.
+    ///
+    /// When true, creates a read-only bind mount from `mounts/` to `shared/`,
+    /// preventing the guest from modifying host-prepared files.
+    ///
+    /// Requires CAP_SYS_ADMIN (privileged) or FUSE (rootless) on Linux.
+    /// Defaults to false.
+    #[serde(default)]
+    pub isolate_mounts: bool,
+
+    /// Automatically remove box when stopped.
+    ///
+    /// When true (default), the box is removed from the database and its
+    /// files are deleted when `stop()` is called. This is similar to
+    /// Docker's `--rm` flag.
+    ///
+    /// When false, the box is preserved after stop and can be restarted
+    /// with `runtime.get(box_id)`.
+    #[serde(default = "default_auto_remove")]
+    pub auto_remove: bool,
+
+    /// Whether the box should continue running when the parent process exits.
+    ///
+    /// When false (default), the box will automatically stop when the process
+    /// that created it exits. This ensures orphan boxes don't accumulate.
+    /// Similar to running a process in the foreground.
+    ///
+    /// When true, the box runs independently and survives parent process exit.
+    /// The box can be reattached using `runtime.get(box_id)`. Similar to
+    /// Docker's `-d` (detach) flag.
+    #[serde(default = "default_detach")]
+    pub detach: bool,
+
+    /// Run a filesystem check (`e2fsck -p`) on the container rootfs when restarting.
+    ///
+    /// The qcow2 structure of the rootfs disk is always verified before a
+    /// stopped box is restarted. When true, the guest additionally repairs
+    /// the ext4 filesystem inside it before mounting, at the cost of a slower
+    /// restart. Defaults to false.
+    #[serde(default)]
+    pub fsck_on_restart: bool,
+
+    /// Lifetime of the box, counted from its creation.
+    ///
+    /// Once it is over, the runtime stops the box, and removes it if
+    /// `auto_remove` is set. `None` (default) means no limit.
+    #[serde(default)]
+    pub ttl: Option<Duration>,
+
+    /// Time the container gets to exit after its stop signal (the image's
+    /// STOPSIGNAL, or SIGTERM) before `stop()` kills the VM. Defaults to
+    /// 10 seconds.
+    #[serde(default = "default_stop_timeout")]
+    pub stop_timeout: Duration,
+
+    /// Wait up to this long in `stop()` for the open connections of published
+    /// `ports` to close before the container gets its stop signal, so that
+    /// in-flight requests to a server in the box finish instead of being
+    /// reset. New connections are still accepted meanwhile. `None` (default)
+    /// stops right away.
+    #[serde(default)]
+    pub drain_timeout: Option<Duration>,
+
+    /// Suspend the box after this long without a command.
+    ///
+    /// The box is idle while no exec is running and none was started for
+    /// this long. What happens then is set by `idle_action`; either way the
+    /// next operation through a handle to the box brings it back first.
+    /// Only watched while a handle to the box is open in this process.
+    /// `None` (default) never suspends.
+    #[serde(default)]
+    pub idle_timeout: Option<Duration>,
+
+    /// How an idle box is suspended, see `idle_timeout`.
+    #[serde(default)]
+    pub idle_action: IdleAction,
+
+    /// Boot the box on the first connection to one of its `ports`.
+    ///
+    /// The runtime listens on the host ports from creation on, and starts
+    /// the box when a connection arrives, holding the connection until the
+    /// guest port accepts it. With `idle_timeout`, a rarely used service
+    /// takes no memory until it is reached, and again once it goes idle;
+    /// open connections count as activity. Only `ports` are published, not
+    /// the image's exposed ports, and only while a runtime is open on the
+    /// box. Needs the gvproxy `network_backend`.
+    #[serde(default)]
+    pub lazy_start: bool,
+
+    /// Restart the box when its VM goes away on its own.
+    #[serde(default)]
+    pub restart_policy: RestartPolicy,
+
+    /// What happens to the running box when the last handle to it in this
+    /// process is dropped without `stop()`.
+    #[serde(default)]
+    pub on_drop: DropBehavior,
+
+    /// Write quotas enforced inside the box on the container rootfs.
+    ///
+    /// Independent of the total disk size, e.g. limit `/workspace` to 2 GiB.
+    /// Uses the ext4 quota feature, which is enabled on the rootfs disk the
+    /// first time the box starts with quotas. Volumes are not covered.
+    #[serde(default)]
+    pub disk_quotas: Vec<DiskQuota>,
+
+    /// Maximum number of commands running at once in this box.
+    ///
+    /// Protects small-memory boxes from bursts of parallel commands.
+    /// What happens to commands over the limit is set by
+    /// `exec_limit_policy`. `None` (default) means unlimited.
+    #[serde(default)]
+    pub max_concurrent_execs: Option<u32>,
+
+    /// What `exec()` does when `max_concurrent_execs` commands are running.
+    #[serde(default)]
+    pub exec_limit_policy: ExecLimitPolicy,
+
+    /// Buffering of exec output between the command and the caller.
+    #[serde(default)]
+    pub exec_buffer: ExecBufferOptions,
+
+    /// CPU features hidden from software in the box.
+    #[serde(default)]
+    pub cpu_features: CpuFeatureMask,
+
+    /// Largest file, in bytes, that
+    /// [`LiteBox::read_to_string`](crate::LiteBox::read_to_string) and
+    /// [`LiteBox::write_string`](crate::LiteBox::write_string) accept.
+    /// Defaults to 16 MiB.
+    #[serde(default = "default_max_text_file_size")]
+    pub max_text_file_size: u64,
+
+    /// Run the container's init process on a terminal.
+    ///
+    /// For interactive images (e.g. a shell entrypoint) used with
+    /// [`LiteBox::attach`](crate::LiteBox::attach).
+    #[serde(default)]
+    pub tty: bool,
+
+    /// User-defined labels for filtering and organization.
+    #[serde(default)]
+    pub labels: HashMap<String, String>,
+}
+
+/// What to do with a command when a box is at its
+/// [`max_concurrent_execs`](BoxOptions::max_concurrent_execs).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum ExecLimitPolicy {
+    /// Wait until a running command exits.
+    #[default]
+    Queue,
+    /// Fail right away.
+    Reject,
+}
+
+/// How much exec output is buffered per stream, and what happens when the
+/// caller reads slower than the command writes.
+///
+/// The buffers sit in the guest agent and in the host. With
+/// [`Block`](ExecBufferPolicy::Block) a full buffer pauses the command on
+/// its next write, so output must be read for the command to finish; with
+/// [`Drop`](ExecBufferPolicy::Drop) the command keeps running and output
+/// that does not fit is discarded.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ExecBufferOptions {
+    /// Output chunks, as read from the command, buffered per stream.
+    /// `None` (default) buffers without limit on the host.
+    #[serde(default)]
+    pub capacity: Option<usize>,
+    /// What to do with output when the buffer is full.
+    #[serde(default)]
+    pub policy: ExecBufferPolicy,
+}
+
+impl ExecBufferOptions {
+    fn sanitize(&self) -> BoxliteResult<()> {
+        if self.capacity == Some(0) {
+            return Err(boxlite_shared::errors::BoxliteError::Config(
+                "exec_buffer.capacity must be at least 1".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// What to do with exec output when its
+/// [`ExecBufferOptions::capacity`] is reached.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum ExecBufferPolicy {
+    /// Wait for the caller to read, pausing the command.
+    #[default]
+    Block,
+    /// Discard the output that does not fit.
+    Drop,
+}
+
+/// When the runtime restarts a box whose VM went away.
+///
+/// A box fails when its VM process exits without
+/// [`LiteBox::stop`](crate::LiteBox::stop), e.g. on a guest kernel panic
+/// or when the shim is killed. The runtime checks on running boxes once a
+/// second, while it is open. Restarts wait `backoff`, doubled after each
+/// attempt up to 5 minutes; a box that stays up for a minute starts from
+/// `backoff` again.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct RestartPolicy {
+    #[serde(default)]
+    pub mode: RestartMode,
+    /// Restarts in a row before giving up. `None` (default) retries forever.
+    #[serde(default)]
+    pub max_retries: Option<u32>,
+    /// Wait before the first restart. Defaults to 1 second.
+    #[serde(default = "default_restart_backoff")]
+    pub backoff: Duration,
+}
+
+impl Default for RestartPolicy {
+    fn default() -> Self {
+        Self {
+            mode: RestartMode::default(),
+            max_retries: None,
+            backoff: default_restart_backoff(),
+        }
+    }
+}
+
+fn default_restart_backoff() -> Duration {
+    Duration::from_secs(1)
+}
+
+/// Which boxes a [`RestartPolicy`] restarts.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum RestartMode {
+    /// Never restart.
+    #[default]
+    No,
+    /// Restart boxes that failed, also when the failure is found as the
+    /// runtime opens.
+    OnFailure,
+    /// Like `OnFailure`, and also restart boxes that were running when the
+    /// host rebooted.
+    UnlessStopped,
+    /// Like `UnlessStopped`, and also restart boxes stopped with
+    /// [`LiteBox::stop`](crate::LiteBox::stop) when the runtime opens.
+    Always,
+}
+
+/// What dropping the last [`LiteBox`](crate::LiteBox) handle to a running
+/// box does, set with [`BoxOptions::on_drop`].
+///
+/// Every handle the runtime hands out counts, from `create()`, `get()` or
+/// any other call, so dropping one of several leaves the box alone. The
+/// handles the runtime takes for itself, for health checks, `idle_timeout`,
+/// `ttl`, `restart_policy`, `stop_all()` or `inspect()`, do not count.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum DropBehavior {
+    /// Leave the box running; `runtime.get()` returns a new handle to it.
+    #[default]
+    Detach,
+    /// Stop the box in the background, as `stop()` would.
+    Stop,
+    /// Panic, to catch code paths that lose a box without stopping it.
+    Panic,
+}
+
+/// How a box's network reaches the host's, see [`BoxOptions::network_mode`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum NetworkMode {
+    /// Through the user-mode stack of `network_backend`, which also
+    /// publishes the box's ports. Works unprivileged.
+    #[default]
+    User,
+    /// On a TAP device on the host's `boxlite0` bridge (Linux only). Traffic
+    /// is routed and masqueraded by the host kernel, and published ports
+    /// are DNAT rules, for workloads the user-mode stack is too slow for.
+    ///
+    /// Needs `CAP_NET_ADMIN` and the `ip` and `iptables` tools. Without
+    /// `network_policy`, `network_rate_limit`, `ipv6`, `lazy_start`,
+    /// traffic captures, and host-to-box tunnels such as
+    /// [`LiteBox::http`](crate::LiteBox::http).
+    ///
+    /// Changes the host's network while such a box runs: the bridge and
+    /// the TAP devices, `ip_forward` turned on, and iptables rules tagged
+    /// `boxlite:` in the `nat` POSTROUTING, PREROUTING and OUTPUT chains
+    /// and at the top of the `filter` FORWARD and INPUT chains. The bridge
+    /// and its rules are removed with the last box on it, and `ip_forward`
+    /// is set back to what it was.
+    ///
+    /// The boxes cannot reach each other, nor connect to services of the
+    /// host, `10.87.0.1` included, while published ports and replies reach
+    /// them. They can reach every network the host routes to, its LAN and
+    /// other private ranges included.
+    Host,
+}
+
+/// How a box is suspended once [`BoxOptions::idle_timeout`] is over.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum IdleAction {
+    /// Stop the VM, freeing its memory. The next operation restarts the box
+    /// from its rootfs, so processes left running in it are lost.
+    #[default]
+    Stop,
+    /// Freeze the VM. Its memory stays allocated, but it resumes quickly
+    /// with its processes intact.
+    Pause,
+}
+
+/// Options for [`BoxliteRuntime::clone_box`](crate::BoxliteRuntime::clone_box).
+#[derive(Clone, Debug, Default)]
+pub struct CloneOptions {
+    /// Name of the new box.
+    pub name: Option<String>,
+}
+
+/// Which boxes [`BoxliteRuntime::prune`](crate::BoxliteRuntime::prune)
+/// removes. Only stopped boxes are ever removed; the default removes all
+/// of them.
+#[derive(Clone, Debug, Default)]
+pub struct PruneFilter {
+    /// Only boxes created at least this long ago.
+    pub older_than: Option<Duration>,
+    /// Only boxes whose labels match.
+    pub labels: LabelSelector,
+}
+
+/// CPU features hidden from software in a box.
+///
+/// Code that picks an implementation by CPU feature (glibc's string and
+/// math routines, libraries installed under glibc-hwcaps) then takes the
+/// same path on every host of a mixed fleet. libkrun has no CPUID filter,
+/// so the mask is applied through glibc's `glibc.cpu.hwcaps` tunable:
+/// programs that execute CPUID themselves still see the host CPU.
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct CpuFeatureMask {
+    /// Highest x86-64 microarchitecture level to expose (`None` = host's).
+    #[serde(default)]
+    pub max_level: Option<X86Level>,
+    /// Further features to hide, by glibc name (e.g. `"AVX512F"`).
+    #[serde(default)]
+    pub disable: Vec<String>,
+}
+
+/// x86-64 microarchitecture levels from the psABI.
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, serde::Serialize, serde::Deserialize,
+)]
+pub enum X86Level {
+    /// Baseline x86-64 (SSE2).
+    V1,
+    /// Adds SSE4.2, SSSE3 and POPCNT.
+    V2,
+    /// Adds AVX2, FMA, BMI1/2 and friends.
+    V3,
+}
+
+/// Features added by each level above v1, as glibc names them.
+const X86_V2_FEATURES: &[&str] = &["SSSE3", "SSE4_1", "SSE4_2", "POPCNT"];
+const X86_V3_FEATURES: &[&str] = &[
+    "AVX", "AVX2", "BMI1", "BMI2", "F16C", "FMA", "LZCNT", "MOVBE",
+];
+const X86_V4_FEATURES: &[&str] = &["AVX512F", "AVX512BW", "AVX512CD", "AVX512DQ", "AVX512VL"];
+
+impl CpuFeatureMask {
+    /// Hide every feature above `level`.
+    pub fn max_level(level: X86Level) -> Self {
+        Self {
+            max_level: Some(level),
+            disable: Vec::new(),
+        }
+    }
+
+    /// Whether anything is hidden.
+    pub fn is_empty(&self) -> bool {
+        self.max_level.is_none() && self.disable.is_empty()
+    }
+
+    /// Features to hide, in glibc's naming.
+    fn hidden(&self) -> Vec<&str> {
+        let above: &[&[&str]] = match self.max_level {
+            None => &[],
+            Some(X86Level::V1) => &[X86_V2_FEATURES, X86_V3_FEATURES, X86_V4_FEATURES],
+            Some(X86Level::V2) => &[X86_V3_FEATURES, X86_V4_FEATURES],
+            Some(X86Level::V3) => &[X86_V4_FEATURES],
+        };
+        let mut hidden: Vec<&str> = above.iter().flat_map(|f| f.iter().copied()).collect();
+        for feature in &self.disable {
+            if !hidden.contains(&feature.as_str()) {
+                hidden.push(feature);
+            }
+        }
+        hidden
+    }
+
+    /// The `GLIBC_TUNABLES` entry that applies the mask.
+    fn glibc_tunable(&self) -> Option<String> {
+        let hidden = self.hidden();
+        if hidden.is_empty() {
+            return None;
+        }
+        let features: Vec<String> = hidden.iter().map(|f| format!("-{}", f)).collect();
+        Some(format!("glibc.cpu.hwcaps={}", features.join(",")))
+    }
+
+    fn sanitize(&self) -> BoxliteResult<()> {
+        if self.is_empty() {
+            return Ok(());
+        }
+        if !cfg!(target_arch = "x86_64") {
+            return Err(BoxliteError::Unsupported(
+                "cpu_features masking is only supported on x86_64 hosts".to_string(),
+            ));
+        }
+        for feature in &self.disable {
+            if feature.is_empty()
+                || !feature
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_')
+            {
+                return Err(BoxliteError::Config(format!(
+                    "Invalid CPU feature name: {:?}",
+                    feature
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn default_auto_remove() -> bool {
+    true
+}
+
+fn default_detach() -> bool {
+    false
+}
+
+fn default_max_text_file_size() -> u64 {
+    16 * 1024 * 1024
+}
+
+fn default_stop_timeout() -> Duration {
+    Duration::from_secs(10)
+}
+
+impl Default for BoxOptions {
+    fn default() -> Self {
+        Self {
+            cpus: None,
+            memory_mib: None,
+            disk_size_gb: None,
+            working_dir: None,
+            env: Vec::new(),
+            env_files: Vec::new(),
+            rootfs: RootfsSpec::default(),
+            volumes: Vec::new(),
+            sockets: Vec::new(),
+            reverse_ports: Vec::new(),
+            network: NetworkSpec::default(),
+            ports: Vec::new(),
+            auto_assign_on_conflict: false,
+            hostname: None,
+            dns: Vec::new(),
+            dns_search: Vec::new(),
+            dns_cache: None,
+            extra_hosts: Vec::new(),
+            network_policy: NetworkPolicy::default(),
+            network_rate_limit: NetworkRateLimit::default(),
+            ipv6: false,
+            network_backend: NetworkBackendKind::default(),
+            network_mode: NetworkMode::default(),
+            networks: Vec::new(),
+            http_proxy: None,
+            https_proxy: None,
+            no_proxy: None,
+            inherit_proxy: false,
+            isolate_mounts: false,
+            auto_remove: default_auto_remove(),
+            detach: default_detach(),
+            fsck_on_restart: false,
+            ttl: None,
+            stop_timeout: default_stop_timeout(),
+            drain_timeout: None,
+            idle_timeout: None,
+            idle_action: IdleAction::default(),
+            lazy_start: false,
+            restart_policy: RestartPolicy::default(),
+            on_drop: DropBehavior::default(),
+            disk_quotas: Vec::new(),
+            max_concurrent_execs: None,
+            exec_limit_policy: ExecLimitPolicy::default(),
+            exec_buffer: ExecBufferOptions::default(),
+            cpu_features: CpuFeatureMask::default(),
+            max_text_file_size: default_max_text_file_size(),
+            tty: false,
+            labels: HashMap::new(),
+        }
+    }
+}
+
+impl BoxOptions {
+    /// Sanitize and validate options.
+    ///
+    /// Validates option combinations:
+    /// - `auto_remove=true` with `detach=true` is invalid (detached boxes need manual lifecycle control)
+    /// - `isolate_mounts=true` is only supported on Linux
+    /// - `disk_quotas` must target rootfs directories, not volumes
+    /// - `volumes` naming a volume (no `/` in `host_path`) must use a valid
+    ///   volume name, and tmpfs `volumes` need an absolute path other than
+    ///   `/`, a non-zero size and a mode of permission bits
+    /// - `max_concurrent_execs`, `max_text_file_size` and
+    ///   `exec_buffer.capacity` must be at least 1
+    /// - `ttl`, `idle_timeout` and `drain_timeout` must not be zero
+    /// - `on_drop` must be `Detach` with a `restart_policy` or `lazy_start`
+    /// - `sockets` must be absolute paths, with distinct container paths
+    ///   other than `/`
+    /// - `reverse_ports` must have distinct non-zero box ports, and host
+    ///   targets that are a port, `host:port` or an absolute socket path
+    /// - `hostname` must be dot-separated labels of letters, digits and
+    ///   `-`, up to 64 characters
+    /// - `ports` host IPs and `dns` servers must be IP addresses
+    /// - `ports`, `network_policy`, `network_rate_limit` and `ipv6` need a
+    ///   network (not `NetworkSpec::None`)
+    /// - `network_rate_limit` rates must be at least 1, and need the gvproxy
+    ///   `network_backend`
+    /// - `lazy_start` needs `ports` and the gvproxy `network_backend`
+    /// - `drain_timeout` needs `ports` and the gvproxy `network_backend` in
+    ///   the User `network_mode`
+    /// - `network_mode` Host needs Linux and a network, without
+    ///   `network_policy`, `network_rate_limit`, `ipv6`, `lazy_start` or
+    ///   IPv6 `ports` host IPs
+    /// - `dns_search` domains must be non-empty and without whitespace
+    /// - `dns_cache` needs the default `dns`, a network and the gvproxy
+    ///   `network_backend` in the User `network_mode`
+    /// - `extra_hosts` must be `host:ip` with an IP address or `host-gateway`
+    /// - `network_policy` hosts must be domains, IPs or CIDR blocks, and
+    ///   domain rules need the default `dns` and the gvproxy `network_backend`
+    /// - `networks` must be valid network names, each listed once
+    /// - `http_proxy`, `https_proxy` and `no_proxy` must be non-empty and
+    ///   without whitespace
+    /// - `cpu_features` masking needs an x86_64 host
+    pub fn sanitize(&self) -> BoxliteResult<()> {
+        // Validate auto_remove + detach combination
+        // A detached box that auto-removes doesn't make practical sense:
+        // - detach=true: box survives parent exit
+        // - auto_remove=true: box removed on stop
+        // This combination is confusing - detached boxes should have manual lifecycle control
+        if self.auto_remove && self.detach {
+            return Err(boxlite_shared::errors::BoxliteError::Config(
+                "auto_remove=true is incompatible with detach=true. \
+                 Detached boxes should use auto_remove=false for manual lifecycle control."
+                    .to_string(),
+            ));
+        }
+
+        // The supervisor restarts boxes through handles it drops right away
+        if self.on_drop != DropBehavior::Detach && self.restart_policy.mode != RestartMode::No {
+            return Err(boxlite_shared::errors::BoxliteError::Config(format!(
+                "on_drop={:?} is incompatible with a restart_policy",
+                self.on_drop
+            )));
+        }
+        // Likewise lazy starts, through handles held for a connection
+        if self.on_drop != DropBehavior::Detach && self.lazy_start {
+            return Err(boxlite_shared::errors::BoxliteError::Config(format!(
+                "on_drop={:?} is incompatible with lazy_start",
+                self.on_drop
+            )));
+        }
+
+        for volume in &self.volumes {
+            volume.sanitize()?;
+        }
+        for quota in &self.disk_quotas {
+            quota.sanitize(&self.volumes)?;
+        }
+
+        for (i, socket) in self.sockets.iter().enumerate() {
+            let guest_path = std::path::Path::new(&socket.guest_path);
+            if !std::path::Path::new(&socket.host_path).is_absolute()
+                || !guest_path.is_absolute()
+                || guest_path.file_name().is_none()
+            {
+                return Err(boxlite_shared::errors::BoxliteError::Config(format!(
+                    "socket paths must be absolute files: {} -> {}",
+                    socket.host_path, socket.guest_path
+                )));
+            }
+            if self.sockets[..i]
+                .iter()
+                .any(|s| s.guest_path == socket.guest_path)
+            {
+                return Err(boxlite_shared::errors::BoxliteError::Config(format!(
+                    "socket path {} is listed twice",
+                    socket.guest_path
+                )));
+            }
+        }
+
+        for (i, reverse) in self.reverse_ports.iter().enumerate() {
+            if reverse.guest_port == 0 {
+                return Err(boxlite_shared::errors::BoxliteError::Config(
+                    "reverse_ports box port must not be 0".to_string(),
+                ));
+            }
+            if self.reverse_ports[..i]
+                .iter()
+                .any(|r| r.guest_port == reverse.guest_port)
+            {
+                return Err(boxlite_shared::errors::BoxliteError::Config(format!(
+                    "reverse port {} is listed twice",
+                    reverse.guest_port
+                )));
+            }
+            reverse.target()?;
+        }
+
+        if self.network == NetworkSpec::None
+            && (!self.ports.is_empty()
+                || !self.network_policy.is_open()
+                || !self.network_rate_limit.is_unlimited()
+                || self.ipv6)
+        {
+            return Err(boxlite_shared::errors::BoxliteError::Config(
+                "ports, network_policy, network_rate_limit and ipv6 need a network; network is None"
+                    .to_string(),
+            ));
+        }
+
+        for port in &self.ports {
+            if let Some(ip) = &port.host_ip
+                && ip.parse::<std::net::IpAddr>().is_err()
+            {
+                return Err(boxlite_shared::errors::BoxliteError::Config(format!(
+                    "port {} host_ip is not an IP address: {}",
+                    port.guest_port, ip
+                )));
+            }
+        }
+
+        if let Some(hostname) = &self.hostname
+            && !is_valid_hostname(hostname)
+        {
+            return Err(boxlite_shared::errors::BoxliteError::Config(format!(
+                "invalid hostname: {:?}",
+                hostname
+            )));
+        }
+        for server in &self.dns {
+            if server.parse::<std::net::IpAddr>().is_err() {
+                return Err(boxlite_shared::errors::BoxliteError::Config(format!(
+                    "dns server is not an IP address: {}",
+                    server
+                )));
+            }
+        }
+        for domain in &self.dns_search {
+            if domain.is_empty() || domain.contains(char::is_whitespace) {
+                return Err(boxlite_shared::errors::BoxliteError::Config(format!(
+                    "invalid dns_search domain: {:?}",
+                    domain
+                )));
+            }
+        }
+
+        self.host_entries()?;
+        self.network_policy.sanitize()?;
+        if (!self.network_policy.is_open() || !self.network_rate_limit.is_unlimited())
+            && self.network_backend != NetworkBackendKind::Gvproxy
+        {
+            return Err(boxlite_shared::errors::BoxliteError::Config(format!(
+                "network_policy and network_rate_limit are not supported by the {:?} \
+                 network_backend",
+                self.network_backend
+            )));
+        }
+        if self.network_rate_limit.ingress_bps == Some(0)
+            || self.network_rate_limit.egress_bps == Some(0)
+        {
+            return Err(boxlite_shared::errors::BoxliteError::Config(
+                "network_rate_limit rates must be at least 1 bit/s".to_string(),
+            ));
+        }
+        if self.network_mode == NetworkMode::Host {
+            if !cfg!(target_os = "linux") {
+                return Err(boxlite_shared::errors::BoxliteError::Unsupported(
+                    "network_mode Host is only supported on Linux".to_string(),
+                ));
+            }
+            if self.network == NetworkSpec::None
+                || !self.network_policy.is_open()
+                || !self.network_rate_limit.is_unlimited()
+                || self.ipv6
+                || self.lazy_start
+            {
+                return Err(boxlite_shared::errors::BoxliteError::Config(
+                    "network_mode Host needs a network, and supports no network_policy, \
+                     network_rate_limit, ipv6 or lazy_start"
+                        .to_string(),
+                ));
+            }
+            if let Some(port) = self.ports.iter().find(|port| {
+                port.host_ip
+                    .as_deref()
+                    .is_some_and(|ip| ip.parse::<std::net::Ipv4Addr>().is_err())
+            }) {
+                return Err(boxlite_shared::errors::BoxliteError::Config(format!(
+                    "network_mode Host publishes ports on IPv4 only, port {} has host_ip {:?}",
+                    port.guest_port, port.host_ip
+                )));
+            }
+        }
+        if self.lazy_start
+            && (self.ports.is_empty() || self.network_backend != NetworkBackendKind::Gvproxy)
+        {
+            return Err(boxlite_shared::errors::BoxliteError::Config(format!(
+                "lazy_start needs ports and the Gvproxy network_backend, got {} ports and {:?}",
+                self.ports.len(),
+                self.network_backend
+            )));
+        }
+        if self.drain_timeout.is_some()
+            && (self.ports.is_empty()
+                || self.network_backend != NetworkBackendKind::Gvproxy
+                || self.network_mode != NetworkMode::User)
+        {
+            return Err(boxlite_shared::errors::BoxliteError::Config(
+                "drain_timeout needs ports and the Gvproxy network_backend in the User \
+                 network_mode"
+                    .to_string(),
+            ));
+        }
+        if let Some(cache) = &self.dns_cache {
+            if !self.dns.is_empty()
+                || self.network == NetworkSpec::None
+                || self.network_backend != NetworkBackendKind::Gvproxy
+                || self.network_mode != NetworkMode::User
+            {
+                return Err(boxlite_shared::errors::BoxliteError::Config(
+                    "dns_cache needs the gateway DNS (empty dns), a network, and the Gvproxy \
+                     network_backend in the User network_mode"
+                        .to_string(),
+                ));
+            }
+            cache.sanitize()?;
+        }
+        if !self.dns.is_empty() && self.network_policy.has_domain_rules() {
+            return Err(boxlite_shared::errors::BoxliteError::Config(
+                "network_policy domain rules need the gateway DNS; leave dns empty".to_string(),
+            ));
+        }
+
+        for (i, network) in self.networks.iter().enumerate() {
+            sanitize_network_name(network)?;
+            if self.networks[..i].contains(network) {
+                return Err(boxlite_shared::errors::BoxliteError::Config(format!(
+                    "network {:?} is listed twice",
+                    network
+                )));
+            }
+        }
+
+        for (name, value) in [
+            ("http_proxy", &self.http_proxy),
+            ("https_proxy", &self.https_proxy),
+            ("no_proxy", &self.no_proxy),
+        ] {
+            if let Some(value) = value
+                && (value.is_empty() || value.contains(char::is_whitespace))
+            {
+                return Err(boxlite_shared::errors::BoxliteError::Config(format!(
+                    "{} must be non-empty and without whitespace, got {:?}",
+                    name, value
+                )));
+            }
+        }
+
+        if self.max_concurrent_execs == Some(0) {
+            return Err(boxlite_shared::errors::BoxliteError::Config(
+                "max_concurrent_execs must be at least 1".to_string(),
+            ));
+        }
+        if self.max_text_file_size == 0 {
+            return Err(boxlite_shared::errors::BoxliteError::Config(
+                "max_text_file_size must be at least 1".to_string(),
+            ));
+        }
+        self.exec_buffer.sanitize()?;
+        if self.ttl == Some(Duration::ZERO) {
+            return Err(boxlite_shared::errors::BoxliteError::Config(
+                "ttl must be greater than zero".to_string(),
+            ));
+        }
+        if self.idle_timeout == Some(Duration::ZERO) {
+            return Err(boxlite_shared::errors::BoxliteError::Config(
+                "idle_timeout must be greater than zero".to_string(),
+            ));
+        }
+        if self.drain_timeout == Some(Duration::ZERO) {
+            return Err(boxlite_shared::errors::BoxliteError::Config(
+                "drain_timeout must be greater than zero".to_string(),
+            ));
+        }
+
+        self.cpu_features.sanitize()?;
+
+        #[cfg(not(target_os = "linux"))]
+        if self.isolate_mounts {
+            return Err(boxlite_shared::errors::BoxliteError::Unsupported(
+                "isolate_mounts is only supported on Linux".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Read `env_files` into `env`, so the box keeps the values it was
+    /// created with.
+    pub(crate) fn resolve_env_files(mut self) -> BoxliteResult<Self> {
+        if !self.env_files.is_empty() {
+            let files = std::mem::take(&mut self.env_files);
+            self.env = crate::util::env_file::merge(&files, std::mem::take(&mut self.env))?;
+        }
+        Ok(self)
+    }
+
+    /// Host-to-guest port forwards, sorted by host port: the image's
+    /// `exposed` TCP ports 1:1 on all interfaces, then `ports`, which
+    /// replace the image's mapping of the same guest port. Host port 0
+    /// (to be picked at start) is kept for each port asking for it. None
+    /// without a network.
+    pub(crate) fn port_mappings(&self, exposed: &[u16]) -> Vec<PortMapping> {
+        if self.network == NetworkSpec::None {
+            return Vec::new();
+        }
+        let user_guest_ports: std::collections::HashSet<u16> =
+            self.ports.iter().map(|p| p.guest_port).collect();
+
+        // One forward per host address and port
+        let mut port_map: HashMap<(u16, Option<IpAddr>), u16> = exposed
+            .iter()
+            .filter(|port| !user_guest_ports.contains(port))
+            .map(|&port| ((port, None), port))
+            .collect();
+        let mut ephemeral = Vec::new();
+        for port in &self.ports {
+            let host_port = port.host_port.unwrap_or(port.guest_port);
+            let host_ip = port.host_ip.as_deref().and_then(|ip| ip.parse().ok());
+            if host_port == 0 {
+                ephemeral.push(PortMapping {
+                    host_ip,
+                    host_port,
+                    guest_port: port.guest_port,
+                });
+            } else {
+                port_map.insert((host_port, host_ip), port.guest_port);
+            }
+        }
+
+        let mut mappings: Vec<PortMapping> = port_map
+            .into_iter()
+            .map(|((host_port, host_ip), guest_port)| PortMapping {
+                host_ip,
+                host_port,
+                guest_port,
+            })
+            .chain(ephemeral)
+            .collect();
+        mappings.sort_unstable_by_key(|m| (m.host_port, m.host_ip));
+        mappings
+    }
+
+    /// `extra_hosts` as (host, IP) pairs, with `host-gateway` resolved.
+    pub(crate) fn host_entries(&self) -> BoxliteResult<Vec<(String, IpAddr)>> {
+        self.extra_hosts
+            .iter()
+            .map(|entry| {
+                let invalid = || {
+                    BoxliteError::Config(format!(
+                        "extra_hosts entry must be host:ip, got {:?}",
+                        entry
+                    ))
+                };
+                let (host, ip) = entry.split_once(':').ok_or_else(invalid)?;
+                if host.is_empty() || host.contains(char::is_whitespace) {
+                    return Err(invalid());
+                }
+                let ip = if ip == HOST_GATEWAY {
+                    crate::net::constants::HOST_IP
+                } else {
+                    ip
+                };
+                let ip = ip.parse().map_err(|_| invalid())?;
+                Ok((host.to_string(), ip))
+            })
+            .collect()
+    }
+
+    /// Environment the container is started with: `env`, plus the
+    /// `GLIBC_TUNABLES` entry for `cpu_features` and the proxy variables
+    /// `env` does not set.
+    pub(crate) fn container_env(&self) -> Vec<(String, String)> {
+        const GLIBC_TUNABLES: &str = "GLIBC_TUNABLES";
+
+        let mut env = self.env.clone();
+        for (key, value) in self.proxy_env(|key| std::env::var(key).ok()) {
+            if !env.iter().any(|(k, _)| *k == key) {
+                env.push((key, value));
+            }
+        }
+        if let Some(tunable) = self.cpu_features.glibc_tunable() {
+            match env.iter_mut().find(|(key, _)| key == GLIBC_TUNABLES) {
+                Some((_, value)) => {
+                    value.push(':');
+                    value.push_str(&tunable);
+                }
+                None => env.push((GLIBC_TUNABLES.to_string(), tunable)),
+            }
+        }
+        env
+    }
+
+    /// Proxy variables in both spellings, with the host's looked up through
+    /// `host_var` for settings not given when `inherit_proxy` is set.
+    fn proxy_env(&self, host_var: impl Fn(&str) -> Option<String>) -> Vec<(String, String)> {
+        let settings = [
+            ("http_proxy", &self.http_proxy),
+            ("https_proxy", &self.https_proxy),
+            ("no_proxy", &self.no_proxy),
+        ];
+
+        let mut env = Vec::new();
+        for (name, value) in settings {
+            let upper = name.to_uppercase();
+            let value = value.clone().or_else(|| {
+                if self.inherit_proxy {
+                    host_var(name).or_else(|| host_var(&upper))
+                } else {
+                    None
+                }
+            });
+            if let Some(value) = value.filter(|v| !v.is_empty()) {
+                env.push((name.to_string(), value.clone()));
+                env.push((upper, value));
+            }
+        }
+        env
+    }
+}
+
+/// How to populate the box root filesystem.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub enum RootfsSpec {
+    /// Pull/resolve this registry image reference.
+    Image(String),
+    /// Use an already prepared rootfs at the given host path.
+    RootfsPath(String),
+}
+
+impl Default for RootfsSpec {
+    fn default() -> Self {
+        Self::Image("alpine:latest".into())
+    }
+}
+
+/// Host Unix socket exposed in the container.
+///
+/// Connections to `guest_path` in the container are relayed to `host_path`,
+/// which only needs to be listening when they are made.
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct SocketSpec {
+    pub host_path: String,
+    pub guest_path: String,
+}
+
+/// Host service reachable from the box, see [`BoxOptions::reverse_ports`].
+///
+/// Connections to `127.0.0.1:guest_port` in the box are relayed to `host`,
+/// which only needs to be listening when they are made.
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ReversePortSpec {
+    pub guest_port: u16,
+    /// A port on the host's loopback (`8080`), an address (`10.0.0.5:8080`,
+    /// `db.lan:5432`) or a Unix socket path (`/run/app.sock`).
+    pub host: String,
+}
+
+impl ReversePortSpec {
+    /// Where the host side of the relay connects.
+    pub(crate) fn target(&self) -> BoxliteResult<crate::net::reverse::ReverseTarget> {
+        use crate::net::reverse::ReverseTarget;
+
+        let invalid = || {
+            boxlite_shared::errors::BoxliteError::Config(format!(
+                "reverse port host must be a port, host:port or an absolute socket path: {}",
+                self.host
+            ))
+        };
+        if self.host.starts_with('/') {
+            return Ok(ReverseTarget::Unix(PathBuf::from(&self.host)));
+        }
+        if let Ok(port) = self.host.parse::<u16>() {
+            return match port {
+                0 => Err(invalid()),
+                _ => Ok(ReverseTarget::Tcp(format!("127.0.0.1:{}", port))),
+            };
+        }
+        let (host, port) = self.host.rsplit_once(':').ok_or_else(invalid)?;
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        match port.parse::<u16>() {
+            Ok(port) if port != 0 && !host.is_empty() && !host.contains(char::is_whitespace) => {
+                Ok(ReverseTarget::Tcp(self.host.clone()))
+            }
+            _ => Err(invalid()),
+        }
+    }
+}
+
+/// Filesystem mount specification.
+///
+/// Was a struct with the fields of `Bind` before tmpfs volumes; code
+/// building one now uses [`VolumeSpec::bind`] or the `Bind` variant.
+///
+/// Serialized without a tag, so configs from before tmpfs volumes still
+/// read as binds. Fields are matched exactly: an unknown field, or a
+/// `guest_path` alone with bind fields, is an error rather than a tmpfs.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(untagged, from = "VolumeSpecRepr")]
+pub enum VolumeSpec {
+    /// Host directory or named volume shared with the container.
+    Bind {
+        /// Host directory or file, or the name of a volume created through
+        /// [`Volumes::create`](crate::Volumes::create) when it has no `/`,
+        /// like docker's `-v name:/path`. A file is hard-linked into a
+        /// directory of the box and shared from there, so the VM sees no other
+        /// host file; it must be on the same filesystem as the boxlite home
+        /// unless `read_only`, which copies it otherwise.
+        host_path: String,
+        guest_path: String,
+        read_only: bool,
+    },
+    /// Fresh tmpfs in the container, for scratch space and secrets that
+    /// must never touch the host disk. Its pages count against the box's
+    /// memory.
+    Tmpfs {
+        guest_path: String,
+        /// Size limit in bytes. `None` uses the kernel default (half of
+        /// the box's memory).
+        size: Option<u64>,
+        /// Permission bits of the mount's root, e.g. `0o700`. `None` uses
+        /// `0o1777`.
+        mode: Option<u32>,
+    },
+}
+
+/// Serialized [`VolumeSpec`], told apart by its exact fields.
+#[derive(serde::Deserialize)]
+#[serde(untagged)]
+enum VolumeSpecRepr {
+    Bind(BindRepr),
+    Tmpfs(TmpfsRepr),
+}
+
+#[derive(serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BindRepr {
+    host_path: String,
+    guest_path: String,
+    #[serde(default)]
+    read_only: bool,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TmpfsRepr {
+    guest_path: String,
+    #[serde(default)]
+    size: Option<u64>,
+    #[serde(default)]
+    mode: Option<u32>,
+}
+
+impl From<VolumeSpecRepr> for VolumeSpec {
+    fn from(repr: VolumeSpecRepr) -> Self {
+        match repr {
+            VolumeSpecRepr::Bind(b) => Self::Bind {
+                host_path: b.host_path,
+                guest_path: b.guest_path,
+                read_only: b.read_only,
+            },
+            VolumeSpecRepr::Tmpfs(t) => Self::Tmpfs {
+                guest_path: t.guest_path,
+                size: t.size,
+                mode: t.mode,
+            },
+        }
+    }
+}
+
+impl VolumeSpec {
+    /// Bind `host_path` at `guest_path` in the container.
+    pub fn bind(
+        host_path: impl Into<String>,
+        guest_path: impl Into<String>,
+        read_only: bool,
+    ) -> Self {
+        Self::Bind {
+            host_path: host_path.into(),
+            guest_path: guest_path.into(),
+            read_only,
+        }
+    }
+
+    /// Mount a tmpfs with the default size and mode at `guest_path`.
+    pub fn tmpfs(guest_path: impl Into<String>) -> Self {
+        Self::Tmpfs {
+            guest_path: guest_path.into(),
+            size: None,
+            mode: None,
+        }
+    }
+
+    /// Path of the mount in the container.
+    pub fn guest_path(&self) -> &str {
+        match self {
+            Self::Bind { guest_path, .. } | Self::Tmpfs { guest_path, .. } => guest_path,
+        }
+    }
+
+    /// The named volume this mounts, if `host_path` names one.
+    pub fn volume_name(&self) -> Option<&str> {
+        match self {
+            Self::Bind { host_path, .. } => {
+                (!host_path.contains('/')).then_some(host_path.as_str())
+            }
+            Self::Tmpfs { .. } => None,
+        }
+    }
+
+    fn sanitize(&self) -> BoxliteResult<()> {
+        match self {
+            Self::Bind { .. } => {
+                if let Some(name) = self.volume_name() {
+                    sanitize_volume_name(name)?;
+                }
+            }
+            Self::Tmpfs {
+                guest_path,
+                size,
+                mode,
+            } => {
+                let path = std::path::Path::new(guest_path);
+                if !path.is_absolute() || path.parent().is_none() {
+                    return Err(BoxliteError::Config(format!(
+                        "tmpfs path must be an absolute directory other than /: {}",
+                        guest_path
+                    )));
+                }
+                if *size == Some(0) {
+                    return Err(BoxliteError::Config(format!(
+                        "tmpfs size of {} must be at least 1 byte",
+                        guest_path
+                    )));
+                }
+                if mode.is_some_and(|mode| mode > 0o7777) {
+                    return Err(BoxliteError::Config(format!(
+                        "tmpfs mode of {} must be permission bits (at most 0o7777)",
+                        guest_path
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// How [`Volumes::create`](crate::Volumes::create) backs a volume.
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct VolumeOptions {
+    /// Back the volume with a dedicated ext4 disk image of this size
+    /// instead of a host directory. A disk is faster for many small files
+    /// and keeps the box's writes off the host filesystem, but only one
+    /// running box can mount it at a time. `None` (default) uses a
+    /// directory.
+    #[serde(default)]
+    pub disk_size_gb: Option<u64>,
+}
+
+impl VolumeOptions {
+    /// Validate the disk size.
+    pub fn sanitize(&self) -> BoxliteResult<()> {
+        if self.disk_size_gb == Some(0) {
+            return Err(BoxliteError::Config(
+                "volume disk_size_gb must be at least 1".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Write quota on the container rootfs.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct DiskQuota {
+    pub target: QuotaTarget,
+    /// Hard limit on the blocks used by the target.
+    pub limit_bytes: u64,
+}
+
+/// What a [`DiskQuota`] applies to.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum QuotaTarget {
+    /// Everything under this absolute container directory (created if missing).
+    Path(String),
+    /// Everything owned by this user ID inside the container.
+    User(u32),
+}
+
+impl DiskQuota {
+    /// Limit a container directory.
+    pub fn path(path: impl Into<String>, limit_bytes: u64) -> Self {
+        Self {
+            target: QuotaTarget::Path(path.into()),
+            limit_bytes,
+        }
+    }
+
+    /// Limit a container user.
+    pub fn user(uid: u32, limit_bytes: u64) -> Self {
+        Self {
+            target: QuotaTarget::User(uid),
+            limit_bytes,
+        }
+    }
+
+    fn sanitize(&self, volumes: &[VolumeSpec]) -> BoxliteResult<()> {
+        use boxlite_shared::errors::BoxliteError;
+        use std::path::{Component, Path};
+
+        if self.limit_bytes == 0 {
+            return Err(BoxliteError::Config(format!(
+                "disk quota for {:?} must have a non-zero limit",
+                self.target
+            )));
+        }
+
+        let QuotaTarget::Path(path) = &self.target else {
+            return Ok(());
+        };
+
+        let path = Path::new(path);
+        let mut components = path.components();
+        if components.next() != Some(Component::RootDir)
+            || components.clone().next().is_none()
+            || !components.all(|c| matches!(c, Component::Normal(_)))
+        {
+            return Err(BoxliteError::Config(format!(
+                "disk quota path must be an absolute directory below /: {}",
+                path.display()
+            )));
+        }
+
+        // Volumes are mounted over the rootfs, the quota would not see their writes
+        if let Some(volume) = volumes.iter().find(|v| {
+            path.starts_with(v.guest_path()) || Path::new(v.guest_path()).starts_with(path)
+        }) {
+            return Err(BoxliteError::Config(format!(
+                "disk quota path {} overlaps volume {}",
+                path.display(),
+                volume.guest_path()
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Longest name of an inter-box network.
+const MAX_NETWORK_NAME_LEN: usize = 32;
+
+/// Check the name of an inter-box network, which also names its socket
+/// directory: letters, digits, `_`, `-` and `.`, not starting with `.`.
+pub(crate) fn sanitize_network_name(name: &str) -> BoxliteResult<()> {
+    let valid = !name.is_empty()
+        && name.len() <= MAX_NETWORK_NAME_LEN
+        && !name.starts_with('.')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'));
+    if !valid {
+        return Err(BoxliteError::Config(format!(
+            "invalid network name {:?}: use up to {} letters, digits, '_', '-' or '.'",
+            name, MAX_NETWORK_NAME_LEN
+        )));
+    }
+    Ok(())
+}
+
+/// Longest name of a volume.
+const MAX_VOLUME_NAME_LEN: usize = 64;
+
+/// Check the name of a volume, which also names its directory: letters,
+/// digits, `_`, `-` and `.`, starting with a letter or digit.
+pub(crate) fn sanitize_volume_name(name: &str) -> BoxliteResult<()> {
+    let valid = name.len() <= MAX_VOLUME_NAME_LEN
+        && name.starts_with(|c: char| c.is_ascii_alphanumeric())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'));
+    if !valid {
+        return Err(BoxliteError::Config(format!(
+            "invalid volume name {:?}: use up to {} letters, digits, '_', '-' or '.', \
+             starting with a letter or digit",
+            name, MAX_VOLUME_NAME_LEN
+        )));
+    }
+    Ok(())
+}
+
+/// Longest hostname (the kernel's `HOST_NAME_MAX`).
+const MAX_HOSTNAME_LEN: usize = 64;
+
+/// Whether `name` is a valid hostname: dot-separated labels of letters,
+/// digits and `-`, not starting or ending with `-`.
+pub(crate) fn is_valid_hostname(name: &str) -> bool {
+    name.len() <= MAX_HOSTNAME_LEN
+        && name.split('.').all(|label| {
+            !label.is_empty()
+                && label.len() <= 63
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        })
+}
+
+/// Network isolation options.
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum NetworkSpec {
+    #[default]
+    Isolated,
+    /// No network device and no network backend: the box only has loopback.
+    None,
+    // Host,
+    // Custom(String),
+}
+
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+pub enum PortProtocol {
+    #[default]
+    Tcp,
+    Udp,
+    // Sctp,
+}
+
+fn default_protocol() -> PortProtocol {
+    PortProtocol::Tcp
+}
+
+/// Port mapping specification (host -> guest).
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+pub struct PortSpec {
+    /// Host port to listen on; the guest port if None. `Some(0)` picks a
+    /// free port each time the box starts, reported in `BoxInfo::ports`.
+    pub host_port: Option<u16>,
+    pub guest_port: u16,
+    #[serde(default = "default_protocol")]
+    pub protocol: PortProtocol,
+    /// Host address to listen on, e.g. `127.0.0.1` to publish the port to
+    /// this host only. All interfaces if None.
+    pub host_ip: Option<String>,
+}
+
+/// Bandwidth cap of a box, see [`BoxOptions::network_rate_limit`].
+///
+/// Rates are in bits per second; None is unlimited. Short bursts of up to
+/// a tenth of a second of traffic pass at full speed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct NetworkRateLimit {
+    /// Traffic to the box
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ingress_bps: Option<u64>,
+    /// Traffic from the box
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub egress_bps: Option<u64>,
+}
+
+impl NetworkRateLimit {
+    /// Whether neither direction is capped.
+    pub fn is_unlimited(&self) -> bool {
+        self.ingress_bps.is_none() && self.egress_bps.is_none()
+    }
+}
+
+/// Network backend parameters, see [`BoxliteOptions::network_tuning`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct NetworkTuning {
+    /// MTU of the box's main interface and the backend's virtual network,
+    /// 1280 to 65535. Larger frames cut per-packet overhead on bulk
+    /// transfers; traffic leaving the host is segmented as usual.
+    #[serde(default = "default_mtu")]
+    pub mtu: u16,
+    /// Send and receive buffer size, in bytes, of the backend's end of the
+    /// socket carrying the box's frames (gvproxy only). None keeps the
+    /// system default.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub socket_buffer_bytes: Option<u32>,
+}
+
+impl Default for NetworkTuning {
+    fn default() -> Self {
+        Self {
+            mtu: default_mtu(),
+            socket_buffer_bytes: None,
+        }
+    }
+}
+
+fn default_mtu() -> u16 {
+    crate::net::constants::DEFAULT_MTU
+}
+
+impl NetworkTuning {
+    /// Smallest MTU, the IPv6 minimum.
+    const MIN_MTU: u16 = 1280;
+    /// Largest socket buffer, well past what the kernel grants by default.
+    const MAX_SOCKET_BUFFER_BYTES: u32 = 64 * 1024 * 1024;
+
+    /// Validate the parameters.
+    pub fn sanitize(&self) -> BoxliteResult<()> {
+        if self.mtu < Self::MIN_MTU {
+            return Err(BoxliteError::InvalidArgument(format!(
+                "network_tuning.mtu must be at least {}, got {}",
+                Self::MIN_MTU,
+                self.mtu
+            )));
+        }
+        if let Some(bytes) = self.socket_buffer_bytes
+            && (bytes == 0 || bytes > Self::MAX_SOCKET_BUFFER_BYTES)
+        {
+            return Err(BoxliteError::InvalidArgument(format!(
+                "network_tuning.socket_buffer_bytes must be between 1 and {}, got {}",
+                Self::MAX_SOCKET_BUFFER_BYTES,
+                bytes
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Caching resolver of a box's gateway, see [`BoxOptions::dns_cache`].
+///
+/// Answers are kept for their TTL, up to an hour, and negative answers for
+/// the TTL of their zone's SOA record.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct DnsCache {
+    /// Servers the cache resolves through, as `ip` or `ip:port`, tried in
+    /// order. Empty (default) uses the host's resolvers.
+    #[serde(default)]
+    pub upstreams: Vec<String>,
+    /// Most answers kept; the least recently used are dropped first.
+    #[serde(default = "default_dns_cache_entries")]
+    pub max_entries: u32,
+}
+
+impl Default for DnsCache {
+    fn default() -> Self {
+        Self {
+            upstreams: Vec::new(),
+            max_entries: default_dns_cache_entries(),
+        }
+    }
+}
+
+fn default_dns_cache_entries() -> u32 {
+    4096
+}
+
+impl DnsCache {
+    /// Validate the upstreams and size.
+    pub fn sanitize(&self) -> BoxliteResult<()> {
+        if self.max_entries == 0 {
+            return Err(BoxliteError::Config(
+                "dns_cache.max_entries must be at least 1".to_string(),
+            ));
+        }
+        self.upstream_addrs().map(|_| ())
+    }
+
+    /// The upstreams as socket addresses, port 53 unless given.
+    pub(crate) fn upstream_addrs(&self) -> BoxliteResult<Vec<SocketAddr>> {
+        self.upstreams
+            .iter()
+            .map(|upstream| {
+                upstream
+                    .parse::<SocketAddr>()
+                    .or_else(|_| upstream.parse::<IpAddr>().map(|ip| SocketAddr::new(ip, 53)))
+                    .map_err(|_| {
+                        BoxliteError::Config(format!(
+                            "dns_cache upstream must be an IP address or ip:port: {}",
+                            upstream
+                        ))
+                    })
+            })
+            .collect()
+    }
+}
+
+/// Egress filter of a box, see [`BoxOptions::network_policy`].
+///
+/// A packet is let through when no `deny` rule matches its destination,
+/// and `allow` is empty or one of its rules matches.
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct NetworkPolicy {
+    #[serde(default)]
+    pub allow: Vec<EgressRule>,
+    #[serde(default)]
+    pub deny: Vec<EgressRule>,
+}
+
+impl NetworkPolicy {
+    /// Only allow the destinations of `rules`.
+    pub fn allow_only(rules: impl IntoIterator<Item = EgressRule>) -> Self {
+        Self {
+            allow: rules.into_iter().collect(),
+            deny: Vec::new(),
+        }
+    }
+
+    /// Whether the policy lets all traffic through.
+    pub fn is_open(&self) -> bool {
+        self.allow.is_empty() && self.deny.is_empty()
+    }
+
+    fn has_domain_rules(&self) -> bool {
+        self.allow
+            .iter()
+            .chain(&self.deny)
+            .any(|rule| rule.network().is_none())
+    }
+
+    fn sanitize(&self) -> BoxliteResult<()> {
+        for rule in self.allow.iter().chain(&self.deny) {
+            rule.sanitize()?;
+        }
+        Ok(())
+    }
+}
+
+/// Destination matched by a [`NetworkPolicy`] rule.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct EgressRule {
+    /// Domain (`pypi.org`, or `*.pythonhosted.org` for its subdomains),
+    /// IP address or CIDR block (`10.0.0.0/8`).
+    pub host: String,
+    /// Destination ports; empty matches any port.
+    #[serde(default)]
+    pub ports: Vec<u16>,
+}
+
+impl EgressRule {
+    /// Match `host` on any port.
+    pub fn host(host: impl Into<String>) -> Self {
+        Self {
+            host: host.into(),
+            ports: Vec::new(),
+        }
+    }
+
+    /// Only match these destination ports.
+    pub fn with_ports(mut self, ports: impl IntoIterator<Item = u16>) -> Self {
+        self.ports = ports.into_iter().collect();
+        self
+    }
+
+    /// Address and prefix length of an IP or CIDR `host`, None for a domain.
+    fn network(&self) -> Option<(IpAddr, Option<&str>)> {
+        let (addr, prefix) = match self.host.split_once('/') {
+            Some((addr, prefix)) => (addr, Some(prefix)),
+            None => (self.host.as_str(), None),
+        };
+        addr.parse().ok().map(|addr| (addr, prefix))
+    }
+
+    fn sanitize(&self) -> BoxliteResult<()> {
+        let invalid = || {
+            BoxliteError::Config(format!(
+                "network_policy host must be a domain, IP or CIDR: {:?}",
+                self.host
+            ))
+        };
+        if let Some((addr, prefix)) = self.network() {
+            let bits = if addr.is_ipv4() { 32 } else { 128 };
+            if let Some(prefix) = prefix
+                && !prefix.parse::<u8>().is_ok_and(|p| p <= bits)
+            {
+                return Err(invalid());
+            }
+            return Ok(());
+        }
+        let domain = self.host.strip_prefix("*.").unwrap_or(&self.host);
+        let valid_label = |label: &str| {
+            !label.is_empty()
+                && label
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        };
+        if !domain.trim_end_matches('.').split('.').all(valid_label) {
+            return Err(invalid());
+        }
+        Ok(())
+    }
 }
 
-impl Default for BoxliteOptions {
-    fn default() -> Self {
-        let home_dir = std::env::var(const_envs::BOXLITE_HOME)
-            .map(PathBuf::from)
-            .unwrap_or_else(|_| {
-                let mut path = home_dir().unwrap_or_else(|| PathBuf::from("."));
-                path.push(const_dirs::BOXLITE_DIR);
-                path
-            });
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_memory_policy_defaults() {
+        let policy: MemoryPolicy = serde_json::from_str("{}").unwrap();
+        assert_eq!(policy, MemoryPolicy::default());
+        assert!(!policy.ksm);
+        assert_eq!(policy.thp, ThpPolicy::System);
+        assert!(policy.sanitize().is_ok());
+    }
+
+    #[test]
+    fn test_memory_policy_roundtrip() {
+        let policy = MemoryPolicy {
+            ksm: true,
+            thp: ThpPolicy::Never,
+        };
+
+        let json = serde_json::to_string(&policy).unwrap();
+        let policy2: MemoryPolicy = serde_json::from_str(&json).unwrap();
+        assert_eq!(policy, policy2);
+    }
+
+    #[test]
+    fn test_network_tuning_sanitize() {
+        let tuning: NetworkTuning = serde_json::from_str("{}").unwrap();
+        assert_eq!(tuning, NetworkTuning::default());
+        assert_eq!(tuning.mtu, 1500);
+        assert!(tuning.sanitize().is_ok());
+
+        let tuning = |mtu, socket_buffer_bytes| NetworkTuning {
+            mtu,
+            socket_buffer_bytes,
+        };
+        assert!(tuning(65535, Some(4 * 1024 * 1024)).sanitize().is_ok());
+        assert!(tuning(576, None).sanitize().is_err());
+        assert!(tuning(1500, Some(0)).sanitize().is_err());
+        assert!(tuning(1500, Some(u32::MAX)).sanitize().is_err());
+    }
+
+    #[test]
+    fn test_box_options_defaults() {
+        let opts = BoxOptions::default();
+        assert!(opts.auto_remove, "auto_remove should default to true");
+        assert!(!opts.detach, "detach should default to false");
+    }
+
+    #[test]
+    fn test_box_defaults_apply() {
+        let volume = |guest_path: &str| VolumeSpec::bind("/etc/ssl", guest_path, true);
+        let defaults = BoxDefaults {
+            env: vec![
+                ("HTTPS_PROXY".to_string(), "http://proxy:3128".to_string()),
+                ("TEAM".to_string(), "platform".to_string()),
+            ],
+            volumes: vec![
+                volume("/etc/ssl/certs"),
+                volume("/opt/shared"),
+                VolumeSpec::tmpfs("/scratch"),
+            ],
+            labels: HashMap::from([
+                ("org".to_string(), "acme".to_string()),
+                ("team".to_string(), "platform".to_string()),
+            ]),
+        };
+        let options = BoxOptions {
+            env: vec![("TEAM".to_string(), "ml".to_string())],
+            volumes: vec![volume("/opt/shared"), VolumeSpec::tmpfs("/etc/ssl/certs")],
+            labels: HashMap::from([("team".to_string(), "ml".to_string())]),
+            ..Default::default()
+        };
+
+        let merged = defaults.apply(options);
+
+        assert_eq!(
+            merged.env,
+            vec![
+                ("HTTPS_PROXY".to_string(), "http://proxy:3128".to_string()),
+                ("TEAM".to_string(), "ml".to_string()),
+            ]
+        );
+        // Box's own /opt/shared volume and /etc/ssl/certs tmpfs win
+        assert_eq!(
+            merged.volumes,
+            [
+                VolumeSpec::tmpfs("/scratch"),
+                volume("/opt/shared"),
+                VolumeSpec::tmpfs("/etc/ssl/certs"),
+            ]
+        );
+        assert_eq!(merged.labels["org"], "acme");
+        assert_eq!(merged.labels["team"], "ml");
+    }
+
+    #[test]
+    fn test_cpu_feature_mask_env() {
+        assert!(BoxOptions::default().container_env().is_empty());
+
+        let options = BoxOptions {
+            env: vec![(
+                "GLIBC_TUNABLES".to_string(),
+                "glibc.malloc.arena_max=2".to_string(),
+            )],
+            cpu_features: CpuFeatureMask {
+                max_level: Some(X86Level::V3),
+                disable: vec!["AVX512F".to_string(), "AVX2".to_string()],
+            },
+            ..Default::default()
+        };
+        assert_eq!(
+            options.container_env(),
+            vec![(
+                "GLIBC_TUNABLES".to_string(),
+                "glibc.malloc.arena_max=2:glibc.cpu.hwcaps=-AVX512F,-AVX512BW,-AVX512CD,\
+                 -AVX512DQ,-AVX512VL,-AVX2"
+                    .to_string()
+            )]
+        );
+
+        let invalid = BoxOptions {
+            cpu_features: CpuFeatureMask {
+                max_level: None,
+                disable: vec!["AVX2,-SSE2".to_string()],
+            },
+            ..Default::default()
+        };
+        assert!(invalid.sanitize().is_err());
+    }
+
+    #[test]
+    fn test_proxy_env() {
+        let host = |key: &str| match key {
+            "HTTPS_PROXY" => Some("http://host-proxy:3128".to_string()),
+            "no_proxy" => Some("localhost".to_string()),
+            _ => None,
+        };
+        let options = BoxOptions {
+            http_proxy: Some("http://proxy:8080".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            options.proxy_env(host),
+            vec![
+                ("http_proxy".to_string(), "http://proxy:8080".to_string()),
+                ("HTTP_PROXY".to_string(), "http://proxy:8080".to_string()),
+            ]
+        );
+
+        let inherited = BoxOptions {
+            inherit_proxy: true,
+            ..options
+        };
+        let env = inherited.proxy_env(host);
+        assert_eq!(env.len(), 6);
+        assert!(env.contains(&(
+            "https_proxy".to_string(),
+            "http://host-proxy:3128".to_string()
+        )));
+        assert!(env.contains(&("NO_PROXY".to_string(), "localhost".to_string())));
+
+        // `env` wins
+        let overridden = BoxOptions {
+            env: vec![("http_proxy".to_string(), "http://other:80".to_string())],
+            ..inherited
+        };
+        let env = overridden.container_env();
+        assert_eq!(env.iter().filter(|(k, _)| k == "http_proxy").count(), 1);
+        assert_eq!(env[0].1, "http://other:80");
+
+        let invalid = BoxOptions {
+            no_proxy: Some("localhost, .corp".to_string()),
+            ..Default::default()
+        };
+        assert!(invalid.sanitize().is_err());
+    }
+
+    #[test]
+    fn test_max_concurrent_execs_sanitize() {
+        let with_max = |max| BoxOptions {
+            max_concurrent_execs: max,
+            ..Default::default()
+        };
+        assert!(with_max(None).sanitize().is_ok());
+        assert!(with_max(Some(4)).sanitize().is_ok());
+        assert!(with_max(Some(0)).sanitize().is_err());
+
+        let no_text_files = BoxOptions {
+            max_text_file_size: 0,
+            ..Default::default()
+        };
+        assert!(no_text_files.sanitize().is_err());
+
+        let no_exec_buffer = BoxOptions {
+            exec_buffer: ExecBufferOptions {
+                capacity: Some(0),
+                policy: ExecBufferPolicy::Drop,
+            },
+            ..Default::default()
+        };
+        assert!(no_exec_buffer.sanitize().is_err());
+
+        let no_lifetime = BoxOptions {
+            ttl: Some(Duration::ZERO),
+            ..Default::default()
+        };
+        assert!(no_lifetime.sanitize().is_err());
+
+        let no_idle_time = BoxOptions {
+            idle_timeout: Some(Duration::ZERO),
+            ..Default::default()
+        };
+        assert!(no_idle_time.sanitize().is_err());
+    }
+
+    #[test]
+    fn test_port_mappings() {
+        let opts = BoxOptions {
+            ports: vec![
+                PortSpec {
+                    host_port: Some(8080),
+                    guest_port: 80,
+                    protocol: PortProtocol::Tcp,
+                    host_ip: None,
+                },
+                PortSpec {
+                    host_port: None,
+                    guest_port: 9000,
+                    protocol: PortProtocol::Tcp,
+                    host_ip: Some("127.0.0.1".to_string()),
+                },
+            ],
+            ..Default::default()
+        };
+        let mapping = |host_ip: Option<&str>, host_port, guest_port| PortMapping {
+            host_ip: host_ip.map(|ip| ip.parse().unwrap()),
+            host_port,
+            guest_port,
+        };
+
+        // The image's port 80 is remapped, 443 kept 1:1
+        assert_eq!(
+            opts.port_mappings(&[80, 443]),
+            vec![
+                mapping(None, 443, 443),
+                mapping(None, 8080, 80),
+                mapping(Some("127.0.0.1"), 9000, 9000)
+            ]
+        );
+        assert!(opts.sanitize().is_ok());
+        assert!(BoxOptions::default().port_mappings(&[]).is_empty());
+
+        let bad_ip = BoxOptions {
+            ports: vec![PortSpec {
+                guest_port: 80,
+                host_ip: Some("localhost".to_string()),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        assert!(bad_ip.sanitize().is_err());
+    }
+
+    #[test]
+    fn test_network_none() {
+        let offline = BoxOptions {
+            network: NetworkSpec::None,
+            ..Default::default()
+        };
+        assert!(offline.sanitize().is_ok());
+        assert!(offline.port_mappings(&[80]).is_empty());
+
+        let with_ports = BoxOptions {
+            ports: vec![PortSpec {
+                guest_port: 80,
+                ..Default::default()
+            }],
+            ..offline.clone()
+        };
+        assert!(with_ports.sanitize().is_err());
+
+        let with_ipv6 = BoxOptions {
+            ipv6: true,
+            ..offline.clone()
+        };
+        assert!(with_ipv6.sanitize().is_err());
+    }
+
+    #[test]
+    fn test_networks_sanitize() {
+        let with_networks = |networks: &[&str]| BoxOptions {
+            networks: networks.iter().map(|n| n.to_string()).collect(),
+            ..Default::default()
+        };
+        assert!(with_networks(&["backend", "cache-1"]).sanitize().is_ok());
+        assert!(with_networks(&["backend", "backend"]).sanitize().is_err());
+        assert!(with_networks(&[".."]).sanitize().is_err());
+        assert!(with_networks(&["a/b"]).sanitize().is_err());
+        assert!(with_networks(&[""]).sanitize().is_err());
+    }
+
+    #[test]
+    fn test_sockets_sanitize() {
+        let with_sockets = |sockets: &[(&str, &str)]| BoxOptions {
+            sockets: sockets
+                .iter()
+                .map(|(host_path, guest_path)| SocketSpec {
+                    host_path: host_path.to_string(),
+                    guest_path: guest_path.to_string(),
+                })
+                .collect(),
+            ..Default::default()
+        };
+        let docker = ("/var/run/docker.sock", "/var/run/docker.sock");
+        assert!(with_sockets(&[docker]).sanitize().is_ok());
+        assert!(
+            with_sockets(&[docker, ("/tmp/other.sock", docker.1)])
+                .sanitize()
+                .is_err()
+        );
+        assert!(
+            with_sockets(&[("docker.sock", "/docker.sock")])
+                .sanitize()
+                .is_err()
+        );
+        assert!(with_sockets(&[("/docker.sock", "/")]).sanitize().is_err());
+    }
+
+    #[test]
+    fn test_reverse_port_target() {
+        use crate::net::reverse::ReverseTarget;
+
+        let target = |host: &str| {
+            ReversePortSpec {
+                guest_port: 8080,
+                host: host.to_string(),
+            }
+            .target()
+        };
+        assert_eq!(
+            target("8080").unwrap(),
+            ReverseTarget::Tcp("127.0.0.1:8080".to_string())
+        );
+        assert_eq!(
+            target("db.lan:5432").unwrap(),
+            ReverseTarget::Tcp("db.lan:5432".to_string())
+        );
+        assert_eq!(
+            target("[::1]:80").unwrap(),
+            ReverseTarget::Tcp("[::1]:80".to_string())
+        );
+        assert_eq!(
+            target("/run/app.sock").unwrap(),
+            ReverseTarget::Unix(PathBuf::from("/run/app.sock"))
+        );
+        assert!(target("0").is_err());
+        assert!(target("db.lan").is_err());
+        assert!(target(":80").is_err());
+
+        let twice = BoxOptions {
+            reverse_ports: vec![
+                ReversePortSpec {
+                    guest_port: 8080,
+                    host: "8080".to_string(),
+                },
+                ReversePortSpec {
+                    guest_port: 8080,
+                    host: "9090".to_string(),
+                },
+            ],
+            ..Default::default()
+        };
+        assert!(twice.sanitize().is_err());
+    }
+
+    #[test]
+    fn test_hostname_sanitize() {
+        let hostname = |name: &str| BoxOptions {
+            hostname: Some(name.to_string()),
+            ..Default::default()
+        };
 
-        Self { home_dir }
+        assert!(hostname("worker-1").sanitize().is_ok());
+        assert!(hostname("api.internal").sanitize().is_ok());
+        assert!(hostname("").sanitize().is_err());
+        assert!(hostname("-worker").sanitize().is_err());
+        assert!(hostname("worker_1").sanitize().is_err());
+        assert!(hostname("api..internal").sanitize().is_err());
+        assert!(hostname(&"a".repeat(65)).sanitize().is_err());
     }
-}
 
-/// Options used when constructing a box.
-#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
-pub struct BoxOptions {
-    pub cpus: Option<u8>,
-    pub memory_mib: Option<u32>,
-    /// Disk size in GB for the container rootfs (sparse, grows as needed).
-    ///
-    /// The actual disk will be at least as large as the base image.
-    /// If set, the COW overlay will have this virtual size, allowing
-    /// the container to write more data than the base image size.
-    pub disk_size_gb: Option<u64>,
-    pub working_dir: Option<String>,
-    pub env: Vec<(String, String)>,
-    pub rootfs: RootfsSpec,
-    pub volumes: Vec<VolumeSpec>,
-    pub network: NetworkSpec,
-    pub ports: Vec<PortSpec>,
-    /// Enable bind mount isolation for the shared mounts directory.
-    ///
-    /// When true, creates a read-only bind mount from `mounts/` to `shared/`,
-    /// preventing the guest from modifying host-prepared files.
-    ///
-    /// Requires CAP_SYS_ADMIN (privileged) or FUSE (rootless) on Linux.
-    /// Defaults to false.
-    #[serde(default)]
-    pub isolate_mounts: bool,
+    #[test]
+    fn test_dns_sanitize() {
+        let dns = |servers: &[&str], search: &[&str]| BoxOptions {
+            dns: servers.iter().map(|s| s.to_string()).collect(),
+            dns_search: search.iter().map(|s| s.to_string()).collect(),
+            ..Default::default()
+        };
 
-    /// Automatically remove box when stopped.
-    ///
-    /// When true (default), the box is removed from the database and its
-    /// files are deleted when `stop()` is called. This is similar to
-    /// Docker's `--rm` flag.
-    ///
-    /// When false, the box is preserved after stop and can be restarted
-    /// with `runtime.get(box_id)`.
-    #[serde(default = "default_auto_remove")]
-    pub auto_remove: bool,
+        assert!(
+            dns(&["1.1.1.1", "2606:4700::1111"], &["corp.example"])
+                .sanitize()
+                .is_ok()
+        );
+        assert!(dns(&["dns.example"], &[]).sanitize().is_err());
+        assert!(dns(&[], &[""]).sanitize().is_err());
+        assert!(dns(&[], &["a b"]).sanitize().is_err());
+    }
 
-    /// Whether the box should continue running when the parent process exits.
-    ///
-    /// When false (default), the box will automatically stop when the process
-    /// that created it exits. This ensures orphan boxes don't accumulate.
-    /// Similar to running a process in the foreground.
-    ///
-    /// When true, the box runs independently and survives parent process exit.
-    /// The box can be reattached using `runtime.get(box_id)`. Similar to
-    /// Docker's `-d` (detach) flag.
-    #[serde(default = "default_detach")]
-    pub detach: bool,
-}
+    #[test]
+    fn test_dns_cache_sanitize() {
+        let cache = |upstreams: &[&str], max_entries| BoxOptions {
+            dns_cache: Some(DnsCache {
+                upstreams: upstreams.iter().map(|s| s.to_string()).collect(),
+                max_entries,
+            }),
+            ..Default::default()
+        };
 
-fn default_auto_remove() -> bool {
-    true
-}
+        let ok = cache(&["1.1.1.1", "10.0.0.2:5353", "[fd00::53]:53"], 100);
+        assert!(ok.sanitize().is_ok());
+        let addrs = ok.dns_cache.as_ref().unwrap().upstream_addrs().unwrap();
+        assert_eq!(addrs[0], "1.1.1.1:53".parse().unwrap());
+        assert_eq!(addrs[1], "10.0.0.2:5353".parse().unwrap());
 
-fn default_detach() -> bool {
-    false
-}
+        assert!(cache(&[], 4096).sanitize().is_ok());
+        assert!(cache(&["dns.example"], 4096).sanitize().is_err());
+        assert!(cache(&[], 0).sanitize().is_err());
+        assert!(
+            BoxOptions {
+                dns: vec!["1.1.1.1".to_string()],
+                ..cache(&[], 4096)
+            }
+            .sanitize()
+            .is_err()
+        );
+        assert!(
+            BoxOptions {
+                network_backend: NetworkBackendKind::Passt,
+                ..cache(&[], 4096)
+            }
+            .sanitize()
+            .is_err()
+        );
 
-impl Default for BoxOptions {
-    fn default() -> Self {
-        Self {
-            cpus: None,
-            memory_mib: None,
-            disk_size_gb: None,
-            working_dir: None,
-            env: Vec::new(),
-            rootfs: RootfsSpec::default(),
-            volumes: Vec::new(),
-            network: NetworkSpec::default(),
-            ports: Vec::new(),
-            isolate_mounts: false,
-            auto_remove: default_auto_remove(),
-            detach: default_detach(),
-        }
+        let parsed: DnsCache = serde_json::from_str("{}").unwrap();
+        assert_eq!(parsed, DnsCache::default());
     }
-}
 
-impl BoxOptions {
-    /// Sanitize and validate options.
-    ///
-    /// Validates option combinations:
-    /// - `auto_remove=true` with `detach=true` is invalid (detached boxes need manual lifecycle control)
-    /// - `isolate_mounts=true` is only supported on Linux
-    pub fn sanitize(&self) -> BoxliteResult<()> {
-        // Validate auto_remove + detach combination
-        // A detached box that auto-removes doesn't make practical sense:
-        // - detach=true: box survives parent exit
-        // - auto_remove=true: box removed on stop
-        // This combination is confusing - detached boxes should have manual lifecycle control
-        if self.auto_remove && self.detach {
-            return Err(boxlite_shared::errors::BoxliteError::Config(
-                "auto_remove=true is incompatible with detach=true. \
-                 Detached boxes should use auto_remove=false for manual lifecycle control."
-                    .to_string(),
-            ));
+    #[test]
+    fn test_named_volumes() {
+        let volume = |host_path: &str| VolumeSpec::bind(host_path, "/data", false);
+        assert_eq!(volume("pgdata").volume_name(), Some("pgdata"));
+        assert_eq!(volume("/srv/pgdata").volume_name(), None);
+        assert_eq!(volume("./pgdata").volume_name(), None);
+
+        let options = |host_path: &str| BoxOptions {
+            volumes: vec![volume(host_path)],
+            ..Default::default()
+        };
+        assert!(options("pgdata").sanitize().is_ok());
+        assert!(options("cache-v1.2").sanitize().is_ok());
+        assert!(options("").sanitize().is_err());
+        assert!(options(".hidden").sanitize().is_err());
+        assert!(options("pg data").sanitize().is_err());
+
+        assert!(VolumeOptions::default().sanitize().is_ok());
+        assert!(
+            VolumeOptions {
+                disk_size_gb: Some(0)
+            }
+            .sanitize()
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_tmpfs_volumes() {
+        // Configs from before tmpfs volumes read as binds
+        let bind: VolumeSpec =
+            serde_json::from_str(r#"{"host_path":"/srv","guest_path":"/srv","read_only":true}"#)
+                .unwrap();
+        assert_eq!(bind, VolumeSpec::bind("/srv", "/srv", true));
+        let tmpfs: VolumeSpec = serde_json::from_str(r#"{"guest_path":"/run/secrets"}"#).unwrap();
+        assert_eq!(tmpfs, VolumeSpec::tmpfs("/run/secrets"));
+        assert_eq!(tmpfs.volume_name(), None);
+        let json = serde_json::to_string(&bind).unwrap();
+        assert_eq!(serde_json::from_str::<VolumeSpec>(&json).unwrap(), bind);
+        let bind: VolumeSpec =
+            serde_json::from_str(r#"{"host_path":"/srv","guest_path":"/srv"}"#).unwrap();
+        assert_eq!(bind, VolumeSpec::bind("/srv", "/srv", false));
+        // Neither a bind nor a tmpfs
+        for json in [
+            r#"{"host":"/srv","guest_path":"/srv"}"#,
+            r#"{"guest_path":"/srv","read_only":true}"#,
+            r#"{"host_path":"/srv","guest_path":"/srv","size":1}"#,
+        ] {
+            assert!(
+                serde_json::from_str::<VolumeSpec>(json).is_err(),
+                "{}",
+                json
+            );
         }
 
-        #[cfg(not(target_os = "linux"))]
-        if self.isolate_mounts {
-            return Err(boxlite_shared::errors::BoxliteError::Unsupported(
-                "isolate_mounts is only supported on Linux".to_string(),
-            ));
+        let options = |guest_path: &str, size, mode| BoxOptions {
+            volumes: vec![VolumeSpec::Tmpfs {
+                guest_path: guest_path.to_string(),
+                size,
+                mode,
+            }],
+            ..Default::default()
+        };
+        assert!(
+            options("/run/secrets", Some(1 << 20), Some(0o700))
+                .sanitize()
+                .is_ok()
+        );
+        assert!(options("/", None, None).sanitize().is_err());
+        assert!(options("scratch", None, None).sanitize().is_err());
+        assert!(options("/scratch", Some(0), None).sanitize().is_err());
+        assert!(options("/scratch", None, Some(0o10000)).sanitize().is_err());
+    }
+
+    #[test]
+    fn test_drain_timeout_sanitize() {
+        let drain = |timeout| BoxOptions {
+            ports: vec![PortSpec {
+                guest_port: 80,
+                ..Default::default()
+            }],
+            drain_timeout: Some(timeout),
+            ..Default::default()
+        };
+
+        assert!(drain(Duration::from_secs(30)).sanitize().is_ok());
+        assert!(drain(Duration::ZERO).sanitize().is_err());
+        assert!(
+            BoxOptions {
+                ports: Vec::new(),
+                ..drain(Duration::from_secs(30))
+            }
+            .sanitize()
+            .is_err()
+        );
+        assert!(
+            BoxOptions {
+                network_backend: NetworkBackendKind::Passt,
+                ..drain(Duration::from_secs(30))
+            }
+            .sanitize()
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_network_policy_sanitize() {
+        let policy = |hosts: &[&str]| BoxOptions {
+            network_policy: NetworkPolicy::allow_only(
+                hosts
+                    .iter()
+                    .map(|host| EgressRule::host(*host).with_ports([443])),
+            ),
+            ..Default::default()
+        };
+
+        assert!(
+            policy(&["pypi.org", "*.pythonhosted.org", "10.0.0.0/8", "fd00::1"])
+                .sanitize()
+                .is_ok()
+        );
+        for bad in ["", "10.0.0.0/33", "a..b", "files.*.org", "has space"] {
+            assert!(policy(&[bad]).sanitize().is_err(), "{}", bad);
         }
-        Ok(())
+
+        let mut with_dns = policy(&["pypi.org"]);
+        with_dns.dns = vec!["1.1.1.1".to_string()];
+        assert!(with_dns.sanitize().is_err());
+        with_dns.network_policy = NetworkPolicy::allow_only([EgressRule::host("1.1.1.1")]);
+        assert!(with_dns.sanitize().is_ok());
+
+        // Only gvproxy filters
+        with_dns.network_backend = NetworkBackendKind::Passt;
+        assert!(with_dns.sanitize().is_err());
     }
-}
 
-/// How to populate the box root filesystem.
-#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
-pub enum RootfsSpec {
-    /// Pull/resolve this registry image reference.
-    Image(String),
-    /// Use an already prepared rootfs at the given host path.
-    RootfsPath(String),
-}
+    #[test]
+    fn test_network_rate_limit_sanitize() {
+        let limited = |ingress_bps, egress_bps| BoxOptions {
+            network_rate_limit: NetworkRateLimit {
+                ingress_bps,
+                egress_bps,
+            },
+            ..Default::default()
+        };
+        assert!(limited(Some(100_000_000), None).sanitize().is_ok());
+        assert!(limited(None, Some(0)).sanitize().is_err());
 
-impl Default for RootfsSpec {
-    fn default() -> Self {
-        Self::Image("alpine:latest".into())
+        let offline = BoxOptions {
+            network: NetworkSpec::None,
+            ..limited(Some(1_000_000), None)
+        };
+        assert!(offline.sanitize().is_err());
+        let passt = BoxOptions {
+            network_backend: NetworkBackendKind::Passt,
+            ..limited(Some(1_000_000), None)
+        };
+        assert!(passt.sanitize().is_err());
     }
-}
 
-/// Filesystem mount specification.
-#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
-pub struct VolumeSpec {
-    pub host_path: String,
-    pub guest_path: String,
-    pub read_only: bool,
-}
+    #[test]
+    fn test_lazy_start_sanitize() {
+        let lazy = BoxOptions {
+            lazy_start: true,
+            ports: vec![PortSpec {
+                host_port: Some(8080),
+                guest_port: 80,
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        assert!(lazy.sanitize().is_ok());
 
-/// Network isolation options.
-#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
-pub enum NetworkSpec {
-    #[default]
-    Isolated,
-    // Host,
-    // Custom(String),
-}
+        let no_ports = BoxOptions {
+            ports: Vec::new(),
+            ..lazy.clone()
+        };
+        assert!(no_ports.sanitize().is_err());
+        let passt = BoxOptions {
+            network_backend: NetworkBackendKind::Passt,
+            ..lazy.clone()
+        };
+        assert!(passt.sanitize().is_err());
+        let stop_on_drop = BoxOptions {
+            on_drop: DropBehavior::Stop,
+            ..lazy
+        };
+        assert!(stop_on_drop.sanitize().is_err());
+    }
 
-#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
-pub enum PortProtocol {
-    #[default]
-    Tcp,
-    Udp,
-    // Sctp,
-}
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_network_mode_host_sanitize() {
+        let host = BoxOptions {
+            network_mode: NetworkMode::Host,
+            ports: vec![PortSpec {
+                host_port: Some(8080),
+                guest_port: 80,
+                host_ip: Some("127.0.0.1".to_string()),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        assert!(host.sanitize().is_ok());
 
-fn default_protocol() -> PortProtocol {
-    PortProtocol::Tcp
-}
+        let offline = BoxOptions {
+            network: NetworkSpec::None,
+            ports: Vec::new(),
+            ..host.clone()
+        };
+        assert!(offline.sanitize().is_err());
+        let ipv6 = BoxOptions {
+            ipv6: true,
+            ..host.clone()
+        };
+        assert!(ipv6.sanitize().is_err());
+        let v6_port = BoxOptions {
+            ports: vec![PortSpec {
+                guest_port: 80,
+                host_ip: Some("::1".to_string()),
+                ..Default::default()
+            }],
+            ..host
+        };
+        assert!(v6_port.sanitize().is_err());
+    }
 
-/// Port mapping specification (host -> guest).
-#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
-pub struct PortSpec {
-    pub host_port: Option<u16>, // None/0 => dynamically assigned
-    pub guest_port: u16,
-    #[serde(default = "default_protocol")]
-    pub protocol: PortProtocol,
-    pub host_ip: Option<String>, // Optional bind IP, defaults to 0.0.0.0/:: if None
-}
+    #[test]
+    fn test_host_entries() {
+        let hosts = |entries: &[&str]| BoxOptions {
+            extra_hosts: entries.iter().map(|s| s.to_string()).collect(),
+            ..Default::default()
+        };
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+        let opts = hosts(&["db:10.0.0.5", "v6:fd00::1", "host.internal:host-gateway"]);
+        assert!(opts.sanitize().is_ok());
+        assert_eq!(
+            opts.host_entries().unwrap(),
+            vec![
+                ("db".to_string(), "10.0.0.5".parse().unwrap()),
+                ("v6".to_string(), "fd00::1".parse().unwrap()),
+                (
+                    "host.internal".to_string(),
+                    crate::net::constants::HOST_IP.parse().unwrap()
+                ),
+            ]
+        );
+        assert!(hosts(&["db"]).sanitize().is_err());
+        assert!(hosts(&[":10.0.0.5"]).sanitize().is_err());
+        assert!(hosts(&["db:example.com"]).sanitize().is_err());
+    }
 
     #[test]
-    fn test_box_options_defaults() {
-        let opts = BoxOptions::default();
-        assert!(opts.auto_remove, "auto_remove should default to true");
-        assert!(!opts.detach, "detach should default to false");
+    fn test_disk_quota_sanitize() {
+        let with_quota = |quota: DiskQuota| BoxOptions {
+            volumes: vec![VolumeSpec::bind("/tmp/data", "/srv/data", false)],
+            disk_quotas: vec![quota],
+            ..Default::default()
+        };
+
+        assert!(
+            with_quota(DiskQuota::path("/workspace", 2 << 30))
+                .sanitize()
+                .is_ok()
+        );
+        assert!(
+            with_quota(DiskQuota::user(1000, 1 << 30))
+                .sanitize()
+                .is_ok()
+        );
+
+        for invalid in [
+            DiskQuota::path("/workspace", 0),
+            DiskQuota::path("workspace", 1024),
+            DiskQuota::path("/", 1024),
+            DiskQuota::path("/workspace/../etc", 1024),
+            DiskQuota::path("/srv/data/cache", 1024),
+            DiskQuota::path("/srv", 1024),
+        ] {
+            assert!(
+                with_quota(invalid.clone()).sanitize().is_err(),
+                "{:?}",
+                invalid
+            );
+        }
     }
 
     #[test]
@@ -299,4 +2801,25 @@ mod tests {
         };
         assert!(opts3.sanitize().is_ok());
     }
+
+    #[test]
+    fn test_sanitize_on_drop_with_restart_policy() {
+        let restart_policy = RestartPolicy {
+            mode: RestartMode::Always,
+            ..Default::default()
+        };
+        let opts = BoxOptions {
+            on_drop: DropBehavior::Stop,
+            restart_policy,
+            ..Default::default()
+        };
+        assert!(opts.sanitize().is_err());
+
+        let opts = BoxOptions {
+            on_drop: DropBehavior::Detach,
+            restart_policy,
+            ..Default::default()
+        };
+        assert!(opts.sanitize().is_ok());
+    }
 }
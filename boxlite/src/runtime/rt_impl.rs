@@ -1,22 +1,40 @@
-use crate::db::{BoxStore, Database};
+use crate::db::{BoxStore, Database, NetworkStore, VolumeStore};
+use crate::disk::{Qcow2Helper, create_blank_ext4};
 use crate::images::ImageManager;
 use crate::init_logging_for;
+use crate::litebox::activation::Activator;
 use crate::litebox::config::BoxConfig;
-use crate::litebox::{BoxManager, LiteBox, SharedBoxImpl};
-use crate::lock::{FileLockManager, LockManager};
+use crate::litebox::{BoxManager, ExecRecord, ExecState, LiteBox, SharedBoxImpl};
+use crate::lock::{FileLockManager, LockGuard, LockManager};
 use crate::metrics::{RuntimeMetrics, RuntimeMetricsStorage};
+use crate::net::dns::BoxDns;
+use crate::net::registry_cache::RegistryCache;
+use crate::runtime::admission;
+use crate::runtime::archive;
 use crate::runtime::constants::filenames;
 use crate::runtime::guest_rootfs::GuestRootfs;
 use crate::runtime::layout::{FilesystemLayout, FsLayoutConfig};
 use crate::runtime::lock::RuntimeLock;
-use crate::runtime::options::{BoxOptions, BoxliteOptions};
-use crate::runtime::types::{BoxID, BoxInfo, BoxState, BoxStatus, ContainerID};
+use crate::runtime::options::{
+    BoxOptions, BoxliteOptions, CloneOptions, PruneFilter, VolumeOptions, sanitize_network_name,
+    sanitize_volume_name,
+};
+use crate::runtime::supervisor;
+use crate::runtime::types::{
+    BatchReport, BoxID, BoxInfo, BoxState, BoxStatus, ContainerID, DrainReport, LabelSelector,
+    ListOptions, NetworkInfo, PruneReport, VolumeInfo, VolumeInspect, VolumeKind,
+};
+use crate::runtime::volumes::VolumeLock;
+use crate::telemetry::Telemetry;
 use crate::vmm::VmmKind;
 use boxlite_shared::{BoxliteError, BoxliteResult, Transport};
-use chrono::Utc;
-use std::collections::HashMap;
-use std::sync::{Arc, RwLock, Weak};
-use tokio::sync::OnceCell;
+use chrono::{SubsecRound, Utc};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock, Weak};
+use std::time::Duration;
+use tokio::sync::{OnceCell, OwnedSemaphorePermit, Semaphore};
 
 /// Internal runtime state protected by single lock.
 ///
@@ -44,6 +62,10 @@ pub struct RuntimeImpl {
     pub(crate) box_manager: BoxManager,
     /// Image management (has internal RwLock via ImageStore)
     pub(crate) image_manager: ImageManager,
+    /// Inter-box networks and the addresses of their boxes
+    pub(crate) network_store: NetworkStore,
+    /// Named volumes
+    pub(crate) volume_store: VolumeStore,
 
     // ========================================================================
     // NO COORDINATION NEEDED: Immutable or internally synchronized
@@ -63,7 +85,38 @@ pub struct RuntimeImpl {
 
     /// Runtime filesystem lock (held for lifetime). Prevent from multiple process run on same
     /// BOXLITE_HOME directory
-    pub(crate) _runtime_lock: RuntimeLock,
+    /// Taken by `shutdown()`, which lets another runtime open the directory.
+    runtime_lock: Mutex<Option<RuntimeLock>>,
+
+    /// Current runtime options (reloadable fields may change at runtime).
+    pub(crate) options: RwLock<BoxliteOptions>,
+
+    /// Registered telemetry sinks (shared with box_manager).
+    pub(crate) telemetry: Telemetry,
+
+    /// Package registry mirror, if enabled (immutable after init).
+    registry_cache: Option<RegistryCache>,
+
+    /// Resolver of `<name>.boxlite`, if enabled (immutable after init).
+    _box_dns: Option<BoxDns>,
+
+    /// Set by `drain()`: no new boxes, starts or commands from then on.
+    draining: AtomicBool,
+
+    /// Set by `shutdown()`, along with `draining`.
+    shut_down: AtomicBool,
+
+    /// Boxes watched for their restart policy.
+    pub(crate) supervised: Mutex<HashSet<BoxID>>,
+
+    /// Boxes admitted against the resource limits that are still booting.
+    booting: admission::Booting,
+
+    /// Boot slots (`max_concurrent_boots`), handed out in FIFO order.
+    boot_slots: Option<Arc<Semaphore>>,
+
+    /// Port listeners of `lazy_start` boxes, closed when they are removed.
+    activators: Mutex<HashMap<BoxID, Activator>>,
 }
 
 /// Synchronized state protected by RwLock.
@@ -94,6 +147,18 @@ impl RuntimeImpl {
                 options.home_dir.display()
             )));
         }
+        options.memory_policy.sanitize()?;
+        options.network_tuning.sanitize()?;
+        if options.max_concurrent_boots == Some(0) {
+            return Err(BoxliteError::InvalidArgument(
+                "max_concurrent_boots must be at least 1".into(),
+            ));
+        }
+        if options.reap_interval == Some(Duration::ZERO) {
+            return Err(BoxliteError::InvalidArgument(
+                "reap_interval must be greater than zero".into(),
+            ));
+        }
 
         // Configure bind mount support based on platform
         #[cfg(target_os = "linux")]
@@ -112,6 +177,9 @@ impl RuntimeImpl {
         })?;
 
         init_logging_for(&layout)?;
+        if let Some(ref log_level) = options.log_level {
+            crate::util::reload_log_filter(log_level)?;
+        }
 
         let runtime_lock = RuntimeLock::acquire(layout.home_dir()).map_err(|e| {
             BoxliteError::Internal(format!(
@@ -149,7 +217,10 @@ impl RuntimeImpl {
             ))
         })?;
 
+        let network_store = NetworkStore::new(db.clone());
+        let volume_store = VolumeStore::new(db.clone());
         let box_store = BoxStore::new(db);
+        let telemetry = Telemetry::default();
 
         // Initialize lock manager for per-entity multiprocess-safe locking
         let lock_manager: Arc<dyn LockManager> =
@@ -166,18 +237,44 @@ impl RuntimeImpl {
             "Initialized lock manager"
         );
 
+        let registry_cache = options
+            .registry_cache
+            .as_ref()
+            .map(|cache| RegistryCache::start(cache, layout.registry_cache_dir()))
+            .transpose()?;
+        let box_dns = options
+            .dns_listen
+            .map(|listen| BoxDns::start(listen, box_store.clone()))
+            .transpose()?;
+
+        let boot_slots = options
+            .max_concurrent_boots
+            .map(|max| Arc::new(Semaphore::new(max)));
+
         let inner = Arc::new(Self {
             sync_state: RwLock::new(SynchronizedState {
                 active_boxes_by_id: HashMap::new(),
                 active_boxes_by_name: HashMap::new(),
             }),
-            box_manager: BoxManager::new(box_store),
+            box_manager: BoxManager::new(box_store).with_telemetry(telemetry.clone()),
             image_manager,
+            network_store,
+            volume_store,
             layout,
             guest_rootfs: Arc::new(OnceCell::new()),
             runtime_metrics: RuntimeMetricsStorage::new(),
             lock_manager,
-            _runtime_lock: runtime_lock,
+            runtime_lock: Mutex::new(Some(runtime_lock)),
+            options: RwLock::new(options),
+            telemetry,
+            registry_cache,
+            _box_dns: box_dns,
+            draining: AtomicBool::new(false),
+            shut_down: AtomicBool::new(false),
+            supervised: Mutex::new(HashSet::new()),
+            booting: admission::Booting::default(),
+            boot_slots,
+            activators: Mutex::new(HashMap::new()),
         });
 
         tracing::debug!("initialized runtime");
@@ -185,6 +282,11 @@ impl RuntimeImpl {
         // Recover boxes from database
         inner.recover_boxes()?;
 
+        let reap_interval = inner.options.read().unwrap().reap_interval;
+        if let Some(interval) = reap_interval {
+            inner.spawn_reaper(interval);
+        }
+
         Ok(inner)
     }
 
@@ -201,6 +303,48 @@ impl RuntimeImpl {
         self: &Arc<Self>,
         options: BoxOptions,
         name: Option<String>,
+    ) -> BoxliteResult<LiteBox> {
+        self.check_not_draining()?;
+
+        let mut sync = self.acquire_write()?;
+        self.create_locked(&mut sync, options, name)
+    }
+
+    /// Get the box named `name`, or create it with `options`.
+    ///
+    /// Both happen under the coordination lock, so concurrent callers get
+    /// the same box. `options` are ignored if the box exists. Returns
+    /// whether the box was created.
+    pub fn get_or_create(
+        self: &Arc<Self>,
+        name: &str,
+        options: BoxOptions,
+    ) -> BoxliteResult<(LiteBox, bool)> {
+        let mut sync = self.acquire_write()?;
+
+        if let Some(weak) = sync.active_boxes_by_name.get(name)
+            && let Some(strong) = weak.upgrade()
+        {
+            return Ok((LiteBox::new(strong), false));
+        }
+        if let Some((config, state)) = self.box_manager.lookup_box(name)?
+            && config.name.as_deref() == Some(name)
+        {
+            let (box_impl, _) = self.cache_box_impl(&mut sync, config, state);
+            return Ok((LiteBox::new(box_impl), false));
+        }
+
+        self.check_not_draining()?;
+        let litebox = self.create_locked(&mut sync, options, Some(name.to_string()))?;
+        Ok((litebox, true))
+    }
+
+    /// Create a box handle, with the coordination lock held.
+    fn create_locked(
+        self: &Arc<Self>,
+        sync: &mut SynchronizedState,
+        options: BoxOptions,
+        name: Option<String>,
     ) -> BoxliteResult<LiteBox> {
         // Check DB for existing name
         if let Some(ref name) = name
@@ -212,17 +356,51 @@ impl RuntimeImpl {
             )));
         }
 
+        // Merge runtime-wide defaults; the box's own settings win
+        let options = options.resolve_env_files()?;
+        let mut options = self.options.read().unwrap().defaults.apply(options);
+        if let Some(cache) = &self.registry_cache {
+            options = cache.apply(options);
+        }
+
+        // A box that could not start now is refused up front
+        self.check_admission(None, &options)?;
+        for network in &options.networks {
+            if self.network_store.get(network)?.is_none() {
+                return Err(BoxliteError::NotFound(format!(
+                    "network '{}' not found",
+                    network
+                )));
+            }
+        }
+        for name in options.volumes.iter().filter_map(|v| v.volume_name()) {
+            if self.volume_store.get(name)?.is_none() {
+                return Err(volume_not_found(name));
+            }
+        }
+
         // Initialize box variables with defaults (no lock, not persisted yet)
         let (config, state) = self.init_box_variables(&options, name);
 
         // Create LiteBox handle with shared BoxImpl
         // This also checks in-memory cache for duplicate names
-        let (box_impl, inserted) = self.get_or_create_box_impl(config, state);
+        let (box_impl, inserted) = self.cache_box_impl(sync, config, state);
         if !inserted {
             return Err(BoxliteError::InvalidArgument(
                 "box with this name already exists".into(),
             ));
         }
+        if options.lazy_start {
+            // Not persisted until it boots, so the listeners hold the box
+            let mut state = box_impl.state.read().clone();
+            if let Err(e) =
+                self.listen_lazily(&box_impl.config, &mut state, Some(Arc::clone(&box_impl)))
+            {
+                self.invalidate_box_impl(&box_impl.config.id, box_impl.config.name.as_deref());
+                return Err(e);
+            }
+            box_impl.state.write().ports = state.ports;
+        }
 
         // Increment boxes_created counter (lock-free!)
         self.runtime_metrics
@@ -233,6 +411,271 @@ impl RuntimeImpl {
         Ok(LiteBox::new(box_impl))
     }
 
+    /// Create a box and start it, all under one timeout.
+    ///
+    /// Either returns a running box, or leaves nothing behind: on error,
+    /// timeout, or if the returned future is dropped, the box is force-removed
+    /// (VM stopped, DB row deleted, lock freed, box directory deleted).
+    pub async fn create_and_start(
+        self: &Arc<Self>,
+        options: BoxOptions,
+        name: Option<String>,
+        timeout: std::time::Duration,
+    ) -> BoxliteResult<LiteBox> {
+        let litebox = self.create(options, name)?;
+        let pending = PendingBoxGuard::new(Arc::clone(self), litebox);
+
+        match tokio::time::timeout(timeout, pending.litebox().start()).await {
+            Ok(Ok(())) => Ok(pending.disarm()),
+            Ok(Err(e)) => Err(e),
+            Err(_) => Err(BoxliteError::Timeout(format!(
+                "box {} did not start within {:?}",
+                pending.litebox().id(),
+                timeout
+            ))),
+        }
+    }
+
+    /// Create a stopped box from the current disks of a stopped box.
+    ///
+    /// The clone gets its own copies of the source's COW overlays. They keep
+    /// sharing the cached base images, so only what the source changed is
+    /// copied. A source that was never started has no disks yet and is
+    /// cloned as a new box with the same options.
+    pub fn clone_box(
+        self: &Arc<Self>,
+        id_or_name: &str,
+        options: CloneOptions,
+    ) -> BoxliteResult<LiteBox> {
+        self.check_not_draining()?;
+
+        let (source, state) = self
+            .box_manager
+            .lookup_box(id_or_name)?
+            .ok_or_else(|| BoxliteError::NotFound(id_or_name.to_string()))?;
+        let Some(lock_id) = state.lock_id else {
+            return self.create(source.options, options.name);
+        };
+
+        // Keep the source from starting while its disks are copied
+        let locker = self.lock_manager.retrieve(lock_id)?;
+        let _guard = LockGuard::new(&*locker);
+        let status = self
+            .box_manager
+            .box_by_id(&source.id)?
+            .map_or(state.status, |(_, state)| state.status);
+        if !status.is_stopped() {
+            return Err(BoxliteError::InvalidState(format!(
+                "cannot clone box {} (status: {:?}). Stop it first",
+                source.id, status
+            )));
+        }
+        if let Some(ref name) = options.name
+            && self.box_manager.lookup_box_id(name)?.is_some()
+        {
+            return Err(BoxliteError::InvalidArgument(format!(
+                "box with name '{}' already exists",
+                name
+            )));
+        }
+
+        let (config, state) = self.init_box_variables(&source.options, options.name);
+        let remove_home = |config: &BoxConfig| {
+            if let Err(e) = std::fs::remove_dir_all(&config.box_home) {
+                tracing::warn!(box_id = %config.id, error = %e, "Failed to remove clone directory");
+            }
+        };
+        if let Err(e) = self.copy_box_disks(&source.id, &config.id) {
+            remove_home(&config);
+            return Err(e);
+        }
+
+        let litebox = self.register_stopped_box(config, state)?;
+        tracing::info!(source = %source.id, box_id = %litebox.id(), "Cloned box");
+        Ok(litebox)
+    }
+
+    /// Write a stopped box, with its disks flattened, to an archive at `path`.
+    pub fn export_box(&self, id_or_name: &str, path: &Path) -> BoxliteResult<()> {
+        let (config, state) = self
+            .box_manager
+            .lookup_box(id_or_name)?
+            .ok_or_else(|| BoxliteError::NotFound(id_or_name.to_string()))?;
+
+        // Keep the box from starting while its disks are read
+        let locker = match state.lock_id {
+            Some(lock_id) => Some(self.lock_manager.retrieve(lock_id)?),
+            None => None,
+        };
+        let _guard = locker.as_ref().map(|locker| LockGuard::new(&**locker));
+        let state = self
+            .box_manager
+            .box_by_id(&config.id)?
+            .map_or(state, |(_, state)| state);
+        if !state.status.is_stopped() {
+            return Err(BoxliteError::InvalidState(format!(
+                "cannot export box {} (status: {:?}). Stop it first",
+                config.id, state.status
+            )));
+        }
+
+        // A box that never started has no disks yet
+        let layout = self.layout.box_layout(config.id.as_str(), false)?;
+        std::fs::create_dir_all(self.layout.temp_dir())?;
+        let staging = tempfile::tempdir_in(self.layout.temp_dir())?;
+        let mut disks = Vec::new();
+        for (name, disk) in [
+            (archive::ROOTFS_DISK, layout.disk_path()),
+            (archive::GUEST_DISK, layout.guest_disk_path()),
+        ] {
+            if disk.exists() {
+                let flat = staging.path().join(name);
+                Qcow2Helper::flatten(&disk, &flat)?;
+                disks.push((name, flat));
+            }
+        }
+        let disks: Vec<_> = disks
+            .iter()
+            .map(|(name, disk)| (*name, disk.as_path()))
+            .collect();
+
+        archive::write(path, &archive::Manifest::new(config.clone(), state), &disks)?;
+        tracing::info!(box_id = %config.id, path = %path.display(), "Exported box");
+        Ok(())
+    }
+
+    /// Create a stopped box from an archive written by [`Self::export_box`].
+    ///
+    /// The box gets a new ID on this host, and keeps its name, options and
+    /// disks.
+    pub fn import_box(self: &Arc<Self>, path: &Path) -> BoxliteResult<LiteBox> {
+        self.check_not_draining()?;
+
+        std::fs::create_dir_all(self.layout.temp_dir())?;
+        let staging = tempfile::tempdir_in(self.layout.temp_dir())?;
+        let archive::Manifest {
+            config: source,
+            state: source_state,
+            ..
+        } = archive::read(path, staging.path())?;
+        if let Some(ref name) = source.name
+            && self.box_manager.lookup_box_id(name)?.is_some()
+        {
+            return Err(BoxliteError::InvalidArgument(format!(
+                "box with name '{}' already exists",
+                name
+            )));
+        }
+
+        let (mut config, mut state) = self.init_box_variables(&source.options, source.name);
+        // The guest's disk refers to the container by ID
+        config.container = source.container;
+        config.created_at = source.created_at;
+        state.image_digest = source_state.image_digest;
+        state.image_manifest = source_state.image_manifest;
+        state.ports = source_state.ports;
+        state.last_exit = source_state.last_exit;
+
+        let layout = self.layout.box_layout(config.id.as_str(), false)?;
+        let moved = std::fs::create_dir_all(layout.root()).and_then(|()| {
+            for (name, disk) in [
+                (archive::ROOTFS_DISK, layout.disk_path()),
+                (archive::GUEST_DISK, layout.guest_disk_path()),
+            ] {
+                let unpacked = staging.path().join(name);
+                if unpacked.exists() {
+                    std::fs::rename(unpacked, disk)?;
+                }
+            }
+            Ok(())
+        });
+        if let Err(e) = moved {
+            let _ = std::fs::remove_dir_all(&config.box_home);
+            return Err(e.into());
+        }
+
+        let litebox = self.register_stopped_box(config, state)?;
+        tracing::info!(source = %source.id, box_id = %litebox.id(), "Imported box");
+        Ok(litebox)
+    }
+
+    /// Persist a new box whose disks are already in its home directory as
+    /// stopped. Removes the directory on failure.
+    fn register_stopped_box(
+        self: &Arc<Self>,
+        config: BoxConfig,
+        mut state: BoxState,
+    ) -> BoxliteResult<LiteBox> {
+        let remove_home = |config: &BoxConfig| {
+            if let Err(e) = std::fs::remove_dir_all(&config.box_home) {
+                tracing::warn!(box_id = %config.id, error = %e, "Failed to remove box directory");
+            }
+        };
+
+        let new_lock = match self.lock_manager.allocate() {
+            Ok(lock) => lock,
+            Err(e) => {
+                remove_home(&config);
+                return Err(e);
+            }
+        };
+        state.set_lock_id(new_lock);
+        state.set_status(BoxStatus::Stopped);
+
+        let (box_impl, inserted) = self.get_or_create_box_impl(config.clone(), state.clone());
+        let persisted = if inserted {
+            self.box_manager.add_box(&config, &state)
+        } else {
+            Err(BoxliteError::InvalidArgument(
+                "box with this name already exists".into(),
+            ))
+        };
+        if let Err(e) = persisted {
+            if inserted {
+                self.invalidate_box_impl(&config.id, config.name.as_deref());
+            }
+            let _ = self.lock_manager.free(new_lock);
+            remove_home(&config);
+            return Err(e);
+        }
+
+        if config.options.lazy_start {
+            let mut state = box_impl.state.read().clone();
+            match self.listen_lazily(&config, &mut state, None) {
+                Ok(()) => {
+                    box_impl.state.write().ports = state.ports.clone();
+                    self.box_manager.save_box(&config.id, &state)?;
+                }
+                Err(e) => {
+                    tracing::warn!(box_id = %config.id, error = %e, "Failed to listen for lazy start");
+                }
+            }
+        }
+
+        self.runtime_metrics
+            .boxes_created
+            .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        Ok(LiteBox::new(box_impl))
+    }
+
+    /// Listen on the ports of a `lazy_start` box, recording them in `state`.
+    /// `pending` is the box if it is not persisted yet.
+    fn listen_lazily(
+        self: &Arc<Self>,
+        config: &BoxConfig,
+        state: &mut BoxState,
+        pending: Option<SharedBoxImpl>,
+    ) -> BoxliteResult<()> {
+        let (activator, ports) = Activator::listen(self, config, pending)?;
+        self.telemetry.ports_published(&config.id, &ports);
+        state.ports = ports;
+        self.activators
+            .lock()
+            .unwrap()
+            .insert(config.id.clone(), activator);
+        Ok(())
+    }
+
     /// Get a handle to an existing box by ID or name.
     ///
     /// Returns a LiteBox handle that can be used to operate on the box.
@@ -241,6 +684,22 @@ impl RuntimeImpl {
     /// If another handle to the same box exists, they share the same BoxImpl
     /// (and thus the same LiveState if initialized).
     pub fn get(self: &Arc<Self>, id_or_name: &str) -> BoxliteResult<Option<LiteBox>> {
+        Ok(self.box_impl(id_or_name)?.map(LiteBox::new))
+    }
+
+    /// Get a handle for the runtime's own use, see [`LiteBox::internal`].
+    pub(crate) fn get_internal(
+        self: &Arc<Self>,
+        id_or_name: &str,
+    ) -> BoxliteResult<Option<LiteBox>> {
+        Ok(self.box_impl(id_or_name)?.map(LiteBox::internal))
+    }
+
+    /// The BoxImpl of a box by ID or name, from the cache or the DB.
+    pub(crate) fn box_impl(
+        self: &Arc<Self>,
+        id_or_name: &str,
+    ) -> BoxliteResult<Option<SharedBoxImpl>> {
         tracing::trace!(id_or_name = %id_or_name, "RuntimeInnerImpl::get called");
 
         // Check in-memory cache first (for boxes created but not yet persisted)
@@ -253,7 +712,7 @@ impl RuntimeImpl {
                 && let Some(strong) = weak.upgrade()
             {
                 tracing::trace!(box_id = %box_id, "Found box in cache by ID");
-                return Ok(Some(LiteBox::new(strong)));
+                return Ok(Some(strong));
             }
 
             // Try as name
@@ -261,7 +720,7 @@ impl RuntimeImpl {
                 && let Some(strong) = weak.upgrade()
             {
                 tracing::trace!(name = %id_or_name, "Found box in cache by name");
-                return Ok(Some(LiteBox::new(strong)));
+                return Ok(Some(strong));
             }
         }
 
@@ -274,8 +733,7 @@ impl RuntimeImpl {
             );
 
             let (box_impl, _) = self.get_or_create_box_impl(config, state);
-            tracing::trace!(id_or_name = %id_or_name, "LiteBox created successfully");
-            return Ok(Some(LiteBox::new(box_impl)));
+            return Ok(Some(box_impl));
         }
 
         tracing::trace!(id_or_name = %id_or_name, "Box not found");
@@ -288,6 +746,677 @@ impl RuntimeImpl {
         self.remove_box(&box_id, force)
     }
 
+    /// Rename a box by ID or name.
+    pub fn rename(&self, id_or_name: &str, new_name: &str) -> BoxliteResult<()> {
+        let box_id = self.resolve_id(id_or_name)?;
+
+        // Hold the cache lock so create() can't take the name meanwhile
+        let mut sync = self.acquire_write()?;
+        if let Some(weak) = sync.active_boxes_by_name.get(new_name)
+            && let Some(strong) = weak.upgrade()
+            && *strong.id() != box_id
+        {
+            return Err(BoxliteError::InvalidState(format!(
+                "box with name '{}' already exists",
+                new_name
+            )));
+        }
+
+        let Some((config, _)) = self.box_manager.box_by_id(&box_id)? else {
+            return Err(BoxliteError::InvalidState(format!(
+                "box {} has not been started yet and cannot be renamed",
+                box_id
+            )));
+        };
+        self.box_manager.rename_box(&box_id, new_name)?;
+
+        // An open BoxImpl keeps serving the box (found by ID); only its old
+        // name must stop resolving
+        if let Some(old_name) = config.name.as_deref() {
+            sync.active_boxes_by_name.remove(old_name);
+        }
+
+        tracing::info!(box_id = %box_id, name = %new_name, "Renamed box");
+        Ok(())
+    }
+
+    /// Remove the stopped boxes that match `filter`.
+    pub fn prune(&self, filter: &PruneFilter) -> BoxliteResult<PruneReport> {
+        let now = chrono::Utc::now();
+        let mut report = PruneReport::default();
+
+        for (config, state) in self.box_manager.all_boxes(true)? {
+            if !state.status.is_stopped() || !filter.labels.matches(&config.options.labels) {
+                continue;
+            }
+            if let Some(older_than) = filter.older_than
+                && (now - config.created_at).to_std().unwrap_or_default() < older_than
+            {
+                continue;
+            }
+
+            let size = crate::util::disk_usage(&config.box_home);
+            match self.remove_box(&config.id, false) {
+                Ok(()) => {
+                    report.removed.push(config.id);
+                    report.freed_bytes += size;
+                }
+                Err(e) => {
+                    tracing::warn!(box_id = %config.id, error = %e, "Failed to prune box");
+                    report.failed.push((config.id, e.to_string()));
+                }
+            }
+        }
+
+        tracing::info!(
+            removed = report.removed.len(),
+            freed_bytes = report.freed_bytes,
+            "Pruned stopped boxes"
+        );
+        Ok(report)
+    }
+
+    /// Restart a box by ID or name, returning a handle to the running box.
+    pub async fn restart(
+        self: &Arc<Self>,
+        id_or_name: &str,
+        timeout: Duration,
+    ) -> BoxliteResult<LiteBox> {
+        let litebox = self
+            .get_internal(id_or_name)?
+            .ok_or_else(|| BoxliteError::NotFound(id_or_name.to_string()))?;
+        litebox.restart(timeout).await
+    }
+
+    /// Stop all running boxes for host maintenance.
+    ///
+    /// Refuses new boxes, starts and commands right away, waits up to
+    /// `deadline` for running (non-detached) commands to finish, then stops
+    /// every running box. The runtime stays draining afterwards.
+    pub async fn drain(self: &Arc<Self>, deadline: Duration) -> BoxliteResult<DrainReport> {
+        const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+        self.draining.store(true, Ordering::SeqCst);
+        let until = tokio::time::Instant::now() + deadline;
+        tracing::info!(?deadline, "Draining runtime");
+
+        let mut boxes = Vec::new();
+        for info in self.list_info()? {
+            if (info.status.is_running() || info.status.is_paused())
+                && let Some(litebox) = self.get_internal(info.id.as_str())?
+            {
+                boxes.push((litebox, Vec::new()));
+            }
+        }
+
+        loop {
+            for (litebox, running) in boxes.iter_mut() {
+                // A paused guest cannot answer, nor finish its commands
+                if litebox.info().status.is_paused() {
+                    continue;
+                }
+                *running = match litebox.list_execs().await {
+                    Ok(execs) => execs
+                        .into_iter()
+                        .filter(|e| !e.detached && matches!(e.state, ExecState::Running))
+                        .map(|e| e.id)
+                        .collect(),
+                    // Nothing to wait for if the guest can't be reached
+                    Err(e) => {
+                        tracing::warn!(box_id = %litebox.id(), error = %e, "Failed to list commands");
+                        Vec::new()
+                    }
+                };
+            }
+            let now = tokio::time::Instant::now();
+            if now >= until || boxes.iter().all(|(_, running)| running.is_empty()) {
+                break;
+            }
+            tokio::time::sleep(POLL_INTERVAL.min(until - now)).await;
+        }
+
+        let mut report = DrainReport::default();
+        for (litebox, running) in boxes {
+            let id = litebox.id().clone();
+            match litebox.stop().await {
+                Err(e) => report.failed.push((id, e.to_string())),
+                Ok(()) if running.is_empty() => report.stopped.push(id),
+                Ok(()) => report.interrupted.push((id, running)),
+            }
+        }
+
+        tracing::info!(
+            stopped = report.stopped.len(),
+            interrupted = report.interrupted.len(),
+            failed = report.failed.len(),
+            "Drained runtime"
+        );
+        Ok(report)
+    }
+
+    /// Stop every box and release the home directory, for a clean exit.
+    ///
+    /// Refuses new boxes, starts and commands right away, stops running and
+    /// paused boxes with `timeout` each, flushes the database and releases
+    /// the runtime lock. Calling it again only stops what is left.
+    pub async fn shutdown(self: &Arc<Self>, timeout: Duration) -> BoxliteResult<BatchReport> {
+        self.draining.store(true, Ordering::SeqCst);
+        self.shut_down.store(true, Ordering::SeqCst);
+        tracing::info!(?timeout, "Shutting down runtime");
+
+        self.activators.lock().unwrap().clear();
+        let report = self.stop_all(timeout).await?;
+        self.box_manager.checkpoint()?;
+        if self.runtime_lock.lock().unwrap().take().is_some() {
+            tracing::info!(
+                home_dir = %self.layout.home_dir().display(),
+                "Released runtime lock"
+            );
+        }
+        Ok(report)
+    }
+
+    /// Stop every running or paused box.
+    ///
+    /// Each box gets `timeout` to shut down, instead of its own stop timeout.
+    pub async fn stop_all(self: &Arc<Self>, timeout: Duration) -> BoxliteResult<BatchReport> {
+        let ids = self
+            .list_info()?
+            .into_iter()
+            .filter(|info| info.status.is_running() || info.status.is_paused())
+            .map(|info| info.id)
+            .collect();
+
+        let report = self
+            .for_each_box(ids, |id| async move {
+                let litebox = self
+                    .get_internal(id.as_str())?
+                    .ok_or_else(|| BoxliteError::NotFound(id.to_string()))?;
+                // Attach first, so stop() shuts down the VM of a recovered box
+                litebox.start().await?;
+                litebox.stop_within(timeout).await
+            })
+            .await;
+
+        tracing::info!(
+            stopped = report.succeeded.len(),
+            failed = report.failed.len(),
+            "Stopped all boxes"
+        );
+        Ok(report)
+    }
+
+    /// Remove every box; active boxes only with `force`.
+    pub async fn remove_all(self: &Arc<Self>, force: bool) -> BoxliteResult<BatchReport> {
+        let ids = self.list_info()?.into_iter().map(|info| info.id).collect();
+
+        let report = self
+            .for_each_box(ids, |id| {
+                let runtime = Arc::clone(self);
+                async move {
+                    tokio::task::spawn_blocking(move || runtime.remove_box(&id, force))
+                        .await
+                        .map_err(|e| BoxliteError::Internal(format!("remove task failed: {}", e)))?
+                }
+            })
+            .await;
+
+        tracing::info!(
+            removed = report.succeeded.len(),
+            failed = report.failed.len(),
+            "Removed all boxes"
+        );
+        Ok(report)
+    }
+
+    /// Run `op` on each box, a few boxes at a time, collecting the results.
+    async fn for_each_box<F, Fut>(&self, ids: Vec<BoxID>, op: F) -> BatchReport
+    where
+        F: Fn(BoxID) -> Fut,
+        Fut: std::future::Future<Output = BoxliteResult<()>>,
+    {
+        use futures::StreamExt;
+
+        const MAX_PARALLEL: usize = 8;
+
+        let results: Vec<_> = futures::stream::iter(ids)
+            .map(|id| {
+                let done = op(id.clone());
+                async move { (id, done.await) }
+            })
+            .buffer_unordered(MAX_PARALLEL)
+            .collect()
+            .await;
+
+        let mut report = BatchReport::default();
+        for (id, result) in results {
+            match result {
+                Ok(()) => report.succeeded.push(id),
+                Err(e) => {
+                    tracing::warn!(box_id = %id, error = %e, "Box operation failed");
+                    report.failed.push((id, e.to_string()));
+                }
+            }
+        }
+        report
+    }
+
+    /// Stop the box once its `ttl` is over, from a background task.
+    ///
+    /// The task holds a weak reference, so it does not keep a dropped
+    /// runtime alive. Without an async runtime to run it on, the TTL is
+    /// only logged.
+    pub(crate) fn schedule_expiry(self: &Arc<Self>, config: &BoxConfig) {
+        let Some(ttl) = config.options.ttl else {
+            return;
+        };
+        let Ok(handle) = tokio::runtime::Handle::try_current() else {
+            tracing::warn!(box_id = %config.id, "No async runtime, box TTL not enforced");
+            return;
+        };
+
+        let age = (Utc::now() - config.created_at)
+            .to_std()
+            .unwrap_or_default();
+        let remaining = ttl.saturating_sub(age);
+        let runtime = Arc::downgrade(self);
+        let box_id = config.id.clone();
+        handle.spawn(async move {
+            tokio::time::sleep(remaining).await;
+            if let Some(runtime) = runtime.upgrade() {
+                runtime.expire_box(&box_id).await;
+            }
+        });
+        tracing::debug!(box_id = %config.id, ?remaining, "Scheduled box expiry");
+    }
+
+    /// Stop a box whose TTL is over, if it is still running.
+    async fn expire_box(self: &Arc<Self>, box_id: &BoxID) {
+        let litebox = match self.get_internal(box_id.as_str()) {
+            Ok(Some(litebox)) => litebox,
+            Ok(None) => return,
+            Err(e) => {
+                tracing::warn!(box_id = %box_id, error = %e, "Failed to look up expired box");
+                return;
+            }
+        };
+        let status = litebox.info().status;
+        if !status.is_running() && !status.is_paused() {
+            return;
+        }
+
+        tracing::info!(box_id = %box_id, "Box TTL is over, stopping it");
+        // Attach first, so stop() shuts down the VM of a recovered box
+        let stopped = match litebox.start().await {
+            Ok(()) => litebox.stop().await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = stopped {
+            tracing::warn!(box_id = %box_id, error = %e, "Failed to stop expired box");
+        }
+    }
+
+    /// Look for boxes whose VM died every `interval`, from a background
+    /// task holding a weak reference to the runtime.
+    ///
+    /// Without an async runtime, such boxes are only found the next time
+    /// the runtime opens.
+    fn spawn_reaper(self: &Arc<Self>, interval: Duration) {
+        let Ok(handle) = tokio::runtime::Handle::try_current() else {
+            tracing::debug!("No async runtime, not watching for crashed boxes");
+            return;
+        };
+        let runtime = Arc::downgrade(self);
+        handle.spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                let Some(runtime) = runtime.upgrade() else {
+                    return;
+                };
+                // Another runtime may own the directory now
+                if runtime.shut_down.load(Ordering::SeqCst) {
+                    return;
+                }
+                runtime.reap_crashed();
+            }
+        });
+    }
+
+    /// Mark boxes whose VM died as stopped, report them, and restart those
+    /// with a restart policy.
+    fn reap_crashed(self: &Arc<Self>) {
+        // Supervised boxes are checked (and restarted) by their supervisor
+        let crashed = self.box_manager.refresh_states(|id| {
+            self.is_stopping(id) || self.supervised.lock().unwrap().contains(id)
+        });
+        let crashed = match crashed {
+            Ok(crashed) => crashed,
+            Err(e) => {
+                tracing::warn!(error = %e, "Failed to check for crashed boxes");
+                return;
+            }
+        };
+
+        for (config, pid) in crashed {
+            // Handles to the dead VM are stale; the next get() starts fresh
+            self.invalidate_box_impl(&config.id, config.name.as_deref());
+            self.telemetry.crash(&config.id, Some(pid));
+            supervisor::supervise(self, &config, true);
+        }
+    }
+
+    /// Count a box that is about to boot against the resource limits
+    /// (`max_boxes` and the like) until the returned guard is dropped.
+    pub(crate) fn admit(&self, config: &BoxConfig) -> BoxliteResult<admission::Admission> {
+        // Held while checking, so two boxes cannot take the last slot
+        let _sync = self.acquire_write()?;
+        self.check_admission(Some(&config.id), &config.options)?;
+        Ok(admission::Admission::new(
+            &self.booting,
+            config.id.clone(),
+            admission::Usage::of(&config.options),
+        ))
+    }
+
+    /// Wait for a boot slot under `max_concurrent_boots`, in arrival order.
+    ///
+    /// `None` without a limit. The slot is freed when the permit drops.
+    pub(crate) async fn boot_slot(&self) -> Option<OwnedSemaphorePermit> {
+        /// Counts the wait in the queue depth metric, also if cancelled.
+        struct Queued<'a>(&'a AtomicU64);
+
+        impl Drop for Queued<'_> {
+            fn drop(&mut self) {
+                self.0.fetch_sub(1, Ordering::Relaxed);
+            }
+        }
+
+        let slots = Arc::clone(self.boot_slots.as_ref()?);
+        if let Ok(permit) = Arc::clone(&slots).try_acquire_owned() {
+            return Some(permit);
+        }
+
+        let queued = &self.runtime_metrics.boots_queued;
+        queued.fetch_add(1, Ordering::Relaxed);
+        let _queued = Queued(queued);
+        tracing::debug!("Waiting for a boot slot");
+        slots.acquire_owned().await.ok()
+    }
+
+    /// Fail if a box with `options` would exceed the resource limits next
+    /// to the running and booting boxes, other than `box_id` itself.
+    pub(crate) fn check_admission(
+        &self,
+        box_id: Option<&BoxID>,
+        options: &BoxOptions,
+    ) -> BoxliteResult<()> {
+        let runtime_options = self.options.read().unwrap();
+        if runtime_options.max_boxes.is_none()
+            && runtime_options.max_total_cpus.is_none()
+            && runtime_options.max_total_memory_mib.is_none()
+        {
+            return Ok(());
+        }
+
+        let booting = self.booting.lock().unwrap();
+        let mut usage = admission::Usage::default();
+        for (config, state) in self.box_manager.all_boxes(true)? {
+            if state.status.is_active()
+                && Some(&config.id) != box_id
+                && !booting.contains_key(&config.id)
+            {
+                usage.add(admission::Usage::of(&config.options));
+            }
+        }
+        for (id, booting_usage) in booting.iter() {
+            if Some(id) != box_id {
+                usage.add(*booting_usage);
+            }
+        }
+        admission::check(&runtime_options, usage, admission::Usage::of(options))
+    }
+
+    /// Fail if `drain()` was called.
+    pub(crate) fn check_not_draining(&self) -> BoxliteResult<()> {
+        if self.shut_down.load(Ordering::SeqCst) {
+            return Err(BoxliteError::InvalidState("Runtime is shut down".into()));
+        }
+        if self.draining.load(Ordering::SeqCst) {
+            return Err(BoxliteError::InvalidState("Runtime is draining".into()));
+        }
+        Ok(())
+    }
+
+    // ========================================================================
+    // PUBLIC API - NETWORK OPERATIONS
+    // ========================================================================
+
+    /// Create an inter-box network.
+    pub fn create_network(&self, name: &str) -> BoxliteResult<NetworkInfo> {
+        sanitize_network_name(name)?;
+        let network = self.network_store.create(name)?;
+        tracing::info!(network = %name, subnet = %network.subnet, "Created network");
+        Ok(network)
+    }
+
+    /// Remove an inter-box network that no box is configured to join.
+    pub fn remove_network(&self, name: &str) -> BoxliteResult<()> {
+        let _sync = self.acquire_write()?;
+        if self.network_store.get(name)?.is_none() {
+            return Err(BoxliteError::NotFound(format!(
+                "network '{}' not found",
+                name
+            )));
+        }
+        let users: Vec<String> = self
+            .box_manager
+            .all_boxes(false)?
+            .into_iter()
+            .filter(|(config, _)| config.options.networks.iter().any(|n| n == name))
+            .map(|(config, _)| config.name.unwrap_or_else(|| config.id.to_string()))
+            .collect();
+        if !users.is_empty() {
+            return Err(BoxliteError::InvalidState(format!(
+                "network '{}' is used by boxes: {}",
+                name,
+                users.join(", ")
+            )));
+        }
+
+        self.network_store.delete(name)?;
+        let _ = std::fs::remove_dir_all(self.layout.network_dir(name));
+        tracing::info!(network = %name, "Removed network");
+        Ok(())
+    }
+
+    /// List inter-box networks.
+    pub fn list_networks(&self) -> BoxliteResult<Vec<NetworkInfo>> {
+        self.network_store.list()
+    }
+
+    // ========================================================================
+    // PUBLIC API - VOLUME OPERATIONS
+    // ========================================================================
+
+    /// Create a named volume and its directory or disk image.
+    pub fn create_volume(&self, name: &str, options: &VolumeOptions) -> BoxliteResult<VolumeInfo> {
+        sanitize_volume_name(name)?;
+        options.sanitize()?;
+        let disk_size_bytes = options
+            .disk_size_gb
+            .map(|size_gb| {
+                size_gb.checked_mul(1024 * 1024 * 1024).ok_or_else(|| {
+                    BoxliteError::Config(format!("volume disk_size_gb {} is too large", size_gb))
+                })
+            })
+            .transpose()?;
+        let _sync = self.acquire_write()?;
+        if self.volume_store.get(name)?.is_some() {
+            return Err(BoxliteError::AlreadyExists(format!(
+                "volume '{}' already exists",
+                name
+            )));
+        }
+
+        // Left behind by a volume of the same name that failed to be removed
+        let dir = self.layout.volume_dir(name);
+        let _ = std::fs::remove_dir_all(&dir);
+        // A disk volume is held by the box that mounts it while its VM runs
+        let lock_id = match disk_size_bytes {
+            Some(_) => Some(self.lock_manager.allocate()?),
+            None => None,
+        };
+        let created = (|| {
+            std::fs::create_dir_all(&dir)?;
+            let (kind, path) = match disk_size_bytes {
+                Some(size_bytes) => {
+                    let disk = create_blank_ext4(&dir.join("disk.ext4"), size_bytes)?;
+                    (VolumeKind::Disk { size_bytes }, disk.path().to_path_buf())
+                }
+                None => {
+                    let path = dir.join("data");
+                    std::fs::create_dir_all(&path)?;
+                    (VolumeKind::Directory, path)
+                }
+            };
+            let volume = VolumeInfo {
+                name: name.to_string(),
+                kind,
+                path,
+                // Round to the stored precision
+                created_at: Utc::now().trunc_subsecs(0),
+            };
+            self.volume_store.create(&volume, lock_id)?;
+            Ok::<_, BoxliteError>(volume)
+        })();
+        match created {
+            Ok(volume) => {
+                tracing::info!(volume = %name, kind = ?volume.kind, "Created volume");
+                Ok(volume)
+            }
+            Err(e) => {
+                let _ = std::fs::remove_dir_all(&dir);
+                if let Some(lock_id) = lock_id {
+                    let _ = self.lock_manager.free(lock_id);
+                }
+                Err(e)
+            }
+        }
+    }
+
+    /// Remove a named volume and its data. Fails while a box is configured
+    /// to mount it.
+    pub fn remove_volume(&self, name: &str) -> BoxliteResult<()> {
+        let _sync = self.acquire_write()?;
+        if self.volume_store.get(name)?.is_none() {
+            return Err(BoxliteError::NotFound(format!(
+                "volume '{}' not found",
+                name
+            )));
+        }
+        let users: Vec<String> = self
+            .volume_users(name)?
+            .into_iter()
+            .map(|(config, _)| config.name.unwrap_or_else(|| config.id.to_string()))
+            .collect();
+        if !users.is_empty() {
+            return Err(BoxliteError::InvalidState(format!(
+                "volume '{}' is used by boxes: {}",
+                name,
+                users.join(", ")
+            )));
+        }
+
+        let lock_id = self.volume_store.lock_id(name)?;
+        self.volume_store.delete(name)?;
+        if let Some(lock_id) = lock_id
+            && let Err(e) = self.lock_manager.free(lock_id)
+        {
+            tracing::warn!(volume = %name, lock_id = %lock_id, error = %e, "Failed to free volume lock");
+        }
+        if let Err(e) = std::fs::remove_dir_all(self.layout.volume_dir(name)) {
+            tracing::warn!(volume = %name, error = %e, "Failed to remove volume data");
+        }
+        tracing::info!(volume = %name, "Removed volume");
+        Ok(())
+    }
+
+    /// Get a named volume and the boxes configured to mount it.
+    pub fn inspect_volume(&self, name: &str) -> BoxliteResult<Option<VolumeInspect>> {
+        let Some(info) = self.volume_store.get(name)? else {
+            return Ok(None);
+        };
+        let used_by = self
+            .volume_users(name)?
+            .into_iter()
+            .map(|(config, _)| config.id)
+            .collect();
+        Ok(Some(VolumeInspect { info, used_by }))
+    }
+
+    /// List named volumes.
+    pub fn list_volumes(&self) -> BoxliteResult<Vec<VolumeInfo>> {
+        self.volume_store.list()
+    }
+
+    /// The named volume `box_id` mounts.
+    ///
+    /// A disk volume is returned with its lock, which the box holds until
+    /// its VM stops: a box booting at the same time fails to take it, and
+    /// one running from before the runtime restarted is found by status.
+    pub(crate) fn resolve_volume(
+        &self,
+        name: &str,
+        box_id: &BoxID,
+    ) -> BoxliteResult<(VolumeInfo, Option<VolumeLock>)> {
+        let volume = self
+            .volume_store
+            .get(name)?
+            .ok_or_else(|| volume_not_found(name))?;
+        let VolumeKind::Disk { .. } = volume.kind else {
+            return Ok((volume, None));
+        };
+
+        let busy = || {
+            BoxliteError::InvalidState(format!("disk volume '{}' is mounted by another box", name))
+        };
+        let lock = match self.volume_store.lock_id(name)? {
+            Some(lock_id) => {
+                let locker = self.lock_manager.retrieve(lock_id)?;
+                Some(VolumeLock::try_acquire(locker).ok_or_else(busy)?)
+            }
+            None => None,
+        };
+        if let Some((config, _)) = self
+            .volume_users(name)?
+            .into_iter()
+            .find(|(config, state)| &config.id != box_id && state.status.is_active())
+        {
+            return Err(BoxliteError::InvalidState(format!(
+                "disk volume '{}' is mounted by running box {}",
+                name,
+                config.name.unwrap_or_else(|| config.id.to_string())
+            )));
+        }
+        Ok((volume, lock))
+    }
+
+    /// Boxes configured to mount a named volume.
+    fn volume_users(&self, name: &str) -> BoxliteResult<Vec<(BoxConfig, BoxState)>> {
+        Ok(self
+            .box_manager
+            .all_boxes(false)?
+            .into_iter()
+            .filter(|(config, _)| {
+                config
+                    .options
+                    .volumes
+                    .iter()
+                    .any(|volume| volume.volume_name() == Some(name))
+            })
+            .collect())
+    }
+
     // ========================================================================
     // PUBLIC API - QUERY OPERATIONS
     // ========================================================================
@@ -324,16 +1453,34 @@ impl RuntimeImpl {
     }
 
     /// List all boxes, sorted by creation time (newest first).
+    pub fn list_info(&self) -> BoxliteResult<Vec<BoxInfo>> {
+        self.list(&ListOptions::default())
+    }
+
+    /// List the boxes whose labels match `selector`, newest first.
+    pub fn list_filtered(&self, selector: &LabelSelector) -> BoxliteResult<Vec<BoxInfo>> {
+        self.list(&ListOptions {
+            labels: selector.clone(),
+            ..Default::default()
+        })
+    }
+
+    /// List the boxes matching `options`, newest first.
     ///
     /// Includes both persisted boxes (from database) and in-memory boxes
     /// (created but not yet persisted).
-    pub fn list_info(&self) -> BoxliteResult<Vec<BoxInfo>> {
-        use std::collections::HashSet;
-
-        // Get boxes from database
-        let db_boxes = self.box_manager.all_boxes(true)?;
-        let mut seen_ids: HashSet<BoxID> = db_boxes.iter().map(|(c, _)| c.id.clone()).collect();
-        let mut infos: Vec<_> = db_boxes
+    pub fn list(&self, options: &ListOptions) -> BoxliteResult<Vec<BoxInfo>> {
+        // The page is within the first `offset + limit` boxes of each
+        let window = ListOptions {
+            limit: options
+                .limit
+                .map(|limit| limit.saturating_add(options.offset)),
+            offset: 0,
+            ..options.clone()
+        };
+        let mut infos: Vec<_> = self
+            .box_manager
+            .list_boxes(&window)?
             .into_iter()
             .map(|(config, state)| BoxInfo::new(&config, &state))
             .collect();
@@ -341,19 +1488,25 @@ impl RuntimeImpl {
         // Add in-memory boxes not yet persisted
         {
             let sync = self.sync_state.read().unwrap();
-            for (box_id, weak) in &sync.active_boxes_by_id {
-                if !seen_ids.contains(box_id)
-                    && let Some(strong) = weak.upgrade()
+            for weak in sync.active_boxes_by_id.values() {
+                if let Some(strong) = weak.upgrade()
+                    && !strong.is_persisted()
                 {
-                    infos.push(strong.info());
-                    seen_ids.insert(box_id.clone());
+                    let info = strong.info();
+                    if options.matches(&info) {
+                        infos.push(info);
+                    }
                 }
             }
         }
 
         // Sort by creation time (newest first)
         infos.sort_by(|a, b| b.created_at.cmp(&a.created_at));
-        Ok(infos)
+        Ok(infos
+            .into_iter()
+            .skip(options.offset)
+            .take(options.limit.unwrap_or(usize::MAX))
+            .collect())
     }
 
     /// Check if a box with the given ID or name exists.
@@ -384,6 +1537,12 @@ impl RuntimeImpl {
         Ok(self.box_manager.lookup_box_id(id_or_name)?.is_some())
     }
 
+    /// Get the exec history of a box by ID or name, oldest first.
+    pub fn exec_history(&self, id_or_name: &str) -> BoxliteResult<Vec<ExecRecord>> {
+        let box_id = self.resolve_id(id_or_name)?;
+        self.box_manager.exec_history(&box_id)
+    }
+
     // ========================================================================
     // PUBLIC API - METRICS
     // ========================================================================
@@ -393,6 +1552,99 @@ impl RuntimeImpl {
         RuntimeMetrics::new(self.runtime_metrics.clone())
     }
 
+    /// Apply new runtime options without restarting.
+    ///
+    /// Reloadable: `memory_policy` (used by boxes started afterwards),
+    /// `defaults` (used by boxes created afterwards), the resource limits
+    /// (`max_boxes` and the like, checked from then on) and `log_level`
+    /// (immediate). The options read once at startup must stay as they are.
+    pub fn reload_config(&self, options: BoxliteOptions) -> BoxliteResult<()> {
+        let mut current = self.options.write().unwrap();
+
+        if options.home_dir != current.home_dir {
+            return Err(BoxliteError::InvalidArgument(format!(
+                "home_dir cannot be changed without restarting the runtime (current: {}, new: {})",
+                current.home_dir.display(),
+                options.home_dir.display()
+            )));
+        }
+        let fixed = [
+            (
+                "registry_cache",
+                options.registry_cache != current.registry_cache,
+            ),
+            (
+                "reap_interval",
+                options.reap_interval != current.reap_interval,
+            ),
+            (
+                "max_concurrent_boots",
+                options.max_concurrent_boots != current.max_concurrent_boots,
+            ),
+            ("dns_listen", options.dns_listen != current.dns_listen),
+            (
+                "network_tuning",
+                options.network_tuning != current.network_tuning,
+            ),
+        ];
+        let changed: Vec<_> = fixed
+            .iter()
+            .filter(|(_, changed)| *changed)
+            .map(|(field, _)| *field)
+            .collect();
+        if !changed.is_empty() {
+            return Err(BoxliteError::InvalidArgument(format!(
+                "{} cannot be changed without restarting the runtime",
+                changed.join(", ")
+            )));
+        }
+        options.memory_policy.sanitize()?;
+
+        if options.log_level != current.log_level {
+            // Unset falls back to RUST_LOG, as on startup
+            let directives = options
+                .log_level
+                .clone()
+                .or_else(|| std::env::var("RUST_LOG").ok())
+                .unwrap_or_else(|| "info".to_string());
+            crate::util::reload_log_filter(&directives)?;
+        }
+
+        tracing::info!(
+            memory_policy = ?options.memory_policy,
+            log_level = ?options.log_level,
+            "Reloaded runtime configuration"
+        );
+        *current = options;
+        Ok(())
+    }
+
+    /// Reload the options returned by `load` on every SIGHUP.
+    pub fn reload_on_sighup<F>(self: &Arc<Self>, load: F) -> BoxliteResult<()>
+    where
+        F: Fn() -> BoxliteResult<BoxliteOptions> + Send + 'static,
+    {
+        use tokio::signal::unix::{SignalKind, signal};
+
+        let handle = tokio::runtime::Handle::try_current().map_err(|_| {
+            BoxliteError::InvalidState("reloading on SIGHUP needs an async runtime".to_string())
+        })?;
+        let mut hangups = signal(SignalKind::hangup())?;
+        let runtime = Arc::downgrade(self);
+        handle.spawn(async move {
+            while hangups.recv().await.is_some() {
+                let Some(runtime) = runtime.upgrade() else {
+                    return;
+                };
+                tracing::info!("Received SIGHUP, reloading runtime configuration");
+                if let Err(e) = load().and_then(|options| runtime.reload_config(options)) {
+                    tracing::warn!(error = %e, "Failed to reload runtime configuration");
+                }
+            }
+        });
+        Ok(())
+    }
+
     // ========================================================================
     // INTERNAL - BOX OPERATIONS
     // ========================================================================
@@ -505,6 +1757,12 @@ impl RuntimeImpl {
 
             // Invalidate cache
             self.invalidate_box_impl(id, config.name.as_deref());
+            // Frees the ports of a lazy_start box
+            self.activators.lock().unwrap().remove(id);
+            // A killed box left its TAP device behind
+            if config.options.network_mode == crate::runtime::options::NetworkMode::Host {
+                crate::net::host::detach(id);
+            }
 
             tracing::info!(box_id = %id, "Removed box");
             return Ok(());
@@ -531,6 +1789,7 @@ impl RuntimeImpl {
 
             // Invalidate cache (removes from in-memory maps)
             self.invalidate_box_impl(id, box_impl.config.name.as_deref());
+            self.activators.lock().unwrap().remove(id);
 
             // Delete box directory if it exists
             let box_home = &box_impl.config.box_home;
@@ -557,6 +1816,36 @@ impl RuntimeImpl {
     // INTERNAL - INITIALIZATION
     // ========================================================================
 
+    /// Copy the COW disks of box `from` into the directory of box `to`.
+    fn copy_box_disks(&self, from: &BoxID, to: &BoxID) -> BoxliteResult<()> {
+        let source = self.layout.box_layout(from.as_str(), false)?;
+        let target = self.layout.box_layout(to.as_str(), false)?;
+        if !source.disk_path().exists() {
+            return Err(BoxliteError::Storage(format!(
+                "box {} has no rootfs disk at {}",
+                from,
+                source.disk_path().display()
+            )));
+        }
+        std::fs::create_dir_all(target.root())?;
+
+        let mut disks = vec![(source.disk_path(), target.disk_path())];
+        if source.guest_disk_path().exists() {
+            disks.push((source.guest_disk_path(), target.guest_disk_path()));
+        }
+        for (from, to) in disks {
+            std::fs::copy(&from, &to).map_err(|e| {
+                BoxliteError::Storage(format!(
+                    "Failed to copy {} to {}: {}",
+                    from.display(),
+                    to.display(),
+                    e
+                ))
+            })?;
+        }
+        Ok(())
+    }
+
     /// Initialize box variables with defaults.
     ///
     /// Creates config and state for a new box. Lock allocation and DB persistence
@@ -605,15 +1894,20 @@ impl RuntimeImpl {
     }
 
     /// Recover boxes from persistent storage on runtime startup.
-    fn recover_boxes(&self) -> BoxliteResult<()> {
+    fn recover_boxes(self: &Arc<Self>) -> BoxliteResult<()> {
         use crate::util::{is_process_alive, is_same_process};
 
         // Check for system reboot and reset active boxes
-        self.box_manager.check_and_handle_reboot()?;
+        let reset_by_reboot = self.box_manager.check_and_handle_reboot()?;
 
         // Clear all locks before recovery - safe because we hold the runtime lock.
         // This ensures a clean slate for lock allocation during recovery.
         self.lock_manager.clear_all_locks()?;
+        for lock_id in self.volume_store.lock_ids()? {
+            if let Err(e) = self.lock_manager.allocate_and_retrieve(lock_id) {
+                tracing::warn!(lock_id = %lock_id, error = %e, "Failed to reclaim volume lock");
+            }
+        }
 
         let persisted = self.box_manager.all_boxes(true)?;
 
@@ -748,6 +2042,31 @@ impl RuntimeImpl {
             if state.status != original_status {
                 self.box_manager.save_box(box_id, &state)?;
             }
+
+            if state.status.is_running() || state.status.is_paused() {
+                self.schedule_expiry(&config);
+            }
+            if config.options.lazy_start {
+                match self.listen_lazily(&config, &mut state, None) {
+                    Ok(()) => self.box_manager.save_box(box_id, &state)?,
+                    Err(e) => {
+                        tracing::warn!(box_id = %box_id, error = %e, "Failed to listen for lazy start");
+                    }
+                }
+            }
+
+            if state.status.is_running() || state.status.is_paused() {
+                supervisor::supervise(self, &config, false);
+            } else if state.lock_id.is_some()
+                && supervisor::restarts_on_open(
+                    config.options.restart_policy.mode,
+                    original_status.is_active(),
+                    reset_by_reboot.contains(box_id),
+                )
+            {
+                // Boxes that went away while the runtime was closed
+                supervisor::supervise(self, &config, true);
+            }
         }
 
         tracing::info!("Box recovery complete");
@@ -769,14 +2088,24 @@ impl RuntimeImpl {
         self: &Arc<Self>,
         config: BoxConfig,
         state: BoxState,
+    ) -> (SharedBoxImpl, bool) {
+        let mut sync = self.sync_state.write().unwrap();
+        self.cache_box_impl(&mut sync, config, state)
+    }
+
+    /// [`get_or_create_box_impl`](Self::get_or_create_box_impl), with the
+    /// coordination lock held.
+    fn cache_box_impl(
+        self: &Arc<Self>,
+        sync: &mut SynchronizedState,
+        config: BoxConfig,
+        state: BoxState,
     ) -> (SharedBoxImpl, bool) {
         use crate::litebox::box_impl::BoxImpl;
 
         let box_id = config.id.clone();
         let box_name = config.name.clone();
 
-        let mut sync = self.sync_state.write().unwrap();
-
         // Check by name first (if provided) - prevents duplicate names
         if let Some(ref name) = box_name
             && let Some(weak) = sync.active_boxes_by_name.get(name)
@@ -814,6 +2143,20 @@ impl RuntimeImpl {
         (box_impl, true)
     }
 
+    /// The box's BoxImpl, if a handle to it is open in this process.
+    pub(crate) fn cached_box(&self, box_id: &BoxID) -> Option<SharedBoxImpl> {
+        let sync = self.sync_state.read().unwrap();
+        sync.active_boxes_by_id
+            .get(box_id)
+            .and_then(|weak| weak.upgrade())
+    }
+
+    /// Check if a handle in this process is stopping the box.
+    pub(crate) fn is_stopping(&self, box_id: &BoxID) -> bool {
+        self.cached_box(box_id)
+            .is_some_and(|box_impl| box_impl.is_shutdown())
+    }
+
     /// Remove BoxImpl from cache.
     ///
     /// Called when box is stopped or removed. Existing handles become stale;
@@ -840,6 +2183,23 @@ impl RuntimeImpl {
     }
 }
 
+/// Error for a missing named volume.
+///
+/// Before named volumes, a host path without `/` was relative to the
+/// working directory; such boxes are told so instead of silently mounting
+/// a volume of that name.
+fn volume_not_found(name: &str) -> BoxliteError {
+    if Path::new(name).exists() {
+        BoxliteError::Config(format!(
+            "volume '{}' not found; host paths must be absolute, use ./{} for a \
+             relative directory",
+            name, name
+        ))
+    } else {
+        BoxliteError::NotFound(format!("volume '{}' not found", name))
+    }
+}
+
 impl std::fmt::Debug for RuntimeImpl {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("RuntimeInner")
@@ -847,3 +2207,49 @@ impl std::fmt::Debug for RuntimeImpl {
             .finish()
     }
 }
+
+/// Force-removes a box created by `create_and_start` unless disarmed.
+///
+/// Runs on drop so a cancelled start (timeout or dropped future) is cleaned up
+/// the same way as a failed one. Holds the handle so the in-memory box stays
+/// reachable until removal.
+struct PendingBoxGuard {
+    runtime: SharedRuntimeImpl,
+    litebox: Option<LiteBox>,
+}
+
+impl PendingBoxGuard {
+    fn new(runtime: SharedRuntimeImpl, litebox: LiteBox) -> Self {
+        Self {
+            runtime,
+            litebox: Some(litebox),
+        }
+    }
+
+    fn litebox(&self) -> &LiteBox {
+        self.litebox.as_ref().expect("pending box already taken")
+    }
+
+    /// Keep the box and hand out its handle.
+    fn disarm(mut self) -> LiteBox {
+        self.litebox.take().expect("pending box already taken")
+    }
+}
+
+impl Drop for PendingBoxGuard {
+    fn drop(&mut self) {
+        let Some(litebox) = self.litebox.as_ref() else {
+            return;
+        };
+
+        tracing::warn!(box_id = %litebox.id(), "Box did not start, removing");
+        match self.runtime.remove_box(litebox.id(), true) {
+            Ok(()) | Err(BoxliteError::NotFound(_)) => {}
+            Err(e) => tracing::warn!(
+                box_id = %litebox.id(),
+                error = %e,
+                "Failed to remove box after aborted start"
+            ),
+        }
+    }
+}
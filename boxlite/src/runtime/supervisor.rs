@@ -0,0 +1,244 @@
+//! Restart of failed boxes, per their [`RestartPolicy`].
+//!
+//! Each box with a policy gets one task while the runtime is open. It polls
+//! the box's shim PID and, once the VM is gone without `stop()`, marks the
+//! box stopped and starts it again with the Stopped (restart) init plan.
+
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+
+use tokio::time::Instant;
+
+use crate::litebox::config::BoxConfig;
+use crate::runtime::options::{RestartMode, RestartPolicy};
+use crate::runtime::rt_impl::RuntimeImpl;
+use crate::runtime::types::{BoxID, BoxStatus};
+use boxlite_shared::BoxliteResult;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(300);
+/// A box that stays up this long counts its restarts from zero again.
+const STABLE_AFTER: Duration = Duration::from_secs(60);
+
+/// Watch a box and restart it when it fails.
+///
+/// `failed` is for boxes found dead while the runtime opens: they are
+/// restarted right away. No-op without a policy, if the box is already
+/// watched, or outside an async runtime.
+pub(crate) fn supervise(runtime: &Arc<RuntimeImpl>, config: &BoxConfig, failed: bool) {
+    let policy = config.options.restart_policy;
+    if policy.mode == RestartMode::No {
+        return;
+    }
+    let Ok(handle) = tokio::runtime::Handle::try_current() else {
+        tracing::warn!(box_id = %config.id, "No async runtime, restart policy not applied");
+        return;
+    };
+    if !runtime.supervised.lock().unwrap().insert(config.id.clone()) {
+        return;
+    }
+
+    let weak = Arc::downgrade(runtime);
+    let box_id = config.id.clone();
+    handle.spawn(async move {
+        watch(&weak, &box_id, policy, failed).await;
+        if let Some(runtime) = weak.upgrade() {
+            runtime.supervised.lock().unwrap().remove(&box_id);
+        }
+    });
+}
+
+/// Run until the box is stopped or removed, or the restarts give up.
+async fn watch(runtime: &Weak<RuntimeImpl>, box_id: &BoxID, policy: RestartPolicy, failed: bool) {
+    let mut failed = failed;
+    let mut retries = 0;
+    // When the box last started, None while restarting it fails
+    let mut started_at = (!failed).then(Instant::now);
+
+    loop {
+        if !failed {
+            tokio::time::sleep(POLL_INTERVAL).await;
+            let Some(runtime) = runtime.upgrade() else {
+                return;
+            };
+            match check(&runtime, box_id) {
+                Ok(Some(true)) => continue,
+                Ok(Some(false)) => failed = true,
+                Ok(None) => return,
+                Err(e) => {
+                    tracing::warn!(box_id = %box_id, error = %e, "Failed to check box");
+                    continue;
+                }
+            }
+        }
+
+        let up_for = started_at.map(|at| at.elapsed());
+        let Some((backoff, attempt)) = next_restart(&policy, retries, up_for) else {
+            tracing::warn!(box_id = %box_id, retries, "Box keeps failing, not restarting it");
+            return;
+        };
+        retries = attempt;
+        tokio::time::sleep(backoff).await;
+
+        let Some(runtime) = runtime.upgrade() else {
+            return;
+        };
+        let litebox = match runtime.get_internal(box_id.as_str()) {
+            Ok(Some(litebox)) => litebox,
+            Ok(None) => return,
+            Err(e) => {
+                tracing::warn!(box_id = %box_id, error = %e, "Failed to look up failed box");
+                continue;
+            }
+        };
+        tracing::info!(box_id = %box_id, attempt = retries, "Restarting failed box");
+        match litebox.start().await {
+            Ok(()) => {
+                failed = false;
+                started_at = Some(Instant::now());
+            }
+            Err(e) => {
+                tracing::warn!(box_id = %box_id, error = %e, "Failed to restart box");
+                started_at = None;
+            }
+        }
+    }
+}
+
+/// Whether a box the runtime finds not running as it opens is restarted:
+/// `was_active` if its VM died while the runtime was closed, `rebooted` if
+/// it was running when the host rebooted; otherwise it was stopped.
+pub(crate) fn restarts_on_open(mode: RestartMode, was_active: bool, rebooted: bool) -> bool {
+    match mode {
+        RestartMode::No => false,
+        RestartMode::OnFailure => was_active,
+        RestartMode::UnlessStopped => was_active || rebooted,
+        RestartMode::Always => true,
+    }
+}
+
+/// Wait before restarting a box that failed `up_for` after its last start
+/// (None if the last restart did not start it), having been restarted
+/// `retries` times in a row, and the restart number that makes. None if the
+/// policy gives up.
+fn next_restart(
+    policy: &RestartPolicy,
+    retries: u32,
+    up_for: Option<Duration>,
+) -> Option<(Duration, u32)> {
+    if policy.mode == RestartMode::No {
+        return None;
+    }
+    let stable = up_for.is_some_and(|up_for| up_for >= STABLE_AFTER);
+    let retries = if stable { 0 } else { retries };
+    if policy.max_retries.is_some_and(|max| retries >= max) {
+        return None;
+    }
+    let backoff = policy
+        .backoff
+        .saturating_mul(2u32.saturating_pow(retries))
+        .min(MAX_BACKOFF);
+    Some((backoff, retries + 1))
+}
+
+/// `Some(true)` while the box runs, `Some(false)` once its VM is gone
+/// (the box is then marked stopped), `None` if it was stopped or removed.
+fn check(runtime: &RuntimeImpl, box_id: &BoxID) -> BoxliteResult<Option<bool>> {
+    let Some((config, mut state)) = runtime.box_manager.box_by_id(box_id)? else {
+        return Ok(None);
+    };
+    match state.status {
+        BoxStatus::Running | BoxStatus::Paused => {}
+        BoxStatus::Stopped => return Ok(None),
+        BoxStatus::Unknown | BoxStatus::Starting | BoxStatus::Stopping => return Ok(Some(true)),
+    }
+    // Being stopped: the DB says stopped on the next check. A reused PID
+    // is not the box's shim.
+    let alive =
+        |pid| !crate::util::has_exited(pid) && crate::util::is_same_process(pid, box_id.as_str());
+    if runtime.is_stopping(box_id) || state.pid.is_some_and(alive) {
+        return Ok(Some(true));
+    }
+
+    tracing::warn!(box_id = %box_id, pid = ?state.pid, "Box VM exited unexpectedly");
+    let pid = state.pid;
+    state.mark_crashed();
+    runtime.box_manager.save_box(box_id, &state)?;
+    runtime.telemetry.crash(box_id, pid);
+    // Handles to the dead VM are stale; the next get() starts fresh
+    runtime.invalidate_box_impl(box_id, config.name.as_deref());
+    Ok(Some(false))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_restarts_on_open() {
+        // (was_active, rebooted)
+        let cases = [(true, false), (false, true), (false, false)];
+        let expect =
+            |mode| cases.map(|(active, rebooted)| restarts_on_open(mode, active, rebooted));
+        assert_eq!(expect(RestartMode::No), [false, false, false]);
+        assert_eq!(expect(RestartMode::OnFailure), [true, false, false]);
+        assert_eq!(expect(RestartMode::UnlessStopped), [true, true, false]);
+        assert_eq!(expect(RestartMode::Always), [true, true, true]);
+    }
+
+    #[test]
+    fn test_next_restart() {
+        let policy = RestartPolicy {
+            mode: RestartMode::OnFailure,
+            max_retries: Some(3),
+            backoff: Duration::from_secs(1),
+        };
+        let quick = Some(Duration::from_secs(1));
+        assert_eq!(
+            next_restart(&policy, 0, quick),
+            Some((Duration::from_secs(1), 1))
+        );
+        assert_eq!(
+            next_restart(&policy, 2, quick),
+            Some((Duration::from_secs(4), 3))
+        );
+        assert_eq!(next_restart(&policy, 3, quick), None);
+        // A box that stayed up starts over
+        assert_eq!(
+            next_restart(&policy, 3, Some(STABLE_AFTER)),
+            Some((Duration::from_secs(1), 1))
+        );
+
+        // A restart that did not start the box never resets the count
+        assert_eq!(next_restart(&policy, 3, None), None);
+
+        let forever = RestartPolicy {
+            max_retries: None,
+            ..policy
+        };
+        assert_eq!(next_restart(&forever, 30, quick), Some((MAX_BACKOFF, 31)));
+        let never = RestartPolicy {
+            mode: RestartMode::No,
+            ..policy
+        };
+        assert_eq!(next_restart(&never, 0, quick), None);
+    }
+
+    #[test]
+    fn test_next_restart_gives_up_on_failed_starts() {
+        let policy = RestartPolicy {
+            mode: RestartMode::OnFailure,
+            max_retries: Some(10),
+            backoff: Duration::from_secs(1),
+        };
+
+        // Every start fails; the backoffs alone add up past STABLE_AFTER
+        let (mut retries, mut waited) = (0, Duration::ZERO);
+        while let Some((backoff, attempt)) = next_restart(&policy, retries, None) {
+            retries = attempt;
+            waited += backoff;
+        }
+        assert_eq!(retries, 10);
+        assert!(waited > STABLE_AFTER);
+    }
+}
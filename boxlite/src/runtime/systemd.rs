@@ -0,0 +1,139 @@
+//! systemd service integration (`sd_notify` protocol).
+//!
+//! For processes that embed a [`BoxliteRuntime`] and run as a
+//! `Type=notify` systemd service. Readiness is reported once, watchdog pings
+//! are only sent while the runtime passes its health check, so systemd
+//! restarts a wedged process (`WatchdogSec=`).
+//!
+//! Everything is a no-op when `NOTIFY_SOCKET` is not set.
+
+use std::os::unix::net::UnixDatagram;
+use std::path::Path;
+use std::time::Duration;
+
+use boxlite_shared::errors::{BoxliteError, BoxliteResult};
+
+use crate::runtime::BoxliteRuntime;
+
+const NOTIFY_SOCKET: &str = "NOTIFY_SOCKET";
+const WATCHDOG_USEC: &str = "WATCHDOG_USEC";
+const WATCHDOG_PID: &str = "WATCHDOG_PID";
+
+/// Send a raw state string (e.g. `"READY=1"`) to the service manager.
+///
+/// Returns `Ok(false)` if not running under systemd.
+pub fn notify(state: &str) -> BoxliteResult<bool> {
+    match std::env::var_os(NOTIFY_SOCKET) {
+        Some(socket) => notify_to(Path::new(&socket), state).map(|()| true),
+        None => Ok(false),
+    }
+}
+
+/// Tell systemd the service finished starting up.
+pub fn notify_ready() -> BoxliteResult<bool> {
+    notify("READY=1")
+}
+
+/// Watchdog interval configured for this process, if any.
+pub fn watchdog_interval() -> Option<Duration> {
+    let usec = std::env::var(WATCHDOG_USEC).ok()?;
+    let pid = std::env::var(WATCHDOG_PID).ok();
+    parse_watchdog(&usec, pid.as_deref(), std::process::id())
+}
+
+impl BoxliteRuntime {
+    /// Report readiness to systemd and keep its watchdog fed.
+    ///
+    /// Sends `READY=1`, then pings the watchdog at half the configured
+    /// interval for as long as the runtime is healthy (its box database is
+    /// readable). An unhealthy check skips the ping, so systemd restarts the
+    /// service once the interval elapses. Runs until the future is dropped;
+    /// returns immediately if no watchdog is configured.
+    pub async fn run_systemd_notify(&self) -> BoxliteResult<()> {
+        notify_ready()?;
+
+        let Some(interval) = watchdog_interval() else {
+            return Ok(());
+        };
+        let mut ticker = tokio::time::interval(interval / 2);
+
+        loop {
+            ticker.tick().await;
+            match self.list_info() {
+                Ok(boxes) => {
+                    notify("WATCHDOG=1")?;
+                    notify(&format!("STATUS={} boxes", boxes.len()))?;
+                }
+                Err(e) => {
+                    tracing::warn!(error = %e, "Runtime health check failed, skipping watchdog ping");
+                    notify(&format!("STATUS=unhealthy: {}", e))?;
+                }
+            }
+        }
+    }
+}
+
+fn notify_to(socket: &Path, state: &str) -> BoxliteResult<()> {
+    let sock = UnixDatagram::unbound()?;
+
+    // '@' prefix means an abstract socket (Linux only)
+    #[cfg(target_os = "linux")]
+    if let Some(name) = socket.to_str().and_then(|s| s.strip_prefix('@')) {
+        use std::os::linux::net::SocketAddrExt;
+        let addr = std::os::unix::net::SocketAddr::from_abstract_name(name)?;
+        sock.send_to_addr(state.as_bytes(), &addr)?;
+        return Ok(());
+    }
+
+    sock.send_to(state.as_bytes(), socket).map_err(|e| {
+        BoxliteError::Internal(format!("sd_notify to {} failed: {}", socket.display(), e))
+    })?;
+    Ok(())
+}
+
+fn parse_watchdog(usec: &str, pid: Option<&str>, own_pid: u32) -> Option<Duration> {
+    // Watchdog is meant for another process (e.g. our parent)
+    if let Some(pid) = pid
+        && pid.parse::<u32>().ok()? != own_pid
+    {
+        return None;
+    }
+
+    match usec.parse::<u64>().ok()? {
+        0 => None,
+        usec => Some(Duration::from_micros(usec)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_watchdog() {
+        assert_eq!(
+            parse_watchdog("30000000", None, 42),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(
+            parse_watchdog("30000000", Some("42"), 42),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(parse_watchdog("30000000", Some("7"), 42), None);
+        assert_eq!(parse_watchdog("0", None, 42), None);
+        assert_eq!(parse_watchdog("soon", None, 42), None);
+    }
+
+    #[test]
+    fn test_notify_to_socket() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("notify.sock");
+        let server = UnixDatagram::bind(&path).unwrap();
+
+        notify_to(&path, "READY=1").unwrap();
+
+        let mut buf = [0u8; 64];
+        let n = server.recv(&mut buf).unwrap();
+        assert_eq!(&buf[..n], b"READY=1");
+    }
+}
@@ -13,7 +13,7 @@ use std::hash::Hash;
 use boxlite_shared::Transport;
 
 // Re-export status types from litebox module
-pub use crate::litebox::{BoxState, BoxStatus};
+pub use crate::litebox::{BoxState, BoxStatus, ExitReason, HealthStatus, LastExit};
 
 // ============================================================================
 // BOX ID
@@ -248,13 +248,40 @@ pub struct BoxInfo {
 
     /// User-defined labels for filtering and organization.
     pub labels: HashMap<String, String>,
+
+    /// Result of the image HEALTHCHECK (None if the image has none).
+    pub health: Option<HealthStatus>,
+
+    /// Where the container rootfs comes from.
+    pub rootfs: crate::runtime::options::RootfsSpec,
+
+    /// Config digest of the image, once the box has booted from it.
+    pub image_digest: Option<String>,
+
+    /// Ports forwarded from the host, as of the last boot; before the
+    /// first boot, those set in `BoxOptions::ports`.
+    pub ports: Vec<PortMapping>,
+
+    /// Host directories mounted into the box.
+    pub volumes: Vec<crate::runtime::options::VolumeSpec>,
+
+    /// How the box last stopped (None if it never did).
+    pub last_exit: Option<LastExit>,
 }
 
 impl BoxInfo {
     /// Create BoxInfo from config and state.
     pub fn new(config: &crate::litebox::config::BoxConfig, state: &BoxState) -> Self {
+        use crate::runtime::constants::vm_defaults::{DEFAULT_CPUS, DEFAULT_MEMORY_MIB};
         use crate::runtime::options::RootfsSpec;
 
+        // Recorded at boot; until then, only the ports set in the options
+        let ports = if state.ports.is_empty() {
+            config.options.port_mappings(&[])
+        } else {
+            state.ports.clone()
+        };
+
         Self {
             id: config.id.clone(),
             name: config.name.clone(),
@@ -267,9 +294,15 @@ impl BoxInfo {
                 RootfsSpec::Image(r) => r.clone(),
                 RootfsSpec::RootfsPath(p) => format!("rootfs:{}", p),
             },
-            cpus: config.options.cpus.unwrap_or(2),
-            memory_mib: config.options.memory_mib.unwrap_or(512),
-            labels: HashMap::new(),
+            cpus: config.options.cpus.unwrap_or(DEFAULT_CPUS),
+            memory_mib: config.options.memory_mib.unwrap_or(DEFAULT_MEMORY_MIB),
+            labels: config.options.labels.clone(),
+            health: state.health,
+            rootfs: config.options.rootfs.clone(),
+            image_digest: state.image_digest.clone(),
+            ports,
+            volumes: config.options.volumes.clone(),
+            last_exit: state.last_exit.clone(),
         }
     }
 }
@@ -284,6 +317,354 @@ impl PartialEq for BoxInfo {
             && self.cpus == other.cpus
             && self.memory_mib == other.memory_mib
             && self.labels == other.labels
+            && self.health == other.health
+            && self.image_digest == other.image_digest
+            && self.ports == other.ports
+            && self.last_exit == other.last_exit
+    }
+}
+
+// ============================================================================
+// INSPECT
+// ============================================================================
+
+/// Everything known about a box, like `docker inspect`.
+///
+/// Returned by [`LiteBox::inspect`](crate::LiteBox::inspect) and
+/// [`BoxliteRuntime::inspect`](crate::BoxliteRuntime::inspect); serialize
+/// it for tooling.
+#[derive(Debug, Clone, Serialize)]
+pub struct BoxInspect {
+    pub id: BoxID,
+    pub name: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub container_id: ContainerID,
+    /// Persisted state: status, PID, health and so on.
+    pub state: BoxState,
+    /// Options the box was created with, after runtime defaults.
+    pub options: crate::runtime::options::BoxOptions,
+    pub engine: crate::vmm::VmmKind,
+    /// The image the box runs (None for a rootfs path, or if the image is
+    /// not in the local store).
+    pub image: Option<ImageInspect>,
+    /// Volumes and tmpfs mounts in the container.
+    pub mounts: Vec<MountInspect>,
+    /// Ports forwarded from the host into the box.
+    pub ports: Vec<PortMapping>,
+    pub paths: BoxPaths,
+    /// Usage snapshot, only while the box runs.
+    pub metrics: Option<crate::metrics::BoxMetrics>,
+}
+
+/// Image part of [`BoxInspect`].
+#[derive(Debug, Clone, Serialize)]
+pub struct ImageInspect {
+    /// Reference the box was created from.
+    pub reference: String,
+    /// Digest of the image config, i.e. the image ID.
+    pub id: String,
+    /// Layer digests, base layer first.
+    pub layers: Vec<String>,
+}
+
+/// A mount in [`BoxInspect`].
+#[derive(Debug, Clone, Serialize)]
+pub struct MountInspect {
+    pub kind: MountKind,
+    /// Host path, for volumes.
+    pub source: Option<String>,
+    /// Path in the container.
+    pub destination: String,
+    pub read_only: bool,
+}
+
+/// Kind of a [`MountInspect`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MountKind {
+    Volume,
+    Tmpfs,
+}
+
+/// A port forwarded from the host into a box.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PortMapping {
+    /// Host address listened on; all interfaces if None.
+    #[serde(default)]
+    pub host_ip: Option<std::net::IpAddr>,
+    pub host_port: u16,
+    pub guest_port: u16,
+}
+
+impl From<(u16, u16)> for PortMapping {
+    /// `(host_port, guest_port)` on all interfaces.
+    fn from((host_port, guest_port): (u16, u16)) -> Self {
+        Self {
+            host_ip: None,
+            host_port,
+            guest_port,
+        }
+    }
+}
+
+/// A network boxes talk to each other on, see
+/// [`BoxliteRuntime::create_network`](crate::BoxliteRuntime::create_network).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NetworkInfo {
+    pub name: String,
+    /// IPv4 subnet of the network's boxes, e.g. `10.89.0.0/24`.
+    pub subnet: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A named volume, see [`Volumes`](crate::Volumes).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VolumeInfo {
+    pub name: String,
+    pub kind: VolumeKind,
+    /// Host path of the volume's directory or disk image.
+    pub path: std::path::PathBuf,
+    pub created_at: DateTime<Utc>,
+}
+
+/// What backs a [`VolumeInfo`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VolumeKind {
+    /// A host directory, shared with the box like a bind mount.
+    Directory,
+    /// A dedicated ext4 disk image of this size, attached to the VM as a
+    /// block device. Faster than a directory for many small files, but
+    /// mounted by one running box at a time.
+    Disk { size_bytes: u64 },
+}
+
+/// A named volume and the boxes configured to mount it, from
+/// [`Volumes::inspect`](crate::Volumes::inspect).
+#[derive(Debug, Clone, Serialize)]
+pub struct VolumeInspect {
+    #[serde(flatten)]
+    pub info: VolumeInfo,
+    /// IDs of the boxes that mount the volume.
+    pub used_by: Vec<BoxID>,
+}
+
+/// Host paths of a box, in [`BoxInspect`].
+#[derive(Debug, Clone, Serialize)]
+pub struct BoxPaths {
+    /// Box directory.
+    pub home: std::path::PathBuf,
+    /// Container rootfs disk.
+    pub rootfs_disk: std::path::PathBuf,
+    /// Where the runtime reaches the guest agent.
+    pub transport: Transport,
+    pub ready_socket: std::path::PathBuf,
+}
+
+// ============================================================================
+// LABEL SELECTOR
+// ============================================================================
+
+/// Query over box labels, for
+/// [`BoxliteRuntime::list_filtered`](crate::BoxliteRuntime::list_filtered).
+///
+/// A box matches when it meets every requirement. Selectors can be built
+/// in code or parsed from the Kubernetes-style text form:
+///
+/// ```
+/// use boxlite::runtime::types::LabelSelector;
+///
+/// let selector: LabelSelector = "tenant=acme,job!=nightly,gpu,!debug".parse().unwrap();
+/// assert_eq!(
+///     selector,
+///     LabelSelector::new()
+///         .eq("tenant", "acme")
+///         .ne("job", "nightly")
+///         .exists("gpu")
+///         .not_exists("debug")
+/// );
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LabelSelector {
+    requirements: Vec<LabelRequirement>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum LabelRequirement {
+    Equals(String, String),
+    NotEquals(String, String),
+    Exists(String),
+    NotExists(String),
+}
+
+impl LabelSelector {
+    /// Selector that matches every box.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Require label `key` to be `value`.
+    pub fn eq(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.requirements
+            .push(LabelRequirement::Equals(key.into(), value.into()));
+        self
+    }
+
+    /// Require label `key` to be missing or not `value`.
+    pub fn ne(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.requirements
+            .push(LabelRequirement::NotEquals(key.into(), value.into()));
+        self
+    }
+
+    /// Require label `key` to be set, to any value.
+    pub fn exists(mut self, key: impl Into<String>) -> Self {
+        self.requirements.push(LabelRequirement::Exists(key.into()));
+        self
+    }
+
+    /// Require label `key` not to be set.
+    pub fn not_exists(mut self, key: impl Into<String>) -> Self {
+        self.requirements
+            .push(LabelRequirement::NotExists(key.into()));
+        self
+    }
+
+    pub(crate) fn requirements(&self) -> &[LabelRequirement] {
+        &self.requirements
+    }
+
+    /// Check a box's labels against the selector.
+    pub fn matches(&self, labels: &HashMap<String, String>) -> bool {
+        self.requirements.iter().all(|req| match req {
+            LabelRequirement::Equals(key, value) => labels.get(key) == Some(value),
+            LabelRequirement::NotEquals(key, value) => labels.get(key) != Some(value),
+            LabelRequirement::Exists(key) => labels.contains_key(key),
+            LabelRequirement::NotExists(key) => !labels.contains_key(key),
+        })
+    }
+}
+
+impl std::str::FromStr for LabelSelector {
+    type Err = boxlite_shared::BoxliteError;
+
+    /// Parse comma-separated `key=value`, `key!=value`, `key` and `!key`
+    /// requirements. An empty string matches every box.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut selector = Self::new();
+        for term in s.split(',').map(str::trim).filter(|t| !t.is_empty()) {
+            let (key, value) = if let Some((key, value)) = term.split_once("!=") {
+                (key, Some(value))
+            } else if let Some((key, value)) = term.split_once('=') {
+                (key, Some(value.strip_prefix('=').unwrap_or(value)))
+            } else {
+                (term.strip_prefix('!').unwrap_or(term), None)
+            };
+            let (key, value) = (key.trim(), value.map(str::trim));
+            if key.is_empty() || key.contains(['=', '!']) || value.is_some_and(|v| v.contains('='))
+            {
+                return Err(boxlite_shared::BoxliteError::InvalidArgument(format!(
+                    "Invalid label selector term: {:?}",
+                    term
+                )));
+            }
+
+            selector = match value {
+                Some(value) if term.contains("!=") => selector.ne(key, value),
+                Some(value) => selector.eq(key, value),
+                None if term.starts_with('!') => selector.not_exists(key),
+                None => selector.exists(key),
+            };
+        }
+        Ok(selector)
+    }
+}
+
+/// Which boxes [`BoxliteRuntime::list`](crate::BoxliteRuntime::list)
+/// returns, newest first.
+///
+/// The filters are applied by the database, so a page of a large runtime
+/// does not load every box.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ListOptions {
+    /// Only boxes in this status.
+    pub status: Option<BoxStatus>,
+    /// Only boxes whose name starts with this.
+    pub name_prefix: Option<String>,
+    /// Only boxes whose labels match.
+    pub labels: LabelSelector,
+    /// Only boxes created from this image reference, as given in
+    /// [`RootfsSpec::Image`](crate::runtime::options::RootfsSpec::Image).
+    pub image: Option<String>,
+    /// Return at most this many boxes.
+    pub limit: Option<usize>,
+    /// Skip this many matching boxes first.
+    pub offset: usize,
+}
+
+impl ListOptions {
+    /// Check a box against the filters (not `limit`/`offset`).
+    pub(crate) fn matches(&self, info: &BoxInfo) -> bool {
+        use crate::runtime::options::RootfsSpec;
+
+        self.status.is_none_or(|status| info.status == status)
+            && self.name_prefix.as_ref().is_none_or(|prefix| {
+                info.name
+                    .as_ref()
+                    .is_some_and(|name| name.starts_with(prefix.as_str()))
+            })
+            && self.labels.matches(&info.labels)
+            && self
+                .image
+                .as_ref()
+                .is_none_or(|image| matches!(&info.rootfs, RootfsSpec::Image(r) if r == image))
+    }
+}
+
+/// Outcome of [`BoxliteRuntime::drain`](crate::BoxliteRuntime::drain).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DrainReport {
+    /// Boxes stopped after their commands finished.
+    pub stopped: Vec<BoxID>,
+    /// Boxes stopped at the deadline, with the commands that were still
+    /// running and got killed.
+    pub interrupted: Vec<(BoxID, Vec<String>)>,
+    /// Boxes that could not be stopped, with the error.
+    pub failed: Vec<(BoxID, String)>,
+}
+
+impl DrainReport {
+    /// Whether every box was stopped without killing a command.
+    pub fn is_clean(&self) -> bool {
+        self.interrupted.is_empty() && self.failed.is_empty()
+    }
+}
+
+/// Outcome of [`BoxliteRuntime::prune`](crate::BoxliteRuntime::prune).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PruneReport {
+    /// Boxes removed.
+    pub removed: Vec<BoxID>,
+    /// Disk space the removed boxes used, in bytes.
+    pub freed_bytes: u64,
+    /// Boxes that could not be removed, with the error.
+    pub failed: Vec<(BoxID, String)>,
+}
+
+/// Outcome of an operation on many boxes, like
+/// [`BoxliteRuntime::stop_all`](crate::BoxliteRuntime::stop_all).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BatchReport {
+    /// Boxes the operation succeeded on.
+    pub succeeded: Vec<BoxID>,
+    /// Boxes it failed on, with the error.
+    pub failed: Vec<(BoxID, String)>,
+}
+
+impl BatchReport {
+    /// Whether the operation succeeded on every box.
+    pub fn is_clean(&self) -> bool {
+        self.failed.is_empty()
     }
 }
 
@@ -394,6 +775,49 @@ mod tests {
         assert_eq!(info.memory_mib, 1024);
     }
 
+    #[test]
+    fn test_label_selector_matches() {
+        let labels = HashMap::from([
+            ("tenant".to_string(), "acme".to_string()),
+            ("gpu".to_string(), "a100".to_string()),
+        ]);
+
+        assert!(LabelSelector::new().matches(&labels));
+        assert!(LabelSelector::new().eq("tenant", "acme").matches(&labels));
+        assert!(!LabelSelector::new().eq("tenant", "other").matches(&labels));
+        assert!(LabelSelector::new().ne("tenant", "other").matches(&labels));
+        assert!(LabelSelector::new().ne("job", "x").matches(&labels));
+        assert!(LabelSelector::new().exists("gpu").matches(&labels));
+        assert!(!LabelSelector::new().not_exists("gpu").matches(&labels));
+        assert!(
+            !LabelSelector::new()
+                .eq("tenant", "acme")
+                .exists("job")
+                .matches(&labels)
+        );
+    }
+
+    #[test]
+    fn test_label_selector_parse() {
+        let selector: LabelSelector = " tenant = acme, job!=nightly ,gpu,!debug,env==prod"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            selector,
+            LabelSelector::new()
+                .eq("tenant", "acme")
+                .ne("job", "nightly")
+                .exists("gpu")
+                .not_exists("debug")
+                .eq("env", "prod")
+        );
+        assert_eq!("".parse::<LabelSelector>().unwrap(), LabelSelector::new());
+
+        for bad in ["=acme", "!=x", "!", "a=b=c", "a!b"] {
+            assert!(bad.parse::<LabelSelector>().is_err(), "{}", bad);
+        }
+    }
+
     #[test]
     fn test_container_id_new() {
         let id1 = ContainerID::new();
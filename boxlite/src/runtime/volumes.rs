@@ -0,0 +1,92 @@
+//! Named volumes, whose data outlives the boxes that mount them.
+
+use std::sync::Arc;
+
+use crate::lock::Locker;
+use crate::runtime::options::VolumeOptions;
+use crate::runtime::rt_impl::SharedRuntimeImpl;
+use crate::runtime::types::{VolumeInfo, VolumeInspect};
+use boxlite_shared::errors::BoxliteResult;
+
+/// Named volumes of a runtime, from [`BoxliteRuntime::volumes`].
+///
+/// A box mounts a volume through a [`VolumeSpec::Bind`] whose `host_path` is the
+/// volume's name. Volumes live under `~/.boxlite/volumes`, each in a
+/// directory or a dedicated ext4 disk image.
+///
+/// [`BoxliteRuntime::volumes`]: crate::BoxliteRuntime::volumes
+/// [`VolumeSpec::Bind`]: crate::runtime::options::VolumeSpec::Bind
+#[derive(Clone)]
+pub struct Volumes {
+    rt_impl: SharedRuntimeImpl,
+}
+
+impl Volumes {
+    pub(crate) fn new(rt_impl: SharedRuntimeImpl) -> Self {
+        Self { rt_impl }
+    }
+
+    /// Create a volume.
+    ///
+    /// Names are up to 64 letters, digits, `_`, `-` or `.`, starting with a
+    /// letter or digit. Returns `AlreadyExists` if the name is taken.
+    pub fn create(&self, name: &str, options: VolumeOptions) -> BoxliteResult<VolumeInfo> {
+        self.rt_impl.create_volume(name, &options)
+    }
+
+    /// List the volumes, oldest first.
+    pub fn list(&self) -> BoxliteResult<Vec<VolumeInfo>> {
+        self.rt_impl.list_volumes()
+    }
+
+    /// Get a volume and the boxes configured to mount it.
+    pub fn inspect(&self, name: &str) -> BoxliteResult<Option<VolumeInspect>> {
+        self.rt_impl.inspect_volume(name)
+    }
+
+    /// Remove a volume and its data. Fails while a box is configured to
+    /// mount it.
+    pub fn remove(&self, name: &str) -> BoxliteResult<()> {
+        self.rt_impl.remove_volume(name)
+    }
+}
+
+impl std::fmt::Debug for Volumes {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Volumes").finish_non_exhaustive()
+    }
+}
+
+/// Lock of a disk volume, held by the box mounting it until its VM stops.
+pub(crate) struct VolumeLock(Arc<dyn Locker>);
+
+impl VolumeLock {
+    /// Take the lock, `None` if another box holds it.
+    pub(crate) fn try_acquire(locker: Arc<dyn Locker>) -> Option<Self> {
+        locker.try_lock().then(|| Self(locker))
+    }
+}
+
+impl Drop for VolumeLock {
+    fn drop(&mut self) {
+        self.0.unlock();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lock::{FileLockManager, LockManager};
+
+    #[test]
+    fn test_volume_lock_excludes_other_boxes() {
+        let dir = tempfile::tempdir().unwrap();
+        let manager = FileLockManager::new(dir.path()).unwrap();
+        let lock_id = manager.allocate().unwrap();
+
+        let held = VolumeLock::try_acquire(manager.retrieve(lock_id).unwrap()).unwrap();
+        assert!(VolumeLock::try_acquire(manager.retrieve(lock_id).unwrap()).is_none());
+        drop(held);
+        assert!(VolumeLock::try_acquire(manager.retrieve(lock_id).unwrap()).is_some());
+    }
+}
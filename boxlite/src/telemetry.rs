@@ -0,0 +1,181 @@
+//! Pluggable telemetry sinks.
+//!
+//! Embedders register a [`TelemetrySink`] on the runtime to receive init
+//! pipeline task spans, exec events, box state changes, crashes and published
+//! ports, and forward them to their own APM system. All methods have no-op
+//! defaults, so a sink only implements what it cares about.
+//!
+//! Callbacks run inline on the runtime's tasks; keep them cheap and
+//! non-blocking (e.g. push into a channel).
+
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use boxlite_shared::errors::BoxliteError;
+
+use crate::litebox::ExecutionId;
+use crate::runtime::types::{BoxID, BoxStatus, PortMapping};
+
+/// Receiver for runtime telemetry events.
+pub trait TelemetrySink: Send + Sync {
+    /// An init pipeline task started.
+    fn on_task_start(&self, _box_id: &BoxID, _task: &str) {}
+
+    /// An init pipeline task finished.
+    fn on_task_end(
+        &self,
+        _box_id: &BoxID,
+        _task: &str,
+        _duration: Duration,
+        _result: Result<(), &BoxliteError>,
+    ) {
+    }
+
+    /// A command was started (or failed to start) in a box.
+    fn on_exec(
+        &self,
+        _box_id: &BoxID,
+        _program: &str,
+        _args: &[String],
+        _result: Result<&ExecutionId, &BoxliteError>,
+    ) {
+    }
+
+    /// A box's persisted status changed. `from` is `None` when the box is
+    /// first persisted.
+    fn on_state_change(&self, _box_id: &BoxID, _from: Option<BoxStatus>, _to: BoxStatus) {}
+
+    /// A box's VM went away without being stopped. `pid` is the VM process
+    /// it last had. Reported after the box was marked stopped.
+    fn on_crash(&self, _box_id: &BoxID, _pid: Option<u32>) {}
+
+    /// A box booted with host ports forwarded to it, including the free
+    /// ports picked for `PortSpec::host_port` 0. Not reported for boxes
+    /// without ports.
+    fn on_ports_published(&self, _box_id: &BoxID, _ports: &[PortMapping]) {}
+}
+
+/// Registered sinks, shared by the runtime and its components.
+#[derive(Clone, Default)]
+pub(crate) struct Telemetry {
+    sinks: Arc<RwLock<Vec<Arc<dyn TelemetrySink>>>>,
+}
+
+impl Telemetry {
+    pub fn add_sink(&self, sink: Arc<dyn TelemetrySink>) {
+        self.sinks.write().unwrap().push(sink);
+    }
+
+    fn emit(&self, f: impl Fn(&dyn TelemetrySink)) {
+        for sink in self.sinks.read().unwrap().iter() {
+            f(sink.as_ref());
+        }
+    }
+
+    pub fn task_start(&self, box_id: &BoxID, task: &str) {
+        self.emit(|sink| sink.on_task_start(box_id, task));
+    }
+
+    pub fn task_end(
+        &self,
+        box_id: &BoxID,
+        task: &str,
+        duration: Duration,
+        result: Result<(), &BoxliteError>,
+    ) {
+        self.emit(|sink| sink.on_task_end(box_id, task, duration, result));
+    }
+
+    pub fn exec(
+        &self,
+        box_id: &BoxID,
+        program: &str,
+        args: &[String],
+        result: Result<&ExecutionId, &BoxliteError>,
+    ) {
+        self.emit(|sink| sink.on_exec(box_id, program, args, result));
+    }
+
+    pub fn state_change(&self, box_id: &BoxID, from: Option<BoxStatus>, to: BoxStatus) {
+        if from != Some(to) {
+            self.emit(|sink| sink.on_state_change(box_id, from, to));
+        }
+    }
+
+    pub fn crash(&self, box_id: &BoxID, pid: Option<u32>) {
+        self.emit(|sink| sink.on_crash(box_id, pid));
+    }
+
+    pub fn ports_published(&self, box_id: &BoxID, ports: &[PortMapping]) {
+        if !ports.is_empty() {
+            self.emit(|sink| sink.on_ports_published(box_id, ports));
+        }
+    }
+}
+
+impl std::fmt::Debug for Telemetry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Telemetry")
+            .field("sinks", &self.sinks.read().unwrap().len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct RecordingSink {
+        events: Mutex<Vec<String>>,
+    }
+
+    impl TelemetrySink for RecordingSink {
+        fn on_task_start(&self, _box_id: &BoxID, task: &str) {
+            self.events.lock().unwrap().push(format!("start {}", task));
+        }
+
+        fn on_state_change(&self, _box_id: &BoxID, from: Option<BoxStatus>, to: BoxStatus) {
+            self.events
+                .lock()
+                .unwrap()
+                .push(format!("{:?} -> {:?}", from, to));
+        }
+    }
+
+    #[test]
+    fn test_fans_out_to_all_sinks() {
+        let telemetry = Telemetry::default();
+        let a = Arc::new(RecordingSink::default());
+        let b = Arc::new(RecordingSink::default());
+        telemetry.add_sink(a.clone());
+        telemetry.add_sink(b.clone());
+
+        let box_id = BoxID::new();
+        telemetry.task_start(&box_id, "vmm_spawn");
+        // Default no-op methods
+        telemetry.task_end(&box_id, "vmm_spawn", Duration::ZERO, Ok(()));
+
+        for sink in [a, b] {
+            assert_eq!(*sink.events.lock().unwrap(), vec!["start vmm_spawn"]);
+        }
+    }
+
+    #[test]
+    fn test_skips_unchanged_state() {
+        let telemetry = Telemetry::default();
+        let sink = Arc::new(RecordingSink::default());
+        telemetry.add_sink(sink.clone());
+
+        let box_id = BoxID::new();
+        telemetry.state_change(&box_id, Some(BoxStatus::Running), BoxStatus::Running);
+        telemetry.state_change(&box_id, None, BoxStatus::Starting);
+        telemetry.state_change(&box_id, Some(BoxStatus::Starting), BoxStatus::Running);
+
+        assert_eq!(
+            *sink.events.lock().unwrap(),
+            vec!["None -> Starting", "Some(Starting) -> Running"]
+        );
+    }
+}
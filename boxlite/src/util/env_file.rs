@@ -0,0 +1,128 @@
+//! Dotenv-style environment files, read like docker's `--env-file`.
+//!
+//! Each line is `KEY=value`; the value is taken literally, quotes and
+//! spaces included. A line with just `KEY` takes the value from the host
+//! environment and is skipped if the host does not set it. Blank lines
+//! and lines starting with `#` are ignored.
+
+use std::path::{Path, PathBuf};
+
+use boxlite_shared::errors::{BoxliteError, BoxliteResult};
+
+/// Read the variables of `files`, later files overriding earlier ones,
+/// and put `env` on top.
+pub fn merge(
+    files: &[PathBuf],
+    env: Vec<(String, String)>,
+) -> BoxliteResult<Vec<(String, String)>> {
+    let mut merged: Vec<(String, String)> = Vec::new();
+    for path in files {
+        for (key, value) in read(path)? {
+            merged.retain(|(k, _)| *k != key);
+            merged.push((key, value));
+        }
+    }
+    merged.retain(|(key, _)| !env.iter().any(|(k, _)| k == key));
+    merged.extend(env);
+    Ok(merged)
+}
+
+/// Read the variables of one file, in order.
+pub fn read(path: &Path) -> BoxliteResult<Vec<(String, String)>> {
+    let content = std::fs::read_to_string(path).map_err(|e| {
+        BoxliteError::Config(format!("Failed to read env file {}: {}", path.display(), e))
+    })?;
+    parse(&content, |key| std::env::var(key).ok())
+        .map_err(|e| BoxliteError::Config(format!("{}: {}", path.display(), e)))
+}
+
+fn parse(
+    content: &str,
+    lookup: impl Fn(&str) -> Option<String>,
+) -> Result<Vec<(String, String)>, String> {
+    let mut vars = Vec::new();
+    for (number, line) in content.lines().enumerate() {
+        let line = line.trim_start();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (key, value) = match line.split_once('=') {
+            Some((key, value)) => (key, Some(value)),
+            None => (line, None),
+        };
+        if key.is_empty() {
+            return Err(format!("line {}: variable name is empty", number + 1));
+        }
+        if key.contains(char::is_whitespace) {
+            return Err(format!(
+                "line {}: variable '{}' contains whitespaces",
+                number + 1,
+                key
+            ));
+        }
+        let value = match value {
+            Some(value) => value.to_string(),
+            None => match lookup(key) {
+                Some(value) => value,
+                None => continue,
+            },
+        };
+        vars.push((key.to_string(), value));
+    }
+    Ok(vars)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_docker_semantics() {
+        let content = "\
+# database
+  DB_HOST=db.internal
+DB_PASS=\"quoted stays\"
+EMPTY=
+FROM_HOST
+UNSET_ON_HOST
+URL=http://x/?a=b
+";
+        let lookup = |key: &str| (key == "FROM_HOST").then(|| "host".to_string());
+        let vars = parse(content, lookup).unwrap();
+        assert_eq!(
+            vars,
+            [
+                ("DB_HOST".to_string(), "db.internal".to_string()),
+                ("DB_PASS".into(), "\"quoted stays\"".into()),
+                ("EMPTY".into(), "".into()),
+                ("FROM_HOST".into(), "host".into()),
+                ("URL".into(), "http://x/?a=b".into()),
+            ]
+        );
+
+        assert!(parse("BAD KEY=1", lookup).is_err());
+        assert!(parse("=1", lookup).is_err());
+    }
+
+    #[test]
+    fn test_merge_precedence() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = dir.path().join("base.env");
+        let local = dir.path().join("local.env");
+        std::fs::write(&base, "A=base\nB=base\nC=base\n").unwrap();
+        std::fs::write(&local, "B=local\n").unwrap();
+
+        let env = vec![("C".to_string(), "explicit".to_string())];
+        let merged = merge(&[base, local], env).unwrap();
+        assert_eq!(
+            merged,
+            [
+                ("A".to_string(), "base".to_string()),
+                ("B".into(), "local".into()),
+                ("C".into(), "explicit".into()),
+            ]
+        );
+
+        assert!(merge(&[dir.path().join("missing.env")], Vec::new()).is_err());
+    }
+}
@@ -1,16 +1,18 @@
+pub mod env_file;
 pub mod process;
 
 use std::path::PathBuf;
 use std::process::Command;
+use std::sync::OnceLock;
 
 use boxlite_shared::errors::{BoxliteError, BoxliteResult};
 use tracing_appender::non_blocking::NonBlocking;
 use tracing_subscriber::layer::SubscriberExt;
 use tracing_subscriber::util::SubscriberInitExt;
-use tracing_subscriber::{EnvFilter, fmt};
+use tracing_subscriber::{EnvFilter, Registry, fmt, reload};
 
 // Re-export process utilities
-pub use process::{is_process_alive, is_same_process, kill_process};
+pub use process::{has_exited, is_process_alive, is_same_process, kill_process};
 
 #[cfg(any(target_os = "linux", target_os = "macos"))]
 unsafe extern "C" {
@@ -158,7 +160,8 @@ pub fn find_binary(binary_name: &str) -> BoxliteResult<PathBuf> {
 }
 
 pub fn register_to_tracing(non_blocking: NonBlocking, env_filter: EnvFilter) {
-    let _ = tracing_subscriber::registry()
+    let (env_filter, handle) = reload::Layer::new(env_filter);
+    let registered = tracing_subscriber::registry()
         .with(env_filter)
         .with(
             fmt::layer()
@@ -170,6 +173,28 @@ pub fn register_to_tracing(non_blocking: NonBlocking, env_filter: EnvFilter) {
                 .with_ansi(false),
         )
         .try_init();
+
+    if registered.is_ok() {
+        let _ = LOG_FILTER.set(handle);
+    }
+}
+
+/// Filter of the subscriber installed by [`register_to_tracing`].
+static LOG_FILTER: OnceLock<reload::Handle<EnvFilter, Registry>> = OnceLock::new();
+
+/// Replace the log filter installed by [`register_to_tracing`].
+///
+/// Does nothing if the host application installed its own subscriber.
+pub fn reload_log_filter(directives: &str) -> BoxliteResult<()> {
+    let filter = EnvFilter::try_new(directives)
+        .map_err(|e| BoxliteError::Config(format!("invalid log filter '{}': {}", directives, e)))?;
+
+    if let Some(handle) = LOG_FILTER.get() {
+        handle
+            .reload(filter)
+            .map_err(|e| BoxliteError::Internal(format!("failed to reload log filter: {}", e)))?;
+    }
+    Ok(())
 }
 
 /// Inject guest binary into a rootfs directory.
@@ -293,6 +318,20 @@ fn is_binary_up_to_date(source: &std::path::Path, dest: &std::path::Path) -> Box
     Ok(false)
 }
 
+/// Disk space used by the files under `path`, in bytes.
+///
+/// Counts allocated blocks, so sparse disk images count for what they hold.
+pub fn disk_usage(path: &std::path::Path) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+
+    walkdir::WalkDir::new(path)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter_map(|entry| entry.metadata().ok())
+        .map(|metadata| metadata.blocks() * 512)
+        .sum()
+}
+
 /// Auto-detect terminal size like Docker does
 /// Returns (rows, cols) tuple
 pub fn get_terminal_size() -> (u32, u32) {
@@ -450,4 +489,18 @@ mod tests {
             "Setuid bit should be preserved in xattr"
         );
     }
+
+    #[test]
+    fn test_disk_usage() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("sub")).unwrap();
+        std::fs::write(dir.path().join("sub/data"), vec![1u8; 64 * 1024]).unwrap();
+        // Sparse: allocates (almost) nothing
+        let sparse = std::fs::File::create(dir.path().join("sparse")).unwrap();
+        sparse.set_len(1 << 30).unwrap();
+
+        let usage = super::disk_usage(dir.path());
+        assert!(usage >= 64 * 1024);
+        assert!(usage < 1 << 30);
+    }
 }
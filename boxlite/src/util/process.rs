@@ -20,6 +20,39 @@ pub fn is_process_alive(pid: u32) -> bool {
     unsafe { libc::kill(pid as i32, 0) == 0 }
 }
 
+/// Check if a process has exited.
+///
+/// Unlike [`is_process_alive`], this also reports a child of ours that
+/// exited but was not waited for yet (a zombie). The zombie is left for
+/// its owner to reap.
+pub fn has_exited(pid: u32) -> bool {
+    let mut info: libc::siginfo_t = unsafe { std::mem::zeroed() };
+    let ret = unsafe {
+        libc::waitid(
+            libc::P_PID,
+            pid as libc::id_t,
+            &mut info,
+            libc::WEXITED | libc::WNOHANG | libc::WNOWAIT,
+        )
+    };
+    if ret == 0 {
+        // si_pid stays 0 while the child is still running
+        return siginfo_pid(&info) != 0;
+    }
+    // Not our child
+    !is_process_alive(pid)
+}
+
+#[cfg(target_os = "linux")]
+fn siginfo_pid(info: &libc::siginfo_t) -> libc::pid_t {
+    unsafe { info.si_pid() }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn siginfo_pid(info: &libc::siginfo_t) -> libc::pid_t {
+    info.si_pid
+}
+
 /// Verify that a PID belongs to a boxlite-shim process for the given box.
 ///
 /// This prevents PID reuse attacks where a PID is recycled for a different process.
@@ -111,6 +144,20 @@ mod tests {
         assert!(!is_process_alive(888888888));
     }
 
+    #[test]
+    fn test_has_exited_zombie_child() {
+        let mut child = std::process::Command::new("true").spawn().unwrap();
+        let pid = child.id();
+        while !has_exited(pid) {
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        // Still a zombie, so it looks alive to kill(0)
+        assert!(is_process_alive(pid));
+        child.wait().unwrap();
+
+        assert!(!has_exited(std::process::id()));
+    }
+
     #[test]
     fn test_is_same_process_current() {
         let current_pid = std::process::id();
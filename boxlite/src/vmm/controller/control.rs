@@ -0,0 +1,260 @@
+//! Control socket of the shim process.
+//!
+//! The shim serves a Unix socket among the box's sockets, on which the host
+//! asks for what only the shim knows or can do, such as the counters of the
+//! network backend running in it or a capture of the box's traffic. Each
+//! connection carries one JSON request line and one JSON response line, on
+//! its own thread so that a capture does not hold up other requests.
+
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use boxlite_shared::errors::{BoxliteError, BoxliteResult};
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt};
+
+use crate::net::{CaptureFilter, CaptureStats, NetworkMetrics};
+
+/// How long the host waits for an answer; a paused shim gives none.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Serialize, Deserialize)]
+enum ShimRequest {
+    NetworkMetrics,
+    CaptureTraffic {
+        path: PathBuf,
+        filter: CaptureFilter,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+enum ShimResponse {
+    /// None when the backend keeps no counters
+    NetworkMetrics(Option<NetworkMetrics>),
+    CaptureTraffic(CaptureStats),
+    Error(String),
+}
+
+/// Counters of the shim's network backend.
+pub type NetworkMetricsSource =
+    Box<dyn Fn() -> BoxliteResult<Option<NetworkMetrics>> + Send + Sync>;
+
+/// Capture of the box's traffic by the shim's network backend, blocking
+/// until it ends.
+pub type TrafficCapture =
+    Box<dyn Fn(&Path, &CaptureFilter) -> BoxliteResult<CaptureStats> + Send + Sync>;
+
+/// What the shim answers control requests with; by default, those of a
+/// backend without counters or captures.
+pub struct ControlHandlers {
+    pub network_metrics: NetworkMetricsSource,
+    pub capture_traffic: TrafficCapture,
+}
+
+impl Default for ControlHandlers {
+    fn default() -> Self {
+        Self {
+            network_metrics: Box::new(|| Ok(None)),
+            capture_traffic: Box::new(|_, _| {
+                Err(BoxliteError::Unsupported(
+                    "the network backend does not capture traffic".to_string(),
+                ))
+            }),
+        }
+    }
+}
+
+/// Serve the control socket at `path` on a thread, for the life of the
+/// shim.
+pub fn serve(path: &Path, handlers: ControlHandlers) -> BoxliteResult<()> {
+    // Left behind by an earlier run of the box
+    let _ = std::fs::remove_file(path);
+    let listener = UnixListener::bind(path).map_err(|e| {
+        BoxliteError::Engine(format!(
+            "Failed to bind control socket {}: {}",
+            path.display(),
+            e
+        ))
+    })?;
+
+    let handlers = Arc::new(handlers);
+    std::thread::Builder::new()
+        .name("shim-control".into())
+        .spawn(move || {
+            for conn in listener.incoming() {
+                let conn = match conn {
+                    Ok(conn) => conn,
+                    Err(e) => {
+                        tracing::debug!(error = %e, "Failed to accept control connection");
+                        continue;
+                    }
+                };
+                let handlers = Arc::clone(&handlers);
+                std::thread::spawn(move || {
+                    if let Err(e) = handle(conn, &handlers) {
+                        tracing::debug!(error = %e, "Control request failed");
+                    }
+                });
+            }
+        })?;
+    Ok(())
+}
+
+fn handle(conn: UnixStream, handlers: &ControlHandlers) -> std::io::Result<()> {
+    let mut line = String::new();
+    BufReader::new(&conn).read_line(&mut line)?;
+    let response = match serde_json::from_str(&line) {
+        Ok(ShimRequest::NetworkMetrics) => match (handlers.network_metrics)() {
+            Ok(metrics) => ShimResponse::NetworkMetrics(metrics),
+            Err(e) => ShimResponse::Error(e.to_string()),
+        },
+        Ok(ShimRequest::CaptureTraffic { path, filter }) => {
+            match (handlers.capture_traffic)(&path, &filter) {
+                Ok(stats) => ShimResponse::CaptureTraffic(stats),
+                Err(e) => ShimResponse::Error(e.to_string()),
+            }
+        }
+        Err(e) => ShimResponse::Error(format!("Invalid request: {}", e)),
+    };
+    let mut out = serde_json::to_vec(&response)?;
+    out.push(b'\n');
+    (&conn).write_all(&out)
+}
+
+async fn request(
+    path: &Path,
+    request: ShimRequest,
+    timeout: Duration,
+) -> BoxliteResult<ShimResponse> {
+    let exchange = async {
+        let mut stream = tokio::net::UnixStream::connect(path).await?;
+        let mut line = serde_json::to_vec(&request)?;
+        line.push(b'\n');
+        stream.write_all(&line).await?;
+        let mut response = String::new();
+        tokio::io::BufReader::new(stream)
+            .read_line(&mut response)
+            .await?;
+        Ok::<_, std::io::Error>(serde_json::from_str(&response)?)
+    };
+    match tokio::time::timeout(timeout, exchange).await {
+        Ok(Ok(response)) => Ok(response),
+        Ok(Err(e)) => Err(BoxliteError::Engine(format!(
+            "Shim control request failed: {}",
+            e
+        ))),
+        Err(_) => Err(BoxliteError::Timeout(
+            "shim did not answer control request".to_string(),
+        )),
+    }
+}
+
+/// Counters of the network backend of the shim serving `path`.
+pub(crate) async fn network_metrics(path: &Path) -> BoxliteResult<Option<NetworkMetrics>> {
+    match request(path, ShimRequest::NetworkMetrics, REQUEST_TIMEOUT).await? {
+        ShimResponse::NetworkMetrics(metrics) => Ok(metrics),
+        ShimResponse::Error(e) => Err(BoxliteError::Network(e)),
+        other => Err(unexpected(other)),
+    }
+}
+
+/// Have the shim serving `path` capture the box's traffic to `capture`,
+/// waiting for the capture to end.
+pub(crate) async fn capture_traffic(
+    path: &Path,
+    capture: &Path,
+    filter: &CaptureFilter,
+) -> BoxliteResult<CaptureStats> {
+    let capture_request = ShimRequest::CaptureTraffic {
+        path: capture.to_path_buf(),
+        filter: filter.clone(),
+    };
+    // The shim answers once the capture ends
+    match request(path, capture_request, filter.duration + REQUEST_TIMEOUT).await? {
+        ShimResponse::CaptureTraffic(stats) => Ok(stats),
+        ShimResponse::Error(e) => Err(BoxliteError::Network(e)),
+        other => Err(unexpected(other)),
+    }
+}
+
+fn unexpected(response: ShimResponse) -> BoxliteError {
+    BoxliteError::Internal(format!("Unexpected shim response: {:?}", response))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_network_metrics() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("shim.sock");
+        serve(
+            &path,
+            ControlHandlers {
+                network_metrics: Box::new(|| {
+                    Ok(Some(NetworkMetrics {
+                        bytes_sent: 1024,
+                        bytes_received: 2048,
+                        tcp_connections: Some(3),
+                        tcp_connection_errors: None,
+                        forwarded_connections: Some(1),
+                    }))
+                }),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let metrics = network_metrics(&path).await.unwrap().unwrap();
+        assert_eq!(metrics.bytes_sent, 1024);
+        assert_eq!(metrics.bytes_received, 2048);
+        assert_eq!(metrics.tcp_connections, Some(3));
+        assert_eq!(metrics.forwarded_connections, Some(1));
+
+        assert!(
+            network_metrics(&dir.path().join("missing.sock"))
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_capture_traffic() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("shim.sock");
+        serve(
+            &path,
+            ControlHandlers {
+                capture_traffic: Box::new(|capture, filter| {
+                    assert_eq!(capture, Path::new("/tmp/box.pcap"));
+                    assert_eq!(filter.port, Some(443));
+                    Ok(CaptureStats {
+                        packets: 2,
+                        bytes: 152,
+                        truncated: false,
+                    })
+                }),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let filter = CaptureFilter {
+            port: Some(443),
+            duration: Duration::from_millis(10),
+            ..Default::default()
+        };
+        let stats = capture_traffic(&path, Path::new("/tmp/box.pcap"), &filter)
+            .await
+            .unwrap();
+        assert_eq!(stats.packets, 2);
+        assert_eq!(stats.bytes, 152);
+
+        // Still answering other requests
+        assert!(network_metrics(&path).await.unwrap().is_none());
+    }
+}
@@ -10,6 +10,7 @@ use boxlite_shared::BoxliteResult;
 ///
 /// The handler is purely about VM lifecycle management:
 /// - Stop the VM
+/// - Pause and resume the VM
 /// - Get VM metrics
 /// - Check if running
 /// - Get process ID
@@ -19,6 +20,12 @@ pub trait VmmHandler: Send {
     /// Stop the VM.
     fn stop(&mut self) -> BoxliteResult<()>;
 
+    /// Freeze the VM's vCPUs.
+    fn pause(&mut self) -> BoxliteResult<()>;
+
+    /// Thaw a VM frozen by [`pause`](Self::pause).
+    fn resume(&mut self) -> BoxliteResult<()>;
+
     /// Get VM metrics (CPU, memory, disk usage).
     fn metrics(&self) -> BoxliteResult<VmmMetrics>;
 
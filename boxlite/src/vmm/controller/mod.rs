@@ -14,6 +14,7 @@
 //! - Clear lifecycle boundaries (spawn vs runtime)
 //! - Caller-controlled GuestSession creation
 
+pub mod control;
 mod handler;
 mod shim;
 mod spawn;
@@ -66,6 +66,17 @@ impl ShimHandler {
             metrics_sys: Mutex::new(sysinfo::System::new()),
         }
     }
+
+    fn signal(&self, signal: libc::c_int) -> BoxliteResult<()> {
+        if unsafe { libc::kill(self.pid as i32, signal) } != 0 {
+            return Err(BoxliteError::Engine(format!(
+                "Failed to signal VM process {}: {}",
+                self.pid,
+                std::io::Error::last_os_error()
+            )));
+        }
+        Ok(())
+    }
 }
 
 impl VmmHandlerTrait for ShimHandler {
@@ -88,6 +99,16 @@ impl VmmHandlerTrait for ShimHandler {
         Ok(())
     }
 
+    fn pause(&mut self) -> BoxliteResult<()> {
+        // The engines have no freeze API; stopping the shim process stops
+        // every vCPU thread (and the network backend) at once
+        self.signal(libc::SIGSTOP)
+    }
+
+    fn resume(&mut self) -> BoxliteResult<()> {
+        self.signal(libc::SIGCONT)
+    }
+
     fn metrics(&self) -> BoxliteResult<VmmMetrics> {
         use sysinfo::Pid;
 
@@ -195,10 +216,16 @@ impl VmmController for ShimController {
             guest_rootfs: config.guest_rootfs.clone(),
             network_config: config.network_config.clone(), // Pass port mappings to subprocess (shim creates gvproxy)
             network_backend_endpoint: None, // Will be populated by shim (not serialized)
+            network_disabled: config.network_disabled,
+            network_attachments: config.network_attachments.clone(),
+            host_sockets: config.host_sockets.clone(),
+            reverse_ports: config.reverse_ports.clone(),
+            control_socket: config.control_socket.clone(),
             home_dir: config.home_dir.clone(),
             console_output: config.console_output.clone(),
             detach: config.detach,
             parent_pid: config.parent_pid,
+            memory_policy: config.memory_policy.clone(),
         };
 
         // Serialize the config for passing to subprocess
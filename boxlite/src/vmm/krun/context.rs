@@ -6,13 +6,14 @@
 
 #![allow(clippy::missing_safety_doc)]
 
-use std::{ffi::CString, ptr};
+use std::{ffi::CString, os::fd::RawFd, ptr};
 
 use crate::vmm::krun::check_status;
 use boxlite_shared::errors::{BoxliteError, BoxliteResult};
 use libkrun_sys::{
-    krun_add_disk2, krun_add_net_unixgram, krun_add_net_unixstream, krun_add_virtiofs,
-    krun_add_vsock_port2, krun_create_ctx, krun_free_ctx, krun_init_log, krun_set_console_output,
+    krun_add_disk2, krun_add_net_tap, krun_add_net_unixgram, krun_add_net_unixstream,
+    krun_add_virtiofs, krun_add_vsock, krun_add_vsock_port2, krun_create_ctx,
+    krun_disable_implicit_vsock, krun_free_ctx, krun_init_log, krun_set_console_output,
     krun_set_env, krun_set_exec, krun_set_gpu_options, krun_set_kernel, krun_set_nested_virt,
     krun_set_port_map, krun_set_rlimits, krun_set_root, krun_set_root_disk_remount,
     krun_set_vm_config, krun_set_workdir, krun_setgid, krun_setuid, krun_split_irqchip,
@@ -415,6 +416,51 @@ impl KrunContext {
         }
     }
 
+    /// Add a network interface whose frames go through a connected datagram
+    /// socket, one frame per datagram.
+    ///
+    /// No offloads are negotiated: checksum state would not survive the
+    /// socket.
+    pub unsafe fn add_net_fd(&self, fd: RawFd, mac_address: [u8; 6]) -> BoxliteResult<()> {
+        tracing::debug!(fd, mac_address = ?mac_address, "Adding network interface via fd");
+        check_status("krun_add_net_unixgram", unsafe {
+            krun_add_net_unixgram(self.ctx_id, ptr::null(), fd, mac_address.as_ptr(), 0, 0)
+        })
+    }
+
+    /// Add a network interface backed by the host's TAP device `tap_name`.
+    pub unsafe fn add_net_tap(
+        &self,
+        tap_name: &str,
+        features: u32,
+        mac_address: [u8; 6],
+    ) -> BoxliteResult<()> {
+        tracing::debug!(tap_name, features, mac_address = ?mac_address, "Adding network interface via TAP device");
+        let tap_name_c = CString::new(tap_name)
+            .map_err(|e| BoxliteError::Engine(format!("invalid TAP device name: {e}")))?;
+        check_status("krun_add_net_tap", unsafe {
+            krun_add_net_tap(
+                self.ctx_id,
+                tap_name_c.as_ptr(),
+                mac_address.as_ptr(),
+                features,
+                0,
+            )
+        })
+    }
+
+    /// Replace the default vsock device with one without TSI, so the guest
+    /// has no network unless a network device is added.
+    ///
+    /// Must be called before `add_vsock_port`.
+    pub unsafe fn disable_tsi(&self) -> BoxliteResult<()> {
+        tracing::debug!("Disabling TSI");
+        check_status("krun_disable_implicit_vsock", unsafe {
+            krun_disable_implicit_vsock(self.ctx_id)
+        })?;
+        check_status("krun_add_vsock", unsafe { krun_add_vsock(self.ctx_id, 0) })
+    }
+
     /// Add a virtiofs mount, sharing a host directory with the guest.
     ///
     /// # Arguments
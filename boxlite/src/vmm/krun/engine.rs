@@ -292,12 +292,33 @@ impl Vmm for Krun {
 
                         tracing::debug!("Successfully configured Unix socket net");
                     }
+                    crate::net::NetworkBackendEndpoint::Tap { name, mac_address } => {
+                        use crate::vmm::krun::constants::network_features::*;
+                        let features = NET_FEATURE_CSUM
+                            | NET_FEATURE_GUEST_CSUM
+                            | NET_FEATURE_GUEST_TSO4
+                            | NET_FEATURE_GUEST_UFO
+                            | NET_FEATURE_HOST_TSO4
+                            | NET_FEATURE_HOST_UFO;
+                        ctx.add_net_tap(name, features, *mac_address)?;
+                        tracing::debug!(tap = %name, "Successfully configured TAP net");
+                    }
                 }
+            } else if config.network_disabled {
+                // Offline box: no virtio-net, and no TSI sockets either
+                tracing::info!("Network disabled - no network device or TSI");
+                ctx.disable_tsi()?;
             } else {
                 // No network connection specified - use libkrun's built-in TSI net
                 tracing::debug!("No network backend - using libkrun's built-in TSI net");
             }
 
+            // Inter-box networks, after the primary interface so it stays eth0
+            for attachment in &config.network_attachments {
+                let fd = crate::net::switch::connect(attachment)?;
+                ctx.add_net_fd(fd, attachment.mac_address)?;
+            }
+
             // Raise RLIMIT_NOFILE to maximum - CRITICAL for virtio-fs!
             // This must be done BEFORE mounting virtiofs shares
             tracing::debug!("Raising RLIMIT_NOFILE for virtio-fs");
@@ -443,6 +464,42 @@ impl Vmm for Krun {
             );
             ctx.add_vsock_port(network::GUEST_READY_PORT, ready_socket_path, false)?;
 
+            // Host sockets exposed in the container: libkrun connects to the
+            // host socket for each guest connection
+            for (port, host_socket) in (network::HOST_SOCKET_BASE_PORT..).zip(&config.host_sockets)
+            {
+                let host_socket = host_socket.to_str().ok_or_else(|| {
+                    BoxliteError::Engine(format!(
+                        "Invalid host socket path: {}",
+                        host_socket.display()
+                    ))
+                })?;
+                tracing::debug!(
+                    socket_path = host_socket,
+                    guest_port = port,
+                    "Configuring vsock bridge for host socket"
+                );
+                ctx.add_vsock_port(port, host_socket, false)?;
+            }
+
+            // Reverse ports: like host sockets, with TCP targets relayed
+            // from a socket of their own
+            for (port, reverse) in (network::REVERSE_PORT_BASE_PORT..).zip(&config.reverse_ports) {
+                let host_socket = reverse.host_socket()?;
+                let host_socket = host_socket.to_str().ok_or_else(|| {
+                    BoxliteError::Engine(format!(
+                        "Invalid reverse port socket path: {}",
+                        host_socket.display()
+                    ))
+                })?;
+                tracing::debug!(
+                    socket_path = host_socket,
+                    guest_port = port,
+                    "Configuring vsock bridge for reverse port"
+                );
+                ctx.add_vsock_port(port, host_socket, false)?;
+            }
+
             // Configure console output redirection if specified
             if let Some(console_path) = &config.console_output {
                 let console_path_str = console_path.to_str().ok_or_else(|| {
@@ -0,0 +1,136 @@
+//! Host memory policy for VMM processes.
+//!
+//! Applied by the shim to its own process before the engine allocates guest
+//! memory. Both settings are process-wide and inherited by the guest memory
+//! mappings created afterwards:
+//!
+//! - KSM: `prctl(PR_SET_MEMORY_MERGE)` marks all mappings mergeable (Linux 6.4+)
+//! - THP: `prctl(PR_SET_THP_DISABLE)` disables hugepages, optionally except
+//!   for `madvise`d regions (Linux 6.18+)
+//!
+//! Failures are logged and ignored: a box should still start on a kernel that
+//! lacks one of these knobs.
+
+use crate::runtime::options::MemoryPolicy;
+#[cfg(target_os = "linux")]
+use crate::runtime::options::ThpPolicy;
+
+#[cfg(target_os = "linux")]
+const PR_SET_MEMORY_MERGE: libc::c_int = 67;
+
+#[cfg(target_os = "linux")]
+const PR_THP_DISABLE_EXCEPT_ADVISED: libc::c_ulong = 1 << 1;
+
+#[cfg(target_os = "linux")]
+const KSM_RUN_PATH: &str = "/sys/kernel/mm/ksm/run";
+
+#[cfg(target_os = "linux")]
+const THP_ENABLED_PATH: &str = "/sys/kernel/mm/transparent_hugepage/enabled";
+
+/// Apply the memory policy to the current process.
+#[cfg(target_os = "linux")]
+pub fn apply_memory_policy(policy: &MemoryPolicy) {
+    if policy.ksm {
+        enable_ksm();
+    }
+
+    match policy.thp {
+        ThpPolicy::System => {}
+        ThpPolicy::Always => {
+            set_thp_disable(0, 0, "always");
+            if system_thp_mode().as_deref() != Some("always") {
+                tracing::warn!(
+                    system = ?system_thp_mode(),
+                    "THP policy 'always' requested but system-wide mode is not 'always'; \
+                     only madvise'd regions will use hugepages"
+                );
+            }
+        }
+        ThpPolicy::Madvise => set_thp_disable(1, PR_THP_DISABLE_EXCEPT_ADVISED, "madvise"),
+        ThpPolicy::Never => set_thp_disable(1, 0, "never"),
+    }
+}
+
+/// Apply the memory policy to the current process.
+#[cfg(not(target_os = "linux"))]
+pub fn apply_memory_policy(policy: &MemoryPolicy) {
+    if *policy != MemoryPolicy::default() {
+        tracing::warn!(
+            ?policy,
+            "Memory policy is only supported on Linux, ignoring"
+        );
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn enable_ksm() {
+    // SAFETY: prctl with integer arguments only, no pointers involved
+    let ret = unsafe { libc::prctl(PR_SET_MEMORY_MERGE, 1, 0, 0, 0) };
+    if ret != 0 {
+        tracing::warn!(
+            error = %std::io::Error::last_os_error(),
+            "Failed to enable KSM for VMM process (requires Linux 6.4+)"
+        );
+        return;
+    }
+
+    let running = std::fs::read_to_string(KSM_RUN_PATH)
+        .map(|s| s.trim() == "1")
+        .unwrap_or(false);
+    if running {
+        tracing::info!("KSM enabled for guest memory");
+    } else {
+        tracing::warn!(
+            "KSM enabled for guest memory, but ksmd is not running ({} != 1)",
+            KSM_RUN_PATH
+        );
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn set_thp_disable(disable: libc::c_ulong, flags: libc::c_ulong, mode: &str) {
+    // SAFETY: prctl with integer arguments only, no pointers involved
+    let ret = unsafe { libc::prctl(libc::PR_SET_THP_DISABLE, disable, flags, 0, 0) };
+    if ret != 0 {
+        tracing::warn!(
+            mode,
+            error = %std::io::Error::last_os_error(),
+            "Failed to apply THP policy for VMM process"
+        );
+    } else {
+        tracing::info!(mode, "Applied THP policy for guest memory");
+    }
+}
+
+/// Read the active system-wide THP mode (the bracketed entry).
+#[cfg(target_os = "linux")]
+fn system_thp_mode() -> Option<String> {
+    let content = std::fs::read_to_string(THP_ENABLED_PATH).ok()?;
+    parse_thp_mode(&content)
+}
+
+#[cfg(target_os = "linux")]
+fn parse_thp_mode(content: &str) -> Option<String> {
+    content
+        .split_whitespace()
+        .find(|word| word.starts_with('[') && word.ends_with(']'))
+        .map(|word| word.trim_matches(|c| c == '[' || c == ']').to_string())
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_thp_mode() {
+        assert_eq!(
+            parse_thp_mode("always [madvise] never\n").as_deref(),
+            Some("madvise")
+        );
+        assert_eq!(
+            parse_thp_mode("[always] madvise never").as_deref(),
+            Some("always")
+        );
+        assert_eq!(parse_thp_mode("always madvise never"), None);
+    }
+}
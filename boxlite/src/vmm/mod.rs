@@ -9,6 +9,7 @@ pub mod controller;
 pub mod engine;
 pub mod factory;
 pub mod krun;
+pub mod memory;
 pub mod registry;
 
 use crate::runtime::guest_rootfs::GuestRootfs;
@@ -161,6 +162,24 @@ pub struct InstanceSpec {
     /// This is not serialized; it's set in-process by the shim before calling the engine.
     #[serde(skip)]
     pub network_backend_endpoint: Option<crate::net::NetworkBackendEndpoint>,
+    /// Give the guest no network at all (`NetworkSpec::None`), not even the
+    /// engine's default one.
+    #[serde(default)]
+    pub network_disabled: bool,
+    /// Extra interfaces on inter-box networks (`BoxOptions::networks`), in order.
+    #[serde(default)]
+    pub network_attachments: Vec<crate::net::switch::NetworkAttachment>,
+    /// Host Unix sockets the guest reaches on vsock ports from
+    /// `HOST_SOCKET_BASE_PORT`, in order (`BoxOptions::sockets`).
+    #[serde(default)]
+    pub host_sockets: Vec<PathBuf>,
+    /// Host services the guest reaches on vsock ports from
+    /// `REVERSE_PORT_BASE_PORT`, in order (`BoxOptions::reverse_ports`).
+    #[serde(default)]
+    pub reverse_ports: Vec<crate::net::reverse::ReversePort>,
+    /// Control socket the shim serves, see [`controller::control`].
+    #[serde(default)]
+    pub control_socket: Option<PathBuf>,
     /// Home directory for boxlite runtime (~/.boxlite or BOXLITE_HOME)
     pub home_dir: PathBuf,
     /// Optional file path to redirect console output (kernel/init messages)
@@ -171,6 +190,9 @@ pub struct InstanceSpec {
     /// PID of the parent process that spawned this box.
     /// Used by watchdog to detect when parent exits (if detach=false).
     pub parent_pid: u32,
+    /// Host memory policy (KSM/THP), applied by the shim before the VM is created.
+    #[serde(default)]
+    pub memory_policy: crate::runtime::options::MemoryPolicy,
 }
 
 /// Entrypoint configuration that the guest should run.
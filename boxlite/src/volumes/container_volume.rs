@@ -7,9 +7,12 @@
 //! - Host: Only tracks volume_name, doesn't know guest paths
 //! - Guest: Constructs paths from `/run/boxlite/shared/containers/{container_id}/volumes/{volume_name}`
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+
+use boxlite_shared::layout::{GUEST_BASE, SharedGuestLayout};
 
 use super::guest_volume::GuestVolumeManager;
+use crate::disk::DiskFormat;
 
 /// Container bind mount entry.
 ///
@@ -23,6 +26,8 @@ pub struct ContainerMount {
     pub destination: String,
     /// Read-only mount
     pub read_only: bool,
+    /// File in the volume to bind instead of the whole volume
+    pub subpath: Option<String>,
 }
 
 /// Manages container-level volume configuration.
@@ -85,18 +90,75 @@ impl<'a> ContainerVolumeManager<'a> {
             volume_name: volume_name.to_string(),
             destination: container_path.to_string(),
             read_only,
+            subpath: None,
         });
     }
 
+    /// Add a single host file.
+    ///
+    /// virtiofs only shares directories, so `host_dir`, a staging directory
+    /// holding only the file, is shared with the guest VM and `file_name`
+    /// in it is bind mounted into the container.
+    pub fn add_file_volume(
+        &mut self,
+        container_id: &str,
+        tag: &str,
+        host_dir: PathBuf,
+        file_name: &str,
+        container_path: &str,
+        read_only: bool,
+    ) {
+        self.guest.add_fs_share(
+            tag,
+            host_dir,
+            None,
+            read_only,
+            Some(container_id.to_string()),
+        );
+        self.container_mounts.push(ContainerMount {
+            volume_name: tag.to_string(),
+            destination: container_path.to_string(),
+            read_only,
+            subpath: Some(file_name.to_string()),
+        });
+    }
+
+    /// Add a named volume backed by an ext4 disk image.
+    ///
+    /// The guest mounts the block device at the convention path of
+    /// `volume_name`, from which it is bind mounted into the container like
+    /// a shared volume.
+    pub fn add_disk_volume(
+        &mut self,
+        container_id: &str,
+        volume_name: &str,
+        disk_path: &Path,
+        container_path: &str,
+        read_only: bool,
+    ) {
+        let guest_mount = SharedGuestLayout::new(Path::new(GUEST_BASE).join("shared"))
+            .container(container_id)
+            .volume_dir(volume_name);
+        self.guest.add_block_device(
+            disk_path,
+            DiskFormat::Ext4,
+            read_only,
+            Some(&guest_mount.to_string_lossy()),
+            false, // need_format: formatted when the volume was created
+            false,
+        );
+        self.add_bind(volume_name, container_path, read_only);
+    }
+
     /// Add a container bind mount directly.
     ///
     /// Use when guest path already exists (e.g., from block device mount).
-    #[allow(dead_code)]
     pub fn add_bind(&mut self, volume_name: &str, container_path: &str, read_only: bool) {
         self.container_mounts.push(ContainerMount {
             volume_name: volume_name.to_string(),
             destination: container_path.to_string(),
             read_only,
+            subpath: None,
         });
     }
 
@@ -1,9 +1,12 @@
 //! Integration tests for box lifecycle (create, list, get, remove, stop).
 
-use boxlite::BoxliteRuntime;
-use boxlite::runtime::options::{BoxOptions, BoxliteOptions, RootfsSpec};
-use boxlite::runtime::types::{BoxID, BoxStatus};
-use boxlite_shared::Transport;
+use boxlite::runtime::options::{
+    BoxOptions, BoxliteOptions, CloneOptions, DropBehavior, IdleAction, RootfsSpec,
+};
+use boxlite::runtime::types::{BoxID, BoxStatus, ExitReason};
+use boxlite::{BoxCommand, BoxliteRuntime, LiteBox};
+use boxlite_shared::{BoxliteError, Transport};
+use std::time::Duration;
 use tempfile::TempDir;
 
 // ============================================================================
@@ -21,6 +24,7 @@ impl TestContext {
         let temp_dir = TempDir::new().expect("Failed to create temp dir");
         let options = BoxliteOptions {
             home_dir: temp_dir.path().to_path_buf(),
+            ..Default::default()
         };
         let runtime = BoxliteRuntime::new(options).expect("Failed to create runtime");
         Self {
@@ -294,6 +298,40 @@ async fn exists_returns_false_for_nonexistent() {
     assert!(!ctx.runtime.exists("nonexistent-id").unwrap());
 }
 
+// ============================================================================
+// CREATE AND START TESTS
+// ============================================================================
+
+#[tokio::test]
+async fn create_and_start_leaves_nothing_behind_on_timeout() {
+    let ctx = TestContext::new();
+    let result = ctx
+        .runtime
+        .create_and_start(
+            BoxOptions {
+                rootfs: RootfsSpec::Image("alpine:latest".into()),
+                ..Default::default()
+            },
+            Some("deadline-box".into()),
+            Duration::ZERO,
+        )
+        .await;
+    assert!(result.is_err());
+
+    assert!(!ctx.runtime.exists("deadline-box").unwrap());
+    assert!(ctx.runtime.list_info().unwrap().is_empty());
+
+    // Name is free again
+    let handle = ctx
+        .runtime
+        .create(BoxOptions::default(), Some("deadline-box".into()))
+        .unwrap();
+    ctx.runtime
+        .remove(handle.id().as_str(), true)
+        .await
+        .unwrap();
+}
+
 // ============================================================================
 // REMOVE TESTS (BoxliteRuntime::remove)
 // ============================================================================
@@ -456,6 +494,392 @@ async fn stop_marks_box_as_stopped() {
     ctx.runtime.remove(box_id.as_str(), false).await.unwrap();
 }
 
+#[tokio::test]
+async fn on_drop_stop_waits_for_last_user_handle() {
+    let ctx = TestContext::new();
+    let handle = ctx
+        .runtime
+        .create(
+            BoxOptions {
+                rootfs: RootfsSpec::Image("alpine:latest".into()),
+                auto_remove: false,
+                on_drop: DropBehavior::Stop,
+                ..Default::default()
+            },
+            None,
+        )
+        .unwrap();
+    let box_id = handle.id().clone();
+    handle.start().await.unwrap();
+
+    // Another handle, and the runtime's own, come and go
+    drop(ctx.runtime.get(box_id.as_str()).unwrap().unwrap());
+    ctx.runtime.inspect(box_id.as_str()).await.unwrap().unwrap();
+    tokio::time::sleep(Duration::from_secs(1)).await;
+    let info = ctx.runtime.get_info(box_id.as_str()).unwrap().unwrap();
+    assert_eq!(info.status, BoxStatus::Running);
+
+    // The last one stops it
+    drop(handle);
+    let mut status = BoxStatus::Running;
+    for _ in 0..60 {
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        status = ctx
+            .runtime
+            .get_info(box_id.as_str())
+            .unwrap()
+            .unwrap()
+            .status;
+        if status == BoxStatus::Stopped {
+            break;
+        }
+    }
+    assert_eq!(status, BoxStatus::Stopped);
+
+    // Cleanup
+    ctx.runtime.remove(box_id.as_str(), false).await.unwrap();
+}
+
+#[tokio::test]
+async fn update_resources_rejects_values_before_touching_the_box() {
+    let ctx = TestContext::new();
+    let handle = ctx
+        .runtime
+        .create(
+            BoxOptions {
+                rootfs: RootfsSpec::Image("alpine:latest".into()),
+                auto_remove: false,
+                cpus: Some(1),
+                ..Default::default()
+            },
+            None,
+        )
+        .unwrap();
+    let box_id = handle.id().clone();
+    handle.start().await.unwrap();
+
+    // More vCPUs than any host has, and zero memory
+    assert!(handle.update_resources(Some(255), None).await.is_err());
+    assert!(handle.update_resources(None, Some(0)).await.is_err());
+
+    // The box kept running with its old values
+    let info = ctx.runtime.get_info(box_id.as_str()).unwrap().unwrap();
+    assert_eq!(info.status, BoxStatus::Running);
+    assert_eq!(info.cpus, 1);
+
+    // Cleanup
+    handle.stop().await.unwrap();
+    ctx.runtime.remove(box_id.as_str(), false).await.unwrap();
+}
+
+#[tokio::test]
+async fn update_resources_on_stopped_box_applies_at_next_start() {
+    let ctx = TestContext::new();
+    let handle = ctx
+        .runtime
+        .create(
+            BoxOptions {
+                rootfs: RootfsSpec::Image("alpine:latest".into()),
+                auto_remove: false,
+                cpus: Some(1),
+                memory_mib: Some(512),
+                ..Default::default()
+            },
+            None,
+        )
+        .unwrap();
+    let box_id = handle.id().clone();
+    handle.start().await.unwrap();
+    handle.stop().await.unwrap();
+
+    let handle = handle.update_resources(None, Some(1024)).await.unwrap();
+    let info = ctx.runtime.get_info(box_id.as_str()).unwrap().unwrap();
+    assert_eq!(info.status, BoxStatus::Stopped);
+    assert_eq!(info.cpus, 1);
+    assert_eq!(info.memory_mib, 1024);
+
+    handle.start().await.unwrap();
+    assert_eq!(handle.info().memory_mib, 1024);
+
+    // Cleanup
+    handle.stop().await.unwrap();
+    ctx.runtime.remove(box_id.as_str(), false).await.unwrap();
+}
+
+#[tokio::test]
+async fn spec_pins_the_image_the_box_booted_from() {
+    let ctx = TestContext::new();
+    let handle = ctx
+        .runtime
+        .create(
+            BoxOptions {
+                rootfs: RootfsSpec::Image("alpine:latest".into()),
+                auto_remove: false,
+                ..Default::default()
+            },
+            None,
+        )
+        .unwrap();
+    let box_id = handle.id().clone();
+
+    // Nothing resolved the image yet
+    assert!(handle.spec().await.is_err());
+
+    handle.start().await.unwrap();
+    let spec = handle.spec().await.unwrap();
+    let digest = spec.image_digest.clone().unwrap();
+    assert!(digest.starts_with("sha256:"));
+    assert!(matches!(
+        &spec.options.rootfs,
+        RootfsSpec::Image(image) if *image == format!("alpine:latest@{}", digest)
+    ));
+
+    // Cleanup
+    handle.stop().await.unwrap();
+    ctx.runtime.remove(box_id.as_str(), false).await.unwrap();
+}
+
+// ============================================================================
+// IDLE TIMEOUT TESTS
+// ============================================================================
+
+/// Options of a box suspended after 2s without a command.
+fn idle_options(action: IdleAction) -> BoxOptions {
+    BoxOptions {
+        rootfs: RootfsSpec::Image("alpine:latest".into()),
+        auto_remove: false,
+        idle_timeout: Some(Duration::from_secs(2)),
+        idle_action: action,
+        ..Default::default()
+    }
+}
+
+/// Wait up to 30s for the box to reach `status`, returning the last seen.
+async fn wait_for_status(ctx: &TestContext, box_id: &BoxID, status: BoxStatus) -> BoxStatus {
+    let mut current = BoxStatus::Unknown;
+    for _ in 0..60 {
+        current = ctx
+            .runtime
+            .get_info(box_id.as_str())
+            .unwrap()
+            .unwrap()
+            .status;
+        if current == status {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(500)).await;
+    }
+    current
+}
+
+#[tokio::test]
+async fn idle_stop_restarts_box_on_next_exec() {
+    let ctx = TestContext::new();
+    let handle = ctx
+        .runtime
+        .create(idle_options(IdleAction::Stop), None)
+        .unwrap();
+    let box_id = handle.id().clone();
+    handle.start().await.unwrap();
+
+    assert_eq!(
+        wait_for_status(&ctx, &box_id, BoxStatus::Stopped).await,
+        BoxStatus::Stopped
+    );
+    let info = ctx.runtime.get_info(box_id.as_str()).unwrap().unwrap();
+    assert_eq!(
+        info.last_exit.map(|exit| exit.reason),
+        Some(ExitReason::Idle)
+    );
+
+    // The same handle boots the box again
+    let mut execution = handle.exec(BoxCommand::new("true")).await.unwrap();
+    assert_eq!(execution.wait().await.unwrap().exit_code, 0);
+    let info = ctx.runtime.get_info(box_id.as_str()).unwrap().unwrap();
+    assert_eq!(info.status, BoxStatus::Running);
+
+    // Cleanup
+    handle.stop().await.unwrap();
+    ctx.runtime.remove(box_id.as_str(), false).await.unwrap();
+}
+
+#[tokio::test]
+async fn idle_pause_resumes_box_on_next_exec() {
+    let ctx = TestContext::new();
+    let handle = ctx
+        .runtime
+        .create(idle_options(IdleAction::Pause), None)
+        .unwrap();
+    let box_id = handle.id().clone();
+    handle.start().await.unwrap();
+
+    assert_eq!(
+        wait_for_status(&ctx, &box_id, BoxStatus::Paused).await,
+        BoxStatus::Paused
+    );
+
+    let mut execution = handle.exec(BoxCommand::new("true")).await.unwrap();
+    assert_eq!(execution.wait().await.unwrap().exit_code, 0);
+    let info = ctx.runtime.get_info(box_id.as_str()).unwrap().unwrap();
+    assert_eq!(info.status, BoxStatus::Running);
+
+    // Cleanup
+    handle.stop().await.unwrap();
+    ctx.runtime.remove(box_id.as_str(), false).await.unwrap();
+}
+
+#[tokio::test]
+async fn running_exec_keeps_idle_box_awake() {
+    let ctx = TestContext::new();
+    let handle = ctx
+        .runtime
+        .create(idle_options(IdleAction::Stop), None)
+        .unwrap();
+    let box_id = handle.id().clone();
+    handle.start().await.unwrap();
+
+    // Outlives the timeout several times over
+    let mut execution = handle
+        .exec(BoxCommand::new("sleep").arg("8"))
+        .await
+        .unwrap();
+    tokio::time::sleep(Duration::from_secs(6)).await;
+    let info = ctx.runtime.get_info(box_id.as_str()).unwrap().unwrap();
+    assert_eq!(info.status, BoxStatus::Running);
+    assert_eq!(execution.wait().await.unwrap().exit_code, 0);
+
+    // Cleanup
+    handle.stop().await.unwrap();
+    ctx.runtime.remove(box_id.as_str(), false).await.unwrap();
+}
+
+// ============================================================================
+// CLONE TESTS
+// ============================================================================
+
+/// Exit code of `sh -c script` in the box.
+async fn sh(handle: &LiteBox, script: &str) -> i32 {
+    let mut execution = handle
+        .exec(BoxCommand::new("sh").arg("-c").arg(script))
+        .await
+        .unwrap();
+    execution.wait().await.unwrap().exit_code
+}
+
+/// Box directories under the runtime's home.
+fn box_dirs(ctx: &TestContext) -> usize {
+    std::fs::read_dir(ctx._temp_dir.path().join("boxes"))
+        .map(|entries| entries.count())
+        .unwrap_or(0)
+}
+
+#[tokio::test]
+async fn clone_box_copies_rootfs_changes() {
+    let ctx = TestContext::new();
+    let source = ctx
+        .runtime
+        .create(
+            BoxOptions {
+                rootfs: RootfsSpec::Image("alpine:latest".into()),
+                auto_remove: false,
+                ..Default::default()
+            },
+            None,
+        )
+        .unwrap();
+    assert_eq!(sh(&source, "echo prepared > /marker").await, 0);
+    source.stop().await.unwrap();
+
+    let clone = ctx
+        .runtime
+        .clone_box(source.id().as_str(), CloneOptions::default())
+        .unwrap();
+    assert_ne!(clone.id(), source.id());
+    assert_eq!(clone.info().status, BoxStatus::Stopped);
+
+    // The clone starts from the source's overlay, and diverges from it
+    assert_eq!(sh(&clone, "grep -q prepared /marker").await, 0);
+    assert_eq!(sh(&clone, "touch /clone-only").await, 0);
+    clone.stop().await.unwrap();
+    let source = ctx.runtime.get(source.id().as_str()).unwrap().unwrap();
+    assert_eq!(sh(&source, "test -e /clone-only").await, 1);
+
+    // Cleanup
+    source.stop().await.unwrap();
+    ctx.runtime
+        .remove(source.id().as_str(), false)
+        .await
+        .unwrap();
+    ctx.runtime
+        .remove(clone.id().as_str(), false)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn clone_box_rejects_running_source() {
+    let ctx = TestContext::new();
+    let source = ctx
+        .runtime
+        .create(
+            BoxOptions {
+                rootfs: RootfsSpec::Image("alpine:latest".into()),
+                auto_remove: false,
+                ..Default::default()
+            },
+            None,
+        )
+        .unwrap();
+    source.start().await.unwrap();
+    let before = box_dirs(&ctx);
+
+    let result = ctx
+        .runtime
+        .clone_box(source.id().as_str(), CloneOptions::default());
+    assert!(matches!(result, Err(BoxliteError::InvalidState(_))));
+    assert_eq!(ctx.runtime.list_info().unwrap().len(), 1);
+    assert_eq!(box_dirs(&ctx), before);
+
+    // Cleanup
+    source.stop().await.unwrap();
+    ctx.runtime
+        .remove(source.id().as_str(), false)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn clone_box_name_collision_leaves_nothing_behind() {
+    let ctx = TestContext::new();
+    let source = ctx
+        .runtime
+        .create(
+            BoxOptions {
+                rootfs: RootfsSpec::Image("alpine:latest".into()),
+                auto_remove: false,
+                ..Default::default()
+            },
+            Some("source".into()),
+        )
+        .unwrap();
+    source.start().await.unwrap();
+    source.stop().await.unwrap();
+    let before = box_dirs(&ctx);
+
+    let result = ctx.runtime.clone_box(
+        "source",
+        CloneOptions {
+            name: Some("source".into()),
+        },
+    );
+    assert!(matches!(result, Err(BoxliteError::InvalidArgument(_))));
+    assert_eq!(ctx.runtime.list_info().unwrap().len(), 1);
+    assert_eq!(box_dirs(&ctx), before);
+
+    // Cleanup
+    ctx.runtime.remove("source", false).await.unwrap();
+}
+
 // ============================================================================
 // LITEBOX INFO TESTS
 // ============================================================================
@@ -547,6 +971,7 @@ async fn boxes_persist_across_runtime_restart() {
     {
         let options = BoxliteOptions {
             home_dir: home_dir.clone(),
+            ..Default::default()
         };
         let runtime = BoxliteRuntime::new(options).expect("Failed to create runtime");
         let litebox = runtime
@@ -571,7 +996,10 @@ async fn boxes_persist_across_runtime_restart() {
 
     // Create new runtime with same home directory (simulates restart)
     {
-        let options = BoxliteOptions { home_dir };
+        let options = BoxliteOptions {
+            home_dir,
+            ..Default::default()
+        };
         let runtime = BoxliteRuntime::new(options).expect("Failed to create runtime");
 
         // Box should be recovered from database
@@ -600,6 +1028,7 @@ async fn multiple_boxes_persist_and_recover_without_lock_errors() {
     {
         let options = BoxliteOptions {
             home_dir: home_dir.clone(),
+            ..Default::default()
         };
         let runtime = BoxliteRuntime::new(options).expect("Failed to create runtime");
 
@@ -652,7 +1081,10 @@ async fn multiple_boxes_persist_and_recover_without_lock_errors() {
     // Create new runtime with same home directory (simulates restart)
     // This should successfully recover all boxes without lock allocation errors
     {
-        let options = BoxliteOptions { home_dir };
+        let options = BoxliteOptions {
+            home_dir,
+            ..Default::default()
+        };
         let runtime = BoxliteRuntime::new(options).expect("Failed to create runtime after restart");
 
         // All boxes should be recovered from database
@@ -818,6 +1250,7 @@ async fn recovery_removes_auto_remove_true_boxes() {
     {
         let options = BoxliteOptions {
             home_dir: home_dir.clone(),
+            ..Default::default()
         };
         let runtime = BoxliteRuntime::new(options).expect("Failed to create runtime");
 
@@ -862,7 +1295,10 @@ async fn recovery_removes_auto_remove_true_boxes() {
 
     // Create new runtime with same home directory (simulates restart)
     {
-        let options = BoxliteOptions { home_dir };
+        let options = BoxliteOptions {
+            home_dir,
+            ..Default::default()
+        };
         let runtime = BoxliteRuntime::new(options).expect("Failed to create runtime after restart");
 
         // auto_remove=true box should be removed during recovery
@@ -900,6 +1336,7 @@ async fn recovery_removes_orphaned_stopped_boxes_without_directory() {
     {
         let options = BoxliteOptions {
             home_dir: home_dir.clone(),
+            ..Default::default()
         };
         let runtime = BoxliteRuntime::new(options).expect("Failed to create runtime");
 
@@ -930,7 +1367,10 @@ async fn recovery_removes_orphaned_stopped_boxes_without_directory() {
 
     // Create new runtime with same home directory (simulates restart)
     {
-        let options = BoxliteOptions { home_dir };
+        let options = BoxliteOptions {
+            home_dir,
+            ..Default::default()
+        };
         let runtime = BoxliteRuntime::new(options).expect("Failed to create runtime after restart");
 
         // Stopped box without directory should be KEPT (it might never have been started)
@@ -1,5 +1,6 @@
 //! Integration tests for network backend selection and configuration.
 
+use boxlite::PortMapping;
 use boxlite::net::{NetworkBackendConfig, NetworkBackendFactory};
 
 #[test]
@@ -22,7 +23,8 @@ fn test_no_backend_when_no_features_enabled() {
 #[test]
 fn test_network_config_creation() {
     // Test NetworkConfig constructor
-    let port_mappings = vec![(8080, 80), (3000, 3000), (5432, 5432)];
+    let port_mappings: Vec<PortMapping> =
+        vec![(8080, 80).into(), (3000, 3000).into(), (5432, 5432).into()];
     let config = NetworkBackendConfig::new(port_mappings.clone());
 
     assert_eq!(config.port_mappings.len(), 3);
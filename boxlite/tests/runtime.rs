@@ -13,12 +13,14 @@ fn test_runtime_prevents_concurrent_access() {
     // Create first runtime
     let config1 = BoxliteOptions {
         home_dir: temp_dir.path().to_path_buf(),
+        ..Default::default()
     };
     let runtime1 = BoxliteRuntime::new(config1).unwrap();
 
     // Try to create second runtime (should fail)
     let config2 = BoxliteOptions {
         home_dir: temp_dir.path().to_path_buf(),
+        ..Default::default()
     };
     let result = BoxliteRuntime::new(config2);
     assert!(result.is_err());
@@ -33,6 +35,7 @@ fn test_runtime_prevents_concurrent_access() {
     // Now should be able to create another
     let config3 = BoxliteOptions {
         home_dir: temp_dir.path().to_path_buf(),
+        ..Default::default()
     };
     let _runtime2 = BoxliteRuntime::new(config3).unwrap();
 }
@@ -45,6 +48,7 @@ fn test_runtime_lock_released_on_drop() {
     {
         let config = BoxliteOptions {
             home_dir: temp_dir.path().to_path_buf(),
+            ..Default::default()
         };
         let _runtime = BoxliteRuntime::new(config).unwrap();
     } // Lock released here
@@ -52,6 +56,7 @@ fn test_runtime_lock_released_on_drop() {
     // Should be able to create new runtime
     let config2 = BoxliteOptions {
         home_dir: temp_dir.path().to_path_buf(),
+        ..Default::default()
     };
     let _runtime2 = BoxliteRuntime::new(config2).unwrap();
 }
@@ -64,6 +69,7 @@ fn test_runtime_lock_across_threads() {
     // Acquire lock in main thread
     let config1 = BoxliteOptions {
         home_dir: dir_path.clone(),
+        ..Default::default()
     };
     let _runtime1 = BoxliteRuntime::new(config1).unwrap();
 
@@ -72,6 +78,7 @@ fn test_runtime_lock_across_threads() {
     let handle = thread::spawn(move || {
         let config = BoxliteOptions {
             home_dir: dir_clone,
+            ..Default::default()
         };
         BoxliteRuntime::new(config)
     });
@@ -88,12 +95,14 @@ fn test_different_home_dirs_independent() {
     // Create runtime in first directory
     let config1 = BoxliteOptions {
         home_dir: temp_dir1.path().to_path_buf(),
+        ..Default::default()
     };
     let _runtime1 = BoxliteRuntime::new(config1).unwrap();
 
     // Should be able to create runtime in second directory
     let config2 = BoxliteOptions {
         home_dir: temp_dir2.path().to_path_buf(),
+        ..Default::default()
     };
     let _runtime2 = BoxliteRuntime::new(config2).unwrap();
 
@@ -108,6 +117,7 @@ fn test_lock_file_created() {
 
     let config = BoxliteOptions {
         home_dir: temp_dir.path().to_path_buf(),
+        ..Default::default()
     };
     let _runtime = BoxliteRuntime::new(config).unwrap();
 
@@ -116,12 +126,25 @@ fn test_lock_file_created() {
     assert!(lock_file.exists());
 }
 
+#[test]
+fn test_rejects_zero_boot_slots() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let config = BoxliteOptions {
+        home_dir: temp_dir.path().to_path_buf(),
+        max_concurrent_boots: Some(0),
+        ..Default::default()
+    };
+    assert!(BoxliteRuntime::new(config).is_err());
+}
+
 #[test]
 fn test_lock_survives_short_operations() {
     let temp_dir = TempDir::new().unwrap();
 
     let config1 = BoxliteOptions {
         home_dir: temp_dir.path().to_path_buf(),
+        ..Default::default()
     };
     let runtime = BoxliteRuntime::new(config1).unwrap();
 
@@ -131,9 +154,152 @@ fn test_lock_survives_short_operations() {
     // Lock should still be held
     let config2 = BoxliteOptions {
         home_dir: temp_dir.path().to_path_buf(),
+        ..Default::default()
     };
     let result = BoxliteRuntime::new(config2);
     assert!(result.is_err());
 
     drop(runtime);
 }
+
+#[test]
+fn test_reload_config() {
+    let temp_dir = TempDir::new().unwrap();
+    let config = BoxliteOptions {
+        home_dir: temp_dir.path().to_path_buf(),
+        ..Default::default()
+    };
+    let runtime = BoxliteRuntime::new(config.clone()).unwrap();
+
+    // Reloadable fields
+    let reloaded = BoxliteOptions {
+        log_level: Some("boxlite=debug,info".into()),
+        ..config.clone()
+    };
+    runtime.reload_config(reloaded).unwrap();
+
+    // Invalid log filter is rejected
+    let invalid = BoxliteOptions {
+        log_level: Some("boxlite=notalevel".into()),
+        ..config.clone()
+    };
+    assert!(runtime.reload_config(invalid).is_err());
+
+    // home_dir is fixed for the runtime's lifetime
+    let other_dir = TempDir::new().unwrap();
+    let moved = BoxliteOptions {
+        home_dir: other_dir.path().to_path_buf(),
+        ..config.clone()
+    };
+    let err = runtime.reload_config(moved).unwrap_err().to_string();
+    assert!(err.contains("home_dir"));
+
+    // So are the options read at startup
+    let restarted = BoxliteOptions {
+        reap_interval: Some(Duration::from_secs(1)),
+        max_concurrent_boots: Some(2),
+        ..config
+    };
+    let err = runtime.reload_config(restarted).unwrap_err().to_string();
+    assert!(err.contains("reap_interval, max_concurrent_boots"));
+}
+
+#[tokio::test]
+async fn test_reload_on_sighup() {
+    let temp_dir = TempDir::new().unwrap();
+    let config = BoxliteOptions {
+        home_dir: temp_dir.path().to_path_buf(),
+        ..Default::default()
+    };
+    let runtime = BoxliteRuntime::new(config.clone()).unwrap();
+
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    runtime
+        .reload_on_sighup(move || {
+            tx.send(()).unwrap();
+            Ok(BoxliteOptions {
+                log_level: Some("debug".into()),
+                ..config.clone()
+            })
+        })
+        .unwrap();
+
+    unsafe { libc::raise(libc::SIGHUP) };
+    tokio::time::timeout(Duration::from_secs(5), rx.recv())
+        .await
+        .unwrap()
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_drain_refuses_new_boxes() {
+    let temp_dir = TempDir::new().unwrap();
+    let config = BoxliteOptions {
+        home_dir: temp_dir.path().to_path_buf(),
+        ..Default::default()
+    };
+    let runtime = BoxliteRuntime::new(config).unwrap();
+
+    // Nothing running: drains immediately
+    let report = runtime.drain(Duration::from_secs(30)).await.unwrap();
+    assert!(report.is_clean());
+    assert!(report.stopped.is_empty());
+
+    let Err(err) = runtime.create(Default::default(), None) else {
+        panic!("create succeeded while draining");
+    };
+    assert!(err.to_string().contains("draining"));
+}
+
+#[tokio::test]
+async fn test_shutdown_releases_home_dir() {
+    let temp_dir = TempDir::new().unwrap();
+    let config = BoxliteOptions {
+        home_dir: temp_dir.path().to_path_buf(),
+        ..Default::default()
+    };
+    let runtime = BoxliteRuntime::new(config.clone()).unwrap();
+
+    let report = runtime.shutdown(Duration::from_secs(5)).await.unwrap();
+    assert!(report.is_clean());
+    assert!(runtime.create(Default::default(), None).is_err());
+
+    // Still alive, but no longer holding the directory
+    let _next = BoxliteRuntime::new(config).unwrap();
+    drop(runtime);
+}
+
+#[test]
+fn test_get_or_create_returns_one_box() {
+    let temp_dir = TempDir::new().unwrap();
+    let config = BoxliteOptions {
+        home_dir: temp_dir.path().to_path_buf(),
+        ..Default::default()
+    };
+    let runtime = std::sync::Arc::new(BoxliteRuntime::new(config).unwrap());
+
+    let (first, created) = runtime.get_or_create("worker", Default::default()).unwrap();
+    assert!(created);
+    let (again, created) = runtime.get_or_create("worker", Default::default()).unwrap();
+    assert!(!created);
+    assert_eq!(again.id(), first.id());
+
+    // Racing callers all end up with the same box
+    let handles: Vec<_> = (0..8)
+        .map(|_| {
+            let runtime = runtime.clone();
+            thread::spawn(move || {
+                let (litebox, created) =
+                    runtime.get_or_create("racer", Default::default()).unwrap();
+                (litebox, created)
+            })
+        })
+        .collect();
+    let results: Vec<_> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+    assert_eq!(results.iter().filter(|(_, created)| *created).count(), 1);
+    assert!(
+        results
+            .iter()
+            .all(|(litebox, _)| litebox.id() == results[0].0.id())
+    );
+}
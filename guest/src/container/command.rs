@@ -4,13 +4,18 @@
 //! following the `std::process::Command` pattern.
 
 use super::capabilities::capability_names;
+use super::user::ExecUser;
+use crate::service::exec::cgroup;
 use crate::service::exec::exec_handle::{ExecHandle, PtyConfig};
 use boxlite_shared::errors::{BoxliteError, BoxliteResult};
 use libcontainer::container::builder::ContainerBuilder;
+use libcontainer::oci_spec::runtime::Spec;
 use libcontainer::syscall::syscall::SyscallType;
+use libcontainer::workload::default::get_executor;
+use libcontainer::workload::{Executor, ExecutorError, ExecutorValidationError};
 use nix::unistd::Pid;
 use std::collections::HashMap;
-use std::os::unix::io::OwnedFd;
+use std::os::unix::io::{OwnedFd, RawFd};
 use std::path::PathBuf;
 
 /// Command builder
@@ -52,11 +57,17 @@ pub struct ContainerCommand {
     /// Working directory (None = use default "/")
     cwd: Option<String>,
 
+    /// Identity to run as
+    user: ExecUser,
+
     /// Console socket path for PTY (internal, set by spawn when pty_config is present)
     console_socket: Option<String>,
 
     /// PTY configuration (set via with_pty())
     pty_config: Option<PtyConfig>,
+
+    /// Open `cgroup.procs` of a cgroup to join before exec (set via cgroup())
+    cgroup: Option<RawFd>,
 }
 
 impl ContainerCommand {
@@ -64,14 +75,21 @@ impl ContainerCommand {
     ///
     /// This is public within the crate for use by Container::exec().
     /// Users should call `container.exec()` instead.
-    pub(super) fn new(id: String, state_root: PathBuf, env: HashMap<String, String>) -> Self {
+    pub(super) fn new(
+        id: String,
+        state_root: PathBuf,
+        env: HashMap<String, String>,
+        user: ExecUser,
+    ) -> Self {
         Self {
             program: None,
             args: Vec::new(),
             env,
             cwd: None,
+            user,
             console_socket: None,
             pty_config: None,
+            cgroup: None,
             id,
             state_root,
         }
@@ -87,6 +105,14 @@ impl ContainerCommand {
         self
     }
 
+    /// Join the cgroup of `procs`, an open `cgroup.procs`, before exec
+    ///
+    /// `procs` must stay open until spawn() returns.
+    pub fn cgroup(mut self, procs: RawFd) -> Self {
+        self.cgroup = Some(procs);
+        self
+    }
+
     /// Set the program to execute
     ///
     /// # Example
@@ -198,6 +224,12 @@ impl ContainerCommand {
         self
     }
 
+    /// Run as `user` instead of the container's default identity
+    pub fn user(mut self, user: ExecUser) -> Self {
+        self.user = user;
+        self
+    }
+
     /// Spawn the process
     ///
     /// Creates a tenant process in the container with stdin/stdout/stderr pipes.
@@ -298,6 +330,10 @@ impl ContainerCommand {
         let mut container_args = vec![program.clone()];
         container_args.extend_from_slice(self.args.as_slice());
 
+        let mut env = self.env.clone();
+        env.entry("HOME".to_string())
+            .or_insert_with(|| self.user.home.clone());
+
         // Build container
         let mut builder = ContainerBuilder::new(self.id.to_string(), SyscallType::default())
             .with_root_path(self.state_root.clone())
@@ -308,6 +344,10 @@ impl ContainerCommand {
             .validate_id()
             .map_err(|e| BoxliteError::Internal(format!("Invalid container ID: {}", e)))?;
 
+        if let Some(procs) = self.cgroup {
+            builder = builder.with_executor(JoinCgroup { procs });
+        }
+
         // Add pipes if provided
         if let Some((stdin, stdout, stderr)) = pipes {
             builder = builder
@@ -350,7 +390,10 @@ impl ContainerCommand {
             .with_no_new_privs(false)
             .with_detach(false)
             .with_cwd(self.cwd.clone().or(Some("/".parse().unwrap())))
-            .with_env(self.env.clone())
+            .with_user(Some(self.user.uid))
+            .with_group(Some(self.user.gid))
+            .with_additional_gids(self.user.additional_gids.clone())
+            .with_env(env)
             .with_container_args(container_args.clone())
             .build()
             .map_err(|e| {
@@ -391,6 +434,28 @@ impl ContainerCommand {
     }
 }
 
+/// Runs the process like libcontainer's default executor, after joining the
+/// cgroup of an open `cgroup.procs`.
+///
+/// Runs in the container's init process, after it switched to the
+/// command's user; the descriptor is inherited from the guest agent.
+#[derive(Clone)]
+struct JoinCgroup {
+    procs: RawFd,
+}
+
+impl Executor for JoinCgroup {
+    fn exec(&self, spec: &Spec) -> Result<(), ExecutorError> {
+        cgroup::join(self.procs)
+            .map_err(|e| ExecutorError::Other(format!("Failed to join execution cgroup: {}", e)))?;
+        get_executor().exec(spec)
+    }
+
+    fn validate(&self, spec: &Spec) -> Result<(), ExecutorValidationError> {
+        get_executor().validate(spec)
+    }
+}
+
 /// Create ExecHandle with PTY.
 ///
 /// Sets terminal window size, reconciles PTY master FD as stdin/stdout,
@@ -13,6 +13,22 @@ pub(crate) fn kill_container(container: &mut LibContainer) {
     let _ = container.kill(sigkill, true);
 }
 
+/// Send `signal` (e.g. `SIGTERM`, `TERM` or `15`) to the container's init
+/// process. An unknown signal name falls back to SIGTERM.
+pub(crate) fn signal_init(container: &mut LibContainer, signal: &str) {
+    if !container.can_kill() {
+        return;
+    }
+
+    let signal = Signal::try_from(signal).unwrap_or_else(|_| {
+        tracing::warn!(signal, "Unknown stop signal, sending SIGTERM");
+        Signal::try_from(15).expect("SIGTERM (15) is a valid signal")
+    });
+    if let Err(e) = container.kill(signal, false) {
+        tracing::warn!(error = %e, "Failed to signal container init");
+    }
+}
+
 /// Delete the container
 pub(crate) fn delete_container(container: &mut LibContainer) {
     let force = !container.can_delete();
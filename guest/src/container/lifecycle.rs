@@ -4,14 +4,18 @@
 //! Follows the OCI Runtime Specification.
 
 use super::command::ContainerCommand;
-use super::spec::UserMount;
-use super::stdio::ContainerStdio;
+use super::console_socket::ConsoleSocket;
+use super::spec::{TmpfsMount, UserMount};
+use super::start::EtcFiles;
+use super::stdio::{AttachFds, ContainerStdio};
+use super::user::ExecUser;
 use super::{kill, start};
 use crate::layout::GuestLayout;
 use boxlite_shared::errors::BoxliteResult;
 use libcontainer::container::Container as LibContainer;
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 /// OCI container
 ///
@@ -44,9 +48,12 @@ pub struct Container {
     state_root: PathBuf,
     bundle_path: PathBuf,
     env: HashMap<String, String>,
-    /// Stdio pipes that keep init process alive.
+    /// Bundle rootfs, for resolving user names.
+    rootfs: PathBuf,
+    /// Identity of execs, from the image `USER`.
+    user: ExecUser,
+    /// Stdio pipes (or PTY) that keep init process alive.
     /// Dropping this closes pipes → init gets EOF → init exits.
-    #[allow(dead_code)]
     stdio: ContainerStdio,
 }
 
@@ -68,7 +75,11 @@ impl Container {
     /// - `entrypoint`: Command and arguments for container init process
     /// - `env`: Environment variables in "KEY=VALUE" format
     /// - `workdir`: Working directory inside container
+    /// - `user`: Image `USER`, the default identity of execs (empty = root)
     /// - `user_mounts`: Bind mounts from guest VM paths into container
+    /// - `tmpfs_mounts`: tmpfs mounts in the container
+    /// - `tty`: Run the init process on a PTY instead of pipes
+    /// - `etc`: Settings for the container's /etc/resolv.conf and /etc/hosts
     ///
     /// # Errors
     ///
@@ -76,13 +87,18 @@ impl Container {
     /// - Failed to create container directory
     /// - Failed to create or start container
     /// - Init process exited immediately
+    #[allow(clippy::too_many_arguments)]
     pub fn start(
         container_id: &str,
         rootfs: impl AsRef<Path>,
         entrypoint: Vec<String>,
         env: Vec<String>,
         workdir: impl AsRef<Path>,
+        user: &str,
         user_mounts: Vec<UserMount>,
+        tmpfs_mounts: Vec<TmpfsMount>,
+        tty: bool,
+        etc: EtcFiles,
     ) -> BoxliteResult<Self> {
         let rootfs = rootfs.as_ref();
         let workdir = workdir.as_ref();
@@ -92,6 +108,7 @@ impl Container {
 
         // Validate inputs early
         start::validate_container_inputs(rootfs, &entrypoint, workdir)?;
+        let user = ExecUser::resolve(rootfs, user)?;
 
         // Parse existing env into map (KEY=VALUE)
         let mut env_map: HashMap<String, String> = HashMap::new();
@@ -116,14 +133,30 @@ impl Container {
             workdir,
             &layout.containers_dir(),
             &user_mounts,
+            &tmpfs_mounts,
+            tty,
+            &etc,
         )?;
 
-        // Create stdio pipes before container creation.
-        // These keep the init process alive by holding stdin open.
-        let (stdio, init_fds) = ContainerStdio::new()?;
+        let stdio = if tty {
+            // The PTY master keeps init alive the same way the stdin pipe does
+            let socket = ConsoleSocket::new(container_id)?;
+            start::create_container_with_console(
+                container_id,
+                &state_root,
+                &bundle_path,
+                socket.path(),
+            )?;
+            ContainerStdio::from_pty(socket.receive_pty_master()?)
+        } else {
+            // Create stdio pipes before container creation.
+            // These keep the init process alive by holding stdin open.
+            let (stdio, init_fds) = ContainerStdio::new()?;
 
-        // Create and start container with custom stdio
-        start::create_container_with_stdio(container_id, &state_root, &bundle_path, init_fds)?;
+            // Create container with custom stdio
+            start::create_container_with_stdio(container_id, &state_root, &bundle_path, init_fds)?;
+            stdio
+        };
         start::start_container(container_id, &state_root)?;
 
         Ok(Self {
@@ -131,6 +164,8 @@ impl Container {
             state_root,
             bundle_path,
             env: env_map,
+            rootfs: rootfs.to_path_buf(),
+            user,
             stdio,
         })
     }
@@ -174,6 +209,16 @@ impl Container {
         }
     }
 
+    /// PID of the container's init process, if it was started.
+    pub fn init_pid(&self) -> Option<nix::unistd::Pid> {
+        LibContainer::load(self.container_state_path()).ok()?.pid()
+    }
+
+    /// Take the init process stdio for attaching (only once).
+    pub fn take_attach_fds(&mut self) -> BoxliteResult<Option<AttachFds>> {
+        self.stdio.take_attach_fds()
+    }
+
     /// Get container ID
     ///
     /// Returns the unique container identifier.
@@ -211,7 +256,18 @@ impl Container {
     /// # }
     /// ```
     pub fn cmd(&self) -> ContainerCommand {
-        ContainerCommand::new(self.id.clone(), self.state_root.clone(), self.env.clone())
+        ContainerCommand::new(
+            self.id.clone(),
+            self.state_root.clone(),
+            self.env.clone(),
+            self.user.clone(),
+        )
+    }
+
+    /// Resolve a user spec (`name`, `uid`, `name:group` or `uid:gid`)
+    /// against the container's `/etc/passwd` and `/etc/group`.
+    pub fn resolve_user(&self, spec: &str) -> BoxliteResult<ExecUser> {
+        ExecUser::resolve(&self.rootfs, spec)
     }
 
     /// Diagnose why container is not running
@@ -291,6 +347,26 @@ impl Container {
         }
     }
 
+    /// Signal the init process to stop and wait up to `timeout` for the
+    /// container to exit. Returns whether it exited in time.
+    pub async fn stop(&self, signal: &str, timeout: Duration) -> bool {
+        const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+        let Ok(mut container) = LibContainer::load(self.container_state_path()) else {
+            return true;
+        };
+        kill::signal_init(&mut container, signal);
+
+        let until = tokio::time::Instant::now() + timeout;
+        while self.is_running() {
+            if tokio::time::Instant::now() >= until {
+                return false;
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+        true
+    }
+
     fn container_state_path(&self) -> PathBuf {
         self.state_root.join(&self.id)
     }
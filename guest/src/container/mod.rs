@@ -77,8 +77,11 @@ mod spec;
 mod start;
 #[cfg(target_os = "linux")]
 mod stdio;
+#[cfg(target_os = "linux")]
+mod user;
 
 #[cfg(target_os = "linux")]
 pub use lifecycle::Container;
 #[cfg(target_os = "linux")]
-pub use spec::UserMount;
+pub use spec::{TmpfsMount, UserMount};
+pub use start::EtcFiles;
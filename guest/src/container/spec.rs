@@ -23,13 +23,24 @@ pub struct UserMount {
     pub read_only: bool,
 }
 
+/// User-specified tmpfs mount for container
+#[derive(Debug, Clone)]
+pub struct TmpfsMount {
+    /// Destination path in container
+    pub destination: String,
+    /// Size limit in bytes (0 = kernel default)
+    pub size_bytes: u64,
+    /// Permission bits of the mount's root (0 = 01777)
+    pub mode: u32,
+}
+
 /// Create OCI runtime specification with default configuration
 ///
 /// Builds an OCI spec with:
 /// - Standard mounts (/proc, /dev, /sys, etc.)
-/// - User-specified bind mounts (volumes)
+/// - User-specified bind mounts (volumes) and tmpfs mounts
 /// - Default capabilities (matching runc defaults)
-/// - Standard namespaces (pid, ipc, uts, mount)
+/// - Standard namespaces (pid, ipc, uts, mount), with `hostname` in the uts one
 /// - UID/GID mappings for user namespace
 /// - Root user (uid=0, gid=0)
 /// - Resource limits (rlimits)
@@ -39,6 +50,7 @@ pub struct UserMount {
 /// Since we're inside a VM with single-tenant isolation, cgroup resource limits
 /// provide minimal benefit. See comments in build_default_namespaces() and
 /// build_standard_mounts() to re-enable if needed.
+#[allow(clippy::too_many_arguments)]
 pub fn create_oci_spec(
     container_id: &str,
     rootfs: &str,
@@ -47,6 +59,9 @@ pub fn create_oci_spec(
     workdir: &str,
     bundle_path: &Path,
     user_mounts: &[UserMount],
+    tmpfs_mounts: &[TmpfsMount],
+    tty: bool,
+    hostname: &str,
 ) -> BoxliteResult<Spec> {
     let caps = build_default_capabilities()?;
     let namespaces = build_default_namespaces()?;
@@ -83,13 +98,48 @@ pub fn create_oci_spec(
         );
     }
 
-    let process = build_process_spec(entrypoint, env, workdir, caps)?;
+    // Add user-specified tmpfs mounts
+    for tmpfs in tmpfs_mounts {
+        let mode = if tmpfs.mode == 0 { 0o1777 } else { tmpfs.mode };
+        let mut options = vec![
+            "nosuid".to_string(),
+            "nodev".to_string(),
+            format!("mode={:o}", mode),
+        ];
+        if tmpfs.size_bytes > 0 {
+            options.push(format!("size={}", tmpfs.size_bytes));
+        }
+
+        mounts.push(
+            MountBuilder::default()
+                .destination(&tmpfs.destination)
+                .typ("tmpfs")
+                .source("tmpfs")
+                .options(options)
+                .build()
+                .map_err(|e| {
+                    BoxliteError::Internal(format!(
+                        "Failed to build tmpfs mount {}: {}",
+                        tmpfs.destination, e
+                    ))
+                })?,
+        );
+
+        tracing::debug!(
+            destination = %tmpfs.destination,
+            size_bytes = tmpfs.size_bytes,
+            mode = %format_args!("{:o}", mode),
+            "Added tmpfs mount to OCI spec"
+        );
+    }
+
+    let process = build_process_spec(entrypoint, env, workdir, caps, tty)?;
     let root = build_root_spec(rootfs)?;
     let linux = build_linux_spec(container_id, namespaces)?;
 
     SpecBuilder::default()
         .version("1.0.2")
-        .hostname("boxlite")
+        .hostname(hostname)
         .root(root)
         .mounts(mounts)
         .process(process)
@@ -149,6 +199,7 @@ fn build_process_spec(
     env: &[String],
     workdir: &str,
     caps: oci_spec::runtime::LinuxCapabilities,
+    tty: bool,
 ) -> BoxliteResult<oci_spec::runtime::Process> {
     let user = UserBuilder::default()
         .uid(0u32)
@@ -168,7 +219,7 @@ fn build_process_spec(
         .map_err(|e| BoxliteError::Internal(format!("Failed to build rlimit: {}", e)))?];
 
     ProcessBuilder::default()
-        .terminal(false)
+        .terminal(tty)
         .user(user)
         .args(entrypoint.to_vec())
         .env(env)
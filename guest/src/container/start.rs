@@ -15,6 +15,36 @@ use std::path::{Path, PathBuf};
 // Setup Functions (Prepare Phase)
 // ====================
 
+/// Gateway of the guest network, which forwards DNS queries to the host.
+const GATEWAY_DNS: &str = "192.168.127.1"; // TODO: Use constant when guest can access boxlite constants
+
+/// Hostname of containers whose host sends none.
+const DEFAULT_HOSTNAME: &str = "boxlite";
+
+/// Settings for the `/etc` files written for the container.
+#[derive(Debug, Clone, Default)]
+pub struct EtcFiles {
+    /// Hostname of the container (empty = boxlite)
+    pub hostname: String,
+    /// `nameserver` entries of resolv.conf (empty = the gateway)
+    pub dns_servers: Vec<String>,
+    /// `search` domains of resolv.conf (empty = localdomain)
+    pub dns_search: Vec<String>,
+    /// (hostname, IP) lines added to /etc/hosts
+    pub extra_hosts: Vec<(String, String)>,
+}
+
+impl EtcFiles {
+    /// Hostname of the container.
+    pub fn hostname(&self) -> &str {
+        if self.hostname.is_empty() {
+            DEFAULT_HOSTNAME
+        } else {
+            &self.hostname
+        }
+    }
+}
+
 /// Validate container creation inputs
 pub(crate) fn validate_container_inputs(
     rootfs: &Path,
@@ -44,17 +74,16 @@ pub(crate) fn validate_container_inputs(
 pub(crate) fn create_container_etc_files(
     bundle_path: &Path,
     _container_id: &str,
+    etc: &EtcFiles,
 ) -> BoxliteResult<()> {
-    const DEFAULT_HOSTNAME: &str = "boxlite";
-
     // Create /etc/hostname
     let hostname_path = bundle_path.join("hostname");
-    fs::write(&hostname_path, format!("{}\n", DEFAULT_HOSTNAME))
+    fs::write(&hostname_path, format!("{}\n", etc.hostname()))
         .map_err(|e| BoxliteError::Internal(format!("Failed to create hostname file: {}", e)))?;
 
-    // Create /etc/hosts with localhost and hostname entries
+    // Create /etc/hosts with localhost, hostname and extra entries
     let hosts_path = bundle_path.join("hosts");
-    let hosts_content = format!(
+    let mut hosts_content = format!(
         "127.0.0.1\tlocalhost\n\
          ::1\t\tlocalhost ip6-localhost ip6-loopback\n\
          fe00::0\t\tip6-localnet\n\
@@ -62,17 +91,17 @@ pub(crate) fn create_container_etc_files(
          ff02::1\t\tip6-allnodes\n\
          ff02::2\t\tip6-allrouters\n\
          127.0.1.1\t{}\n",
-        DEFAULT_HOSTNAME
+        etc.hostname()
     );
+    for (hostname, ip) in &etc.extra_hosts {
+        hosts_content.push_str(&format!("{}\t{}\n", ip, hostname));
+    }
     fs::write(&hosts_path, hosts_content)
         .map_err(|e| BoxliteError::Internal(format!("Failed to create hosts file: {}", e)))?;
 
-    // Create /etc/resolv.conf with gateway as DNS server
+    // Create /etc/resolv.conf, forwarding to the gateway unless servers are given
     let resolv_conf_path = bundle_path.join("resolv.conf");
-    let resolv_conf_content = format!(
-        "# Generated by BoxLite Guest\n# DNS queries forwarded to gateway\nnameserver {}\nsearch localdomain\n",
-        "192.168.127.1" // TODO: Use constant when guest can access boxlite constants
-    );
+    let resolv_conf_content = resolv_conf(etc);
     fs::write(&resolv_conf_path, resolv_conf_content)
         .map_err(|e| BoxliteError::Internal(format!("Failed to create resolv.conf file: {}", e)))?;
 
@@ -86,7 +115,26 @@ pub(crate) fn create_container_etc_files(
     Ok(())
 }
 
+/// Contents of the container's /etc/resolv.conf.
+fn resolv_conf(etc: &EtcFiles) -> String {
+    let mut content = String::from("# Generated by BoxLite Guest\n");
+    if etc.dns_servers.is_empty() {
+        content.push_str("# DNS queries forwarded to gateway\n");
+        content.push_str(&format!("nameserver {}\n", GATEWAY_DNS));
+    }
+    for server in &etc.dns_servers {
+        content.push_str(&format!("nameserver {}\n", server));
+    }
+    if etc.dns_search.is_empty() {
+        content.push_str("search localdomain\n");
+    } else {
+        content.push_str(&format!("search {}\n", etc.dns_search.join(" ")));
+    }
+    content
+}
+
 /// Create OCI bundle (config.json + rootfs reference)
+#[allow(clippy::too_many_arguments)]
 pub(crate) fn create_oci_bundle(
     container_id: &str,
     rootfs: &Path,
@@ -95,6 +143,9 @@ pub(crate) fn create_oci_bundle(
     workdir: &Path,
     bundle_root: &Path,
     user_mounts: &[spec::UserMount],
+    tmpfs_mounts: &[spec::TmpfsMount],
+    tty: bool,
+    etc: &EtcFiles,
 ) -> BoxliteResult<PathBuf> {
     let bundle_path = bundle_root.join(container_id);
 
@@ -108,7 +159,7 @@ pub(crate) fn create_oci_bundle(
 
     // Create /etc/hosts, /etc/hostname and /etc/resolv.conf files
     // These will be bind-mounted into the container to provide hostname and DNS resolution
-    create_container_etc_files(&bundle_path, container_id)?;
+    create_container_etc_files(&bundle_path, container_id, etc)?;
 
     let spec = spec::create_oci_spec(
         container_id,
@@ -122,6 +173,9 @@ pub(crate) fn create_oci_bundle(
             .ok_or_else(|| BoxliteError::Internal("Invalid workdir path".to_string()))?,
         &bundle_path,
         user_mounts,
+        tmpfs_mounts,
+        tty,
+        etc.hostname(),
     )?;
     let config_path = bundle_path.join("config.json");
 
@@ -223,6 +277,39 @@ pub(crate) fn create_container_with_stdio(
     Ok(())
 }
 
+/// Create container whose init process runs on a PTY.
+///
+/// libcontainer sends the PTY master to `console_socket`; receive it with
+/// `ConsoleSocket::receive_pty_master` after this returns.
+pub(crate) fn create_container_with_console(
+    container_id: &str,
+    state_root: &Path,
+    bundle_path: &Path,
+    console_socket: &str,
+) -> BoxliteResult<()> {
+    ContainerBuilder::new(container_id.to_string(), SyscallType::default())
+        .with_root_path(state_root)
+        .map_err(|e| BoxliteError::Internal(format!("Failed to set container root path: {}", e)))?
+        .with_console_socket(Some(console_socket))
+        .validate_id()
+        .map_err(|e| BoxliteError::Internal(format!("Invalid container ID: {}", e)))?
+        .as_init(bundle_path)
+        .with_systemd(false)
+        .with_detach(true)
+        .build()
+        .map_err(|e| {
+            BoxliteError::Internal(format!(
+                "Failed to create container {} at bundle {}: {}",
+                container_id,
+                bundle_path.display(),
+                e
+            ))
+        })?;
+
+    tracing::info!(container_id, "Created OCI container with console");
+    Ok(())
+}
+
 /// Start the container (executes entrypoint)
 pub(crate) fn start_container(container_id: &str, state_root: &Path) -> BoxliteResult<()> {
     let container_state_path = state_root.join(container_id);
@@ -51,17 +51,35 @@ use std::os::unix::io::OwnedFd;
 /// 5. On container stop, drop ContainerStdio → pipes close → init gets EOF
 #[derive(Debug)]
 pub struct ContainerStdio {
-    /// Write-end of stdin pipe (held open, never written to)
-    #[allow(dead_code)]
+    /// Write-end of stdin pipe, or the PTY master (held open, never closed)
     stdin_tx: OwnedFd,
 
-    /// Read-end of stdout pipe (for optional log capture)
-    #[allow(dead_code)]
-    stdout_rx: OwnedFd,
+    /// Read-ends of the output pipes, until handed out for attaching
+    output: Option<(OwnedFd, OwnedFd)>,
 
-    /// Read-end of stderr pipe (for optional log capture)
-    #[allow(dead_code)]
-    stderr_rx: OwnedFd,
+    /// Whether `stdin_tx` is a PTY master
+    tty: bool,
+
+    /// Whether stdio was handed out for attaching
+    attached: bool,
+}
+
+/// Init process stdio handed out for attaching.
+///
+/// `stdin` is a duplicate: closing it doesn't close init's stdin.
+#[derive(Debug)]
+pub struct AttachFds {
+    /// Writes to init's stdin
+    pub stdin: OwnedFd,
+
+    /// Reads init's stdout (and stderr with a PTY)
+    pub stdout: OwnedFd,
+
+    /// Reads init's stderr, `None` with a PTY
+    pub stderr: Option<OwnedFd>,
+
+    /// PTY master for resizing, `None` with pipes
+    pub pty: Option<OwnedFd>,
 }
 
 /// File descriptors to pass to container init process.
@@ -110,8 +128,9 @@ impl ContainerStdio {
         // nix::unistd::pipe() returns OwnedFd directly
         let container_stdio = Self {
             stdin_tx,
-            stdout_rx,
-            stderr_rx,
+            output: Some((stdout_rx, stderr_rx)),
+            tty: false,
+            attached: false,
         };
 
         let init_fds = InitStdioFds {
@@ -124,6 +143,53 @@ impl ContainerStdio {
 
         Ok((container_stdio, init_fds))
     }
+
+    /// Hold the PTY master of an init process started with a terminal.
+    pub fn from_pty(master: OwnedFd) -> Self {
+        Self {
+            stdin_tx: master,
+            output: None,
+            tty: true,
+            attached: false,
+        }
+    }
+
+    /// Hand out init's stdio for attaching.
+    ///
+    /// Output can only be read by one owner, so this succeeds once; later
+    /// calls return `None`.
+    pub fn take_attach_fds(&mut self) -> BoxliteResult<Option<AttachFds>> {
+        let dup = |fd: &OwnedFd| {
+            fd.try_clone().map_err(|e| {
+                BoxliteError::Internal(format!("Failed to duplicate init stdio: {}", e))
+            })
+        };
+
+        if self.attached {
+            return Ok(None);
+        }
+
+        let fds = if self.tty {
+            AttachFds {
+                stdin: dup(&self.stdin_tx)?,
+                stdout: dup(&self.stdin_tx)?,
+                stderr: None,
+                pty: Some(dup(&self.stdin_tx)?),
+            }
+        } else {
+            let Some((stdout, stderr)) = self.output.take() else {
+                return Ok(None);
+            };
+            AttachFds {
+                stdin: dup(&self.stdin_tx)?,
+                stdout,
+                stderr: Some(stderr),
+                pty: None,
+            }
+        };
+        self.attached = true;
+        Ok(Some(fds))
+    }
 }
 
 #[cfg(test)]
@@ -140,8 +206,9 @@ mod tests {
 
         // Verify all FDs are valid (positive integers)
         assert!(stdio.stdin_tx.as_raw_fd() >= 0);
-        assert!(stdio.stdout_rx.as_raw_fd() >= 0);
-        assert!(stdio.stderr_rx.as_raw_fd() >= 0);
+        let (stdout_rx, stderr_rx) = stdio.output.as_ref().unwrap();
+        assert!(stdout_rx.as_raw_fd() >= 0);
+        assert!(stderr_rx.as_raw_fd() >= 0);
         assert!(init_fds.stdin.as_raw_fd() >= 0);
         assert!(init_fds.stdout.as_raw_fd() >= 0);
         assert!(init_fds.stderr.as_raw_fd() >= 0);
@@ -149,8 +216,8 @@ mod tests {
         // Verify all FDs are unique
         let fds = [
             stdio.stdin_tx.as_raw_fd(),
-            stdio.stdout_rx.as_raw_fd(),
-            stdio.stderr_rx.as_raw_fd(),
+            stdout_rx.as_raw_fd(),
+            stderr_rx.as_raw_fd(),
             init_fds.stdin.as_raw_fd(),
             init_fds.stdout.as_raw_fd(),
             init_fds.stderr.as_raw_fd(),
@@ -161,4 +228,29 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_attach_fds_keep_stdin_open() {
+        use std::io::{Read, Write};
+
+        let (mut stdio, init_fds) = ContainerStdio::new().unwrap();
+        let fds = stdio.take_attach_fds().unwrap().unwrap();
+        assert!(fds.pty.is_none());
+        assert!(stdio.take_attach_fds().unwrap().is_none());
+
+        // A client that attaches and leaves must not close init's stdin
+        std::fs::File::from(fds.stdin).write_all(b"hi").unwrap();
+        let mut init_stdin = std::fs::File::from(init_fds.stdin);
+        let mut buf = [0u8; 2];
+        init_stdin.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"hi");
+
+        nix::fcntl::fcntl(
+            init_stdin.as_raw_fd(),
+            nix::fcntl::FcntlArg::F_SETFL(nix::fcntl::OFlag::O_NONBLOCK),
+        )
+        .unwrap();
+        let err = init_stdin.read(&mut buf).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::WouldBlock);
+    }
 }
@@ -0,0 +1,171 @@
+//! Process identity inside a container.
+//!
+//! Resolves docker-style user specs (`name`, `uid`, `name:group`,
+//! `uid:gid`) against the container's `/etc/passwd` and `/etc/group`, the
+//! same way runc does for the image `USER`.
+
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use boxlite_shared::errors::{BoxliteError, BoxliteResult};
+use nix::libc;
+
+use crate::storage::archive::open_in_root;
+
+/// Identity a process runs as.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExecUser {
+    pub uid: u32,
+    pub gid: u32,
+    /// Supplementary groups.
+    pub additional_gids: Vec<u32>,
+    /// Home directory, used for `HOME` unless the environment sets it.
+    pub home: String,
+}
+
+impl ExecUser {
+    /// Resolve `spec` in the container rootfs. An empty spec is root.
+    pub fn resolve(rootfs: &Path, spec: &str) -> BoxliteResult<Self> {
+        let root = File::open(rootfs).map_err(|e| {
+            BoxliteError::Internal(format!("Failed to open rootfs {}: {}", rootfs.display(), e))
+        })?;
+        let passwd = read_in_root(&root, "/etc/passwd");
+        let group = read_in_root(&root, "/etc/group");
+        parse(spec, &passwd, &group).map_err(BoxliteError::InvalidArgument)
+    }
+}
+
+/// Read a file of the container, empty if it cannot be read.
+fn read_in_root(root: &File, path: &str) -> String {
+    let mut content = String::new();
+    if let Ok(fd) = open_in_root(root, Path::new(path), libc::O_RDONLY, 0) {
+        let _ = File::from(fd).read_to_string(&mut content);
+    }
+    content
+}
+
+fn parse(spec: &str, passwd: &str, group: &str) -> Result<ExecUser, String> {
+    let (user_spec, group_spec) = match spec.split_once(':') {
+        Some((user, group)) => (user, Some(group)),
+        None => (spec, None),
+    };
+    let user_spec = if user_spec.is_empty() { "0" } else { user_spec };
+
+    let users: Vec<Vec<&str>> = entries(passwd, 7);
+    let entry = match user_spec.parse::<u32>() {
+        Ok(uid) => users.iter().find(|e| e[2].parse() == Ok(uid)),
+        Err(_) => users.iter().find(|e| e[0] == user_spec),
+    };
+    let mut user = match (entry, user_spec.parse::<u32>()) {
+        (Some(e), _) => ExecUser {
+            uid: e[2].parse().map_err(|_| bad_entry(e))?,
+            gid: e[3].parse().map_err(|_| bad_entry(e))?,
+            additional_gids: Vec::new(),
+            home: e[5].to_string(),
+        },
+        (None, Ok(uid)) => ExecUser {
+            uid,
+            gid: 0,
+            additional_gids: Vec::new(),
+            home: "/".to_string(),
+        },
+        (None, Err(_)) => return Err(format!("unable to find user {}", user_spec)),
+    };
+    let name = entry.map(|e| e[0]);
+
+    let groups: Vec<Vec<&str>> = entries(group, 4);
+    match group_spec {
+        Some(group_spec) => {
+            let entry = groups.iter().find(|e| e[0] == group_spec);
+            user.gid = match (entry, group_spec.parse::<u32>()) {
+                (Some(e), _) => e[2].parse().map_err(|_| bad_entry(e))?,
+                (None, Ok(gid)) => gid,
+                (None, Err(_)) => return Err(format!("unable to find group {}", group_spec)),
+            };
+        }
+        None => {
+            // Without an explicit group, the user keeps the groups that
+            // list it as a member
+            if let Some(name) = name {
+                for e in &groups {
+                    let member = e[3].split(',').any(|m| m == name);
+                    if let (true, Ok(gid)) = (member, e[2].parse::<u32>()) {
+                        if gid != user.gid && !user.additional_gids.contains(&gid) {
+                            user.additional_gids.push(gid);
+                        }
+                    }
+                }
+            }
+        }
+    }
+    Ok(user)
+}
+
+/// Colon-separated lines with at least `fields` fields.
+fn entries(content: &str, fields: usize) -> Vec<Vec<&str>> {
+    content
+        .lines()
+        .filter(|line| !line.trim().is_empty() && !line.starts_with('#'))
+        .map(|line| line.split(':').collect::<Vec<_>>())
+        .filter(|e| e.len() >= fields)
+        .collect()
+}
+
+fn bad_entry(entry: &[&str]) -> String {
+    format!("malformed entry for {}", entry[0])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PASSWD: &str = "\
+root:x:0:0:root:/root:/bin/sh
+app:x:1000:1000::/home/app:/bin/sh
+";
+    const GROUP: &str = "\
+root:x:0:
+app:x:1000:
+docker:x:999:app,other
+video:x:44:app
+";
+
+    #[test]
+    fn test_parse_user_specs() {
+        let app = parse("app", PASSWD, GROUP).unwrap();
+        assert_eq!(
+            app,
+            ExecUser {
+                uid: 1000,
+                gid: 1000,
+                additional_gids: vec![999, 44],
+                home: "/home/app".into(),
+            }
+        );
+        assert_eq!(parse("1000", PASSWD, GROUP).unwrap(), app);
+        assert_eq!(parse("", PASSWD, GROUP).unwrap().home, "/root");
+
+        let explicit = parse("app:video", PASSWD, GROUP).unwrap();
+        assert_eq!((explicit.gid, explicit.additional_gids), (44, vec![]));
+
+        // Unknown numeric ids are used as they are
+        let unknown = parse("4242:4343", PASSWD, GROUP).unwrap();
+        assert_eq!((unknown.uid, unknown.gid), (4242, 4343));
+        assert_eq!(unknown.home, "/");
+
+        assert!(parse("nobody", PASSWD, GROUP).is_err());
+        assert!(parse("app:nogroup", PASSWD, GROUP).is_err());
+    }
+
+    #[test]
+    fn test_resolve_in_rootfs() {
+        let rootfs = tempfile::tempdir().unwrap();
+        std::fs::create_dir(rootfs.path().join("etc")).unwrap();
+        std::fs::write(rootfs.path().join("etc/passwd"), PASSWD).unwrap();
+
+        let user = ExecUser::resolve(rootfs.path(), "app").unwrap();
+        assert_eq!((user.uid, user.gid), (1000, 1000));
+        assert!(user.additional_gids.is_empty());
+    }
+}
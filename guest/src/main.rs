@@ -16,6 +16,8 @@ mod overlayfs;
 #[cfg(target_os = "linux")]
 mod service;
 #[cfg(target_os = "linux")]
+mod sockets;
+#[cfg(target_os = "linux")]
 mod storage;
 
 #[cfg(target_os = "linux")]
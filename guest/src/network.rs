@@ -8,7 +8,9 @@
 
 use boxlite_shared::errors::{BoxliteError, BoxliteResult};
 use futures::stream::TryStreamExt;
-use std::net::Ipv4Addr;
+use std::fmt::Display;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::str::FromStr;
 
 /// Configure guest network interface
 ///
@@ -155,10 +157,16 @@ pub async fn configure_network() -> BoxliteResult<()> {
 /// * `interface` - Network interface name (e.g., "eth0")
 /// * `ip` - Optional IP address with prefix (e.g., "192.168.127.2/24"). If None, skips IP assignment.
 /// * `gateway` - Optional gateway address (e.g., "192.168.127.1"). If None, skips route setup.
+/// * `ipv6` - Optional IPv6 address with prefix (e.g., "fd42:b0c5:127::2/64").
+/// * `gateway_ipv6` - Optional IPv6 gateway address (e.g., "fd42:b0c5:127::1").
+/// * `mtu` - Optional interface MTU. If None, keeps the kernel default.
 pub async fn configure_network_from_config(
     interface: &str,
     ip: Option<&str>,
     gateway: Option<&str>,
+    ipv6: Option<&str>,
+    gateway_ipv6: Option<&str>,
+    mtu: Option<u32>,
 ) -> BoxliteResult<()> {
     use rtnetlink::new_connection;
 
@@ -175,22 +183,8 @@ pub async fn configure_network_from_config(
     // Spawn the netlink connection in the background
     tokio::spawn(connection);
 
-    // 1. Find the loopback interface (lo) and bring it up
-    tracing::debug!("  ↑ Bringing up loopback interface");
-    let mut links = handle.link().get().match_name("lo".to_string()).execute();
-    if let Some(link) = links
-        .try_next()
-        .await
-        .map_err(|e| BoxliteError::Internal(format!("Failed to get lo interface: {}", e)))?
-    {
-        handle
-            .link()
-            .set(link.header.index)
-            .up()
-            .execute()
-            .await
-            .map_err(|e| BoxliteError::Internal(format!("Failed to bring up lo: {}", e)))?;
-    }
+    // 1. Bring up the loopback interface
+    bring_up_loopback(&handle).await?;
 
     // 2. Find interface
     tracing::info!("  🔍 Finding {} interface", interface);
@@ -211,12 +205,14 @@ pub async fn configure_network_from_config(
     let if_index = link.header.index;
     tracing::debug!("  ✓ Found {} with index {}", interface, if_index);
 
-    // 3. Bring up interface
+    // 3. Bring up interface, with the network backend's MTU
     tracing::info!("  ↑ Bringing up {}", interface);
-    handle
-        .link()
-        .set(if_index)
-        .up()
+    let mut request = handle.link().set(if_index).up();
+    if let Some(mtu) = mtu {
+        tracing::info!("  📏 Setting MTU: {}", mtu);
+        request = request.mtu(mtu);
+    }
+    request
         .execute()
         .await
         .map_err(|e| BoxliteError::Internal(format!("Failed to bring up {}: {}", interface, e)))?;
@@ -224,76 +220,161 @@ pub async fn configure_network_from_config(
     // 4. Assign IP address (if provided)
     if let Some(ip_str) = ip {
         // Parse IP/prefix (e.g., "192.168.127.2/24")
-        let (ip_addr, prefix) = parse_ip_prefix(ip_str)?;
+        let (ip_addr, prefix) = parse_ip_prefix::<Ipv4Addr>(ip_str, 24)?;
+        add_address(&handle, if_index, interface, ip_addr.into(), prefix).await?;
+    }
 
-        tracing::info!("  📍 Assigning IP: {}/{}", ip_addr, prefix);
+    // 5. Add default route (if gateway provided)
+    if let Some(gw_str) = gateway {
+        let gw_addr: Ipv4Addr = gw_str.parse().map_err(|e| {
+            BoxliteError::Internal(format!("Invalid gateway address '{}': {}", gw_str, e))
+        })?;
+
+        tracing::info!("  🚪 Setting default gateway: {}", gw_addr);
 
         handle
-            .address()
-            .add(if_index, ip_addr.into(), prefix)
+            .route()
+            .add()
+            .v4()
+            .gateway(gw_addr)
             .execute()
             .await
             .or_else(|e| {
                 if e.to_string().contains("File exists") {
-                    tracing::debug!("IP address already assigned (this is OK)");
+                    tracing::debug!("Default route already exists (this is OK)");
                     Ok(())
                 } else {
                     Err(e)
                 }
             })
-            .map_err(|e| {
-                BoxliteError::Internal(format!(
-                    "Failed to assign IP address to {}: {}",
-                    interface, e
-                ))
-            })?;
+            .map_err(|e| BoxliteError::Internal(format!("Failed to set default gateway: {}", e)))?;
     }
 
-    // 5. Add default route (if gateway provided)
-    if let Some(gw_str) = gateway {
-        let gw_addr: Ipv4Addr = gw_str.parse().map_err(|e| {
-            BoxliteError::Internal(format!("Invalid gateway address '{}': {}", gw_str, e))
+    // 6. Assign IPv6 address and default route (if provided)
+    if let Some(ip_str) = ipv6 {
+        let (ip_addr, prefix) = parse_ip_prefix::<Ipv6Addr>(ip_str, 64)?;
+
+        // Nothing else on the virtual network claims the address; skip
+        // duplicate address detection so it is usable right away
+        let dad = format!("/proc/sys/net/ipv6/conf/{}/accept_dad", interface);
+        if let Err(e) = std::fs::write(&dad, "0") {
+            tracing::warn!("Failed to disable DAD on {}: {}", interface, e);
+        }
+
+        add_address(&handle, if_index, interface, ip_addr.into(), prefix).await?;
+    }
+
+    if let Some(gw_str) = gateway_ipv6 {
+        let gw_addr: Ipv6Addr = gw_str.parse().map_err(|e| {
+            BoxliteError::Internal(format!("Invalid IPv6 gateway address '{}': {}", gw_str, e))
         })?;
 
-        tracing::info!("  🚪 Setting default gateway: {}", gw_addr);
+        tracing::info!("  🚪 Setting default IPv6 gateway: {}", gw_addr);
 
         handle
             .route()
             .add()
-            .v4()
+            .v6()
             .gateway(gw_addr)
             .execute()
             .await
             .or_else(|e| {
                 if e.to_string().contains("File exists") {
-                    tracing::debug!("Default route already exists (this is OK)");
+                    tracing::debug!("Default IPv6 route already exists (this is OK)");
                     Ok(())
                 } else {
                     Err(e)
                 }
             })
-            .map_err(|e| BoxliteError::Internal(format!("Failed to set default gateway: {}", e)))?;
+            .map_err(|e| {
+                BoxliteError::Internal(format!("Failed to set default IPv6 gateway: {}", e))
+            })?;
     }
 
     tracing::info!("✅ Network configured: {} is UP", interface);
     Ok(())
 }
 
-/// Parse IP address with optional prefix (e.g., "192.168.127.2/24" or "192.168.127.2")
-fn parse_ip_prefix(ip_str: &str) -> BoxliteResult<(Ipv4Addr, u8)> {
-    if let Some((ip_part, prefix_part)) = ip_str.split_once('/') {
-        let ip_addr: Ipv4Addr = ip_part.parse().map_err(|e| {
-            BoxliteError::Internal(format!("Invalid IP address '{}': {}", ip_part, e))
-        })?;
-        let prefix: u8 = prefix_part.parse().map_err(|e| {
-            BoxliteError::Internal(format!("Invalid prefix '{}': {}", prefix_part, e))
-        })?;
-        Ok((ip_addr, prefix))
-    } else {
-        // Default to /24 if no prefix specified
-        let ip_addr: Ipv4Addr = ip_str.parse().map_err(|e| {
-            BoxliteError::Internal(format!("Invalid IP address '{}': {}", ip_str, e))
-        })?;
-        Ok((ip_addr, 24))
+/// Configure the loopback interface only, for boxes without a network.
+pub async fn configure_loopback() -> BoxliteResult<()> {
+    use rtnetlink::new_connection;
+
+    let (connection, handle, _) = new_connection().map_err(|e| {
+        BoxliteError::Internal(format!("Failed to create netlink connection: {}", e))
+    })?;
+    tokio::spawn(connection);
+
+    bring_up_loopback(&handle).await
+}
+
+async fn bring_up_loopback(handle: &rtnetlink::Handle) -> BoxliteResult<()> {
+    tracing::debug!("  ↑ Bringing up loopback interface");
+    let mut links = handle.link().get().match_name("lo".to_string()).execute();
+    if let Some(link) = links
+        .try_next()
+        .await
+        .map_err(|e| BoxliteError::Internal(format!("Failed to get lo interface: {}", e)))?
+    {
+        handle
+            .link()
+            .set(link.header.index)
+            .up()
+            .execute()
+            .await
+            .map_err(|e| BoxliteError::Internal(format!("Failed to bring up lo: {}", e)))?;
     }
+    Ok(())
+}
+
+/// Assign `ip_addr/prefix` to an interface, keeping an existing assignment.
+async fn add_address(
+    handle: &rtnetlink::Handle,
+    if_index: u32,
+    interface: &str,
+    ip_addr: IpAddr,
+    prefix: u8,
+) -> BoxliteResult<()> {
+    tracing::info!("  📍 Assigning IP: {}/{}", ip_addr, prefix);
+
+    handle
+        .address()
+        .add(if_index, ip_addr, prefix)
+        .execute()
+        .await
+        .or_else(|e| {
+            if e.to_string().contains("File exists") {
+                tracing::debug!("IP address already assigned (this is OK)");
+                Ok(())
+            } else {
+                Err(e)
+            }
+        })
+        .map_err(|e| {
+            BoxliteError::Internal(format!(
+                "Failed to assign IP address to {}: {}",
+                interface, e
+            ))
+        })
+}
+
+/// Parse IP address with optional prefix (e.g., "192.168.127.2/24" or "192.168.127.2"),
+/// using `default_prefix` when none is given
+fn parse_ip_prefix<T>(ip_str: &str, default_prefix: u8) -> BoxliteResult<(T, u8)>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    let (ip_part, prefix) = match ip_str.split_once('/') {
+        Some((ip_part, prefix_part)) => {
+            let prefix: u8 = prefix_part.parse().map_err(|e| {
+                BoxliteError::Internal(format!("Invalid prefix '{}': {}", prefix_part, e))
+            })?;
+            (ip_part, prefix)
+        }
+        None => (ip_str, default_prefix),
+    };
+    let ip_addr: T = ip_part
+        .parse()
+        .map_err(|e| BoxliteError::Internal(format!("Invalid IP address '{}': {}", ip_part, e)))?;
+    Ok((ip_addr, prefix))
 }
@@ -1,29 +1,37 @@
 #![cfg(target_os = "linux")]
 //! Container service implementation.
 //!
-//! Handles OCI container lifecycle (Init RPC).
+//! Handles OCI container lifecycle (Init RPC) and rootfs export
+//! (ListFiles, ExportLayer RPCs).
 
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
 
 use crate::service::server::GuestServer;
 use boxlite_shared::{
     container_init_response, rootfs_init, Container as ContainerService, ContainerInitError,
-    ContainerInitRequest, ContainerInitResponse, ContainerInitSuccess, Filesystem, RootfsInit,
+    ContainerInitRequest, ContainerInitResponse, ContainerInitSuccess, ExportLayerRequest,
+    Filesystem, LayerChunk, ListFilesRequest, ListFilesResponse, RootfsInit, SocketForward,
 };
+use futures::stream::Stream;
 use nix::mount::{mount, MsFlags};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
 use tonic::{Request, Response, Status};
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
 
-use crate::container::{Container, UserMount};
+use crate::container::{Container, EtcFiles, TmpfsMount, UserMount};
 use crate::layout::GuestLayout;
+use crate::service::exec;
 use crate::storage::block_device::BlockDeviceMount;
+use crate::storage::{layer, quota};
 
 /// Prepare container rootfs based on the initialization strategy.
 ///
 /// Handles three strategies:
 /// - Merged: Shared rootfs already exists (no-op)
 /// - Overlay: Bind-mount layers to diff dir, create overlayfs
-/// - Disk: Mount block device to shared rootfs
+/// - Disk: Mount block device to shared rootfs (with optional write quotas)
 fn prepare_rootfs(
     rootfs_init: &RootfsInit,
     container_id: &str,
@@ -69,24 +77,111 @@ fn prepare_rootfs(
             std::fs::create_dir_all(shared_rootfs)
                 .map_err(|e| format!("Failed to create shared rootfs directory: {}", e))?;
 
+            // Repair filesystem before mounting (restart with fsck_on_restart)
+            if disk.need_fsck {
+                BlockDeviceMount::check_filesystem(Path::new(&disk.device), Filesystem::Ext4)
+                    .map_err(|e| format!("Failed to check rootfs disk: {}", e))?;
+            }
+
+            // Quota features can only be enabled while unmounted
+            let device = Path::new(&disk.device);
+            let mount_data = if disk.quotas.is_empty() {
+                None
+            } else {
+                quota::enable(device).map_err(|e| format!("Failed to enable quotas: {}", e))?;
+                Some(quota::MOUNT_OPTIONS)
+            };
+
             // Mount container rootfs disk with options from host
             BlockDeviceMount::mount(
-                Path::new(&disk.device),
+                device,
                 shared_rootfs,
                 Filesystem::Ext4,
                 disk.need_format,
                 disk.need_resize,
+                mount_data,
             )
             .map_err(|e| format!("Failed to mount rootfs disk: {}", e))?;
 
+            quota::apply(device, shared_rootfs, &disk.quotas)
+                .map_err(|e| format!("Failed to apply disk quotas: {}", e))?;
+
             Ok(())
         }
         None => Err("Missing rootfs strategy in Container.Init request".to_string()),
     }
 }
 
+/// Expose the host sockets in a started container.
+fn forward_sockets(container: &Container, sockets: &[SocketForward]) -> Result<(), String> {
+    let pid = container
+        .init_pid()
+        .ok_or_else(|| "Container init process not found".to_string())?;
+    let root_path = format!("/proc/{}/root", pid);
+    let root = std::fs::File::open(&root_path)
+        .map_err(|e| format!("Failed to open {}: {}", root_path, e))?;
+    for socket in sockets {
+        crate::sockets::forward(&root, &socket.destination, socket.port)
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+impl GuestServer {
+    /// Rootfs of a started container.
+    async fn container_rootfs(&self, container_id: &str) -> Result<PathBuf, Status> {
+        if !self.containers.lock().await.contains_key(container_id) {
+            return Err(Status::not_found(format!(
+                "Container not found: {}",
+                container_id
+            )));
+        }
+        Ok(self.layout.shared().container(container_id).rootfs_dir())
+    }
+}
+
 #[tonic::async_trait]
 impl ContainerService for GuestServer {
+    type ListFilesStream =
+        Pin<Box<dyn Stream<Item = Result<ListFilesResponse, Status>> + Send + 'static>>;
+    type ExportLayerStream =
+        Pin<Box<dyn Stream<Item = Result<LayerChunk, Status>> + Send + 'static>>;
+
+    async fn list_files(
+        &self,
+        request: Request<ListFilesRequest>,
+    ) -> Result<Response<Self::ListFilesStream>, Status> {
+        let rootfs = self
+            .container_rootfs(&request.into_inner().container_id)
+            .await?;
+
+        let (tx, rx) = mpsc::channel(4);
+        tokio::task::spawn_blocking(move || layer::list_files(&rootfs, &tx));
+
+        Ok(Response::new(Box::pin(ReceiverStream::new(rx))))
+    }
+
+    async fn export_layer(
+        &self,
+        request: Request<ExportLayerRequest>,
+    ) -> Result<Response<Self::ExportLayerStream>, Status> {
+        let request = request.into_inner();
+        let rootfs = self.container_rootfs(&request.container_id).await?;
+        info!(
+            container_id = %request.container_id,
+            paths = request.paths.len(),
+            deleted = request.deleted.len(),
+            "Exporting rootfs changes"
+        );
+
+        let (tx, rx) = mpsc::channel(4);
+        tokio::task::spawn_blocking(move || {
+            layer::export_layer(&rootfs, &request.paths, &request.deleted, tx)
+        });
+
+        Ok(Response::new(Box::pin(ReceiverStream::new(rx))))
+    }
+
     async fn init(
         &self,
         request: Request<ContainerInitRequest>,
@@ -196,7 +291,10 @@ impl ContainerService for GuestServer {
             .mounts
             .iter()
             .map(|m| {
-                let source = container_layout.volume_dir(&m.volume_name);
+                let mut source = container_layout.volume_dir(&m.volume_name);
+                if !m.subpath.is_empty() {
+                    source.push(&m.subpath);
+                }
                 UserMount {
                     source: source.to_string_lossy().to_string(),
                     destination: m.destination.clone(),
@@ -205,17 +303,59 @@ impl ContainerService for GuestServer {
             })
             .collect();
 
+        let tmpfs_mounts: Vec<TmpfsMount> = init_req
+            .tmpfs
+            .iter()
+            .map(|t| TmpfsMount {
+                destination: t.destination.clone(),
+                size_bytes: t.size_bytes,
+                mode: t.mode,
+            })
+            .collect();
+
+        let network = init_req.network.unwrap_or_default();
+        let etc = EtcFiles {
+            hostname: network.hostname,
+            dns_servers: network.dns_servers,
+            dns_search: network.dns_search,
+            extra_hosts: network
+                .extra_hosts
+                .into_iter()
+                .map(|h| (h.hostname, h.ip))
+                .collect(),
+        };
+
         debug!(
             entrypoint = ?config.entrypoint,
             workdir = %config.workdir,
+            user = %config.user,
             env_count = config.env.len(),
             shared_rootfs = %shared_rootfs.display(),
             bundle_rootfs = %bundle_rootfs.display(),
             container_id = %container_id,
             user_mounts_count = user_mounts.len(),
+            tmpfs_mounts_count = tmpfs_mounts.len(),
             "Container configuration"
         );
 
+        // Reverse ports, listening before the container's processes look
+        for reverse in &init_req.reverse_ports {
+            let bound = match u16::try_from(reverse.guest_port) {
+                Ok(guest_port) => crate::sockets::forward_port(guest_port, reverse.port)
+                    .await
+                    .map_err(|e| e.to_string()),
+                Err(_) => Err(format!("Invalid reverse port {}", reverse.guest_port)),
+            };
+            if let Err(reason) = bound {
+                error!("{}", reason);
+                return Ok(Response::new(ContainerInitResponse {
+                    result: Some(container_init_response::Result::Error(ContainerInitError {
+                        reason,
+                    })),
+                }));
+            }
+        }
+
         // Start container using OCI bundle rootfs
         // Container init process uses pipe-based stdio to stay alive indefinitely.
         // boxlite-guest holds the write-end of stdin pipe open, so init blocks on read() forever.
@@ -224,15 +364,20 @@ impl ContainerService for GuestServer {
             entrypoint = ?config.entrypoint,
             "Starting OCI container with pipe-based stdio"
         );
+        let entrypoint = config.entrypoint.clone();
         match Container::start(
             &container_id,
             &bundle_rootfs,
             config.entrypoint,
             config.env,
             &config.workdir,
+            &config.user,
             user_mounts,
+            tmpfs_mounts,
+            init_req.tty,
+            etc,
         ) {
-            Ok(container) => {
+            Ok(mut container) => {
                 debug!(container_id = %container_id, "Container started, checking if init process is running");
                 // Verify container init process is running
                 if !container.is_running() {
@@ -259,6 +404,26 @@ impl ContainerService for GuestServer {
                     "✅ Container started successfully and ready for exec"
                 );
 
+                // Host sockets, created in the container's own view of its
+                // filesystem (past its volume and tmpfs mounts)
+                if !init_req.sockets.is_empty() {
+                    if let Err(reason) = forward_sockets(&container, &init_req.sockets) {
+                        error!("{}", reason);
+                        return Ok(Response::new(ContainerInitResponse {
+                            result: Some(container_init_response::Result::Error(
+                                ContainerInitError { reason },
+                            )),
+                        }));
+                    }
+                }
+
+                // Make init attachable; the container works without it
+                if let Err(e) =
+                    exec::register_init(self, &container_id, &mut container, &entrypoint).await
+                {
+                    warn!(container_id = %container_id, error = %e, "Failed to register init process");
+                }
+
                 // Store container in registry
                 self.containers.lock().await.insert(
                     container_id.clone(),
@@ -0,0 +1,274 @@
+//! Output backlog of executions.
+//!
+//! An execution keeps its output pipes drained for its whole lifetime, so
+//! it survives its client going away (a detached command, or a host that
+//! lost its connection and reattaches). Output goes to the attached client
+//! if there is one, otherwise it is buffered (bounded, oldest dropped
+//! first) and replayed on the next attach. Output left unread after the
+//! process exits is discarded after a while.
+//! A client that reads too slowly holds up the process, or loses output if
+//! the execution drops output when its buffer is full.
+
+use super::state::OutputBuffer;
+use boxlite_shared::{exec_output, ExecOutput};
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use tokio::sync::mpsc;
+use tonic::Status;
+
+/// Item sent over an attach stream.
+pub(super) type OutputItem = Result<ExecOutput, Status>;
+
+/// Maximum buffered output while no client is attached.
+const MAX_BACKLOG_BYTES: usize = 1024 * 1024;
+
+struct Inner {
+    /// Currently attached client, if any.
+    sink: Option<mpsc::Sender<OutputItem>>,
+    /// Output produced while no client was attached.
+    buffered: VecDeque<OutputItem>,
+    buffered_bytes: usize,
+    /// Output streams still being forwarded (stdout, stderr).
+    open_streams: usize,
+}
+
+impl Inner {
+    fn buffer(&mut self, item: OutputItem) {
+        self.buffered_bytes += item_len(&item);
+        self.buffered.push_back(item);
+
+        while self.buffered_bytes > MAX_BACKLOG_BYTES && self.buffered.len() > 1 {
+            if let Some(dropped) = self.buffered.pop_front() {
+                self.buffered_bytes -= item_len(&dropped);
+            }
+        }
+    }
+}
+
+/// Output sink shared by the forwarding tasks of one detached execution.
+pub(super) struct OutputBacklog {
+    inner: Mutex<Inner>,
+    /// Channel headroom on top of the replayed backlog.
+    output_buffer: OutputBuffer,
+}
+
+impl OutputBacklog {
+    pub fn new(open_streams: usize, output_buffer: OutputBuffer) -> Self {
+        Self {
+            inner: Mutex::new(Inner {
+                sink: None,
+                buffered: VecDeque::new(),
+                buffered_bytes: 0,
+                open_streams,
+            }),
+            output_buffer,
+        }
+    }
+
+    /// Deliver an item to the attached client, or buffer it.
+    pub async fn push(&self, item: OutputItem) {
+        let sink = {
+            let mut inner = self.inner.lock().unwrap();
+            match &inner.sink {
+                Some(sink) => sink.clone(),
+                None => {
+                    inner.buffer(item);
+                    return;
+                }
+            }
+        };
+
+        // An item handed back means the client went away
+        let returned = if self.output_buffer.drop_when_full && item.is_ok() {
+            match sink.try_send(item) {
+                Err(mpsc::error::TrySendError::Closed(item)) => Some(item),
+                Ok(()) | Err(mpsc::error::TrySendError::Full(_)) => None,
+            }
+        } else {
+            match sink.send(item).await {
+                Err(mpsc::error::SendError(item)) => Some(item),
+                Ok(()) => None,
+            }
+        };
+
+        // Client went away: keep the item for the next attach
+        if let Some(item) = returned {
+            let mut inner = self.inner.lock().unwrap();
+            inner.sink = None;
+            inner.buffer(item);
+        }
+    }
+
+    /// Mark one output stream as finished.
+    ///
+    /// Once all streams are finished the attach stream is closed.
+    pub fn close_stream(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.open_streams = inner.open_streams.saturating_sub(1);
+        if inner.open_streams == 0 {
+            inner.sink = None;
+        }
+    }
+
+    /// Whether output is still being produced with no client attached.
+    pub fn is_unattended(&self) -> bool {
+        let inner = self.inner.lock().unwrap();
+        inner.open_streams > 0 && !inner.sink.as_ref().is_some_and(|sink| !sink.is_closed())
+    }
+
+    /// Whether all output has been produced and handed to a client.
+    pub fn is_drained(&self) -> bool {
+        let inner = self.inner.lock().unwrap();
+        inner.open_streams == 0 && inner.buffered.is_empty()
+    }
+
+    /// Drop buffered output no client has read.
+    pub fn discard(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.buffered.clear();
+        inner.buffered_bytes = 0;
+    }
+
+    /// Attach a client, replaying any buffered output first.
+    ///
+    /// Returns `None` if another client is still attached.
+    pub fn attach(&self) -> Option<mpsc::Receiver<OutputItem>> {
+        let mut inner = self.inner.lock().unwrap();
+
+        if inner.sink.as_ref().is_some_and(|sink| !sink.is_closed()) {
+            return None;
+        }
+
+        let (tx, rx) = mpsc::channel(inner.buffered.len() + self.output_buffer.capacity);
+        for item in inner.buffered.drain(..) {
+            // Capacity covers the whole backlog
+            let _ = tx.try_send(item);
+        }
+        inner.buffered_bytes = 0;
+
+        // Finished executions only replay; dropping tx ends the stream
+        if inner.open_streams > 0 {
+            inner.sink = Some(tx);
+        }
+
+        Some(rx)
+    }
+}
+
+fn item_len(item: &OutputItem) -> usize {
+    match item {
+        Ok(ExecOutput {
+            event: Some(exec_output::Event::Stdout(chunk)),
+        }) => chunk.data.len(),
+        Ok(ExecOutput {
+            event: Some(exec_output::Event::Stderr(chunk)),
+        }) => chunk.data.len(),
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use boxlite_shared::Stdout;
+
+    fn stdout(data: &[u8]) -> ExecOutput {
+        ExecOutput {
+            event: Some(exec_output::Event::Stdout(Stdout {
+                data: data.to_vec(),
+                ..Default::default()
+            })),
+        }
+    }
+
+    fn data(item: OutputItem) -> Vec<u8> {
+        match item.unwrap().event {
+            Some(exec_output::Event::Stdout(chunk)) => chunk.data,
+            other => panic!("unexpected event: {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_replays_output_buffered_while_detached() {
+        let backlog = OutputBacklog::new(1, OutputBuffer::default());
+        backlog.push(Ok(stdout(b"a"))).await;
+        backlog.push(Ok(stdout(b"b"))).await;
+
+        let mut rx = backlog.attach().unwrap();
+        backlog.push(Ok(stdout(b"c"))).await;
+        backlog.close_stream();
+
+        assert_eq!(data(rx.recv().await.unwrap()), b"a");
+        assert_eq!(data(rx.recv().await.unwrap()), b"b");
+        assert_eq!(data(rx.recv().await.unwrap()), b"c");
+        assert!(rx.recv().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_reattach_after_client_drops() {
+        let backlog = OutputBacklog::new(1, OutputBuffer::default());
+
+        let rx = backlog.attach().unwrap();
+        assert!(backlog.attach().is_none());
+        drop(rx);
+
+        backlog.push(Ok(stdout(b"kept"))).await;
+        let mut rx = backlog.attach().unwrap();
+        assert_eq!(data(rx.recv().await.unwrap()), b"kept");
+    }
+
+    #[tokio::test]
+    async fn test_backlog_drops_oldest_when_full() {
+        let backlog = OutputBacklog::new(1, OutputBuffer::default());
+        backlog.push(Ok(stdout(&[0; MAX_BACKLOG_BYTES]))).await;
+        backlog.push(Ok(stdout(b"newest"))).await;
+        backlog.close_stream();
+
+        let mut rx = backlog.attach().unwrap();
+        assert_eq!(data(rx.recv().await.unwrap()), b"newest");
+        assert!(rx.recv().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_unattended_until_output_ends() {
+        let backlog = OutputBacklog::new(1, OutputBuffer::default());
+        assert!(backlog.is_unattended());
+
+        let rx = backlog.attach().unwrap();
+        assert!(!backlog.is_unattended());
+        drop(rx);
+        assert!(backlog.is_unattended());
+
+        backlog.close_stream();
+        assert!(!backlog.is_unattended());
+    }
+
+    #[tokio::test]
+    async fn test_discard_drops_unread_output() {
+        let backlog = OutputBacklog::new(1, OutputBuffer::default());
+        backlog.push(Ok(stdout(b"unread"))).await;
+        backlog.close_stream();
+        backlog.discard();
+
+        let mut rx = backlog.attach().unwrap();
+        assert!(rx.recv().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_drop_policy_discards_when_client_is_slow() {
+        let output_buffer = OutputBuffer {
+            capacity: 1,
+            drop_when_full: true,
+        };
+        let backlog = OutputBacklog::new(1, output_buffer);
+        let mut rx = backlog.attach().unwrap();
+
+        // Would block with the default policy
+        backlog.push(Ok(stdout(b"a"))).await;
+        backlog.push(Ok(stdout(b"b"))).await;
+        backlog.close_stream();
+
+        assert_eq!(data(rx.recv().await.unwrap()), b"a");
+        assert!(rx.recv().await.is_none());
+    }
+}
@@ -0,0 +1,174 @@
+//! Per-execution resource limits.
+//!
+//! Executions with limits get a transient cgroup (v2) under
+//! `/sys/fs/cgroup/boxlite-exec/<execution_id>`. The process joins it between
+//! fork and exec, so the command never runs unlimited and everything it forks
+//! is limited too. The cgroup is removed once the process has exited.
+
+use boxlite_shared::errors::{BoxliteError, BoxliteResult};
+use boxlite_shared::ExecLimits;
+use nix::mount::{mount, MsFlags};
+use std::fs;
+use std::os::fd::{OwnedFd, RawFd};
+use std::path::{Path, PathBuf};
+
+const CGROUP_ROOT: &str = "/sys/fs/cgroup";
+
+/// Parent of all execution cgroups.
+const EXEC_PARENT: &str = "boxlite-exec";
+
+/// Controllers needed to enforce [`ExecLimits`].
+const CONTROLLERS: &str = "+cpu +memory +pids";
+
+/// Transient cgroup of one execution.
+pub(super) struct ExecCgroup {
+    path: PathBuf,
+}
+
+impl ExecCgroup {
+    /// Create the cgroup and write its limits.
+    pub fn create(execution_id: &str, limits: &ExecLimits) -> BoxliteResult<Self> {
+        let root = Path::new(CGROUP_ROOT);
+        ensure_mounted(root)?;
+
+        let parent = root.join(EXEC_PARENT);
+        if !parent.exists() {
+            write(&root.join("cgroup.subtree_control"), CONTROLLERS)?;
+            create_dir(&parent)?;
+            write(&parent.join("cgroup.subtree_control"), CONTROLLERS)?;
+        }
+
+        let path = parent.join(execution_id);
+        create_dir(&path)?;
+        let cgroup = Self { path };
+
+        // On error the cgroup is removed again by Drop
+        for (file, value) in limit_files(limits) {
+            write(&cgroup.path.join(file), &value)?;
+        }
+        Ok(cgroup)
+    }
+
+    /// Open `cgroup.procs`, for a new process to [`join`] the cgroup before
+    /// exec.
+    ///
+    /// The kernel checks permissions against the opener, so joining works
+    /// after the process switched to the command's user. Closed on exec.
+    pub fn open_procs(&self) -> BoxliteResult<OwnedFd> {
+        let path = self.path.join("cgroup.procs");
+        fs::OpenOptions::new()
+            .write(true)
+            .open(&path)
+            .map(OwnedFd::from)
+            .map_err(|e| {
+                BoxliteError::Internal(format!("Failed to open {}: {}", path.display(), e))
+            })
+    }
+}
+
+/// Move the calling process into the cgroup of `procs`, an open
+/// `cgroup.procs`.
+///
+/// Only makes a syscall, so it can run between fork and exec.
+pub(crate) fn join(procs: RawFd) -> std::io::Result<()> {
+    // "0" is the writing process
+    if unsafe { nix::libc::write(procs, b"0".as_ptr().cast(), 1) } == 1 {
+        Ok(())
+    } else {
+        Err(std::io::Error::last_os_error())
+    }
+}
+
+impl Drop for ExecCgroup {
+    fn drop(&mut self) {
+        // Fails while processes the command left behind are still alive
+        if let Err(e) = fs::remove_dir(&self.path) {
+            tracing::warn!(
+                path = %self.path.display(),
+                error = %e,
+                "Failed to remove execution cgroup"
+            );
+        }
+    }
+}
+
+/// Control files and values for the requested limits (0 = unlimited).
+fn limit_files(limits: &ExecLimits) -> Vec<(&'static str, String)> {
+    let mut files = Vec::new();
+    if limits.cpu_quota_us > 0 {
+        files.push((
+            "cpu.max",
+            format!("{} {}", limits.cpu_quota_us, limits.cpu_period_us),
+        ));
+    }
+    if limits.memory_max > 0 {
+        files.push(("memory.max", limits.memory_max.to_string()));
+    }
+    if limits.pids_max > 0 {
+        files.push(("pids.max", limits.pids_max.to_string()));
+    }
+    files
+}
+
+/// Mount cgroup2 on first use (skipped at boot, it costs ~100ms).
+fn ensure_mounted(root: &Path) -> BoxliteResult<()> {
+    if root.join("cgroup.controllers").exists() {
+        return Ok(());
+    }
+
+    tracing::info!("Mounting cgroup2 on {}", root.display());
+    create_dir(root)?;
+    mount(
+        Some("cgroup2"),
+        root,
+        Some("cgroup2"),
+        MsFlags::MS_NOSUID | MsFlags::MS_NODEV | MsFlags::MS_NOEXEC,
+        None::<&str>,
+    )
+    .map_err(|e| {
+        BoxliteError::Internal(format!(
+            "Failed to mount cgroup2 on {}: {}",
+            root.display(),
+            e
+        ))
+    })
+}
+
+fn create_dir(path: &Path) -> BoxliteResult<()> {
+    fs::create_dir_all(path)
+        .map_err(|e| BoxliteError::Internal(format!("Failed to create {}: {}", path.display(), e)))
+}
+
+fn write(path: &Path, value: &str) -> BoxliteResult<()> {
+    fs::write(path, value).map_err(|e| {
+        BoxliteError::Internal(format!(
+            "Failed to write '{}' to {}: {}",
+            value,
+            path.display(),
+            e
+        ))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_limit_files() {
+        let limits = ExecLimits {
+            cpu_quota_us: 50_000,
+            cpu_period_us: 100_000,
+            memory_max: 0,
+            pids_max: 64,
+        };
+        assert_eq!(
+            limit_files(&limits),
+            vec![
+                ("cpu.max", "50000 100000".to_string()),
+                ("pids.max", "64".to_string()),
+            ]
+        );
+        assert!(limit_files(&ExecLimits::default()).is_empty());
+    }
+}
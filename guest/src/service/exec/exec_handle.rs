@@ -40,6 +40,21 @@ impl ExecStdin {
             .await
             .map_err(|e| BoxliteError::Internal(format!("Failed to write to stdin: {}", e)))
     }
+
+    /// Signal EOF to the process and close stdin.
+    ///
+    /// With a pipe, closing the fd is enough. A PTY master stays open for
+    /// output, so there the terminal's EOF character (Ctrl-D) is sent instead.
+    pub async fn close(mut self, tty: bool) -> BoxliteResult<()> {
+        const EOT: u8 = 0x04;
+        if tty {
+            self.write_all(&[EOT]).await?;
+        }
+        self.inner
+            .flush()
+            .await
+            .map_err(|e| BoxliteError::Internal(format!("Failed to flush stdin: {}", e)))
+    }
 }
 
 // Shared output stream implementation
@@ -293,6 +308,11 @@ impl ExecHandle {
         self.stdin.take()
     }
 
+    /// Put back a stdin writer taken with [`stdin`](Self::stdin).
+    pub fn restore_stdin(&mut self, stdin: ExecStdin) {
+        self.stdin = Some(stdin);
+    }
+
     /// Close stdin (signals EOF to process)
     ///
     /// This drops the stdin handle, preventing further writes.
@@ -5,10 +5,12 @@
 //! - GuestExecutor: runs commands directly on guest
 
 use crate::container::Container;
+use crate::service::exec::cgroup;
 use crate::service::exec::exec_handle::{ExecHandle, PtyConfig};
 use async_trait::async_trait;
 use boxlite_shared::errors::{BoxliteError, BoxliteResult};
 use boxlite_shared::ExecRequest;
+use std::os::fd::RawFd;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
@@ -16,7 +18,10 @@ use tokio::sync::Mutex;
 #[async_trait]
 pub trait Executor: Send + Sync {
     /// Spawn process from ExecRequest.
-    async fn spawn(&self, req: &ExecRequest) -> BoxliteResult<ExecHandle>;
+    ///
+    /// With `cgroup`, an open `cgroup.procs`, the process joins that cgroup
+    /// before exec.
+    async fn spawn(&self, req: &ExecRequest, cgroup: Option<RawFd>) -> BoxliteResult<ExecHandle>;
 }
 
 /// Executes commands inside OCI container.
@@ -32,7 +37,7 @@ impl ContainerExecutor {
 
 #[async_trait]
 impl Executor for ContainerExecutor {
-    async fn spawn(&self, req: &ExecRequest) -> BoxliteResult<ExecHandle> {
+    async fn spawn(&self, req: &ExecRequest, cgroup: Option<RawFd>) -> BoxliteResult<ExecHandle> {
         let container = self.container.lock().await;
 
         let mut cmd = container
@@ -45,6 +50,16 @@ impl Executor for ContainerExecutor {
             cmd = cmd.current_dir(&req.workdir);
         }
 
+        if req.privileged {
+            cmd = cmd.user(container.resolve_user("0")?);
+        } else if let Some(user) = &req.user {
+            cmd = cmd.user(container.resolve_user(user)?);
+        }
+
+        if let Some(procs) = cgroup {
+            cmd = cmd.cgroup(procs);
+        }
+
         if let Some(tty) = &req.tty {
             cmd = cmd.with_pty(PtyConfig {
                 rows: tty.rows as u16,
@@ -63,7 +78,7 @@ pub struct GuestExecutor;
 
 #[async_trait]
 impl Executor for GuestExecutor {
-    async fn spawn(&self, req: &ExecRequest) -> BoxliteResult<ExecHandle> {
+    async fn spawn(&self, req: &ExecRequest, cgroup: Option<RawFd>) -> BoxliteResult<ExecHandle> {
         if let Some(tty) = &req.tty {
             let config = PtyConfig {
                 rows: tty.rows as u16,
@@ -71,15 +86,15 @@ impl Executor for GuestExecutor {
                 x_pixels: tty.x_pixels as u16,
                 y_pixels: tty.y_pixels as u16,
             };
-            spawn_with_pty(req, config)
+            spawn_with_pty(req, config, cgroup)
         } else {
-            spawn_with_pipes(req)
+            spawn_with_pipes(req, cgroup)
         }
     }
 }
 
 /// Spawn process with pipes (standard mode).
-fn spawn_with_pipes(req: &ExecRequest) -> BoxliteResult<ExecHandle> {
+fn spawn_with_pipes(req: &ExecRequest, cgroup: Option<RawFd>) -> BoxliteResult<ExecHandle> {
     use nix::unistd::Pid;
     use std::os::unix::io::{AsRawFd, FromRawFd};
     use tokio::process::Command;
@@ -110,6 +125,12 @@ fn spawn_with_pipes(req: &ExecRequest) -> BoxliteResult<ExecHandle> {
         cmd.stderr(std::process::Stdio::from_raw_fd(stderr_write.as_raw_fd()));
     }
 
+    if let Some(procs) = cgroup {
+        unsafe {
+            cmd.pre_exec(move || cgroup::join(procs));
+        }
+    }
+
     let child = cmd
         .spawn()
         .map_err(|e| BoxliteError::Internal(format!("Failed to spawn '{}': {}", req.program, e)))?;
@@ -133,7 +154,11 @@ fn spawn_with_pipes(req: &ExecRequest) -> BoxliteResult<ExecHandle> {
 }
 
 /// Spawn process with PTY (interactive mode).
-fn spawn_with_pty(req: &ExecRequest, config: PtyConfig) -> BoxliteResult<ExecHandle> {
+fn spawn_with_pty(
+    req: &ExecRequest,
+    config: PtyConfig,
+    cgroup: Option<RawFd>,
+) -> BoxliteResult<ExecHandle> {
     use nix::pty::{openpty, OpenptyResult, Winsize};
     use nix::unistd::{dup, Pid};
     use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
@@ -185,6 +210,10 @@ fn spawn_with_pty(req: &ExecRequest, config: PtyConfig) -> BoxliteResult<ExecHan
     // Set up session and controlling terminal in child
     unsafe {
         cmd.pre_exec(move || {
+            if let Some(procs) = cgroup {
+                cgroup::join(procs)?;
+            }
+
             // Create new session (detach from parent's controlling terminal)
             nix::unistd::setsid().map_err(std::io::Error::other)?;
 
@@ -10,15 +10,18 @@
 //!
 //! - **Protocol Layer** (mod.rs): gRPC service implementation
 //! - **Executor Layer** (executor.rs): Process spawning abstraction
-//! - **Lifecycle Layer** (timeout.rs): Process management
-//! - **State Layer** (registry.rs, state.rs): Execution state
+//! - **Lifecycle Layer** (timeout.rs, output_limit.rs, cgroup.rs): Process management
+//! - **State Layer** (registry.rs, state.rs, backlog.rs): Execution state
 //! - **Types** (types.rs): Shared types
 //!
 //! Each file has a single, clear responsibility.
 
+mod backlog;
+pub(crate) mod cgroup;
 #[cfg(target_os = "linux")]
 pub mod exec_handle;
 pub(in crate::service) mod executor;
+mod output_limit;
 pub(in crate::service) mod registry;
 mod state;
 mod timeout;
@@ -27,10 +30,12 @@ use crate::service::exec::executor::{ContainerExecutor, GuestExecutor};
 use crate::service::server::GuestServer;
 use boxlite_shared::{
     constants::executor as executor_const, AttachRequest, ExecError, ExecOutput, ExecRequest,
-    ExecResponse, ExecStdin, Execution, KillRequest, KillResponse, ResizeTtyRequest,
-    ResizeTtyResponse, SendInputAck, WaitRequest, WaitResponse,
+    ExecResponse, ExecStdin, Execution, ExecutionInfo, KillRequest, KillResponse,
+    ListExecutionsRequest, ListExecutionsResponse, ResizeTtyRequest, ResizeTtyResponse,
+    SendInputAck, WaitRequest, WaitResponse,
 };
 use futures::stream::Stream;
+use std::os::fd::{AsRawFd, RawFd};
 use std::pin::Pin;
 use tokio_stream::wrappers::ReceiverStream;
 use tonic::{Request, Response, Status, Streaming};
@@ -84,7 +89,7 @@ impl Execution for GuestServer {
             .ok_or_else(|| Status::not_found(format!("Execution not found: {}", exec_id)))?;
 
         // Call state directly
-        let rx = state.attach(&exec_id).await?;
+        let rx = state.attach().await?;
 
         Ok(Response::new(
             Box::pin(ReceiverStream::new(rx)) as Self::AttachStream
@@ -161,11 +166,13 @@ impl Execution for GuestServer {
             }
         };
 
+        state.mark_exit_delivered();
         Ok(Response::new(WaitResponse {
             exit_code,
             signal,
             timed_out: false,
             duration_ms: 0,
+            oom_killed: state.oom_killed(),
         }))
     }
 
@@ -268,6 +275,45 @@ impl Execution for GuestServer {
             }
         }
     }
+
+    async fn list_executions(
+        &self,
+        _request: Request<ListExecutionsRequest>,
+    ) -> Result<Response<ListExecutionsResponse>, Status> {
+        use exec_handle::ExitStatus;
+
+        let mut executions: Vec<ExecutionInfo> = self
+            .registry
+            .list()
+            .await
+            .into_iter()
+            .map(|(execution_id, state)| {
+                let meta = state.meta();
+                let error = state.reap_error();
+                let (running, exit_code, signal) = match state.exit_status() {
+                    None => (error.is_none(), 0, 0),
+                    Some(ExitStatus::Code(code)) => (false, code, 0),
+                    Some(ExitStatus::Signal(sig)) => (false, 0, sig as i32),
+                };
+                ExecutionInfo {
+                    execution_id,
+                    program: meta.program.clone(),
+                    args: meta.args.clone(),
+                    pid: meta.pid,
+                    started_at_ms: meta.started_at_ms,
+                    detached: meta.detached,
+                    running,
+                    exit_code,
+                    signal,
+                    error: error.unwrap_or_default(),
+                }
+            })
+            .collect();
+        executions.sort_by_key(|e| e.started_at_ms);
+
+        debug!(count = executions.len(), "list_executions request");
+        Ok(Response::new(ListExecutionsResponse { executions }))
+    }
 }
 
 /// Spawn execution (orchestrates full lifecycle).
@@ -278,27 +324,62 @@ async fn spawn_execution(
 ) -> Result<ExecResponse, ExecResponse> {
     let started_at_ms = now_ms();
 
-    // Step 1: Spawn process using executor selected by BOXLITE_EXECUTOR env var
-    let child = spawn_with_executor(server, &req, &execution_id).await?;
+    // Step 1: Prepare the resource-limited cgroup (if requested)
+    let cgroup = req
+        .limits
+        .as_ref()
+        .map(|limits| cgroup::ExecCgroup::create(&execution_id, limits))
+        .transpose()
+        .map_err(|e| spawn_error(&execution_id, e.to_string()))?;
+
+    // Step 2: Spawn process using executor selected by BOXLITE_EXECUTOR env var,
+    // joining the cgroup before exec
+    let procs = cgroup
+        .as_ref()
+        .map(|cgroup| cgroup.open_procs())
+        .transpose()
+        .map_err(|e| spawn_error(&execution_id, e.to_string()))?;
+    let procs_fd = procs.as_ref().map(|procs| procs.as_raw_fd());
+    let child = spawn_with_executor(server, &req, &execution_id, procs_fd).await?;
+    drop(procs);
 
     let pid = child.pid().as_raw() as u32;
 
-    // Step 2: Create execution state and register
-    let state = state::ExecutionState::new(child);
+    // Step 3: Create execution state and register
+    let output_limit =
+        output_limit::OutputLimit::from_request(req.max_output_bytes, req.output_limit_policy());
+    let meta = state::ExecutionMeta {
+        program: req.program.clone(),
+        args: req.args.clone(),
+        pid,
+        started_at_ms,
+        detached: req.detach,
+    };
+    let output_buffer =
+        state::OutputBuffer::from_request(req.output_buffer, req.output_buffer_policy());
+    let state = state::ExecutionState::new(&execution_id, child, meta, output_limit, output_buffer);
     server
         .registry
         .register(execution_id.clone(), state.clone())
         .await;
 
-    // Step 3: Start timeout watcher (if requested)
+    // Step 4: Start timeout watcher (if requested)
     if req.timeout_ms > 0 {
         timeout::start_timeout_watcher(
-            state,
+            state.clone(),
             execution_id.clone(),
             std::time::Duration::from_millis(req.timeout_ms),
         );
     }
 
+    // Step 5: Remove the cgroup once the process is gone
+    if let Some(cgroup) = cgroup {
+        tokio::spawn(async move {
+            let _ = state.wait_process().await;
+            drop(cgroup);
+        });
+    }
+
     Ok(ExecResponse {
         execution_id,
         pid,
@@ -307,6 +388,54 @@ async fn spawn_execution(
     })
 }
 
+/// Register a container's init process as a detached execution.
+///
+/// The execution ID is the container ID, so clients attach to init the
+/// same way as to any detached execution. Init's stdin stays open when
+/// they leave.
+pub(in crate::service) async fn register_init(
+    server: &GuestServer,
+    container_id: &str,
+    container: &mut crate::container::Container,
+    entrypoint: &[String],
+) -> boxlite_shared::errors::BoxliteResult<()> {
+    use boxlite_shared::errors::BoxliteError;
+
+    let pid = container
+        .init_pid()
+        .ok_or_else(|| BoxliteError::Internal("Container init process has no PID".into()))?;
+    let Some(fds) = container.take_attach_fds()? else {
+        return Ok(());
+    };
+
+    let mut handle = exec_handle::ExecHandle::new(pid, fds.stdin, fds.stdout, fds.stderr);
+    if let Some(pty) = fds.pty {
+        let config = exec_handle::PtyConfig {
+            rows: 24,
+            cols: 80,
+            x_pixels: 0,
+            y_pixels: 0,
+        };
+        handle.set_pty(std::fs::File::from(pty), config);
+    }
+
+    let meta = state::ExecutionMeta {
+        program: entrypoint.first().cloned().unwrap_or_default(),
+        args: entrypoint.iter().skip(1).cloned().collect(),
+        pid: pid.as_raw() as u32,
+        started_at_ms: now_ms(),
+        detached: true,
+    };
+    let state = state::ExecutionState::new(container_id, handle, meta, None, Default::default())
+        .with_persistent_stdin()
+        .await;
+    server
+        .registry
+        .register(container_id.to_string(), state)
+        .await;
+    Ok(())
+}
+
 fn error_response(id: String, reason: &str, detail: &str) -> ExecResponse {
     ExecResponse {
         execution_id: id,
@@ -349,6 +478,7 @@ async fn spawn_with_executor(
     server: &GuestServer,
     req: &ExecRequest,
     execution_id: &str,
+    cgroup: Option<RawFd>,
 ) -> Result<exec_handle::ExecHandle, ExecResponse> {
     use executor::Executor;
 
@@ -359,7 +489,7 @@ async fn spawn_with_executor(
             // Guest executor (explicit or default)
             debug!(execution_id = %execution_id, "Using GuestExecutor");
             GuestExecutor
-                .spawn(req)
+                .spawn(req, cgroup)
                 .await
                 .map_err(|e| spawn_error(execution_id, e.to_string()))
         }
@@ -392,7 +522,7 @@ async fn spawn_with_executor(
             };
             let executor = ContainerExecutor::new(container_arc);
             executor
-                .spawn(req)
+                .spawn(req, cgroup)
                 .await
                 .map_err(|e| spawn_error(execution_id, e.to_string()))
         }
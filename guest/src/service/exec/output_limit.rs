@@ -0,0 +1,148 @@
+//! Output size limits.
+//!
+//! Caps how many bytes of each output stream are forwarded to the host, so a
+//! runaway process can't exhaust host memory through the attach channel.
+
+use boxlite_shared::OutputLimitPolicy;
+
+/// Output limit requested for an execution (per stream).
+#[derive(Debug, Clone, Copy)]
+pub(super) struct OutputLimit {
+    pub max_bytes: usize,
+    pub policy: OutputLimitPolicy,
+}
+
+impl OutputLimit {
+    /// Build from request fields (`max_bytes == 0` means unlimited).
+    pub fn from_request(max_bytes: u64, policy: OutputLimitPolicy) -> Option<Self> {
+        (max_bytes > 0).then(|| Self {
+            max_bytes: usize::try_from(max_bytes).unwrap_or(usize::MAX),
+            policy,
+        })
+    }
+}
+
+/// Decision for a single output chunk.
+#[derive(Debug, PartialEq, Eq)]
+pub(super) enum Admit {
+    /// Forward these bytes now.
+    Forward(Vec<u8>),
+    /// Nothing to forward (dropped or buffered).
+    Hold,
+    /// Limit exceeded under the error policy.
+    Exceeded,
+}
+
+/// Applies an [`OutputLimit`] to one output stream.
+pub(super) struct OutputLimiter {
+    limit: Option<OutputLimit>,
+    forwarded: usize,
+    tail: Vec<u8>,
+    truncated: bool,
+}
+
+impl OutputLimiter {
+    pub fn new(limit: Option<OutputLimit>) -> Self {
+        Self {
+            limit,
+            forwarded: 0,
+            tail: Vec::new(),
+            truncated: false,
+        }
+    }
+
+    /// Whether any output was discarded.
+    pub fn truncated(&self) -> bool {
+        self.truncated
+    }
+
+    /// Process the next chunk read from the process.
+    pub fn admit(&mut self, mut chunk: Vec<u8>) -> Admit {
+        let Some(limit) = self.limit else {
+            return Admit::Forward(chunk);
+        };
+
+        match limit.policy {
+            OutputLimitPolicy::Head => {
+                let remaining = limit.max_bytes - self.forwarded;
+                if chunk.len() > remaining {
+                    self.truncated = true;
+                    chunk.truncate(remaining);
+                }
+                if chunk.is_empty() {
+                    return Admit::Hold;
+                }
+                self.forwarded += chunk.len();
+                Admit::Forward(chunk)
+            }
+            OutputLimitPolicy::Tail => {
+                self.tail.extend_from_slice(&chunk);
+                if self.tail.len() > limit.max_bytes {
+                    self.truncated = true;
+                    let excess = self.tail.len() - limit.max_bytes;
+                    self.tail.drain(..excess);
+                }
+                Admit::Hold
+            }
+            OutputLimitPolicy::Error => {
+                if self.forwarded + chunk.len() > limit.max_bytes {
+                    self.truncated = true;
+                    return Admit::Exceeded;
+                }
+                self.forwarded += chunk.len();
+                Admit::Forward(chunk)
+            }
+        }
+    }
+
+    /// Flush buffered output once the stream has ended.
+    pub fn finish(&mut self) -> Option<Vec<u8>> {
+        if self.tail.is_empty() {
+            None
+        } else {
+            Some(std::mem::take(&mut self.tail))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limiter(max_bytes: u64, policy: OutputLimitPolicy) -> OutputLimiter {
+        OutputLimiter::new(OutputLimit::from_request(max_bytes, policy))
+    }
+
+    #[test]
+    fn test_unlimited_forwards_everything() {
+        let mut l = limiter(0, OutputLimitPolicy::Head);
+        assert_eq!(l.admit(vec![1; 10]), Admit::Forward(vec![1; 10]));
+        assert_eq!(l.finish(), None);
+        assert!(!l.truncated());
+    }
+
+    #[test]
+    fn test_head_truncates_and_drops() {
+        let mut l = limiter(5, OutputLimitPolicy::Head);
+        assert_eq!(l.admit(b"abc".to_vec()), Admit::Forward(b"abc".to_vec()));
+        assert_eq!(l.admit(b"defg".to_vec()), Admit::Forward(b"de".to_vec()));
+        assert_eq!(l.admit(b"hij".to_vec()), Admit::Hold);
+        assert!(l.truncated());
+    }
+
+    #[test]
+    fn test_tail_keeps_last_bytes() {
+        let mut l = limiter(4, OutputLimitPolicy::Tail);
+        assert_eq!(l.admit(b"abc".to_vec()), Admit::Hold);
+        assert_eq!(l.admit(b"defg".to_vec()), Admit::Hold);
+        assert_eq!(l.finish(), Some(b"defg".to_vec()));
+        assert!(l.truncated());
+    }
+
+    #[test]
+    fn test_error_policy_reports_exceeded() {
+        let mut l = limiter(4, OutputLimitPolicy::Error);
+        assert_eq!(l.admit(b"abcd".to_vec()), Admit::Forward(b"abcd".to_vec()));
+        assert_eq!(l.admit(b"e".to_vec()), Admit::Exceeded);
+    }
+}
@@ -2,12 +2,20 @@
 //!
 //! Manages the state of all active executions, providing thread-safe access
 //! to execution metadata, I/O channels, and completion status.
+//!
+//! An execution is removed once its process has exited, a client has been
+//! told its exit status and its output has been read. Executions nobody
+//! comes back for are removed a while after they exit.
 
 use crate::service::exec::state::ExecutionState;
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::Mutex;
 
+/// How long an exited execution is kept for a client to collect it.
+const DEFAULT_TTL: Duration = Duration::from_secs(600);
+
 /// Registry of active executions.
 ///
 /// Thread-safe registry that stores execution state and provides
@@ -15,13 +23,21 @@ use tokio::sync::Mutex;
 #[derive(Clone)]
 pub(crate) struct ExecutionRegistry {
     executions: Arc<Mutex<HashMap<String, ExecutionState>>>,
+    /// How long an exited execution is kept for a client to collect it.
+    ttl: Duration,
 }
 
 impl ExecutionRegistry {
     /// Create new registry.
     pub fn new() -> Self {
+        Self::with_ttl(DEFAULT_TTL)
+    }
+
+    /// Create a registry keeping exited, uncollected executions for `ttl`.
+    pub fn with_ttl(ttl: Duration) -> Self {
         Self {
             executions: Arc::new(Mutex::new(HashMap::new())),
+            ttl,
         }
     }
 
@@ -35,8 +51,88 @@ impl ExecutionRegistry {
         self.executions.lock().await.get(exec_id).cloned()
     }
 
+    /// Snapshot of all executions.
+    pub async fn list(&self) -> Vec<(String, ExecutionState)> {
+        self.executions
+            .lock()
+            .await
+            .iter()
+            .map(|(id, state)| (id.clone(), state.clone()))
+            .collect()
+    }
+
     /// Register new execution state.
+    ///
+    /// The execution is removed again once it is no longer needed.
     pub async fn register(&self, exec_id: String, state: ExecutionState) {
-        self.executions.lock().await.insert(exec_id, state);
+        self.executions
+            .lock()
+            .await
+            .insert(exec_id.clone(), state.clone());
+
+        let executions = self.executions.clone();
+        let ttl = self.ttl;
+        tokio::spawn(async move {
+            state.released(ttl).await;
+            executions.lock().await.remove(&exec_id);
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service::exec::executor::{Executor, GuestExecutor};
+    use crate::service::exec::state::ExecutionMeta;
+    use boxlite_shared::ExecRequest;
+
+    async fn register(registry: &ExecutionRegistry, exec_id: &str) -> ExecutionState {
+        let req = ExecRequest {
+            program: "true".to_string(),
+            ..Default::default()
+        };
+        let handle = GuestExecutor.spawn(&req, None).await.unwrap();
+        let meta = ExecutionMeta {
+            program: req.program.clone(),
+            args: vec![],
+            pid: handle.pid().as_raw() as u32,
+            started_at_ms: 0,
+            detached: true,
+        };
+        let state = ExecutionState::new(exec_id, handle, meta, None, Default::default());
+        registry.register(exec_id.to_string(), state.clone()).await;
+        state
+    }
+
+    async fn wait_removed(registry: &ExecutionRegistry, exec_id: &str) {
+        tokio::time::timeout(Duration::from_secs(10), async {
+            while registry.exists(exec_id).await {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+            }
+        })
+        .await
+        .expect("execution was not removed");
+    }
+
+    #[tokio::test]
+    async fn test_removed_once_collected() {
+        let registry = ExecutionRegistry::with_ttl(Duration::from_secs(3600));
+        let state = register(&registry, "collected").await;
+        assert!(registry.exists("collected").await);
+
+        let mut output = state.attach().await.unwrap();
+        while output.recv().await.is_some() {}
+        state.wait_process().await.unwrap();
+        state.mark_exit_delivered();
+
+        wait_removed(&registry, "collected").await;
+    }
+
+    #[tokio::test]
+    async fn test_removed_after_ttl() {
+        let registry = ExecutionRegistry::with_ttl(Duration::from_millis(100));
+        register(&registry, "abandoned").await;
+
+        wait_removed(&registry, "abandoned").await;
     }
 }
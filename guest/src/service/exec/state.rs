@@ -1,8 +1,12 @@
-use crate::service::exec::exec_handle::ExecHandle;
-use boxlite_shared::ExecOutput;
+use crate::service::exec::backlog::OutputBacklog;
+use crate::service::exec::exec_handle::{ExecHandle, ExecStdin, ExitStatus};
+use crate::service::exec::output_limit::{Admit, OutputLimit, OutputLimiter};
+use boxlite_shared::{ExecOutput, OutputBufferPolicy};
 use std::os::unix::io::AsRawFd;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
-use tokio::sync::{mpsc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, watch, Mutex};
 use tokio::task::JoinHandle;
 use tonic::Status;
 use tracing::info;
@@ -11,36 +15,221 @@ use tracing::info;
 struct Inner {
     /// The process handle (owns pid, pty_controller, stdin, stdout, stderr)
     handle: Option<ExecHandle>,
-    /// Stdout/stderr forwarding tasks (set on attach)
-    output_tasks: Vec<JoinHandle<()>>,
+    /// Return stdin to the handle when a client is done with it
+    persistent_stdin: bool,
     /// Timeout flag
     #[allow(dead_code)] // Will be used for timeout handling
     timed_out: bool,
 }
 
+/// How long a non-detached execution may run with no client attached
+/// before it is killed. Gives a host that lost its connection time to
+/// reattach.
+const RECONNECT_GRACE: Duration = Duration::from_secs(15);
+
+/// How long output of an exited execution is kept for a client to read.
+const BACKLOG_TTL: Duration = Duration::from_secs(300);
+
+/// Exit result published by the reaper task.
+type ExitResult = Option<Result<ExitStatus, String>>;
+
+/// Immutable description of an execution (reported by ListExecutions).
+#[derive(Debug, Clone)]
+pub(crate) struct ExecutionMeta {
+    pub program: String,
+    pub args: Vec<String>,
+    pub pid: u32,
+    pub started_at_ms: u64,
+    pub detached: bool,
+}
+
+/// Chunks buffered for the attached client, and whether output is
+/// discarded instead of waiting for room.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct OutputBuffer {
+    pub capacity: usize,
+    pub drop_when_full: bool,
+}
+
+impl OutputBuffer {
+    /// Default attach channel capacity.
+    const DEFAULT_CAPACITY: usize = 100;
+
+    pub fn from_request(capacity: u32, policy: OutputBufferPolicy) -> Self {
+        Self {
+            capacity: match capacity {
+                0 => Self::DEFAULT_CAPACITY,
+                n => n as usize,
+            },
+            drop_when_full: policy == OutputBufferPolicy::Drop,
+        }
+    }
+}
+
+impl Default for OutputBuffer {
+    fn default() -> Self {
+        Self::from_request(0, OutputBufferPolicy::Block)
+    }
+}
+
+/// Ordering metadata for output chunks.
+///
+/// Shared by the stdout and stderr forwarders (and kept across reattaches),
+/// so chunks of both streams can be put back in the order they were read.
+pub(super) struct OutputClock {
+    started: Instant,
+    next_seq: AtomicU64,
+}
+
+impl OutputClock {
+    fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            next_seq: AtomicU64::new(1),
+        }
+    }
+
+    /// Sequence number and nanoseconds since the process started.
+    fn stamp(&self) -> (u64, u64) {
+        let seq = self.next_seq.fetch_add(1, Ordering::Relaxed);
+        let elapsed = u64::try_from(self.started.elapsed().as_nanos()).unwrap_or(u64::MAX);
+        (seq, elapsed)
+    }
+}
+
 /// Execution state.
 ///
 /// Handle owns pid, pty_controller, stdin, stdout, stderr.
-/// stdin is taken on send_input(), stdout/stderr are taken on attach().
+/// stdin is taken on send_input(); stdout/stderr are taken at creation and
+/// forwarded into a backlog, so clients can attach (and reattach after a
+/// lost connection) for as long as the execution is registered.
 #[derive(Clone)]
 pub(crate) struct ExecutionState {
     inner: Arc<Mutex<Inner>>,
+    /// Exit status, set once the reaper task has collected the process.
+    exit_rx: watch::Receiver<ExitResult>,
+    /// Output not yet taken by a client.
+    backlog: Arc<OutputBacklog>,
+    /// Set once a client has been told the exit status.
+    exit_delivered: Arc<watch::Sender<bool>>,
+    meta: Arc<ExecutionMeta>,
+    /// Guest OOM kill count when the process started.
+    oom_kills_at_start: u64,
 }
 
 impl ExecutionState {
     /// Create new execution state.
-    pub(super) fn new(handle: ExecHandle) -> Self {
+    ///
+    /// Starts a reaper task so the exit status survives waiters going away,
+    /// and starts forwarding output right away. A non-detached execution is
+    /// killed once it has had no client for [`RECONNECT_GRACE`].
+    pub(super) fn new(
+        exec_id: &str,
+        mut handle: ExecHandle,
+        meta: ExecutionMeta,
+        output_limit: Option<OutputLimit>,
+        output_buffer: OutputBuffer,
+    ) -> Self {
+        let pid = handle.pid();
+        let oom_kills_at_start = oom_kill_count();
+        let exit_rx = spawn_reaper(pid);
+        let clock = Arc::new(OutputClock::new());
+
+        let stdout = handle.stdout();
+        let stderr = handle.stderr();
+        let backlog = Arc::new(OutputBacklog::new(
+            usize::from(stdout.is_some()) + usize::from(stderr.is_some()),
+            output_buffer,
+        ));
+        if let Some(stdout) = stdout {
+            tokio::spawn(forward_output(
+                stdout,
+                backlog.clone(),
+                exec_id.to_string(),
+                pid,
+                OutputLimiter::new(output_limit),
+                clock.clone(),
+                stdout_event,
+            ));
+        }
+        if let Some(stderr) = stderr {
+            tokio::spawn(forward_output(
+                stderr,
+                backlog.clone(),
+                exec_id.to_string(),
+                pid,
+                OutputLimiter::new(output_limit),
+                clock,
+                stderr_event,
+            ));
+        }
+
+        tokio::spawn(watch_clients(
+            exit_rx.clone(),
+            backlog.clone(),
+            exec_id.to_string(),
+            pid,
+            meta.detached,
+        ));
+
         let inner = Inner {
             handle: Some(handle),
-            output_tasks: Vec::new(),
+            persistent_stdin: false,
             timed_out: false,
         };
 
         Self {
             inner: Arc::new(Mutex::new(inner)),
+            exit_rx,
+            backlog,
+            exit_delivered: Arc::new(watch::channel(false).0),
+            meta: Arc::new(meta),
+            oom_kills_at_start,
         }
     }
 
+    /// Keep stdin open across clients.
+    ///
+    /// Used for container init processes, which must not see EOF when an
+    /// attached client leaves or closes its input.
+    pub(super) async fn with_persistent_stdin(self) -> Self {
+        self.inner.lock().await.persistent_stdin = true;
+        self
+    }
+
+    /// Description of the execution.
+    pub fn meta(&self) -> &ExecutionMeta {
+        &self.meta
+    }
+
+    /// Exit status, or `None` while the process is running or if it could
+    /// not be collected.
+    pub fn exit_status(&self) -> Option<ExitStatus> {
+        match *self.exit_rx.borrow() {
+            Some(Ok(status)) => Some(status),
+            _ => None,
+        }
+    }
+
+    /// Why the exit status could not be collected, if the reaper failed.
+    pub fn reap_error(&self) -> Option<String> {
+        match &*self.exit_rx.borrow() {
+            Some(Err(e)) => Some(e.clone()),
+            _ => None,
+        }
+    }
+
+    /// Whether the process was killed by the OOM killer.
+    ///
+    /// The VM runs one container, so a SIGKILL while the guest's OOM kill
+    /// count went up is taken to be the OOM killer's.
+    pub fn oom_killed(&self) -> bool {
+        matches!(
+            self.exit_status(),
+            Some(ExitStatus::Signal(nix::sys::signal::Signal::SIGKILL))
+        ) && oom_kill_count() > self.oom_kills_at_start
+    }
+
     /// Get PID for execution.
     #[allow(dead_code)] // API completeness
     pub async fn get_pid(&self) -> Option<u32> {
@@ -58,43 +247,54 @@ impl ExecutionState {
         mut stream: tonic::Streaming<boxlite_shared::ExecStdin>,
     ) -> Result<JoinHandle<Result<(), Status>>, Status> {
         // Take stdin from handle
-        let mut stdin = {
+        let (mut stdin, tty, persistent) = {
             let mut inner = self.inner.lock().await;
+            let persistent = inner.persistent_stdin;
             let handle = inner
                 .handle
                 .as_mut()
                 .ok_or_else(|| Status::failed_precondition("Handle not available"))?;
 
-            handle
+            let tty = handle.pty_controller().is_some();
+            let stdin = handle
                 .stdin()
-                .ok_or_else(|| Status::already_exists("Stdin already taken"))?
+                .ok_or_else(|| Status::already_exists("Stdin already taken"))?;
+            (stdin, tty, persistent)
         };
 
+        if persistent {
+            let inner = self.inner.clone();
+            return Ok(tokio::spawn(async move {
+                let result = forward_persistent_stdin(&mut stdin, first, stream).await;
+                // Hand stdin back for the next client
+                if let Some(handle) = inner.lock().await.handle.as_mut() {
+                    handle.restore_stdin(stdin);
+                }
+                result
+            }));
+        }
+
         // Spawn forwarding task
         let task = tokio::spawn(async move {
-            // Write first message data
-            if !first.data.is_empty() {
-                stdin
-                    .write_all(&first.data)
-                    .await
-                    .map_err(|e| Status::internal(format!("Stdin write failed: {}", e)))?;
-            }
-            if first.close {
-                return Ok(());
-            }
-
-            // Forward remaining messages
-            while let Some(msg) = stream.message().await? {
-                if !msg.data.is_empty() {
+            let mut msg = Some(first);
+            while let Some(input) = msg {
+                if !input.data.is_empty() {
                     stdin
-                        .write_all(&msg.data)
+                        .write_all(&input.data)
                         .await
                         .map_err(|e| Status::internal(format!("Stdin write failed: {}", e)))?;
                 }
-                if msg.close {
-                    break;
+                if input.close {
+                    // Half-close: the process sees EOF but keeps running
+                    return stdin
+                        .close(tty)
+                        .await
+                        .map_err(|e| Status::internal(format!("Stdin close failed: {}", e)));
                 }
+                msg = stream.message().await?;
             }
+
+            // Client went away without closing: the fd is still closed on drop
             Ok(())
         });
 
@@ -103,117 +303,50 @@ impl ExecutionState {
 
     /// Wait for process to exit.
     ///
-    /// Gets pid from handle and waits using waitpid.
-    pub async fn wait_process(
-        &self,
-    ) -> Result<crate::service::exec::exec_handle::ExitStatus, Status> {
-        use crate::service::exec::exec_handle::ExitStatus;
-        use nix::sys::wait::{waitpid, WaitStatus};
-
-        // Get pid from handle
-        let pid = {
-            let inner = self.inner.lock().await;
-            inner
-                .handle
-                .as_ref()
-                .ok_or_else(|| Status::failed_precondition("Handle not available"))?
-                .pid()
-        };
-
-        // Wait for process (blocking call in spawn_blocking)
-        let result = tokio::task::spawn_blocking(move || waitpid(pid, None))
+    /// Can be called any number of times; every caller gets the same status.
+    pub async fn wait_process(&self) -> Result<ExitStatus, Status> {
+        let mut exit_rx = self.exit_rx.clone();
+        let exit = exit_rx
+            .wait_for(Option::is_some)
             .await
-            .map_err(|e| Status::internal(format!("spawn_blocking failed: {}", e)))?
-            .map_err(|e| Status::internal(format!("waitpid failed: {}", e)))?;
-
-        match result {
-            WaitStatus::Exited(_, code) => Ok(ExitStatus::Code(code)),
-            WaitStatus::Signaled(_, sig, _) => Ok(ExitStatus::Signal(sig)),
-            other => Err(Status::internal(format!(
-                "Unexpected wait status: {:?}",
-                other
-            ))),
+            .map_err(|_| Status::internal("Reaper task ended without exit status"))?;
+
+        match exit.as_ref() {
+            Some(Ok(status)) => Ok(*status),
+            Some(Err(e)) => Err(Status::internal(e.clone())),
+            None => unreachable!("wait_for returned without exit status"),
         }
     }
 
-    /// Attach to execution output.
-    ///
-    /// Takes stdout/stderr from handle and starts forwarding tasks.
-    /// Returns stream of output chunks.
-    pub async fn attach(
-        &self,
-        exec_id: &str,
-    ) -> Result<mpsc::Receiver<Result<ExecOutput, Status>>, Status> {
-        use boxlite_shared::{exec_output, Stderr, Stdout};
-        use futures::StreamExt;
-
-        let (tx, rx) = mpsc::channel(100);
-
-        // Take stdout/stderr from handle
-        let (stdout, stderr) = {
-            let mut inner = self.inner.lock().await;
+    /// Record that a client has been told the exit status.
+    pub fn mark_exit_delivered(&self) {
+        self.exit_delivered.send_replace(true);
+    }
 
-            if !inner.output_tasks.is_empty() {
-                return Err(Status::already_exists("Already attached"));
+    /// Wait until the execution is no longer needed: the process exited,
+    /// a client got its exit status and all output was read. Gives up
+    /// waiting for a client `ttl` after the exit.
+    pub async fn released(&self, ttl: Duration) {
+        let _ = self.wait_process().await;
+
+        let mut delivered = self.exit_delivered.subscribe();
+        let consumed = async {
+            let _ = delivered.wait_for(|delivered| *delivered).await;
+            while !self.backlog.is_drained() {
+                tokio::time::sleep(Duration::from_secs(1)).await;
             }
-
-            let handle = inner
-                .handle
-                .as_mut()
-                .ok_or_else(|| Status::failed_precondition("Handle not available"))?;
-
-            let stdout = handle.stdout();
-            let stderr = handle.stderr();
-
-            (stdout, stderr)
         };
+        let _ = tokio::time::timeout(ttl, consumed).await;
+    }
 
-        // Spawn forwarding tasks
-        let mut tasks = Vec::new();
-
-        // Spawn stdout forwarding task
-        let exec_id_string = exec_id.to_string();
-        if let Some(mut stdout) = stdout {
-            let tx = tx.clone();
-            let handle = tokio::spawn(async move {
-                while let Some(chunk) = stdout.next().await {
-                    let msg = ExecOutput {
-                        event: Some(exec_output::Event::Stdout(Stdout { data: chunk })),
-                    };
-                    if tx.send(Ok(msg)).await.is_err() {
-                        break;
-                    }
-                }
-                info!(execution = ?exec_id_string, "Stdout forwarding task ended");
-            });
-            tasks.push(handle);
-        }
-
-        // Spawn stderr forwarding task
-        let exec_id_string = exec_id.to_string();
-        if let Some(mut stderr) = stderr {
-            let tx = tx.clone();
-            let handle = tokio::spawn(async move {
-                while let Some(chunk) = stderr.next().await {
-                    let msg = ExecOutput {
-                        event: Some(exec_output::Event::Stderr(Stderr { data: chunk })),
-                    };
-                    if tx.send(Ok(msg)).await.is_err() {
-                        break;
-                    }
-                }
-                info!(execution = ?exec_id_string, "Stderr forwarding task ended");
-            });
-            tasks.push(handle);
-        }
-
-        // Store tasks
-        {
-            let mut inner = self.inner.lock().await;
-            inner.output_tasks = tasks;
-        }
-
-        Ok(rx)
+    /// Attach to execution output.
+    ///
+    /// Replays output produced while no client was attached, then streams
+    /// the rest. Fails while another client is attached.
+    pub async fn attach(&self) -> Result<mpsc::Receiver<Result<ExecOutput, Status>>, Status> {
+        self.backlog
+            .attach()
+            .ok_or_else(|| Status::already_exists("Already attached"))
     }
 
     /// Kill process with signal.
@@ -268,3 +401,161 @@ impl ExecutionState {
         Ok(())
     }
 }
+
+fn stdout_event(
+    data: Vec<u8>,
+    (seq, timestamp_ns): (u64, u64),
+) -> boxlite_shared::exec_output::Event {
+    boxlite_shared::exec_output::Event::Stdout(boxlite_shared::Stdout {
+        data,
+        seq,
+        timestamp_ns,
+    })
+}
+
+fn stderr_event(
+    data: Vec<u8>,
+    (seq, timestamp_ns): (u64, u64),
+) -> boxlite_shared::exec_output::Event {
+    boxlite_shared::exec_output::Event::Stderr(boxlite_shared::Stderr {
+        data,
+        seq,
+        timestamp_ns,
+    })
+}
+
+/// Forward a client's input to stdin that outlives the client.
+///
+/// A close from the client only ends its session: on a PTY, Ctrl-D would
+/// end an interactive init process just because a client detached.
+async fn forward_persistent_stdin(
+    stdin: &mut ExecStdin,
+    first: boxlite_shared::ExecStdin,
+    mut stream: tonic::Streaming<boxlite_shared::ExecStdin>,
+) -> Result<(), Status> {
+    let mut msg = Some(first);
+    while let Some(input) = msg {
+        if !input.data.is_empty() {
+            stdin
+                .write_all(&input.data)
+                .await
+                .map_err(|e| Status::internal(format!("Stdin write failed: {}", e)))?;
+        }
+        if input.close {
+            return Ok(());
+        }
+        msg = stream.message().await?;
+    }
+    Ok(())
+}
+
+/// Number of processes killed by the OOM killer since boot.
+fn oom_kill_count() -> u64 {
+    std::fs::read_to_string("/proc/vmstat")
+        .ok()
+        .and_then(|vmstat| {
+            vmstat
+                .lines()
+                .find_map(|line| line.strip_prefix("oom_kill "))
+                .and_then(|count| count.trim().parse().ok())
+        })
+        .unwrap_or(0)
+}
+
+/// Reap the process in the background and publish its exit status.
+fn spawn_reaper(pid: nix::unistd::Pid) -> watch::Receiver<ExitResult> {
+    use nix::sys::wait::{waitpid, WaitStatus};
+
+    let (tx, rx) = watch::channel(None);
+    tokio::spawn(async move {
+        let result = match tokio::task::spawn_blocking(move || waitpid(pid, None)).await {
+            Ok(Ok(WaitStatus::Exited(_, code))) => Ok(ExitStatus::Code(code)),
+            Ok(Ok(WaitStatus::Signaled(_, sig, _))) => Ok(ExitStatus::Signal(sig)),
+            Ok(Ok(other)) => Err(format!("Unexpected wait status: {:?}", other)),
+            Ok(Err(e)) => Err(format!("waitpid failed: {}", e)),
+            Err(e) => Err(format!("spawn_blocking failed: {}", e)),
+        };
+        let _ = tx.send(Some(result));
+    });
+    rx
+}
+
+/// Kill a non-detached execution whose client is gone for good, and drop
+/// output still unread [`BACKLOG_TTL`] after the process exited.
+async fn watch_clients(
+    mut exit_rx: watch::Receiver<ExitResult>,
+    backlog: Arc<OutputBacklog>,
+    exec_id: String,
+    pid: nix::unistd::Pid,
+    detached: bool,
+) {
+    if !detached {
+        let mut unattended_since: Option<Instant> = None;
+        let mut tick = tokio::time::interval(Duration::from_secs(1));
+        loop {
+            tokio::select! {
+                _ = exit_rx.wait_for(Option::is_some) => break,
+                _ = tick.tick() => {}
+            }
+            if !backlog.is_unattended() {
+                unattended_since = None;
+                continue;
+            }
+            if unattended_since.get_or_insert_with(Instant::now).elapsed() >= RECONNECT_GRACE {
+                info!(execution = ?exec_id, "Client gone, killing process");
+                let _ = nix::sys::signal::kill(pid, nix::sys::signal::Signal::SIGKILL);
+                break;
+            }
+        }
+    }
+
+    let _ = exit_rx.wait_for(Option::is_some).await;
+    tokio::time::sleep(BACKLOG_TTL).await;
+    backlog.discard();
+}
+
+/// Forward one output stream to the backlog, applying the output limit.
+async fn forward_output<S>(
+    mut stream: S,
+    backlog: Arc<OutputBacklog>,
+    exec_id: String,
+    pid: nix::unistd::Pid,
+    mut limiter: OutputLimiter,
+    clock: Arc<OutputClock>,
+    to_event: fn(Vec<u8>, (u64, u64)) -> boxlite_shared::exec_output::Event,
+) where
+    S: futures::Stream<Item = Vec<u8>> + Unpin,
+{
+    use futures::StreamExt;
+
+    let output = |data: Vec<u8>, stamp| ExecOutput {
+        event: Some(to_event(data, stamp)),
+    };
+
+    while let Some(chunk) = stream.next().await {
+        // Stamp on read, before any buffering
+        let stamp = clock.stamp();
+        match limiter.admit(chunk) {
+            Admit::Forward(data) => backlog.push(Ok(output(data, stamp))).await,
+            Admit::Hold => {}
+            Admit::Exceeded => {
+                info!(execution = ?exec_id, "Output limit exceeded, killing process");
+                let _ = nix::sys::signal::kill(pid, nix::sys::signal::Signal::SIGKILL);
+                backlog
+                    .push(Err(Status::resource_exhausted("Output limit exceeded")))
+                    .await;
+                backlog.close_stream();
+                return;
+            }
+        }
+    }
+
+    if let Some(data) = limiter.finish() {
+        backlog.push(Ok(output(data, clock.stamp()))).await;
+    }
+    if limiter.truncated() {
+        info!(execution = ?exec_id, "Output truncated by output limit");
+    }
+    backlog.close_stream();
+    info!(execution = ?exec_id, "Output forwarding task ended");
+}
@@ -0,0 +1,474 @@
+//! Files service implementation.
+//!
+//! Paths are resolved in the container's mount namespace through
+//! `/proc/<init pid>/root`, so files on volumes and tmpfs mounts are
+//! reachable too. See [`crate::storage::archive`] for how lookups are kept
+//! inside the container.
+
+use std::fs::File;
+use std::io::{self, Read};
+use std::os::unix::io::AsRawFd;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+
+use boxlite_shared::{
+    ChmodRequest, ChmodResponse, ChownRequest, ChownResponse, CopyInChunk, CopyInResponse,
+    CopyOutRequest, FileChunk, FileStat, Files, GetFileRequest, ListDirRequest, ListDirResponse,
+    ListFilesResponse, ListTreeRequest, PutFileChunk, PutFileResponse, RemovePathsRequest,
+    RemovePathsResponse, StatRequest, WatchEvent, WatchRequest,
+};
+use futures::stream::Stream;
+use nix::libc;
+use nix::sys::stat::{fchmod, Mode};
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{Request, Response, Status, Streaming};
+use tracing::debug;
+
+use crate::service::server::GuestServer;
+use crate::storage::archive::{self, open_in_root};
+use crate::storage::layer::{self, ChunkWriter};
+use crate::storage::watch::Watcher;
+
+/// Bytes per `GetFile` chunk.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Watch events buffered for a slow reader.
+const WATCH_BUFFER: usize = 256;
+
+type FileChunkStream = Pin<Box<dyn Stream<Item = Result<FileChunk, Status>> + Send + 'static>>;
+
+impl GuestServer {
+    /// Root directory of a container as its processes see it.
+    async fn container_root(&self, container_id: &str) -> Result<File, Status> {
+        let container = self
+            .containers
+            .lock()
+            .await
+            .get(container_id)
+            .cloned()
+            .ok_or_else(|| Status::not_found(format!("Container not found: {}", container_id)))?;
+        let pid = container.lock().await.init_pid().ok_or_else(|| {
+            Status::failed_precondition(format!("Container is not running: {}", container_id))
+        })?;
+        let root = PathBuf::from(format!("/proc/{}/root", pid));
+        File::open(&root).map_err(|e| io_status(&root.to_string_lossy(), e))
+    }
+}
+
+#[tonic::async_trait]
+impl Files for GuestServer {
+    type GetFileStream = FileChunkStream;
+    type CopyOutStream = FileChunkStream;
+    type ListTreeStream =
+        Pin<Box<dyn Stream<Item = Result<ListFilesResponse, Status>> + Send + 'static>>;
+    type WatchStream = Pin<Box<dyn Stream<Item = Result<WatchEvent, Status>> + Send + 'static>>;
+
+    async fn put_file(
+        &self,
+        request: Request<Streaming<PutFileChunk>>,
+    ) -> Result<Response<PutFileResponse>, Status> {
+        let mut stream = request.into_inner();
+        let first = stream
+            .message()
+            .await?
+            .ok_or_else(|| Status::invalid_argument("Empty PutFile stream"))?;
+        let header = first
+            .header
+            .ok_or_else(|| Status::invalid_argument("First PutFile chunk has no header"))?;
+
+        debug!(
+            container_id = %header.container_id,
+            path = %header.path,
+            mode = format!("{:o}", header.mode),
+            offset = header.offset,
+            "put_file request"
+        );
+
+        let root = self.container_root(&header.container_id).await?;
+        let mode = header.mode & 0o7777;
+        let flags = if header.offset > 0 {
+            libc::O_WRONLY | libc::O_CREAT
+        } else {
+            libc::O_WRONLY | libc::O_CREAT | libc::O_TRUNC
+        };
+        let fd = open_in_root(&root, Path::new(&header.path), flags, mode)
+            .map_err(|e| io_status(&header.path, e))?;
+        // The mode given to open() is filtered by the umask
+        fchmod(fd.as_raw_fd(), Mode::from_bits_truncate(mode))
+            .map_err(|e| io_status(&header.path, e.into()))?;
+
+        let file = File::from(fd);
+        if header.offset > 0 {
+            let existing = file
+                .metadata()
+                .map_err(|e| io_status(&header.path, e))?
+                .len();
+            if existing < header.offset {
+                return Err(Status::failed_precondition(format!(
+                    "Cannot resume {} at {}: only {} bytes written",
+                    header.path, header.offset, existing
+                )));
+            }
+            // Drop whatever came after the resume point
+            file.set_len(header.offset)
+                .map_err(|e| io_status(&header.path, e))?;
+        }
+        let mut file = tokio::fs::File::from_std(file);
+        file.seek(io::SeekFrom::Start(header.offset))
+            .await
+            .map_err(|e| io_status(&header.path, e))?;
+        let mut size = header.offset;
+        let mut data = first.data;
+        loop {
+            file.write_all(&data)
+                .await
+                .map_err(|e| io_status(&header.path, e))?;
+            size += data.len() as u64;
+            match stream.message().await? {
+                Some(chunk) => data = chunk.data,
+                None => break,
+            }
+        }
+        file.flush().await.map_err(|e| io_status(&header.path, e))?;
+
+        if size != header.size {
+            return Err(Status::data_loss(format!(
+                "Upload of {} ended after {} of {} bytes",
+                header.path, size, header.size
+            )));
+        }
+
+        Ok(Response::new(PutFileResponse { size }))
+    }
+
+    async fn get_file(
+        &self,
+        request: Request<GetFileRequest>,
+    ) -> Result<Response<Self::GetFileStream>, Status> {
+        let req = request.into_inner();
+        debug!(
+            container_id = %req.container_id,
+            path = %req.path,
+            offset = req.offset,
+            "get_file request"
+        );
+
+        let root = self.container_root(&req.container_id).await?;
+        let file = open_in_root(&root, Path::new(&req.path), libc::O_RDONLY, 0)
+            .map(File::from)
+            .map_err(|e| io_status(&req.path, e))?;
+        let metadata = file.metadata().map_err(|e| io_status(&req.path, e))?;
+        if !metadata.is_file() {
+            return Err(Status::invalid_argument(format!(
+                "Not a regular file: {}",
+                req.path
+            )));
+        }
+        if req.max_size > 0 && metadata.len() > req.max_size {
+            return Err(Status::out_of_range(format!(
+                "{} is {} bytes, over the limit of {}",
+                req.path,
+                metadata.len(),
+                req.max_size
+            )));
+        }
+
+        let (tx, rx) = mpsc::channel(4);
+        tokio::spawn(async move {
+            let mut file = tokio::fs::File::from_std(file);
+            if let Err(e) = file.seek(io::SeekFrom::Start(req.offset)).await {
+                let _ = tx.send(Err(io_status(&req.path, e))).await;
+                return;
+            }
+            loop {
+                let mut data = vec![0; CHUNK_SIZE];
+                let chunk = match file.read(&mut data).await {
+                    Ok(0) => return,
+                    Ok(n) => {
+                        data.truncate(n);
+                        Ok(FileChunk { data })
+                    }
+                    Err(e) => Err(io_status(&req.path, e)),
+                };
+                let failed = chunk.is_err();
+                if tx.send(chunk).await.is_err() || failed {
+                    return;
+                }
+            }
+        });
+
+        Ok(Response::new(Box::pin(ReceiverStream::new(rx))))
+    }
+
+    async fn copy_in(
+        &self,
+        request: Request<Streaming<CopyInChunk>>,
+    ) -> Result<Response<CopyInResponse>, Status> {
+        let mut stream = request.into_inner();
+        let first = stream
+            .message()
+            .await?
+            .ok_or_else(|| Status::invalid_argument("Empty CopyIn stream"))?;
+        let header = first
+            .header
+            .ok_or_else(|| Status::invalid_argument("First CopyIn chunk has no header"))?;
+        debug!(
+            container_id = %header.container_id,
+            dir = %header.dir,
+            create_dir = header.create_dir,
+            "copy_in request"
+        );
+
+        let root = self.container_root(&header.container_id).await?;
+
+        // Feed the blocking extractor as chunks arrive
+        let (tx, rx) = mpsc::channel::<Vec<u8>>(4);
+        let dir = header.dir.clone();
+        let create_dir = header.create_dir;
+        let extractor = tokio::task::spawn_blocking(move || {
+            if create_dir {
+                archive::create_dir_all(&root, Path::new(&dir))?;
+            }
+            archive::extract(&root, Path::new(&dir), ChannelReader::new(rx))
+        });
+
+        let mut data = first.data;
+        loop {
+            if !data.is_empty() && tx.send(data).await.is_err() {
+                // The extractor stopped early; its result says why
+                break;
+            }
+            match stream.message().await? {
+                Some(chunk) => data = chunk.data,
+                None => break,
+            }
+        }
+        drop(tx);
+
+        let entries = extractor
+            .await
+            .map_err(|e| Status::internal(format!("Extract task failed: {}", e)))?
+            .map_err(|e| io_status(&header.dir, e))?;
+        Ok(Response::new(CopyInResponse { entries }))
+    }
+
+    async fn copy_out(
+        &self,
+        request: Request<CopyOutRequest>,
+    ) -> Result<Response<Self::CopyOutStream>, Status> {
+        let req = request.into_inner();
+        debug!(container_id = %req.container_id, path = %req.path, "copy_out request");
+
+        let root = self.container_root(&req.container_id).await?;
+        let (tx, rx) = mpsc::channel(4);
+        tokio::task::spawn_blocking(move || {
+            let writer = ChunkWriter::new(tx.clone(), |data| FileChunk { data });
+            if let Err(e) = archive::archive(&root, Path::new(&req.path), writer) {
+                let _ = tx.blocking_send(Err(io_status(&req.path, e)));
+            }
+        });
+
+        Ok(Response::new(Box::pin(ReceiverStream::new(rx))))
+    }
+
+    async fn list_tree(
+        &self,
+        request: Request<ListTreeRequest>,
+    ) -> Result<Response<Self::ListTreeStream>, Status> {
+        let req = request.into_inner();
+        debug!(container_id = %req.container_id, dir = %req.dir, "list_tree request");
+
+        let root = self.container_root(&req.container_id).await?;
+        let dir = open_in_root(
+            &root,
+            Path::new(&req.dir),
+            libc::O_PATH | libc::O_DIRECTORY,
+            0,
+        )
+        .map_err(|e| io_status(&req.dir, e))?;
+
+        let (tx, rx) = mpsc::channel(4);
+        tokio::task::spawn_blocking(move || {
+            // Walk through the fd, so the listing stays below the resolved dir
+            let path = PathBuf::from(format!("/proc/self/fd/{}", dir.as_raw_fd()));
+            layer::list_tree(&path, req.checksums, &tx);
+        });
+
+        Ok(Response::new(Box::pin(ReceiverStream::new(rx))))
+    }
+
+    async fn remove_paths(
+        &self,
+        request: Request<RemovePathsRequest>,
+    ) -> Result<Response<RemovePathsResponse>, Status> {
+        let req = request.into_inner();
+        debug!(
+            container_id = %req.container_id,
+            dir = %req.dir,
+            count = req.paths.len(),
+            "remove_paths request"
+        );
+
+        let root = self.container_root(&req.container_id).await?;
+        let dir = req.dir.clone();
+        let removed = tokio::task::spawn_blocking(move || {
+            archive::remove_paths(&root, Path::new(&dir), &req.paths)
+        })
+        .await
+        .map_err(|e| Status::internal(format!("Remove task failed: {}", e)))?
+        .map_err(|e| io_status(&req.dir, e))?;
+
+        Ok(Response::new(RemovePathsResponse { removed }))
+    }
+
+    async fn stat(&self, request: Request<StatRequest>) -> Result<Response<FileStat>, Status> {
+        let req = request.into_inner();
+        debug!(container_id = %req.container_id, path = %req.path, "stat request");
+
+        let root = self.container_root(&req.container_id).await?;
+        let stat =
+            archive::stat(&root, Path::new(&req.path)).map_err(|e| io_status(&req.path, e))?;
+        Ok(Response::new(stat))
+    }
+
+    async fn list_dir(
+        &self,
+        request: Request<ListDirRequest>,
+    ) -> Result<Response<ListDirResponse>, Status> {
+        let req = request.into_inner();
+        debug!(container_id = %req.container_id, path = %req.path, "list_dir request");
+
+        let root = self.container_root(&req.container_id).await?;
+        let path = req.path.clone();
+        let entries =
+            tokio::task::spawn_blocking(move || archive::list_dir(&root, Path::new(&path)))
+                .await
+                .map_err(|e| Status::internal(format!("List task failed: {}", e)))?
+                .map_err(|e| io_status(&req.path, e))?;
+        Ok(Response::new(ListDirResponse { entries }))
+    }
+
+    async fn chmod(
+        &self,
+        request: Request<ChmodRequest>,
+    ) -> Result<Response<ChmodResponse>, Status> {
+        let req = request.into_inner();
+        debug!(
+            container_id = %req.container_id,
+            path = %req.path,
+            mode = format!("{:o}", req.mode),
+            "chmod request"
+        );
+
+        let root = self.container_root(&req.container_id).await?;
+        archive::chmod(&root, Path::new(&req.path), req.mode)
+            .map_err(|e| io_status(&req.path, e))?;
+        Ok(Response::new(ChmodResponse {}))
+    }
+
+    async fn chown(
+        &self,
+        request: Request<ChownRequest>,
+    ) -> Result<Response<ChownResponse>, Status> {
+        let req = request.into_inner();
+        debug!(
+            container_id = %req.container_id,
+            path = %req.path,
+            uid = req.uid,
+            gid = req.gid,
+            "chown request"
+        );
+
+        let root = self.container_root(&req.container_id).await?;
+        archive::chown(&root, Path::new(&req.path), req.uid, req.gid)
+            .map_err(|e| io_status(&req.path, e))?;
+        Ok(Response::new(ChownResponse {}))
+    }
+
+    async fn watch(
+        &self,
+        request: Request<WatchRequest>,
+    ) -> Result<Response<Self::WatchStream>, Status> {
+        let req = request.into_inner();
+        debug!(
+            container_id = %req.container_id,
+            path = %req.path,
+            recursive = req.recursive,
+            "watch request"
+        );
+
+        let root = self.container_root(&req.container_id).await?;
+        let mut watcher = Watcher::new(root, Path::new(&req.path), req.recursive)
+            .map_err(|e| io_status(&req.path, e))?;
+
+        let (tx, rx) = mpsc::channel(WATCH_BUFFER);
+        tokio::spawn(async move {
+            loop {
+                let events = tokio::select! {
+                    _ = tx.closed() => return,
+                    events = watcher.next() => events,
+                };
+                match events {
+                    Ok(Some(events)) => {
+                        for event in events {
+                            if tx.send(Ok(event)).await.is_err() {
+                                return;
+                            }
+                        }
+                    }
+                    Ok(None) => return,
+                    Err(e) => {
+                        let _ = tx.send(Err(io_status(&req.path, e))).await;
+                        return;
+                    }
+                }
+            }
+        });
+
+        Ok(Response::new(Box::pin(ReceiverStream::new(rx))))
+    }
+}
+
+/// Blocking reader over chunks received by an async task.
+struct ChannelReader {
+    rx: mpsc::Receiver<Vec<u8>>,
+    chunk: io::Cursor<Vec<u8>>,
+}
+
+impl ChannelReader {
+    fn new(rx: mpsc::Receiver<Vec<u8>>) -> Self {
+        Self {
+            rx,
+            chunk: io::Cursor::new(Vec::new()),
+        }
+    }
+}
+
+impl Read for ChannelReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            let n = Read::read(&mut self.chunk, buf)?;
+            if n > 0 || buf.is_empty() {
+                return Ok(n);
+            }
+            match self.rx.blocking_recv() {
+                Some(chunk) => self.chunk = io::Cursor::new(chunk),
+                None => return Ok(0),
+            }
+        }
+    }
+}
+
+fn io_status(path: &str, err: io::Error) -> Status {
+    let message = format!("{}: {}", path, err);
+    match err.kind() {
+        io::ErrorKind::NotFound => Status::not_found(message),
+        io::ErrorKind::PermissionDenied => Status::permission_denied(message),
+        io::ErrorKind::InvalidInput
+        | io::ErrorKind::IsADirectory
+        | io::ErrorKind::NotADirectory => Status::invalid_argument(message),
+        _ => Status::internal(message),
+    }
+}
@@ -1,15 +1,19 @@
 //! Guest service implementation.
 //!
-//! Handles guest initialization and management (Init, Ping, Shutdown RPCs).
+//! Handles guest initialization and management (Init, Ping, CheckPort,
+//! Shutdown RPCs).
 
 use crate::service::server::GuestServer;
 use boxlite_shared::{
-    guest_init_response, Guest as GuestService, GuestInitError, GuestInitRequest,
-    GuestInitResponse, GuestInitSuccess, PingRequest, PingResponse, ShutdownRequest,
-    ShutdownResponse,
+    guest_init_response, CheckPortRequest, CheckPortResponse, Guest as GuestService,
+    GuestInitError, GuestInitRequest, GuestInitResponse, GuestInitSuccess, PingRequest,
+    PingResponse, ShutdownRequest, ShutdownResponse,
 };
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::time::Duration;
+use tokio::time::Instant;
 use tonic::{Request, Response, Status};
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
 
 #[tonic::async_trait]
 impl GuestService for GuestServer {
@@ -18,6 +22,7 @@ impl GuestService for GuestServer {
     /// This must be called first after connection. It:
     /// 1. Mounts all volumes (virtiofs + block devices)
     /// 2. Configures network (if specified)
+    /// 3. Configures interfaces on inter-box networks
     ///
     /// Note: Rootfs setup is handled by Container.Init.
     async fn init(
@@ -50,13 +55,16 @@ impl GuestService for GuestServer {
             }));
         }
 
-        // Step 2: Configure network (if specified)
+        // Step 2: Configure network (if specified), or just loopback
         if let Some(network) = req.network {
             info!("Configuring network interface: {}", network.interface);
             if let Err(e) = crate::network::configure_network_from_config(
                 &network.interface,
                 network.ip.as_deref(),
                 network.gateway.as_deref(),
+                network.ipv6.as_deref(),
+                network.gateway_ipv6.as_deref(),
+                network.mtu,
             )
             .await
             {
@@ -67,6 +75,38 @@ impl GuestService for GuestServer {
                     })),
                 }));
             }
+        } else if let Err(e) = crate::network::configure_loopback().await {
+            error!("Failed to configure loopback: {}", e);
+            return Ok(Response::new(GuestInitResponse {
+                result: Some(guest_init_response::Result::Error(GuestInitError {
+                    reason: format!("Failed to configure loopback: {}", e),
+                })),
+            }));
+        }
+
+        // Step 3: Interfaces on inter-box networks (address only, no routes)
+        for network in req.networks {
+            info!(
+                "Configuring inter-box network interface: {}",
+                network.interface
+            );
+            if let Err(e) = crate::network::configure_network_from_config(
+                &network.interface,
+                network.ip.as_deref(),
+                None,
+                None,
+                None,
+                network.mtu,
+            )
+            .await
+            {
+                error!("Failed to configure {}: {}", network.interface, e);
+                return Ok(Response::new(GuestInitResponse {
+                    result: Some(guest_init_response::Result::Error(GuestInitError {
+                        reason: format!("Failed to configure {}: {}", network.interface, e),
+                    })),
+                }));
+            }
         }
 
         // Mark as initialized
@@ -85,11 +125,56 @@ impl GuestService for GuestServer {
         }))
     }
 
+    /// Try connecting to the port on IPv4 and IPv6 loopback, which the
+    /// containers share with the guest.
+    async fn check_port(
+        &self,
+        request: Request<CheckPortRequest>,
+    ) -> Result<Response<CheckPortResponse>, Status> {
+        const CONNECT_TIMEOUT: Duration = Duration::from_secs(1);
+
+        let port = u16::try_from(request.into_inner().port)
+            .map_err(|_| Status::invalid_argument("port out of range"))?;
+        let mut open = false;
+        for ip in [Ipv4Addr::LOCALHOST.into(), Ipv6Addr::LOCALHOST.into()] {
+            let connect = tokio::net::TcpStream::connect(SocketAddr::new(ip, port));
+            if let Ok(Ok(_)) = tokio::time::timeout(CONNECT_TIMEOUT, connect).await {
+                open = true;
+                break;
+            }
+        }
+        debug!(port, open, "Checked port");
+        Ok(Response::new(CheckPortResponse { open }))
+    }
+
+    /// Stop the containers before the host kills the VM.
+    ///
+    /// Each container's init gets the requested signal, and the containers
+    /// together get `timeout_ms` to exit. Filesystems are synced either way.
     async fn shutdown(
         &self,
-        _request: Request<ShutdownRequest>,
+        request: Request<ShutdownRequest>,
     ) -> Result<Response<ShutdownResponse>, Status> {
-        info!("Received shutdown request");
+        let req = request.into_inner();
+        info!(
+            signal = %req.signal,
+            timeout_ms = req.timeout_ms,
+            "Received shutdown request"
+        );
+
+        if !req.signal.is_empty() {
+            let until = Instant::now() + Duration::from_millis(req.timeout_ms);
+            let containers: Vec<_> = self.containers.lock().await.values().cloned().collect();
+            for container in containers {
+                let container = container.lock().await;
+                let timeout = until.saturating_duration_since(Instant::now());
+                if !container.stop(&req.signal, timeout).await {
+                    warn!(container_id = %container.id(), "Container did not stop in time");
+                }
+            }
+        }
+
+        nix::unistd::sync();
         Ok(Response::new(ShutdownResponse {}))
     }
 }
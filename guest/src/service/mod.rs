@@ -4,8 +4,10 @@
 //! - `guest`: Guest initialization and management (Init, Ping, Shutdown RPCs)
 //! - `container`: Container lifecycle (Init RPC)
 //! - `execution`: Command execution (Exec, Wait, Kill RPCs)
+//! - `files`: File transfer and change notifications (PutFile, GetFile, Watch RPCs)
 
 mod container;
 pub(crate) mod exec;
+mod files;
 mod guest;
 pub(crate) mod server;
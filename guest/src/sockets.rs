@@ -0,0 +1,95 @@
+//! Host Unix sockets and services exposed in containers.
+//!
+//! The guest listens on a socket at the container path, or on a loopback
+//! port for reverse ports, and relays each connection to a vsock port,
+//! which the VMM bridges to the host socket.
+
+use std::fs::{File, Permissions};
+use std::net::{Ipv4Addr, SocketAddr};
+use std::os::fd::AsRawFd;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+
+use boxlite_shared::errors::{BoxliteError, BoxliteResult};
+use nix::errno::Errno;
+use nix::unistd::{unlinkat, UnlinkatFlags};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::{TcpListener, UnixListener};
+use tokio_vsock::{VsockAddr, VsockStream, VMADDR_CID_HOST};
+use tracing::{debug, info, warn};
+
+use crate::storage::archive;
+
+/// Listen at `destination` in the container with root directory `root`,
+/// relaying connections to vsock `port` for the life of the guest.
+pub fn forward(root: &File, destination: &str, port: u32) -> BoxliteResult<()> {
+    let path = Path::new(destination);
+    let failed = |e: std::io::Error| {
+        BoxliteError::Internal(format!("Failed to create socket {}: {}", destination, e))
+    };
+    let name = path
+        .file_name()
+        .ok_or_else(|| BoxliteError::Internal(format!("Invalid socket path: {}", destination)))?;
+
+    let parent =
+        archive::create_dir_all(root, path.parent().unwrap_or(Path::new("/"))).map_err(failed)?;
+    // Left on the rootfs by an earlier start of the box
+    match unlinkat(Some(parent.as_raw_fd()), name, UnlinkatFlags::NoRemoveDir) {
+        Ok(()) | Err(Errno::ENOENT) => {}
+        Err(e) => return Err(failed(e.into())),
+    }
+
+    // Bind through the resolved directory, so symlinks in the container
+    // cannot point it elsewhere in the guest
+    let socket_path = PathBuf::from(format!("/proc/self/fd/{}", parent.as_raw_fd())).join(name);
+    let listener = UnixListener::bind(&socket_path).map_err(failed)?;
+    // Any container user may connect, access control is the host socket's
+    std::fs::set_permissions(&socket_path, Permissions::from_mode(0o666)).map_err(failed)?;
+    info!(destination, port, "Forwarding host socket");
+
+    let destination = destination.to_string();
+    tokio::spawn(async move {
+        loop {
+            match listener.accept().await {
+                Ok((conn, _)) => relay(conn, destination.clone(), port),
+                Err(e) => warn!(socket = %destination, error = %e, "Failed to accept connection"),
+            }
+        }
+    });
+    Ok(())
+}
+
+/// Listen on `guest_port` of the guest's loopback, which the container
+/// shares, relaying connections to vsock `port` for the life of the guest.
+pub async fn forward_port(guest_port: u16, port: u32) -> BoxliteResult<()> {
+    let address = SocketAddr::from((Ipv4Addr::LOCALHOST, guest_port));
+    let listener = TcpListener::bind(address)
+        .await
+        .map_err(|e| BoxliteError::Internal(format!("Failed to listen on {}: {}", address, e)))?;
+    info!(guest_port, port, "Forwarding reverse port");
+
+    tokio::spawn(async move {
+        loop {
+            match listener.accept().await {
+                Ok((conn, _)) => relay(conn, address.to_string(), port),
+                Err(e) => warn!(address = %address, error = %e, "Failed to accept connection"),
+            }
+        }
+    });
+    Ok(())
+}
+
+/// Relay a container connection to the host through vsock `port`.
+fn relay<C>(mut conn: C, name: String, port: u32)
+where
+    C: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    tokio::spawn(async move {
+        match VsockStream::connect(VsockAddr::new(VMADDR_CID_HOST, port)).await {
+            Ok(mut host) => {
+                let _ = tokio::io::copy_bidirectional(&mut conn, &mut host).await;
+            }
+            Err(e) => debug!(name = %name, error = %e, "Failed to reach host"),
+        }
+    });
+}
@@ -0,0 +1,502 @@
+//! File access, metadata and tar copy inside a container.
+//!
+//! Paths resolve in the container's root directory (`/proc/<init pid>/root`)
+//! with `openat2(RESOLVE_IN_ROOT)`, so `..` and symlinks, whether in
+//! requested paths, archive entries or the container's own files, never
+//! lead outside of it. Archive entries are created relative to their
+//! resolved parent directory.
+
+use std::ffi::{CString, OsStr};
+use std::fs::File;
+use std::fs::Metadata;
+use std::io::{self, Read, Write};
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::{MetadataExt, PermissionsExt};
+use std::os::unix::io::{AsRawFd, FromRawFd, OwnedFd};
+use std::path::{Component, Path, PathBuf};
+
+use boxlite_shared::{FileKind, FileStat};
+use nix::errno::Errno;
+use nix::fcntl::{openat, readlinkat, AtFlags, OFlag};
+use nix::libc;
+use nix::sys::stat::{fchmod, futimens, mkdirat, Mode};
+use nix::sys::time::TimeSpec;
+use nix::unistd::{linkat, symlinkat, unlinkat, UnlinkatFlags};
+use tar::EntryType;
+
+/// Flags for directories that are only used as `*at()` anchors.
+const DIR_FLAGS: libc::c_int = libc::O_PATH | libc::O_DIRECTORY;
+
+/// Open an absolute `path` as if `root` were `/`.
+pub fn open_in_root(
+    root: &File,
+    path: &Path,
+    flags: libc::c_int,
+    mode: u32,
+) -> io::Result<OwnedFd> {
+    if !path.is_absolute() {
+        return Err(invalid(format!(
+            "path must be absolute: {}",
+            path.display()
+        )));
+    }
+    let path_c = CString::new(path.as_os_str().as_bytes())
+        .map_err(|_| invalid(format!("path contains NUL: {}", path.display())))?;
+
+    // SAFETY: open_how is a plain C struct, all-zero is a valid value
+    let mut how: libc::open_how = unsafe { std::mem::zeroed() };
+    how.flags = (flags | libc::O_CLOEXEC) as u64;
+    how.mode = if flags & libc::O_CREAT != 0 {
+        mode as u64
+    } else {
+        0
+    };
+    how.resolve = libc::RESOLVE_IN_ROOT | libc::RESOLVE_NO_MAGICLINKS;
+
+    // SAFETY: all pointers are valid for the duration of the call
+    let fd = unsafe {
+        libc::syscall(
+            libc::SYS_openat2,
+            root.as_raw_fd(),
+            path_c.as_ptr(),
+            &how as *const libc::open_how,
+            std::mem::size_of::<libc::open_how>(),
+        )
+    };
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // SAFETY: openat2 returned a new file descriptor we own
+    Ok(unsafe { OwnedFd::from_raw_fd(fd as libc::c_int) })
+}
+
+/// Extract a tar archive into the existing directory `dir`.
+///
+/// Permissions, symlinks and file modification times are kept, ownership
+/// is not. Device nodes and FIFOs are skipped. Returns the number of
+/// entries extracted.
+pub fn extract(root: &File, dir: &Path, reader: impl Read) -> io::Result<u64> {
+    open_in_root(root, dir, DIR_FLAGS, 0)?;
+
+    let mut archive = tar::Archive::new(reader);
+    let mut count = 0;
+    let mut dir_modes = Vec::new();
+
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let Some(relative) = entry_path(&entry.path()?)? else {
+            continue;
+        };
+        let path = dir.join(relative);
+        let (parent, name) = create_parent(root, &path)?;
+        let parent = Some(parent.as_raw_fd());
+        let mode = Mode::from_bits_truncate(entry.header().mode()? & 0o7777);
+
+        match entry.header().entry_type() {
+            EntryType::Directory => {
+                match mkdirat(parent, name, Mode::from_bits_truncate(0o700)) {
+                    Ok(()) | Err(Errno::EEXIST) => {}
+                    Err(e) => return Err(e.into()),
+                }
+                // Applied at the end, so read-only directories can be filled
+                dir_modes.push((path, mode));
+            }
+            EntryType::Regular | EntryType::Continuous => {
+                remove(parent, name)?;
+                let flags = OFlag::O_WRONLY
+                    | OFlag::O_CREAT
+                    | OFlag::O_EXCL
+                    | OFlag::O_NOFOLLOW
+                    | OFlag::O_CLOEXEC;
+                let fd = openat(parent, name, flags, Mode::from_bits_truncate(0o600))?;
+                // SAFETY: openat returned a new file descriptor we own
+                let mut file = unsafe { File::from_raw_fd(fd) };
+                io::copy(&mut entry, &mut file)?;
+                fchmod(file.as_raw_fd(), mode)?;
+                let mtime = TimeSpec::new(entry.header().mtime()? as i64, 0);
+                futimens(file.as_raw_fd(), &TimeSpec::UTIME_OMIT, &mtime)?;
+            }
+            EntryType::Symlink => {
+                let target = link_name(&entry)?;
+                remove(parent, name)?;
+                symlinkat(target.as_path(), parent, name)?;
+            }
+            EntryType::Link => {
+                let target = link_name(&entry)?;
+                let target = entry_path(&target)?
+                    .ok_or_else(|| invalid(format!("Invalid hard link target: {:?}", target)))?;
+                let target = dir.join(target);
+                let (target_parent, target_name) = create_parent(root, &target)?;
+                remove(parent, name)?;
+                linkat(
+                    Some(target_parent.as_raw_fd()),
+                    target_name,
+                    parent,
+                    name,
+                    AtFlags::empty(),
+                )?;
+            }
+            other => {
+                tracing::debug!(path = %path.display(), kind = ?other, "Skipping archive entry");
+                continue;
+            }
+        }
+        count += 1;
+    }
+
+    for (path, mode) in dir_modes {
+        let dir = open_in_root(root, &path, libc::O_RDONLY | libc::O_DIRECTORY, 0)?;
+        fchmod(dir.as_raw_fd(), mode)?;
+    }
+    Ok(count)
+}
+
+/// Write a tar archive of the file or directory at `path` to `out`.
+///
+/// Entries are named after the last component of `path` (`.` for `/`).
+/// Symlinks are archived as links, not followed.
+pub fn archive(root: &File, path: &Path, out: impl Write) -> io::Result<()> {
+    let fd = open_in_root(root, path, libc::O_PATH, 0)?;
+    // Lookups below the resolved path go through the fd and can't escape
+    let source = PathBuf::from(format!("/proc/self/fd/{}", fd.as_raw_fd()));
+    let name = path.file_name().unwrap_or(OsStr::new("."));
+
+    let mut builder = tar::Builder::new(out);
+    builder.follow_symlinks(false);
+    let metadata = std::fs::metadata(&source)?;
+    if metadata.is_dir() {
+        builder.append_dir_all(name, &source)?;
+    } else if metadata.is_file() {
+        builder.append_file(name, &mut File::open(&source)?)?;
+    } else {
+        return Err(invalid(format!(
+            "Not a file or directory: {}",
+            path.display()
+        )));
+    }
+    builder.into_inner()?.flush()
+}
+
+/// Metadata of `path`, without following a final symlink.
+pub fn stat(root: &File, path: &Path) -> io::Result<FileStat> {
+    let fd = open_in_root(root, path, libc::O_PATH | libc::O_NOFOLLOW, 0)?;
+    let file = File::from(fd);
+    let metadata = file.metadata()?;
+    let link_target = if metadata.file_type().is_symlink() {
+        // An empty path reads the link the fd refers to
+        readlinkat(Some(file.as_raw_fd()), "")?
+    } else {
+        Default::default()
+    };
+    let name = path.file_name().unwrap_or(OsStr::new("/"));
+    Ok(file_stat(name, &metadata, link_target.as_os_str()))
+}
+
+/// Metadata of the entries in the directory `path`, sorted by name.
+pub fn list_dir(root: &File, path: &Path) -> io::Result<Vec<FileStat>> {
+    let fd = open_in_root(root, path, libc::O_RDONLY | libc::O_DIRECTORY, 0)?;
+    // Lookups below the resolved path go through the fd and can't escape
+    let dir = PathBuf::from(format!("/proc/self/fd/{}", fd.as_raw_fd()));
+
+    let mut entries = Vec::new();
+    for entry in std::fs::read_dir(&dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        let metadata = match std::fs::symlink_metadata(&path) {
+            // Removed since the directory was read
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            result => result?,
+        };
+        let link_target = if metadata.file_type().is_symlink() {
+            std::fs::read_link(&path)?.into_os_string()
+        } else {
+            Default::default()
+        };
+        entries.push(file_stat(&entry.file_name(), &metadata, &link_target));
+    }
+    entries.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(entries)
+}
+
+fn file_stat(name: &OsStr, metadata: &Metadata, link_target: &OsStr) -> FileStat {
+    let file_type = metadata.file_type();
+    let kind = if file_type.is_symlink() {
+        FileKind::Symlink
+    } else if file_type.is_dir() {
+        FileKind::Directory
+    } else if file_type.is_file() {
+        FileKind::Regular
+    } else {
+        FileKind::Other
+    };
+    FileStat {
+        name: name.to_string_lossy().into_owned(),
+        kind: kind as i32,
+        mode: metadata.permissions().mode() & 0o7777,
+        uid: metadata.uid(),
+        gid: metadata.gid(),
+        size: metadata.size(),
+        mtime: metadata.mtime(),
+        link_target: link_target.to_string_lossy().into_owned(),
+    }
+}
+
+/// Set the permission bits of `path`.
+pub fn chmod(root: &File, path: &Path, mode: u32) -> io::Result<()> {
+    let fd = open_in_root(root, path, libc::O_PATH, 0)?;
+    // fchmod() refuses O_PATH fds; the fd's proc link works
+    let target = PathBuf::from(format!("/proc/self/fd/{}", fd.as_raw_fd()));
+    std::fs::set_permissions(target, std::fs::Permissions::from_mode(mode & 0o7777))
+}
+
+/// Set the owner of `path`.
+pub fn chown(root: &File, path: &Path, uid: u32, gid: u32) -> io::Result<()> {
+    let fd = open_in_root(root, path, libc::O_PATH, 0)?;
+    let target = PathBuf::from(format!("/proc/self/fd/{}", fd.as_raw_fd()));
+    std::os::unix::fs::chown(target, Some(uid), Some(gid))
+}
+
+/// Remove `paths` below `dir`, directories with their contents.
+///
+/// Paths are relative to `dir` and may not contain `..`. Symlinks are
+/// removed, not followed. Returns the number of paths that existed.
+pub fn remove_paths(root: &File, dir: &Path, paths: &[String]) -> io::Result<u64> {
+    let mut removed = 0;
+    for path in paths {
+        let relative = entry_path(Path::new(path))?
+            .ok_or_else(|| invalid(format!("Invalid path to remove: {:?}", path)))?;
+        let path = dir.join(relative);
+        let (Some(parent), Some(name)) = (path.parent(), path.file_name()) else {
+            continue;
+        };
+        let parent = match open_in_root(root, parent, DIR_FLAGS, 0) {
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            result => result?,
+        };
+
+        // Lookups below the resolved parent go through the fd and can't escape
+        let target = PathBuf::from(format!("/proc/self/fd/{}", parent.as_raw_fd())).join(name);
+        let result = match std::fs::symlink_metadata(&target) {
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) => Err(e),
+            Ok(metadata) if metadata.is_dir() => std::fs::remove_dir_all(&target),
+            Ok(_) => std::fs::remove_file(&target),
+        };
+        result?;
+        removed += 1;
+    }
+    Ok(removed)
+}
+
+/// Resolve `path`, creating missing directories along the way.
+pub fn create_dir_all(root: &File, path: &Path) -> io::Result<OwnedFd> {
+    match open_in_root(root, path, DIR_FLAGS, 0) {
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+        result => return result,
+    }
+
+    let mut current = PathBuf::from("/");
+    let mut fd = open_in_root(root, &current, DIR_FLAGS, 0)?;
+    for component in path.components().skip(1) {
+        current.push(component);
+        match mkdirat(
+            Some(fd.as_raw_fd()),
+            component.as_os_str(),
+            Mode::from_bits_truncate(0o755),
+        ) {
+            Ok(()) | Err(Errno::EEXIST) => {}
+            Err(e) => return Err(e.into()),
+        }
+        fd = open_in_root(root, &current, DIR_FLAGS, 0)?;
+    }
+    Ok(fd)
+}
+
+/// Resolve (creating as needed) the parent directory of `path`.
+fn create_parent<'a>(root: &File, path: &'a Path) -> io::Result<(OwnedFd, &'a OsStr)> {
+    let name = path
+        .file_name()
+        .ok_or_else(|| invalid(format!("Invalid path: {}", path.display())))?;
+    let parent = path.parent().unwrap_or(Path::new("/"));
+    Ok((create_dir_all(root, parent)?, name))
+}
+
+/// Remove an existing non-directory entry, so it can be replaced.
+fn remove(parent: Option<i32>, name: &OsStr) -> io::Result<()> {
+    match unlinkat(parent, name, UnlinkatFlags::NoRemoveDir) {
+        Ok(()) | Err(Errno::ENOENT) => Ok(()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Entry path relative to the extraction directory, `None` for the
+/// directory itself. Rejects `..`.
+fn entry_path(path: &Path) -> io::Result<Option<PathBuf>> {
+    let mut relative = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(name) => relative.push(name),
+            Component::RootDir | Component::CurDir => {}
+            Component::ParentDir | Component::Prefix(_) => {
+                return Err(invalid(format!(
+                    "Archive entry leaves the directory: {}",
+                    path.display()
+                )));
+            }
+        }
+    }
+    Ok((!relative.as_os_str().is_empty()).then_some(relative))
+}
+
+fn link_name<R: Read>(entry: &tar::Entry<'_, R>) -> io::Result<PathBuf> {
+    entry
+        .link_name()?
+        .map(|target| target.into_owned())
+        .ok_or_else(|| invalid("Link entry without target".to_string()))
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+
+    #[test]
+    fn test_open_in_root_stays_in_root() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("etc")).unwrap();
+        std::fs::write(dir.path().join("etc/hostname"), "box").unwrap();
+        std::os::unix::fs::symlink("/etc/hostname", dir.path().join("link")).unwrap();
+        let root = File::open(dir.path()).unwrap();
+
+        for path in ["/etc/hostname", "/../../etc/hostname", "/link"] {
+            let mut content = String::new();
+            File::from(open_in_root(&root, Path::new(path), libc::O_RDONLY, 0).unwrap())
+                .read_to_string(&mut content)
+                .unwrap();
+            assert_eq!(content, "box", "{}", path);
+        }
+
+        assert!(open_in_root(&root, Path::new("etc/hostname"), libc::O_RDONLY, 0).is_err());
+        let missing = open_in_root(&root, Path::new("/missing"), libc::O_RDONLY, 0).unwrap_err();
+        assert_eq!(missing.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn test_archive_extract_roundtrip() {
+        let src = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(src.path().join("app/bin")).unwrap();
+        std::fs::write(src.path().join("app/bin/run"), "#!/bin/sh\n").unwrap();
+        std::fs::set_permissions(
+            src.path().join("app/bin/run"),
+            std::fs::Permissions::from_mode(0o750),
+        )
+        .unwrap();
+        std::os::unix::fs::symlink("bin/run", src.path().join("app/start")).unwrap();
+
+        let mut tar = Vec::new();
+        archive(
+            &File::open(src.path()).unwrap(),
+            Path::new("/app"),
+            &mut tar,
+        )
+        .unwrap();
+
+        let dest = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dest.path().join("srv")).unwrap();
+        // Symlinks already in the destination resolve inside it
+        std::os::unix::fs::symlink("/srv", dest.path().join("srv/up")).unwrap();
+        let root = File::open(dest.path()).unwrap();
+        let count = extract(&root, Path::new("/srv/up"), tar.as_slice()).unwrap();
+
+        assert_eq!(count, 4);
+        let run = dest.path().join("srv/app/bin/run");
+        assert_eq!(std::fs::read_to_string(&run).unwrap(), "#!/bin/sh\n");
+        let mode = std::fs::metadata(&run).unwrap().permissions().mode();
+        assert_eq!(mode & 0o7777, 0o750);
+        assert_eq!(
+            std::fs::read_link(dest.path().join("srv/app/start")).unwrap(),
+            Path::new("bin/run")
+        );
+    }
+
+    #[test]
+    fn test_remove_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("work/build/out")).unwrap();
+        std::fs::write(dir.path().join("work/build/out/a.o"), "").unwrap();
+        std::fs::write(dir.path().join("keep"), "").unwrap();
+        std::os::unix::fs::symlink("/keep", dir.path().join("work/link")).unwrap();
+        let root = File::open(dir.path()).unwrap();
+
+        let paths = ["build".to_string(), "link".into(), "missing".into()];
+        assert_eq!(remove_paths(&root, Path::new("/work"), &paths).unwrap(), 2);
+        assert!(!dir.path().join("work/build").exists());
+        assert!(!dir.path().join("work/link").exists());
+        assert!(dir.path().join("keep").exists());
+
+        assert!(remove_paths(&root, Path::new("/work"), &["../keep".into()]).is_err());
+        assert!(remove_paths(&root, Path::new("/work"), &["".into()]).is_err());
+    }
+
+    #[test]
+    fn test_stat_and_list_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("out")).unwrap();
+        std::fs::write(dir.path().join("out/result.json"), "{}").unwrap();
+        std::os::unix::fs::symlink("/etc/passwd", dir.path().join("out/link")).unwrap();
+        let root = File::open(dir.path()).unwrap();
+
+        let link = stat(&root, Path::new("/out/link")).unwrap();
+        assert_eq!(link.kind(), FileKind::Symlink);
+        assert_eq!(link.link_target, "/etc/passwd");
+
+        let entries = list_dir(&root, Path::new("/out")).unwrap();
+        let names: Vec<&str> = entries.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, ["link", "result.json"]);
+        assert_eq!(entries[1].kind(), FileKind::Regular);
+        assert_eq!(entries[1].size, 2);
+
+        let err = list_dir(&root, Path::new("/out/result.json")).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotADirectory);
+    }
+
+    #[test]
+    fn test_chmod_chown() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("run.sh"), "").unwrap();
+        std::os::unix::fs::symlink("/run.sh", dir.path().join("link")).unwrap();
+        let root = File::open(dir.path()).unwrap();
+
+        // Through the symlink, which resolves inside the root
+        chmod(&root, Path::new("/link"), 0o750).unwrap();
+        let metadata = std::fs::metadata(dir.path().join("run.sh")).unwrap();
+        assert_eq!(metadata.permissions().mode() & 0o7777, 0o750);
+
+        chown(&root, Path::new("/run.sh"), metadata.uid(), metadata.gid()).unwrap();
+        let missing = chmod(&root, Path::new("/missing"), 0o644).unwrap_err();
+        assert_eq!(missing.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn test_extract_rejects_parent_dir() {
+        let mut builder = tar::Builder::new(Vec::new());
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(EntryType::Regular);
+        header.set_size(0);
+        header.set_mode(0o644);
+        // set_path refuses "..", so write the name directly
+        header.as_gnu_mut().unwrap().name[..9].copy_from_slice(b"../escape");
+        header.set_cksum();
+        builder.append(&header, io::empty()).unwrap();
+        let tar = builder.into_inner().unwrap();
+
+        let dest = tempfile::tempdir().unwrap();
+        let root = File::open(dest.path()).unwrap();
+        let err = extract(&root, Path::new("/"), tar.as_slice()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+}
@@ -22,12 +22,14 @@ impl BlockDeviceMount {
     /// * `filesystem` - Target filesystem type
     /// * `need_format` - If true, format device before mounting
     /// * `need_resize` - If true, resize filesystem after mounting to fill disk
+    /// * `data` - Filesystem-specific mount options (e.g., "usrquota")
     pub fn mount(
         device: &Path,
         mount_point: &Path,
         filesystem: Filesystem,
         need_format: bool,
         need_resize: bool,
+        data: Option<&str>,
     ) -> BoxliteResult<()> {
         let fs_name = filesystem_to_str(filesystem);
 
@@ -72,14 +74,7 @@ impl BlockDeviceMount {
         let mount_flags = MsFlags::MS_NOATIME | MsFlags::MS_NODIRATIME;
 
         // Mount using nix
-        mount(
-            Some(device),
-            mount_point,
-            Some(fs_name),
-            mount_flags,
-            None::<&str>,
-        )
-        .map_err(|e| {
+        mount(Some(device), mount_point, Some(fs_name), mount_flags, data).map_err(|e| {
             BoxliteError::Storage(format!(
                 "Failed to mount {} to {}: {}",
                 device.display(),
@@ -214,6 +209,54 @@ impl BlockDeviceMount {
         Ok(())
     }
 
+    /// Check and automatically repair an unmounted ext4 filesystem.
+    ///
+    /// Runs `e2fsck -p` (preen mode), which fixes problems that can be
+    /// repaired safely without human intervention.
+    ///
+    /// # Errors
+    /// Returns error if filesystem is not ext4 or if e2fsck reports errors
+    /// it could not correct (exit code >= 4).
+    pub fn check_filesystem(device: &Path, filesystem: Filesystem) -> BoxliteResult<()> {
+        if filesystem != Filesystem::Ext4 {
+            return Err(BoxliteError::Storage(format!(
+                "Filesystem check only supported for ext4, got {:?}",
+                filesystem
+            )));
+        }
+
+        tracing::info!("Checking ext4 filesystem on {}", device.display());
+
+        let output = Command::new("e2fsck")
+            .arg("-p")
+            .arg(device)
+            .output()
+            .map_err(|e| BoxliteError::Storage(format!("Failed to execute e2fsck: {}", e)))?;
+
+        // e2fsck exit codes: 0 = clean, 1 = errors corrected,
+        // 2 = errors corrected (reboot suggested), >= 4 = uncorrected errors
+        match output.status.code() {
+            Some(0) => {
+                tracing::info!("Filesystem on {} is clean", device.display());
+                Ok(())
+            }
+            Some(1) | Some(2) => {
+                tracing::warn!(
+                    "Filesystem errors on {} were corrected: {}",
+                    device.display(),
+                    String::from_utf8_lossy(&output.stdout).trim()
+                );
+                Ok(())
+            }
+            code => Err(BoxliteError::Storage(format!(
+                "e2fsck failed on {} (exit code {:?}): {}",
+                device.display(),
+                code,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ))),
+        }
+    }
+
     /// Format device with specified filesystem.
     fn format(device: &Path, filesystem: &str) -> BoxliteResult<()> {
         // Debug: log user info and device status
@@ -0,0 +1,321 @@
+//! Rootfs listing and layer export.
+//!
+//! The host diffs the container rootfs against its image: it asks for a
+//! listing of the rootfs, compares it with the image layers and then asks
+//! for an OCI layer tar of the paths that differ. Directory syncs use the
+//! same listing, optionally with file checksums.
+
+use std::fs;
+use std::io::{self, Read, Write};
+use std::os::unix::fs::{MetadataExt, PermissionsExt};
+use std::path::{Component, Path};
+
+use boxlite_shared::{FileEntry, FileKind, LayerChunk, ListFilesResponse};
+use sha2::{Digest, Sha256};
+use tokio::sync::mpsc;
+use tonic::Status;
+
+/// Entries per `ListFiles` message.
+const LIST_BATCH: usize = 1024;
+
+/// Bytes per `ExportLayer` chunk.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Walk the rootfs and send its entries in batches.
+///
+/// Stays on the rootfs filesystem and skips special files. Stops early
+/// when the receiver goes away.
+pub fn list_files(rootfs: &Path, tx: &mpsc::Sender<Result<ListFilesResponse, Status>>) {
+    walk(rootfs, true, false, tx);
+}
+
+/// Walk the tree below `dir`, mounts included, and send its entries in
+/// batches, with SHA-256 digests of regular files if `checksums` is set.
+pub fn list_tree(
+    dir: &Path,
+    checksums: bool,
+    tx: &mpsc::Sender<Result<ListFilesResponse, Status>>,
+) {
+    walk(dir, false, checksums, tx);
+}
+
+fn walk(
+    rootfs: &Path,
+    one_file_system: bool,
+    checksums: bool,
+    tx: &mpsc::Sender<Result<ListFilesResponse, Status>>,
+) {
+    let root_dev = match fs::metadata(rootfs) {
+        Ok(metadata) => metadata.dev(),
+        Err(e) => {
+            let status = match e.kind() {
+                io::ErrorKind::NotFound => {
+                    Status::not_found(format!("{}: {}", rootfs.display(), e))
+                }
+                _ => Status::internal(format!("Failed to list {}: {}", rootfs.display(), e)),
+            };
+            let _ = tx.blocking_send(Err(status));
+            return;
+        }
+    };
+
+    let mut batch = Vec::with_capacity(LIST_BATCH);
+    let mut pending = vec![rootfs.to_path_buf()];
+
+    while let Some(dir) = pending.pop() {
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(e) => {
+                tracing::warn!(path = %dir.display(), error = %e, "Skipping unreadable directory");
+                continue;
+            }
+        };
+
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let Ok(metadata) = fs::symlink_metadata(&path) else {
+                continue;
+            };
+            if one_file_system && metadata.dev() != root_dev {
+                continue;
+            }
+
+            let file_type = metadata.file_type();
+            let (kind, link_target) = if file_type.is_symlink() {
+                let target = fs::read_link(&path).unwrap_or_default();
+                (FileKind::Symlink, target.to_string_lossy().into_owned())
+            } else if file_type.is_dir() {
+                pending.push(path.clone());
+                (FileKind::Directory, String::new())
+            } else if file_type.is_file() {
+                (FileKind::Regular, String::new())
+            } else {
+                continue;
+            };
+
+            let sha256 = if checksums && file_type.is_file() {
+                match file_digest(&path) {
+                    Ok(digest) => digest,
+                    Err(e) => {
+                        tracing::warn!(path = %path.display(), error = %e, "Skipping unreadable file");
+                        continue;
+                    }
+                }
+            } else {
+                String::new()
+            };
+
+            let relative = path.strip_prefix(rootfs).unwrap_or(&path);
+            batch.push(FileEntry {
+                path: relative.to_string_lossy().into_owned(),
+                kind: kind as i32,
+                mode: metadata.permissions().mode() & 0o7777,
+                size: if file_type.is_file() {
+                    metadata.len()
+                } else {
+                    0
+                },
+                mtime: metadata.mtime(),
+                link_target,
+                sha256,
+            });
+
+            if batch.len() == LIST_BATCH {
+                let entries = std::mem::replace(&mut batch, Vec::with_capacity(LIST_BATCH));
+                if tx.blocking_send(Ok(ListFilesResponse { entries })).is_err() {
+                    return;
+                }
+            }
+        }
+    }
+
+    if !batch.is_empty() {
+        let _ = tx.blocking_send(Ok(ListFilesResponse { entries: batch }));
+    }
+}
+
+/// Hex SHA-256 of a file's content.
+fn file_digest(path: &Path) -> io::Result<String> {
+    let mut file = fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0; CHUNK_SIZE];
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Write a layer tar of `paths` plus whiteouts for `deleted`, in chunks.
+pub fn export_layer(
+    rootfs: &Path,
+    paths: &[String],
+    deleted: &[String],
+    tx: mpsc::Sender<Result<LayerChunk, Status>>,
+) {
+    let mut builder = tar::Builder::new(ChunkWriter::new(tx.clone(), |data| LayerChunk { data }));
+    builder.follow_symlinks(false);
+
+    let result = (|| -> io::Result<()> {
+        for path in deleted {
+            let relative = relative_path(path)?;
+            builder.append_data(&mut empty_header(), whiteout_name(relative)?, io::empty())?;
+        }
+        for path in paths {
+            let relative = relative_path(path)?;
+            builder.append_path_with_name(rootfs.join(relative), relative)?;
+        }
+        builder.finish()?;
+        builder.get_mut().flush()
+    })();
+
+    if let Err(e) = result {
+        let status = match e.kind() {
+            io::ErrorKind::InvalidInput => Status::invalid_argument(e.to_string()),
+            _ => Status::internal(format!("Failed to export layer: {}", e)),
+        };
+        let _ = tx.blocking_send(Err(status));
+    }
+}
+
+/// Reject absolute paths and `..` so exports stay inside the rootfs.
+fn relative_path(path: &str) -> io::Result<&Path> {
+    let relative = Path::new(path);
+    if path.is_empty()
+        || relative
+            .components()
+            .any(|c| !matches!(c, Component::Normal(_)))
+    {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Invalid rootfs path: {}", path),
+        ));
+    }
+    Ok(relative)
+}
+
+/// `dir/name` -> `dir/.wh.name`
+fn whiteout_name(path: &Path) -> io::Result<std::path::PathBuf> {
+    let name = path.file_name().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Invalid rootfs path: {}", path.display()),
+        )
+    })?;
+    let mut whiteout = std::ffi::OsString::from(".wh.");
+    whiteout.push(name);
+    Ok(path.with_file_name(whiteout))
+}
+
+fn empty_header() -> tar::Header {
+    let mut header = tar::Header::new_gnu();
+    header.set_entry_type(tar::EntryType::Regular);
+    header.set_size(0);
+    header.set_mode(0o644);
+    header
+}
+
+/// Buffers tar output and sends it as fixed-size chunks.
+pub(crate) struct ChunkWriter<T> {
+    buf: Vec<u8>,
+    tx: mpsc::Sender<Result<T, Status>>,
+    wrap: fn(Vec<u8>) -> T,
+}
+
+impl<T> ChunkWriter<T> {
+    /// Send chunks to `tx`, wrapped into messages by `wrap`.
+    pub fn new(tx: mpsc::Sender<Result<T, Status>>, wrap: fn(Vec<u8>) -> T) -> Self {
+        Self {
+            buf: Vec::with_capacity(CHUNK_SIZE),
+            tx,
+            wrap,
+        }
+    }
+
+    fn send(&mut self) -> io::Result<()> {
+        let data = std::mem::replace(&mut self.buf, Vec::with_capacity(CHUNK_SIZE));
+        self.tx
+            .blocking_send(Ok((self.wrap)(data)))
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "Export cancelled"))
+    }
+}
+
+impl<T> Write for ChunkWriter<T> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        self.buf.extend_from_slice(data);
+        if self.buf.len() >= CHUNK_SIZE {
+            self.send()?;
+        }
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if !self.buf.is_empty() {
+            self.send()?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_export_layer_writes_whiteouts() {
+        let rootfs = tempfile::tempdir().unwrap();
+        fs::create_dir(rootfs.path().join("app")).unwrap();
+        fs::write(rootfs.path().join("app/main.py"), "print()").unwrap();
+
+        let (tx, mut rx) = mpsc::channel(16);
+        export_layer(
+            rootfs.path(),
+            &["app".into(), "app/main.py".into()],
+            &["etc/motd".into()],
+            tx,
+        );
+
+        let mut data = Vec::new();
+        while let Ok(chunk) = rx.try_recv() {
+            data.extend(chunk.unwrap().data);
+        }
+        let mut archive = tar::Archive::new(data.as_slice());
+        let names: Vec<String> = archive
+            .entries()
+            .unwrap()
+            .map(|e| e.unwrap().path().unwrap().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(names, ["etc/.wh.motd", "app", "app/main.py"]);
+    }
+
+    #[test]
+    fn test_list_tree_checksums() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("src")).unwrap();
+        fs::write(dir.path().join("src/lib.rs"), "abc").unwrap();
+
+        let (tx, mut rx) = mpsc::channel(16);
+        list_tree(dir.path(), true, &tx);
+        let mut entries = rx.try_recv().unwrap().unwrap().entries;
+        entries.sort_by(|a, b| a.path.cmp(&b.path));
+
+        assert_eq!(entries[0].path, "src");
+        assert!(entries[0].sha256.is_empty());
+        assert_eq!(entries[1].path, "src/lib.rs");
+        assert_eq!(
+            entries[1].sha256,
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn test_export_layer_rejects_escapes() {
+        let rootfs = tempfile::tempdir().unwrap();
+        let (tx, mut rx) = mpsc::channel(16);
+        export_layer(rootfs.path(), &["../etc/passwd".into()], &[], tx);
+        assert!(rx.try_recv().unwrap().is_err());
+    }
+}
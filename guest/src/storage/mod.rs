@@ -3,12 +3,20 @@
 //! Provides unified abstraction for mounting different volume types:
 //! - Virtiofs: Host-shared directories via virtio-fs
 //! - Block devices: Disk images attached via virtio-blk
+//!
+//! Also enforces write quotas on the container rootfs, exports its
+//! changes as a layer, copies files in and out of containers and watches
+//! them for changes.
 
+pub mod archive;
 pub mod block_device;
 #[allow(dead_code)]
 mod copy;
+pub mod layer;
 mod perms;
+pub mod quota;
 mod virtiofs;
 mod volume;
+pub mod watch;
 
 pub use volume::mount_volumes;
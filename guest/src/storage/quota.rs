@@ -0,0 +1,350 @@
+//! Filesystem write quotas on the container rootfs.
+//!
+//! Uses the ext4 quota feature:
+//! - Directory quotas: a project ID is assigned to the directory (inherited
+//!   by everything created below it) and limited with a project quota
+//! - User quotas: limit all blocks owned by a uid
+//!
+//! Quotas are set after every mount, so they always match the request:
+//! limits of IDs no longer requested are cleared.
+
+use std::collections::HashMap;
+use std::ffi::{CStr, CString};
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::MetadataExt;
+use std::os::unix::io::AsRawFd;
+use std::path::{Component, Path, PathBuf};
+use std::process::Command;
+
+use boxlite_shared::errors::{BoxliteError, BoxliteResult};
+use boxlite_shared::{disk_quota, DiskQuota};
+use nix::libc;
+
+/// Mount options that turn on quota enforcement.
+pub const MOUNT_OPTIONS: &str = "usrquota,prjquota";
+
+/// Project quota type (`PRJQUOTA` in linux/quota.h).
+const PRJQUOTA: libc::c_int = 2;
+
+/// `Q_GETNEXTQUOTA` (linux/quota.h).
+const Q_GETNEXTQUOTA: libc::c_int = 0x80_0009;
+
+/// Quota block size used by `quotactl` (`QIF_DQBLKSIZE`).
+const QUOTA_BLOCK_SIZE: u64 = 1024;
+
+/// `FS_IOC_FSGETXATTR` / `FS_IOC_FSSETXATTR` (linux/fs.h).
+const FS_IOC_FSGETXATTR: u32 = 0x801c_581f;
+const FS_IOC_FSSETXATTR: u32 = 0x401c_5820;
+
+/// New files and directories inherit the directory's project ID.
+const FS_XFLAG_PROJINHERIT: u32 = 0x0000_0200;
+
+/// `struct fsxattr` (linux/fs.h).
+#[repr(C)]
+#[derive(Default)]
+struct FsXattr {
+    fsx_xflags: u32,
+    fsx_extsize: u32,
+    fsx_nextents: u32,
+    fsx_projid: u32,
+    fsx_cowextsize: u32,
+    fsx_pad: [u8; 8],
+}
+
+/// `struct if_nextdqblk` (linux/quota.h).
+#[repr(C)]
+#[derive(Default)]
+struct NextDqblk {
+    dqb_bhardlimit: u64,
+    dqb_bsoftlimit: u64,
+    dqb_curspace: u64,
+    dqb_ihardlimit: u64,
+    dqb_isoftlimit: u64,
+    dqb_curinodes: u64,
+    dqb_btime: u64,
+    dqb_itime: u64,
+    dqb_valid: u32,
+    dqb_id: u32,
+}
+
+/// Enable the ext4 quota and project features on an unmounted device.
+///
+/// Idempotent. Fails on filesystems with 128-byte inodes, which have no
+/// room for project IDs.
+pub fn enable(device: &Path) -> BoxliteResult<()> {
+    tracing::info!("Enabling quota support on {}", device.display());
+
+    let output = Command::new("tune2fs")
+        .args(["-O", "quota,project", "-Q", "usrquota,prjquota"])
+        .arg(device)
+        .output()
+        .map_err(|e| BoxliteError::Storage(format!("Failed to execute tune2fs: {}", e)))?;
+
+    if !output.status.success() {
+        return Err(BoxliteError::Storage(format!(
+            "Failed to enable quotas on {}: {}",
+            device.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(())
+}
+
+/// Apply quotas to a mounted rootfs.
+///
+/// Directory quotas get a project ID derived from their path, the same on
+/// every boot. Limits set on an earlier boot for other project IDs or
+/// uids are cleared.
+pub fn apply(device: &Path, rootfs: &Path, quotas: &[DiskQuota]) -> BoxliteResult<()> {
+    let device_c = CString::new(device.as_os_str().as_bytes())
+        .map_err(|e| BoxliteError::Storage(format!("Invalid device path: {}", e)))?;
+    let rootfs_canonical = rootfs.canonicalize()?;
+    let mut projects: HashMap<u32, &str> = HashMap::new();
+    let mut uids = Vec::new();
+
+    for quota in quotas {
+        match &quota.target {
+            Some(disk_quota::Target::Path(path)) => {
+                let dir = resolve_dir(rootfs, path)?;
+                let project_id = project_id_of(dir.strip_prefix(&rootfs_canonical).unwrap_or(&dir));
+                if let Some(other) = projects.insert(project_id, path) {
+                    return Err(BoxliteError::Storage(format!(
+                        "Quota paths {} and {} are the same directory or share project ID {}",
+                        other, path, project_id
+                    )));
+                }
+                assign_project(&dir, project_id)?;
+                set_limit(&device_c, PRJQUOTA, project_id, quota.limit_bytes)?;
+                tracing::info!(
+                    path = %path,
+                    project_id,
+                    limit_bytes = quota.limit_bytes,
+                    "Applied directory quota"
+                );
+            }
+            Some(disk_quota::Target::Uid(uid)) => {
+                set_limit(&device_c, libc::USRQUOTA, *uid, quota.limit_bytes)?;
+                uids.push(*uid);
+                tracing::info!(uid, limit_bytes = quota.limit_bytes, "Applied user quota");
+            }
+            None => {
+                return Err(BoxliteError::Storage(
+                    "Disk quota without a target".to_string(),
+                ))
+            }
+        }
+    }
+
+    for (quota_type, kept) in [
+        (PRJQUOTA, projects.keys().copied().collect::<Vec<_>>()),
+        (libc::USRQUOTA, uids),
+    ] {
+        for id in limited_ids(&device_c, quota_type)? {
+            if !kept.contains(&id) {
+                set_limit(&device_c, quota_type, id, 0)?;
+                tracing::info!(quota_type, id, "Cleared quota no longer requested");
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Project ID of a directory, from its path relative to the rootfs: a
+/// 32-bit FNV-1a hash, skipping 0 (no project) and `u32::MAX`.
+fn project_id_of(relative: &Path) -> u32 {
+    let hash = relative
+        .as_os_str()
+        .as_bytes()
+        .iter()
+        .fold(0x811c_9dc5_u32, |hash, byte| {
+            (hash ^ u32::from(*byte)).wrapping_mul(0x0100_0193)
+        });
+    hash % (u32::MAX - 1) + 1
+}
+
+/// Resolve a container path to a directory on the mounted rootfs,
+/// creating it if needed.
+fn resolve_dir(rootfs: &Path, container_path: &str) -> BoxliteResult<PathBuf> {
+    let relative = Path::new(container_path).strip_prefix("/").map_err(|_| {
+        BoxliteError::Storage(format!("Quota path must be absolute: {}", container_path))
+    })?;
+    if relative
+        .components()
+        .any(|c| !matches!(c, Component::Normal(_)))
+    {
+        return Err(BoxliteError::Storage(format!(
+            "Invalid quota path: {}",
+            container_path
+        )));
+    }
+
+    let dir = rootfs.join(relative);
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| BoxliteError::Storage(format!("Failed to create {}: {}", dir.display(), e)))?;
+
+    // Symlinks in the image must not lead outside the rootfs
+    let canonical = dir.canonicalize()?;
+    if !canonical.starts_with(rootfs.canonicalize()?) {
+        return Err(BoxliteError::Storage(format!(
+            "Quota path {} resolves outside the container rootfs",
+            container_path
+        )));
+    }
+    Ok(canonical)
+}
+
+/// Set the project ID of a directory tree and mark directories inheritable.
+fn assign_project(dir: &Path, project_id: u32) -> BoxliteResult<()> {
+    let root_dev = std::fs::metadata(dir)?.dev();
+    let mut pending = vec![dir.to_path_buf()];
+
+    while let Some(path) = pending.pop() {
+        let metadata = std::fs::symlink_metadata(&path)?;
+        // Stay on this filesystem; skip symlinks and special files
+        if metadata.dev() != root_dev || !(metadata.is_dir() || metadata.is_file()) {
+            continue;
+        }
+
+        set_project_id(&path, project_id, metadata.is_dir())?;
+
+        if metadata.is_dir() {
+            for entry in std::fs::read_dir(&path)? {
+                pending.push(entry?.path());
+            }
+        }
+    }
+    Ok(())
+}
+
+fn set_project_id(path: &Path, project_id: u32, inherit: bool) -> BoxliteResult<()> {
+    let file = std::fs::File::open(path)?;
+    let fd = file.as_raw_fd();
+    let mut attr = FsXattr::default();
+
+    // SAFETY: fd is valid for the lifetime of `file`; attr matches struct fsxattr
+    if unsafe { libc::ioctl(fd, FS_IOC_FSGETXATTR as libc::Ioctl, &mut attr) } != 0 {
+        return Err(ioctl_error("FS_IOC_FSGETXATTR", path));
+    }
+
+    attr.fsx_projid = project_id;
+    if inherit {
+        attr.fsx_xflags |= FS_XFLAG_PROJINHERIT;
+    }
+
+    // SAFETY: as above
+    if unsafe { libc::ioctl(fd, FS_IOC_FSSETXATTR as libc::Ioctl, &attr) } != 0 {
+        return Err(ioctl_error("FS_IOC_FSSETXATTR", path));
+    }
+    Ok(())
+}
+
+/// Set the block limit of `id`; a limit of 0 removes it.
+fn set_limit(
+    device: &CStr,
+    quota_type: libc::c_int,
+    id: u32,
+    limit_bytes: u64,
+) -> BoxliteResult<()> {
+    // SAFETY: dqblk is plain integers, all-zero is valid
+    let mut dqblk: libc::dqblk = unsafe { std::mem::zeroed() };
+    dqblk.dqb_bhardlimit = limit_bytes.div_ceil(QUOTA_BLOCK_SIZE);
+    dqblk.dqb_bsoftlimit = dqblk.dqb_bhardlimit;
+    dqblk.dqb_valid = libc::QIF_BLIMITS;
+
+    // SAFETY: device is a valid C string; dqblk matches struct if_dqblk
+    let ret = unsafe {
+        libc::quotactl(
+            libc::QCMD(libc::Q_SETQUOTA, quota_type),
+            device.as_ptr(),
+            id as libc::c_int,
+            &mut dqblk as *mut libc::dqblk as *mut libc::c_char,
+        )
+    };
+    if ret != 0 {
+        return Err(BoxliteError::Storage(format!(
+            "Failed to set quota for id {} on {}: {}",
+            id,
+            device.to_string_lossy(),
+            std::io::Error::last_os_error()
+        )));
+    }
+    Ok(())
+}
+
+/// IDs of `quota_type` with a block limit on the device.
+fn limited_ids(device: &CStr, quota_type: libc::c_int) -> BoxliteResult<Vec<u32>> {
+    let mut ids = Vec::new();
+    let mut next_id = 0u32;
+    loop {
+        let mut next = NextDqblk::default();
+        // SAFETY: device is a valid C string; next matches struct if_nextdqblk
+        let ret = unsafe {
+            libc::quotactl(
+                libc::QCMD(Q_GETNEXTQUOTA, quota_type),
+                device.as_ptr(),
+                next_id as libc::c_int,
+                &mut next as *mut NextDqblk as *mut libc::c_char,
+            )
+        };
+        if ret != 0 {
+            let error = std::io::Error::last_os_error();
+            // No ID at or after next_id
+            if error.raw_os_error() == Some(libc::ENOENT) {
+                return Ok(ids);
+            }
+            return Err(BoxliteError::Storage(format!(
+                "Failed to list quotas on {}: {}",
+                device.to_string_lossy(),
+                error
+            )));
+        }
+        if next.dqb_bhardlimit != 0 || next.dqb_bsoftlimit != 0 {
+            ids.push(next.dqb_id);
+        }
+        let Some(after) = next.dqb_id.checked_add(1) else {
+            return Ok(ids);
+        };
+        next_id = after;
+    }
+}
+
+fn ioctl_error(name: &str, path: &Path) -> BoxliteError {
+    BoxliteError::Storage(format!(
+        "{} failed on {}: {}",
+        name,
+        path.display(),
+        std::io::Error::last_os_error()
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_dir_creates_directory() {
+        let rootfs = tempfile::tempdir().unwrap();
+        let dir = resolve_dir(rootfs.path(), "/workspace/data").unwrap();
+        assert!(dir.is_dir());
+        assert!(dir.ends_with("workspace/data"));
+    }
+
+    #[test]
+    fn test_project_id_is_stable() {
+        let id = project_id_of(Path::new("workspace/data"));
+        assert_eq!(id, project_id_of(Path::new("workspace/data")));
+        assert_ne!(id, project_id_of(Path::new("workspace/cache")));
+        assert_ne!(project_id_of(Path::new("")), 0);
+    }
+
+    #[test]
+    fn test_resolve_dir_rejects_escapes() {
+        let rootfs = tempfile::tempdir().unwrap();
+        assert!(resolve_dir(rootfs.path(), "workspace").is_err());
+        assert!(resolve_dir(rootfs.path(), "/workspace/../..").is_err());
+
+        let outside = tempfile::tempdir().unwrap();
+        std::os::unix::fs::symlink(outside.path(), rootfs.path().join("link")).unwrap();
+        assert!(resolve_dir(rootfs.path(), "/link").is_err());
+    }
+}
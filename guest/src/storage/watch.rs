@@ -0,0 +1,263 @@
+//! File change notifications inside a container.
+//!
+//! Built on inotify. Every watched directory is resolved with
+//! [`open_in_root`] and watched through its `/proc/self/fd` path, so
+//! symlinks in the container never lead the watch outside of it.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io;
+use std::os::unix::io::{AsFd, AsRawFd, RawFd};
+use std::path::{Path, PathBuf};
+
+use boxlite_shared::{WatchEvent, WatchEventKind};
+use nix::errno::Errno;
+use nix::libc;
+use nix::sys::inotify::{AddWatchFlags, InitFlags, Inotify, InotifyEvent, WatchDescriptor};
+use tokio::io::unix::AsyncFd;
+
+use super::archive::open_in_root;
+
+/// Raw fd access for [`AsyncFd`].
+struct InotifyFd(Inotify);
+
+impl AsRawFd for InotifyFd {
+    fn as_raw_fd(&self) -> RawFd {
+        self.0.as_fd().as_raw_fd()
+    }
+}
+
+/// Watches a file or directory in a container.
+pub struct Watcher {
+    root: File,
+    inotify: AsyncFd<InotifyFd>,
+    recursive: bool,
+    /// Container path of every watch.
+    watches: HashMap<WatchDescriptor, PathBuf>,
+    /// The watch on the requested path itself.
+    top: WatchDescriptor,
+    top_is_dir: bool,
+    /// Set once the requested path is gone.
+    done: bool,
+}
+
+impl Watcher {
+    /// Start watching `path` (absolute in `root`).
+    pub fn new(root: File, path: &Path, recursive: bool) -> io::Result<Self> {
+        let inotify = Inotify::init(InitFlags::IN_NONBLOCK | InitFlags::IN_CLOEXEC)?;
+        let (top, top_is_dir) = watch_path(&root, &inotify, path)?;
+        let mut watcher = Self {
+            root,
+            inotify: AsyncFd::new(InotifyFd(inotify))?,
+            recursive,
+            watches: HashMap::from([(top, path.to_path_buf())]),
+            top,
+            top_is_dir,
+            done: false,
+        };
+        if recursive && top_is_dir {
+            watcher.add_subdirs(path, false, &mut Vec::new());
+        }
+        Ok(watcher)
+    }
+
+    /// Wait for the next events. `None` once the watched path is gone.
+    pub async fn next(&mut self) -> io::Result<Option<Vec<WatchEvent>>> {
+        while !self.done {
+            let mut guard = self.inotify.readable().await?;
+            let events = match guard.get_inner().0.read_events() {
+                Ok(events) => events,
+                Err(Errno::EAGAIN) => {
+                    guard.clear_ready();
+                    continue;
+                }
+                Err(e) => return Err(e.into()),
+            };
+            drop(guard);
+
+            let mut out = Vec::new();
+            for event in events {
+                if !self.translate(event, &mut out) {
+                    self.done = true;
+                    break;
+                }
+            }
+            if !out.is_empty() {
+                return Ok(Some(out));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Append the events for one inotify event. `false` once the watched
+    /// path is gone.
+    fn translate(&mut self, event: InotifyEvent, out: &mut Vec<WatchEvent>) -> bool {
+        let mask = event.mask;
+        if mask.contains(AddWatchFlags::IN_Q_OVERFLOW) {
+            let path = self.watches.get(&self.top).cloned().unwrap_or_default();
+            out.push(watch_event(WatchEventKind::Overflow, &path, false));
+            return true;
+        }
+        let Some(dir) = self.watches.get(&event.wd).cloned() else {
+            return true;
+        };
+        let is_dir = mask.contains(AddWatchFlags::IN_ISDIR);
+
+        if mask.intersects(AddWatchFlags::IN_DELETE_SELF | AddWatchFlags::IN_MOVE_SELF)
+            && event.wd == self.top
+        {
+            out.push(watch_event(WatchEventKind::Deleted, &dir, self.top_is_dir));
+            return false;
+        }
+        if mask.contains(AddWatchFlags::IN_IGNORED) {
+            self.watches.remove(&event.wd);
+            return event.wd != self.top;
+        }
+
+        let path = match &event.name {
+            Some(name) => dir.join(name),
+            None => dir,
+        };
+        if mask.intersects(AddWatchFlags::IN_CREATE | AddWatchFlags::IN_MOVED_TO) {
+            out.push(watch_event(WatchEventKind::Created, &path, is_dir));
+            if is_dir && self.recursive {
+                // Entries made before the watch was in place are reported
+                // as created
+                if self.add_watch(&path).is_ok() {
+                    self.add_subdirs(&path, true, out);
+                }
+            }
+        } else if mask.contains(AddWatchFlags::IN_CLOSE_WRITE) {
+            out.push(watch_event(WatchEventKind::Modified, &path, false));
+        } else if mask.intersects(AddWatchFlags::IN_DELETE | AddWatchFlags::IN_MOVED_FROM) {
+            if is_dir && mask.contains(AddWatchFlags::IN_MOVED_FROM) {
+                // A directory moved elsewhere would keep reporting under
+                // its old path
+                self.remove_watches(&path);
+            }
+            out.push(watch_event(WatchEventKind::Deleted, &path, is_dir));
+        }
+        true
+    }
+
+    fn add_watch(&mut self, path: &Path) -> io::Result<()> {
+        let (wd, _) = watch_path(&self.root, &self.inotify.get_ref().0, path)?;
+        self.watches.insert(wd, path.to_path_buf());
+        Ok(())
+    }
+
+    /// Drop the watches on `dir` and below.
+    fn remove_watches(&mut self, dir: &Path) {
+        let inotify = &self.inotify.get_ref().0;
+        self.watches.retain(|wd, path| {
+            let keep = !path.starts_with(dir);
+            if !keep {
+                let _ = inotify.rm_watch(*wd);
+            }
+            keep
+        });
+    }
+
+    /// Watch the directories below `dir`, reporting their entries as
+    /// created if `report` is set. Unreadable directories are skipped.
+    fn add_subdirs(&mut self, dir: &Path, report: bool, out: &mut Vec<WatchEvent>) {
+        let mut pending = vec![dir.to_path_buf()];
+        while let Some(dir) = pending.pop() {
+            let Ok(fd) = open_in_root(&self.root, &dir, libc::O_RDONLY | libc::O_DIRECTORY, 0)
+            else {
+                continue;
+            };
+            let Ok(entries) = std::fs::read_dir(format!("/proc/self/fd/{}", fd.as_raw_fd())) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let path = dir.join(entry.file_name());
+                let is_dir = entry.file_type().is_ok_and(|t| t.is_dir());
+                if report {
+                    out.push(watch_event(WatchEventKind::Created, &path, is_dir));
+                }
+                if is_dir && self.add_watch(&path).is_ok() {
+                    pending.push(path);
+                }
+            }
+        }
+    }
+}
+
+/// Add a watch on `path`, returning it and whether `path` is a directory.
+fn watch_path(root: &File, inotify: &Inotify, path: &Path) -> io::Result<(WatchDescriptor, bool)> {
+    let file = File::from(open_in_root(root, path, libc::O_PATH, 0)?);
+    let is_dir = file.metadata()?.is_dir();
+    let wd = inotify.add_watch(
+        format!("/proc/self/fd/{}", file.as_raw_fd()).as_str(),
+        AddWatchFlags::IN_CREATE
+            | AddWatchFlags::IN_MOVED_TO
+            | AddWatchFlags::IN_CLOSE_WRITE
+            | AddWatchFlags::IN_DELETE
+            | AddWatchFlags::IN_MOVED_FROM
+            | AddWatchFlags::IN_DELETE_SELF
+            | AddWatchFlags::IN_MOVE_SELF,
+    )?;
+    Ok((wd, is_dir))
+}
+
+fn watch_event(kind: WatchEventKind, path: &Path, is_dir: bool) -> WatchEvent {
+    WatchEvent {
+        kind: kind as i32,
+        path: path.to_string_lossy().into_owned(),
+        is_dir,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    async fn collect(watcher: &mut Watcher) -> Vec<(WatchEventKind, String)> {
+        let mut events = Vec::new();
+        while let Ok(Ok(Some(batch))) =
+            tokio::time::timeout(Duration::from_millis(200), watcher.next()).await
+        {
+            events.extend(batch.into_iter().map(|e| (e.kind(), e.path)));
+        }
+        events
+    }
+
+    #[tokio::test]
+    async fn test_watch_recursive() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("out")).unwrap();
+        let root = File::open(dir.path()).unwrap();
+        let mut watcher = Watcher::new(root, Path::new("/out"), true).unwrap();
+
+        // Files made before the new directory is watched are reported too
+        std::fs::create_dir(dir.path().join("out/logs")).unwrap();
+        std::fs::write(dir.path().join("out/logs/early.log"), "").unwrap();
+        assert_eq!(
+            collect(&mut watcher).await,
+            [
+                (WatchEventKind::Created, "/out/logs".into()),
+                (WatchEventKind::Created, "/out/logs/early.log".into()),
+            ]
+        );
+
+        std::fs::write(dir.path().join("out/logs/run.log"), "ok").unwrap();
+        std::fs::remove_file(dir.path().join("out/logs/run.log")).unwrap();
+        assert_eq!(
+            collect(&mut watcher).await,
+            [
+                (WatchEventKind::Created, "/out/logs/run.log".into()),
+                (WatchEventKind::Modified, "/out/logs/run.log".into()),
+                (WatchEventKind::Deleted, "/out/logs/run.log".into()),
+            ]
+        );
+
+        std::fs::remove_dir_all(dir.path().join("out")).unwrap();
+        let mut last = None;
+        while let Some(batch) = watcher.next().await.unwrap() {
+            last = batch.last().cloned();
+        }
+        assert_eq!(last.unwrap().kind(), WatchEventKind::Deleted);
+    }
+}
@@ -121,6 +121,22 @@ impl JsExecStdin {
     pub async fn write_string(&self, text: String) -> Result<()> {
         self.write(text.into_bytes().into()).await
     }
+
+    /// Close stdin, sending EOF to the process.
+    ///
+    /// The command keeps running, so programs that read until EOF
+    /// (e.g. `wc -l`) can finish and their output can still be read.
+    ///
+    /// # Example
+    /// ```javascript
+    /// await stdin.writeString('a\nb\n');
+    /// await stdin.close();
+    /// ```
+    #[napi]
+    pub async fn close(&self) -> Result<()> {
+        self.stream.lock().await.close();
+        Ok(())
+    }
 }
 
 /// Execution handle for a running command.
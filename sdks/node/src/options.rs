@@ -58,7 +58,7 @@ pub struct JsBoxOptions {
     /// Volume mounts as array of volume specs
     pub volumes: Option<Vec<JsVolumeSpec>>,
 
-    /// Network mode ("isolated" - only option currently)
+    /// Network mode: "isolated" (default) or "none" for no network
     pub network: Option<String>,
 
     /// Port mappings as array of port specs
@@ -81,7 +81,7 @@ pub struct JsEnvVar {
 
 /// Volume mount specification.
 ///
-/// Maps a host directory to a guest path inside the container.
+/// Maps a host directory or file to a guest path inside the container.
 #[napi(object)]
 #[derive(Clone, Debug)]
 pub struct JsVolumeSpec {
@@ -97,7 +97,7 @@ pub struct JsVolumeSpec {
 
 impl From<JsVolumeSpec> for VolumeSpec {
     fn from(v: JsVolumeSpec) -> Self {
-        VolumeSpec {
+        VolumeSpec::Bind {
             host_path: v.host_path,
             guest_path: v.guest_path,
             read_only: v.read_only.unwrap_or(false),
@@ -153,6 +153,7 @@ impl From<JsBoxOptions> for BoxOptions {
         // Convert network spec
         let network = match js_opts.network.as_deref() {
             Some(s) if s.eq_ignore_ascii_case("isolated") => NetworkSpec::Isolated,
+            Some(s) if s.eq_ignore_ascii_case("none") => NetworkSpec::None,
             _ => NetworkSpec::Isolated,
         };
 
@@ -197,6 +198,7 @@ impl From<JsBoxOptions> for BoxOptions {
             isolate_mounts: false, // Not exposed in JS API yet
             auto_remove: js_opts.auto_remove.unwrap_or(false),
             detach: js_opts.detach.unwrap_or(false),
+            ..Default::default()
         }
     }
 }
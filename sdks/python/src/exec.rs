@@ -53,6 +53,16 @@ impl PyExecStdin {
         })
     }
 
+    /// Close stdin, sending EOF to the process (it keeps running).
+    fn close<'a>(&self, py: Python<'a>) -> PyResult<Bound<'a, PyAny>> {
+        let stream = Arc::clone(&self.stream);
+
+        pyo3_async_runtimes::tokio::future_into_py(py, async move {
+            stream.lock().await.close();
+            Ok(())
+        })
+    }
+
     fn __repr__(&self) -> String {
         "ExecStdin(...)".to_string()
     }
@@ -30,6 +30,7 @@ impl From<BoxInfo> for PyBoxInfo {
             BoxStatus::Unknown => "unknown",
             BoxStatus::Starting => "starting",
             BoxStatus::Running => "running",
+            BoxStatus::Paused => "paused",
             BoxStatus::Stopping => "stopping",
             BoxStatus::Stopped => "stopped",
         };
@@ -130,6 +130,7 @@ impl From<PyBoxOptions> for BoxOptions {
         let network = match py_opts.network {
             // Some(ref s) if s.eq_ignore_ascii_case("host") => NetworkSpec::Host,
             Some(ref s) if s.eq_ignore_ascii_case("isolated") => NetworkSpec::Isolated,
+            Some(ref s) if s.eq_ignore_ascii_case("none") => NetworkSpec::None,
             // Some(s) if !s.is_empty() => NetworkSpec::Custom(s),
             _ => NetworkSpec::Isolated,
         };
@@ -182,7 +183,7 @@ pub(crate) struct PyVolumeSpec {
 
 impl From<PyVolumeSpec> for VolumeSpec {
     fn from(v: PyVolumeSpec) -> Self {
-        VolumeSpec {
+        VolumeSpec::Bind {
             host_path: v.host,
             guest_path: v.guest,
             read_only: v.read_only,